@@ -3,6 +3,9 @@ use std::ops::{Range, RangeInclusive};
 
 use bitpacking::{BitPacker as ExternalBitPackerTrait, BitPacker1x};
 
+/// Number of values decoded at once by `BitUnpacker::get_batch_u64s`.
+const BATCH_BUFFER_LEN: usize = 128;
+
 pub struct BitPacker {
     mini_buffer: u64,
     mini_buffer_written: usize,
@@ -192,6 +195,28 @@ impl BitUnpacker {
         get_batch_ramp(highway_end, &mut output[output_cursor..]);
     }
 
+    /// Decodes the range of bitpacked values with idx
+    /// in [start_idx, start_idx + output.len()).
+    ///
+    /// Bit widths up to 32 are decoded 32 values at a time.
+    pub fn get_batch_u64s(&self, start_idx: u32, data: &[u8], output: &mut [u64]) {
+        if self.bit_width() > 32 {
+            for (out, idx) in output.iter_mut().zip(start_idx..) {
+                *out = self.get(idx, data);
+            }
+            return;
+        }
+        let mut buffer = [0u32; BATCH_BUFFER_LEN];
+        for (chunk_ord, output_chunk) in output.chunks_mut(BATCH_BUFFER_LEN).enumerate() {
+            let chunk_start_idx = start_idx + (chunk_ord * BATCH_BUFFER_LEN) as u32;
+            let buffer = &mut buffer[..output_chunk.len()];
+            self.get_batch_u32s(chunk_start_idx, data, buffer);
+            for (out, val) in output_chunk.iter_mut().zip(buffer.iter()) {
+                *out = u64::from(*val);
+            }
+        }
+    }
+
     pub fn get_ids_for_value_range(
         &self,
         range: RangeInclusive<u64>,
@@ -347,6 +372,19 @@ mod test {
         bitunpacker.get_batch_u32s(8 * 4 - 2, &[0u8, 0u8, 0u8, 0u8], &mut output[..]);
     }
 
+    #[test]
+    fn test_get_batch_u64s() {
+        for num_bits in [0, 1, 7, 32, 33, 56] {
+            let (bitunpacker, vals, data) = create_bitpacker(1_000, num_bits);
+            let mut output: Vec<u64> = Vec::new();
+            for (start_idx, len) in [(0, 1_000), (3, 997), (0, 0), (129, 300), (999, 1)] {
+                output.resize(len, 0);
+                bitunpacker.get_batch_u64s(start_idx as u32, &data, &mut output);
+                assert_eq!(&output[..], &vals[start_idx..start_idx + len]);
+            }
+        }
+    }
+
     proptest::proptest! {
         #[test]
         fn test_get_batch_u32s_proptest(num_bits in 0u8..=32u8) {
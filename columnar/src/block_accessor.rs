@@ -1,6 +1,6 @@
 use std::cmp::Ordering;
 
-use crate::{Column, DocId, RowId};
+use crate::{Column, ColumnValues, DocId, RowId};
 
 #[derive(Debug, Default, Clone)]
 pub struct ColumnBlockAccessor<T> {
@@ -17,15 +17,13 @@ impl<T: PartialOrd + Copy + std::fmt::Debug + Send + Sync + 'static + Default>
     pub fn fetch_block<'a>(&'a mut self, docs: &'a [u32], accessor: &Column<T>) {
         if accessor.index.get_cardinality().is_full() {
             self.val_cache.resize(docs.len(), T::default());
-            accessor.values.get_vals(docs, &mut self.val_cache);
+            fetch_vals(&*accessor.values, docs, &mut self.val_cache);
         } else {
             self.docid_cache.clear();
             self.row_id_cache.clear();
             accessor.row_ids_for_docs(docs, &mut self.docid_cache, &mut self.row_id_cache);
             self.val_cache.resize(self.row_id_cache.len(), T::default());
-            accessor
-                .values
-                .get_vals(&self.row_id_cache, &mut self.val_cache);
+            fetch_vals(&*accessor.values, &self.row_id_cache, &mut self.val_cache);
         }
     }
     #[inline]
@@ -78,6 +76,17 @@ impl<T: PartialOrd + Copy + std::fmt::Debug + Send + Sync + 'static + Default>
     }
 }
 
+/// Fetches the values of the sorted `row_ids`, decoding them as a range if they are contiguous.
+#[inline]
+fn fetch_vals<T: PartialOrd>(values: &dyn ColumnValues<T>, row_ids: &[RowId], output: &mut [T]) {
+    match (row_ids.first(), row_ids.last()) {
+        (Some(&first), Some(&last)) if (last - first) as usize + 1 == row_ids.len() => {
+            values.get_range(first as u64, output);
+        }
+        _ => values.get_vals(row_ids, output),
+    }
+}
+
 /// Given two sorted lists of docids `docs` and `hits`, hits is a subset of `docs`.
 /// Return all docs that are not in `hits`.
 fn find_missing_docs<F>(docs: &[u32], hits: &[u32], mut callback: F)
@@ -113,6 +122,18 @@ where F: FnMut(u32) {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::column_values::VecColumn;
+
+    #[test]
+    fn test_fetch_vals() {
+        let column = VecColumn::from((0..100u64).map(|val| val * 3).collect::<Vec<_>>());
+        let mut output = vec![0u64; 3];
+        fetch_vals(&column, &[10, 11, 12], &mut output);
+        assert_eq!(output, vec![30, 33, 36]);
+        fetch_vals(&column, &[1, 5, 99], &mut output);
+        assert_eq!(output, vec![3, 15, 297]);
+        fetch_vals(&column, &[], &mut []);
+    }
 
     #[test]
     fn test_find_missing_docs() {
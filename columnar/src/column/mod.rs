@@ -12,10 +12,17 @@ pub use serialize::{
     open_column_bytes, open_column_str, open_column_u128, open_column_u128_as_compact_u64,
     open_column_u64, serialize_column_mappable_to_u128, serialize_column_mappable_to_u64,
 };
+pub(crate) use serialize::{
+    pinned_or_default_codecs, serialize_column_mappable_to_u64_with_estimation,
+    DATETIME_CODEC_TYPES, F64_CODEC_TYPES, U64_CODEC_TYPES,
+};
 
 use crate::column_index::{ColumnIndex, Set};
 use crate::column_values::monotonic_mapping::StrictlyMonotonicMappingToInternal;
-use crate::column_values::{monotonic_map_column, ColumnValues};
+use crate::column_values::{
+    monotonic_map_column, monotonic_transform_column, CardinalitySketch, ColumnValues,
+    MonotonicTransform,
+};
 use crate::{Cardinality, DocId, EmptyColumnValues, MonotonicallyMappableToU64, RowId};
 
 #[derive(Clone)]
@@ -83,6 +90,21 @@ impl<T: PartialOrd + Copy + Debug + Send + Sync + 'static> Column<T> {
         self.values.max_value()
     }
 
+    /// Returns the sketch estimating the number of distinct values of the column, if it was
+    /// serialized with one. See [`CardinalitySketch`].
+    pub fn cardinality_sketch(&self) -> Option<&CardinalitySketch> {
+        self.values.cardinality_sketch()
+    }
+
+    /// Returns a view of the column whose values are transformed by a [`MonotonicTransform`],
+    /// e.g. a date column truncated to the hour, without materializing the derived column.
+    pub fn monotonic_transform(self, transform: impl MonotonicTransform<T>) -> Column<T> {
+        Column {
+            index: self.index,
+            values: Arc::new(monotonic_transform_column(self.values, transform)),
+        }
+    }
+
     #[inline]
     pub fn first(&self, row_id: RowId) -> Option<T> {
         self.values_for_doc(row_id).next()
@@ -8,12 +8,57 @@ use sstable::Dictionary;
 use crate::column::{BytesColumn, Column};
 use crate::column_index::{serialize_column_index, SerializableColumnIndex};
 use crate::column_values::{
-    load_u64_based_column_values, serialize_column_values_u128, serialize_u64_based_column_values,
-    CodecType, MonotonicallyMappableToU128, MonotonicallyMappableToU64,
+    load_u64_based_column_values_with_version, serialize_column_values_u128,
+    serialize_u64_based_column_values_with_estimation, CodecEstimation, CodecType, CompetingCodecs,
+    MonotonicallyMappableToU128, MonotonicallyMappableToU64,
 };
 use crate::iterable::Iterable;
 use crate::{StrColumn, Version};
 
+/// Codecs competing for the values of the columns mapped to u64.
+pub(crate) const U64_CODEC_TYPES: &[CodecType] = &[
+    CodecType::Bitpacked,
+    CodecType::BlockwiseLinear,
+    CodecType::BlockwiseFor,
+    CodecType::Dictionary,
+    CodecType::Sparse,
+    CodecType::RunLength,
+];
+
+/// Codecs competing for the values of the datetime columns, which are often timestamps with
+/// near-constant increments.
+pub(crate) const DATETIME_CODEC_TYPES: &[CodecType] = &[
+    CodecType::Bitpacked,
+    CodecType::BlockwiseLinear,
+    CodecType::BlockwiseFor,
+    CodecType::Dictionary,
+    CodecType::Sparse,
+    CodecType::DeltaOfDelta,
+    CodecType::RunLength,
+];
+
+/// Codecs competing for the values of the `f64` columns.
+pub(crate) const F64_CODEC_TYPES: &[CodecType] = &[
+    CodecType::Bitpacked,
+    CodecType::BlockwiseLinear,
+    CodecType::BlockwiseFor,
+    CodecType::Dictionary,
+    CodecType::Sparse,
+    CodecType::Float,
+    CodecType::RunLength,
+];
+
+/// Returns the codecs competing for the values of a column: only the codec pinned for the
+/// column if any, `codec_types` and the registered codecs otherwise.
+pub(crate) fn pinned_or_default_codecs<'a>(
+    pinned_codec_opt: Option<&'a CodecType>,
+    codec_types: &'a [CodecType],
+) -> CompetingCodecs<'a> {
+    pinned_codec_opt
+        .map(CompetingCodecs::pinned)
+        .unwrap_or_else(|| CompetingCodecs::new(codec_types))
+}
+
 pub fn serialize_column_mappable_to_u128<T: MonotonicallyMappableToU128>(
     column_index: SerializableColumnIndex<'_>,
     iterable: &dyn Iterable<T>,
@@ -28,14 +73,29 @@ pub fn serialize_column_mappable_to_u128<T: MonotonicallyMappableToU128>(
 pub fn serialize_column_mappable_to_u64<T: MonotonicallyMappableToU64>(
     column_index: SerializableColumnIndex<'_>,
     column_values: &impl Iterable<T>,
+    codec_types: &[CodecType],
     output: &mut impl Write,
 ) -> io::Result<()> {
-    let column_index_num_bytes = serialize_column_index(column_index, output)?;
-    serialize_u64_based_column_values(
+    serialize_column_mappable_to_u64_with_estimation(
+        column_index,
         column_values,
-        &[CodecType::Bitpacked, CodecType::BlockwiseLinear],
+        CompetingCodecs::new(codec_types),
+        CodecEstimation::Sampled,
         output,
-    )?;
+    )
+}
+
+/// Same as [`serialize_column_mappable_to_u64`], estimating the codecs of the values as per
+/// `estimation`.
+pub(crate) fn serialize_column_mappable_to_u64_with_estimation<T: MonotonicallyMappableToU64>(
+    column_index: SerializableColumnIndex<'_>,
+    column_values: &impl Iterable<T>,
+    codecs: CompetingCodecs,
+    estimation: CodecEstimation,
+    output: &mut impl Write,
+) -> io::Result<()> {
+    let column_index_num_bytes = serialize_column_index(column_index, output)?;
+    serialize_u64_based_column_values_with_estimation(column_values, codecs, estimation, output)?;
     output.write_all(&column_index_num_bytes.to_le_bytes())?;
     Ok(())
 }
@@ -53,7 +113,8 @@ pub fn open_column_u64<T: MonotonicallyMappableToU64>(
     );
     let (column_index_data, column_values_data) = body.split(column_index_num_bytes as usize);
     let column_index = crate::column_index::open_column_index(column_index_data, format_version)?;
-    let column_values = load_u64_based_column_values(column_values_data)?;
+    let column_values =
+        load_u64_based_column_values_with_version(column_values_data, format_version)?;
     Ok(Column {
         index: column_index,
         values: column_values,
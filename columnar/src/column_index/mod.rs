@@ -12,6 +12,7 @@ use std::ops::Range;
 
 pub use merge::merge_column_index;
 pub(crate) use multivalued_index::SerializableMultivalueIndex;
+pub(crate) use optional_index::{open_optional_index, serialize_optional_index};
 pub use optional_index::{OptionalIndex, Set};
 pub use serialize::{
     open_column_index, serialize_column_index, SerializableColumnIndex, SerializableOptionalIndex,
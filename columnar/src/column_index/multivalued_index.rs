@@ -8,7 +8,8 @@ use common::{CountingWriter, OwnedBytes};
 use super::optional_index::{open_optional_index, serialize_optional_index};
 use super::{OptionalIndex, SerializableOptionalIndex, Set};
 use crate::column_values::{
-    load_u64_based_column_values, serialize_u64_based_column_values, CodecType, ColumnValues,
+    load_u64_based_column_values_with_version,
+    serialize_u64_based_column_values_without_cardinality_sketch, CodecType, ColumnValues,
 };
 use crate::iterable::Iterable;
 use crate::{DocId, RowId, Version};
@@ -34,9 +35,9 @@ pub fn serialize_multivalued_index(
     serialize_optional_index(&**non_null_row_ids, *num_rows, &mut count_writer)?;
     let optional_len = count_writer.written_bytes() as u32;
     let output = count_writer.finish();
-    serialize_u64_based_column_values(
+    serialize_u64_based_column_values_without_cardinality_sketch(
         &**start_offsets,
-        &[CodecType::Bitpacked, CodecType::Linear],
+        &[CodecType::Bitpacked, CodecType::Linear, CodecType::Delta],
         output,
     )?;
     output.write_all(&optional_len.to_le_bytes())?;
@@ -50,12 +51,12 @@ pub fn open_multivalued_index(
     match format_version {
         Version::V1 => {
             let start_index_column: Arc<dyn ColumnValues<RowId>> =
-                load_u64_based_column_values(bytes)?;
+                load_u64_based_column_values_with_version(bytes, format_version)?;
             Ok(MultiValueIndex::MultiValueIndexV1(MultiValueIndexV1 {
                 start_index_column,
             }))
         }
-        Version::V2 => {
+        Version::V2 | Version::V3 => {
             let (body_bytes, optional_index_len) = bytes.rsplit(4);
             let optional_index_len =
                 u32::from_le_bytes(optional_index_len.as_slice().try_into().unwrap());
@@ -63,7 +64,7 @@ pub fn open_multivalued_index(
                 body_bytes.split(optional_index_len as usize);
             let optional_index = open_optional_index(optional_index_bytes)?;
             let start_index_column: Arc<dyn ColumnValues<RowId>> =
-                load_u64_based_column_values(start_index_bytes)?;
+                load_u64_based_column_values_with_version(start_index_bytes, format_version)?;
             Ok(MultiValueIndex::MultiValueIndexV2(MultiValueIndexV2 {
                 optional_index,
                 start_index_column,
@@ -95,9 +96,10 @@ impl MultiValueIndexV1 {
         if doc_id >= self.num_docs() {
             return 0..0;
         }
-        let start = self.start_index_column.get_val(doc_id);
-        let end = self.start_index_column.get_val(doc_id + 1);
-        start..end
+        let mut bounds = [0; 2];
+        self.start_index_column
+            .get_range(doc_id as u64, &mut bounds);
+        bounds[0]..bounds[1]
     }
 
     /// Returns the number of documents in the index.
@@ -186,7 +188,7 @@ impl MultiValueIndex {
         let mut buffer = Vec::new();
         serialize_multivalued_index(&serializable_multivalued_index, &mut buffer).unwrap();
         let bytes = OwnedBytes::new(buffer);
-        open_multivalued_index(bytes, Version::V2).unwrap()
+        open_multivalued_index(bytes, Version::V3).unwrap()
     }
 
     pub fn get_start_index_column(&self) -> &Arc<dyn crate::ColumnValues<RowId>> {
@@ -245,9 +247,11 @@ impl MultiValueIndexV2 {
         let Some(rank) = self.optional_index.rank_if_exists(doc_id) else {
             return 0..0;
         };
-        let start = self.start_index_column.get_val(rank);
-        let end = self.start_index_column.get_val(rank + 1);
-        start..end
+        // The start and the end are decoded together, which codecs like the delta codec do at
+        // the cost of a single access.
+        let mut bounds = [0; 2];
+        self.start_index_column.get_range(rank as u64, &mut bounds);
+        bounds[0]..bounds[1]
     }
 
     /// Returns the number of documents in the index.
@@ -335,6 +339,25 @@ mod tests {
         assert_eq!(index_to_pos_helper(&index, 2..5, &[12, 14, 15]), vec![2, 3]);
     }
 
+    #[test]
+    fn test_range_many_docs() {
+        let mut start_offsets = vec![0u32];
+        for doc in 0..10_000u32 {
+            let num_vals = doc * 7_919 % 5;
+            start_offsets.push(start_offsets[doc as usize] + num_vals);
+        }
+        let index = MultiValueIndex::for_test(&start_offsets);
+        assert_eq!(index.num_docs(), 10_000);
+        for doc in 0..10_000u32 {
+            let doc_range = start_offsets[doc as usize]..start_offsets[doc as usize + 1];
+            if doc_range.is_empty() {
+                assert!(index.range(doc).is_empty());
+            } else {
+                assert_eq!(index.range(doc), doc_range);
+            }
+        }
+    }
+
     #[test]
     fn test_range_to_rowids() {
         use crate::ColumnarWriter;
@@ -23,20 +23,34 @@ mod u64_based;
 mod vec_column;
 
 mod monotonic_column;
+mod monotonic_transform;
 
 pub(crate) use merge::MergedColumnValues;
-pub use stats::ColumnStats;
+pub use stats::{ColumnStats, ValueDistribution};
 pub use u128_based::{
     open_u128_as_compact_u64, open_u128_mapped, serialize_column_values_u128,
     CompactSpaceU64Accessor,
 };
 pub use u64_based::{
-    load_u64_based_column_values, serialize_and_load_u64_based_column_values,
-    serialize_u64_based_column_values, CodecType, ALL_U64_CODEC_TYPES,
+    load_u64_based_column_values, load_u64_based_column_values_with_version, register_codec,
+    serialize_and_load_u64_based_column_values, serialize_u64_based_column_values,
+    validate_u64_based_column_values_checksum, BitpackedCodec, BlockStats, BlockwiseForCodec,
+    BlockwiseLinearCodec, CardinalitySketch, CodecType, ColumnCodec, ColumnCodecEstimator,
+    ComposedCodec, DeltaTransform, LinearCodec, UnsupportedCodecError, ValueTransform,
+    ALL_U64_CODEC_TYPES, BLOCK_STATS_NUM_ROWS, CARDINALITY_SKETCH_NUM_HASHES,
+    FIRST_REGISTERED_CODEC_CODE, MAX_DICTIONARY_SIZE,
+};
+pub(crate) use u64_based::{
+    serialize_u64_based_column_values_with_estimation,
+    serialize_u64_based_column_values_without_cardinality_sketch, CodecEstimation,
+    CompetingCodecs,
 };
 pub use vec_column::VecColumn;
 
 pub use self::monotonic_column::monotonic_map_column;
+pub use self::monotonic_transform::{
+    monotonic_transform_column, LinearTransform, MonotonicTransform, TruncateDateTime,
+};
 use crate::RowId;
 
 /// `ColumnValues` provides access to a dense field column.
@@ -171,6 +185,23 @@ pub trait ColumnValues<T: PartialOrd = u64>: Send + Sync + DowncastSync {
     fn iter<'a>(&'a self) -> Box<dyn Iterator<Item = T> + 'a> {
         Box::new((0..self.num_vals()).map(|idx| self.get_val(idx)))
     }
+    /// Returns the bounds of the values of each block of [`BLOCK_STATS_NUM_ROWS`] rows, in the
+    /// `u64` space the values are serialized in.
+    ///
+    /// `get_row_ids_for_value_range` relies on them to skip the blocks that cannot match without
+    /// decoding them. Columns that were not loaded from serialized u64-based values have none.
+    fn block_stats(&self) -> &[BlockStats] {
+        &[]
+    }
+
+    /// Returns the sketch of the distinct values of the column, estimating their number without
+    /// reading the values.
+    ///
+    /// Only the u64-based columns serialized with [`Version::V3`](crate::Version::V3) or later
+    /// have one.
+    fn cardinality_sketch(&self) -> Option<&CardinalitySketch> {
+        None
+    }
 }
 downcast_rs::impl_downcast!(sync ColumnValues<T> where T: PartialOrd);
 
@@ -241,6 +272,15 @@ impl<T: Copy + PartialOrd + Debug + 'static> ColumnValues<T> for Arc<dyn ColumnV
         self.as_ref()
             .get_row_ids_for_value_range(range, doc_id_range, positions)
     }
+    #[inline(always)]
+    fn block_stats(&self) -> &[BlockStats] {
+        self.as_ref().block_stats()
+    }
+
+    #[inline(always)]
+    fn cardinality_sketch(&self) -> Option<&CardinalitySketch> {
+        self.as_ref().cardinality_sketch()
+    }
 }
 
 #[cfg(all(test, feature = "unstable"))]
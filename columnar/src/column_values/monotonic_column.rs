@@ -3,8 +3,14 @@ use std::marker::PhantomData;
 use std::ops::{Range, RangeInclusive};
 
 use crate::column_values::monotonic_mapping::StrictlyMonotonicFn;
+use crate::column_values::{BlockStats, CardinalitySketch};
 use crate::ColumnValues;
 
+/// Number of values decoded at once by `get_range`, before being mapped.
+const GET_RANGE_CHUNK_LEN: usize = 128;
+/// Ranges of up to `SMALL_RANGE_LEN` values are decoded without allocating.
+const SMALL_RANGE_LEN: usize = 4;
+
 struct MonotonicMappingColumn<C, T, Input> {
     from_column: C,
     monotonic_mapping: T,
@@ -92,8 +98,45 @@ where
         )
     }
 
-    // We voluntarily do not implement get_range as it yields a regression,
-    // and we do not have any specialized implementation anyway.
+    fn block_stats(&self) -> &[BlockStats] {
+        // Monotonic mappings keep the blocks ordered the same way in the `u64` space.
+        self.from_column.block_stats()
+    }
+
+    fn cardinality_sketch(&self) -> Option<&CardinalitySketch> {
+        // Strictly monotonic mappings are injective: the distinct values are not merged.
+        self.from_column.cardinality_sketch()
+    }
+
+    fn get_range(&self, start: u64, output: &mut [Output]) {
+        if output.is_empty() {
+            return;
+        }
+        if output.len() <= SMALL_RANGE_LEN {
+            // Short ranges, like the bounds of the values of a document in a multivalued index,
+            // are decoded on the stack.
+            let min_value = self.from_column.min_value();
+            let mut buffer: [Input; SMALL_RANGE_LEN] = std::array::from_fn(|_| min_value.clone());
+            let buffer = &mut buffer[..output.len()];
+            self.from_column.get_range(start, buffer);
+            for (out, from_val) in output.iter_mut().zip(buffer.iter()) {
+                *out = self.monotonic_mapping.mapping(from_val.clone());
+            }
+            return;
+        }
+        // Decoding the range in the inner column is worth the extra copy, as codecs decode
+        // ranges of values in batches.
+        let chunk_len = output.len().min(GET_RANGE_CHUNK_LEN);
+        let mut buffer = vec![self.from_column.min_value(); chunk_len];
+        for (chunk_ord, output_chunk) in output.chunks_mut(chunk_len).enumerate() {
+            let buffer = &mut buffer[..output_chunk.len()];
+            self.from_column
+                .get_range(start + (chunk_ord * chunk_len) as u64, buffer);
+            for (out, from_val) in output_chunk.iter_mut().zip(buffer.iter()) {
+                *out = self.monotonic_mapping.mapping(from_val.clone());
+            }
+        }
+    }
 }
 
 #[cfg(test)]
@@ -104,6 +147,21 @@ mod tests {
     };
     use crate::column_values::VecColumn;
 
+    #[test]
+    fn test_monotonic_mapping_get_range() {
+        let vals: Vec<u64> = (0..1_000u64).map(|el| el * 10).collect();
+        let col = VecColumn::from(vals);
+        let mapped = monotonic_map_column(
+            col,
+            StrictlyMonotonicMappingInverter::from(StrictlyMonotonicMappingToInternal::<i64>::new()),
+        );
+        let mut output = vec![0u64; 300];
+        mapped.get_range(100, &mut output);
+        for (i, val) in output.iter().enumerate() {
+            assert_eq!(*val, mapped.get_val(100 + i as u32));
+        }
+    }
+
     #[test]
     fn test_monotonic_mapping_iter() {
         let vals: Vec<u64> = (0..100u64).map(|el| el * 10).collect();
@@ -0,0 +1,396 @@
+use std::fmt::Debug;
+use std::marker::PhantomData;
+use std::ops::{Range, RangeInclusive};
+
+use common::DateTime;
+
+use crate::{ColumnValues, RowId};
+
+/// Non-decreasing transform of the values of a column, used to read a column as a derived one
+/// (e.g. a timestamp truncated to the hour) without materializing it. See
+/// [`monotonic_transform_column`].
+///
+/// Unlike a [`StrictlyMonotonicFn`](crate::column_values::StrictlyMonotonicFn), the transform
+/// does not need to be injective: several values may be transformed into the same one, so range
+/// queries rely on [`MonotonicTransform::preimage`] rather than on an inverse.
+pub trait MonotonicTransform<T>: Send + Sync + 'static {
+    /// Transforms a value.
+    ///
+    /// For all `a <= b`, we must have `transform(a) <= transform(b)`.
+    fn transform(&self, val: T) -> T;
+
+    /// Returns the range of the values that are transformed into a value of `range`, or `None` if
+    /// there are none.
+    ///
+    /// As the transform is non-decreasing, these values form a range.
+    fn preimage(&self, range: RangeInclusive<T>) -> Option<RangeInclusive<T>>;
+}
+
+/// Transform `val * multiplier + offset`, saturating at the bounds of the type.
+///
+/// It covers scaling values, shifting them, and multiplying them back by the gcd they were
+/// divided by.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct LinearTransform<T> {
+    multiplier: T,
+    offset: T,
+}
+
+// The transforms and their preimages are computed in `i128`, in which neither `u64` nor `i64`
+// values overflow before being clamped back to the bounds of the type.
+macro_rules! impl_linear_transform {
+    ($typ:ty) => {
+        impl LinearTransform<$typ> {
+            /// Creates the transform `val * multiplier + offset`.
+            ///
+            /// # Panics
+            ///
+            /// Panics if `multiplier` is not strictly positive, as the transform would not be
+            /// non-decreasing.
+            pub fn new(multiplier: $typ, offset: $typ) -> Self {
+                assert!(
+                    multiplier > 0,
+                    "The multiplier of a linear transform must be strictly positive"
+                );
+                LinearTransform { multiplier, offset }
+            }
+
+            /// Creates the transform `val * multiplier`.
+            pub fn scale(multiplier: $typ) -> Self {
+                Self::new(multiplier, 0)
+            }
+
+            /// Creates the transform `val + offset`.
+            pub fn offset(offset: $typ) -> Self {
+                Self::new(1, offset)
+            }
+        }
+
+        impl MonotonicTransform<$typ> for LinearTransform<$typ> {
+            #[inline]
+            fn transform(&self, val: $typ) -> $typ {
+                (val as i128)
+                    .saturating_mul(self.multiplier as i128)
+                    .saturating_add(self.offset as i128)
+                    .clamp(<$typ>::MIN as i128, <$typ>::MAX as i128) as $typ
+            }
+
+            fn preimage(&self, range: RangeInclusive<$typ>) -> Option<RangeInclusive<$typ>> {
+                let (min, max) = (<$typ>::MIN as i128, <$typ>::MAX as i128);
+                let multiplier = self.multiplier as i128;
+                let offset = self.offset as i128;
+                let (start, end) = (*range.start() as i128, *range.end() as i128);
+                // Saturated values are transformed into the bounds of the type, so ranges reaching
+                // a bound also contain the values saturating there.
+                let preimage_start = if start <= min {
+                    min
+                } else {
+                    -(offset - start).div_euclid(multiplier)
+                };
+                let preimage_end = if end >= max {
+                    max
+                } else {
+                    (end - offset).div_euclid(multiplier).min(max)
+                };
+                let preimage_start = preimage_start.max(min);
+                if preimage_start > preimage_end || preimage_start > max || preimage_end < min {
+                    return None;
+                }
+                Some(preimage_start as $typ..=preimage_end as $typ)
+            }
+        }
+    };
+}
+
+impl_linear_transform!(u64);
+impl_linear_transform!(i64);
+
+/// Transform truncating a date to the start of the interval it belongs to, intervals being
+/// aligned on the UNIX epoch.
+///
+/// E.g. `TruncateDateTime::hours(1)` buckets timestamps by hour.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct TruncateDateTime {
+    interval_nanos: i64,
+}
+
+impl TruncateDateTime {
+    /// Creates the transform truncating dates to a multiple of `interval_nanos` nanoseconds.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `interval_nanos` is not strictly positive.
+    pub fn new(interval_nanos: i64) -> Self {
+        assert!(
+            interval_nanos > 0,
+            "The truncation interval must be strictly positive"
+        );
+        TruncateDateTime { interval_nanos }
+    }
+
+    /// Creates the transform truncating dates to a multiple of `num_seconds` seconds.
+    pub fn seconds(num_seconds: u32) -> Self {
+        Self::new(num_seconds as i64 * 1_000_000_000)
+    }
+
+    /// Creates the transform truncating dates to a multiple of `num_hours` hours.
+    pub fn hours(num_hours: u32) -> Self {
+        Self::new(num_hours as i64 * 3_600 * 1_000_000_000)
+    }
+
+    /// Creates the transform truncating dates to a multiple of `num_days` days.
+    pub fn days(num_days: u32) -> Self {
+        Self::new(num_days as i64 * 86_400 * 1_000_000_000)
+    }
+}
+
+impl MonotonicTransform<DateTime> for TruncateDateTime {
+    #[inline]
+    fn transform(&self, val: DateTime) -> DateTime {
+        let interval = self.interval_nanos as i128;
+        let truncated = (val.into_timestamp_nanos() as i128).div_euclid(interval) * interval;
+        // Only the dates in the interval straddling `i64::MIN` saturate.
+        DateTime::from_timestamp_nanos(truncated.max(i64::MIN as i128) as i64)
+    }
+
+    fn preimage(&self, range: RangeInclusive<DateTime>) -> Option<RangeInclusive<DateTime>> {
+        let interval = self.interval_nanos as i128;
+        let start = range.start().into_timestamp_nanos() as i128;
+        let end = range.end().into_timestamp_nanos() as i128;
+        // The first date of the first interval starting at `start` or after it...
+        let preimage_start = if start <= i64::MIN as i128 {
+            i64::MIN as i128
+        } else {
+            -(-start).div_euclid(interval) * interval
+        };
+        // ... and the last date of the interval containing `end`.
+        let preimage_end = ((end.div_euclid(interval) + 1) * interval - 1).min(i64::MAX as i128);
+        if preimage_start > preimage_end {
+            return None;
+        }
+        Some(
+            DateTime::from_timestamp_nanos(preimage_start as i64)
+                ..=DateTime::from_timestamp_nanos(preimage_end as i64),
+        )
+    }
+}
+
+struct MonotonicTransformColumn<C, M, T> {
+    from_column: C,
+    transform: M,
+    _phantom: PhantomData<T>,
+}
+
+/// Creates a view of a column whose values are transformed by a [`MonotonicTransform`].
+///
+/// The values are transformed as they are read, so that the derived column is never
+/// materialized. Range queries are resolved on the original column, over the preimage of the
+/// range.
+pub fn monotonic_transform_column<C, M, T>(from_column: C, transform: M) -> impl ColumnValues<T>
+where
+    C: ColumnValues<T> + 'static,
+    M: MonotonicTransform<T>,
+    T: PartialOrd + Debug + Send + Sync + Clone + 'static,
+{
+    MonotonicTransformColumn {
+        from_column,
+        transform,
+        _phantom: PhantomData,
+    }
+}
+
+impl<C, M, T> ColumnValues<T> for MonotonicTransformColumn<C, M, T>
+where
+    C: ColumnValues<T> + 'static,
+    M: MonotonicTransform<T>,
+    T: PartialOrd + Debug + Send + Sync + Clone + 'static,
+{
+    #[inline(always)]
+    fn get_val(&self, idx: u32) -> T {
+        self.transform.transform(self.from_column.get_val(idx))
+    }
+
+    fn get_range(&self, start: u64, output: &mut [T]) {
+        self.from_column.get_range(start, output);
+        for val in output.iter_mut() {
+            *val = self.transform.transform(val.clone());
+        }
+    }
+
+    fn get_row_ids_for_value_range(
+        &self,
+        value_range: RangeInclusive<T>,
+        row_id_range: Range<RowId>,
+        row_id_hits: &mut Vec<RowId>,
+    ) {
+        let Some(preimage) = self.transform.preimage(value_range) else {
+            row_id_hits.clear();
+            return;
+        };
+        self.from_column
+            .get_row_ids_for_value_range(preimage, row_id_range, row_id_hits);
+    }
+
+    fn min_value(&self) -> T {
+        self.transform.transform(self.from_column.min_value())
+    }
+
+    fn max_value(&self) -> T {
+        self.transform.transform(self.from_column.max_value())
+    }
+
+    fn num_vals(&self) -> u32 {
+        self.from_column.num_vals()
+    }
+
+    fn iter(&self) -> Box<dyn Iterator<Item = T> + '_> {
+        Box::new(
+            self.from_column
+                .iter()
+                .map(|val| self.transform.transform(val)),
+        )
+    }
+
+    // The block stats of the original column bound its original values, not the transformed
+    // ones, and the transform may merge distinct values, so neither the block stats nor the
+    // cardinality sketch are passed through.
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::column_values::VecColumn;
+
+    fn assert_preimage_consistent<T, M>(transform: &M, vals: &[T], range: RangeInclusive<T>)
+    where
+        T: PartialOrd + Debug + Copy,
+        M: MonotonicTransform<T>,
+    {
+        let preimage = transform.preimage(range.clone());
+        for &val in vals {
+            let in_range = range.contains(&transform.transform(val));
+            let in_preimage = preimage
+                .as_ref()
+                .map(|preimage| preimage.contains(&val))
+                .unwrap_or(false);
+            assert_eq!(in_range, in_preimage, "val={val:?} range={range:?}");
+        }
+    }
+
+    #[test]
+    fn test_linear_transform_u64() {
+        let transform = LinearTransform::new(3u64, 10);
+        assert_eq!(transform.transform(4), 22);
+        assert_eq!(transform.transform(u64::MAX / 2), u64::MAX);
+        assert_eq!(transform.preimage(0..=9), None);
+        assert_eq!(transform.preimage(11..=12), None);
+        assert_eq!(transform.preimage(11..=13), Some(1..=1));
+        assert_eq!(
+            transform.preimage(u64::MAX..=u64::MAX).unwrap().end(),
+            &u64::MAX
+        );
+        let vals: Vec<u64> = (0..100).chain(u64::MAX - 100..=u64::MAX).collect();
+        for range in [
+            0..=u64::MAX,
+            10..=40,
+            11..=41,
+            50..=20,
+            u64::MAX - 5..=u64::MAX,
+        ] {
+            assert_preimage_consistent(&transform, &vals, range);
+        }
+    }
+
+    #[test]
+    fn test_linear_transform_i64() {
+        let transform = LinearTransform::new(7i64, -20);
+        assert_eq!(transform.transform(-3), -41);
+        assert_eq!(transform.transform(i64::MIN), i64::MIN);
+        assert_eq!(transform.transform(i64::MAX), i64::MAX);
+        let vals: Vec<i64> = (-100..100)
+            .chain(i64::MIN..=i64::MIN + 10)
+            .chain(i64::MAX - 10..=i64::MAX)
+            .collect();
+        for range in [
+            i64::MIN..=i64::MAX,
+            -50..=50,
+            -49..=-48,
+            i64::MIN..=-1_000,
+            1_000..=i64::MAX,
+            10..=-10,
+        ] {
+            assert_preimage_consistent(&transform, &vals, range);
+        }
+        assert_eq!(LinearTransform::offset(5i64).transform(-5), 0);
+        assert_eq!(LinearTransform::scale(5i64).transform(-5), -25);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_linear_transform_requires_positive_multiplier() {
+        LinearTransform::new(0u64, 1);
+    }
+
+    #[test]
+    fn test_truncate_date_time() {
+        let transform = TruncateDateTime::hours(1);
+        let hour_nanos = 3_600 * 1_000_000_000i64;
+        let date = |nanos: i64| DateTime::from_timestamp_nanos(nanos);
+        assert_eq!(transform.transform(date(hour_nanos + 1)), date(hour_nanos));
+        assert_eq!(transform.transform(date(-1)), date(-hour_nanos));
+        assert_eq!(transform.transform(date(i64::MIN)), date(i64::MIN));
+        assert_eq!(
+            transform.preimage(date(1)..=date(hour_nanos)),
+            Some(date(hour_nanos)..=date(2 * hour_nanos - 1))
+        );
+        assert_eq!(transform.preimage(date(1)..=date(hour_nanos - 1)), None);
+        let vals: Vec<DateTime> = (-100..100)
+            .map(|i| date(i * hour_nanos / 7))
+            .chain([date(i64::MIN), date(i64::MAX)])
+            .collect();
+        for range in [
+            date(i64::MIN)..=date(i64::MAX),
+            date(-hour_nanos)..=date(hour_nanos),
+            date(-hour_nanos + 1)..=date(hour_nanos - 1),
+            date(5 * hour_nanos)..=date(i64::MAX),
+            date(i64::MIN)..=date(-5 * hour_nanos - 1),
+        ] {
+            assert_preimage_consistent(&transform, &vals, range);
+        }
+    }
+
+    #[test]
+    fn test_monotonic_transform_column() {
+        let hour_nanos = 3_600 * 1_000_000_000i64;
+        let vals: Vec<DateTime> = (0..1_000i64)
+            .map(|i| DateTime::from_timestamp_nanos(i * hour_nanos / 10))
+            .collect();
+        let column =
+            monotonic_transform_column(VecColumn::from(vals.clone()), TruncateDateTime::hours(1));
+        let expected: Vec<DateTime> = vals
+            .iter()
+            .map(|val| TruncateDateTime::hours(1).transform(*val))
+            .collect();
+        assert_eq!(column.iter().collect::<Vec<DateTime>>(), expected);
+        assert_eq!(
+            column.get_val(15),
+            DateTime::from_timestamp_nanos(hour_nanos)
+        );
+        assert_eq!(column.min_value(), expected[0]);
+        assert_eq!(column.max_value(), expected[999]);
+        let mut output = vec![DateTime::default(); 100];
+        column.get_range(50, &mut output);
+        assert_eq!(&output[..], &expected[50..150]);
+
+        let value_range = DateTime::from_timestamp_nanos(3 * hour_nanos)
+            ..=DateTime::from_timestamp_nanos(4 * hour_nanos);
+        let mut row_ids = Vec::new();
+        column.get_row_ids_for_value_range(value_range.clone(), 0..1_000, &mut row_ids);
+        assert_eq!(row_ids, (30..50).collect::<Vec<RowId>>());
+
+        let value_range = DateTime::from_timestamp_nanos(3 * hour_nanos + 1)
+            ..=DateTime::from_timestamp_nanos(4 * hour_nanos - 1);
+        column.get_row_ids_for_value_range(value_range, 0..1_000, &mut row_ids);
+        assert!(row_ids.is_empty());
+    }
+}
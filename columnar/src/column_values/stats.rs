@@ -1,6 +1,7 @@
 use std::io;
 use std::io::Write;
 use std::num::NonZeroU64;
+use std::sync::Arc;
 
 use common::{BinarySerializable, VInt};
 
@@ -17,6 +18,56 @@ pub struct ColumnStats {
     pub max_value: u64,
     /// Number of rows in the column.
     pub num_rows: RowId,
+    /// Number of runs of identical consecutive values.
+    ///
+    /// It is only known when the stats are collected from the values: it is not serialized, and
+    /// deserialized stats count one run per row.
+    pub num_runs: RowId,
+    /// Distribution of the values of the whole column, if it was gathered.
+    ///
+    /// When codecs are estimated on a sample of the column, the other stats describe the
+    /// sample, but the distribution still describes the whole column. It is not serialized.
+    pub distribution: Option<ValueDistribution>,
+}
+
+/// Distribution of the values of a whole column: its extreme values, along with a sketch of its
+/// quantiles.
+///
+/// It lets codecs estimated on a sample account for the values outside of the sample, e.g. the
+/// rare large values of a heavy-tailed column.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct ValueDistribution {
+    /// Minimum value of the whole column.
+    pub min_value: u64,
+    /// Maximum value of the whole column.
+    pub max_value: u64,
+    // Evenly spaced values of the column, sorted.
+    sorted_sample: Arc<[u64]>,
+}
+
+impl ValueDistribution {
+    pub(crate) fn new(min_value: u64, max_value: u64, mut sample: Vec<u64>) -> Self {
+        sample.sort_unstable();
+        ValueDistribution {
+            min_value,
+            max_value,
+            sorted_sample: sample.into(),
+        }
+    }
+
+    /// Difference between the maximum and the minimum value of the whole column.
+    pub fn amplitude(&self) -> u64 {
+        self.max_value - self.min_value
+    }
+
+    /// Returns an approximation of the `q`-quantile of the values, for `q` between 0 and 1.
+    pub fn quantile(&self, q: f64) -> u64 {
+        if self.sorted_sample.is_empty() {
+            return self.min_value;
+        }
+        let idx = ((self.sorted_sample.len() - 1) as f64 * q.clamp(0.0, 1.0)).round() as usize;
+        self.sorted_sample[idx]
+    }
 }
 
 impl ColumnStats {
@@ -48,6 +99,8 @@ impl BinarySerializable for ColumnStats {
             min_value,
             max_value,
             num_rows,
+            num_runs: num_rows,
+            distribution: None,
             gcd,
         })
     }
@@ -78,6 +131,8 @@ mod tests {
                 min_value: 1,
                 max_value: 3001,
                 num_rows: 10,
+                num_runs: 10,
+                distribution: None,
             }),
             5,
         );
@@ -87,6 +142,8 @@ mod tests {
                 min_value: 1,
                 max_value: 3001,
                 num_rows: 10,
+                num_runs: 10,
+                distribution: None,
             }),
             5,
         );
@@ -96,6 +153,8 @@ mod tests {
                 min_value: 0,
                 max_value: 0,
                 num_rows: 0,
+                num_runs: 0,
+                distribution: None,
             }),
             4,
         );
@@ -0,0 +1,218 @@
+use std::collections::BTreeSet;
+use std::io::{self, Write};
+use std::ops::{Range, RangeInclusive};
+
+use common::{BinarySerializable, OwnedBytes, VIntU128};
+use tantivy_bitpacker::{compute_num_bits, BitPacker, BitUnpacker};
+
+use crate::column_values::u128_based::CompactU64ColumnValues;
+use crate::{ColumnValues, RowId};
+
+/// Reader of u128 values stored as the bitpacked offsets from the minimum value of the column.
+#[derive(Clone)]
+pub(crate) struct BitpackedU128Reader {
+    data: OwnedBytes,
+    bit_unpacker: BitUnpacker,
+    min_value: u128,
+    amplitude: u64,
+    num_vals: RowId,
+}
+
+/// Returns the amplitude of the values, if it fits in a u64.
+fn amplitude(distinct_values: &BTreeSet<u128>) -> Option<u64> {
+    let min_value = distinct_values.first().copied().unwrap_or(0);
+    let max_value = distinct_values.last().copied().unwrap_or(0);
+    u64::try_from(max_value - min_value).ok()
+}
+
+/// Returns the number of bytes [`serialize`] writes, or `None` if the bitpacked codec is not
+/// applicable.
+pub(crate) fn estimate(distinct_values: &BTreeSet<u128>, num_vals: RowId) -> Option<u64> {
+    let min_value = distinct_values.first().copied().unwrap_or(0);
+    let amplitude = amplitude(distinct_values)?;
+    let num_bits = compute_num_bits(amplitude);
+    Some(
+        VIntU128(min_value).num_bytes()
+            + VIntU128(amplitude as u128).num_bytes()
+            + (num_vals as u64 * num_bits as u64 + 7) / 8,
+    )
+}
+
+pub(crate) fn serialize(
+    distinct_values: &BTreeSet<u128>,
+    vals: impl Iterator<Item = u128>,
+    wrt: &mut impl Write,
+) -> io::Result<()> {
+    let min_value = distinct_values.first().copied().unwrap_or(0);
+    let amplitude = amplitude(distinct_values).ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "The amplitude of the values does not fit in a u64.",
+        )
+    })?;
+    VIntU128(min_value).serialize(wrt)?;
+    VIntU128(amplitude as u128).serialize(wrt)?;
+    let num_bits = compute_num_bits(amplitude);
+    let mut bit_packer = BitPacker::new();
+    for val in vals {
+        bit_packer.write((val - min_value) as u64, num_bits, wrt)?;
+    }
+    bit_packer.close(wrt)?;
+    Ok(())
+}
+
+impl BitpackedU128Reader {
+    pub fn open(num_vals: RowId, mut data: OwnedBytes) -> io::Result<BitpackedU128Reader> {
+        let min_value = VIntU128::deserialize(&mut data)?.0;
+        let amplitude = u64::try_from(VIntU128::deserialize(&mut data)?.0)
+            .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "Invalid amplitude."))?;
+        Ok(BitpackedU128Reader {
+            data,
+            bit_unpacker: BitUnpacker::new(compute_num_bits(amplitude)),
+            min_value,
+            amplitude,
+            num_vals,
+        })
+    }
+}
+
+impl ColumnValues<u128> for BitpackedU128Reader {
+    #[inline]
+    fn get_val(&self, idx: u32) -> u128 {
+        self.min_value + self.bit_unpacker.get(idx, &self.data) as u128
+    }
+
+    fn min_value(&self) -> u128 {
+        self.min_value
+    }
+
+    fn max_value(&self) -> u128 {
+        self.min_value + self.amplitude as u128
+    }
+
+    fn num_vals(&self) -> RowId {
+        self.num_vals
+    }
+
+    fn iter(&self) -> Box<dyn Iterator<Item = u128> + '_> {
+        Box::new((0..self.num_vals).map(|idx| self.get_val(idx)))
+    }
+
+    fn get_row_ids_for_value_range(
+        &self,
+        value_range: RangeInclusive<u128>,
+        row_id_range: Range<RowId>,
+        row_id_hits: &mut Vec<RowId>,
+    ) {
+        let max_value = self.max_value();
+        if value_range.is_empty()
+            || *value_range.end() < self.min_value
+            || *value_range.start() > max_value
+        {
+            row_id_hits.clear();
+            return;
+        }
+        let compact_range = (value_range.start().saturating_sub(self.min_value) as u64)
+            ..=((*value_range.end()).min(max_value) - self.min_value) as u64;
+        self.get_row_ids_for_compact_val_range(compact_range, row_id_range, row_id_hits);
+    }
+}
+
+impl CompactU64ColumnValues for BitpackedU128Reader {
+    #[inline]
+    fn get_compact_val(&self, idx: u32) -> u64 {
+        self.bit_unpacker.get(idx, &self.data)
+    }
+
+    fn compact_val_to_u128(&self, compact: u64) -> u128 {
+        self.min_value + compact as u128
+    }
+
+    fn min_compact_val(&self) -> u64 {
+        0
+    }
+
+    fn max_compact_val(&self) -> u64 {
+        self.amplitude
+    }
+
+    fn iter_compact_vals(&self) -> Box<dyn Iterator<Item = u64> + '_> {
+        Box::new((0..self.num_vals).map(|idx| self.get_compact_val(idx)))
+    }
+
+    fn get_row_ids_for_compact_val_range(
+        &self,
+        compact_range: RangeInclusive<u64>,
+        row_id_range: Range<RowId>,
+        row_id_hits: &mut Vec<RowId>,
+    ) {
+        let row_id_range = row_id_range.start..row_id_range.end.min(self.num_vals);
+        self.bit_unpacker.get_ids_for_value_range(
+            compact_range,
+            row_id_range,
+            &self.data,
+            row_id_hits,
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::column_values::u128_based::{
+        open_u128_as_compact_u64, open_u128_mapped, serialize_column_values_u128_with_codec_types,
+        U128FastFieldCodecType,
+    };
+
+    fn serialize_bitpacked(vals: &[u128]) -> OwnedBytes {
+        let mut out = Vec::new();
+        serialize_column_values_u128_with_codec_types(
+            &vals,
+            &[U128FastFieldCodecType::Bitpacked],
+            &mut out,
+        )
+        .unwrap();
+        OwnedBytes::new(out)
+    }
+
+    #[test]
+    fn test_bitpacked_u128() {
+        let base = u64::MAX as u128 * 1_000;
+        let vals: Vec<u128> = (0..1_000u128).map(|i| base + (i * 7919) % 5_000).collect();
+        let data = serialize_bitpacked(&vals);
+        let column = open_u128_mapped::<u128>(data.clone()).unwrap();
+        assert_eq!(column.num_vals(), 1_000);
+        assert_eq!(column.iter().collect::<Vec<u128>>(), vals);
+        assert_eq!(column.min_value(), base);
+        assert_eq!(column.max_value(), *vals.iter().max().unwrap());
+        for value_range in [
+            0..=u128::MAX,
+            base + 100..=base + 2_000,
+            base + 10_000..=u128::MAX,
+            0..=base - 1,
+        ] {
+            let mut row_ids = Vec::new();
+            column.get_row_ids_for_value_range(value_range.clone(), 10..900, &mut row_ids);
+            let expected_row_ids: Vec<RowId> = (10..900)
+                .filter(|row_id| value_range.contains(&vals[*row_id as usize]))
+                .collect();
+            assert_eq!(row_ids, expected_row_ids);
+        }
+
+        let compact_column = open_u128_as_compact_u64(data).unwrap();
+        assert_eq!(compact_column.min_value(), 0);
+        assert_eq!(
+            compact_column.max_value(),
+            (*vals.iter().max().unwrap() - base) as u64
+        );
+        assert_eq!(compact_column.get_val(3), (vals[3] - base) as u64);
+    }
+
+    #[test]
+    fn test_bitpacked_u128_not_applicable_to_large_amplitude() {
+        let distinct_values: BTreeSet<u128> = [0u128, u64::MAX as u128 + 1].into_iter().collect();
+        assert!(estimate(&distinct_values, 2).is_none());
+        let distinct_values: BTreeSet<u128> = [1u128, u64::MAX as u128 + 1].into_iter().collect();
+        assert!(estimate(&distinct_values, 2).is_some());
+    }
+}
@@ -24,6 +24,7 @@ use build_compact_space::get_compact_space;
 use common::{BinarySerializable, CountingWriter, OwnedBytes, VInt, VIntU128};
 use tantivy_bitpacker::{BitPacker, BitUnpacker};
 
+use crate::column_values::u128_based::CompactU64ColumnValues;
 use crate::column_values::ColumnValues;
 use crate::RowId;
 
@@ -184,11 +185,17 @@ impl CompactSpaceCompressor {
             total_num_values += 1u32;
             values_sorted.insert(val);
         }
+        Self::train_from_sorted(&values_sorted, total_num_values)
+    }
+
+    /// Same as [`CompactSpaceCompressor::train_from`], from the distinct values of the column and
+    /// their total number, redundancy included.
+    pub(crate) fn train_from_sorted(values_sorted: &BTreeSet<u128>, total_num_values: u32) -> Self {
         let min_value = *values_sorted.iter().next().unwrap_or(&0);
         let max_value = *values_sorted.iter().last().unwrap_or(&0);
 
         let compact_space =
-            get_compact_space(&values_sorted, total_num_values, COST_PER_BLANK_IN_BITS);
+            get_compact_space(values_sorted, total_num_values, COST_PER_BLANK_IN_BITS);
         let amplitude_compact_space = compact_space.amplitude_compact_space();
 
         assert!(
@@ -216,6 +223,12 @@ impl CompactSpaceCompressor {
         }
     }
 
+    /// Returns the number of bytes [`CompactSpaceCompressor::compress_into`] writes.
+    pub(crate) fn num_bytes(&self) -> u64 {
+        let num_bits_values = self.params.num_vals as u64 * self.params.num_bits as u64;
+        (num_bits_values + 7) / 8 + self.params.num_bytes() + 4
+    }
+
     fn write_footer(self, writer: &mut impl Write) -> io::Result<()> {
         let writer = &mut CountingWriter::wrap(writer);
         self.params.serialize(writer)?;
@@ -292,63 +305,6 @@ impl BinarySerializable for IPCodecParams {
     }
 }
 
-/// Exposes the compact space compressed values as u64.
-///
-/// This allows faster access to the values, as u64 is faster to work with than u128.
-/// It also allows to handle u128 values like u64, via the `open_u64_lenient` as a uniform
-/// access interface.
-///
-/// When converting from the internal u64 to u128 `compact_to_u128` can be used.
-pub struct CompactSpaceU64Accessor(CompactSpaceDecompressor);
-impl CompactSpaceU64Accessor {
-    pub(crate) fn open(data: OwnedBytes) -> io::Result<CompactSpaceU64Accessor> {
-        let decompressor = CompactSpaceU64Accessor(CompactSpaceDecompressor::open(data)?);
-        Ok(decompressor)
-    }
-    /// Convert a compact space value to u128
-    pub fn compact_to_u128(&self, compact: u32) -> u128 {
-        self.0.compact_to_u128(compact)
-    }
-}
-
-impl ColumnValues<u64> for CompactSpaceU64Accessor {
-    #[inline]
-    fn get_val(&self, doc: u32) -> u64 {
-        let compact = self.0.get_compact(doc);
-        compact as u64
-    }
-
-    fn min_value(&self) -> u64 {
-        self.0.u128_to_compact(self.0.min_value()).unwrap() as u64
-    }
-
-    fn max_value(&self) -> u64 {
-        self.0.u128_to_compact(self.0.max_value()).unwrap() as u64
-    }
-
-    fn num_vals(&self) -> u32 {
-        self.0.params.num_vals
-    }
-
-    #[inline]
-    fn iter(&self) -> Box<dyn Iterator<Item = u64> + '_> {
-        Box::new(self.0.iter_compact().map(|el| el as u64))
-    }
-
-    #[inline]
-    fn get_row_ids_for_value_range(
-        &self,
-        value_range: RangeInclusive<u64>,
-        position_range: Range<u32>,
-        positions: &mut Vec<u32>,
-    ) {
-        let value_range = self.0.compact_to_u128(*value_range.start() as u32)
-            ..=self.0.compact_to_u128(*value_range.end() as u32);
-        self.0
-            .get_row_ids_for_value_range(value_range, position_range, positions)
-    }
-}
-
 impl ColumnValues<u128> for CompactSpaceDecompressor {
     #[inline]
     fn get_val(&self, doc: u32) -> u128 {
@@ -418,6 +374,44 @@ impl ColumnValues<u128> for CompactSpaceDecompressor {
     }
 }
 
+impl CompactU64ColumnValues for CompactSpaceDecompressor {
+    #[inline]
+    fn get_compact_val(&self, idx: u32) -> u64 {
+        self.get_compact(idx) as u64
+    }
+
+    fn compact_val_to_u128(&self, compact: u64) -> u128 {
+        self.compact_to_u128(compact as u32)
+    }
+
+    fn min_compact_val(&self) -> u64 {
+        self.u128_to_compact(self.min_value()).unwrap() as u64
+    }
+
+    fn max_compact_val(&self) -> u64 {
+        self.u128_to_compact(self.max_value()).unwrap() as u64
+    }
+
+    fn iter_compact_vals(&self) -> Box<dyn Iterator<Item = u64> + '_> {
+        Box::new(self.iter_compact().map(|compact| compact as u64))
+    }
+
+    fn get_row_ids_for_compact_val_range(
+        &self,
+        compact_range: RangeInclusive<u64>,
+        position_range: Range<u32>,
+        positions: &mut Vec<u32>,
+    ) {
+        let position_range = position_range.start..position_range.end.min(self.num_vals());
+        self.params.bit_unpacker.get_ids_for_value_range(
+            compact_range,
+            position_range,
+            &self.data,
+            positions,
+        );
+    }
+}
+
 impl CompactSpaceDecompressor {
     pub fn open(data: OwnedBytes) -> io::Result<CompactSpaceDecompressor> {
         let (data_slice, footer_len_bytes) = data.split_at(data.len() - 4);
@@ -499,7 +493,9 @@ mod tests {
     use itertools::Itertools;
 
     use super::*;
-    use crate::column_values::u128_based::U128Header;
+    use crate::column_values::u128_based::{
+        serialize_column_values_u128_with_codec_types, U128FastFieldCodecType, U128Header,
+    };
     use crate::column_values::{open_u128_mapped, serialize_column_values_u128};
 
     #[test]
@@ -576,7 +572,12 @@ mod tests {
 
     fn test_aux_vals(u128_vals: &[u128]) -> OwnedBytes {
         let mut out = Vec::new();
-        serialize_column_values_u128(&u128_vals, &mut out).unwrap();
+        serialize_column_values_u128_with_codec_types(
+            &u128_vals,
+            &[U128FastFieldCodecType::CompactSpace],
+            &mut out,
+        )
+        .unwrap();
         let data = OwnedBytes::new(out);
         test_all(data.clone(), u128_vals);
         data
@@ -0,0 +1,266 @@
+use std::collections::BTreeSet;
+use std::io::{self, Write};
+use std::ops::{Range, RangeInclusive};
+use std::sync::Arc;
+
+use common::{BinarySerializable, OwnedBytes, VInt, VIntU128};
+use tantivy_bitpacker::{compute_num_bits, BitPacker, BitUnpacker};
+
+use crate::column_values::u128_based::CompactU64ColumnValues;
+use crate::column_values::MAX_DICTIONARY_SIZE;
+use crate::{ColumnValues, RowId};
+
+/// Reader of u128 values stored as the bitpacked ordinals of the values in the sorted dictionary
+/// of the distinct values of the column.
+#[derive(Clone)]
+pub(crate) struct DictionaryU128Reader {
+    dictionary: Arc<[u128]>,
+    data: OwnedBytes,
+    bit_unpacker: BitUnpacker,
+    num_vals: RowId,
+}
+
+fn num_bits(dictionary_len: usize) -> u8 {
+    compute_num_bits(dictionary_len.saturating_sub(1) as u64)
+}
+
+/// Returns the number of bytes [`serialize`] writes, or `None` if there are more than
+/// [`MAX_DICTIONARY_SIZE`] distinct values.
+pub(crate) fn estimate(distinct_values: &BTreeSet<u128>, num_vals: RowId) -> Option<u64> {
+    if distinct_values.len() > MAX_DICTIONARY_SIZE {
+        return None;
+    }
+    let mut dictionary_num_bytes = VInt(distinct_values.len() as u64).num_bytes();
+    let mut previous_val = 0u128;
+    for &val in distinct_values {
+        dictionary_num_bytes += VIntU128(val - previous_val).num_bytes();
+        previous_val = val;
+    }
+    let num_bits = num_bits(distinct_values.len());
+    Some(dictionary_num_bytes + (num_vals as u64 * num_bits as u64 + 7) / 8)
+}
+
+pub(crate) fn serialize(
+    distinct_values: &BTreeSet<u128>,
+    vals: impl Iterator<Item = u128>,
+    wrt: &mut impl Write,
+) -> io::Result<()> {
+    let dictionary: Vec<u128> = distinct_values.iter().copied().collect();
+    VInt(dictionary.len() as u64).serialize(wrt)?;
+    let mut previous_val = 0u128;
+    for &val in &dictionary {
+        VIntU128(val - previous_val).serialize(wrt)?;
+        previous_val = val;
+    }
+    let num_bits = num_bits(dictionary.len());
+    let mut bit_packer = BitPacker::new();
+    for val in vals {
+        let ord = dictionary.binary_search(&val).map_err(|_| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "Value missing from the dictionary.",
+            )
+        })?;
+        bit_packer.write(ord as u64, num_bits, wrt)?;
+    }
+    bit_packer.close(wrt)?;
+    Ok(())
+}
+
+impl DictionaryU128Reader {
+    pub fn open(num_vals: RowId, mut data: OwnedBytes) -> io::Result<DictionaryU128Reader> {
+        let dictionary_len = VInt::deserialize(&mut data)?.0;
+        // Each value of the dictionary takes at least one byte.
+        if dictionary_len > data.len() as u64 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "Dictionary is truncated",
+            ));
+        }
+        let dictionary_len = dictionary_len as usize;
+        let mut dictionary = Vec::with_capacity(dictionary_len);
+        let mut val = 0u128;
+        for _ in 0..dictionary_len {
+            let delta = VIntU128::deserialize(&mut data)?.0;
+            val = val.checked_add(delta).ok_or_else(|| {
+                io::Error::new(io::ErrorKind::InvalidData, "Dictionary value overflows")
+            })?;
+            dictionary.push(val);
+        }
+        Ok(DictionaryU128Reader {
+            dictionary: dictionary.into(),
+            data,
+            bit_unpacker: BitUnpacker::new(num_bits(dictionary_len)),
+            num_vals,
+        })
+    }
+}
+
+impl ColumnValues<u128> for DictionaryU128Reader {
+    #[inline]
+    fn get_val(&self, idx: u32) -> u128 {
+        let ord = self.bit_unpacker.get(idx, &self.data);
+        self.dictionary[ord as usize]
+    }
+
+    fn min_value(&self) -> u128 {
+        self.dictionary.first().copied().unwrap_or(0)
+    }
+
+    fn max_value(&self) -> u128 {
+        self.dictionary.last().copied().unwrap_or(0)
+    }
+
+    fn num_vals(&self) -> RowId {
+        self.num_vals
+    }
+
+    fn iter(&self) -> Box<dyn Iterator<Item = u128> + '_> {
+        Box::new((0..self.num_vals).map(|idx| self.get_val(idx)))
+    }
+
+    fn get_row_ids_for_value_range(
+        &self,
+        value_range: RangeInclusive<u128>,
+        row_id_range: Range<RowId>,
+        row_id_hits: &mut Vec<RowId>,
+    ) {
+        // The dictionary is sorted, so the values of the range map to a range of ordinals.
+        let start_ord = self
+            .dictionary
+            .partition_point(|val| val < value_range.start());
+        let end_ord = self
+            .dictionary
+            .partition_point(|val| val <= value_range.end());
+        if start_ord >= end_ord {
+            row_id_hits.clear();
+            return;
+        }
+        self.get_row_ids_for_compact_val_range(
+            start_ord as u64..=(end_ord - 1) as u64,
+            row_id_range,
+            row_id_hits,
+        );
+    }
+}
+
+impl CompactU64ColumnValues for DictionaryU128Reader {
+    #[inline]
+    fn get_compact_val(&self, idx: u32) -> u64 {
+        self.bit_unpacker.get(idx, &self.data)
+    }
+
+    fn compact_val_to_u128(&self, compact: u64) -> u128 {
+        self.dictionary[compact as usize]
+    }
+
+    fn min_compact_val(&self) -> u64 {
+        0
+    }
+
+    fn max_compact_val(&self) -> u64 {
+        self.dictionary.len().saturating_sub(1) as u64
+    }
+
+    fn iter_compact_vals(&self) -> Box<dyn Iterator<Item = u64> + '_> {
+        Box::new((0..self.num_vals).map(|idx| self.get_compact_val(idx)))
+    }
+
+    fn get_row_ids_for_compact_val_range(
+        &self,
+        compact_range: RangeInclusive<u64>,
+        row_id_range: Range<RowId>,
+        row_id_hits: &mut Vec<RowId>,
+    ) {
+        let row_id_range = row_id_range.start..row_id_range.end.min(self.num_vals);
+        self.bit_unpacker.get_ids_for_value_range(
+            compact_range,
+            row_id_range,
+            &self.data,
+            row_id_hits,
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::column_values::u128_based::{
+        open_u128_as_compact_u64, open_u128_mapped, serialize_column_values_u128,
+        serialize_column_values_u128_with_codec_types, U128FastFieldCodecType, U128Header,
+    };
+
+    #[test]
+    fn test_dictionary_u128() {
+        // A handful of IPv6 addresses, far apart from each other.
+        let ips = [
+            0x2001_0db8_0000_0000_0000_0000_0000_0001u128,
+            0x2001_0db8_85a3_0000_0000_8a2e_0370_7334u128,
+            0xfe80_0000_0000_0000_0202_b3ff_fe1e_8329u128,
+            0x0000_0000_0000_0000_0000_ffff_c0a8_0001u128,
+        ];
+        let vals: Vec<u128> = (0..1_000usize).map(|i| ips[i * i % 7 % 4]).collect();
+        let mut out = Vec::new();
+        serialize_column_values_u128_with_codec_types(
+            &&vals[..],
+            &[U128FastFieldCodecType::Dictionary],
+            &mut out,
+        )
+        .unwrap();
+        // 2 bits per value, along with the 4 values of the dictionary.
+        assert!(out.len() < 1_000 * 2 / 8 + 100);
+        let data = OwnedBytes::new(out);
+        let column = open_u128_mapped::<u128>(data.clone()).unwrap();
+        assert_eq!(column.iter().collect::<Vec<u128>>(), vals);
+        assert_eq!(column.min_value(), ips[3]);
+        assert_eq!(column.max_value(), ips[2]);
+        for value_range in [0..=u128::MAX, ips[0]..=ips[1], ips[0] + 1..=ips[1] - 1] {
+            let mut row_ids = Vec::new();
+            column.get_row_ids_for_value_range(value_range.clone(), 5..500, &mut row_ids);
+            let expected_row_ids: Vec<RowId> = (5..500)
+                .filter(|row_id| value_range.contains(&vals[*row_id as usize]))
+                .collect();
+            assert_eq!(row_ids, expected_row_ids);
+        }
+
+        let compact_column = open_u128_as_compact_u64(data).unwrap();
+        assert_eq!(compact_column.max_value(), 3);
+        let mut row_ids = Vec::new();
+        compact_column.get_row_ids_for_value_range(2..=u64::MAX, 0..1_000, &mut row_ids);
+        let expected_row_ids: Vec<RowId> = (0..1_000)
+            .filter(|row_id| vals[*row_id as usize] >= ips[1])
+            .collect();
+        assert_eq!(row_ids, expected_row_ids);
+    }
+
+    #[test]
+    fn test_dictionary_u128_picked_for_sparse_low_cardinality() {
+        let vals: Vec<u128> = (0..1_000u128).map(|i| (i % 3) << 100).collect();
+        let mut out = Vec::new();
+        serialize_column_values_u128(&&vals[..], &mut out).unwrap();
+        let header = U128Header::deserialize(&mut &out[..]).unwrap();
+        assert_eq!(header.codec_type, U128FastFieldCodecType::Dictionary);
+    }
+
+    #[test]
+    fn test_dictionary_u128_open_corrupted() {
+        let mut data = Vec::new();
+        VInt(1_000_000).serialize(&mut data).unwrap();
+        VIntU128(1).serialize(&mut data).unwrap();
+        assert!(DictionaryU128Reader::open(0, OwnedBytes::new(data)).is_err());
+
+        let mut data = Vec::new();
+        VInt(2).serialize(&mut data).unwrap();
+        VIntU128(u128::MAX).serialize(&mut data).unwrap();
+        VIntU128(1).serialize(&mut data).unwrap();
+        assert!(DictionaryU128Reader::open(0, OwnedBytes::new(data)).is_err());
+    }
+
+    #[test]
+    fn test_dictionary_u128_not_applicable_to_high_cardinality() {
+        let distinct_values: BTreeSet<u128> = (0..=MAX_DICTIONARY_SIZE as u128).collect();
+        assert!(estimate(&distinct_values, distinct_values.len() as u32).is_none());
+        let distinct_values: BTreeSet<u128> = (0..MAX_DICTIONARY_SIZE as u128).collect();
+        assert!(estimate(&distinct_values, distinct_values.len() as u32).is_some());
+    }
+}
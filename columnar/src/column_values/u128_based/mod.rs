@@ -1,21 +1,25 @@
+use std::collections::BTreeSet;
 use std::fmt::Debug;
 use std::io;
 use std::io::Write;
+use std::ops::{Range, RangeInclusive};
 use std::sync::Arc;
 
+mod bitpacked;
 mod compact_space;
+mod dictionary;
 
+use bitpacked::BitpackedU128Reader;
 use common::{BinarySerializable, OwnedBytes, VInt};
-pub use compact_space::{
-    CompactSpaceCompressor, CompactSpaceDecompressor, CompactSpaceU64Accessor,
-};
+pub use compact_space::{CompactSpaceCompressor, CompactSpaceDecompressor};
+use dictionary::DictionaryU128Reader;
 
 use crate::column_values::monotonic_map_column;
 use crate::column_values::monotonic_mapping::{
     StrictlyMonotonicMappingInverter, StrictlyMonotonicMappingToInternal,
 };
 use crate::iterable::Iterable;
-use crate::{ColumnValues, MonotonicallyMappableToU128};
+use crate::{ColumnValues, MonotonicallyMappableToU128, RowId};
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub(crate) struct U128Header {
@@ -40,28 +44,72 @@ impl BinarySerializable for U128Header {
     }
 }
 
-/// Serializes u128 values with the compact space codec.
+/// Serializes u128 values with the codec resulting in the smallest column.
 pub fn serialize_column_values_u128<T: MonotonicallyMappableToU128>(
     iterable: &dyn Iterable<T>,
     output: &mut impl io::Write,
 ) -> io::Result<()> {
-    let compressor = CompactSpaceCompressor::train_from(
-        iterable
-            .boxed_iter()
-            .map(MonotonicallyMappableToU128::to_u128),
-    );
+    serialize_column_values_u128_with_codec_types(iterable, &ALL_U128_CODEC_TYPES, output)
+}
+
+/// Serializes u128 values with the codec, among `codec_types`, resulting in the smallest column.
+///
+/// In case of a tie, the codec listed first wins.
+pub(crate) fn serialize_column_values_u128_with_codec_types<T: MonotonicallyMappableToU128>(
+    iterable: &dyn Iterable<T>,
+    codec_types: &[U128FastFieldCodecType],
+    output: &mut impl io::Write,
+) -> io::Result<()> {
+    let mut distinct_values = BTreeSet::new();
+    // Total number of values, with their redundancy.
+    let mut num_vals = 0u32;
+    for val in iterable
+        .boxed_iter()
+        .map(MonotonicallyMappableToU128::to_u128)
+    {
+        num_vals += 1;
+        distinct_values.insert(val);
+    }
+    let compact_space_compressor = codec_types
+        .contains(&U128FastFieldCodecType::CompactSpace)
+        .then(|| CompactSpaceCompressor::train_from_sorted(&distinct_values, num_vals));
+    let mut best_codec: Option<(u64, U128FastFieldCodecType)> = None;
+    for &codec_type in codec_types {
+        let num_bytes_opt = match codec_type {
+            U128FastFieldCodecType::CompactSpace => compact_space_compressor
+                .as_ref()
+                .map(CompactSpaceCompressor::num_bytes),
+            U128FastFieldCodecType::Bitpacked => bitpacked::estimate(&distinct_values, num_vals),
+            U128FastFieldCodecType::Dictionary => dictionary::estimate(&distinct_values, num_vals),
+        };
+        let Some(num_bytes) = num_bytes_opt else {
+            continue;
+        };
+        if best_codec.map_or(true, |(best_num_bytes, _)| num_bytes < best_num_bytes) {
+            best_codec = Some((num_bytes, codec_type));
+        }
+    }
+    let (_, codec_type) = best_codec.ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "No applicable codec for the u128 values.",
+        )
+    })?;
     let header = U128Header {
-        num_vals: compressor.num_vals(),
-        codec_type: U128FastFieldCodecType::CompactSpace,
+        num_vals,
+        codec_type,
     };
     header.serialize(output)?;
-    compressor.compress_into(
-        iterable
-            .boxed_iter()
-            .map(MonotonicallyMappableToU128::to_u128),
-        output,
-    )?;
-    Ok(())
+    let vals = iterable
+        .boxed_iter()
+        .map(MonotonicallyMappableToU128::to_u128);
+    match codec_type {
+        U128FastFieldCodecType::CompactSpace => compact_space_compressor
+            .expect("the compact space compressor is trained when its codec is available")
+            .compress_into(vals, output),
+        U128FastFieldCodecType::Bitpacked => bitpacked::serialize(&distinct_values, vals, output),
+        U128FastFieldCodecType::Dictionary => dictionary::serialize(&distinct_values, vals, output),
+    }
 }
 
 #[derive(PartialEq, Eq, PartialOrd, Ord, Debug, Clone, Copy)]
@@ -71,8 +119,19 @@ pub(crate) enum U128FastFieldCodecType {
     /// This codec takes a large number space (u128) and reduces it to a compact number space, by
     /// removing the holes.
     CompactSpace = 1,
+    /// Bitpacks the offsets of the values from the minimum value, when they fit in a u64.
+    Bitpacked = 2,
+    /// Bitpacks the ordinals of the values in the sorted dictionary of the distinct values.
+    Dictionary = 3,
 }
 
+/// All the u128 codecs, in the order they win ties in.
+pub(crate) const ALL_U128_CODEC_TYPES: [U128FastFieldCodecType; 3] = [
+    U128FastFieldCodecType::CompactSpace,
+    U128FastFieldCodecType::Bitpacked,
+    U128FastFieldCodecType::Dictionary,
+];
+
 impl BinarySerializable for U128FastFieldCodecType {
     fn serialize<W: Write + ?Sized>(&self, wrt: &mut W) -> io::Result<()> {
         self.to_code().serialize(wrt)
@@ -94,6 +153,8 @@ impl U128FastFieldCodecType {
     pub(crate) fn from_code(code: u8) -> Option<Self> {
         match code {
             1 => Some(Self::CompactSpace),
+            2 => Some(Self::Bitpacked),
+            3 => Some(Self::Dictionary),
             _ => None,
         }
     }
@@ -104,8 +165,15 @@ pub fn open_u128_mapped<T: MonotonicallyMappableToU128 + Debug>(
     mut bytes: OwnedBytes,
 ) -> io::Result<Arc<dyn ColumnValues<T>>> {
     let header = U128Header::deserialize(&mut bytes)?;
-    assert_eq!(header.codec_type, U128FastFieldCodecType::CompactSpace);
-    let reader = CompactSpaceDecompressor::open(bytes)?;
+    let reader: Arc<dyn ColumnValues<u128>> = match header.codec_type {
+        U128FastFieldCodecType::CompactSpace => Arc::new(CompactSpaceDecompressor::open(bytes)?),
+        U128FastFieldCodecType::Bitpacked => {
+            Arc::new(BitpackedU128Reader::open(header.num_vals, bytes)?)
+        }
+        U128FastFieldCodecType::Dictionary => {
+            Arc::new(DictionaryU128Reader::open(header.num_vals, bytes)?)
+        }
+    };
     let inverted: StrictlyMonotonicMappingInverter<StrictlyMonotonicMappingToInternal<T>> =
         StrictlyMonotonicMappingToInternal::<T>::new().into();
     Ok(Arc::new(monotonic_map_column(reader, inverted)))
@@ -116,15 +184,111 @@ pub fn open_u128_mapped<T: MonotonicallyMappableToU128 + Debug>(
 ///
 /// In order to convert to u128 back cast to `CompactSpaceU64Accessor` and call
 /// `compact_to_u128`.
-///
-/// # Notice
-/// In case there are new codecs added, check for usages of `CompactSpaceDecompressorU64` and
-/// also handle the new codecs.
 pub fn open_u128_as_compact_u64(mut bytes: OwnedBytes) -> io::Result<Arc<dyn ColumnValues<u64>>> {
     let header = U128Header::deserialize(&mut bytes)?;
-    assert_eq!(header.codec_type, U128FastFieldCodecType::CompactSpace);
-    let reader = CompactSpaceU64Accessor::open(bytes)?;
-    Ok(Arc::new(reader))
+    let reader: Box<dyn CompactU64ColumnValues> = match header.codec_type {
+        U128FastFieldCodecType::CompactSpace => Box::new(CompactSpaceDecompressor::open(bytes)?),
+        U128FastFieldCodecType::Bitpacked => {
+            Box::new(BitpackedU128Reader::open(header.num_vals, bytes)?)
+        }
+        U128FastFieldCodecType::Dictionary => {
+            Box::new(DictionaryU128Reader::open(header.num_vals, bytes)?)
+        }
+    };
+    Ok(Arc::new(CompactSpaceU64Accessor(reader)))
+}
+
+/// u128 column values, along with an order preserving mapping of their values to compact `u64`
+/// values.
+///
+/// The compact value is the position in the compact space for the compact space codec, the
+/// offset from the minimum value for the bitpacked codec, and the ordinal of the value for the
+/// dictionary codec.
+pub(crate) trait CompactU64ColumnValues: ColumnValues<u128> {
+    /// Returns the compact value of the row `idx`.
+    fn get_compact_val(&self, idx: u32) -> u64;
+
+    /// Maps a compact value back to its u128 value.
+    fn compact_val_to_u128(&self, compact: u64) -> u128;
+
+    /// Returns the compact value of the minimum value.
+    fn min_compact_val(&self) -> u64;
+
+    /// Returns the compact value of the maximum value.
+    fn max_compact_val(&self) -> u64;
+
+    /// Returns the compact values of all the rows.
+    fn iter_compact_vals(&self) -> Box<dyn Iterator<Item = u64> + '_>;
+
+    /// Same as [`ColumnValues::get_row_ids_for_value_range`], for a range of compact values
+    /// ending at most at `max_compact_val()`.
+    fn get_row_ids_for_compact_val_range(
+        &self,
+        compact_range: RangeInclusive<u64>,
+        row_id_range: Range<RowId>,
+        row_id_hits: &mut Vec<RowId>,
+    );
+}
+
+/// Exposes the u128 values as compact u64 values, whatever their codec.
+///
+/// This allows faster access to the values, as u64 is faster to work with than u128.
+/// It also allows to handle u128 values like u64, via the `open_u64_lenient` as a uniform
+/// access interface.
+///
+/// When converting from the internal u64 to u128 `compact_to_u128` can be used.
+pub struct CompactSpaceU64Accessor(Box<dyn CompactU64ColumnValues>);
+
+impl CompactSpaceU64Accessor {
+    /// Convert a compact value to u128
+    pub fn compact_to_u128(&self, compact: u64) -> u128 {
+        self.0.compact_val_to_u128(compact)
+    }
+}
+
+impl ColumnValues<u64> for CompactSpaceU64Accessor {
+    #[inline]
+    fn get_val(&self, doc: u32) -> u64 {
+        self.0.get_compact_val(doc)
+    }
+
+    fn min_value(&self) -> u64 {
+        self.0.min_compact_val()
+    }
+
+    fn max_value(&self) -> u64 {
+        self.0.max_compact_val()
+    }
+
+    fn num_vals(&self) -> u32 {
+        self.0.num_vals()
+    }
+
+    #[inline]
+    fn iter(&self) -> Box<dyn Iterator<Item = u64> + '_> {
+        self.0.iter_compact_vals()
+    }
+
+    #[inline]
+    fn get_row_ids_for_value_range(
+        &self,
+        value_range: RangeInclusive<u64>,
+        position_range: Range<u32>,
+        positions: &mut Vec<u32>,
+    ) {
+        if self.num_vals() == 0 {
+            positions.clear();
+            return;
+        }
+        let compact_range =
+            *value_range.start()..=(*value_range.end()).min(self.0.max_compact_val());
+        if compact_range.is_empty() {
+            positions.clear();
+            return;
+        }
+        self.0
+            .get_row_ids_for_compact_val_range(compact_range, position_range, positions)
+    }
 }
 
 #[cfg(test)]
@@ -168,8 +332,9 @@ pub mod tests {
         )
         .unwrap();
         // TODO put the header as a footer so that it serves as a padding.
-        // 5 bytes of header, 1 byte of value, 7 bytes of padding.
-        assert_eq!(buffer.len(), 5 + 1);
+        // 5 bytes of header, 1 byte of value, 1 byte of block stats footer, 20 bytes of
+        // cardinality sketch, 10 bytes of codec footer.
+        assert_eq!(buffer.len(), 5 + 1 + 1 + 20 + 10);
     }
 
     #[test]
@@ -181,8 +346,9 @@ pub mod tests {
             &mut buffer,
         )
         .unwrap();
-        // 6 bytes of header, 0 bytes of value, 7 bytes of padding.
-        assert_eq!(buffer.len(), 6);
+        // 6 bytes of header, 0 bytes of value, 1 byte of block stats footer, 20 bytes of
+        // cardinality sketch, 10 bytes of codec footer.
+        assert_eq!(buffer.len(), 6 + 1 + 20 + 10);
     }
 
     #[test]
@@ -191,7 +357,7 @@ pub mod tests {
         let vals: Vec<u64> = (0..80).map(|val| (val % 7) * 1_000u64).collect();
         serialize_u64_based_column_values(&&vals[..], &[CodecType::Bitpacked], &mut buffer)
             .unwrap();
-        // Values are stored over 3 bits.
-        assert_eq!(buffer.len(), 6 + (3 * 80 / 8));
+        // Values are stored over 3 bits, and the cardinality sketch holds the 7 distinct values.
+        assert_eq!(buffer.len(), 6 + (3 * 80 / 8) + 1 + (4 + 7 * 8) + 10);
     }
 }
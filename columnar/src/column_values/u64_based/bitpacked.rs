@@ -6,7 +6,9 @@ use common::{BinarySerializable, OwnedBytes};
 use fastdivide::DividerU64;
 use tantivy_bitpacker::{compute_num_bits, BitPacker, BitUnpacker};
 
-use crate::column_values::u64_based::{ColumnCodec, ColumnCodecEstimator, ColumnStats};
+use crate::column_values::u64_based::{
+    BlockwiseIter, ColumnCodec, ColumnCodecEstimator, ColumnStats,
+};
 use crate::{ColumnValues, RowId};
 
 /// Depending on the field type, a different
@@ -64,6 +66,17 @@ impl ColumnValues for BitpackedReader {
         self.stats.min_value + self.stats.gcd.get() * self.bit_unpacker.get(doc, &self.data)
     }
     #[inline]
+    fn get_range(&self, start: u64, output: &mut [u64]) {
+        self.bit_unpacker
+            .get_batch_u64s(start as u32, &self.data, output);
+        for out in output.iter_mut() {
+            *out = self.stats.min_value + self.stats.gcd.get() * *out;
+        }
+    }
+    fn iter(&self) -> Box<dyn Iterator<Item = u64> + '_> {
+        Box::new(BlockwiseIter::new(self))
+    }
+    #[inline]
     fn min_value(&self) -> u64 {
         self.stats.min_value
     }
@@ -108,7 +121,14 @@ impl ColumnCodecEstimator for BitpackedCodecEstimator {
     fn collect(&mut self, _value: u64) {}
 
     fn estimate(&self, stats: &ColumnStats) -> Option<u64> {
-        let num_bits_per_value = num_bits(stats);
+        // When estimating on a sample, the values left out still widen the bitpacked values.
+        let amplitude = stats
+            .distribution
+            .as_ref()
+            .map(|distribution| distribution.amplitude())
+            .unwrap_or(0u64)
+            .max(stats.amplitude());
+        let num_bits_per_value = compute_num_bits(amplitude / stats.gcd);
         Some(stats.num_bytes() + (stats.num_rows as u64 * (num_bits_per_value as u64) + 7) / 8)
     }
 
@@ -0,0 +1,415 @@
+use std::io::{self, Write};
+use std::ops::{Range, RangeInclusive};
+
+use common::{BinarySerializable, OwnedBytes, VInt};
+
+use crate::column_values::u64_based::lazy_blocks::{check_blocks_num_bytes, LazyBlocks};
+use crate::column_values::CardinalitySketch;
+use crate::{ColumnValues, RowId};
+
+/// Number of rows of the blocks [`BlockStats`] are kept for.
+pub const BLOCK_STATS_NUM_ROWS: RowId = 512;
+
+/// Bounds of the values of a block of [`BLOCK_STATS_NUM_ROWS`] consecutive rows, in the `u64`
+/// space the values of the column are serialized in.
+///
+/// The block `i` holds the rows `i * BLOCK_STATS_NUM_ROWS..(i + 1) * BLOCK_STATS_NUM_ROWS`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct BlockStats {
+    /// Lower bound of the values of the block.
+    pub min_value: u64,
+    /// Upper bound of the values of the block.
+    pub max_value: u64,
+}
+
+impl BlockStats {
+    fn intersects(&self, range: &RangeInclusive<u64>) -> bool {
+        self.min_value <= *range.end() && *range.start() <= self.max_value
+    }
+
+    fn is_within(&self, range: &RangeInclusive<u64>) -> bool {
+        range.contains(&self.min_value) && range.contains(&self.max_value)
+    }
+}
+
+/// Computes the [`BlockStats`] of a column while its values are collected.
+#[derive(Default)]
+pub(crate) struct BlockStatsCollector {
+    block_stats: Vec<BlockStats>,
+    num_rows: RowId,
+}
+
+impl BlockStatsCollector {
+    pub fn collect(&mut self, val: u64) {
+        if self.num_rows % BLOCK_STATS_NUM_ROWS == 0 {
+            self.block_stats.push(BlockStats {
+                min_value: val,
+                max_value: val,
+            });
+        } else if let Some(block_stats) = self.block_stats.last_mut() {
+            block_stats.min_value = block_stats.min_value.min(val);
+            block_stats.max_value = block_stats.max_value.max(val);
+        }
+        self.num_rows += 1;
+    }
+
+    /// Appends the block stats after the serialized column values.
+    ///
+    /// Columns of a single block only get a trailing `0u8`: their block stats are the bounds of
+    /// the column. Otherwise, the block stats are written as VInts, relative to the minimum of
+    /// the column, followed by their number of bytes and a trailing `1u8`.
+    pub fn serialize(&self, wrt: &mut dyn Write) -> io::Result<()> {
+        if self.block_stats.len() <= 1 {
+            return 0u8.serialize(wrt);
+        }
+        let min_value = self
+            .block_stats
+            .iter()
+            .map(|block_stats| block_stats.min_value)
+            .min()
+            .unwrap_or(0u64);
+        let mut buffer = Vec::new();
+        VInt(min_value).serialize(&mut buffer)?;
+        for block_stats in &self.block_stats {
+            VInt(block_stats.min_value - min_value).serialize(&mut buffer)?;
+            VInt(block_stats.max_value - block_stats.min_value).serialize(&mut buffer)?;
+        }
+        wrt.write_all(&buffer)?;
+        (buffer.len() as u32).serialize(wrt)?;
+        1u8.serialize(wrt)
+    }
+}
+
+/// Splits the block stats appended by [`BlockStatsCollector::serialize`] off the column values.
+///
+/// The block stats are returned serialized, and are empty for columns of a single block. They
+/// are only deserialized, via [`deserialize_block_stats`], on first access.
+pub(crate) fn split_block_stats(bytes: OwnedBytes) -> io::Result<(OwnedBytes, OwnedBytes)> {
+    let has_block_stats =
+        bytes.as_slice().last().copied().ok_or_else(|| {
+            io::Error::new(io::ErrorKind::InvalidData, "Missing block stats footer")
+        })?;
+    let (bytes, _) = bytes.rsplit(1);
+    if has_block_stats == 0u8 {
+        return Ok((bytes, OwnedBytes::empty()));
+    }
+    if bytes.len() < 4 {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "Block stats footer is truncated",
+        ));
+    }
+    let (bytes, mut num_bytes_data) = bytes.rsplit(4);
+    let num_bytes = u32::deserialize(&mut num_bytes_data)? as usize;
+    if num_bytes > bytes.len() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "Block stats footer is truncated",
+        ));
+    }
+    Ok(bytes.rsplit(num_bytes))
+}
+
+/// Maximum number of bytes of a VInt encoded u64.
+const VINT_MAX_NUM_BYTES: usize = 10;
+
+/// Deserializes the `num_blocks` block stats split off the column values by
+/// [`split_block_stats`].
+///
+/// This cannot fail: the stats of a block that cannot be read are replaced by
+/// `fallback_block_stats`, the bounds of the whole column.
+pub(crate) fn deserialize_block_stats(
+    mut block_stats_data: OwnedBytes,
+    num_blocks: usize,
+    fallback_block_stats: BlockStats,
+) -> Vec<BlockStats> {
+    let mut block_stats = Vec::with_capacity(num_blocks);
+    if let Ok(min_value) = VInt::deserialize_u64(&mut block_stats_data) {
+        while block_stats.len() < num_blocks {
+            let (Ok(block_min_delta), Ok(block_amplitude)) = (
+                VInt::deserialize_u64(&mut block_stats_data),
+                VInt::deserialize_u64(&mut block_stats_data),
+            ) else {
+                break;
+            };
+            let block_min_value = min_value.checked_add(block_min_delta);
+            let block_max_value = block_min_value
+                .and_then(|block_min_value| block_min_value.checked_add(block_amplitude));
+            block_stats.push(match (block_min_value, block_max_value) {
+                (Some(min_value), Some(max_value)) => BlockStats {
+                    min_value,
+                    max_value,
+                },
+                _ => fallback_block_stats,
+            });
+        }
+    }
+    block_stats.resize(num_blocks, fallback_block_stats);
+    block_stats
+}
+
+/// Column values along with the [`BlockStats`] of their blocks, used to skip the blocks that
+/// cannot match, or entirely match, a range of values without decoding them.
+///
+/// It also holds the [`CardinalitySketch`] serialized along with the block stats, if any.
+pub(crate) struct BlockStatsColumnValues<C> {
+    values: C,
+    block_stats: LazyBlocks<BlockStats>,
+    cardinality_sketch: Option<CardinalitySketch>,
+}
+
+impl<C: ColumnValues> BlockStatsColumnValues<C> {
+    /// Creates the column values, `block_stats_data` being the serialized block stats returned by
+    /// [`split_block_stats`].
+    ///
+    /// Returns an error if the size of the block stats does not match the number of rows.
+    pub fn new(
+        values: C,
+        block_stats_data: OwnedBytes,
+        cardinality_sketch: Option<CardinalitySketch>,
+    ) -> io::Result<Self> {
+        let block_stats = if !block_stats_data.is_empty() {
+            let num_vals = values.num_vals() as usize;
+            let num_blocks =
+                (num_vals + BLOCK_STATS_NUM_ROWS as usize - 1) / BLOCK_STATS_NUM_ROWS as usize;
+            // The minimum value is followed by the minimum and the amplitude of each block.
+            check_blocks_num_bytes(
+                block_stats_data.len().saturating_sub(1),
+                num_blocks,
+                2..=2 * VINT_MAX_NUM_BYTES,
+            )?;
+            let fallback_block_stats = BlockStats {
+                min_value: values.min_value(),
+                max_value: values.max_value(),
+            };
+            LazyBlocks::new(move || {
+                deserialize_block_stats(block_stats_data.clone(), num_blocks, fallback_block_stats)
+            })
+        } else if values.num_vals() > 0 {
+            LazyBlocks::from_blocks(vec![BlockStats {
+                min_value: values.min_value(),
+                max_value: values.max_value(),
+            }])
+        } else {
+            LazyBlocks::from_blocks(Vec::new())
+        };
+        Ok(BlockStatsColumnValues {
+            values,
+            block_stats,
+            cardinality_sketch,
+        })
+    }
+}
+
+impl<C: ColumnValues> ColumnValues for BlockStatsColumnValues<C> {
+    #[inline(always)]
+    fn get_val(&self, idx: u32) -> u64 {
+        self.values.get_val(idx)
+    }
+
+    #[inline(always)]
+    fn get_vals(&self, indexes: &[u32], output: &mut [u64]) {
+        self.values.get_vals(indexes, output)
+    }
+
+    #[inline(always)]
+    fn get_vals_opt(&self, indexes: &[u32], output: &mut [Option<u64>]) {
+        self.values.get_vals_opt(indexes, output)
+    }
+
+    #[inline(always)]
+    fn get_range(&self, start: u64, output: &mut [u64]) {
+        self.values.get_range(start, output)
+    }
+
+    fn get_row_ids_for_value_range(
+        &self,
+        value_range: RangeInclusive<u64>,
+        row_id_range: Range<RowId>,
+        row_id_hits: &mut Vec<RowId>,
+    ) {
+        let block_stats = self.block_stats.get();
+        if block_stats.len() <= 1 {
+            return self
+                .values
+                .get_row_ids_for_value_range(value_range, row_id_range, row_id_hits);
+        }
+        row_id_hits.clear();
+        let row_id_range = row_id_range.start..row_id_range.end.min(self.num_vals());
+        if row_id_range.is_empty() {
+            return;
+        }
+        let mut block_row_id_hits = Vec::new();
+        let mut decode_rows = |row_ids: Range<RowId>, row_id_hits: &mut Vec<RowId>| {
+            block_row_id_hits.clear();
+            self.values.get_row_ids_for_value_range(
+                value_range.clone(),
+                row_ids,
+                &mut block_row_id_hits,
+            );
+            row_id_hits.extend_from_slice(&block_row_id_hits);
+        };
+        // Consecutive blocks that need to be decoded are handed over to the column values at once.
+        let mut pending_row_ids: Option<Range<RowId>> = None;
+        let first_block = row_id_range.start / BLOCK_STATS_NUM_ROWS;
+        let last_block = (row_id_range.end - 1) / BLOCK_STATS_NUM_ROWS;
+        for block_id in first_block..=last_block {
+            let block_row_ids = (block_id * BLOCK_STATS_NUM_ROWS).max(row_id_range.start)
+                ..((block_id + 1) * BLOCK_STATS_NUM_ROWS).min(row_id_range.end);
+            let block_stats = &block_stats[block_id as usize];
+            if block_stats.intersects(&value_range) && !block_stats.is_within(&value_range) {
+                pending_row_ids.get_or_insert(block_row_ids.clone()).end = block_row_ids.end;
+                continue;
+            }
+            if let Some(pending) = pending_row_ids.take() {
+                decode_rows(pending, row_id_hits);
+            }
+            if block_stats.is_within(&value_range) {
+                row_id_hits.extend(block_row_ids);
+            }
+        }
+        if let Some(pending) = pending_row_ids {
+            decode_rows(pending, row_id_hits);
+        }
+    }
+
+    #[inline(always)]
+    fn min_value(&self) -> u64 {
+        self.values.min_value()
+    }
+
+    #[inline(always)]
+    fn max_value(&self) -> u64 {
+        self.values.max_value()
+    }
+
+    #[inline(always)]
+    fn num_vals(&self) -> u32 {
+        self.values.num_vals()
+    }
+
+    #[inline(always)]
+    fn iter(&self) -> Box<dyn Iterator<Item = u64> + '_> {
+        self.values.iter()
+    }
+
+    fn block_stats(&self) -> &[BlockStats] {
+        self.block_stats.get()
+    }
+
+    fn cardinality_sketch(&self) -> Option<&CardinalitySketch> {
+        self.cardinality_sketch.as_ref()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::column_values::u64_based::{
+        serialize_and_load_u64_based_column_values, CodecType, ALL_U64_CODEC_TYPES,
+    };
+
+    #[test]
+    fn test_block_stats_serialization() -> io::Result<()> {
+        let mut block_stats_collector = BlockStatsCollector::default();
+        for val in (0..1_200u64).map(|i| 1_000 + (i % 600)) {
+            block_stats_collector.collect(val);
+        }
+        let mut buffer = vec![1u8, 2u8, 3u8];
+        block_stats_collector.serialize(&mut buffer)?;
+        let (bytes, block_stats_data) = split_block_stats(OwnedBytes::new(buffer))?;
+        assert_eq!(bytes.as_slice(), &[1u8, 2u8, 3u8]);
+        assert_eq!(
+            &deserialize_block_stats(
+                block_stats_data,
+                3,
+                BlockStats {
+                    min_value: 0,
+                    max_value: 0
+                }
+            ),
+            &[
+                BlockStats {
+                    min_value: 1_000,
+                    max_value: 1_511
+                },
+                BlockStats {
+                    min_value: 1_000,
+                    max_value: 1_599
+                },
+                BlockStats {
+                    min_value: 1_000 + 424,
+                    max_value: 1_599
+                },
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_block_stats_single_block() -> io::Result<()> {
+        let mut block_stats_collector = BlockStatsCollector::default();
+        for val in [3u64, 1u64, 2u64] {
+            block_stats_collector.collect(val);
+        }
+        let mut buffer = vec![1u8];
+        block_stats_collector.serialize(&mut buffer)?;
+        assert_eq!(buffer.len(), 2);
+        let (bytes, block_stats_data) = split_block_stats(OwnedBytes::new(buffer))?;
+        assert_eq!(bytes.as_slice(), &[1u8]);
+        assert!(block_stats_data.is_empty());
+
+        let column = serialize_and_load_u64_based_column_values::<u64>(
+            &&[3u64, 1u64, 2u64][..],
+            &ALL_U64_CODEC_TYPES,
+        );
+        assert_eq!(
+            column.block_stats(),
+            &[BlockStats {
+                min_value: 1,
+                max_value: 3
+            }]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_block_stats_get_row_ids_for_value_range() {
+        // Blocks of increasing values, with a block of outliers.
+        let mut vals: Vec<u64> = (0..3_000u64).map(|i| i / 10).collect();
+        for val in &mut vals[1_100..1_200] {
+            *val += 10_000;
+        }
+        for codec_type in [CodecType::Bitpacked, CodecType::BlockwiseLinear] {
+            let column =
+                serialize_and_load_u64_based_column_values::<u64>(&&vals[..], &[codec_type]);
+            assert_eq!(column.block_stats().len(), 6);
+            assert_eq!(
+                column.block_stats()[2],
+                BlockStats {
+                    min_value: 102,
+                    max_value: 10_119
+                }
+            );
+            for (value_range, row_id_range) in [
+                (0..=299, 0..3_000),
+                (50..=120, 0..3_000),
+                (10_000..=20_000, 0..3_000),
+                (60..=250, 700..2_600),
+                (300..=400, 0..3_000),
+                (0..=u64::MAX, 511..513),
+            ] {
+                let mut row_ids = vec![7u32];
+                column.get_row_ids_for_value_range(
+                    value_range.clone(),
+                    row_id_range.clone(),
+                    &mut row_ids,
+                );
+                let expected_row_ids: Vec<u32> = row_id_range
+                    .filter(|row_id| value_range.contains(&vals[*row_id as usize]))
+                    .collect();
+                assert_eq!(row_ids, expected_row_ids);
+            }
+        }
+    }
+}
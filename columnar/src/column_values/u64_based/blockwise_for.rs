@@ -0,0 +1,380 @@
+use std::io::{self, Write};
+use std::ops::RangeInclusive;
+use std::sync::Arc;
+
+use common::{BinarySerializable, CountingWriter, OwnedBytes, VInt};
+use fastdivide::DividerU64;
+use tantivy_bitpacker::{compute_num_bits, BitPacker, BitUnpacker};
+
+use crate::column_values::u64_based::lazy_blocks::{
+    check_blocks_num_bytes, split_blocks_footer, LazyBlocks,
+};
+use crate::column_values::u64_based::{
+    BlockwiseIter, ColumnCodec, ColumnCodecEstimator, ColumnStats,
+};
+use crate::column_values::ColumnValues;
+
+const BLOCK_SIZE: u32 = 512u32;
+
+// Each block stores the values as deltas against the minimum value of the block.
+// `block_min` is expressed after the column wide transformation `(val - min_value) / gcd`.
+#[derive(Debug, Default)]
+struct Block {
+    block_min: u64,
+    bit_unpacker: BitUnpacker,
+    data_start_offset: usize,
+}
+
+impl BinarySerializable for Block {
+    fn serialize<W: Write + ?Sized>(&self, writer: &mut W) -> io::Result<()> {
+        VInt(self.block_min).serialize(writer)?;
+        self.bit_unpacker.bit_width().serialize(writer)?;
+        Ok(())
+    }
+
+    fn deserialize<R: io::Read>(reader: &mut R) -> io::Result<Self> {
+        let block_min = VInt::deserialize(reader)?.0;
+        let bit_width = u8::deserialize(reader)?;
+        if bit_width > 56 && bit_width != 64 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Invalid bit width {bit_width}"),
+            ));
+        }
+        Ok(Block {
+            block_min,
+            bit_unpacker: BitUnpacker::new(bit_width),
+            data_start_offset: 0,
+        })
+    }
+}
+
+fn compute_num_blocks(num_vals: u32) -> u32 {
+    (num_vals + BLOCK_SIZE - 1) / BLOCK_SIZE
+}
+
+// A block takes a VInt for its minimum and a byte for its bit width.
+const BLOCK_NUM_BYTES: RangeInclusive<usize> = 2..=11;
+
+/// Deserializes the blocks of a column of `num_rows` rows, of which `footer` was checked to
+/// match the number of blocks.
+///
+/// A block that cannot be read, or whose values would lie past the end of the `data_len` bytes
+/// of data, is replaced by a block of zero bit width.
+fn deserialize_blocks(mut footer: OwnedBytes, num_rows: u32, data_len: usize) -> Vec<Block> {
+    let num_blocks = compute_num_blocks(num_rows) as usize;
+    let mut blocks = Vec::with_capacity(num_blocks);
+    let mut start_offset = 0;
+    for block_id in 0..num_blocks {
+        let mut block = Block::deserialize(&mut footer).unwrap_or_default();
+        let num_block_rows = (num_rows - block_id as u32 * BLOCK_SIZE).min(BLOCK_SIZE) as usize;
+        let num_block_bytes = (num_block_rows * block.bit_unpacker.bit_width() as usize + 7) / 8;
+        if start_offset + num_block_bytes > data_len {
+            block = Block::default();
+        }
+        block.data_start_offset = start_offset;
+        start_offset += (block.bit_unpacker.bit_width() as usize) * BLOCK_SIZE as usize / 8;
+        blocks.push(block);
+    }
+    blocks
+}
+
+pub struct BlockwiseForEstimator {
+    block: Vec<u64>,
+    values_num_bytes: u64,
+    meta_num_bytes: u64,
+}
+
+impl Default for BlockwiseForEstimator {
+    fn default() -> Self {
+        Self {
+            block: Vec::with_capacity(BLOCK_SIZE as usize),
+            values_num_bytes: 0u64,
+            meta_num_bytes: 0u64,
+        }
+    }
+}
+
+impl BlockwiseForEstimator {
+    fn flush_block_estimate(&mut self) {
+        let Some(block_min) = self.block.iter().copied().min() else {
+            return;
+        };
+        let block_max = self.block.iter().copied().max().unwrap_or(block_min);
+        let bit_width = compute_num_bits(block_max - block_min) as usize;
+        self.values_num_bytes += (bit_width * self.block.len() + 7) as u64 / 8;
+        // The serialized minimum is shifted by the column minimum and divided by the gcd, so
+        // the raw minimum gives an upper bound of its size.
+        self.meta_num_bytes += 1 + VInt(block_min).num_bytes();
+    }
+}
+
+impl ColumnCodecEstimator for BlockwiseForEstimator {
+    fn collect(&mut self, value: u64) {
+        self.block.push(value);
+        if self.block.len() == BLOCK_SIZE as usize {
+            self.flush_block_estimate();
+            self.block.clear();
+        }
+    }
+
+    fn estimate(&self, stats: &ColumnStats) -> Option<u64> {
+        let mut estimate = 4 + stats.num_bytes() + self.meta_num_bytes + self.values_num_bytes;
+        if stats.gcd.get() > 1 {
+            let estimate_gain_from_gcd =
+                (stats.gcd.get() as f32).log2().floor() * stats.num_rows as f32 / 8.0f32;
+            estimate = estimate.saturating_sub(estimate_gain_from_gcd as u64);
+        }
+        Some(estimate)
+    }
+
+    fn finalize(&mut self) {
+        self.flush_block_estimate();
+    }
+
+    fn serialize(
+        &self,
+        stats: &ColumnStats,
+        mut vals: &mut dyn Iterator<Item = u64>,
+        wrt: &mut dyn Write,
+    ) -> io::Result<()> {
+        stats.serialize(wrt)?;
+        let mut buffer = Vec::with_capacity(BLOCK_SIZE as usize);
+        let num_blocks = compute_num_blocks(stats.num_rows) as usize;
+        let mut blocks = Vec::with_capacity(num_blocks);
+
+        let mut bit_packer = BitPacker::new();
+
+        let gcd_divider = DividerU64::divide_by(stats.gcd.get());
+
+        for _ in 0..num_blocks {
+            buffer.clear();
+            buffer.extend(
+                (&mut vals)
+                    .map(|val| gcd_divider.divide(val - stats.min_value))
+                    .take(BLOCK_SIZE as usize),
+            );
+            assert!(!buffer.is_empty());
+
+            let block_min = buffer.iter().copied().min().unwrap();
+            let block_max = buffer.iter().copied().max().unwrap();
+            let bit_width = compute_num_bits(block_max - block_min);
+            for &buffer_val in &buffer {
+                bit_packer.write(buffer_val - block_min, bit_width, wrt)?;
+            }
+
+            blocks.push(Block {
+                block_min,
+                bit_unpacker: BitUnpacker::new(bit_width),
+                data_start_offset: 0,
+            });
+        }
+
+        bit_packer.close(wrt)?;
+
+        assert_eq!(blocks.len(), num_blocks);
+
+        let mut counting_wrt = CountingWriter::wrap(wrt);
+        for block in &blocks {
+            block.serialize(&mut counting_wrt)?;
+        }
+        let footer_len = counting_wrt.written_bytes();
+        (footer_len as u32).serialize(&mut counting_wrt)?;
+
+        Ok(())
+    }
+}
+
+/// Frame of reference codec: values are split in blocks of 512 elements, and bitpacked as
+/// deltas against the minimum value of their block.
+///
+/// Columns of which values are close to their neighbours, like timestamps, get a small number
+/// of bits per value, even if their overall amplitude is large and their progression is not
+/// linear.
+pub struct BlockwiseForCodec;
+
+impl ColumnCodec<u64> for BlockwiseForCodec {
+    type ColumnValues = BlockwiseForReader;
+
+    type Estimator = BlockwiseForEstimator;
+
+    fn load(mut bytes: OwnedBytes) -> io::Result<Self::ColumnValues> {
+        let stats = ColumnStats::deserialize(&mut bytes)?;
+        let (data, footer) = split_blocks_footer(bytes)?;
+        let num_blocks = compute_num_blocks(stats.num_rows) as usize;
+        check_blocks_num_bytes(footer.len() - 4, num_blocks, BLOCK_NUM_BYTES)?;
+        let num_rows = stats.num_rows;
+        let data_len = data.len();
+        let blocks =
+            LazyBlocks::new(move || deserialize_blocks(footer.clone(), num_rows, data_len));
+        Ok(BlockwiseForReader {
+            blocks: Arc::new(blocks),
+            data,
+            stats,
+        })
+    }
+}
+
+#[derive(Clone)]
+pub struct BlockwiseForReader {
+    blocks: Arc<LazyBlocks<Block>>,
+    data: OwnedBytes,
+    stats: ColumnStats,
+}
+
+impl ColumnValues for BlockwiseForReader {
+    #[inline(always)]
+    fn get_val(&self, idx: u32) -> u64 {
+        let block_id = (idx / BLOCK_SIZE) as usize;
+        let idx_within_block = idx % BLOCK_SIZE;
+        let block = &self.blocks.get()[block_id];
+        let block_bytes = &self.data[block.data_start_offset..];
+        let delta = block.bit_unpacker.get(idx_within_block, block_bytes);
+        self.stats.min_value + self.stats.gcd.get() * (block.block_min + delta)
+    }
+
+    fn get_range(&self, start: u64, output: &mut [u64]) {
+        let mut idx = start as u32;
+        let mut output = output;
+        while !output.is_empty() {
+            let block = &self.blocks.get()[(idx / BLOCK_SIZE) as usize];
+            let idx_within_block = idx % BLOCK_SIZE;
+            let len = ((BLOCK_SIZE - idx_within_block) as usize).min(output.len());
+            let (block_output, remaining_output) = output.split_at_mut(len);
+            block.bit_unpacker.get_batch_u64s(
+                idx_within_block,
+                &self.data[block.data_start_offset..],
+                block_output,
+            );
+            let block_min_value = self.stats.min_value + self.stats.gcd.get() * block.block_min;
+            for out in block_output.iter_mut() {
+                *out = block_min_value + self.stats.gcd.get() * *out;
+            }
+            output = remaining_output;
+            idx += len as u32;
+        }
+    }
+
+    fn iter(&self) -> Box<dyn Iterator<Item = u64> + '_> {
+        Box::new(BlockwiseIter::new(self))
+    }
+
+    #[inline(always)]
+    fn min_value(&self) -> u64 {
+        self.stats.min_value
+    }
+
+    #[inline(always)]
+    fn max_value(&self) -> u64 {
+        self.stats.max_value
+    }
+
+    #[inline(always)]
+    fn num_vals(&self) -> u32 {
+        self.stats.num_rows
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::column_values::u64_based::tests::create_and_validate;
+    use crate::column_values::u64_based::{BitpackedCodec, LinearCodec, StatsCollector};
+
+    #[test]
+    fn test_with_codec_data_sets_simple() {
+        create_and_validate::<BlockwiseForCodec>(
+            &[11, 20, 40, 20, 10, 10, 10, 10, 10, 10],
+            "simple test",
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn test_blockwise_for_corrupted_footer() {
+        let vals: Vec<u64> = (0..2_000u64).map(|i| i * 7 % 1_000).collect();
+        let mut stats_collector = StatsCollector::default();
+        let mut estimator = BlockwiseForEstimator::default();
+        for &val in &vals {
+            stats_collector.collect(val);
+            estimator.collect(val);
+        }
+        estimator.finalize();
+        let mut buffer = Vec::new();
+        estimator
+            .serialize(
+                &stats_collector.stats(),
+                &mut vals.iter().copied(),
+                &mut buffer,
+            )
+            .unwrap();
+        let num_bytes = buffer.len();
+
+        // A footer that does not fit in the column is rejected when the column is opened.
+        let mut truncated_buffer = buffer.clone();
+        truncated_buffer[num_bytes - 4..].copy_from_slice(&u32::MAX.to_le_bytes());
+        assert!(BlockwiseForCodec::load(OwnedBytes::new(truncated_buffer)).is_err());
+        // So is a footer that does not match the number of blocks.
+        let mut short_buffer = buffer.clone();
+        short_buffer[num_bytes - 4..].copy_from_slice(&1u32.to_le_bytes());
+        assert!(BlockwiseForCodec::load(OwnedBytes::new(short_buffer)).is_err());
+
+        // Blocks with an invalid bit width, or whose values would lie past the end of the data,
+        // are read as blocks of zero bit width rather than panicking.
+        for bit_width in [60u8, 56u8] {
+            let mut corrupted_buffer = buffer.clone();
+            corrupted_buffer[num_bytes - 5] = bit_width;
+            let reader = BlockwiseForCodec::load(OwnedBytes::new(corrupted_buffer)).unwrap();
+            for row_id in 0..reader.num_vals() {
+                reader.get_val(row_id);
+            }
+        }
+    }
+
+    #[test]
+    fn test_with_codec_data_sets() {
+        let data_sets = crate::column_values::u64_based::tests::get_codec_test_datasets();
+        for (mut data, name) in data_sets {
+            create_and_validate::<BlockwiseForCodec>(&data, name);
+            data.reverse();
+            create_and_validate::<BlockwiseForCodec>(&data, name);
+        }
+    }
+
+    #[test]
+    fn test_blockwise_for_fast_field_rand() {
+        for _ in 0..500 {
+            let mut data = (0..1 + rand::random::<u8>() as usize)
+                .map(|_| rand::random::<i64>() as u64 / 2)
+                .collect::<Vec<_>>();
+            create_and_validate::<BlockwiseForCodec>(&data, "rand");
+            data.reverse();
+            create_and_validate::<BlockwiseForCodec>(&data, "rand");
+        }
+    }
+
+    #[test]
+    fn test_blockwise_for_timestamps() {
+        // Batches of events in microseconds, with irregular gaps of one to three days between
+        // batches.
+        let mut timestamp = 1_600_000_000_000_000u64;
+        let data: Vec<u64> = (0..16_384u64)
+            .map(|i| {
+                if i % 4_096 == 0 {
+                    timestamp += 86_400_000_000 * (1 + i / 4_096 % 3);
+                }
+                timestamp += (i * 7_919) % 1_000;
+                timestamp
+            })
+            .collect();
+        let (_, for_compression) =
+            create_and_validate::<BlockwiseForCodec>(&data, "timestamps").unwrap();
+        let (_, linear_compression) =
+            create_and_validate::<LinearCodec>(&data, "timestamps").unwrap();
+        let (_, bitpacked_compression) =
+            create_and_validate::<BitpackedCodec>(&data, "timestamps").unwrap();
+        assert!(for_compression < 0.35);
+        assert!(for_compression < linear_compression);
+        assert!(for_compression < bitpacked_compression);
+    }
+}
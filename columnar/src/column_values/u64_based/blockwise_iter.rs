@@ -0,0 +1,80 @@
+use crate::ColumnValues;
+
+/// Number of values decoded at once by the [`BlockwiseIter`].
+const ITER_BLOCK_LEN: usize = 128;
+
+/// Iterator over the values of a column, decoding them in blocks with
+/// [`ColumnValues::get_range`] rather than one at a time.
+///
+/// Codecs overriding `get_range` use it as their implementation of [`ColumnValues::iter`].
+pub(crate) struct BlockwiseIter<'a, C: ?Sized> {
+    column: &'a C,
+    block: Vec<u64>,
+    idx_in_block: usize,
+    next_block_start: u32,
+}
+
+impl<'a, C: ColumnValues + ?Sized> BlockwiseIter<'a, C> {
+    pub(crate) fn new(column: &'a C) -> BlockwiseIter<'a, C> {
+        BlockwiseIter {
+            column,
+            block: Vec::with_capacity(ITER_BLOCK_LEN),
+            idx_in_block: 0,
+            next_block_start: 0,
+        }
+    }
+
+    fn num_remaining_vals(&self) -> usize {
+        (self.column.num_vals() - self.next_block_start) as usize + self.block.len()
+            - self.idx_in_block
+    }
+}
+
+impl<'a, C: ColumnValues + ?Sized> Iterator for BlockwiseIter<'a, C> {
+    type Item = u64;
+
+    #[inline]
+    fn next(&mut self) -> Option<u64> {
+        if self.idx_in_block == self.block.len() {
+            let num_vals = self.column.num_vals();
+            if self.next_block_start >= num_vals {
+                return None;
+            }
+            let block_len = ((num_vals - self.next_block_start) as usize).min(ITER_BLOCK_LEN);
+            self.block.resize(block_len, 0u64);
+            self.column
+                .get_range(self.next_block_start as u64, &mut self.block);
+            self.next_block_start += block_len as u32;
+            self.idx_in_block = 0;
+        }
+        let val = self.block[self.idx_in_block];
+        self.idx_in_block += 1;
+        Some(val)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let num_remaining_vals = self.num_remaining_vals();
+        (num_remaining_vals, Some(num_remaining_vals))
+    }
+}
+
+impl<'a, C: ColumnValues + ?Sized> ExactSizeIterator for BlockwiseIter<'a, C> {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::column_values::VecColumn;
+
+    #[test]
+    fn test_blockwise_iter() {
+        for num_vals in [0u64, 1, 127, 128, 129, 1_000] {
+            let vals: Vec<u64> = (0..num_vals).map(|i| i * 3).collect();
+            let column = VecColumn::from(vals.clone());
+            let mut iter = BlockwiseIter::new(&column);
+            assert_eq!(iter.len(), num_vals as usize);
+            iter.next();
+            assert_eq!(iter.len(), (num_vals as usize).saturating_sub(1));
+            assert_eq!(BlockwiseIter::new(&column).collect::<Vec<u64>>(), vals);
+        }
+    }
+}
@@ -1,13 +1,18 @@
-use std::io::Write;
+use std::io::{self, Write};
+use std::ops::RangeInclusive;
 use std::sync::Arc;
-use std::{io, iter};
 
-use common::{BinarySerializable, CountingWriter, DeserializeFrom, OwnedBytes};
+use common::{BinarySerializable, CountingWriter, OwnedBytes};
 use fastdivide::DividerU64;
 use tantivy_bitpacker::{compute_num_bits, BitPacker, BitUnpacker};
 
+use crate::column_values::u64_based::lazy_blocks::{
+    check_blocks_num_bytes, split_blocks_footer, LazyBlocks,
+};
 use crate::column_values::u64_based::line::Line;
-use crate::column_values::u64_based::{ColumnCodec, ColumnCodecEstimator, ColumnStats};
+use crate::column_values::u64_based::{
+    BlockwiseIter, ColumnCodec, ColumnCodecEstimator, ColumnStats,
+};
 use crate::column_values::{ColumnValues, VecColumn};
 use crate::MonotonicallyMappableToU64;
 
@@ -30,6 +35,12 @@ impl BinarySerializable for Block {
     fn deserialize<R: io::Read>(reader: &mut R) -> io::Result<Self> {
         let line = Line::deserialize(reader)?;
         let bit_width = u8::deserialize(reader)?;
+        if bit_width > 56 && bit_width != 64 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Invalid bit width {bit_width}"),
+            ));
+        }
         Ok(Block {
             line,
             bit_unpacker: BitUnpacker::new(bit_width),
@@ -42,6 +53,32 @@ fn compute_num_blocks(num_vals: u32) -> u32 {
     (num_vals + BLOCK_SIZE - 1) / BLOCK_SIZE
 }
 
+// A block takes two VInts for its line and a byte for its bit width.
+const BLOCK_NUM_BYTES: RangeInclusive<usize> = 3..=21;
+
+/// Deserializes the blocks of a column of `num_rows` rows, of which `footer` was checked to
+/// match the number of blocks.
+///
+/// A block that cannot be read, or whose values would lie past the end of the `data_len` bytes
+/// of data, is replaced by a block of zero bit width.
+fn deserialize_blocks(mut footer: OwnedBytes, num_rows: u32, data_len: usize) -> Vec<Block> {
+    let num_blocks = compute_num_blocks(num_rows) as usize;
+    let mut blocks = Vec::with_capacity(num_blocks);
+    let mut start_offset = 0;
+    for block_id in 0..num_blocks {
+        let mut block = Block::deserialize(&mut footer).unwrap_or_default();
+        let num_block_rows = (num_rows - block_id as u32 * BLOCK_SIZE).min(BLOCK_SIZE) as usize;
+        let num_block_bytes = (num_block_rows * block.bit_unpacker.bit_width() as usize + 7) / 8;
+        if start_offset + num_block_bytes > data_len {
+            block = Block::default();
+        }
+        block.data_start_offset = start_offset;
+        start_offset += (block.bit_unpacker.bit_width() as usize) * BLOCK_SIZE as usize / 8;
+        blocks.push(block);
+    }
+    blocks
+}
+
 pub struct BlockwiseLinearEstimator {
     block: Vec<u64>,
     values_num_bytes: u64,
@@ -174,20 +211,15 @@ impl ColumnCodec<u64> for BlockwiseLinearCodec {
 
     fn load(mut bytes: OwnedBytes) -> io::Result<Self::ColumnValues> {
         let stats = ColumnStats::deserialize(&mut bytes)?;
-        let footer_len: u32 = (&bytes[bytes.len() - 4..]).deserialize()?;
-        let footer_offset = bytes.len() - 4 - footer_len as usize;
-        let (data, mut footer) = bytes.split(footer_offset);
-        let num_blocks = compute_num_blocks(stats.num_rows);
-        let mut blocks: Vec<Block> = iter::repeat_with(|| Block::deserialize(&mut footer))
-            .take(num_blocks as usize)
-            .collect::<io::Result<_>>()?;
-        let mut start_offset = 0;
-        for block in &mut blocks {
-            block.data_start_offset = start_offset;
-            start_offset += (block.bit_unpacker.bit_width() as usize) * BLOCK_SIZE as usize / 8;
-        }
+        let (data, footer) = split_blocks_footer(bytes)?;
+        let num_blocks = compute_num_blocks(stats.num_rows) as usize;
+        check_blocks_num_bytes(footer.len() - 4, num_blocks, BLOCK_NUM_BYTES)?;
+        let num_rows = stats.num_rows;
+        let data_len = data.len();
+        let blocks =
+            LazyBlocks::new(move || deserialize_blocks(footer.clone(), num_rows, data_len));
         Ok(BlockwiseLinearReader {
-            blocks: blocks.into_boxed_slice().into(),
+            blocks: Arc::new(blocks),
             data,
             stats,
         })
@@ -196,7 +228,7 @@ impl ColumnCodec<u64> for BlockwiseLinearCodec {
 
 #[derive(Clone)]
 pub struct BlockwiseLinearReader {
-    blocks: Arc<[Block]>,
+    blocks: Arc<LazyBlocks<Block>>,
     data: OwnedBytes,
     stats: ColumnStats,
 }
@@ -206,7 +238,7 @@ impl ColumnValues for BlockwiseLinearReader {
     fn get_val(&self, idx: u32) -> u64 {
         let block_id = (idx / BLOCK_SIZE) as usize;
         let idx_within_block = idx % BLOCK_SIZE;
-        let block = &self.blocks[block_id];
+        let block = &self.blocks.get()[block_id];
         let interpoled_val: u64 = block.line.eval(idx_within_block);
         let block_bytes = &self.data[block.data_start_offset..];
         let bitpacked_diff = block.bit_unpacker.get(idx_within_block, block_bytes);
@@ -220,6 +252,37 @@ impl ColumnValues for BlockwiseLinearReader {
                 .wrapping_mul(interpoled_val.wrapping_add(bitpacked_diff))
     }
 
+    fn get_range(&self, start: u64, output: &mut [u64]) {
+        let mut idx = start as u32;
+        let mut output = output;
+        while !output.is_empty() {
+            let block = &self.blocks.get()[(idx / BLOCK_SIZE) as usize];
+            let idx_within_block = idx % BLOCK_SIZE;
+            let len = ((BLOCK_SIZE - idx_within_block) as usize).min(output.len());
+            let (block_output, remaining_output) = output.split_at_mut(len);
+            block.bit_unpacker.get_batch_u64s(
+                idx_within_block,
+                &self.data[block.data_start_offset..],
+                block_output,
+            );
+            for (out, idx_within_block) in block_output.iter_mut().zip(idx_within_block..) {
+                let interpoled_val: u64 = block.line.eval(idx_within_block);
+                *out = self.stats.min_value
+                    + self
+                        .stats
+                        .gcd
+                        .get()
+                        .wrapping_mul(interpoled_val.wrapping_add(*out));
+            }
+            output = remaining_output;
+            idx += len as u32;
+        }
+    }
+
+    fn iter(&self) -> Box<dyn Iterator<Item = u64> + '_> {
+        Box::new(BlockwiseIter::new(self))
+    }
+
     #[inline(always)]
     fn min_value(&self) -> u64 {
         self.stats.min_value
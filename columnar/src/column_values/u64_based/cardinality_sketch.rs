@@ -0,0 +1,263 @@
+use std::collections::BTreeSet;
+use std::fmt;
+use std::io::{self, Write};
+
+use common::{BinarySerializable, OwnedBytes};
+
+/// Maximum number of hashes kept by a [`CardinalitySketch`].
+///
+/// Estimates of the number of distinct values of columns having more distinct values than that
+/// have a relative standard error of about 9%.
+pub const CARDINALITY_SKETCH_NUM_HASHES: usize = 128;
+
+const HASH_NUM_BYTES: usize = std::mem::size_of::<u64>();
+
+/// Hash of a value, in the `u64` space the values of the column are serialized in.
+///
+/// This is the finalizer of MurmurHash3, a bijection: distinct values have distinct hashes.
+#[inline]
+fn hash(val: u64) -> u64 {
+    let mut h = val;
+    h ^= h >> 33;
+    h = h.wrapping_mul(0xff51_afd7_ed55_8ccd);
+    h ^= h >> 33;
+    h = h.wrapping_mul(0xc4ce_b9fe_1a85_ec53);
+    h ^= h >> 33;
+    h
+}
+
+/// K-minimum values sketch of the distinct values of a column, estimating their number without
+/// reading the column.
+///
+/// It keeps the [`CARDINALITY_SKETCH_NUM_HASHES`] smallest hashes of the distinct values. If the
+/// column has fewer distinct values than that, the count is exact.
+///
+/// The sketches of columns of the same type can be merged, e.g. to estimate the number of
+/// distinct values of a field across segments. This does not apply to the term ordinals of
+/// dictionary-encoded columns, which are specific to their segment.
+#[derive(Clone)]
+pub struct CardinalitySketch {
+    // The smallest hashes, sorted, as little endian u64s.
+    hashes: OwnedBytes,
+    num_vals: u64,
+}
+
+impl CardinalitySketch {
+    pub(crate) fn open(hashes: OwnedBytes, num_vals: u64) -> io::Result<CardinalitySketch> {
+        if hashes.len() % HASH_NUM_BYTES != 0
+            || hashes.len() > CARDINALITY_SKETCH_NUM_HASHES * HASH_NUM_BYTES
+        {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "Invalid cardinality sketch",
+            ));
+        }
+        Ok(CardinalitySketch { hashes, num_vals })
+    }
+
+    fn num_hashes(&self) -> usize {
+        self.hashes.len() / HASH_NUM_BYTES
+    }
+
+    fn hashes(&self) -> impl Iterator<Item = u64> + '_ {
+        self.hashes
+            .as_slice()
+            .chunks_exact(HASH_NUM_BYTES)
+            .map(|hash_bytes| u64::from_le_bytes(hash_bytes.try_into().unwrap()))
+    }
+
+    /// Returns true if the sketch counts the distinct values exactly, i.e. if there are fewer
+    /// than [`CARDINALITY_SKETCH_NUM_HASHES`] of them.
+    pub fn is_exact(&self) -> bool {
+        self.num_hashes() < CARDINALITY_SKETCH_NUM_HASHES
+    }
+
+    /// Returns an estimate of the number of distinct values.
+    ///
+    /// It never exceeds the number of values the sketch was built from.
+    pub fn estimate(&self) -> u64 {
+        if self.is_exact() {
+            return self.num_hashes() as u64;
+        }
+        let max_hash = self.hashes().last().unwrap_or(u64::MAX);
+        // The hashes being uniformly distributed, the k-th smallest of n distinct hashes is
+        // expected to be around `k / n` of the hash space.
+        let max_hash_quantile = (max_hash as f64 + 1.0) / (u64::MAX as f64 + 1.0);
+        let estimate = (CARDINALITY_SKETCH_NUM_HASHES - 1) as f64 / max_hash_quantile;
+        (estimate.round() as u64).min(self.num_vals)
+    }
+
+    /// Returns the sketch of the union of the values of both sketches.
+    pub fn merge(&self, other: &CardinalitySketch) -> CardinalitySketch {
+        // Each sketch holds all of the hashes of its values up to its largest hash, if it dropped
+        // some, so the union is only known up to the smallest of those.
+        let threshold = [self, other]
+            .into_iter()
+            .filter(|sketch| !sketch.is_exact())
+            .flat_map(|sketch| sketch.hashes().last())
+            .min()
+            .unwrap_or(u64::MAX);
+        let merged_hashes: BTreeSet<u64> = self
+            .hashes()
+            .chain(other.hashes())
+            .filter(|hash| *hash <= threshold)
+            .collect();
+        let mut hashes = Vec::with_capacity(CARDINALITY_SKETCH_NUM_HASHES * HASH_NUM_BYTES);
+        for hash in merged_hashes
+            .into_iter()
+            .take(CARDINALITY_SKETCH_NUM_HASHES)
+        {
+            hashes.extend_from_slice(&hash.to_le_bytes());
+        }
+        CardinalitySketch {
+            hashes: OwnedBytes::new(hashes),
+            num_vals: self.num_vals + other.num_vals,
+        }
+    }
+}
+
+impl fmt::Debug for CardinalitySketch {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("CardinalitySketch")
+            .field("estimate", &self.estimate())
+            .field("is_exact", &self.is_exact())
+            .finish()
+    }
+}
+
+/// Builds the [`CardinalitySketch`] of a column while its values are collected.
+#[derive(Default)]
+pub(crate) struct CardinalitySketchCollector {
+    hashes: BTreeSet<u64>,
+    num_vals: u64,
+}
+
+impl CardinalitySketchCollector {
+    #[inline]
+    pub fn collect(&mut self, val: u64) {
+        self.num_vals += 1;
+        let hash = hash(val);
+        if self.hashes.len() < CARDINALITY_SKETCH_NUM_HASHES {
+            self.hashes.insert(hash);
+            return;
+        }
+        let max_hash = self.hashes.last().copied().unwrap_or(u64::MAX);
+        if hash < max_hash && self.hashes.insert(hash) {
+            self.hashes.pop_last();
+        }
+    }
+
+    /// Appends the sketch after the serialized column values: its hashes as little endian u64s,
+    /// followed by their number as a u32.
+    pub fn serialize(&self, wrt: &mut dyn Write) -> io::Result<()> {
+        for hash in &self.hashes {
+            wrt.write_all(&hash.to_le_bytes())?;
+        }
+        (self.hashes.len() as u32).serialize(wrt)
+    }
+
+    #[cfg(test)]
+    fn sketch(&self) -> CardinalitySketch {
+        let mut buffer = Vec::new();
+        self.serialize(&mut buffer).unwrap();
+        let (_, hashes) = split_cardinality_sketch(OwnedBytes::new(buffer)).unwrap();
+        CardinalitySketch::open(hashes, self.num_vals).unwrap()
+    }
+}
+
+/// Splits the hashes appended by [`CardinalitySketchCollector::serialize`] off the column values.
+pub(crate) fn split_cardinality_sketch(bytes: OwnedBytes) -> io::Result<(OwnedBytes, OwnedBytes)> {
+    let truncated_err = || {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            "Cardinality sketch is truncated",
+        )
+    };
+    if bytes.len() < 4 {
+        return Err(truncated_err());
+    }
+    let (bytes, mut num_hashes_data) = bytes.rsplit(4);
+    let num_hashes = u32::deserialize(&mut num_hashes_data)? as usize;
+    let num_bytes = num_hashes * HASH_NUM_BYTES;
+    if num_bytes > bytes.len() {
+        return Err(truncated_err());
+    }
+    Ok(bytes.rsplit(num_bytes))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sketch_of(vals: impl Iterator<Item = u64>) -> CardinalitySketch {
+        let mut collector = CardinalitySketchCollector::default();
+        for val in vals {
+            collector.collect(val);
+        }
+        collector.sketch()
+    }
+
+    #[test]
+    fn test_cardinality_sketch_exact_for_few_distinct_values() {
+        let sketch = sketch_of((0..10_000u64).map(|i| i % 100 * 1_000));
+        assert!(sketch.is_exact());
+        assert_eq!(sketch.estimate(), 100);
+        let empty_sketch = sketch_of(std::iter::empty());
+        assert!(empty_sketch.is_exact());
+        assert_eq!(empty_sketch.estimate(), 0);
+    }
+
+    #[test]
+    fn test_cardinality_sketch_estimate() {
+        for num_distinct_vals in [1_000u64, 50_000, 1_000_000] {
+            let sketch = sketch_of((0..num_distinct_vals).chain(0..num_distinct_vals / 2));
+            assert!(!sketch.is_exact());
+            let error = sketch.estimate() as f64 / num_distinct_vals as f64 - 1.0;
+            assert!(error.abs() < 0.3, "{num_distinct_vals}: {sketch:?}");
+        }
+    }
+
+    #[test]
+    fn test_cardinality_sketch_estimate_bounded_by_num_vals() {
+        let sketch = sketch_of(0..CARDINALITY_SKETCH_NUM_HASHES as u64);
+        assert!(!sketch.is_exact());
+        assert!(sketch.estimate() <= CARDINALITY_SKETCH_NUM_HASHES as u64);
+    }
+
+    #[test]
+    fn test_cardinality_sketch_merge() {
+        let left = sketch_of(0..60_000u64);
+        let right = sketch_of(40_000..100_000u64);
+        let merged = left.merge(&right);
+        let error = merged.estimate() as f64 / 100_000f64 - 1.0;
+        assert!(error.abs() < 0.3, "{merged:?}");
+
+        let exact_left = sketch_of(0..50u64);
+        let exact_right = sketch_of(25..75u64);
+        let merged = exact_left.merge(&exact_right);
+        assert!(merged.is_exact());
+        assert_eq!(merged.estimate(), 75);
+
+        // Only the hashes of the exact sketch below the threshold of the other one are kept.
+        let merged = exact_left.merge(&right);
+        assert!(!merged.is_exact());
+        assert_eq!(merged.hashes().count(), CARDINALITY_SKETCH_NUM_HASHES);
+        assert!(merged.hashes().last() <= right.hashes().last());
+    }
+
+    #[test]
+    fn test_split_cardinality_sketch() {
+        let mut buffer = vec![1u8, 2u8];
+        let mut collector = CardinalitySketchCollector::default();
+        collector.collect(3);
+        collector.collect(3);
+        collector.collect(5);
+        collector.serialize(&mut buffer).unwrap();
+        assert_eq!(buffer.len(), 2 + 2 * 8 + 4);
+        let (bytes, hashes) = split_cardinality_sketch(OwnedBytes::new(buffer)).unwrap();
+        assert_eq!(bytes.as_slice(), &[1u8, 2u8]);
+        assert_eq!(CardinalitySketch::open(hashes, 3).unwrap().estimate(), 2);
+        assert!(split_cardinality_sketch(OwnedBytes::new(vec![0u8; 3])).is_err());
+        assert!(split_cardinality_sketch(OwnedBytes::new(vec![1u8, 0, 0, 0])).is_err());
+    }
+}
@@ -0,0 +1,173 @@
+use std::fmt;
+use std::io::{self, Write};
+
+use common::{BinarySerializable, OwnedBytes};
+use crc32fast::Hasher;
+
+use crate::RowId;
+
+/// Number of bytes of the [`CodecFooter`].
+pub const CODEC_FOOTER_NUM_BYTES: usize = 10;
+
+/// Footer ending the u64-based column values, from [`Version::V3`](crate::Version::V3) on.
+///
+/// It records the codec and the version of the codec layout the column was serialized with, so
+/// that a reader refuses a column it cannot decode rather than returning garbage values.
+///
+/// Layout: `[codec code: u8][codec version: u8][num rows: u32][checksum: u32]`, the checksum
+/// being the CRC32 of all the bytes preceding it.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(crate) struct CodecFooter {
+    pub codec_code: u8,
+    pub codec_version: u8,
+    pub num_rows: RowId,
+    pub checksum: u32,
+}
+
+impl CodecFooter {
+    /// Splits the footer off the end of the column values.
+    pub fn split_off(bytes: OwnedBytes) -> io::Result<(OwnedBytes, CodecFooter)> {
+        if bytes.len() < CODEC_FOOTER_NUM_BYTES {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "Codec footer is truncated",
+            ));
+        }
+        let (bytes, mut footer_bytes) = bytes.rsplit(CODEC_FOOTER_NUM_BYTES);
+        let codec_code = u8::deserialize(&mut footer_bytes)?;
+        let codec_version = u8::deserialize(&mut footer_bytes)?;
+        let num_rows = u32::deserialize(&mut footer_bytes)?;
+        let checksum = u32::deserialize(&mut footer_bytes)?;
+        let footer = CodecFooter {
+            codec_code,
+            codec_version,
+            num_rows,
+            checksum,
+        };
+        Ok((bytes, footer))
+    }
+
+    /// Returns true if the checksum matches `bytes`, the column values preceding the footer.
+    pub fn is_checksum_valid(&self, bytes: &[u8]) -> bool {
+        let mut hasher = Hasher::new();
+        hasher.update(bytes);
+        hasher.update(&[self.codec_code, self.codec_version]);
+        hasher.update(&self.num_rows.to_le_bytes());
+        hasher.finalize() == self.checksum
+    }
+}
+
+/// Writer computing the checksum of the column values, to serialize their [`CodecFooter`].
+pub(crate) struct CodecFooterWriter<'a> {
+    wrt: &'a mut dyn Write,
+    hasher: Hasher,
+}
+
+impl<'a> CodecFooterWriter<'a> {
+    pub fn wrap(wrt: &'a mut dyn Write) -> CodecFooterWriter<'a> {
+        CodecFooterWriter {
+            wrt,
+            hasher: Hasher::new(),
+        }
+    }
+
+    /// Writes the footer of the column values written so far.
+    pub fn finish(mut self, codec_code: u8, codec_version: u8, num_rows: RowId) -> io::Result<()> {
+        let mut footer_bytes = [0u8; CODEC_FOOTER_NUM_BYTES - 4];
+        footer_bytes[0] = codec_code;
+        footer_bytes[1] = codec_version;
+        footer_bytes[2..].copy_from_slice(&num_rows.to_le_bytes());
+        self.write_all(&footer_bytes)?;
+        let checksum = self.hasher.finalize();
+        checksum.serialize(self.wrt)
+    }
+}
+
+impl<'a> Write for CodecFooterWriter<'a> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let num_bytes = self.wrt.write(buf)?;
+        self.hasher.update(&buf[..num_bytes]);
+        Ok(num_bytes)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.wrt.flush()
+    }
+}
+
+/// Error returned when loading column values serialized with a codec, or a version of a codec,
+/// that this reader does not support.
+///
+/// It is wrapped in an [`io::Error`] of kind [`io::ErrorKind::InvalidData`], from which it can be
+/// retrieved via [`io::Error::get_ref`] and a downcast.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum UnsupportedCodecError {
+    /// The codec is neither a built-in codec nor a registered one.
+    UnknownCodec {
+        /// Code of the codec the column was serialized with.
+        code: u8,
+    },
+    /// The column was serialized with a newer version of the codec layout.
+    UnsupportedVersion {
+        /// Code of the codec the column was serialized with.
+        code: u8,
+        /// Version of the codec layout the column was serialized with.
+        version: u8,
+        /// Latest version of the codec layout this reader supports.
+        supported_version: u8,
+    },
+}
+
+impl fmt::Display for UnsupportedCodecError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            UnsupportedCodecError::UnknownCodec { code } => {
+                write!(f, "Unknown codec type {code}, was its codec registered?")
+            }
+            UnsupportedCodecError::UnsupportedVersion {
+                code,
+                version,
+                supported_version,
+            } => write!(
+                f,
+                "Codec type {code} has version {version}, only versions up to {supported_version} \
+                 are supported"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for UnsupportedCodecError {}
+
+impl From<UnsupportedCodecError> for io::Error {
+    fn from(error: UnsupportedCodecError) -> Self {
+        io::Error::new(io::ErrorKind::InvalidData, error)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_codec_footer_serialization() -> io::Result<()> {
+        let mut buffer = Vec::new();
+        let mut footer_writer = CodecFooterWriter::wrap(&mut buffer);
+        footer_writer.write_all(&[1u8, 2u8, 3u8])?;
+        footer_writer.finish(7, 2, 1_000)?;
+        assert_eq!(buffer.len(), 3 + CODEC_FOOTER_NUM_BYTES);
+        let (bytes, footer) = CodecFooter::split_off(OwnedBytes::new(buffer.clone()))?;
+        assert_eq!(bytes.as_slice(), &[1u8, 2u8, 3u8]);
+        assert_eq!(footer.codec_code, 7);
+        assert_eq!(footer.codec_version, 2);
+        assert_eq!(footer.num_rows, 1_000);
+        assert!(footer.is_checksum_valid(bytes.as_slice()));
+        assert!(!footer.is_checksum_valid(&[1u8, 2u8, 4u8]));
+        Ok(())
+    }
+
+    #[test]
+    fn test_codec_footer_truncated() {
+        assert!(CodecFooter::split_off(OwnedBytes::new(vec![0u8; 9])).is_err());
+    }
+}
@@ -0,0 +1,358 @@
+use std::io::{self, Write};
+use std::marker::PhantomData;
+use std::ops::{Range, RangeInclusive};
+
+use common::{BinarySerializable, OwnedBytes};
+use tantivy_bitpacker::{compute_num_bits, BitPacker, BitUnpacker};
+
+use crate::column_values::u64_based::{
+    BlockwiseIter, ColumnCodec, ColumnCodecEstimator, ColumnStats, StatsCollector,
+};
+use crate::{ColumnValues, RowId};
+
+/// The state of the transform is stored every `CHECKPOINT_INTERVAL` rows, so that decoding a
+/// value requires inverting at most `CHECKPOINT_INTERVAL` transformed values.
+const CHECKPOINT_INTERVAL: u32 = 64;
+
+/// Number of values decoded at once when scanning for a value range.
+const SCAN_CHUNK_LEN: u32 = 512;
+
+fn num_checkpoints(num_rows: RowId) -> u32 {
+    (num_rows + CHECKPOINT_INTERVAL - 1) / CHECKPOINT_INTERVAL
+}
+
+/// Reversible transform applied to the values of a column, in row order, before handing them to
+/// the codec of a [`ComposedCodec`].
+///
+/// Transforms only need to take care of what depends on the previous values: all codecs
+/// already subtract the minimum value and divide by the GCD of the values they receive, so
+/// offset and GCD removal happen after every transform.
+pub trait ValueTransform: Send + Sync + 'static {
+    /// Creates the transform, before the first value of the column `first_val`.
+    fn new(first_val: u64) -> Self;
+    /// Recreates a transform from the state returned by [`ValueTransform::state`].
+    fn from_state(state: u64) -> Self;
+    /// Returns the state of the transform, from which the following values can be inverted.
+    fn state(&self) -> u64;
+    /// Transforms the next value of the column.
+    fn forward(&mut self, val: u64) -> u64;
+    /// Inverts the next transformed value of the column.
+    fn inverse(&mut self, transformed_val: u64) -> u64;
+}
+
+/// Replaces the values by their difference with the previous value, zigzag encoded so that
+/// small decreases also result in small values.
+pub struct DeltaTransform {
+    previous_val: u64,
+}
+
+#[inline]
+pub(super) fn zigzag_encode(val: i64) -> u64 {
+    ((val << 1) ^ (val >> 63)) as u64
+}
+
+#[inline]
+pub(super) fn zigzag_decode(val: u64) -> i64 {
+    (val >> 1) as i64 ^ -((val & 1) as i64)
+}
+
+impl ValueTransform for DeltaTransform {
+    fn new(first_val: u64) -> Self {
+        DeltaTransform {
+            previous_val: first_val,
+        }
+    }
+
+    fn from_state(state: u64) -> Self {
+        DeltaTransform {
+            previous_val: state,
+        }
+    }
+
+    fn state(&self) -> u64 {
+        self.previous_val
+    }
+
+    #[inline]
+    fn forward(&mut self, val: u64) -> u64 {
+        let delta = val.wrapping_sub(self.previous_val) as i64;
+        self.previous_val = val;
+        zigzag_encode(delta)
+    }
+
+    #[inline]
+    fn inverse(&mut self, transformed_val: u64) -> u64 {
+        let val = self
+            .previous_val
+            .wrapping_add(zigzag_decode(transformed_val) as u64);
+        self.previous_val = val;
+        val
+    }
+}
+
+/// Feeds the transformed values to the estimator of the inner codec, along with their own
+/// stats.
+pub struct ComposedEstimator<T, C: ColumnCodec> {
+    // `None` before the first value, and after rows left out of the sample.
+    transform_opt: Option<T>,
+    stats_collector: StatsCollector,
+    inner_estimator: C::Estimator,
+    max_checkpoint: u64,
+    row_id: RowId,
+}
+
+impl<T, C: ColumnCodec> Default for ComposedEstimator<T, C> {
+    fn default() -> Self {
+        ComposedEstimator {
+            transform_opt: None,
+            stats_collector: StatsCollector::default(),
+            inner_estimator: C::Estimator::default(),
+            max_checkpoint: 0,
+            row_id: 0,
+        }
+    }
+}
+
+impl<T: ValueTransform, C: ColumnCodec + 'static> ColumnCodecEstimator for ComposedEstimator<T, C> {
+    fn collect(&mut self, value: u64) {
+        let transform = self.transform_opt.get_or_insert_with(|| T::new(value));
+        if self.row_id % CHECKPOINT_INTERVAL == 0 {
+            self.max_checkpoint = self.max_checkpoint.max(transform.state());
+        }
+        let transformed_val = transform.forward(value);
+        self.stats_collector.collect(transformed_val);
+        self.inner_estimator.collect(transformed_val);
+        self.row_id += 1;
+    }
+
+    fn skip(&mut self, num_rows: RowId) {
+        // The next value does not follow the last collected one.
+        self.transform_opt = None;
+        self.inner_estimator.skip(num_rows);
+        self.row_id += num_rows;
+    }
+
+    fn finalize(&mut self) {
+        self.inner_estimator.finalize();
+    }
+
+    fn estimate(&self, stats: &ColumnStats) -> Option<u64> {
+        let inner_stats = self.stats_collector.stats();
+        let inner_num_bytes = self.inner_estimator.estimate(&inner_stats)?;
+        let checkpoint_num_bits = compute_num_bits(self.max_checkpoint) as u64;
+        let checkpoints_num_bytes =
+            (num_checkpoints(inner_stats.num_rows) as u64 * checkpoint_num_bits + 7) / 8;
+        Some(stats.num_bytes() + inner_num_bytes + checkpoints_num_bytes + 1)
+    }
+
+    fn serialize(
+        &self,
+        stats: &ColumnStats,
+        vals: &mut dyn Iterator<Item = u64>,
+        wrt: &mut dyn Write,
+    ) -> io::Result<()> {
+        stats.serialize(wrt)?;
+        let mut checkpoints: Vec<u64> =
+            Vec::with_capacity(num_checkpoints(stats.num_rows) as usize);
+        {
+            let mut transform_opt: Option<T> = None;
+            let mut transformed_vals = vals.enumerate().map(|(row_id, val)| {
+                let transform = transform_opt.get_or_insert_with(|| T::new(val));
+                if row_id as RowId % CHECKPOINT_INTERVAL == 0 {
+                    checkpoints.push(transform.state());
+                }
+                transform.forward(val)
+            });
+            self.inner_estimator.serialize(
+                &self.stats_collector.stats(),
+                &mut transformed_vals,
+                wrt,
+            )?;
+            // Some codecs serialize what they collected, without consuming the values.
+            transformed_vals.for_each(|_| {});
+        }
+        let checkpoint_num_bits =
+            compute_num_bits(checkpoints.iter().copied().max().unwrap_or(0u64));
+        let mut bit_packer = BitPacker::new();
+        for checkpoint in checkpoints {
+            bit_packer.write(checkpoint, checkpoint_num_bits, wrt)?;
+        }
+        bit_packer.close(wrt)?;
+        checkpoint_num_bits.serialize(wrt)?;
+        Ok(())
+    }
+}
+
+/// Reader of a column serialized with a [`ComposedCodec`].
+pub struct ComposedReader<T, R> {
+    stats: ColumnStats,
+    inner: R,
+    checkpoint_unpacker: BitUnpacker,
+    checkpoints: OwnedBytes,
+    _transform: PhantomData<fn() -> T>,
+}
+
+impl<T: ValueTransform, R: ColumnValues> ComposedReader<T, R> {
+    /// Returns the transform in its state right before the row `row_id`.
+    fn transform_before(&self, row_id: RowId) -> T {
+        let checkpoint_id = row_id / CHECKPOINT_INTERVAL;
+        let checkpoint = self
+            .checkpoint_unpacker
+            .get(checkpoint_id, &self.checkpoints);
+        let mut transform = T::from_state(checkpoint);
+        let checkpoint_row_id = checkpoint_id * CHECKPOINT_INTERVAL;
+        let mut transformed_vals = [0u64; CHECKPOINT_INTERVAL as usize];
+        let transformed_vals = &mut transformed_vals[..(row_id - checkpoint_row_id) as usize];
+        self.inner
+            .get_range(checkpoint_row_id as u64, transformed_vals);
+        for &transformed_val in transformed_vals.iter() {
+            transform.inverse(transformed_val);
+        }
+        transform
+    }
+}
+
+impl<T: ValueTransform, R: ColumnValues> ColumnValues for ComposedReader<T, R> {
+    #[inline]
+    fn get_val(&self, idx: u32) -> u64 {
+        self.transform_before(idx).inverse(self.inner.get_val(idx))
+    }
+
+    fn get_range(&self, start: u64, output: &mut [u64]) {
+        if output.is_empty() {
+            return;
+        }
+        let mut transform = self.transform_before(start as RowId);
+        self.inner.get_range(start, output);
+        for out in output.iter_mut() {
+            *out = transform.inverse(*out);
+        }
+    }
+
+    fn iter(&self) -> Box<dyn Iterator<Item = u64> + '_> {
+        Box::new(BlockwiseIter::new(self))
+    }
+
+    fn get_row_ids_for_value_range(
+        &self,
+        range: RangeInclusive<u64>,
+        row_id_range: Range<u32>,
+        positions: &mut Vec<u32>,
+    ) {
+        positions.clear();
+        let row_id_range = row_id_range.start..row_id_range.end.min(self.num_vals());
+        let mut chunk = vec![0u64; SCAN_CHUNK_LEN as usize];
+        let mut row_id = row_id_range.start;
+        while row_id < row_id_range.end {
+            let chunk_len = (row_id_range.end - row_id).min(SCAN_CHUNK_LEN);
+            let chunk = &mut chunk[..chunk_len as usize];
+            self.get_range(row_id as u64, chunk);
+            for (offset, val) in chunk.iter().enumerate() {
+                if range.contains(val) {
+                    positions.push(row_id + offset as u32);
+                }
+            }
+            row_id += chunk_len;
+        }
+    }
+
+    #[inline]
+    fn min_value(&self) -> u64 {
+        self.stats.min_value
+    }
+
+    #[inline]
+    fn max_value(&self) -> u64 {
+        self.stats.max_value
+    }
+
+    #[inline]
+    fn num_vals(&self) -> RowId {
+        self.stats.num_rows
+    }
+}
+
+/// Chains a [`ValueTransform`] in front of a codec: the values of the column are transformed
+/// before being estimated and serialized by the codec `C`.
+///
+/// Composed codecs are codecs themselves, and can be chained further, e.g.
+/// `ComposedCodec<DeltaTransform, ComposedCodec<DeltaTransform, BitpackedCodec>>` encodes the
+/// deltas of the deltas of the values. They can be made available to the columns through
+/// [`register_codec`](super::register_codec).
+pub struct ComposedCodec<T, C> {
+    _phantom: PhantomData<(T, C)>,
+}
+
+impl<T: ValueTransform, C: ColumnCodec + 'static> ColumnCodec for ComposedCodec<T, C> {
+    type ColumnValues = ComposedReader<T, C::ColumnValues>;
+    type Estimator = ComposedEstimator<T, C>;
+
+    fn load(bytes: OwnedBytes) -> io::Result<Self::ColumnValues> {
+        let mut bytes = bytes;
+        let stats = ColumnStats::deserialize(&mut bytes)?;
+        let (bytes, checkpoint_num_bits_bytes) = bytes.rsplit(1);
+        let checkpoint_num_bits = checkpoint_num_bits_bytes.as_slice()[0];
+        let checkpoints_num_bytes =
+            (num_checkpoints(stats.num_rows) as usize * checkpoint_num_bits as usize + 7) / 8;
+        let (inner_bytes, checkpoints) = bytes.rsplit(checkpoints_num_bytes);
+        Ok(ComposedReader {
+            stats,
+            inner: C::load(inner_bytes)?,
+            checkpoint_unpacker: BitUnpacker::new(checkpoint_num_bits),
+            checkpoints,
+            _transform: PhantomData,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::column_values::u64_based::tests::{create_and_validate, get_codec_test_datasets};
+    use crate::column_values::u64_based::{BitpackedCodec, BlockwiseForCodec};
+
+    type DeltaBitpackedCodec = ComposedCodec<DeltaTransform, BitpackedCodec>;
+
+    #[test]
+    fn test_zigzag() {
+        for val in [0i64, 1, -1, 2, -2, i64::MAX, i64::MIN] {
+            assert_eq!(zigzag_decode(zigzag_encode(val)), val);
+        }
+        assert_eq!(zigzag_encode(-1), 1);
+        assert_eq!(zigzag_encode(1), 2);
+    }
+
+    #[test]
+    fn test_with_codec_data_sets() {
+        for (mut data, name) in get_codec_test_datasets() {
+            create_and_validate::<DeltaBitpackedCodec>(&data, name).unwrap();
+            data.reverse();
+            create_and_validate::<DeltaBitpackedCodec>(&data, name).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_composed_codec_chained() {
+        type DeltaOfDeltaCodec =
+            ComposedCodec<DeltaTransform, ComposedCodec<DeltaTransform, BlockwiseForCodec>>;
+        for (data, name) in get_codec_test_datasets() {
+            create_and_validate::<DeltaOfDeltaCodec>(&data, name).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_composed_codec_random_walk() {
+        let mut val = 1u64 << 40;
+        let data: Vec<u64> = (0..100_000)
+            .map(|_| {
+                val = val + rand::random::<u64>() % 8 - 4;
+                val
+            })
+            .collect();
+        let (_, delta_compression) =
+            create_and_validate::<DeltaBitpackedCodec>(&data, "random walk").unwrap();
+        let (_, blockwise_for_compression) =
+            create_and_validate::<BlockwiseForCodec>(&data, "random walk").unwrap();
+        assert!(delta_compression < blockwise_for_compression);
+    }
+}
@@ -0,0 +1,282 @@
+use std::io::{self, Write};
+use std::ops::{Range, RangeInclusive};
+
+use common::{BinarySerializable, OwnedBytes};
+use fastdivide::DividerU64;
+use tantivy_bitpacker::{compute_num_bits, BitPacker, BitUnpacker};
+
+use crate::column_values::u64_based::{
+    BlockwiseIter, ColumnCodec, ColumnCodecEstimator, ColumnStats,
+};
+use crate::{ColumnValues, RowId};
+
+/// The value of one row every `CHECKPOINT_INTERVAL` rows is stored as is, so that accessing a
+/// value requires summing at most `CHECKPOINT_INTERVAL - 1` deltas.
+const CHECKPOINT_INTERVAL: u32 = 16;
+
+fn num_checkpoints(num_rows: RowId) -> u32 {
+    (num_rows + CHECKPOINT_INTERVAL - 1) / CHECKPOINT_INTERVAL
+}
+
+/// Reader of a column serialized with the [`DeltaCodec`].
+#[derive(Clone)]
+pub struct DeltaReader {
+    stats: ColumnStats,
+    delta_unpacker: BitUnpacker,
+    deltas: OwnedBytes,
+    checkpoint_unpacker: BitUnpacker,
+    checkpoints: OwnedBytes,
+}
+
+impl DeltaReader {
+    /// Returns `(val - min_value) / gcd` for the value of the row `idx`.
+    #[inline]
+    fn get_normalized_val(&self, idx: u32) -> u64 {
+        let checkpoint_id = idx / CHECKPOINT_INTERVAL;
+        let mut normalized_val = self
+            .checkpoint_unpacker
+            .get(checkpoint_id, &self.checkpoints);
+        for delta_idx in checkpoint_id * CHECKPOINT_INTERVAL + 1..=idx {
+            normalized_val += self.delta_unpacker.get(delta_idx, &self.deltas);
+        }
+        normalized_val
+    }
+
+    /// Returns the first row of `row_range` such that all of the following rows do not satisfy
+    /// `pred`, relying on the values being sorted.
+    fn partition_point(&self, row_range: Range<u32>, pred: impl Fn(u64) -> bool) -> u32 {
+        let (mut start, mut end) = (row_range.start, row_range.end);
+        while start < end {
+            let mid = start + (end - start) / 2;
+            if pred(self.get_val(mid)) {
+                start = mid + 1;
+            } else {
+                end = mid;
+            }
+        }
+        start
+    }
+}
+
+impl ColumnValues for DeltaReader {
+    #[inline]
+    fn get_val(&self, idx: u32) -> u64 {
+        self.stats.min_value + self.stats.gcd.get() * self.get_normalized_val(idx)
+    }
+
+    fn get_range(&self, start: u64, output: &mut [u64]) {
+        let Some((first, remaining_output)) = output.split_first_mut() else {
+            return;
+        };
+        let mut normalized_val = self.get_normalized_val(start as u32);
+        *first = normalized_val;
+        // The deltas of the following rows are decoded in place, then summed up.
+        self.delta_unpacker
+            .get_batch_u64s(start as u32 + 1, &self.deltas, remaining_output);
+        for out in remaining_output.iter_mut() {
+            normalized_val += *out;
+            *out = normalized_val;
+        }
+        for out in output.iter_mut() {
+            *out = self.stats.min_value + self.stats.gcd.get() * *out;
+        }
+    }
+
+    fn iter(&self) -> Box<dyn Iterator<Item = u64> + '_> {
+        Box::new(BlockwiseIter::new(self))
+    }
+
+    #[inline]
+    fn min_value(&self) -> u64 {
+        self.stats.min_value
+    }
+
+    #[inline]
+    fn max_value(&self) -> u64 {
+        self.stats.max_value
+    }
+
+    #[inline]
+    fn num_vals(&self) -> RowId {
+        self.stats.num_rows
+    }
+
+    fn get_row_ids_for_value_range(
+        &self,
+        range: RangeInclusive<u64>,
+        row_id_range: Range<u32>,
+        positions: &mut Vec<u32>,
+    ) {
+        positions.clear();
+        let row_id_range = row_id_range.start..row_id_range.end.min(self.num_vals());
+        let start = self.partition_point(row_id_range.clone(), |val| val < *range.start());
+        let end = self.partition_point(start..row_id_range.end, |val| val <= *range.end());
+        positions.extend(start..end);
+    }
+}
+
+/// Checks that the values of the column are non-decreasing, and gathers the largest difference
+/// between two consecutive values.
+#[derive(Default)]
+pub struct DeltaEstimator {
+    previous_val_opt: Option<u64>,
+    max_delta: u64,
+    is_decreasing: bool,
+}
+
+impl ColumnCodecEstimator for DeltaEstimator {
+    fn collect(&mut self, value: u64) {
+        if let Some(previous_val) = self.previous_val_opt {
+            if value < previous_val {
+                self.is_decreasing = true;
+            } else {
+                self.max_delta = self.max_delta.max(value - previous_val);
+            }
+        }
+        self.previous_val_opt = Some(value);
+    }
+
+    fn skip(&mut self, _num_rows: RowId) {
+        // The difference with the next value spans the skipped rows.
+        self.previous_val_opt = None;
+    }
+
+    fn estimate(&self, stats: &ColumnStats) -> Option<u64> {
+        if self.is_decreasing {
+            return None;
+        }
+        let delta_num_bits = compute_num_bits(self.max_delta / stats.gcd) as u64;
+        let checkpoint_num_bits = compute_num_bits(stats.amplitude() / stats.gcd) as u64;
+        Some(
+            stats.num_bytes()
+                + 1
+                + (stats.num_rows as u64 * delta_num_bits + 7) / 8
+                + (num_checkpoints(stats.num_rows) as u64 * checkpoint_num_bits + 7) / 8,
+        )
+    }
+
+    fn serialize(
+        &self,
+        stats: &ColumnStats,
+        vals: &mut dyn Iterator<Item = u64>,
+        wrt: &mut dyn Write,
+    ) -> io::Result<()> {
+        stats.serialize(wrt)?;
+        let delta_num_bits = compute_num_bits(self.max_delta / stats.gcd);
+        delta_num_bits.serialize(wrt)?;
+        let divider = DividerU64::divide_by(stats.gcd.get());
+        let mut checkpoints: Vec<u64> =
+            Vec::with_capacity(num_checkpoints(stats.num_rows) as usize);
+        let mut bit_packer = BitPacker::new();
+        let mut previous_normalized_val = 0u64;
+        for (row_id, val) in vals.enumerate() {
+            let normalized_val = divider.divide(val - stats.min_value);
+            if row_id as u32 % CHECKPOINT_INTERVAL == 0 {
+                checkpoints.push(normalized_val);
+            }
+            let delta = normalized_val
+                .checked_sub(previous_normalized_val)
+                .ok_or_else(|| {
+                    io::Error::new(
+                        io::ErrorKind::InvalidInput,
+                        "The delta codec requires non-decreasing values.",
+                    )
+                })?;
+            bit_packer.write(delta, delta_num_bits, wrt)?;
+            previous_normalized_val = normalized_val;
+        }
+        bit_packer.close(wrt)?;
+        let checkpoint_num_bits = compute_num_bits(stats.amplitude() / stats.gcd);
+        for checkpoint in checkpoints {
+            bit_packer.write(checkpoint, checkpoint_num_bits, wrt)?;
+        }
+        bit_packer.close(wrt)?;
+        Ok(())
+    }
+}
+
+/// Delta codec, for non-decreasing columns like the start offsets of the multivalued indexes.
+///
+/// The differences between consecutive values are bitpacked, along with the value of one row
+/// every 16 rows. Reading the values of consecutive rows, like the start and the end of the
+/// values of a document, only requires decoding one checkpoint followed by contiguous deltas.
+pub struct DeltaCodec;
+
+impl ColumnCodec for DeltaCodec {
+    type ColumnValues = DeltaReader;
+    type Estimator = DeltaEstimator;
+
+    fn load(mut data: OwnedBytes) -> io::Result<Self::ColumnValues> {
+        let stats = ColumnStats::deserialize(&mut data)?;
+        let delta_num_bits = u8::deserialize(&mut data)?;
+        let deltas_num_bytes = (stats.num_rows as usize * delta_num_bits as usize + 7) / 8;
+        let (deltas, checkpoints) = data.split(deltas_num_bytes);
+        let checkpoint_unpacker = BitUnpacker::new(compute_num_bits(stats.amplitude() / stats.gcd));
+        Ok(DeltaReader {
+            stats,
+            delta_unpacker: BitUnpacker::new(delta_num_bits),
+            deltas,
+            checkpoint_unpacker,
+            checkpoints,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::column_values::u64_based::tests::create_and_validate;
+    use crate::column_values::u64_based::{
+        serialize_and_load_u64_based_column_values, BitpackedCodec, CodecType, LinearCodec,
+    };
+
+    #[test]
+    fn test_with_codec_data_sets_simple() {
+        create_and_validate::<DeltaCodec>(&[0, 3, 3, 4, 10, 10, 10, 12], "simple").unwrap();
+        assert!(create_and_validate::<DeltaCodec>(&[0, 3, 2], "decreasing").is_none());
+    }
+
+    #[test]
+    fn test_with_codec_data_sets() {
+        let data_sets = crate::column_values::u64_based::tests::get_codec_test_datasets();
+        for (mut data, name) in data_sets {
+            create_and_validate::<DeltaCodec>(&data, name);
+            data.sort();
+            create_and_validate::<DeltaCodec>(&data, name).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_delta_get_row_ids_for_value_range() {
+        let data: Vec<u64> = (0..1_000u64).map(|i| i / 3 * 2).collect();
+        let column =
+            serialize_and_load_u64_based_column_values::<u64>(&&data[..], &[CodecType::Delta]);
+        let mut positions = Vec::new();
+        column.get_row_ids_for_value_range(10..=13, 0..1_000, &mut positions);
+        assert_eq!(positions, vec![15, 16, 17, 18, 19, 20]);
+        column.get_row_ids_for_value_range(10..=13, 17..2_000, &mut positions);
+        assert_eq!(positions, vec![17, 18, 19, 20]);
+        column.get_row_ids_for_value_range(11..=11, 0..1_000, &mut positions);
+        assert!(positions.is_empty());
+    }
+
+    #[test]
+    fn test_delta_multivalued_start_offsets() {
+        let mut start_offset = 0u64;
+        let data: Vec<u64> = (0..100_000u64)
+            .map(|_| {
+                start_offset += rand::random::<u64>() % 4;
+                start_offset
+            })
+            .collect();
+        let (_, delta_compression) =
+            create_and_validate::<DeltaCodec>(&data, "start offsets").unwrap();
+        let (_, bitpacked_compression) =
+            create_and_validate::<BitpackedCodec>(&data, "start offsets").unwrap();
+        let (_, linear_compression) =
+            create_and_validate::<LinearCodec>(&data, "start offsets").unwrap();
+        assert!(delta_compression < 0.1);
+        assert!(delta_compression < bitpacked_compression);
+        assert!(delta_compression < linear_compression);
+    }
+}
@@ -0,0 +1,402 @@
+use std::io::{self, Write};
+use std::ops::{Range, RangeInclusive};
+
+use common::{BinarySerializable, OwnedBytes};
+use fastdivide::DividerU64;
+use tantivy_bitpacker::{compute_num_bits, BitPacker, BitUnpacker};
+
+use crate::column_values::u64_based::composed::{zigzag_decode, zigzag_encode};
+use crate::column_values::u64_based::{
+    BlockwiseIter, ColumnCodec, ColumnCodecEstimator, ColumnStats,
+};
+use crate::{ColumnValues, RowId};
+
+/// The first value and the first difference of each block of `BLOCK_LEN` rows are stored as is.
+const BLOCK_LEN: u32 = 64;
+
+fn num_blocks(num_rows: RowId) -> u32 {
+    (num_rows + BLOCK_LEN - 1) / BLOCK_LEN
+}
+
+/// Reader of a column serialized with the [`DeltaOfDeltaCodec`].
+#[derive(Clone)]
+pub struct DeltaOfDeltaReader {
+    stats: ColumnStats,
+    delta_of_delta_unpacker: BitUnpacker,
+    delta_of_deltas: OwnedBytes,
+    checkpoint_unpacker: BitUnpacker,
+    checkpoints: OwnedBytes,
+    first_delta_unpacker: BitUnpacker,
+    first_deltas: OwnedBytes,
+}
+
+impl DeltaOfDeltaReader {
+    /// Returns `(val - min_value) / gcd` for the value of the row `idx`, along with the
+    /// difference with the normalized value of the previous row.
+    ///
+    /// For the first row of a block, the difference returned is the one of the second row.
+    #[inline]
+    fn get_normalized_val_and_delta(&self, idx: u32) -> (u64, u64) {
+        let block_id = idx / BLOCK_LEN;
+        let mut normalized_val = self.checkpoint_unpacker.get(block_id, &self.checkpoints);
+        let mut delta = self.first_delta_unpacker.get(block_id, &self.first_deltas);
+        for delta_of_delta_idx in block_id * BLOCK_LEN + 1..=idx {
+            let delta_of_delta = self
+                .delta_of_delta_unpacker
+                .get(delta_of_delta_idx, &self.delta_of_deltas);
+            delta = delta.wrapping_add(zigzag_decode(delta_of_delta) as u64);
+            normalized_val = normalized_val.wrapping_add(delta);
+        }
+        (normalized_val, delta)
+    }
+
+    /// Returns the first row of `row_range` such that all of the following rows do not satisfy
+    /// `pred`, relying on the values being sorted.
+    fn partition_point(&self, row_range: Range<u32>, pred: impl Fn(u64) -> bool) -> u32 {
+        let (mut start, mut end) = (row_range.start, row_range.end);
+        while start < end {
+            let mid = start + (end - start) / 2;
+            if pred(self.get_val(mid)) {
+                start = mid + 1;
+            } else {
+                end = mid;
+            }
+        }
+        start
+    }
+}
+
+impl ColumnValues for DeltaOfDeltaReader {
+    #[inline]
+    fn get_val(&self, idx: u32) -> u64 {
+        let (normalized_val, _) = self.get_normalized_val_and_delta(idx);
+        self.stats.min_value + self.stats.gcd.get() * normalized_val
+    }
+
+    fn get_range(&self, start: u64, output: &mut [u64]) {
+        let mut row_id = start as u32;
+        let mut output = output;
+        while !output.is_empty() {
+            let block_end = (row_id / BLOCK_LEN + 1) * BLOCK_LEN;
+            let len = ((block_end - row_id) as usize).min(output.len());
+            let (block_output, remaining_output) = output.split_at_mut(len);
+            let (mut normalized_val, mut delta) = self.get_normalized_val_and_delta(row_id);
+            block_output[0] = normalized_val;
+            // The second differences of the following rows of the block are decoded in place,
+            // then summed up twice.
+            self.delta_of_delta_unpacker.get_batch_u64s(
+                row_id + 1,
+                &self.delta_of_deltas,
+                &mut block_output[1..],
+            );
+            for out in block_output[1..].iter_mut() {
+                delta = delta.wrapping_add(zigzag_decode(*out) as u64);
+                normalized_val = normalized_val.wrapping_add(delta);
+                *out = normalized_val;
+            }
+            for out in block_output.iter_mut() {
+                *out = self.stats.min_value + self.stats.gcd.get() * *out;
+            }
+            output = remaining_output;
+            row_id += len as u32;
+        }
+    }
+
+    fn iter(&self) -> Box<dyn Iterator<Item = u64> + '_> {
+        Box::new(BlockwiseIter::new(self))
+    }
+
+    #[inline]
+    fn min_value(&self) -> u64 {
+        self.stats.min_value
+    }
+
+    #[inline]
+    fn max_value(&self) -> u64 {
+        self.stats.max_value
+    }
+
+    #[inline]
+    fn num_vals(&self) -> RowId {
+        self.stats.num_rows
+    }
+
+    fn get_row_ids_for_value_range(
+        &self,
+        range: RangeInclusive<u64>,
+        row_id_range: Range<u32>,
+        positions: &mut Vec<u32>,
+    ) {
+        positions.clear();
+        let row_id_range = row_id_range.start..row_id_range.end.min(self.num_vals());
+        let start = self.partition_point(row_id_range.clone(), |val| val < *range.start());
+        let end = self.partition_point(start..row_id_range.end, |val| val <= *range.end());
+        positions.extend(start..end);
+    }
+}
+
+/// Checks that the values of the column are non-decreasing with near-constant differences,
+/// and gathers the largest first and second differences.
+#[derive(Default)]
+pub struct DeltaOfDeltaEstimator {
+    previous_val_opt: Option<u64>,
+    previous_delta_opt: Option<u64>,
+    is_decreasing: bool,
+    max_delta: u64,
+    num_deltas: u64,
+    sum_deltas: u128,
+    // Largest increase and decrease between two consecutive differences.
+    max_delta_increase: u64,
+    max_delta_decrease: u64,
+    // Running mean and sum of squared deviations of the second differences.
+    num_delta_of_deltas: u64,
+    delta_of_delta_mean: f64,
+    delta_of_delta_m2: f64,
+}
+
+impl DeltaOfDeltaEstimator {
+    /// The codec only pays off for non-decreasing columns with near-constant differences, like
+    /// the timestamps of regularly sampled events. The standard deviation of the second
+    /// differences is required not to exceed the average difference.
+    fn is_applicable(&self) -> bool {
+        if self.is_decreasing {
+            return false;
+        }
+        if self.num_delta_of_deltas == 0 {
+            return true;
+        }
+        let mean_delta = self.sum_deltas as f64 / self.num_deltas as f64;
+        let delta_of_delta_variance = self.delta_of_delta_m2 / self.num_delta_of_deltas as f64;
+        delta_of_delta_variance <= mean_delta * mean_delta
+    }
+
+    /// Returns the number of bits of the zigzag encoded normalized second differences.
+    fn delta_of_delta_num_bits(&self, stats: &ColumnStats) -> u8 {
+        let gcd = stats.gcd.get();
+        let max_increase = (self.max_delta_increase / gcd).saturating_mul(2);
+        let max_decrease = (self.max_delta_decrease / gcd)
+            .saturating_mul(2)
+            .saturating_sub(1);
+        compute_num_bits(max_increase.max(max_decrease))
+    }
+
+    fn collect_delta_of_delta(&mut self, delta_of_delta: f64) {
+        self.num_delta_of_deltas += 1;
+        let deviation = delta_of_delta - self.delta_of_delta_mean;
+        self.delta_of_delta_mean += deviation / self.num_delta_of_deltas as f64;
+        self.delta_of_delta_m2 += deviation * (delta_of_delta - self.delta_of_delta_mean);
+    }
+}
+
+impl ColumnCodecEstimator for DeltaOfDeltaEstimator {
+    fn collect(&mut self, value: u64) {
+        let Some(previous_val) = self.previous_val_opt.replace(value) else {
+            return;
+        };
+        let Some(delta) = value.checked_sub(previous_val) else {
+            self.is_decreasing = true;
+            return;
+        };
+        self.max_delta = self.max_delta.max(delta);
+        self.num_deltas += 1;
+        self.sum_deltas += delta as u128;
+        if let Some(previous_delta) = self.previous_delta_opt {
+            if delta >= previous_delta {
+                let increase = delta - previous_delta;
+                self.max_delta_increase = self.max_delta_increase.max(increase);
+                self.collect_delta_of_delta(increase as f64);
+            } else {
+                let decrease = previous_delta - delta;
+                self.max_delta_decrease = self.max_delta_decrease.max(decrease);
+                self.collect_delta_of_delta(-(decrease as f64));
+            }
+        }
+        self.previous_delta_opt = Some(delta);
+    }
+
+    fn skip(&mut self, _num_rows: RowId) {
+        // The differences with the next value span the skipped rows.
+        self.previous_val_opt = None;
+        self.previous_delta_opt = None;
+    }
+
+    fn estimate(&self, stats: &ColumnStats) -> Option<u64> {
+        if !self.is_applicable() {
+            return None;
+        }
+        let delta_of_delta_num_bits = self.delta_of_delta_num_bits(stats) as u64;
+        let checkpoint_num_bits = compute_num_bits(stats.amplitude() / stats.gcd) as u64;
+        let first_delta_num_bits = compute_num_bits(self.max_delta / stats.gcd) as u64;
+        let num_blocks = num_blocks(stats.num_rows) as u64;
+        Some(
+            stats.num_bytes()
+                + 2
+                + (stats.num_rows as u64 * delta_of_delta_num_bits + 7) / 8
+                + (num_blocks * checkpoint_num_bits + 7) / 8
+                + (num_blocks * first_delta_num_bits + 7) / 8,
+        )
+    }
+
+    fn serialize(
+        &self,
+        stats: &ColumnStats,
+        vals: &mut dyn Iterator<Item = u64>,
+        wrt: &mut dyn Write,
+    ) -> io::Result<()> {
+        stats.serialize(wrt)?;
+        let first_delta_num_bits = compute_num_bits(self.max_delta / stats.gcd);
+        let delta_of_delta_num_bits = self.delta_of_delta_num_bits(stats);
+        first_delta_num_bits.serialize(wrt)?;
+        delta_of_delta_num_bits.serialize(wrt)?;
+        let divider = DividerU64::divide_by(stats.gcd.get());
+        let num_blocks = num_blocks(stats.num_rows) as usize;
+        let mut checkpoints: Vec<u64> = Vec::with_capacity(num_blocks);
+        let mut first_deltas: Vec<u64> = Vec::with_capacity(num_blocks);
+        let mut bit_packer = BitPacker::new();
+        let mut previous_normalized_val = 0u64;
+        let mut previous_delta = 0u64;
+        for (row_id, val) in vals.enumerate() {
+            let normalized_val = divider.divide(val - stats.min_value);
+            let idx_within_block = row_id as u32 % BLOCK_LEN;
+            // The second differences of the first two rows of a block are not needed, as the
+            // block starts with its first value and its first difference.
+            let delta_of_delta = if idx_within_block == 0 {
+                checkpoints.push(normalized_val);
+                first_deltas.push(0u64);
+                0u64
+            } else {
+                let delta = normalized_val
+                    .checked_sub(previous_normalized_val)
+                    .ok_or_else(|| {
+                        io::Error::new(
+                            io::ErrorKind::InvalidInput,
+                            "The delta of delta codec requires non-decreasing values.",
+                        )
+                    })?;
+                let delta_of_delta = if idx_within_block == 1 {
+                    *first_deltas.last_mut().unwrap() = delta;
+                    0u64
+                } else {
+                    zigzag_encode(delta.wrapping_sub(previous_delta) as i64)
+                };
+                previous_delta = delta;
+                delta_of_delta
+            };
+            bit_packer.write(delta_of_delta, delta_of_delta_num_bits, wrt)?;
+            previous_normalized_val = normalized_val;
+        }
+        bit_packer.close(wrt)?;
+        let checkpoint_num_bits = compute_num_bits(stats.amplitude() / stats.gcd);
+        for checkpoint in checkpoints {
+            bit_packer.write(checkpoint, checkpoint_num_bits, wrt)?;
+        }
+        bit_packer.close(wrt)?;
+        for first_delta in first_deltas {
+            bit_packer.write(first_delta, first_delta_num_bits, wrt)?;
+        }
+        bit_packer.close(wrt)?;
+        Ok(())
+    }
+}
+
+/// Delta of delta codec, for non-decreasing columns with near-constant increments like the
+/// timestamps of logs or metrics.
+///
+/// The columns are split in blocks of 64 rows. The first value and the first difference of each
+/// block are bitpacked, along with the differences between consecutive differences of all of the
+/// rows, zigzag encoded. Regularly spaced values therefore take close to no bits per row.
+pub struct DeltaOfDeltaCodec;
+
+impl ColumnCodec for DeltaOfDeltaCodec {
+    type ColumnValues = DeltaOfDeltaReader;
+    type Estimator = DeltaOfDeltaEstimator;
+
+    fn load(mut data: OwnedBytes) -> io::Result<Self::ColumnValues> {
+        let stats = ColumnStats::deserialize(&mut data)?;
+        let first_delta_num_bits = u8::deserialize(&mut data)?;
+        let delta_of_delta_num_bits = u8::deserialize(&mut data)?;
+        let delta_of_deltas_num_bytes =
+            (stats.num_rows as usize * delta_of_delta_num_bits as usize + 7) / 8;
+        let (delta_of_deltas, data) = data.split(delta_of_deltas_num_bytes);
+        let checkpoint_num_bits = compute_num_bits(stats.amplitude() / stats.gcd);
+        let checkpoints_num_bytes =
+            (num_blocks(stats.num_rows) as usize * checkpoint_num_bits as usize + 7) / 8;
+        let (checkpoints, first_deltas) = data.split(checkpoints_num_bytes);
+        Ok(DeltaOfDeltaReader {
+            stats,
+            delta_of_delta_unpacker: BitUnpacker::new(delta_of_delta_num_bits),
+            delta_of_deltas,
+            checkpoint_unpacker: BitUnpacker::new(checkpoint_num_bits),
+            checkpoints,
+            first_delta_unpacker: BitUnpacker::new(first_delta_num_bits),
+            first_deltas,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::column_values::u64_based::tests::create_and_validate;
+    use crate::column_values::u64_based::{
+        serialize_and_load_u64_based_column_values, BlockwiseLinearCodec, CodecType, DeltaCodec,
+    };
+
+    #[test]
+    fn test_with_codec_data_sets_simple() {
+        create_and_validate::<DeltaOfDeltaCodec>(&[10, 20, 30, 41, 50, 60, 60], "simple").unwrap();
+        create_and_validate::<DeltaOfDeltaCodec>(&[7], "single value").unwrap();
+        assert!(create_and_validate::<DeltaOfDeltaCodec>(&[0, 3, 2], "decreasing").is_none());
+        assert!(
+            create_and_validate::<DeltaOfDeltaCodec>(&[0, 1, 2, 1_000, 1_001], "irregular")
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn test_with_codec_data_sets() {
+        let data_sets = crate::column_values::u64_based::tests::get_codec_test_datasets();
+        for (mut data, name) in data_sets {
+            create_and_validate::<DeltaOfDeltaCodec>(&data, name);
+            data.sort();
+            create_and_validate::<DeltaOfDeltaCodec>(&data, name);
+        }
+    }
+
+    #[test]
+    fn test_delta_of_delta_get_row_ids_for_value_range() {
+        let data: Vec<u64> = (0..1_000u64).map(|i| i / 3 * 2).collect();
+        let column = serialize_and_load_u64_based_column_values::<u64>(
+            &&data[..],
+            &[CodecType::DeltaOfDelta],
+        );
+        let mut positions = Vec::new();
+        column.get_row_ids_for_value_range(10..=13, 0..1_000, &mut positions);
+        assert_eq!(positions, vec![15, 16, 17, 18, 19, 20]);
+        column.get_row_ids_for_value_range(10..=13, 17..2_000, &mut positions);
+        assert_eq!(positions, vec![17, 18, 19, 20]);
+        column.get_row_ids_for_value_range(11..=11, 0..1_000, &mut positions);
+        assert!(positions.is_empty());
+    }
+
+    #[test]
+    fn test_delta_of_delta_timestamps() {
+        // Timestamps in microseconds of events sampled every second, with some jitter.
+        let mut timestamp = 1_700_000_000_000_000u64;
+        let data: Vec<u64> = (0..100_000u64)
+            .map(|_| {
+                timestamp += 1_000_000 - 1 + rand::random::<u64>() % 3;
+                timestamp
+            })
+            .collect();
+        let (_, delta_of_delta_compression) =
+            create_and_validate::<DeltaOfDeltaCodec>(&data, "timestamps").unwrap();
+        let (_, delta_compression) =
+            create_and_validate::<DeltaCodec>(&data, "timestamps").unwrap();
+        let (_, blockwise_linear_compression) =
+            create_and_validate::<BlockwiseLinearCodec>(&data, "timestamps").unwrap();
+        assert!(delta_of_delta_compression < 0.1);
+        assert!(delta_of_delta_compression < delta_compression);
+        assert!(delta_of_delta_compression < blockwise_linear_compression);
+    }
+}
@@ -0,0 +1,232 @@
+use std::collections::BTreeSet;
+use std::io::{self, Write};
+use std::ops::{Range, RangeInclusive};
+use std::sync::Arc;
+
+use common::{BinarySerializable, OwnedBytes, VInt};
+use tantivy_bitpacker::{compute_num_bits, BitPacker, BitUnpacker};
+
+use crate::column_values::u64_based::{
+    BlockwiseIter, ColumnCodec, ColumnCodecEstimator, ColumnStats,
+};
+use crate::{ColumnValues, RowId};
+
+/// Maximum number of distinct values for the dictionary codec to be applicable.
+pub const MAX_DICTIONARY_SIZE: usize = 256;
+
+/// Reader of a column serialized with the [`DictionaryCodec`].
+#[derive(Clone)]
+pub struct DictionaryReader {
+    dictionary: Arc<[u64]>,
+    data: OwnedBytes,
+    bit_unpacker: BitUnpacker,
+    stats: ColumnStats,
+}
+
+fn num_bits(dictionary_len: usize) -> u8 {
+    compute_num_bits(dictionary_len.saturating_sub(1) as u64)
+}
+
+impl ColumnValues for DictionaryReader {
+    #[inline(always)]
+    fn get_val(&self, idx: u32) -> u64 {
+        let ord = self.bit_unpacker.get(idx, &self.data);
+        self.dictionary[ord as usize]
+    }
+
+    #[inline]
+    fn get_range(&self, start: u64, output: &mut [u64]) {
+        self.bit_unpacker
+            .get_batch_u64s(start as u32, &self.data, output);
+        for out in output.iter_mut() {
+            *out = self.dictionary[*out as usize];
+        }
+    }
+
+    fn iter(&self) -> Box<dyn Iterator<Item = u64> + '_> {
+        Box::new(BlockwiseIter::new(self))
+    }
+
+    #[inline]
+    fn min_value(&self) -> u64 {
+        self.stats.min_value
+    }
+
+    #[inline]
+    fn max_value(&self) -> u64 {
+        self.stats.max_value
+    }
+
+    #[inline]
+    fn num_vals(&self) -> RowId {
+        self.stats.num_rows
+    }
+
+    fn get_row_ids_for_value_range(
+        &self,
+        range: RangeInclusive<u64>,
+        doc_id_range: Range<u32>,
+        positions: &mut Vec<u32>,
+    ) {
+        // The dictionary is sorted, so the values of the range map to a range of ordinals.
+        let start_ord = self.dictionary.partition_point(|val| val < range.start());
+        let end_ord = self.dictionary.partition_point(|val| val <= range.end());
+        if start_ord >= end_ord {
+            positions.clear();
+            return;
+        }
+        self.bit_unpacker.get_ids_for_value_range(
+            start_ord as u64..=(end_ord - 1) as u64,
+            doc_id_range,
+            &self.data,
+            positions,
+        );
+    }
+}
+
+/// Gathers the distinct values of the column, until there are more than
+/// [`MAX_DICTIONARY_SIZE`] of them.
+#[derive(Default)]
+pub struct DictionaryEstimator {
+    distinct_values: BTreeSet<u64>,
+    dictionary: Vec<u64>,
+    too_many_values: bool,
+}
+
+impl ColumnCodecEstimator for DictionaryEstimator {
+    fn collect(&mut self, value: u64) {
+        if self.too_many_values {
+            return;
+        }
+        self.distinct_values.insert(value);
+        if self.distinct_values.len() > MAX_DICTIONARY_SIZE {
+            self.too_many_values = true;
+            self.distinct_values.clear();
+        }
+    }
+
+    fn finalize(&mut self) {
+        self.dictionary = std::mem::take(&mut self.distinct_values)
+            .into_iter()
+            .collect();
+    }
+
+    fn estimate(&self, stats: &ColumnStats) -> Option<u64> {
+        if self.too_many_values {
+            return None;
+        }
+        let mut dictionary_num_bytes = VInt(self.dictionary.len() as u64).num_bytes();
+        let mut previous_val = stats.min_value;
+        for &val in &self.dictionary {
+            dictionary_num_bytes += VInt((val - previous_val) / stats.gcd).num_bytes();
+            previous_val = val;
+        }
+        let num_bits_per_value = num_bits(self.dictionary.len());
+        Some(
+            stats.num_bytes()
+                + dictionary_num_bytes
+                + (stats.num_rows as u64 * (num_bits_per_value as u64) + 7) / 8,
+        )
+    }
+
+    fn serialize(
+        &self,
+        stats: &ColumnStats,
+        vals: &mut dyn Iterator<Item = u64>,
+        wrt: &mut dyn Write,
+    ) -> io::Result<()> {
+        stats.serialize(wrt)?;
+        VInt(self.dictionary.len() as u64).serialize(wrt)?;
+        let mut previous_val = stats.min_value;
+        for &val in &self.dictionary {
+            VInt((val - previous_val) / stats.gcd).serialize(wrt)?;
+            previous_val = val;
+        }
+        let num_bits = num_bits(self.dictionary.len());
+        let mut bit_packer = BitPacker::new();
+        for val in vals {
+            let ord = self.dictionary.binary_search(&val).map_err(|_| {
+                io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "Value missing from the dictionary.",
+                )
+            })?;
+            bit_packer.write(ord as u64, num_bits, wrt)?;
+        }
+        bit_packer.close(wrt)?;
+        Ok(())
+    }
+}
+
+/// Dictionary codec: stores the sorted distinct values of the column, followed by the
+/// bitpacked ordinals of the values.
+///
+/// It is only applicable to columns with at most [`MAX_DICTIONARY_SIZE`] distinct values.
+pub struct DictionaryCodec;
+
+impl ColumnCodec for DictionaryCodec {
+    type ColumnValues = DictionaryReader;
+    type Estimator = DictionaryEstimator;
+
+    fn load(mut data: OwnedBytes) -> io::Result<Self::ColumnValues> {
+        let stats = ColumnStats::deserialize(&mut data)?;
+        let dictionary_len = VInt::deserialize(&mut data)?.0 as usize;
+        let mut dictionary = Vec::with_capacity(dictionary_len);
+        let mut val = stats.min_value;
+        for _ in 0..dictionary_len {
+            val += VInt::deserialize(&mut data)?.0 * stats.gcd.get();
+            dictionary.push(val);
+        }
+        let bit_unpacker = BitUnpacker::new(num_bits(dictionary_len));
+        Ok(DictionaryReader {
+            dictionary: dictionary.into(),
+            data,
+            bit_unpacker,
+            stats,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::column_values::u64_based::tests::create_and_validate;
+    use crate::column_values::u64_based::BitpackedCodec;
+
+    #[test]
+    fn test_with_codec_data_sets_simple() {
+        create_and_validate::<DictionaryCodec>(&[4, 3, 12, 3, 4, 4], "name");
+    }
+
+    #[test]
+    fn test_with_codec_data_sets() {
+        let data_sets = crate::column_values::u64_based::tests::get_codec_test_datasets();
+        for (mut data, name) in data_sets {
+            create_and_validate::<DictionaryCodec>(&data, name);
+            data.reverse();
+            create_and_validate::<DictionaryCodec>(&data, name);
+        }
+    }
+
+    #[test]
+    fn test_dictionary_status_codes() {
+        let status_codes = [200u64, 201, 304, 404, 500, 503];
+        let data: Vec<u64> = (0..10_000u64)
+            .map(|i| status_codes[(i * i % 7 % 6) as usize])
+            .collect();
+        let (_, dictionary_compression) =
+            create_and_validate::<DictionaryCodec>(&data, "status codes").unwrap();
+        let (_, bitpacked_compression) =
+            create_and_validate::<BitpackedCodec>(&data, "status codes").unwrap();
+        assert!(dictionary_compression < 0.05);
+        assert!(dictionary_compression < bitpacked_compression);
+    }
+
+    #[test]
+    fn test_dictionary_not_applicable_to_high_cardinality() {
+        let data: Vec<u64> = (0..=MAX_DICTIONARY_SIZE as u64).collect();
+        assert!(create_and_validate::<DictionaryCodec>(&data, "high cardinality").is_none());
+        let data: Vec<u64> = (0..MAX_DICTIONARY_SIZE as u64).collect();
+        assert!(create_and_validate::<DictionaryCodec>(&data, "max cardinality").is_some());
+    }
+}
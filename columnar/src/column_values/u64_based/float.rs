@@ -0,0 +1,325 @@
+use std::io::{self, Write};
+
+use common::{f64_to_u64, u64_to_f64, BinarySerializable, OwnedBytes, VInt};
+use tantivy_bitpacker::{compute_num_bits, BitPacker, BitUnpacker};
+
+use crate::column_values::u64_based::{ColumnCodec, ColumnCodecEstimator, ColumnStats};
+use crate::{ColumnValues, RowId};
+
+const NUM_MANTISSA_BITS: u32 = 52;
+const MANTISSA_MASK: u64 = (1u64 << NUM_MANTISSA_BITS) - 1;
+const EXPONENT_MASK: u64 = (1u64 << 11) - 1;
+
+/// Splits the u64 representation of a `f64` into its sign, exponent and mantissa.
+fn split_float(val: u64) -> [u64; 3] {
+    let bits = u64_to_f64(val).to_bits();
+    [
+        bits >> 63,
+        (bits >> NUM_MANTISSA_BITS) & EXPONENT_MASK,
+        bits & MANTISSA_MASK,
+    ]
+}
+
+fn join_float(sign: u64, exponent: u64, mantissa: u64) -> u64 {
+    let bits = (sign << 63) | (exponent << NUM_MANTISSA_BITS) | mantissa;
+    f64_to_u64(f64::from_bits(bits))
+}
+
+/// One of the sign, exponent and mantissa streams.
+///
+/// A value of the stream is stored as `(val >> shift) - min`, bitpacked.
+#[derive(Clone, Copy, Debug, Default)]
+struct Stream {
+    min: u64,
+    shift: u8,
+    bit_unpacker: BitUnpacker,
+}
+
+impl Stream {
+    fn num_bytes(&self, num_rows: RowId) -> u64 {
+        VInt(self.min).num_bytes() + 2 + self.num_data_bytes(num_rows) as u64
+    }
+
+    fn num_data_bytes(&self, num_rows: RowId) -> usize {
+        (self.bit_unpacker.bit_width() as usize * num_rows as usize + 7) / 8
+    }
+
+    #[inline]
+    fn get(&self, idx: u32, data: &[u8]) -> u64 {
+        (self.min + self.bit_unpacker.get(idx, data)) << self.shift
+    }
+
+    fn write<W: Write + ?Sized>(
+        &self,
+        val: u64,
+        bit_packer: &mut BitPacker,
+        wrt: &mut W,
+    ) -> io::Result<()> {
+        bit_packer.write(
+            (val >> self.shift) - self.min,
+            self.bit_unpacker.bit_width(),
+            wrt,
+        )
+    }
+}
+
+impl BinarySerializable for Stream {
+    fn serialize<W: Write + ?Sized>(&self, writer: &mut W) -> io::Result<()> {
+        VInt(self.min).serialize(writer)?;
+        self.shift.serialize(writer)?;
+        self.bit_unpacker.bit_width().serialize(writer)?;
+        Ok(())
+    }
+
+    fn deserialize<R: io::Read>(reader: &mut R) -> io::Result<Self> {
+        let min = VInt::deserialize(reader)?.0;
+        let shift = u8::deserialize(reader)?;
+        let bit_width = u8::deserialize(reader)?;
+        Ok(Stream {
+            min,
+            shift,
+            bit_unpacker: BitUnpacker::new(bit_width),
+        })
+    }
+}
+
+#[derive(Clone, Copy)]
+struct StreamStats {
+    min: u64,
+    max: u64,
+    min_trailing_zeros: u32,
+}
+
+impl Default for StreamStats {
+    fn default() -> Self {
+        StreamStats {
+            min: u64::MAX,
+            max: 0,
+            min_trailing_zeros: u64::BITS,
+        }
+    }
+}
+
+impl StreamStats {
+    fn collect(&mut self, val: u64) {
+        self.min = self.min.min(val);
+        self.max = self.max.max(val);
+        self.min_trailing_zeros = self.min_trailing_zeros.min(val.trailing_zeros());
+    }
+
+    fn stream(&self, max_shift: u32) -> Stream {
+        if self.min > self.max {
+            return Stream::default();
+        }
+        // Every value of the stream is a multiple of `1 << shift`.
+        let shift = self.min_trailing_zeros.min(max_shift);
+        let min = self.min >> shift;
+        Stream {
+            min,
+            shift: shift as u8,
+            bit_unpacker: BitUnpacker::new(compute_num_bits((self.max >> shift) - min)),
+        }
+    }
+}
+
+/// Gathers the range of the sign, of the exponent and of the mantissa of the values, as well as
+/// the number of trailing zeros shared by all of the mantissas.
+#[derive(Default)]
+pub struct FloatEstimator {
+    stream_stats: [StreamStats; 3],
+    num_rows: RowId,
+}
+
+impl FloatEstimator {
+    fn streams(&self) -> [Stream; 3] {
+        let [sign_stats, exponent_stats, mantissa_stats] = &self.stream_stats;
+        [
+            sign_stats.stream(0),
+            exponent_stats.stream(0),
+            mantissa_stats.stream(NUM_MANTISSA_BITS),
+        ]
+    }
+}
+
+impl ColumnCodecEstimator for FloatEstimator {
+    fn collect(&mut self, value: u64) {
+        for (stream_stats, val) in self.stream_stats.iter_mut().zip(split_float(value)) {
+            stream_stats.collect(val);
+        }
+        self.num_rows += 1;
+    }
+
+    fn estimate(&self, stats: &ColumnStats) -> Option<u64> {
+        let streams_num_bytes: u64 = self
+            .streams()
+            .iter()
+            .map(|stream| stream.num_bytes(self.num_rows))
+            .sum();
+        Some(stats.num_bytes() + streams_num_bytes)
+    }
+
+    fn serialize(
+        &self,
+        stats: &ColumnStats,
+        vals: &mut dyn Iterator<Item = u64>,
+        wrt: &mut dyn Write,
+    ) -> io::Result<()> {
+        stats.serialize(wrt)?;
+        let streams = self.streams();
+        for stream in &streams {
+            stream.serialize(wrt)?;
+        }
+        let [sign_stream, exponent_stream, mantissa_stream] = streams;
+        // The mantissas are written as they come, while the signs and the exponents, which take
+        // a lot less space, are buffered and appended after them.
+        let mut sign_buffer: Vec<u8> = Vec::new();
+        let mut exponent_buffer: Vec<u8> = Vec::new();
+        let mut sign_bit_packer = BitPacker::new();
+        let mut exponent_bit_packer = BitPacker::new();
+        let mut mantissa_bit_packer = BitPacker::new();
+        for val in vals {
+            let [sign, exponent, mantissa] = split_float(val);
+            sign_stream.write(sign, &mut sign_bit_packer, &mut sign_buffer)?;
+            exponent_stream.write(exponent, &mut exponent_bit_packer, &mut exponent_buffer)?;
+            mantissa_stream.write(mantissa, &mut mantissa_bit_packer, wrt)?;
+        }
+        mantissa_bit_packer.close(wrt)?;
+        sign_bit_packer.close(&mut sign_buffer)?;
+        exponent_bit_packer.close(&mut exponent_buffer)?;
+        wrt.write_all(&sign_buffer)?;
+        wrt.write_all(&exponent_buffer)?;
+        Ok(())
+    }
+}
+
+/// Reader of a column serialized with the [`FloatCodec`].
+#[derive(Clone)]
+pub struct FloatReader {
+    streams: [(Stream, OwnedBytes); 3],
+    stats: ColumnStats,
+}
+
+impl ColumnValues for FloatReader {
+    #[inline]
+    fn get_val(&self, idx: u32) -> u64 {
+        let [sign, exponent, mantissa] = &self.streams;
+        join_float(
+            sign.0.get(idx, &sign.1),
+            exponent.0.get(idx, &exponent.1),
+            mantissa.0.get(idx, &mantissa.1),
+        )
+    }
+
+    #[inline]
+    fn min_value(&self) -> u64 {
+        self.stats.min_value
+    }
+
+    #[inline]
+    fn max_value(&self) -> u64 {
+        self.stats.max_value
+    }
+
+    #[inline]
+    fn num_vals(&self) -> RowId {
+        self.stats.num_rows
+    }
+}
+
+/// Codec for the columns of `f64` values: the sign, the exponent and the mantissa of the values
+/// are bitpacked in three separate streams.
+///
+/// Floats with few significant digits, like prices or measures, have mantissas ending with a
+/// lot of zeros, which are dropped, and most of the time a narrow range of exponents.
+pub struct FloatCodec;
+
+impl ColumnCodec for FloatCodec {
+    type ColumnValues = FloatReader;
+    type Estimator = FloatEstimator;
+
+    fn load(mut data: OwnedBytes) -> io::Result<Self::ColumnValues> {
+        let stats = ColumnStats::deserialize(&mut data)?;
+        let [sign, exponent, mantissa] = [
+            Stream::deserialize(&mut data)?,
+            Stream::deserialize(&mut data)?,
+            Stream::deserialize(&mut data)?,
+        ];
+        // The mantissas come first, followed by the signs and the exponents.
+        let (mantissa_data, data) = data.split(mantissa.num_data_bytes(stats.num_rows));
+        let (sign_data, exponent_data) = data.split(sign.num_data_bytes(stats.num_rows));
+        let streams = [
+            (sign, sign_data),
+            (exponent, exponent_data),
+            (mantissa, mantissa_data),
+        ];
+        Ok(FloatReader { streams, stats })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::column::F64_CODEC_TYPES;
+    use crate::column_values::u64_based::tests::create_and_validate;
+    use crate::column_values::u64_based::{
+        load_u64_based_column_values, serialize_u64_based_column_values, BitpackedCodec,
+        BlockwiseLinearCodec, CodecType,
+    };
+    use crate::MonotonicallyMappableToU64;
+
+    #[test]
+    fn test_split_float() {
+        for val in [0.0f64, -0.0, 1.5, -1.5, f64::MAX, f64::MIN, f64::INFINITY] {
+            let [sign, exponent, mantissa] = split_float(val.to_u64());
+            assert_eq!(join_float(sign, exponent, mantissa), val.to_u64());
+        }
+    }
+
+    #[test]
+    fn test_with_codec_data_sets() {
+        let data_sets = crate::column_values::u64_based::tests::get_codec_test_datasets();
+        for (mut data, name) in data_sets {
+            create_and_validate::<FloatCodec>(&data, name);
+            data.reverse();
+            create_and_validate::<FloatCodec>(&data, name);
+        }
+    }
+
+    #[test]
+    fn test_float_codec_mixed_signs() {
+        let data: Vec<u64> = [-3.25f64, 0.0, 17.5, -0.0, 1e-300, -1e300, f64::NAN]
+            .iter()
+            .map(|val| val.to_u64())
+            .collect();
+        create_and_validate::<FloatCodec>(&data, "mixed signs").unwrap();
+    }
+
+    #[test]
+    fn test_float_codec_prices() {
+        let data: Vec<u64> = (0..10_000u64)
+            .map(|i| (100.0 + (i * 7_919 % 1_000) as f64 * 0.5).to_u64())
+            .collect();
+        let (_, float_compression) = create_and_validate::<FloatCodec>(&data, "prices").unwrap();
+        let (_, bitpacked_compression) =
+            create_and_validate::<BitpackedCodec>(&data, "prices").unwrap();
+        let (_, blockwise_linear_compression) =
+            create_and_validate::<BlockwiseLinearCodec>(&data, "prices").unwrap();
+        assert!(float_compression < 0.25);
+        assert!(float_compression < bitpacked_compression);
+        assert!(float_compression < blockwise_linear_compression);
+    }
+
+    #[test]
+    fn test_float_codec_picked_for_f64_columns() -> io::Result<()> {
+        let vals: Vec<f64> = (0..10_000u64)
+            .map(|i| 100.0 + (i * 7_919 % 1_000) as f64 * 0.5)
+            .collect();
+        let mut buffer = Vec::new();
+        serialize_u64_based_column_values(&&vals[..], F64_CODEC_TYPES, &mut buffer)?;
+        assert_eq!(CodecType::try_from_code(buffer[0]), Some(CodecType::Float));
+        let column = load_u64_based_column_values::<f64>(OwnedBytes::new(buffer))?;
+        assert_eq!(column.get_val(1), vals[1]);
+        assert_eq!(column.max_value(), 599.5);
+        Ok(())
+    }
+}
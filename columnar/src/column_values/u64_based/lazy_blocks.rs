@@ -0,0 +1,127 @@
+use std::io;
+use std::ops::RangeInclusive;
+
+use common::{DeserializeFrom, OwnedBytes};
+use once_cell::sync::OnceCell;
+
+/// Metadata of the blocks of a column, parsed on first access rather than when the column is
+/// opened.
+///
+/// Opening a column over an mmap then only reads its header and the few bytes locating its
+/// metadata, so that opening a searcher over many fast fields does not page in all of them.
+///
+/// The bounds of the metadata and its size with regard to the number of blocks are checked
+/// when the column is opened, with [`check_blocks_num_bytes`]. Past that, deserializing the
+/// blocks cannot fail: a block that cannot be read is replaced by a block that is valid for the
+/// column, so that a corrupted column does not panic while it is searched.
+pub(crate) struct LazyBlocks<B> {
+    blocks: OnceCell<Box<[B]>>,
+    deserialize: Box<dyn Fn() -> Vec<B> + Send + Sync>,
+}
+
+impl<B> LazyBlocks<B> {
+    /// Creates the blocks, to be deserialized by `deserialize` on first access.
+    pub fn new(deserialize: impl Fn() -> Vec<B> + Send + Sync + 'static) -> Self {
+        LazyBlocks {
+            blocks: OnceCell::new(),
+            deserialize: Box::new(deserialize),
+        }
+    }
+
+    /// Creates blocks that are already known.
+    pub fn from_blocks(blocks: Vec<B>) -> Self {
+        LazyBlocks {
+            blocks: OnceCell::with_value(blocks.into_boxed_slice()),
+            deserialize: Box::new(Vec::new),
+        }
+    }
+
+    /// Returns the blocks, deserializing them on the first call.
+    #[inline]
+    pub fn get(&self) -> &[B] {
+        self.blocks
+            .get_or_init(|| (self.deserialize)().into_boxed_slice())
+    }
+}
+
+/// Splits the footer holding the metadata of the blocks off the data of a column, the footer
+/// being followed by its length as a u32.
+pub(crate) fn split_blocks_footer(bytes: OwnedBytes) -> io::Result<(OwnedBytes, OwnedBytes)> {
+    let truncated_err = || io::Error::new(io::ErrorKind::InvalidData, "Blocks footer is truncated");
+    if bytes.len() < 4 {
+        return Err(truncated_err());
+    }
+    let footer_len: u32 = (&bytes[bytes.len() - 4..]).deserialize()?;
+    let footer_offset = (bytes.len() - 4)
+        .checked_sub(footer_len as usize)
+        .ok_or_else(truncated_err)?;
+    Ok(bytes.split(footer_offset))
+}
+
+/// Checks that `num_bytes` of metadata can hold `num_blocks` blocks, each of them taking a
+/// number of bytes within `block_num_bytes`.
+pub(crate) fn check_blocks_num_bytes(
+    num_bytes: usize,
+    num_blocks: usize,
+    block_num_bytes: RangeInclusive<usize>,
+) -> io::Result<()> {
+    let min_num_bytes = num_blocks.saturating_mul(*block_num_bytes.start());
+    let max_num_bytes = num_blocks.saturating_mul(*block_num_bytes.end());
+    if num_bytes < min_num_bytes || num_bytes > max_num_bytes {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!(
+                "Blocks metadata of {num_bytes} bytes does not match the {num_blocks} blocks of \
+                 the column"
+            ),
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    use super::*;
+
+    #[test]
+    fn test_lazy_blocks_deserialized_once_on_first_access() {
+        let num_calls = Arc::new(AtomicUsize::new(0));
+        let num_calls_clone = num_calls.clone();
+        let blocks = LazyBlocks::new(move || {
+            num_calls_clone.fetch_add(1, Ordering::SeqCst);
+            vec![1u32, 2u32]
+        });
+        assert_eq!(num_calls.load(Ordering::SeqCst), 0);
+        assert_eq!(blocks.get(), &[1u32, 2u32]);
+        assert_eq!(blocks.get(), &[1u32, 2u32]);
+        assert_eq!(num_calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn test_lazy_blocks_from_blocks() {
+        let blocks = LazyBlocks::from_blocks(vec![3u64]);
+        assert_eq!(blocks.get(), &[3u64]);
+    }
+
+    #[test]
+    fn test_split_blocks_footer() {
+        let (data, footer) =
+            split_blocks_footer(OwnedBytes::new(vec![1u8, 2u8, 3u8, 1u8, 0, 0, 0])).unwrap();
+        assert_eq!(data.as_slice(), &[1u8, 2u8]);
+        assert_eq!(footer.as_slice(), &[3u8, 1u8, 0, 0, 0]);
+        assert!(split_blocks_footer(OwnedBytes::new(vec![1u8, 0, 0])).is_err());
+        assert!(split_blocks_footer(OwnedBytes::new(vec![5u8, 0, 0, 0])).is_err());
+    }
+
+    #[test]
+    fn test_check_blocks_num_bytes() {
+        assert!(check_blocks_num_bytes(0, 0, 2..=11).is_ok());
+        assert!(check_blocks_num_bytes(6, 3, 2..=11).is_ok());
+        assert!(check_blocks_num_bytes(33, 3, 2..=11).is_ok());
+        assert!(check_blocks_num_bytes(5, 3, 2..=11).is_err());
+        assert!(check_blocks_num_bytes(34, 3, 2..=11).is_err());
+    }
+}
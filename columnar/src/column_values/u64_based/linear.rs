@@ -5,7 +5,9 @@ use tantivy_bitpacker::{compute_num_bits, BitPacker, BitUnpacker};
 
 use super::line::Line;
 use super::ColumnValues;
-use crate::column_values::u64_based::{ColumnCodec, ColumnCodecEstimator, ColumnStats};
+use crate::column_values::u64_based::{
+    BlockwiseIter, ColumnCodec, ColumnCodecEstimator, ColumnStats,
+};
 use crate::column_values::VecColumn;
 use crate::RowId;
 
@@ -29,6 +31,20 @@ impl ColumnValues for LinearReader {
         interpoled_val.wrapping_add(bitpacked_diff)
     }
 
+    #[inline]
+    fn get_range(&self, start: u64, output: &mut [u64]) {
+        self.linear_params
+            .bit_unpacker
+            .get_batch_u64s(start as u32, &self.data, output);
+        for (out, doc) in output.iter_mut().zip(start as u32..) {
+            *out = self.linear_params.line.eval(doc).wrapping_add(*out);
+        }
+    }
+
+    fn iter(&self) -> Box<dyn Iterator<Item = u64> + '_> {
+        Box::new(BlockwiseIter::new(self))
+    }
+
     #[inline(always)]
     fn min_value(&self) -> u64 {
         self.stats.min_value
@@ -108,8 +124,7 @@ impl ColumnCodecEstimator for LinearCodecEstimator {
 
     fn estimate(&self, stats: &ColumnStats) -> Option<u64> {
         let line = self.line?;
-        let amplitude = self.max_deviation - self.min_deviation;
-        let num_bits = compute_num_bits(amplitude);
+        let num_bits = self.estimate_num_bits(&line, stats);
         let linear_params = LinearParams {
             line,
             bit_unpacker: BitUnpacker::new(num_bits),
@@ -146,6 +161,11 @@ impl ColumnCodecEstimator for LinearCodecEstimator {
         Ok(())
     }
 
+    fn skip(&mut self, num_rows: RowId) {
+        // The line is trained on the first 512 values, which are never skipped.
+        self.row_id += num_rows;
+    }
+
     fn collect(&mut self, value: u64) {
         if let Some(line) = self.line {
             self.collect_after_line_estimation(&line, value);
@@ -156,6 +176,24 @@ impl ColumnCodecEstimator for LinearCodecEstimator {
 }
 
 impl LinearCodecEstimator {
+    /// Returns the number of bits of the deviations from the line.
+    ///
+    /// When estimating on a sample, the rows left out may deviate a lot more, e.g. the rare
+    /// large values of a heavy-tailed column. The values of the whole column span at least its
+    /// amplitude, of which the line only covers its own span: the rest is made of deviations.
+    fn estimate_num_bits(&self, line: &Line, stats: &ColumnStats) -> u8 {
+        let mut amplitude = self.max_deviation - self.min_deviation;
+        if let Some(distribution) = &stats.distribution {
+            let last_row_id = self.row_id.saturating_sub(1);
+            let line_span = line.eval(last_row_id).wrapping_sub(line.eval(0)) as i64;
+            let min_amplitude = distribution
+                .amplitude()
+                .saturating_sub(line_span.unsigned_abs());
+            amplitude = amplitude.max(min_amplitude);
+        }
+        compute_num_bits(amplitude)
+    }
+
     #[inline]
     fn collect_after_line_estimation(&mut self, line: &Line, value: u64) {
         let interpoled_val: u64 = line.eval(self.row_id);
@@ -1,8 +1,24 @@
 mod bitpacked;
+mod block_stats;
+mod blockwise_for;
+mod blockwise_iter;
 mod blockwise_linear;
+mod cardinality_sketch;
+mod codec_footer;
+mod composed;
+mod delta;
+mod delta_of_delta;
+mod dictionary;
+mod float;
+mod lazy_blocks;
 mod line;
 mod linear;
+mod registry;
+mod run_length;
+mod sparse;
 mod stats_collector;
+#[cfg(feature = "zstd")]
+mod zstd_block;
 
 use std::io;
 use std::io::Write;
@@ -14,12 +30,49 @@ use crate::column_values::monotonic_mapping::{
     StrictlyMonotonicMappingInverter, StrictlyMonotonicMappingToInternal,
 };
 pub use crate::column_values::u64_based::bitpacked::BitpackedCodec;
+use crate::column_values::u64_based::block_stats::{
+    split_block_stats, BlockStatsCollector, BlockStatsColumnValues,
+};
+pub use crate::column_values::u64_based::block_stats::{BlockStats, BLOCK_STATS_NUM_ROWS};
+pub use crate::column_values::u64_based::blockwise_for::BlockwiseForCodec;
+use crate::column_values::u64_based::blockwise_iter::BlockwiseIter;
 pub use crate::column_values::u64_based::blockwise_linear::BlockwiseLinearCodec;
+use crate::column_values::u64_based::cardinality_sketch::{
+    split_cardinality_sketch, CardinalitySketchCollector,
+};
+pub use crate::column_values::u64_based::cardinality_sketch::{
+    CardinalitySketch, CARDINALITY_SKETCH_NUM_HASHES,
+};
+pub use crate::column_values::u64_based::codec_footer::UnsupportedCodecError;
+use crate::column_values::u64_based::codec_footer::{CodecFooter, CodecFooterWriter};
+pub use crate::column_values::u64_based::composed::{
+    ComposedCodec, DeltaTransform, ValueTransform,
+};
+pub use crate::column_values::u64_based::delta::DeltaCodec;
+pub use crate::column_values::u64_based::delta_of_delta::DeltaOfDeltaCodec;
+pub use crate::column_values::u64_based::dictionary::{DictionaryCodec, MAX_DICTIONARY_SIZE};
+pub use crate::column_values::u64_based::float::FloatCodec;
 pub use crate::column_values::u64_based::linear::LinearCodec;
+pub use crate::column_values::u64_based::registry::{register_codec, FIRST_REGISTERED_CODEC_CODE};
+pub use crate::column_values::u64_based::run_length::RunLengthCodec;
+pub use crate::column_values::u64_based::sparse::SparseCodec;
 pub use crate::column_values::u64_based::stats_collector::StatsCollector;
+#[cfg(feature = "zstd")]
+pub use crate::column_values::u64_based::zstd_block::ZstdBlockCodec;
 use crate::column_values::{monotonic_map_column, ColumnStats};
 use crate::iterable::Iterable;
-use crate::{ColumnValues, MonotonicallyMappableToU64};
+use crate::{ColumnValues, MonotonicallyMappableToU64, RowId, Version, CURRENT_VERSION};
+
+/// Columns with up to this number of rows are estimated on all of their values.
+const FULL_ESTIMATION_NUM_ROWS: RowId = 1 << 16;
+/// Past `FULL_ESTIMATION_NUM_ROWS`, codecs are estimated on one block of
+/// `SAMPLE_BLOCK_LEN` values every `SAMPLING_STRIDE` blocks.
+const SAMPLE_BLOCK_LEN: RowId = 512;
+const SAMPLING_STRIDE: RowId = 16;
+
+fn is_sampled(row_id: RowId) -> bool {
+    row_id < FULL_ESTIMATION_NUM_ROWS || (row_id / SAMPLE_BLOCK_LEN) % SAMPLING_STRIDE == 0
+}
 
 /// A `ColumnCodecEstimator` is in charge of gathering all
 /// data required to serialize a column.
@@ -28,6 +81,10 @@ use crate::{ColumnValues, MonotonicallyMappableToU64};
 /// During that pass, all column estimators receive a call to their
 /// `.collect(el)`.
 ///
+/// For large columns, the estimators used to pick a codec only receive a deterministic sample
+/// of the values: blocks of 512 values, separated by calls to `.skip(..)`. The estimator of
+/// the picked codec then collects all of the values, before serializing the column.
+///
 /// After this first pass, finalize is called.
 /// `.estimate(..)` then should return an accurate estimation of the
 /// size of the serialized column (were we to pick this codec.).
@@ -37,6 +94,10 @@ pub trait ColumnCodecEstimator<T = u64>: 'static {
     /// This method will be called for each element of the column during
     /// `estimation`.
     fn collect(&mut self, value: u64);
+    /// Records that the next `num_rows` values of the column were left out of the sample.
+    ///
+    /// This is never called before the first 512 values were collected.
+    fn skip(&mut self, _num_rows: RowId) {}
     /// Finalizes the first pass phase.
     fn finalize(&mut self) {}
     /// Returns an accurate estimation of the number of bytes that will
@@ -44,6 +105,9 @@ pub trait ColumnCodecEstimator<T = u64>: 'static {
     fn estimate(&self, stats: &ColumnStats) -> Option<u64>;
     /// Serializes the column using the given codec.
     /// This constitutes a second pass over the columns values.
+    ///
+    /// `vals` may be backed by a merge of several columns rather than by values in memory:
+    /// codecs should write the values as they come, buffering no more than a block of them.
     fn serialize(
         &self,
         stats: &ColumnStats,
@@ -59,6 +123,12 @@ pub trait ColumnCodec<T: PartialOrd = u64> {
     /// `Estimator` for the given codec.
     type Estimator: ColumnCodecEstimator + Default;
 
+    /// Version of the layout of the codec, recorded along with the serialized columns.
+    ///
+    /// It must be incremented whenever the layout changes, so that older readers refuse the
+    /// columns serialized with the new layout.
+    const VERSION: u8 = 1;
+
     /// Loads a column that has been serialized using this codec.
     fn load(bytes: OwnedBytes) -> io::Result<Self::ColumnValues>;
 
@@ -86,13 +156,63 @@ pub enum CodecType {
     Linear = 1u8,
     /// Same as [`CodecType::Linear`], but encodes in blocks of 512 elements.
     BlockwiseLinear = 2u8,
+    /// Frame of reference: encodes in blocks of 512 elements, bitpacking the offsets of the values
+    /// from the minimum value of their block.
+    BlockwiseFor = 3u8,
+    /// Stores the distinct values of the column, and bitpacks the ordinals of the values.
+    /// Only applicable to columns with at most [`MAX_DICTIONARY_SIZE`] distinct values.
+    Dictionary = 4u8,
+    /// Only stores the values different from the most frequent of the minimum and the maximum
+    /// value, along with their rows. Only applicable if such values are rare.
+    Sparse = 5u8,
+    /// Bitpacks the sign, the exponent and the mantissa of the `f64` values in separate streams,
+    /// dropping the trailing zeros shared by all of the mantissas.
+    Float = 6u8,
+    /// Bitpacks the differences between consecutive values, along with the value of one row
+    /// every 16 rows. Only applicable to non-decreasing columns.
+    Delta = 7u8,
+    /// Compresses blocks of 1,024 values with zstd, decompressing them on access. Meant for
+    /// rarely accessed columns. Requires the `zstd` feature.
+    #[cfg(feature = "zstd")]
+    ZstdBlock = 8u8,
+    /// Bitpacks the differences between consecutive differences of the values, along with the
+    /// first value and the first difference of every block of 64 rows. Only applicable to
+    /// non-decreasing columns with near-constant increments, like timestamps.
+    DeltaOfDelta = 9u8,
+    /// Bitpacks the value and the end of every run of identical consecutive values. The run
+    /// of a row is found by binary search.
+    RunLength = 10u8,
 }
 
 /// List of all available u64-base codecs.
-pub const ALL_U64_CODEC_TYPES: [CodecType; 3] = [
+#[cfg(feature = "zstd")]
+pub const ALL_U64_CODEC_TYPES: [CodecType; 11] = [
+    CodecType::Bitpacked,
+    CodecType::Linear,
+    CodecType::BlockwiseLinear,
+    CodecType::BlockwiseFor,
+    CodecType::Dictionary,
+    CodecType::Sparse,
+    CodecType::Float,
+    CodecType::Delta,
+    CodecType::ZstdBlock,
+    CodecType::DeltaOfDelta,
+    CodecType::RunLength,
+];
+
+/// List of all available u64-base codecs.
+#[cfg(not(feature = "zstd"))]
+pub const ALL_U64_CODEC_TYPES: [CodecType; 10] = [
     CodecType::Bitpacked,
     CodecType::Linear,
     CodecType::BlockwiseLinear,
+    CodecType::BlockwiseFor,
+    CodecType::Dictionary,
+    CodecType::Sparse,
+    CodecType::Float,
+    CodecType::Delta,
+    CodecType::DeltaOfDelta,
+    CodecType::RunLength,
 ];
 
 impl CodecType {
@@ -100,11 +220,20 @@ impl CodecType {
         self as u8
     }
 
-    fn try_from_code(code: u8) -> Option<CodecType> {
+    pub(crate) fn try_from_code(code: u8) -> Option<CodecType> {
         match code {
             0u8 => Some(CodecType::Bitpacked),
             1u8 => Some(CodecType::Linear),
             2u8 => Some(CodecType::BlockwiseLinear),
+            3u8 => Some(CodecType::BlockwiseFor),
+            4u8 => Some(CodecType::Dictionary),
+            5u8 => Some(CodecType::Sparse),
+            6u8 => Some(CodecType::Float),
+            7u8 => Some(CodecType::Delta),
+            #[cfg(feature = "zstd")]
+            8u8 => Some(CodecType::ZstdBlock),
+            9u8 => Some(CodecType::DeltaOfDelta),
+            10u8 => Some(CodecType::RunLength),
             _ => None,
         }
     }
@@ -112,24 +241,71 @@ impl CodecType {
     fn load<T: MonotonicallyMappableToU64>(
         &self,
         bytes: OwnedBytes,
+        block_stats: OwnedBytes,
+        cardinality_sketch: Option<CardinalitySketch>,
     ) -> io::Result<Arc<dyn ColumnValues<T>>> {
         match self {
-            CodecType::Bitpacked => load_specific_codec::<BitpackedCodec, T>(bytes),
-            CodecType::Linear => load_specific_codec::<LinearCodec, T>(bytes),
-            CodecType::BlockwiseLinear => load_specific_codec::<BlockwiseLinearCodec, T>(bytes),
+            CodecType::Bitpacked => {
+                load_specific_codec::<BitpackedCodec, T>(bytes, block_stats, cardinality_sketch)
+            }
+            CodecType::Linear => {
+                load_specific_codec::<LinearCodec, T>(bytes, block_stats, cardinality_sketch)
+            }
+            CodecType::BlockwiseLinear => load_specific_codec::<BlockwiseLinearCodec, T>(
+                bytes,
+                block_stats,
+                cardinality_sketch,
+            ),
+            CodecType::BlockwiseFor => {
+                load_specific_codec::<BlockwiseForCodec, T>(bytes, block_stats, cardinality_sketch)
+            }
+            CodecType::Dictionary => {
+                load_specific_codec::<DictionaryCodec, T>(bytes, block_stats, cardinality_sketch)
+            }
+            CodecType::Sparse => {
+                load_specific_codec::<SparseCodec, T>(bytes, block_stats, cardinality_sketch)
+            }
+            CodecType::Float => {
+                load_specific_codec::<FloatCodec, T>(bytes, block_stats, cardinality_sketch)
+            }
+            CodecType::Delta => {
+                load_specific_codec::<DeltaCodec, T>(bytes, block_stats, cardinality_sketch)
+            }
+            #[cfg(feature = "zstd")]
+            CodecType::ZstdBlock => {
+                load_specific_codec::<ZstdBlockCodec, T>(bytes, block_stats, cardinality_sketch)
+            }
+            CodecType::DeltaOfDelta => {
+                load_specific_codec::<DeltaOfDeltaCodec, T>(bytes, block_stats, cardinality_sketch)
+            }
+            CodecType::RunLength => {
+                load_specific_codec::<RunLengthCodec, T>(bytes, block_stats, cardinality_sketch)
+            }
         }
     }
 }
 
 fn load_specific_codec<C: ColumnCodec, T: MonotonicallyMappableToU64>(
     bytes: OwnedBytes,
+    block_stats: OwnedBytes,
+    cardinality_sketch: Option<CardinalitySketch>,
 ) -> io::Result<Arc<dyn ColumnValues<T>>> {
     let reader = C::load(bytes)?;
+    Ok(map_to_typed_column(BlockStatsColumnValues::new(
+        reader,
+        block_stats,
+        cardinality_sketch,
+    )?))
+}
+
+fn map_to_typed_column<C: ColumnValues + 'static, T: MonotonicallyMappableToU64>(
+    reader: C,
+) -> Arc<dyn ColumnValues<T>> {
     let reader_typed = monotonic_map_column(
         reader,
         StrictlyMonotonicMappingInverter::from(StrictlyMonotonicMappingToInternal::<T>::new()),
     );
-    Ok(Arc::new(reader_typed))
+    Arc::new(reader_typed)
 }
 
 impl CodecType {
@@ -139,25 +315,186 @@ impl CodecType {
             CodecType::Bitpacked => BitpackedCodec::boxed_estimator(),
             CodecType::Linear => LinearCodec::boxed_estimator(),
             CodecType::BlockwiseLinear => BlockwiseLinearCodec::boxed_estimator(),
+            CodecType::BlockwiseFor => BlockwiseForCodec::boxed_estimator(),
+            CodecType::Dictionary => DictionaryCodec::boxed_estimator(),
+            CodecType::Sparse => SparseCodec::boxed_estimator(),
+            CodecType::Float => FloatCodec::boxed_estimator(),
+            CodecType::Delta => DeltaCodec::boxed_estimator(),
+            #[cfg(feature = "zstd")]
+            CodecType::ZstdBlock => ZstdBlockCodec::boxed_estimator(),
+            CodecType::DeltaOfDelta => DeltaOfDeltaCodec::boxed_estimator(),
+            CodecType::RunLength => RunLengthCodec::boxed_estimator(),
+        }
+    }
+
+    /// Returns the version of the layout of the codec.
+    pub fn version(&self) -> u8 {
+        match self {
+            CodecType::Bitpacked => BitpackedCodec::VERSION,
+            CodecType::Linear => LinearCodec::VERSION,
+            CodecType::BlockwiseLinear => BlockwiseLinearCodec::VERSION,
+            CodecType::BlockwiseFor => BlockwiseForCodec::VERSION,
+            CodecType::Dictionary => DictionaryCodec::VERSION,
+            CodecType::Sparse => SparseCodec::VERSION,
+            CodecType::Float => FloatCodec::VERSION,
+            CodecType::Delta => DeltaCodec::VERSION,
+            #[cfg(feature = "zstd")]
+            CodecType::ZstdBlock => ZstdBlockCodec::VERSION,
+            CodecType::DeltaOfDelta => DeltaOfDeltaCodec::VERSION,
+            CodecType::RunLength => RunLengthCodec::VERSION,
         }
     }
 }
 
+/// Returns the version of the layout of the built-in or registered codec identified by `code`.
+fn codec_version(code: u8) -> Result<u8, UnsupportedCodecError> {
+    if let Some(codec_type) = CodecType::try_from_code(code) {
+        return Ok(codec_type.version());
+    }
+    let registered_codec =
+        registry::registered_codec(code).ok_or(UnsupportedCodecError::UnknownCodec { code })?;
+    Ok(registered_codec.version)
+}
+
+fn new_estimator(code: u8) -> Option<Box<dyn ColumnCodecEstimator>> {
+    if let Some(codec_type) = CodecType::try_from_code(code) {
+        return Some(codec_type.estimator());
+    }
+    let registered_codec = registry::registered_codec(code)?;
+    Some((registered_codec.estimator)())
+}
+
 /// Serializes a given column of u64-mapped values.
+///
+/// The codec is picked among `codec_types` and the codecs registered with [`register_codec`],
+/// as the one resulting in the smallest column. Past 65,536 rows, the size of the column is
+/// estimated on a sample of its values, along with the [`ValueDistribution`] of all of its
+/// values.
+///
+/// The [`BlockStats`] of the column are appended after the serialized values, along with its
+/// [`CardinalitySketch`], followed by a footer recording the codec, the version of its layout, the
+/// number of rows and a checksum of the column.
 pub fn serialize_u64_based_column_values<T: MonotonicallyMappableToU64>(
     vals: &dyn Iterable<T>,
     codec_types: &[CodecType],
     wrt: &mut dyn Write,
 ) -> io::Result<()> {
-    let mut stats_collector = StatsCollector::default();
-    let mut estimators: Vec<(CodecType, Box<dyn ColumnCodecEstimator>)> =
-        Vec::with_capacity(codec_types.len());
-    for &codec_type in codec_types {
-        estimators.push((codec_type, codec_type.estimator()));
+    serialize_u64_based_column_values_with_estimation(
+        vals,
+        CompetingCodecs::new(codec_types),
+        CodecEstimation::Sampled,
+        wrt,
+    )
+}
+
+/// The codecs competing for a column of u64-mapped values.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct CompetingCodecs<'a> {
+    codec_types: &'a [CodecType],
+    with_registered_codecs: bool,
+}
+
+impl<'a> CompetingCodecs<'a> {
+    /// `codec_types` along with the codecs registered with [`register_codec`].
+    pub fn new(codec_types: &'a [CodecType]) -> CompetingCodecs<'a> {
+        CompetingCodecs {
+            codec_types,
+            with_registered_codecs: true,
+        }
     }
-    for val in vals.boxed_iter() {
+
+    /// Only `codec_type`, pinned for the column: the registered codecs do not compete.
+    pub fn pinned(codec_type: &'a CodecType) -> CompetingCodecs<'a> {
+        CompetingCodecs {
+            codec_types: std::slice::from_ref(codec_type),
+            with_registered_codecs: false,
+        }
+    }
+}
+
+/// How the size of a column is estimated for each of the codecs competing for it.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(crate) enum CodecEstimation {
+    /// Past 65,536 rows, the codecs are estimated on a sample of the values.
+    Sampled,
+    /// The codecs are estimated on all of the values, however many rows the column has.
+    Exhaustive,
+}
+
+/// Same as [`serialize_u64_based_column_values`], estimating the codecs as per `estimation`.
+pub(crate) fn serialize_u64_based_column_values_with_estimation<T: MonotonicallyMappableToU64>(
+    vals: &dyn Iterable<T>,
+    codecs: CompetingCodecs,
+    estimation: CodecEstimation,
+    wrt: &mut dyn Write,
+) -> io::Result<()> {
+    serialize_u64_based_column_values_impl(vals, codecs, estimation, true, wrt)
+}
+
+/// Same as [`serialize_u64_based_column_values`], with an empty [`CardinalitySketch`].
+///
+/// This is meant for values whose number of distinct values is of no interest, like the row
+/// offsets of a multivalued index, which would otherwise always fill the sketch.
+pub(crate) fn serialize_u64_based_column_values_without_cardinality_sketch<
+    T: MonotonicallyMappableToU64,
+>(
+    vals: &dyn Iterable<T>,
+    codec_types: &[CodecType],
+    wrt: &mut dyn Write,
+) -> io::Result<()> {
+    serialize_u64_based_column_values_impl(
+        vals,
+        CompetingCodecs::new(codec_types),
+        CodecEstimation::Sampled,
+        false,
+        wrt,
+    )
+}
+
+fn serialize_u64_based_column_values_impl<T: MonotonicallyMappableToU64>(
+    vals: &dyn Iterable<T>,
+    codecs: CompetingCodecs,
+    estimation: CodecEstimation,
+    with_cardinality_sketch: bool,
+    wrt: &mut dyn Write,
+) -> io::Result<()> {
+    let mut wrt = CodecFooterWriter::wrap(wrt);
+    let mut stats_collector = StatsCollector::with_distribution();
+    let mut sample_stats_collector = StatsCollector::default();
+    let mut block_stats_collector = BlockStatsCollector::default();
+    let mut cardinality_sketch_collector = CardinalitySketchCollector::default();
+    let registered_codecs = if codecs.with_registered_codecs {
+        registry::registered_codecs()
+    } else {
+        Vec::new()
+    };
+    let mut estimators: Vec<(u8, Box<dyn ColumnCodecEstimator>)> =
+        Vec::with_capacity(codecs.codec_types.len() + registered_codecs.len());
+    for &codec_type in codecs.codec_types {
+        estimators.push((codec_type.to_code(), codec_type.estimator()));
+    }
+    for registered_codec in registered_codecs {
+        estimators.push((registered_codec.code, (registered_codec.estimator)()));
+    }
+    let mut num_skipped_rows: RowId = 0;
+    for (row_id, val) in vals.boxed_iter().enumerate() {
         let val_u64 = val.to_u64();
         stats_collector.collect(val_u64);
+        block_stats_collector.collect(val_u64);
+        if with_cardinality_sketch {
+            cardinality_sketch_collector.collect(val_u64);
+        }
+        if estimation == CodecEstimation::Sampled && !is_sampled(row_id as RowId) {
+            num_skipped_rows += 1;
+            continue;
+        }
+        if num_skipped_rows > 0 {
+            for (_, estimator) in &mut estimators {
+                estimator.skip(num_skipped_rows);
+            }
+            num_skipped_rows = 0;
+        }
+        sample_stats_collector.collect(val_u64);
         for (_, estimator) in &mut estimators {
             estimator.collect(val_u64);
         }
@@ -166,38 +503,176 @@ pub fn serialize_u64_based_column_values<T: MonotonicallyMappableToU64>(
         estimator.finalize();
     }
     let stats = stats_collector.stats();
-    let (_, best_codec, best_codec_estimator) = estimators
+    // The codecs estimated on the sample can still rely on the distribution of the whole column.
+    let sample_stats = ColumnStats {
+        distribution: stats.distribution.clone(),
+        ..sample_stats_collector.stats()
+    };
+    let code = if sample_stats.num_rows < stats.num_rows {
+        serialize_with_sampled_estimates(vals, estimators, &stats, &sample_stats, &mut wrt)?
+    } else {
+        let (_, best_codec, best_codec_estimator) = estimators
+            .into_iter()
+            .flat_map(|(code, estimator)| {
+                let num_bytes = estimator.estimate(&stats)?;
+                Some((num_bytes, code, estimator))
+            })
+            .min_by_key(|(num_bytes, _, _)| *num_bytes)
+            .ok_or_else(|| {
+                io::Error::new(io::ErrorKind::InvalidData, "No available applicable codec.")
+            })?;
+        best_codec.serialize(&mut wrt)?;
+        best_codec_estimator.serialize(
+            &stats,
+            &mut vals.boxed_iter().map(MonotonicallyMappableToU64::to_u64),
+            &mut wrt,
+        )?;
+        best_codec
+    };
+    block_stats_collector.serialize(&mut wrt)?;
+    cardinality_sketch_collector.serialize(&mut wrt)?;
+    wrt.finish(code, codec_version(code)?, stats.num_rows)
+}
+
+/// Serializes the column with the codec with the smallest estimate extrapolated from the sample,
+/// if it applies to the whole column. Otherwise, falls back to the next codec.
+///
+/// Returns the code of the codec the column was serialized with.
+fn serialize_with_sampled_estimates<T: MonotonicallyMappableToU64>(
+    vals: &dyn Iterable<T>,
+    sample_estimators: Vec<(u8, Box<dyn ColumnCodecEstimator>)>,
+    stats: &ColumnStats,
+    sample_stats: &ColumnStats,
+    wrt: &mut dyn Write,
+) -> io::Result<u8> {
+    let mut candidates: Vec<(u64, u8)> = sample_estimators
         .into_iter()
-        .flat_map(|(codec_type, estimator)| {
-            let num_bytes = estimator.estimate(&stats)?;
-            Some((num_bytes, codec_type, estimator))
+        .flat_map(|(code, estimator)| {
+            let sample_num_bytes = estimator.estimate(sample_stats)?;
+            let num_bytes =
+                sample_num_bytes as u128 * stats.num_rows as u128 / sample_stats.num_rows as u128;
+            Some((num_bytes as u64, code))
         })
-        .min_by_key(|(num_bytes, _, _)| *num_bytes)
-        .ok_or_else(|| {
-            io::Error::new(io::ErrorKind::InvalidData, "No available applicable codec.")
-        })?;
-    best_codec.to_code().serialize(wrt)?;
-    best_codec_estimator.serialize(
-        &stats,
-        &mut vals.boxed_iter().map(MonotonicallyMappableToU64::to_u64),
-        wrt,
-    )?;
-    Ok(())
+        .collect();
+    // The sort is stable: ties are still won by the first codec.
+    candidates.sort_by_key(|(num_bytes, _)| *num_bytes);
+    for (_, code) in candidates {
+        let Some(mut estimator) = new_estimator(code) else {
+            continue;
+        };
+        for val in vals.boxed_iter() {
+            estimator.collect(val.to_u64());
+        }
+        estimator.finalize();
+        if estimator.estimate(stats).is_none() {
+            continue;
+        }
+        code.serialize(wrt)?;
+        estimator.serialize(
+            stats,
+            &mut vals.boxed_iter().map(MonotonicallyMappableToU64::to_u64),
+            wrt,
+        )?;
+        return Ok(code);
+    }
+    Err(io::Error::new(
+        io::ErrorKind::InvalidData,
+        "No available applicable codec.",
+    ))
 }
 
-/// Load u64-based column values.
-///
-/// This method first identifies the codec off the first byte.
+/// Load u64-based column values serialized with the [`CURRENT_VERSION`] of the format.
 pub fn load_u64_based_column_values<T: MonotonicallyMappableToU64>(
+    bytes: OwnedBytes,
+) -> io::Result<Arc<dyn ColumnValues<T>>> {
+    load_u64_based_column_values_with_version(bytes, CURRENT_VERSION)
+}
+
+/// Load u64-based column values serialized with the given version of the format.
+///
+/// This method first identifies the codec off the first byte. From [`Version::V3`] on, the
+/// footer, the [`CardinalitySketch`] and the [`BlockStats`] are then split off the end of the
+/// column. The column is
+/// refused with an [`UnsupportedCodecError`] if its codec is unknown, or if its layout is more
+/// recent than the one of the codec of this reader.
+///
+/// The checksum of the footer is not verified, as it would require reading the whole column.
+/// See [`validate_u64_based_column_values_checksum`].
+pub fn load_u64_based_column_values_with_version<T: MonotonicallyMappableToU64>(
     mut bytes: OwnedBytes,
+    format_version: Version,
 ) -> io::Result<Arc<dyn ColumnValues<T>>> {
-    let codec_type: CodecType = bytes
+    let code: u8 = bytes
         .first()
         .copied()
-        .and_then(CodecType::try_from_code)
         .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "Failed to read codec type"))?;
     bytes.advance(1);
-    codec_type.load(bytes)
+    let (bytes, block_stats, cardinality_sketch, num_rows_opt) = match format_version {
+        Version::V1 | Version::V2 => (bytes, OwnedBytes::empty(), None, None),
+        Version::V3 => {
+            let (bytes, footer) = CodecFooter::split_off(bytes)?;
+            if footer.codec_code != code {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!(
+                        "Codec type {code} does not match the codec type {} of the footer",
+                        footer.codec_code
+                    ),
+                ));
+            }
+            let supported_version = codec_version(code)?;
+            if footer.codec_version > supported_version {
+                return Err(UnsupportedCodecError::UnsupportedVersion {
+                    code,
+                    version: footer.codec_version,
+                    supported_version,
+                }
+                .into());
+            }
+            let (bytes, cardinality_sketch_hashes) = split_cardinality_sketch(bytes)?;
+            let cardinality_sketch =
+                CardinalitySketch::open(cardinality_sketch_hashes, footer.num_rows as u64)?;
+            let (bytes, block_stats) = split_block_stats(bytes)?;
+            (
+                bytes,
+                block_stats,
+                Some(cardinality_sketch),
+                Some(footer.num_rows),
+            )
+        }
+    };
+    let column: Arc<dyn ColumnValues<T>> = if let Some(codec_type) = CodecType::try_from_code(code)
+    {
+        codec_type.load(bytes, block_stats, cardinality_sketch)?
+    } else {
+        let registered_codec =
+            registry::registered_codec(code).ok_or(UnsupportedCodecError::UnknownCodec { code })?;
+        let reader = (registered_codec.load)(bytes)?;
+        map_to_typed_column(BlockStatsColumnValues::new(
+            reader,
+            block_stats,
+            cardinality_sketch,
+        )?)
+    };
+    if let Some(num_rows) = num_rows_opt {
+        if column.num_vals() != num_rows {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!(
+                    "The column has {} rows, while its footer records {num_rows} rows",
+                    column.num_vals()
+                ),
+            ));
+        }
+    }
+    Ok(column)
+}
+
+/// Returns true if the checksum recorded in the footer of u64-based column values, serialized
+/// with [`Version::V3`] or later, matches their content.
+pub fn validate_u64_based_column_values_checksum(bytes: OwnedBytes) -> io::Result<bool> {
+    let (bytes, footer) = CodecFooter::split_off(bytes)?;
+    Ok(footer.is_checksum_valid(bytes.as_slice()))
 }
 
 /// Helper function to serialize a column (autodetect from all codecs) and then open it
@@ -0,0 +1,169 @@
+use std::io;
+use std::sync::{Arc, RwLock};
+
+use common::OwnedBytes;
+
+use crate::column_values::u64_based::{ColumnCodec, ColumnCodecEstimator};
+use crate::ColumnValues;
+
+/// Codes below this value are reserved to the codecs of [`CodecType`](super::CodecType).
+pub const FIRST_REGISTERED_CODEC_CODE: u8 = 128;
+
+#[derive(Clone, Copy)]
+pub(crate) struct RegisteredCodec {
+    pub(crate) code: u8,
+    pub(crate) version: u8,
+    pub(crate) estimator: fn() -> Box<dyn ColumnCodecEstimator>,
+    pub(crate) load: fn(OwnedBytes) -> io::Result<Arc<dyn ColumnValues>>,
+}
+
+static REGISTERED_CODECS: RwLock<Vec<RegisteredCodec>> = RwLock::new(Vec::new());
+
+fn load_registered_codec<C: ColumnCodec>(bytes: OwnedBytes) -> io::Result<Arc<dyn ColumnValues>> {
+    Ok(Arc::new(C::load(bytes)?))
+}
+
+/// Registers a codec implemented outside of this crate, identified by `code` in the serialized
+/// columns.
+///
+/// Once registered, the codec competes with the requested codecs whenever u64-based column
+/// values are serialized, and columns serialized with it can be loaded. The registration is
+/// process-wide, and codecs must be registered before opening the columns using them.
+///
+/// Returns an error if `code` is below [`FIRST_REGISTERED_CODEC_CODE`], or if a codec is
+/// already registered with this code.
+pub fn register_codec<C: ColumnCodec>(code: u8) -> io::Result<()> {
+    if code < FIRST_REGISTERED_CODEC_CODE {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("Codec code {code} is reserved to the built-in codecs."),
+        ));
+    }
+    let mut registered_codecs = REGISTERED_CODECS.write().unwrap();
+    if registered_codecs.iter().any(|codec| codec.code == code) {
+        return Err(io::Error::new(
+            io::ErrorKind::AlreadyExists,
+            format!("A codec is already registered with code {code}."),
+        ));
+    }
+    registered_codecs.push(RegisteredCodec {
+        code,
+        version: C::VERSION,
+        estimator: C::boxed_estimator,
+        load: load_registered_codec::<C>,
+    });
+    Ok(())
+}
+
+pub(crate) fn registered_codecs() -> Vec<RegisteredCodec> {
+    REGISTERED_CODECS.read().unwrap().clone()
+}
+
+pub(crate) fn registered_codec(code: u8) -> Option<RegisteredCodec> {
+    REGISTERED_CODECS
+        .read()
+        .unwrap()
+        .iter()
+        .find(|codec| codec.code == code)
+        .copied()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use common::{BinarySerializable, VInt};
+
+    use super::*;
+    use crate::column_values::u64_based::{
+        load_u64_based_column_values, serialize_u64_based_column_values,
+        serialize_u64_based_column_values_with_estimation, CodecEstimation, CodecType, ColumnStats,
+        CompetingCodecs,
+    };
+    use crate::column_values::{VecColumn, ALL_U64_CODEC_TYPES};
+
+    // Registrations are process-wide: the test codec only applies to a value that no other
+    // test uses.
+    const MAGIC_VALUE: u64 = 0xDEAD_BEEF_0BAD_CAFE;
+
+    #[derive(Default)]
+    struct MagicEstimator {
+        only_magic_values: bool,
+        num_vals: u64,
+    }
+
+    impl ColumnCodecEstimator for MagicEstimator {
+        fn collect(&mut self, value: u64) {
+            if self.num_vals == 0 {
+                self.only_magic_values = true;
+            }
+            self.only_magic_values &= value == MAGIC_VALUE;
+            self.num_vals += 1;
+        }
+
+        fn estimate(&self, _stats: &ColumnStats) -> Option<u64> {
+            if !self.only_magic_values {
+                return None;
+            }
+            Some(VInt(self.num_vals).num_bytes())
+        }
+
+        fn serialize(
+            &self,
+            _stats: &ColumnStats,
+            _vals: &mut dyn Iterator<Item = u64>,
+            wrt: &mut dyn Write,
+        ) -> io::Result<()> {
+            VInt(self.num_vals).serialize(wrt)
+        }
+    }
+
+    struct MagicCodec;
+
+    impl ColumnCodec for MagicCodec {
+        type ColumnValues = VecColumn;
+        type Estimator = MagicEstimator;
+
+        fn load(mut bytes: OwnedBytes) -> io::Result<VecColumn> {
+            let num_vals = VInt::deserialize(&mut bytes)?.0 as usize;
+            Ok(VecColumn::from(vec![MAGIC_VALUE; num_vals]))
+        }
+    }
+
+    #[test]
+    fn test_register_codec() -> io::Result<()> {
+        assert!(register_codec::<MagicCodec>(3).is_err());
+        register_codec::<MagicCodec>(200)?;
+        assert!(register_codec::<MagicCodec>(200).is_err());
+
+        let mut buffer = Vec::new();
+        serialize_u64_based_column_values(
+            &&[MAGIC_VALUE; 1_000][..],
+            &ALL_U64_CODEC_TYPES,
+            &mut buffer,
+        )?;
+        assert_eq!(buffer[0], 200);
+        // 3 bytes for the codec, 19 bytes for the stats of the 2 blocks of the column, 12 bytes
+        // for the cardinality sketch of its single distinct value and 10 bytes of codec footer.
+        assert_eq!(buffer.len(), 3 + 19 + 12 + 10);
+        let column = load_u64_based_column_values::<u64>(OwnedBytes::new(buffer))?;
+        assert_eq!(column.num_vals(), 1_000);
+        assert_eq!(column.get_val(999), MAGIC_VALUE);
+
+        // The registered codec does not apply to other values.
+        let mut buffer = Vec::new();
+        serialize_u64_based_column_values(&&[1u64, 2u64][..], &ALL_U64_CODEC_TYPES, &mut buffer)?;
+        assert!(buffer[0] < FIRST_REGISTERED_CODEC_CODE);
+
+        // Nor to the columns a codec is pinned for.
+        let mut buffer = Vec::new();
+        serialize_u64_based_column_values_with_estimation(
+            &&[MAGIC_VALUE; 1_000][..],
+            CompetingCodecs::pinned(&CodecType::Bitpacked),
+            CodecEstimation::Sampled,
+            &mut buffer,
+        )?;
+        assert_eq!(buffer[0], CodecType::Bitpacked.to_code());
+        Ok(())
+    }
+}
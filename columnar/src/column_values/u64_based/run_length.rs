@@ -0,0 +1,260 @@
+use std::io::{self, Write};
+use std::ops::{Range, RangeInclusive};
+
+use common::{BinarySerializable, OwnedBytes, VInt};
+use fastdivide::DividerU64;
+use tantivy_bitpacker::{compute_num_bits, BitPacker, BitUnpacker};
+
+use crate::column_values::u64_based::{
+    BlockwiseIter, ColumnCodec, ColumnCodecEstimator, ColumnStats,
+};
+use crate::{ColumnValues, RowId};
+
+/// Reader of a column serialized with the [`RunLengthCodec`].
+#[derive(Clone)]
+pub struct RunLengthReader {
+    stats: ColumnStats,
+    num_runs: u32,
+    run_value_unpacker: BitUnpacker,
+    run_values: OwnedBytes,
+    run_end_unpacker: BitUnpacker,
+    run_ends: OwnedBytes,
+}
+
+impl RunLengthReader {
+    #[inline]
+    fn run_val(&self, run_id: u32) -> u64 {
+        let normalized_val = self.run_value_unpacker.get(run_id, &self.run_values);
+        self.stats.min_value + self.stats.gcd.get() * normalized_val
+    }
+
+    /// Returns the first row after the run `run_id`.
+    #[inline]
+    fn run_end(&self, run_id: u32) -> RowId {
+        self.run_end_unpacker.get(run_id, &self.run_ends) as RowId
+    }
+
+    /// Returns the run containing the row `row_id`, by binary search over the ends of the runs.
+    fn find_run(&self, row_id: RowId) -> u32 {
+        let (mut start, mut end) = (0u32, self.num_runs);
+        while start < end {
+            let mid = start + (end - start) / 2;
+            if self.run_end(mid) <= row_id {
+                start = mid + 1;
+            } else {
+                end = mid;
+            }
+        }
+        start
+    }
+}
+
+impl ColumnValues for RunLengthReader {
+    #[inline]
+    fn get_val(&self, idx: u32) -> u64 {
+        self.run_val(self.find_run(idx))
+    }
+
+    fn get_range(&self, start: u64, output: &mut [u64]) {
+        if output.is_empty() {
+            return;
+        }
+        let mut row_id = start as RowId;
+        let mut run_id = self.find_run(row_id);
+        let mut output = output;
+        while !output.is_empty() {
+            let len = ((self.run_end(run_id) - row_id) as usize).min(output.len());
+            let (run_output, remaining_output) = output.split_at_mut(len);
+            run_output.fill(self.run_val(run_id));
+            output = remaining_output;
+            row_id += len as RowId;
+            run_id += 1;
+        }
+    }
+
+    fn iter(&self) -> Box<dyn Iterator<Item = u64> + '_> {
+        Box::new(BlockwiseIter::new(self))
+    }
+
+    fn get_row_ids_for_value_range(
+        &self,
+        range: RangeInclusive<u64>,
+        row_id_range: Range<u32>,
+        positions: &mut Vec<u32>,
+    ) {
+        positions.clear();
+        let row_id_range = row_id_range.start..row_id_range.end.min(self.num_vals());
+        if row_id_range.is_empty() {
+            return;
+        }
+        let mut row_id = row_id_range.start;
+        let mut run_id = self.find_run(row_id);
+        while row_id < row_id_range.end {
+            let run_end = self.run_end(run_id).min(row_id_range.end);
+            if range.contains(&self.run_val(run_id)) {
+                positions.extend(row_id..run_end);
+            }
+            row_id = run_end;
+            run_id += 1;
+        }
+    }
+
+    #[inline]
+    fn min_value(&self) -> u64 {
+        self.stats.min_value
+    }
+
+    #[inline]
+    fn max_value(&self) -> u64 {
+        self.stats.max_value
+    }
+
+    #[inline]
+    fn num_vals(&self) -> RowId {
+        self.stats.num_rows
+    }
+}
+
+/// The size of the column only depends on its stats, including its number of runs.
+#[derive(Default)]
+pub struct RunLengthEstimator;
+
+impl ColumnCodecEstimator for RunLengthEstimator {
+    fn collect(&mut self, _value: u64) {}
+
+    fn estimate(&self, stats: &ColumnStats) -> Option<u64> {
+        let run_value_num_bits = compute_num_bits(stats.amplitude() / stats.gcd) as u64;
+        let run_end_num_bits = compute_num_bits(stats.num_rows as u64) as u64;
+        let num_runs = stats.num_runs as u64;
+        Some(
+            stats.num_bytes()
+                + VInt(num_runs).num_bytes()
+                + (num_runs * run_value_num_bits + 7) / 8
+                + (num_runs * run_end_num_bits + 7) / 8,
+        )
+    }
+
+    fn serialize(
+        &self,
+        stats: &ColumnStats,
+        vals: &mut dyn Iterator<Item = u64>,
+        wrt: &mut dyn Write,
+    ) -> io::Result<()> {
+        stats.serialize(wrt)?;
+        VInt(stats.num_runs as u64).serialize(wrt)?;
+        let run_value_num_bits = compute_num_bits(stats.amplitude() / stats.gcd);
+        let divider = DividerU64::divide_by(stats.gcd.get());
+        // The values of the runs are written as they come, the ends of the runs are appended
+        // afterwards.
+        let mut run_ends: Vec<RowId> = Vec::with_capacity(stats.num_runs as usize);
+        let mut bit_packer = BitPacker::new();
+        let mut previous_val_opt: Option<u64> = None;
+        for (row_id, val) in vals.enumerate() {
+            if previous_val_opt.replace(val) == Some(val) {
+                continue;
+            }
+            if row_id > 0 {
+                run_ends.push(row_id as RowId);
+            }
+            bit_packer.write(
+                divider.divide(val - stats.min_value),
+                run_value_num_bits,
+                wrt,
+            )?;
+        }
+        if stats.num_rows > 0 {
+            run_ends.push(stats.num_rows);
+        }
+        bit_packer.close(wrt)?;
+        let run_end_num_bits = compute_num_bits(stats.num_rows as u64);
+        for run_end in run_ends {
+            bit_packer.write(run_end as u64, run_end_num_bits, wrt)?;
+        }
+        bit_packer.close(wrt)?;
+        Ok(())
+    }
+}
+
+/// Run length codec, for columns with long runs of identical values, like the columns an index
+/// is sorted by.
+///
+/// The value and the end of each run are bitpacked. Accessing a value requires a binary search
+/// over the ends of the runs.
+pub struct RunLengthCodec;
+
+impl ColumnCodec for RunLengthCodec {
+    type ColumnValues = RunLengthReader;
+    type Estimator = RunLengthEstimator;
+
+    fn load(mut data: OwnedBytes) -> io::Result<Self::ColumnValues> {
+        let stats = ColumnStats::deserialize(&mut data)?;
+        let num_runs = VInt::deserialize(&mut data)?.0 as u32;
+        let run_value_num_bits = compute_num_bits(stats.amplitude() / stats.gcd);
+        let run_values_num_bytes = (num_runs as usize * run_value_num_bits as usize + 7) / 8;
+        let (run_values, run_ends) = data.split(run_values_num_bytes);
+        Ok(RunLengthReader {
+            num_runs,
+            run_value_unpacker: BitUnpacker::new(run_value_num_bits),
+            run_values,
+            run_end_unpacker: BitUnpacker::new(compute_num_bits(stats.num_rows as u64)),
+            run_ends,
+            stats,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::column_values::u64_based::tests::create_and_validate;
+    use crate::column_values::u64_based::{
+        serialize_and_load_u64_based_column_values, BitpackedCodec, CodecType,
+    };
+
+    #[test]
+    fn test_with_codec_data_sets_simple() {
+        create_and_validate::<RunLengthCodec>(&[4, 4, 4, 12, 12, 4, 3], "simple").unwrap();
+        create_and_validate::<RunLengthCodec>(&[7], "single value").unwrap();
+        create_and_validate::<RunLengthCodec>(&[], "empty").unwrap();
+    }
+
+    #[test]
+    fn test_with_codec_data_sets() {
+        let data_sets = crate::column_values::u64_based::tests::get_codec_test_datasets();
+        for (mut data, name) in data_sets {
+            create_and_validate::<RunLengthCodec>(&data, name).unwrap();
+            data.sort();
+            create_and_validate::<RunLengthCodec>(&data, name).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_run_length_get_row_ids_for_value_range() {
+        let data: Vec<u64> = (0..1_000u64).map(|i| i / 100 * 3).collect();
+        let column =
+            serialize_and_load_u64_based_column_values::<u64>(&&data[..], &[CodecType::RunLength]);
+        let mut positions = Vec::new();
+        column.get_row_ids_for_value_range(6..=9, 0..1_000, &mut positions);
+        assert_eq!(positions, (200..400).collect::<Vec<u32>>());
+        column.get_row_ids_for_value_range(6..=9, 250..320, &mut positions);
+        assert_eq!(positions, (250..320).collect::<Vec<u32>>());
+        column.get_row_ids_for_value_range(7..=8, 0..1_000, &mut positions);
+        assert!(positions.is_empty());
+    }
+
+    #[test]
+    fn test_run_length_sorted_tenant_ids() {
+        let data: Vec<u64> = (0..100_000u64)
+            .map(|i| 1_000 + i * i / 10_000_000)
+            .collect();
+        let (_, run_length_compression) =
+            create_and_validate::<RunLengthCodec>(&data, "tenant ids").unwrap();
+        let (_, bitpacked_compression) =
+            create_and_validate::<BitpackedCodec>(&data, "tenant ids").unwrap();
+        assert!(run_length_compression < 0.01);
+        assert!(run_length_compression < bitpacked_compression);
+        let column =
+            serialize_and_load_u64_based_column_values::<u64>(&&data[..], &[CodecType::RunLength]);
+        assert_eq!(column.get_val(99_999), data[99_999]);
+    }
+}
@@ -0,0 +1,233 @@
+use std::io::{self, Write};
+
+use common::{BinarySerializable, OwnedBytes, VInt};
+use fastdivide::DividerU64;
+use tantivy_bitpacker::{compute_num_bits, BitPacker, BitUnpacker};
+
+use crate::column_index::{open_optional_index, serialize_optional_index, OptionalIndex, Set};
+use crate::column_values::u64_based::{ColumnCodec, ColumnCodecEstimator, ColumnStats};
+use crate::{ColumnValues, RowId};
+
+/// The sparse codec is only applicable if there is at most one exception every
+/// `MIN_ROWS_PER_EXCEPTION` rows.
+///
+/// Past this density, the rows of the exceptions may get stored in dense blocks and the
+/// estimation is not accurate anymore.
+const MIN_ROWS_PER_EXCEPTION: u32 = 16;
+
+/// Number of rows of the blocks of the [`OptionalIndex`].
+const ROWS_PER_INDEX_BLOCK: u32 = 1 << 16;
+
+/// Reader of a column serialized with the [`SparseCodec`].
+#[derive(Clone)]
+pub struct SparseReader {
+    default_value: u64,
+    exceptions: OptionalIndex,
+    data: OwnedBytes,
+    bit_unpacker: BitUnpacker,
+    stats: ColumnStats,
+}
+
+impl ColumnValues for SparseReader {
+    #[inline]
+    fn get_val(&self, idx: u32) -> u64 {
+        if let Some(rank) = self.exceptions.rank_if_exists(idx) {
+            self.stats.min_value + self.stats.gcd.get() * self.bit_unpacker.get(rank, &self.data)
+        } else {
+            self.default_value
+        }
+    }
+
+    #[inline]
+    fn min_value(&self) -> u64 {
+        self.stats.min_value
+    }
+
+    #[inline]
+    fn max_value(&self) -> u64 {
+        self.stats.max_value
+    }
+
+    #[inline]
+    fn num_vals(&self) -> RowId {
+        self.stats.num_rows
+    }
+}
+
+fn num_bits(stats: &ColumnStats) -> u8 {
+    compute_num_bits(stats.amplitude() / stats.gcd)
+}
+
+/// Counts the occurrences of the minimum and of the maximum value of the column: the most
+/// frequent of the two is the default value, and the other rows are exceptions.
+#[derive(Default)]
+pub struct SparseEstimator {
+    min_opt: Option<(u64, RowId)>,
+    max_opt: Option<(u64, RowId)>,
+    num_rows: RowId,
+}
+
+impl SparseEstimator {
+    /// Returns the default value and its number of occurrences.
+    fn default_value(&self) -> (u64, RowId) {
+        match (self.min_opt, self.max_opt) {
+            (Some(min), Some(max)) if max.1 > min.1 => max,
+            (Some(min), _) => min,
+            _ => (0u64, 0),
+        }
+    }
+}
+
+fn count_extremum(extremum_opt: &mut Option<(u64, RowId)>, value: u64, is_better: bool) {
+    match extremum_opt {
+        Some((extremum, count)) if *extremum == value => *count += 1,
+        Some(_) if !is_better => {}
+        _ => *extremum_opt = Some((value, 1)),
+    }
+}
+
+impl ColumnCodecEstimator for SparseEstimator {
+    fn collect(&mut self, value: u64) {
+        let is_min = self.min_opt.map(|(min, _)| value < min).unwrap_or(true);
+        count_extremum(&mut self.min_opt, value, is_min);
+        let is_max = self.max_opt.map(|(max, _)| value > max).unwrap_or(true);
+        count_extremum(&mut self.max_opt, value, is_max);
+        self.num_rows += 1;
+    }
+
+    fn estimate(&self, stats: &ColumnStats) -> Option<u64> {
+        let (default_value, num_default_values) = self.default_value();
+        let num_exceptions = self.num_rows - num_default_values;
+        if num_exceptions as u64 * MIN_ROWS_PER_EXCEPTION as u64 > self.num_rows as u64 {
+            return None;
+        }
+        let num_index_blocks = (self.num_rows + ROWS_PER_INDEX_BLOCK - 1) / ROWS_PER_INDEX_BLOCK;
+        // Exceptions are stored as u16 in sparse blocks, and each non-empty block has 4 bytes
+        // of metadata.
+        let index_num_bytes = VInt(self.num_rows as u64).num_bytes()
+            + 2
+            + 4 * num_index_blocks.min(num_exceptions) as u64
+            + 2 * num_exceptions as u64;
+        let values_num_bytes = (num_exceptions as u64 * num_bits(stats) as u64 + 7) / 8;
+        Some(
+            stats.num_bytes()
+                + VInt((default_value - stats.min_value) / stats.gcd).num_bytes()
+                + VInt(index_num_bytes).num_bytes()
+                + index_num_bytes
+                + values_num_bytes,
+        )
+    }
+
+    fn serialize(
+        &self,
+        stats: &ColumnStats,
+        vals: &mut dyn Iterator<Item = u64>,
+        wrt: &mut dyn Write,
+    ) -> io::Result<()> {
+        stats.serialize(wrt)?;
+        let (default_value, _) = self.default_value();
+        let divider = DividerU64::divide_by(stats.gcd.get());
+        VInt(divider.divide(default_value - stats.min_value)).serialize(wrt)?;
+        let mut exception_rows: Vec<RowId> = Vec::new();
+        let mut exception_vals: Vec<u64> = Vec::new();
+        for (row_id, val) in vals.enumerate() {
+            if val != default_value {
+                exception_rows.push(row_id as RowId);
+                exception_vals.push(val);
+            }
+        }
+        let mut index_buffer = Vec::new();
+        serialize_optional_index(&&exception_rows[..], stats.num_rows, &mut index_buffer)?;
+        VInt(index_buffer.len() as u64).serialize(wrt)?;
+        wrt.write_all(&index_buffer)?;
+        let num_bits = num_bits(stats);
+        let mut bit_packer = BitPacker::new();
+        for val in exception_vals {
+            bit_packer.write(divider.divide(val - stats.min_value), num_bits, wrt)?;
+        }
+        bit_packer.close(wrt)?;
+        Ok(())
+    }
+}
+
+/// Sparse codec, for columns where most of the rows hold the same value, either the minimum
+/// or the maximum value of the column.
+///
+/// Only the other values, called exceptions, are stored: their rows in an [`OptionalIndex`],
+/// and their values bitpacked.
+pub struct SparseCodec;
+
+impl ColumnCodec for SparseCodec {
+    type ColumnValues = SparseReader;
+    type Estimator = SparseEstimator;
+
+    fn load(mut data: OwnedBytes) -> io::Result<Self::ColumnValues> {
+        let stats = ColumnStats::deserialize(&mut data)?;
+        let default_value = stats.min_value + VInt::deserialize(&mut data)?.0 * stats.gcd.get();
+        let index_num_bytes = VInt::deserialize(&mut data)?.0 as usize;
+        let (index_bytes, data) = data.split(index_num_bytes);
+        let exceptions = open_optional_index(index_bytes)?;
+        let bit_unpacker = BitUnpacker::new(num_bits(&stats));
+        Ok(SparseReader {
+            default_value,
+            exceptions,
+            data,
+            bit_unpacker,
+            stats,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::column_values::u64_based::tests::create_and_validate;
+    use crate::column_values::u64_based::BitpackedCodec;
+
+    #[test]
+    fn test_with_codec_data_sets_simple() {
+        create_and_validate::<SparseCodec>(&[0; 20], "zeros");
+        let mut data = vec![0u64; 100];
+        data[17] = 1_000;
+        create_and_validate::<SparseCodec>(&data, "one exception");
+        data.reverse();
+        create_and_validate::<SparseCodec>(&data, "one exception");
+    }
+
+    #[test]
+    fn test_with_codec_data_sets() {
+        let data_sets = crate::column_values::u64_based::tests::get_codec_test_datasets();
+        for (mut data, name) in data_sets {
+            create_and_validate::<SparseCodec>(&data, name);
+            data.reverse();
+            create_and_validate::<SparseCodec>(&data, name);
+        }
+    }
+
+    #[test]
+    fn test_sparse_default_is_max_value() {
+        let mut data = vec![u64::MAX; 1_000];
+        data[3] = 0;
+        data[500] = 7;
+        create_and_validate::<SparseCodec>(&data, "default max").unwrap();
+    }
+
+    #[test]
+    fn test_sparse_mostly_zeros() {
+        let data: Vec<u64> = (0..200_000u64)
+            .map(|i| if i % 1_000 == 0 { i * 31 } else { 0 })
+            .collect();
+        let (_, sparse_compression) =
+            create_and_validate::<SparseCodec>(&data, "mostly zeros").unwrap();
+        let (_, bitpacked_compression) =
+            create_and_validate::<BitpackedCodec>(&data, "mostly zeros").unwrap();
+        assert!(sparse_compression < 0.001);
+        assert!(sparse_compression < bitpacked_compression);
+    }
+
+    #[test]
+    fn test_sparse_not_applicable_to_dense_exceptions() {
+        let data: Vec<u64> = (0..1_000u64).map(|i| i % 4).collect();
+        assert!(create_and_validate::<SparseCodec>(&data, "dense").is_none());
+    }
+}
@@ -2,7 +2,7 @@ use std::num::NonZeroU64;
 
 use fastdivide::DividerU64;
 
-use crate::column_values::ColumnStats;
+use crate::column_values::{ColumnStats, ValueDistribution};
 use crate::RowId;
 
 /// Compute the gcd of two non null numbers.
@@ -19,6 +19,43 @@ fn compute_gcd(mut large: NonZeroU64, mut small: NonZeroU64) -> NonZeroU64 {
     }
 }
 
+/// The number of values kept to sketch the distribution of a column is between
+/// `DISTRIBUTION_SAMPLE_LEN / 2` and `DISTRIBUTION_SAMPLE_LEN`.
+const DISTRIBUTION_SAMPLE_LEN: usize = 1_024;
+
+/// Keeps evenly spaced values of a column: one value every `stride` values, doubling the
+/// stride whenever the sample is full.
+struct DistributionCollector {
+    sample: Vec<u64>,
+    stride: u64,
+    num_vals: u64,
+}
+
+impl DistributionCollector {
+    fn new() -> Self {
+        DistributionCollector {
+            sample: Vec::with_capacity(DISTRIBUTION_SAMPLE_LEN),
+            stride: 1,
+            num_vals: 0,
+        }
+    }
+
+    fn collect(&mut self, value: u64) {
+        if self.num_vals % self.stride == 0 {
+            self.sample.push(value);
+            if self.sample.len() == DISTRIBUTION_SAMPLE_LEN {
+                let mut idx = 0;
+                self.sample.retain(|_| {
+                    idx += 1;
+                    idx % 2 == 1
+                });
+                self.stride *= 2;
+            }
+        }
+        self.num_vals += 1;
+    }
+}
+
 #[derive(Default)]
 pub struct StatsCollector {
     min_max_opt: Option<(u64, u64)>,
@@ -30,9 +67,20 @@ pub struct StatsCollector {
     // seconds, only to be converted in nanoseconds).
     increment_gcd_opt: Option<(NonZeroU64, DividerU64)>,
     first_value_opt: Option<u64>,
+    last_value_opt: Option<u64>,
+    num_runs: RowId,
+    distribution_collector_opt: Option<DistributionCollector>,
 }
 
 impl StatsCollector {
+    /// Creates a collector that also gathers the [`ValueDistribution`] of the values.
+    pub fn with_distribution() -> StatsCollector {
+        StatsCollector {
+            distribution_collector_opt: Some(DistributionCollector::new()),
+            ..Default::default()
+        }
+    }
+
     pub fn stats(&self) -> ColumnStats {
         let (min_value, max_value) = self.min_max_opt.unwrap_or((0u64, 0u64));
         let increment_gcd = if let Some((increment_gcd, _)) = self.increment_gcd_opt {
@@ -44,7 +92,18 @@ impl StatsCollector {
             min_value,
             max_value,
             num_rows: self.num_rows,
+            num_runs: self.num_runs,
             gcd: increment_gcd,
+            distribution: self
+                .distribution_collector_opt
+                .as_ref()
+                .map(|distribution_collector| {
+                    ValueDistribution::new(
+                        min_value,
+                        max_value,
+                        distribution_collector.sample.clone(),
+                    )
+                }),
         }
     }
 
@@ -88,7 +147,13 @@ impl StatsCollector {
             (value, value)
         });
         self.num_rows += 1;
+        if self.last_value_opt.replace(value) != Some(value) {
+            self.num_runs += 1;
+        }
         self.update_increment_gcd(value);
+        if let Some(distribution_collector) = self.distribution_collector_opt.as_mut() {
+            distribution_collector.collect(value);
+        }
     }
 }
 
@@ -140,6 +205,31 @@ mod tests {
         assert_eq!(find_gcd([1, 10, 0, 4, 1, 7, 10].into_iter()), 1);
     }
 
+    #[test]
+    fn test_num_runs() {
+        let num_runs = |vals: &[u64]| compute_stats(vals.iter().copied()).num_runs;
+        assert_eq!(num_runs(&[]), 0);
+        assert_eq!(num_runs(&[3]), 1);
+        assert_eq!(num_runs(&[3, 3, 3]), 1);
+        assert_eq!(num_runs(&[3, 3, 5, 5, 3]), 3);
+        assert_eq!(num_runs(&[0, 1, 2]), 3);
+    }
+
+    #[test]
+    fn test_distribution() {
+        let mut stats_collector = StatsCollector::with_distribution();
+        for val in (0..100_000u64).rev() {
+            stats_collector.collect(val);
+        }
+        let distribution = stats_collector.stats().distribution.unwrap();
+        assert_eq!(distribution.amplitude(), 99_999);
+        assert!(distribution.quantile(0.0) < 500);
+        assert!(distribution.quantile(0.5).abs_diff(50_000) < 500);
+        assert!(distribution.quantile(0.99).abs_diff(99_000) < 500);
+        assert!(distribution.quantile(1.0) > 99_000);
+        assert!(StatsCollector::default().stats().distribution.is_none());
+    }
+
     #[test]
     fn test_stats() {
         assert_eq!(
@@ -148,7 +238,9 @@ mod tests {
                 gcd: NonZeroU64::new(1).unwrap(),
                 min_value: 0,
                 max_value: 0,
-                num_rows: 0
+                num_rows: 0,
+                num_runs: 0,
+                distribution: None
             }
         );
         assert_eq!(
@@ -157,7 +249,9 @@ mod tests {
                 gcd: NonZeroU64::new(1).unwrap(),
                 min_value: 0,
                 max_value: 1,
-                num_rows: 2
+                num_rows: 2,
+                num_runs: 2,
+                distribution: None
             }
         );
         assert_eq!(
@@ -166,7 +260,9 @@ mod tests {
                 gcd: NonZeroU64::new(1).unwrap(),
                 min_value: 0,
                 max_value: 1,
-                num_rows: 2
+                num_rows: 2,
+                num_runs: 2,
+                distribution: None
             }
         );
         assert_eq!(
@@ -175,7 +271,9 @@ mod tests {
                 gcd: NonZeroU64::new(10).unwrap(),
                 min_value: 10,
                 max_value: 30,
-                num_rows: 3
+                num_rows: 3,
+                num_runs: 3,
+                distribution: None
             }
         );
         assert_eq!(
@@ -184,7 +282,9 @@ mod tests {
                 gcd: NonZeroU64::new(20).unwrap(),
                 min_value: 10,
                 max_value: 50,
-                num_rows: 4
+                num_rows: 4,
+                num_runs: 4,
+                distribution: None
             }
         );
         assert_eq!(
@@ -193,7 +293,9 @@ mod tests {
                 gcd: NonZeroU64::new(10).unwrap(),
                 min_value: 0,
                 max_value: 30,
-                num_rows: 3
+                num_rows: 3,
+                num_runs: 3,
+                distribution: None
             }
         );
     }
@@ -11,7 +11,7 @@ fn test_serialize_and_load_simple() {
         &mut buffer,
     )
     .unwrap();
-    assert_eq!(buffer.len(), 7);
+    assert_eq!(buffer.len(), 18 + 4 + 3 * 8);
     let col = load_u64_based_column_values::<u64>(OwnedBytes::new(buffer)).unwrap();
     assert_eq!(col.num_vals(), 3);
     assert_eq!(col.get_val(0), 1);
@@ -119,6 +119,24 @@ pub(crate) fn create_and_validate<TColumnCodec: ColumnCodec>(
     buffer.resize(all_docs.len(), 0);
     reader.get_vals(&all_docs, &mut buffer);
     assert_eq!(vals, buffer);
+    assert_eq!(
+        reader.iter().collect::<Vec<u64>>(),
+        vals,
+        "iter in data set {name}"
+    );
+
+    for start in [0, 1, vals.len() / 2] {
+        if start > vals.len() {
+            continue;
+        }
+        buffer.resize(vals.len() - start, 0);
+        reader.get_range(start as u64, &mut buffer);
+        assert_eq!(
+            &vals[start..],
+            &buffer[..],
+            "get_range from {start} in data set {name}"
+        );
+    }
 
     if !vals.is_empty() {
         let test_rand_idx = rand::thread_rng().gen_range(0..=vals.len() - 1);
@@ -173,6 +191,37 @@ proptest! {
     fn test_proptest_small_blockwise_linear(data in proptest::collection::vec(num_strategy(), 1..10)) {
         create_and_validate::<BlockwiseLinearCodec>(&data, "proptest multilinearinterpol");
     }
+
+    #[test]
+    fn test_proptest_small_blockwise_for(data in proptest::collection::vec(num_strategy(), 1..10)) {
+        create_and_validate::<BlockwiseForCodec>(&data, "proptest blockwise for");
+    }
+
+    #[test]
+    fn test_proptest_small_dictionary(data in proptest::collection::vec(num_strategy(), 1..10)) {
+        create_and_validate::<DictionaryCodec>(&data, "proptest dictionary");
+    }
+
+    #[test]
+    fn test_proptest_small_sparse(data in proptest::collection::vec(num_strategy(), 1..10)) {
+        create_and_validate::<SparseCodec>(&data, "proptest sparse");
+    }
+
+    #[test]
+    fn test_proptest_small_float(data in proptest::collection::vec(num_strategy(), 1..10)) {
+        create_and_validate::<FloatCodec>(&data, "proptest float");
+    }
+
+    #[test]
+    #[cfg(feature = "zstd")]
+    fn test_proptest_small_zstd_block(data in proptest::collection::vec(num_strategy(), 1..10)) {
+        create_and_validate::<ZstdBlockCodec>(&data, "proptest zstd block");
+    }
+
+    #[test]
+    fn test_proptest_small_run_length(data in proptest::collection::vec(num_strategy(), 1..10)) {
+        create_and_validate::<RunLengthCodec>(&data, "proptest run length");
+    }
 }
 
 #[test]
@@ -200,6 +249,27 @@ proptest! {
     fn test_proptest_large_blockwise_linear(data in proptest::collection::vec(num_strategy(), 1..6000)) {
         create_and_validate::<BlockwiseLinearCodec>(&data, "proptest multilinearinterpol");
     }
+
+    #[test]
+    fn test_proptest_large_blockwise_for(data in proptest::collection::vec(num_strategy(), 1..6000)) {
+        create_and_validate::<BlockwiseForCodec>(&data, "proptest blockwise for");
+    }
+
+    #[test]
+    fn test_proptest_large_float(data in proptest::collection::vec(num_strategy(), 1..6000)) {
+        create_and_validate::<FloatCodec>(&data, "proptest float");
+    }
+
+    #[test]
+    #[cfg(feature = "zstd")]
+    fn test_proptest_large_zstd_block(data in proptest::collection::vec(num_strategy(), 1..6000)) {
+        create_and_validate::<ZstdBlockCodec>(&data, "proptest zstd block");
+    }
+
+    #[test]
+    fn test_proptest_large_run_length(data in proptest::collection::vec(num_strategy(), 1..6000)) {
+        create_and_validate::<RunLengthCodec>(&data, "proptest run length");
+    }
 }
 
 fn num_strategy() -> impl Strategy<Value = u64> {
@@ -256,6 +326,39 @@ fn test_codec_interpolation() {
 fn test_codec_multi_interpolation() {
     test_codec::<BlockwiseLinearCodec>();
 }
+#[test]
+fn test_codec_blockwise_for() {
+    test_codec::<BlockwiseForCodec>();
+}
+#[test]
+fn test_codec_dictionary() {
+    test_codec::<DictionaryCodec>();
+}
+#[test]
+fn test_codec_sparse() {
+    test_codec::<SparseCodec>();
+}
+#[test]
+fn test_codec_float() {
+    test_codec::<FloatCodec>();
+}
+#[test]
+fn test_codec_delta() {
+    test_codec::<DeltaCodec>();
+}
+#[test]
+#[cfg(feature = "zstd")]
+fn test_codec_zstd_block() {
+    test_codec::<ZstdBlockCodec>();
+}
+#[test]
+fn test_codec_delta_of_delta() {
+    test_codec::<DeltaOfDeltaCodec>();
+}
+#[test]
+fn test_codec_run_length() {
+    test_codec::<RunLengthCodec>();
+}
 
 use super::*;
 
@@ -314,7 +417,7 @@ fn test_fast_field_codec_type_to_code() {
             count_codec += 1;
         }
     }
-    assert_eq!(count_codec, 3);
+    assert_eq!(count_codec, 11);
 }
 
 fn test_fastfield_gcd_i64_with_codec(codec_type: CodecType, num_vals: usize) -> io::Result<()> {
@@ -354,6 +457,7 @@ fn test_fastfield_gcd_i64() -> io::Result<()> {
         CodecType::Bitpacked,
         CodecType::BlockwiseLinear,
         CodecType::Linear,
+        CodecType::BlockwiseFor,
     ] {
         test_fastfield_gcd_i64_with_codec(codec_type, 5500)?;
     }
@@ -396,6 +500,7 @@ fn test_fastfield_gcd_u64() -> io::Result<()> {
         CodecType::Bitpacked,
         CodecType::BlockwiseLinear,
         CodecType::Linear,
+        CodecType::BlockwiseFor,
     ] {
         test_fastfield_gcd_u64_with_codec(codec_type, 5500)?;
     }
@@ -412,3 +517,203 @@ pub fn test_fastfield2() {
     assert_eq!(test_fastfield.get_val(1), 200);
     assert_eq!(test_fastfield.get_val(2), 300);
 }
+
+#[test]
+fn test_sampled_estimation_picks_linear() -> io::Result<()> {
+    let vals: Vec<u64> = (0..1_000_000u64).map(|i| 1_000 + 3 * i).collect();
+    let mut buffer = Vec::new();
+    serialize_u64_based_column_values(&&vals[..], &ALL_U64_CODEC_TYPES, &mut buffer)?;
+    assert_eq!(CodecType::try_from_code(buffer[0]), Some(CodecType::Linear));
+    // The line takes less than 100 bytes, the stats of the blocks at most 6 bytes per block, and
+    // the cardinality sketch is full.
+    let num_blocks = vals.len() / BLOCK_STATS_NUM_ROWS as usize + 1;
+    assert!(buffer.len() < 100 + 6 * num_blocks + 4 + 8 * CARDINALITY_SKETCH_NUM_HASHES);
+    let col = load_u64_based_column_values::<u64>(OwnedBytes::new(buffer))?;
+    for row_id in [0, 65_535, 65_536, 500_000, 999_999] {
+        assert_eq!(col.get_val(row_id), vals[row_id as usize]);
+    }
+    Ok(())
+}
+
+#[test]
+fn test_sampled_estimation_accounts_for_heavy_tail() -> io::Result<()> {
+    let mut vals: Vec<u64> = (0..1_000_000u64)
+        .map(|i| 1_000 + 3 * i + i * 7 % 5)
+        .collect();
+    // Rare large values, in blocks left out of the sample: the line fits the sample, but not
+    // the whole column.
+    vals[100_000] += 1 << 40;
+    vals[700_001] += 1 << 40;
+    let codec_types = [
+        CodecType::Bitpacked,
+        CodecType::Linear,
+        CodecType::BlockwiseLinear,
+    ];
+    let mut buffer = Vec::new();
+    serialize_u64_based_column_values(&&vals[..], &codec_types, &mut buffer)?;
+    assert_eq!(
+        CodecType::try_from_code(buffer[0]),
+        Some(CodecType::BlockwiseLinear)
+    );
+    assert!(buffer.len() < 1_000_000);
+    let col = load_u64_based_column_values::<u64>(OwnedBytes::new(buffer))?;
+    for row_id in [0, 100_000, 500_000, 700_001, 999_999] {
+        assert_eq!(col.get_val(row_id), vals[row_id as usize]);
+    }
+    Ok(())
+}
+
+#[test]
+fn test_sampled_estimation_falls_back_if_codec_does_not_apply() -> io::Result<()> {
+    // The dictionary codec wins on the sample, but the block 129 is not part of it and holds
+    // too many distinct values.
+    let mut vals: Vec<u64> = (0..200_000u64)
+        .map(|i| [3, 1_000, 70_000, 1_000_000][i as usize % 4])
+        .collect();
+    for (i, val) in vals[129 * 512..130 * 512].iter_mut().enumerate() {
+        *val = 2_000_000 + i as u64;
+    }
+    let codec_types: Vec<CodecType> = ALL_U64_CODEC_TYPES.to_vec();
+    // The zstd block codec would win on the sample as well, and apply to the whole column.
+    #[cfg(feature = "zstd")]
+    let codec_types: Vec<CodecType> = codec_types
+        .into_iter()
+        .filter(|codec_type| *codec_type != CodecType::ZstdBlock)
+        .collect();
+    let mut buffer = Vec::new();
+    serialize_u64_based_column_values(&&vals[..], &codec_types, &mut buffer)?;
+    assert_ne!(
+        CodecType::try_from_code(buffer[0]),
+        Some(CodecType::Dictionary)
+    );
+    let col = load_u64_based_column_values::<u64>(OwnedBytes::new(buffer))?;
+    let mut output = vec![0u64; vals.len()];
+    col.get_range(0, &mut output);
+    assert_eq!(output, vals);
+    Ok(())
+}
+
+#[test]
+fn test_exhaustive_estimation_on_values_left_out_of_the_sample() -> io::Result<()> {
+    // Runs of identical values in the sample, but alternating values in the blocks left out of
+    // it: the run length codec wins on the sample, not on the whole column.
+    let vals: Vec<u64> = (0..200_000u32)
+        .map(|row_id| {
+            if row_id < 32_768 {
+                0
+            } else if is_sampled(row_id) {
+                1
+            } else {
+                (row_id % 2) as u64
+            }
+        })
+        .collect();
+    let codec_types = [CodecType::Bitpacked, CodecType::RunLength];
+    let mut sampled_buffer = Vec::new();
+    serialize_u64_based_column_values_with_estimation(
+        &&vals[..],
+        CompetingCodecs::new(&codec_types),
+        CodecEstimation::Sampled,
+        &mut sampled_buffer,
+    )?;
+    assert_eq!(
+        CodecType::try_from_code(sampled_buffer[0]),
+        Some(CodecType::RunLength)
+    );
+    let mut exhaustive_buffer = Vec::new();
+    serialize_u64_based_column_values_with_estimation(
+        &&vals[..],
+        CompetingCodecs::new(&codec_types),
+        CodecEstimation::Exhaustive,
+        &mut exhaustive_buffer,
+    )?;
+    assert_eq!(
+        CodecType::try_from_code(exhaustive_buffer[0]),
+        Some(CodecType::Bitpacked)
+    );
+    assert!(exhaustive_buffer.len() * 4 < sampled_buffer.len());
+    let col = load_u64_based_column_values::<u64>(OwnedBytes::new(exhaustive_buffer))?;
+    let mut output = vec![0u64; vals.len()];
+    col.get_range(0, &mut output);
+    assert_eq!(output, vals);
+    Ok(())
+}
+
+#[test]
+fn test_codec_footer_refuses_unsupported_columns() -> io::Result<()> {
+    let vals: Vec<u64> = (0..100u64).collect();
+    let mut buffer = Vec::new();
+    serialize_u64_based_column_values(&&vals[..], &[CodecType::Bitpacked], &mut buffer)?;
+    assert!(validate_u64_based_column_values_checksum(OwnedBytes::new(
+        buffer.clone()
+    ))?);
+    let footer_start = buffer.len() - codec_footer::CODEC_FOOTER_NUM_BYTES;
+    let load_error = |bytes: Vec<u8>| -> io::Error {
+        load_u64_based_column_values::<u64>(OwnedBytes::new(bytes))
+            .err()
+            .unwrap()
+    };
+    let unsupported_codec_error = |error: &io::Error| -> Option<UnsupportedCodecError> {
+        error
+            .get_ref()?
+            .downcast_ref::<UnsupportedCodecError>()
+            .copied()
+    };
+
+    // Serialized with a newer version of the layout of the codec.
+    let mut newer_version = buffer.clone();
+    newer_version[footer_start + 1] = CodecType::Bitpacked.version() + 1;
+    assert!(!validate_u64_based_column_values_checksum(
+        OwnedBytes::new(newer_version.clone())
+    )?);
+    let error = load_error(newer_version);
+    assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+    assert_eq!(
+        unsupported_codec_error(&error),
+        Some(UnsupportedCodecError::UnsupportedVersion {
+            code: 0,
+            version: CodecType::Bitpacked.version() + 1,
+            supported_version: CodecType::Bitpacked.version(),
+        })
+    );
+
+    // Serialized with a codec that is not registered.
+    let mut unknown_codec = buffer.clone();
+    unknown_codec[0] = 250;
+    unknown_codec[footer_start] = 250;
+    let error = load_error(unknown_codec);
+    assert_eq!(
+        unsupported_codec_error(&error),
+        Some(UnsupportedCodecError::UnknownCodec { code: 250 })
+    );
+
+    // The number of rows of the footer does not match the column.
+    let mut wrong_num_rows = buffer;
+    wrong_num_rows[footer_start + 2] = 99;
+    let error = load_error(wrong_num_rows);
+    assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+    assert_eq!(unsupported_codec_error(&error), None);
+    Ok(())
+}
+
+#[test]
+fn test_cardinality_sketch_of_loaded_column() -> io::Result<()> {
+    let vals: Vec<i64> = (0..100_000i64)
+        .map(|i| (i * 7_919) % 20_000 - 10_000)
+        .collect();
+    let mut buffer = Vec::new();
+    serialize_u64_based_column_values(&&vals[..], &ALL_U64_CODEC_TYPES, &mut buffer)?;
+    let col = load_u64_based_column_values::<i64>(OwnedBytes::new(buffer))?;
+    let sketch = col.cardinality_sketch().unwrap();
+    assert!(!sketch.is_exact());
+    let error = sketch.estimate() as f64 / 20_000f64 - 1.0;
+    assert!(error.abs() < 0.3, "{sketch:?}");
+
+    let mut buffer = Vec::new();
+    serialize_u64_based_column_values(&&[3u64, 1u64, 3u64][..], &ALL_U64_CODEC_TYPES, &mut buffer)?;
+    let col = load_u64_based_column_values::<u64>(OwnedBytes::new(buffer))?;
+    let sketch = col.cardinality_sketch().unwrap();
+    assert!(sketch.is_exact());
+    assert_eq!(sketch.estimate(), 2);
+    Ok(())
+}
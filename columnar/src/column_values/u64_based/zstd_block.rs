@@ -0,0 +1,349 @@
+use std::io::{self, Write};
+use std::num::NonZeroUsize;
+use std::ops::{Range, RangeInclusive};
+use std::sync::{Arc, Mutex};
+
+use common::{BinarySerializable, CountingWriter, DeserializeFrom, OwnedBytes, VInt};
+use lru::LruCache;
+use zstd::DEFAULT_COMPRESSION_LEVEL;
+
+use crate::column_values::u64_based::{
+    BlockwiseIter, ColumnCodec, ColumnCodecEstimator, ColumnStats,
+};
+use crate::{ColumnValues, RowId};
+
+const BLOCK_LEN: u32 = 1_024;
+
+/// Number of decompressed blocks kept in the cache of a reader.
+const CACHE_NUM_BLOCKS: usize = 4;
+
+fn compute_num_blocks(num_rows: RowId) -> u32 {
+    (num_rows + BLOCK_LEN - 1) / BLOCK_LEN
+}
+
+// Each block stores the values as little endian u64 offsets from the minimum value of the
+// block, compressed with zstd.
+#[derive(Debug)]
+struct Block {
+    block_min: u64,
+    compressed_len: u64,
+    data_start_offset: usize,
+}
+
+impl BinarySerializable for Block {
+    fn serialize<W: Write + ?Sized>(&self, writer: &mut W) -> io::Result<()> {
+        VInt(self.block_min).serialize(writer)?;
+        VInt(self.compressed_len).serialize(writer)?;
+        Ok(())
+    }
+
+    fn deserialize<R: io::Read>(reader: &mut R) -> io::Result<Self> {
+        let block_min = VInt::deserialize(reader)?.0;
+        let compressed_len = VInt::deserialize(reader)?.0;
+        Ok(Block {
+            block_min,
+            compressed_len,
+            data_start_offset: 0,
+        })
+    }
+}
+
+impl Block {
+    fn num_bytes(&self) -> u64 {
+        VInt(self.block_min).num_bytes() + VInt(self.compressed_len).num_bytes()
+    }
+}
+
+/// Compresses the values of a block, and returns their minimum value along with the compressed
+/// bytes.
+fn compress_block(vals: &[u64]) -> io::Result<(u64, Vec<u8>)> {
+    let block_min = vals.iter().copied().min().unwrap_or(0u64);
+    let mut uncompressed: Vec<u8> = Vec::with_capacity(vals.len() * 8);
+    for val in vals {
+        uncompressed.extend_from_slice(&(val - block_min).to_le_bytes());
+    }
+    let compressed = zstd::bulk::compress(&uncompressed, DEFAULT_COMPRESSION_LEVEL)?;
+    Ok((block_min, compressed))
+}
+
+/// Compresses the values of the column block by block.
+///
+/// The compressed blocks are kept, so that serializing does not compress them again.
+pub struct ZstdBlockEstimator {
+    block: Vec<u64>,
+    blocks: Vec<(Block, Vec<u8>)>,
+    compression_failed: bool,
+}
+
+impl Default for ZstdBlockEstimator {
+    fn default() -> Self {
+        ZstdBlockEstimator {
+            block: Vec::with_capacity(BLOCK_LEN as usize),
+            blocks: Vec::new(),
+            compression_failed: false,
+        }
+    }
+}
+
+impl ZstdBlockEstimator {
+    fn flush_block(&mut self) {
+        if self.block.is_empty() {
+            return;
+        }
+        match compress_block(&self.block) {
+            Ok((block_min, compressed)) => {
+                let block = Block {
+                    block_min,
+                    compressed_len: compressed.len() as u64,
+                    data_start_offset: 0,
+                };
+                self.blocks.push((block, compressed));
+            }
+            Err(_) => {
+                self.compression_failed = true;
+            }
+        }
+        self.block.clear();
+    }
+}
+
+impl ColumnCodecEstimator for ZstdBlockEstimator {
+    fn collect(&mut self, value: u64) {
+        self.block.push(value);
+        if self.block.len() == BLOCK_LEN as usize {
+            self.flush_block();
+        }
+    }
+
+    fn skip(&mut self, _num_rows: RowId) {
+        self.flush_block();
+    }
+
+    fn finalize(&mut self) {
+        self.flush_block();
+    }
+
+    fn estimate(&self, stats: &ColumnStats) -> Option<u64> {
+        if self.compression_failed {
+            return None;
+        }
+        let blocks_num_bytes: u64 = self
+            .blocks
+            .iter()
+            .map(|(block, compressed)| block.num_bytes() + compressed.len() as u64)
+            .sum();
+        Some(stats.num_bytes() + blocks_num_bytes + 4)
+    }
+
+    fn serialize(
+        &self,
+        stats: &ColumnStats,
+        _vals: &mut dyn Iterator<Item = u64>,
+        wrt: &mut dyn Write,
+    ) -> io::Result<()> {
+        stats.serialize(wrt)?;
+        for (_, compressed) in &self.blocks {
+            wrt.write_all(compressed)?;
+        }
+        let mut counting_wrt = CountingWriter::wrap(wrt);
+        for (block, _) in &self.blocks {
+            block.serialize(&mut counting_wrt)?;
+        }
+        let footer_len = counting_wrt.written_bytes();
+        (footer_len as u32).serialize(&mut counting_wrt)?;
+        Ok(())
+    }
+}
+
+/// Zstd block codec, for cold columns: values are split in blocks of 1,024 values, compressed
+/// with zstd.
+///
+/// Accessing a value requires decompressing its block. Readers keep the last decompressed
+/// blocks in a small LRU cache, but random accesses remain a lot more expensive than with the
+/// other codecs. This codec is therefore not part of the default codecs, and needs to be
+/// requested explicitly.
+pub struct ZstdBlockCodec;
+
+impl ColumnCodec for ZstdBlockCodec {
+    type ColumnValues = ZstdBlockReader;
+    type Estimator = ZstdBlockEstimator;
+
+    fn load(mut bytes: OwnedBytes) -> io::Result<Self::ColumnValues> {
+        let stats = ColumnStats::deserialize(&mut bytes)?;
+        let footer_len: u32 = (&bytes[bytes.len() - 4..]).deserialize()?;
+        let footer_offset = bytes.len() - 4 - footer_len as usize;
+        let (data, mut footer) = bytes.split(footer_offset);
+        let num_blocks = compute_num_blocks(stats.num_rows);
+        let mut blocks: Vec<Block> = std::iter::repeat_with(|| Block::deserialize(&mut footer))
+            .take(num_blocks as usize)
+            .collect::<io::Result<_>>()?;
+        let mut start_offset = 0;
+        for block in &mut blocks {
+            block.data_start_offset = start_offset;
+            start_offset += block.compressed_len as usize;
+        }
+        if start_offset > data.len() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "Zstd block column is truncated.",
+            ));
+        }
+        let cache_num_blocks = NonZeroUsize::new(CACHE_NUM_BLOCKS).unwrap();
+        Ok(ZstdBlockReader {
+            blocks: blocks.into(),
+            data,
+            stats,
+            cache: Arc::new(Mutex::new(LruCache::new(cache_num_blocks))),
+        })
+    }
+}
+
+/// Reader of a column serialized with the [`ZstdBlockCodec`].
+#[derive(Clone)]
+pub struct ZstdBlockReader {
+    blocks: Arc<[Block]>,
+    data: OwnedBytes,
+    stats: ColumnStats,
+    cache: Arc<Mutex<LruCache<u32, Arc<[u64]>>>>,
+}
+
+impl ZstdBlockReader {
+    /// Returns the values of a block, decompressing it if it is not in the cache.
+    fn block_vals(&self, block_id: u32) -> Arc<[u64]> {
+        if let Some(block_vals) = self.cache.lock().unwrap().get(&block_id) {
+            return block_vals.clone();
+        }
+        let block = &self.blocks[block_id as usize];
+        let compressed = &self.data[block.data_start_offset..][..block.compressed_len as usize];
+        let decompressed = zstd::bulk::decompress(compressed, BLOCK_LEN as usize * 8)
+            .expect("Failed to decompress a block of a zstd block column.");
+        let block_vals: Arc<[u64]> = decompressed
+            .chunks_exact(8)
+            .map(|val_bytes| block.block_min + u64::from_le_bytes(val_bytes.try_into().unwrap()))
+            .collect();
+        self.cache.lock().unwrap().put(block_id, block_vals.clone());
+        block_vals
+    }
+}
+
+impl ColumnValues for ZstdBlockReader {
+    #[inline]
+    fn get_val(&self, idx: u32) -> u64 {
+        self.block_vals(idx / BLOCK_LEN)[(idx % BLOCK_LEN) as usize]
+    }
+
+    fn get_range(&self, start: u64, output: &mut [u64]) {
+        let mut idx = start as u32;
+        let mut output = output;
+        while !output.is_empty() {
+            let block_vals = self.block_vals(idx / BLOCK_LEN);
+            let idx_within_block = (idx % BLOCK_LEN) as usize;
+            let len = (block_vals.len() - idx_within_block).min(output.len());
+            let (block_output, remaining_output) = output.split_at_mut(len);
+            block_output.copy_from_slice(&block_vals[idx_within_block..][..len]);
+            output = remaining_output;
+            idx += len as u32;
+        }
+    }
+
+    fn iter(&self) -> Box<dyn Iterator<Item = u64> + '_> {
+        Box::new(BlockwiseIter::new(self))
+    }
+
+    fn get_row_ids_for_value_range(
+        &self,
+        range: RangeInclusive<u64>,
+        row_id_range: Range<u32>,
+        positions: &mut Vec<u32>,
+    ) {
+        positions.clear();
+        let row_id_range = row_id_range.start..row_id_range.end.min(self.num_vals());
+        let mut row_id = row_id_range.start;
+        while row_id < row_id_range.end {
+            let block_id = row_id / BLOCK_LEN;
+            let block_vals = self.block_vals(block_id);
+            let block_end = (block_id * BLOCK_LEN + block_vals.len() as u32).min(row_id_range.end);
+            for row_id in row_id..block_end {
+                if range.contains(&block_vals[(row_id % BLOCK_LEN) as usize]) {
+                    positions.push(row_id);
+                }
+            }
+            row_id = block_end;
+        }
+    }
+
+    #[inline]
+    fn min_value(&self) -> u64 {
+        self.stats.min_value
+    }
+
+    #[inline]
+    fn max_value(&self) -> u64 {
+        self.stats.max_value
+    }
+
+    #[inline]
+    fn num_vals(&self) -> RowId {
+        self.stats.num_rows
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::column_values::u64_based::tests::create_and_validate;
+    use crate::column_values::u64_based::BitpackedCodec;
+
+    #[test]
+    fn test_with_codec_data_sets_simple() {
+        create_and_validate::<ZstdBlockCodec>(&[4, 3, 12, 3, 4, 4], "simple").unwrap();
+        create_and_validate::<ZstdBlockCodec>(&[], "empty").unwrap();
+    }
+
+    #[test]
+    fn test_with_codec_data_sets() {
+        let data_sets = crate::column_values::u64_based::tests::get_codec_test_datasets();
+        for (mut data, name) in data_sets {
+            create_and_validate::<ZstdBlockCodec>(&data, name).unwrap();
+            data.reverse();
+            create_and_validate::<ZstdBlockCodec>(&data, name).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_zstd_block_repetitive_values() {
+        let data: Vec<u64> = (0..20_000u64)
+            .map(|i| (i % 50).wrapping_mul(0x9E37_79B9_7F4A_7C15) >> 1)
+            .collect();
+        let (_, zstd_compression) =
+            create_and_validate::<ZstdBlockCodec>(&data, "repetitive").unwrap();
+        let (_, bitpacked_compression) =
+            create_and_validate::<BitpackedCodec>(&data, "repetitive").unwrap();
+        assert!(zstd_compression < 0.05);
+        assert!(zstd_compression < bitpacked_compression);
+    }
+
+    #[test]
+    fn test_zstd_block_cache() {
+        let data: Vec<u64> = (0..10 * BLOCK_LEN as u64).map(|i| i * i).collect();
+        let mut estimator = ZstdBlockEstimator::default();
+        let mut stats_collector = crate::column_values::u64_based::StatsCollector::default();
+        for &val in &data {
+            estimator.collect(val);
+            stats_collector.collect(val);
+        }
+        estimator.finalize();
+        let stats = stats_collector.stats();
+        let mut buffer = Vec::new();
+        estimator
+            .serialize(&stats, &mut data.iter().copied(), &mut buffer)
+            .unwrap();
+        let reader = ZstdBlockCodec::load(OwnedBytes::new(buffer)).unwrap();
+        for idx in (0..data.len() as u32).step_by(100) {
+            assert_eq!(reader.get_val(idx), data[idx as usize]);
+        }
+        assert_eq!(reader.cache.lock().unwrap().len(), CACHE_NUM_BLOCKS);
+        assert_eq!(reader.get_val(5), data[5]);
+        assert!(reader.cache.lock().unwrap().contains(&0));
+    }
+}
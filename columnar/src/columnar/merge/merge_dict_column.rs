@@ -4,8 +4,9 @@ use common::{BitSet, CountingWriter, ReadOnlyBitSet};
 use sstable::{SSTable, Streamer, TermOrdinal, VoidSSTable};
 
 use super::term_merger::TermMerger;
-use crate::column::serialize_column_mappable_to_u64;
+use crate::column::{serialize_column_mappable_to_u64_with_estimation, U64_CODEC_TYPES};
 use crate::column_index::SerializableColumnIndex;
+use crate::column_values::{CodecEstimation, CompetingCodecs};
 use crate::iterable::Iterable;
 use crate::{BytesColumn, MergeRowOrder, ShuffleMergeOrder};
 
@@ -28,7 +29,13 @@ pub fn merge_bytes_or_str_column(
         term_ord_mapping: &term_ord_mapping,
         merge_row_order,
     };
-    serialize_column_mappable_to_u64(column_index, &remapped_term_ordinals_values, output)?;
+    serialize_column_mappable_to_u64_with_estimation(
+        column_index,
+        &remapped_term_ordinals_values,
+        CompetingCodecs::new(U64_CODEC_TYPES),
+        CodecEstimation::Exhaustive,
+        output,
+    )?;
     output.write_all(&dictionary_num_bytes.to_le_bytes())?;
     Ok(())
 }
@@ -10,8 +10,12 @@ use std::sync::Arc;
 pub use merge_mapping::{MergeRowOrder, ShuffleMergeOrder, StackMergeOrder};
 
 use super::writer::ColumnarSerializer;
-use crate::column::{serialize_column_mappable_to_u128, serialize_column_mappable_to_u64};
-use crate::column_values::MergedColumnValues;
+use crate::column::{
+    pinned_or_default_codecs, serialize_column_mappable_to_u128,
+    serialize_column_mappable_to_u64_with_estimation, DATETIME_CODEC_TYPES, F64_CODEC_TYPES,
+    U64_CODEC_TYPES,
+};
+use crate::column_values::{CodecEstimation, CodecType, MergedColumnValues};
 use crate::columnar::merge::merge_dict_column::merge_bytes_or_str_column;
 use crate::columnar::writer::CompatibleNumericalTypes;
 use crate::columnar::ColumnarReader;
@@ -72,6 +76,9 @@ impl From<ColumnType> for ColumnTypeCategory {
 /// `merge_row_order` makes it possible to remove or reorder row in the resulting
 /// `Columnar` table.
 ///
+/// The codecs of the merged columns are picked again, on the merged values, rather than
+/// inherited from the input columns.
+///
 /// Reminder: a string and a numerical column may bare the same column name. This is not
 /// considered a conflict.
 pub fn merge_columnar(
@@ -79,6 +86,26 @@ pub fn merge_columnar(
     required_columns: &[(String, ColumnType)],
     merge_row_order: MergeRowOrder,
     output: &mut impl io::Write,
+) -> io::Result<()> {
+    merge_columnar_with_codecs(
+        columnar_readers,
+        required_columns,
+        &[],
+        merge_row_order,
+        output,
+    )
+}
+
+/// Same as [`merge_columnar`], pinning the codec used for the values of the numerical, bool and
+/// datetime columns listed in `column_codecs`.
+///
+/// See [`ColumnarWriter::set_column_codec`](crate::ColumnarWriter::set_column_codec).
+pub fn merge_columnar_with_codecs(
+    columnar_readers: &[&ColumnarReader],
+    required_columns: &[(String, ColumnType)],
+    column_codecs: &[(String, CodecType)],
+    merge_row_order: MergeRowOrder,
+    output: &mut impl io::Write,
 ) -> io::Result<()> {
     let mut serializer = ColumnarSerializer::new(output);
     let num_rows_per_columnar = columnar_readers
@@ -99,6 +126,10 @@ pub fn merge_columnar(
         let mut columns = grouped_columns.columns;
         coerce_columns(column_type, &mut columns)?;
 
+        let pinned_codec_opt = column_codecs
+            .iter()
+            .find(|(pinned_column_name, _)| *pinned_column_name == column_name)
+            .map(|(_, codec_type)| codec_type);
         let mut column_serializer =
             serializer.start_serialize_column(column_name.as_bytes(), column_type);
         merge_column(
@@ -106,6 +137,7 @@ pub fn merge_columnar(
             &num_rows_per_columnar,
             columns,
             &merge_row_order,
+            pinned_codec_opt,
             &mut column_serializer,
         )?;
         column_serializer.finalize()?;
@@ -131,6 +163,7 @@ fn merge_column(
     num_docs_per_column: &[u32],
     columns: Vec<Option<DynamicColumn>>,
     merge_row_order: &MergeRowOrder,
+    pinned_codec_opt: Option<&CodecType>,
     wrt: &mut impl io::Write,
 ) -> io::Result<()> {
     match column_type {
@@ -162,7 +195,20 @@ fn merge_column(
                 column_values: &column_values[..],
                 merge_row_order,
             };
-            serialize_column_mappable_to_u64(merged_column_index, &merge_column_values, wrt)?;
+            let codec_types = match column_type {
+                ColumnType::F64 => F64_CODEC_TYPES,
+                ColumnType::DateTime => DATETIME_CODEC_TYPES,
+                _ => U64_CODEC_TYPES,
+            };
+            // The merged column is kept until its segment is merged again: its codec is picked
+            // on all of its values rather than on a sample.
+            serialize_column_mappable_to_u64_with_estimation(
+                merged_column_index,
+                &merge_column_values,
+                pinned_or_default_codecs(pinned_codec_opt, codec_types),
+                CodecEstimation::Exhaustive,
+                wrt,
+            )?;
         }
         ColumnType::IpAddr => {
             let mut column_indexes: Vec<ColumnIndex> = Vec::with_capacity(columns.len());
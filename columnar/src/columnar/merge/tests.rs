@@ -237,6 +237,34 @@ fn test_merge_columnar_numbers() {
     assert_eq!(vals.first(2u32), Some(-3f64));
 }
 
+#[test]
+fn test_merge_columnar_with_pinned_codec() {
+    let vals: Vec<u64> = (0..1_000u64).collect();
+    let columnar1 = make_columnar("numbers", &vals[..]);
+    let columnar2 = make_columnar("numbers", &vals[..]);
+    let columnars = &[&columnar1, &columnar2];
+    let merge_and_get_codec = |column_codecs: &[(String, CodecType)]| {
+        let mut buffer = Vec::new();
+        let stack_merge_order = StackMergeOrder::stack(columnars);
+        crate::columnar::merge_columnar_with_codecs(
+            columnars,
+            &[],
+            column_codecs,
+            MergeRowOrder::Stack(stack_merge_order),
+            &mut buffer,
+        )
+        .unwrap();
+        let columnar_reader = ColumnarReader::open(buffer).unwrap();
+        let cols = columnar_reader.read_columns("numbers").unwrap();
+        cols[0].values_codec().unwrap()
+    };
+    assert_eq!(merge_and_get_codec(&[]), Some(CodecType::BlockwiseLinear));
+    assert_eq!(
+        merge_and_get_codec(&[("numbers".to_string(), CodecType::Bitpacked)]),
+        Some(CodecType::Bitpacked)
+    );
+}
+
 #[test]
 fn test_merge_columnar_texts() {
     let columnar1 = make_text_columnar_multiple_columns(&[("texts", &[&["a"]])]);
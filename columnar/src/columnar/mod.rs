@@ -8,6 +8,8 @@ pub use column_type::{ColumnType, HasAssociatedColumnType};
 pub use format_version::{Version, CURRENT_VERSION};
 #[cfg(test)]
 pub(crate) use merge::ColumnTypeCategory;
-pub use merge::{merge_columnar, MergeRowOrder, ShuffleMergeOrder, StackMergeOrder};
+pub use merge::{
+    merge_columnar, merge_columnar_with_codecs, MergeRowOrder, ShuffleMergeOrder, StackMergeOrder,
+};
 pub use reader::ColumnarReader;
 pub use writer::ColumnarWriter;
@@ -3,8 +3,11 @@ mod column_writers;
 mod serializer;
 mod value_index;
 
+use std::collections::HashMap;
 use std::io;
+use std::io::Write;
 use std::net::Ipv6Addr;
+use std::sync::Mutex;
 
 use column_operation::ColumnOperation;
 pub(crate) use column_writers::CompatibleNumericalTypes;
@@ -13,8 +16,15 @@ use common::CountingWriter;
 pub(crate) use serializer::ColumnarSerializer;
 use stacker::{Addr, ArenaHashMap, MemoryArena};
 
+use crate::column::{
+    pinned_or_default_codecs, serialize_column_mappable_to_u64_with_estimation,
+    DATETIME_CODEC_TYPES, F64_CODEC_TYPES, U64_CODEC_TYPES,
+};
 use crate::column_index::{SerializableColumnIndex, SerializableOptionalIndex};
-use crate::column_values::{MonotonicallyMappableToU128, MonotonicallyMappableToU64};
+use crate::column_values::{
+    CodecEstimation, CodecType, CompetingCodecs, MonotonicallyMappableToU128,
+    MonotonicallyMappableToU64,
+};
 use crate::columnar::column_type::ColumnType;
 use crate::columnar::writer::column_writers::{
     ColumnWriter, NumericalColumnWriter, StrOrBytesColumnWriter,
@@ -57,6 +67,8 @@ pub struct ColumnarWriter {
     arena: MemoryArena,
     // Dictionaries used to store dictionary-encoded values.
     dictionaries: Vec<DictionaryBuilder>,
+    // Codecs pinned for the values of some of the columns.
+    column_codecs: HashMap<Vec<u8>, CodecType>,
     buffers: SpareBuffers,
 }
 
@@ -148,6 +160,16 @@ impl ColumnarWriter {
         }
     }
 
+    /// Pins the codec used for the values of the numerical, bool and datetime columns named
+    /// `column_name`, instead of picking the codec resulting in the smallest column.
+    ///
+    /// Serializing fails if the codec does not apply to the values of the column, like
+    /// [`CodecType::Dictionary`] for a column with too many distinct values.
+    pub fn set_column_codec(&mut self, column_name: &str, codec_type: CodecType) {
+        self.column_codecs
+            .insert(column_name.as_bytes().to_vec(), codec_type);
+    }
+
     pub fn record_numerical<T: Into<NumericalValue> + Copy>(
         &mut self,
         doc: RowId,
@@ -247,8 +269,55 @@ impl ColumnarWriter {
         );
     }
     pub fn serialize(&mut self, num_docs: RowId, wrt: &mut dyn io::Write) -> io::Result<()> {
+        self.serialize_with_num_threads(num_docs, 1, wrt)
+    }
+
+    /// Serializes the columnar, encoding its columns concurrently on up to `num_threads` threads.
+    ///
+    /// The output is identical to the one of [`ColumnarWriter::serialize`]. With more than one
+    /// thread, the encoded columns are buffered in memory until they are written in order.
+    pub fn serialize_with_num_threads(
+        &mut self,
+        num_docs: RowId,
+        num_threads: usize,
+        wrt: &mut dyn io::Write,
+    ) -> io::Result<()> {
         let mut serializer = ColumnarSerializer::new(wrt);
+        let mut buffers = std::mem::take(&mut self.buffers);
+        let columns = self.columns_to_serialize();
+        if num_threads <= 1 || columns.len() <= 1 {
+            let mut symbol_byte_buffer: Vec<u8> = Vec::new();
+            for (column_name, column_type, addr) in columns {
+                let mut column_serializer =
+                    serializer.start_serialize_column(column_name, column_type);
+                self.serialize_column(
+                    column_name,
+                    column_type,
+                    addr,
+                    num_docs,
+                    &mut buffers,
+                    &mut symbol_byte_buffer,
+                    &mut column_serializer,
+                )?;
+                column_serializer.finalize()?;
+            }
+        } else {
+            let column_bytes =
+                self.serialize_columns_concurrently(&columns, num_docs, num_threads)?;
+            for ((column_name, column_type, _), column_bytes) in columns.iter().zip(column_bytes) {
+                let mut column_serializer =
+                    serializer.start_serialize_column(column_name, *column_type);
+                column_serializer.write_all(&column_bytes)?;
+                column_serializer.finalize()?;
+            }
+        }
+        serializer.finalize(num_docs)?;
+        self.buffers = buffers;
+        Ok(())
+    }
 
+    /// Returns the columns to serialize, sorted by name and type.
+    fn columns_to_serialize(&self) -> Vec<(&[u8], ColumnType, Addr)> {
         let mut columns: Vec<(&[u8], ColumnType, Addr)> = self
             .numerical_field_hash_map
             .iter()
@@ -284,108 +353,156 @@ impl ColumnarWriter {
                 .iter()
                 .map(|(column_name, addr)| (column_name, ColumnType::DateTime, addr)),
         );
+        // Tantivy uses b'0' as a separator for nested fields in JSON.
+        // Column names with a b'0' are not simply ignored by the columnar (and the inverted
+        // index).
+        columns.retain(|(column_name, _, _)| !column_name.contains(&JSON_END_OF_PATH));
         columns.sort_unstable_by_key(|(column_name, col_type, _)| (*column_name, *col_type));
+        columns
+    }
 
-        let (arena, buffers, dictionaries) = (&self.arena, &mut self.buffers, &self.dictionaries);
-        let mut symbol_byte_buffer: Vec<u8> = Vec::new();
-        for (column_name, column_type, addr) in columns {
-            if column_name.contains(&JSON_END_OF_PATH) {
-                // Tantivy uses b'0' as a separator for nested fields in JSON.
-                // Column names with a b'0' are not simply ignored by the columnar (and the inverted
-                // index).
-                continue;
+    /// Encodes the `columns` on up to `num_threads` threads, and returns their bytes in the
+    /// order of `columns`.
+    fn serialize_columns_concurrently(
+        &self,
+        columns: &[(&[u8], ColumnType, Addr)],
+        num_docs: RowId,
+        num_threads: usize,
+    ) -> io::Result<Vec<Vec<u8>>> {
+        let num_threads = num_threads.min(columns.len());
+        let columns_it = Mutex::new(columns.iter().enumerate());
+        let columns_it = &columns_it;
+        let thread_results: Vec<io::Result<Vec<(usize, Vec<u8>)>>> = std::thread::scope(|scope| {
+            let handles: Vec<_> = (0..num_threads)
+                .map(|_| {
+                    scope.spawn(move || -> io::Result<Vec<(usize, Vec<u8>)>> {
+                        let mut buffers = SpareBuffers::default();
+                        let mut symbol_byte_buffer: Vec<u8> = Vec::new();
+                        let mut serialized_columns = Vec::new();
+                        loop {
+                            let Some((column_ord, &(column_name, column_type, addr))) =
+                                columns_it.lock().unwrap().next()
+                            else {
+                                return Ok(serialized_columns);
+                            };
+                            let mut column_bytes: Vec<u8> = Vec::new();
+                            self.serialize_column(
+                                column_name,
+                                column_type,
+                                addr,
+                                num_docs,
+                                &mut buffers,
+                                &mut symbol_byte_buffer,
+                                &mut column_bytes,
+                            )?;
+                            serialized_columns.push((column_ord, column_bytes));
+                        }
+                    })
+                })
+                .collect();
+            handles
+                .into_iter()
+                .map(|handle| handle.join().expect("column serialization thread panicked"))
+                .collect()
+        });
+        let mut column_bytes: Vec<Vec<u8>> = vec![Vec::new(); columns.len()];
+        for thread_result in thread_results {
+            for (column_ord, bytes) in thread_result? {
+                column_bytes[column_ord] = bytes;
+            }
+        }
+        Ok(column_bytes)
+    }
+
+    /// Serializes the values and the index of a single column.
+    #[allow(clippy::too_many_arguments)]
+    fn serialize_column<W: io::Write>(
+        &self,
+        column_name: &[u8],
+        column_type: ColumnType,
+        addr: Addr,
+        num_docs: RowId,
+        buffers: &mut SpareBuffers,
+        symbol_byte_buffer: &mut Vec<u8>,
+        wrt: &mut W,
+    ) -> io::Result<()> {
+        let (arena, dictionaries) = (&self.arena, &self.dictionaries);
+        let column_codecs = &self.column_codecs;
+        match column_type {
+            ColumnType::Bool => {
+                let column_writer: ColumnWriter = self.bool_field_hash_map.read(addr);
+                let cardinality = column_writer.get_cardinality(num_docs);
+                serialize_bool_column(
+                    cardinality,
+                    num_docs,
+                    column_writer.operation_iterator(arena, symbol_byte_buffer),
+                    column_codecs.get(column_name),
+                    buffers,
+                    wrt,
+                )?;
+            }
+            ColumnType::IpAddr => {
+                let column_writer: ColumnWriter = self.ip_addr_field_hash_map.read(addr);
+                let cardinality = column_writer.get_cardinality(num_docs);
+                serialize_ip_addr_column(
+                    cardinality,
+                    num_docs,
+                    column_writer.operation_iterator(arena, symbol_byte_buffer),
+                    buffers,
+                    wrt,
+                )?;
+            }
+            ColumnType::Bytes | ColumnType::Str => {
+                let str_or_bytes_column_writer: StrOrBytesColumnWriter =
+                    if column_type == ColumnType::Bytes {
+                        self.bytes_field_hash_map.read(addr)
+                    } else {
+                        self.str_field_hash_map.read(addr)
+                    };
+                let dictionary_builder =
+                    &dictionaries[str_or_bytes_column_writer.dictionary_id as usize];
+                let cardinality = str_or_bytes_column_writer
+                    .column_writer
+                    .get_cardinality(num_docs);
+                serialize_bytes_or_str_column(
+                    cardinality,
+                    num_docs,
+                    str_or_bytes_column_writer.sort_values_within_row,
+                    dictionary_builder,
+                    str_or_bytes_column_writer.operation_iterator(arena, symbol_byte_buffer),
+                    buffers,
+                    arena,
+                    wrt,
+                )?;
+            }
+            ColumnType::F64 | ColumnType::I64 | ColumnType::U64 => {
+                let numerical_column_writer: NumericalColumnWriter =
+                    self.numerical_field_hash_map.read(addr);
+                let cardinality = numerical_column_writer.cardinality(num_docs);
+                let numerical_type = column_type.numerical_type().unwrap();
+                serialize_numerical_column(
+                    cardinality,
+                    num_docs,
+                    numerical_type,
+                    numerical_column_writer.operation_iterator(arena, symbol_byte_buffer),
+                    column_codecs.get(column_name),
+                    buffers,
+                    wrt,
+                )?;
+            }
+            ColumnType::DateTime => {
+                let column_writer: ColumnWriter = self.datetime_field_hash_map.read(addr);
+                let cardinality = column_writer.get_cardinality(num_docs);
+                serialize_datetime_column(
+                    cardinality,
+                    num_docs,
+                    column_writer.operation_iterator(arena, symbol_byte_buffer),
+                    column_codecs.get(column_name),
+                    buffers,
+                    wrt,
+                )?;
             }
-            match column_type {
-                ColumnType::Bool => {
-                    let column_writer: ColumnWriter = self.bool_field_hash_map.read(addr);
-                    let cardinality = column_writer.get_cardinality(num_docs);
-                    let mut column_serializer =
-                        serializer.start_serialize_column(column_name, column_type);
-                    serialize_bool_column(
-                        cardinality,
-                        num_docs,
-                        column_writer.operation_iterator(arena, &mut symbol_byte_buffer),
-                        buffers,
-                        &mut column_serializer,
-                    )?;
-                    column_serializer.finalize()?;
-                }
-                ColumnType::IpAddr => {
-                    let column_writer: ColumnWriter = self.ip_addr_field_hash_map.read(addr);
-                    let cardinality = column_writer.get_cardinality(num_docs);
-                    let mut column_serializer =
-                        serializer.start_serialize_column(column_name, ColumnType::IpAddr);
-                    serialize_ip_addr_column(
-                        cardinality,
-                        num_docs,
-                        column_writer.operation_iterator(arena, &mut symbol_byte_buffer),
-                        buffers,
-                        &mut column_serializer,
-                    )?;
-                    column_serializer.finalize()?;
-                }
-                ColumnType::Bytes | ColumnType::Str => {
-                    let str_or_bytes_column_writer: StrOrBytesColumnWriter =
-                        if column_type == ColumnType::Bytes {
-                            self.bytes_field_hash_map.read(addr)
-                        } else {
-                            self.str_field_hash_map.read(addr)
-                        };
-                    let dictionary_builder =
-                        &dictionaries[str_or_bytes_column_writer.dictionary_id as usize];
-                    let cardinality = str_or_bytes_column_writer
-                        .column_writer
-                        .get_cardinality(num_docs);
-                    let mut column_serializer =
-                        serializer.start_serialize_column(column_name, column_type);
-                    serialize_bytes_or_str_column(
-                        cardinality,
-                        num_docs,
-                        str_or_bytes_column_writer.sort_values_within_row,
-                        dictionary_builder,
-                        str_or_bytes_column_writer
-                            .operation_iterator(arena, &mut symbol_byte_buffer),
-                        buffers,
-                        &self.arena,
-                        &mut column_serializer,
-                    )?;
-                    column_serializer.finalize()?;
-                }
-                ColumnType::F64 | ColumnType::I64 | ColumnType::U64 => {
-                    let numerical_column_writer: NumericalColumnWriter =
-                        self.numerical_field_hash_map.read(addr);
-                    let cardinality = numerical_column_writer.cardinality(num_docs);
-                    let mut column_serializer =
-                        serializer.start_serialize_column(column_name, column_type);
-                    let numerical_type = column_type.numerical_type().unwrap();
-                    serialize_numerical_column(
-                        cardinality,
-                        num_docs,
-                        numerical_type,
-                        numerical_column_writer.operation_iterator(arena, &mut symbol_byte_buffer),
-                        buffers,
-                        &mut column_serializer,
-                    )?;
-                    column_serializer.finalize()?;
-                }
-                ColumnType::DateTime => {
-                    let column_writer: ColumnWriter = self.datetime_field_hash_map.read(addr);
-                    let cardinality = column_writer.get_cardinality(num_docs);
-                    let mut column_serializer =
-                        serializer.start_serialize_column(column_name, ColumnType::DateTime);
-                    serialize_numerical_column(
-                        cardinality,
-                        num_docs,
-                        NumericalType::I64,
-                        column_writer.operation_iterator(arena, &mut symbol_byte_buffer),
-                        buffers,
-                        &mut column_serializer,
-                    )?;
-                    column_serializer.finalize()?;
-                }
-            };
         }
-        serializer.finalize(num_docs)?;
         Ok(())
     }
 }
@@ -430,6 +547,7 @@ fn serialize_bytes_or_str_column(
         sort_values_within_row,
         value_index_builders,
         u64_values,
+        CompetingCodecs::new(U64_CODEC_TYPES),
         &mut wrt,
     )?;
     wrt.write_all(&dictionary_num_bytes.to_le_bytes()[..])?;
@@ -441,6 +559,7 @@ fn serialize_numerical_column(
     num_docs: RowId,
     numerical_type: NumericalType,
     op_iterator: impl Iterator<Item = ColumnOperation<NumericalValue>>,
+    pinned_codec_opt: Option<&CodecType>,
     buffers: &mut SpareBuffers,
     wrt: &mut impl io::Write,
 ) -> io::Result<()> {
@@ -458,6 +577,7 @@ fn serialize_numerical_column(
                 false,
                 value_index_builders,
                 u64_values,
+                pinned_or_default_codecs(pinned_codec_opt, U64_CODEC_TYPES),
                 wrt,
             )?;
         }
@@ -469,6 +589,7 @@ fn serialize_numerical_column(
                 false,
                 value_index_builders,
                 u64_values,
+                pinned_or_default_codecs(pinned_codec_opt, U64_CODEC_TYPES),
                 wrt,
             )?;
         }
@@ -480,6 +601,7 @@ fn serialize_numerical_column(
                 false,
                 value_index_builders,
                 u64_values,
+                pinned_or_default_codecs(pinned_codec_opt, F64_CODEC_TYPES),
                 wrt,
             )?;
         }
@@ -487,10 +609,37 @@ fn serialize_numerical_column(
     Ok(())
 }
 
+fn serialize_datetime_column(
+    cardinality: Cardinality,
+    num_docs: RowId,
+    op_iterator: impl Iterator<Item = ColumnOperation<NumericalValue>>,
+    pinned_codec_opt: Option<&CodecType>,
+    buffers: &mut SpareBuffers,
+    wrt: &mut impl io::Write,
+) -> io::Result<()> {
+    let SpareBuffers {
+        value_index_builders,
+        u64_values,
+        ..
+    } = buffers;
+    send_to_serialize_column_mappable_to_u64(
+        coerce_numerical_symbol::<i64>(op_iterator),
+        cardinality,
+        num_docs,
+        false,
+        value_index_builders,
+        u64_values,
+        pinned_or_default_codecs(pinned_codec_opt, DATETIME_CODEC_TYPES),
+        wrt,
+    )?;
+    Ok(())
+}
+
 fn serialize_bool_column(
     cardinality: Cardinality,
     num_docs: RowId,
     column_operations_it: impl Iterator<Item = ColumnOperation<bool>>,
+    pinned_codec_opt: Option<&CodecType>,
     buffers: &mut SpareBuffers,
     wrt: &mut impl io::Write,
 ) -> io::Result<()> {
@@ -509,6 +658,7 @@ fn serialize_bool_column(
         false,
         value_index_builders,
         u64_values,
+        pinned_or_default_codecs(pinned_codec_opt, U64_CODEC_TYPES),
         wrt,
     )?;
     Ok(())
@@ -582,6 +732,7 @@ fn send_to_serialize_column_mappable_to_u128<
     Ok(())
 }
 
+#[allow(clippy::too_many_arguments)]
 fn send_to_serialize_column_mappable_to_u64(
     op_iterator: impl Iterator<Item = ColumnOperation<u64>>,
     cardinality: Cardinality,
@@ -589,6 +740,7 @@ fn send_to_serialize_column_mappable_to_u64(
     sort_values_within_row: bool,
     value_index_builders: &mut PreallocatedIndexBuilders,
     values: &mut Vec<u64>,
+    codecs: CompetingCodecs,
     mut wrt: impl io::Write,
 ) -> io::Result<()> {
     values.clear();
@@ -623,9 +775,11 @@ fn send_to_serialize_column_mappable_to_u64(
             SerializableColumnIndex::Multivalued(serializable_multivalued_index)
         }
     };
-    crate::column::serialize_column_mappable_to_u64(
+    serialize_column_mappable_to_u64_with_estimation(
         serializable_column_index,
         &&values[..],
+        codecs,
+        CodecEstimation::Sampled,
         &mut wrt,
     )?;
     Ok(())
@@ -6,7 +6,7 @@ use common::file_slice::FileSlice;
 use common::{ByteCount, DateTime, HasLen, OwnedBytes};
 
 use crate::column::{BytesColumn, Column, StrColumn};
-use crate::column_values::{monotonic_map_column, StrictlyMonotonicFn};
+use crate::column_values::{monotonic_map_column, CodecType, StrictlyMonotonicFn};
 use crate::columnar::ColumnType;
 use crate::{Cardinality, ColumnIndex, ColumnValues, NumericalType, Version};
 
@@ -323,4 +323,44 @@ impl DynamicColumnHandle {
     pub fn column_type(&self) -> ColumnType {
         self.column_type
     }
+
+    /// Returns the codec of the values of the column, for the columns of numerical, boolean
+    /// or date values.
+    ///
+    /// Returns `None` for the other column types.
+    pub fn values_codec(&self) -> io::Result<Option<CodecType>> {
+        match self.column_type {
+            ColumnType::Bool
+            | ColumnType::I64
+            | ColumnType::U64
+            | ColumnType::F64
+            | ColumnType::DateTime => {}
+            ColumnType::Str | ColumnType::Bytes | ColumnType::IpAddr => return Ok(None),
+        }
+        // The column index comes first, and its length is stored in the last 4 bytes.
+        let num_bytes = self.file_slice.len();
+        if num_bytes < 4 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "Column is too short",
+            ));
+        }
+        let column_index_num_bytes_payload =
+            self.file_slice.read_bytes_slice(num_bytes - 4..num_bytes)?;
+        let column_index_num_bytes = u32::from_le_bytes(
+            column_index_num_bytes_payload
+                .as_slice()
+                .try_into()
+                .unwrap(),
+        ) as usize;
+        let codec_code = self
+            .file_slice
+            .read_bytes_slice(column_index_num_bytes..column_index_num_bytes + 1)?;
+        codec_code
+            .first()
+            .copied()
+            .and_then(CodecType::try_from_code)
+            .map(Some)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "Failed to read codec type"))
+    }
 }
@@ -47,8 +47,9 @@ pub use column_values::{
     ColumnValues, EmptyColumnValues, MonotonicallyMappableToU128, MonotonicallyMappableToU64,
 };
 pub use columnar::{
-    merge_columnar, ColumnType, ColumnarReader, ColumnarWriter, HasAssociatedColumnType,
-    MergeRowOrder, ShuffleMergeOrder, StackMergeOrder, Version, CURRENT_VERSION,
+    merge_columnar, merge_columnar_with_codecs, ColumnType, ColumnarReader, ColumnarWriter,
+    HasAssociatedColumnType, MergeRowOrder, ShuffleMergeOrder, StackMergeOrder, Version,
+    CURRENT_VERSION,
 };
 use sstable::VoidSSTable;
 pub use value::{NumericalType, NumericalValue};
@@ -6,7 +6,7 @@ use common::DateTime;
 use proptest::prelude::*;
 use proptest::sample::subsequence;
 
-use crate::column_values::MonotonicallyMappableToU128;
+use crate::column_values::{CodecType, MonotonicallyMappableToU128};
 use crate::columnar::{ColumnType, ColumnTypeCategory};
 use crate::dynamic_column::{DynamicColumn, DynamicColumnHandle};
 use crate::value::{Coerce, NumericalValue};
@@ -26,7 +26,7 @@ fn test_dataframe_writer_str() {
     assert_eq!(columnar.num_columns(), 1);
     let cols: Vec<DynamicColumnHandle> = columnar.read_columns("my_string").unwrap();
     assert_eq!(cols.len(), 1);
-    assert_eq!(cols[0].num_bytes(), 73);
+    assert_eq!(cols[0].num_bytes(), 104);
 }
 
 #[test]
@@ -40,7 +40,7 @@ fn test_dataframe_writer_bytes() {
     assert_eq!(columnar.num_columns(), 1);
     let cols: Vec<DynamicColumnHandle> = columnar.read_columns("my_string").unwrap();
     assert_eq!(cols.len(), 1);
-    assert_eq!(cols[0].num_bytes(), 73);
+    assert_eq!(cols[0].num_bytes(), 104);
 }
 
 #[test]
@@ -54,7 +54,7 @@ fn test_dataframe_writer_bool() {
     assert_eq!(columnar.num_columns(), 1);
     let cols: Vec<DynamicColumnHandle> = columnar.read_columns("bool.value").unwrap();
     assert_eq!(cols.len(), 1);
-    assert_eq!(cols[0].num_bytes(), 22);
+    assert_eq!(cols[0].num_bytes(), 53);
     assert_eq!(cols[0].column_type(), ColumnType::Bool);
     let dyn_bool_col = cols[0].open().unwrap();
     let DynamicColumn::Bool(bool_col) = dyn_bool_col else {
@@ -79,7 +79,7 @@ fn test_dataframe_writer_u64_multivalued() {
     assert_eq!(columnar.num_columns(), 1);
     let cols: Vec<DynamicColumnHandle> = columnar.read_columns("divisor").unwrap();
     assert_eq!(cols.len(), 1);
-    assert_eq!(cols[0].num_bytes(), 50);
+    assert_eq!(cols[0].num_bytes(), 104);
     let dyn_i64_col = cols[0].open().unwrap();
     let DynamicColumn::I64(divisor_col) = dyn_i64_col else {
         panic!();
@@ -91,6 +91,71 @@ fn test_dataframe_writer_u64_multivalued() {
     assert_eq!(divisor_col.num_docs(), 7);
 }
 
+#[test]
+fn test_dynamic_column_handle_values_codec() {
+    let mut dataframe_writer = ColumnarWriter::default();
+    for doc in 0..100u32 {
+        dataframe_writer.record_numerical(doc, "doc", doc as u64);
+    }
+    dataframe_writer.record_str(1u32, "my_string", "hello");
+    let mut buffer: Vec<u8> = Vec::new();
+    dataframe_writer.serialize(100, &mut buffer).unwrap();
+    let columnar = ColumnarReader::open(buffer).unwrap();
+    let doc_cols: Vec<DynamicColumnHandle> = columnar.read_columns("doc").unwrap();
+    assert!(doc_cols[0].values_codec().unwrap().is_some());
+    let str_cols: Vec<DynamicColumnHandle> = columnar.read_columns("my_string").unwrap();
+    assert_eq!(str_cols[0].values_codec().unwrap(), None);
+}
+
+#[test]
+fn test_dataframe_writer_pinned_codec() {
+    let mut dataframe_writer = ColumnarWriter::default();
+    for doc in 0..1_000u32 {
+        dataframe_writer.record_numerical(doc, "doc", doc as u64);
+        dataframe_writer.record_numerical(doc, "pinned_doc", doc as u64);
+    }
+    dataframe_writer.set_column_codec("pinned_doc", CodecType::Bitpacked);
+    let mut buffer: Vec<u8> = Vec::new();
+    dataframe_writer.serialize(1_000, &mut buffer).unwrap();
+    let columnar = ColumnarReader::open(buffer).unwrap();
+    let doc_cols: Vec<DynamicColumnHandle> = columnar.read_columns("doc").unwrap();
+    assert_eq!(
+        doc_cols[0].values_codec().unwrap(),
+        Some(CodecType::BlockwiseLinear)
+    );
+    let pinned_doc_cols: Vec<DynamicColumnHandle> = columnar.read_columns("pinned_doc").unwrap();
+    assert_eq!(
+        pinned_doc_cols[0].values_codec().unwrap(),
+        Some(CodecType::Bitpacked)
+    );
+}
+
+#[test]
+fn test_dataframe_writer_datetime_delta_of_delta() {
+    let mut dataframe_writer = ColumnarWriter::default();
+    // One event per second, with a jitter of a microsecond.
+    let mut timestamp_micros = 1_700_000_000_000_000i64;
+    for doc in 0..10_000u32 {
+        timestamp_micros += 1_000_000 - 1 + rand::random::<i64>().rem_euclid(3);
+        let datetime = DateTime::from_timestamp_micros(timestamp_micros);
+        dataframe_writer.record_datetime(doc, "timestamp", datetime);
+        dataframe_writer.record_numerical(doc, "timestamp_i64", timestamp_micros);
+    }
+    let mut buffer: Vec<u8> = Vec::new();
+    dataframe_writer.serialize(10_000, &mut buffer).unwrap();
+    let columnar = ColumnarReader::open(buffer).unwrap();
+    let datetime_cols: Vec<DynamicColumnHandle> = columnar.read_columns("timestamp").unwrap();
+    assert_eq!(
+        datetime_cols[0].values_codec().unwrap(),
+        Some(CodecType::DeltaOfDelta)
+    );
+    let i64_cols: Vec<DynamicColumnHandle> = columnar.read_columns("timestamp_i64").unwrap();
+    assert_ne!(
+        i64_cols[0].values_codec().unwrap(),
+        Some(CodecType::DeltaOfDelta)
+    );
+}
+
 #[test]
 fn test_dataframe_writer_ip_addr() {
     let mut dataframe_writer = ColumnarWriter::default();
@@ -102,7 +167,7 @@ fn test_dataframe_writer_ip_addr() {
     assert_eq!(columnar.num_columns(), 1);
     let cols: Vec<DynamicColumnHandle> = columnar.read_columns("ip_addr").unwrap();
     assert_eq!(cols.len(), 1);
-    assert_eq!(cols[0].num_bytes(), 42);
+    assert_eq!(cols[0].num_bytes(), 23);
     assert_eq!(cols[0].column_type(), ColumnType::IpAddr);
     let dyn_bool_col = cols[0].open().unwrap();
     let DynamicColumn::IpAddr(ip_col) = dyn_bool_col else {
@@ -138,7 +203,10 @@ fn test_dataframe_writer_numerical() {
     // - header 14 bytes
     // - vals  8 //< due to padding? could have been 1byte?.
     // - null footer 6 bytes
-    assert_eq!(cols[0].num_bytes(), 33);
+    // - block stats footer 1 byte
+    // - cardinality sketch 28 bytes
+    // - codec footer 10 bytes
+    assert_eq!(cols[0].num_bytes(), 72);
     let column = cols[0].open().unwrap();
     let DynamicColumn::I64(column_i64) = column else {
         panic!();
@@ -232,6 +300,42 @@ fn test_dictionary_encoded_bytes() {
     assert_eq!(term_buffer, b"b");
 }
 
+#[test]
+fn test_columnar_writer_serialize_with_num_threads() {
+    let build_columnar_writer = || {
+        let mut columnar_writer = ColumnarWriter::default();
+        for doc in 0..1_000u32 {
+            columnar_writer.record_numerical(doc, "num", (doc as u64) * 7 % 100);
+            columnar_writer.record_numerical(doc, "float", doc as f64 / 3.0);
+            columnar_writer.record_bool(doc, "bool", doc % 3 == 0);
+            columnar_writer.record_str(doc, "str", if doc % 2 == 0 { "even" } else { "odd" });
+            columnar_writer.record_ip_addr(doc, "ip", Ipv6Addr::from_u128(doc as u128));
+            if doc % 5 == 0 {
+                columnar_writer.record_bytes(doc, "bytes", &doc.to_le_bytes());
+                columnar_writer.record_datetime(
+                    doc,
+                    "datetime",
+                    DateTime::from_timestamp_secs(doc as i64),
+                );
+            }
+        }
+        columnar_writer
+    };
+    let mut expected_buffer = Vec::new();
+    build_columnar_writer()
+        .serialize(1_000, &mut expected_buffer)
+        .unwrap();
+    for num_threads in [2, 4, 16] {
+        let mut buffer = Vec::new();
+        build_columnar_writer()
+            .serialize_with_num_threads(1_000, num_threads, &mut buffer)
+            .unwrap();
+        assert_eq!(buffer, expected_buffer);
+    }
+    let columnar_reader = ColumnarReader::open(expected_buffer).unwrap();
+    assert_eq!(columnar_reader.num_columns(), 7);
+}
+
 fn num_strategy() -> impl Strategy<Value = NumericalValue> {
     prop_oneof![
         3 => Just(NumericalValue::U64(0u64)),
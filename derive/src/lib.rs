@@ -0,0 +1,179 @@
+//! `#[derive(TypedDocument)]`, re-exported by tantivy as `tantivy::schema::TypedDocument` with
+//! its `derive` feature.
+//!
+//! See the documentation of the `TypedDocument` trait for the supported attributes.
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::punctuated::Punctuated;
+use syn::spanned::Spanned;
+use syn::{Data, DeriveInput, Expr, ExprLit, Fields, Lit, Token, Type};
+
+/// Implements `tantivy::schema::TypedDocument` for a struct with named fields.
+#[proc_macro_derive(TypedDocument, attributes(tantivy))]
+pub fn derive_typed_document(input: TokenStream) -> TokenStream {
+    let input = syn::parse_macro_input!(input as DeriveInput);
+    expand(input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Mapping of a struct field to a schema field.
+struct FieldMapping {
+    ident: syn::Ident,
+    ty: Type,
+    field_name: String,
+    /// Field options, `STORED` if the attribute does not give any.
+    options: Expr,
+    skip: bool,
+}
+
+impl FieldMapping {
+    fn parse(field: &syn::Field) -> syn::Result<FieldMapping> {
+        let ident = field
+            .ident
+            .clone()
+            .ok_or_else(|| syn::Error::new(field.span(), "expected a named field"))?;
+        let mut mapping = FieldMapping {
+            field_name: ident.to_string(),
+            ident,
+            ty: field.ty.clone(),
+            options: syn::parse_quote!(STORED),
+            skip: false,
+        };
+        for attr in &field.attrs {
+            if !attr.path().is_ident("tantivy") {
+                continue;
+            }
+            let args = attr.parse_args_with(Punctuated::<Expr, Token![,]>::parse_terminated)?;
+            for arg in args {
+                mapping.apply(arg)?;
+            }
+        }
+        Ok(mapping)
+    }
+
+    fn apply(&mut self, arg: Expr) -> syn::Result<()> {
+        match arg {
+            Expr::Path(path) if path.path.is_ident("skip") => {
+                self.skip = true;
+            }
+            Expr::Assign(assign) => {
+                let is_name =
+                    matches!(&*assign.left, Expr::Path(path) if path.path.is_ident("name"));
+                match *assign.right {
+                    Expr::Lit(ExprLit {
+                        lit: Lit::Str(field_name),
+                        ..
+                    }) if is_name => {
+                        self.field_name = field_name.value();
+                    }
+                    _ => {
+                        return Err(syn::Error::new(
+                            assign.span(),
+                            "expected `name = \"<field name>\"`",
+                        ));
+                    }
+                }
+            }
+            options => {
+                self.options = options;
+            }
+        }
+        Ok(())
+    }
+}
+
+fn expand(input: DeriveInput) -> syn::Result<TokenStream2> {
+    let named_fields = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => &fields.named,
+            _ => {
+                return Err(syn::Error::new(
+                    input.ident.span(),
+                    "TypedDocument can only be derived for structs with named fields",
+                ));
+            }
+        },
+        _ => {
+            return Err(syn::Error::new(
+                input.ident.span(),
+                "TypedDocument can only be derived for structs",
+            ));
+        }
+    };
+    let mappings = named_fields
+        .iter()
+        .map(FieldMapping::parse)
+        .collect::<syn::Result<Vec<_>>>()?;
+
+    let mut add_fields = Vec::new();
+    let mut to_document = Vec::new();
+    let mut from_document = Vec::new();
+    for FieldMapping {
+        ident,
+        ty,
+        field_name,
+        options,
+        skip,
+    } in &mappings
+    {
+        if *skip {
+            from_document.push(quote! {
+                #ident: ::std::default::Default::default()
+            });
+            continue;
+        }
+        add_fields.push(quote! {
+            <#ty as ::tantivy::schema::TypedValue>::add_field(
+                schema_builder,
+                #field_name,
+                (#options).into(),
+            );
+        });
+        to_document.push(quote! {
+            ::tantivy::schema::TypedValue::add_to_document(
+                &self.#ident,
+                &mut document,
+                schema.get_field(#field_name)?,
+            );
+        });
+        from_document.push(quote! {
+            #ident: <#ty as ::tantivy::schema::TypedValue>::from_values(
+                #field_name,
+                document.get_all(schema.get_field(#field_name)?).collect(),
+            )?
+        });
+    }
+
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics ::tantivy::schema::TypedDocument for #name #ty_generics #where_clause {
+            fn add_fields(schema_builder: &mut ::tantivy::schema::SchemaBuilder) {
+                #[allow(unused_imports)]
+                use ::tantivy::schema::{COERCE, FAST, INDEXED, STORED, STRING, TEXT};
+                #(#add_fields)*
+            }
+
+            fn to_document(
+                &self,
+                schema: &::tantivy::schema::Schema,
+            ) -> ::tantivy::Result<::tantivy::schema::TantivyDocument> {
+                let mut document = ::tantivy::schema::TantivyDocument::new();
+                #(#to_document)*
+                Ok(document)
+            }
+
+            fn from_document(
+                document: &::tantivy::schema::TantivyDocument,
+                schema: &::tantivy::schema::Schema,
+            ) -> ::tantivy::Result<Self> {
+                Ok(#name {
+                    #(#from_document,)*
+                })
+            }
+        }
+    })
+}
@@ -0,0 +1,69 @@
+use tantivy::collector::TopDocs;
+use tantivy::query::TermQuery;
+use tantivy::schema::{IndexRecordOption, Term, TypedDocument};
+use tantivy::{DateTime, Index, IndexWriter};
+
+#[derive(TypedDocument, Debug, Default, PartialEq)]
+struct Article {
+    #[tantivy(TEXT | STORED)]
+    title: String,
+    #[tantivy(STRING | STORED, name = "article_author")]
+    author: String,
+    #[tantivy(INDEXED | FAST | STORED)]
+    views: u64,
+    score: Option<f64>,
+    #[tantivy(STRING | STORED)]
+    tags: Vec<String>,
+    published: DateTime,
+    #[tantivy(skip)]
+    cached_summary: Option<String>,
+}
+
+#[test]
+fn test_derived_schema() {
+    let schema = Article::schema();
+    assert!(schema.get_field("title").is_ok());
+    assert!(schema.get_field("author").is_err());
+    assert!(schema.get_field("cached_summary").is_err());
+    let views = schema.get_field_entry(schema.get_field("views").unwrap());
+    assert!(views.is_indexed() && views.is_fast() && views.is_stored());
+    let score = schema.get_field_entry(schema.get_field("score").unwrap());
+    assert!(score.is_stored() && !score.is_indexed());
+}
+
+#[test]
+fn test_derived_round_trip() -> tantivy::Result<()> {
+    let schema = Article::schema();
+    let index = Index::create_in_ram(schema.clone());
+    let mut index_writer: IndexWriter = index.writer(15_000_000)?;
+    let article = Article {
+        title: "Derive macros".to_string(),
+        author: "ferris".to_string(),
+        views: 42,
+        score: Some(0.5),
+        tags: vec!["rust".to_string(), "macros".to_string()],
+        published: DateTime::from_timestamp_secs(1_700_000_000),
+        cached_summary: Some("not indexed".to_string()),
+    };
+    index_writer.add_document(article.to_document(&schema)?)?;
+    index_writer.add_document(Article::default().to_document(&schema)?)?;
+    index_writer.commit()?;
+
+    let searcher = index.reader()?.searcher();
+    let author = schema.get_field("article_author")?;
+    let query = TermQuery::new(
+        Term::from_field_text(author, "ferris"),
+        IndexRecordOption::Basic,
+    );
+    let hits = searcher.search(&query, &TopDocs::with_limit(10))?;
+    assert_eq!(hits.len(), 1);
+    let fetched = Article::fetch(&searcher, hits[0].1)?;
+    assert_eq!(
+        fetched,
+        Article {
+            cached_summary: None,
+            ..article
+        }
+    );
+    Ok(())
+}
@@ -0,0 +1,78 @@
+/*
+ * C API of tantivy, available with the `capi` feature.
+ *
+ * Indexes, searchers and writers are opaque handles. Schemas, documents, search requests
+ * and search results are JSON strings: see the documentation of the `tantivy::capi` module
+ * for their format.
+ *
+ * Fallible functions take a `char **error` last argument. On failure, they return NULL, or
+ * -1 for the functions returning an integer, and, if `error` is not NULL, set `*error` to a
+ * message describing the error.
+ *
+ * Strings returned by the API, error messages included, must be freed with
+ * `tantivy_string_free`, and handles with the `free` function of their type.
+ *
+ * The library is built with
+ *
+ *     cargo rustc --release --lib --features capi --crate-type cdylib
+ *
+ * for `target/release/libtantivy.so`, or `--crate-type staticlib` for
+ * `target/release/libtantivy.a`.
+ */
+
+#ifndef TANTIVY_H
+#define TANTIVY_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+typedef struct TantivyIndex TantivyIndex;
+typedef struct TantivySearcher TantivySearcher;
+typedef struct TantivyWriter TantivyWriter;
+
+/* Indexes */
+
+TantivyIndex *tantivy_index_create_in_dir(const char *path, const char *schema_json,
+                                          char **error);
+TantivyIndex *tantivy_index_create_in_ram(const char *schema_json, char **error);
+TantivyIndex *tantivy_index_open_in_dir(const char *path, char **error);
+char *tantivy_index_schema(const TantivyIndex *index, char **error);
+/* Searchers and writers created from the index remain valid. */
+void tantivy_index_free(TantivyIndex *index);
+
+/* Searchers, snapshots of the index as of their creation */
+
+TantivySearcher *tantivy_index_searcher(const TantivyIndex *index, char **error);
+int64_t tantivy_searcher_num_docs(const TantivySearcher *searcher, char **error);
+char *tantivy_searcher_search(const TantivySearcher *searcher, const char *request_json,
+                              char **error);
+/* Doc addresses are only valid for the searcher which returned them. */
+char *tantivy_searcher_doc(const TantivySearcher *searcher, uint32_t segment_ord,
+                           uint32_t doc_id, char **error);
+void tantivy_searcher_free(TantivySearcher *searcher);
+
+/* Writers, returning opstamps */
+
+TantivyWriter *tantivy_writer_new(const TantivyIndex *index, size_t memory_budget_in_bytes,
+                                  char **error);
+int64_t tantivy_writer_add_document(TantivyWriter *writer, const char *document_json,
+                                    char **error);
+/* Terms of the query must be prefixed by their field, as in `id:42`. */
+int64_t tantivy_writer_delete_documents(TantivyWriter *writer, const char *query,
+                                        char **error);
+int64_t tantivy_writer_commit(TantivyWriter *writer, char **error);
+int64_t tantivy_writer_rollback(TantivyWriter *writer, char **error);
+/* Uncommitted changes are lost. */
+void tantivy_writer_free(TantivyWriter *writer);
+
+void tantivy_string_free(char *string);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* TANTIVY_H */
@@ -0,0 +1,9 @@
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    // The vendored protoc is used unless `PROTOC` points to another one, so that building
+    // the crate does not require protoc to be installed.
+    if std::env::var_os("PROTOC").is_none() {
+        std::env::set_var("PROTOC", protoc_bin_vendored::protoc_bin_path()?);
+    }
+    tonic_build::compile_protos("proto/tantivy.proto")?;
+    Ok(())
+}
@@ -0,0 +1,414 @@
+//! gRPC service exposing a tantivy index.
+//!
+//! [`IndexService`] implements the `Tantivy` service of `proto/tantivy.proto`: it searches the
+//! index with the queries of the [`QueryParser`], returns the top hits, their count and the
+//! results of aggregations, fetches stored documents and indexes documents given in JSON.
+//!
+//! The calls to tantivy run on the blocking thread pool of tokio, so that searches and commits
+//! do not stall the other requests.
+
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+
+use tantivy::aggregation::agg_req::Aggregations;
+use tantivy::aggregation::{AggregationCollector, AggregationLimits};
+use tantivy::collector::{Count, TopDocs};
+use tantivy::query::{Query, QueryParser};
+use tantivy::schema::FieldType;
+use tantivy::{
+    DocAddress, Document, Index, IndexReader, IndexWriter, ReloadPolicy, TantivyDocument,
+    TantivyError,
+};
+use tonic::{Request, Response, Status};
+
+/// Messages and service generated from `proto/tantivy.proto`.
+pub mod proto {
+    tonic::include_proto!("tantivy.serve.v1");
+}
+
+use self::proto::tantivy_server::{Tantivy, TantivyServer};
+
+/// Number of hits returned by a search without a limit.
+const DEFAULT_LIMIT: usize = 10;
+
+/// Serves the searches, document fetches and indexing requests of an index.
+pub struct IndexService {
+    index: Index,
+    reader: IndexReader,
+    writer: Arc<Mutex<IndexWriter>>,
+}
+
+impl IndexService {
+    /// Creates a service indexing documents with `writer`.
+    ///
+    /// The reader of the service is reloaded on each commit: the searches see the documents
+    /// committed through the service only.
+    pub fn new(index: Index, writer: IndexWriter) -> tantivy::Result<IndexService> {
+        let reader = index
+            .reader_builder()
+            .reload_policy(ReloadPolicy::Manual)
+            .try_into()?;
+        Ok(IndexService {
+            index,
+            reader,
+            writer: Arc::new(Mutex::new(writer)),
+        })
+    }
+
+    /// Wraps the service in a tonic server, to be added to a
+    /// [`Router`](tonic::transport::server::Router).
+    pub fn into_server(self) -> TantivyServer<IndexService> {
+        TantivyServer::new(self)
+    }
+
+    /// Serves the service on `addr` until the future is dropped or an error occurs.
+    pub async fn serve(self, addr: SocketAddr) -> Result<(), tonic::transport::Error> {
+        tonic::transport::Server::builder()
+            .add_service(self.into_server())
+            .serve(addr)
+            .await
+    }
+
+    fn parse_query(&self, query: Option<proto::Query>) -> Result<Box<dyn Query>, Status> {
+        let query = query.ok_or_else(|| Status::invalid_argument("missing query"))?;
+        let schema = self.index.schema();
+        let default_fields = if query.default_fields.is_empty() {
+            schema
+                .fields()
+                .filter(|(_, field_entry)| {
+                    field_entry.is_indexed()
+                        && matches!(
+                            field_entry.field_type(),
+                            FieldType::Str(_) | FieldType::JsonObject(_)
+                        )
+                })
+                .map(|(field, _)| field)
+                .collect()
+        } else {
+            query
+                .default_fields
+                .iter()
+                .map(|field_name| schema.get_field(field_name))
+                .collect::<tantivy::Result<Vec<_>>>()
+                .map_err(to_status)?
+        };
+        QueryParser::for_index(&self.index, default_fields)
+            .parse_query(&query.query)
+            .map_err(|query_parser_error| {
+                Status::invalid_argument(format!("invalid query: {query_parser_error}"))
+            })
+    }
+}
+
+#[tonic::async_trait]
+impl Tantivy for IndexService {
+    async fn search(
+        &self,
+        request: Request<proto::SearchRequest>,
+    ) -> Result<Response<proto::SearchResponse>, Status> {
+        let request = request.into_inner();
+        let query = self.parse_query(request.query)?;
+        let limit = match request.limit {
+            0 => DEFAULT_LIMIT,
+            limit => limit as usize,
+        };
+        let top_docs = TopDocs::with_limit(limit).and_offset(request.offset as usize);
+        let aggregation_collector = request
+            .aggregations_json
+            .map(|aggregations_json| {
+                let aggregations: Aggregations =
+                    serde_json::from_str(&aggregations_json).map_err(|err| {
+                        Status::invalid_argument(format!("invalid aggregations: {err}"))
+                    })?;
+                Ok::<_, Status>(AggregationCollector::from_aggs(
+                    aggregations,
+                    AggregationLimits::default(),
+                ))
+            })
+            .transpose()?;
+        let fetch_documents = request.fetch_documents;
+        let searcher = self.reader.searcher();
+        run_blocking(move || {
+            let (top_docs, count, aggregation_results) =
+                searcher.search(&query, &(top_docs, Count, aggregation_collector))?;
+            let hits = top_docs
+                .into_iter()
+                .map(|(score, doc_address)| {
+                    let document_json = if fetch_documents {
+                        let document: TantivyDocument = searcher.doc(doc_address)?;
+                        Some(document.to_json(searcher.schema()))
+                    } else {
+                        None
+                    };
+                    Ok(proto::Hit {
+                        score,
+                        address: Some(doc_address.into()),
+                        document_json,
+                    })
+                })
+                .collect::<tantivy::Result<Vec<_>>>()?;
+            let aggregation_results_json = aggregation_results
+                .map(|aggregation_results| serde_json::to_string(&aggregation_results))
+                .transpose()
+                .map_err(|err| TantivyError::InternalError(err.to_string()))?;
+            Ok(proto::SearchResponse {
+                searcher_generation: searcher.generation().generation_id(),
+                hits,
+                count: count as u64,
+                aggregation_results_json,
+            })
+        })
+        .await
+        .map(Response::new)
+    }
+
+    async fn get_documents(
+        &self,
+        request: Request<proto::GetDocumentsRequest>,
+    ) -> Result<Response<proto::GetDocumentsResponse>, Status> {
+        let request = request.into_inner();
+        let searcher = self.reader.searcher();
+        if searcher.generation().generation_id() != request.searcher_generation {
+            return Err(Status::failed_precondition(format!(
+                "searcher generation {} is not the current one ({})",
+                request.searcher_generation,
+                searcher.generation().generation_id()
+            )));
+        }
+        let mut doc_addresses = Vec::with_capacity(request.addresses.len());
+        for address in request.addresses {
+            let is_valid = searcher
+                .segment_readers()
+                .get(address.segment_ord as usize)
+                .map_or(false, |segment_reader| {
+                    address.doc_id < segment_reader.max_doc()
+                });
+            if !is_valid {
+                return Err(Status::invalid_argument(format!(
+                    "invalid doc address {address:?}"
+                )));
+            }
+            doc_addresses.push(DocAddress::new(address.segment_ord, address.doc_id));
+        }
+        run_blocking(move || {
+            let documents_json = doc_addresses
+                .into_iter()
+                .map(|doc_address| {
+                    let document: TantivyDocument = searcher.doc(doc_address)?;
+                    Ok(document.to_json(searcher.schema()))
+                })
+                .collect::<tantivy::Result<Vec<_>>>()?;
+            Ok(proto::GetDocumentsResponse { documents_json })
+        })
+        .await
+        .map(Response::new)
+    }
+
+    async fn add_documents(
+        &self,
+        request: Request<proto::AddDocumentsRequest>,
+    ) -> Result<Response<proto::AddDocumentsResponse>, Status> {
+        let schema = self.index.schema();
+        let documents = request
+            .into_inner()
+            .documents_json
+            .iter()
+            .enumerate()
+            .map(|(doc_ord, document_json)| {
+                TantivyDocument::parse_json(&schema, document_json).map_err(|err| {
+                    Status::invalid_argument(format!("invalid document #{doc_ord}: {err}"))
+                })
+            })
+            .collect::<Result<Vec<_>, Status>>()?;
+        let writer = self.writer.clone();
+        run_blocking(move || {
+            let writer = writer.lock().map_err(|_| TantivyError::Poisoned)?;
+            let mut opstamp = writer.commit_opstamp();
+            for document in documents {
+                opstamp = writer.add_document(document)?;
+            }
+            Ok(proto::AddDocumentsResponse { opstamp })
+        })
+        .await
+        .map(Response::new)
+    }
+
+    async fn delete_documents(
+        &self,
+        request: Request<proto::DeleteDocumentsRequest>,
+    ) -> Result<Response<proto::DeleteDocumentsResponse>, Status> {
+        let query = self.parse_query(request.into_inner().query)?;
+        let writer = self.writer.clone();
+        run_blocking(move || {
+            let writer = writer.lock().map_err(|_| TantivyError::Poisoned)?;
+            let opstamp = writer.delete_query(query)?;
+            Ok(proto::DeleteDocumentsResponse { opstamp })
+        })
+        .await
+        .map(Response::new)
+    }
+
+    async fn commit(
+        &self,
+        _request: Request<proto::CommitRequest>,
+    ) -> Result<Response<proto::CommitResponse>, Status> {
+        let writer = self.writer.clone();
+        let reader = self.reader.clone();
+        run_blocking(move || {
+            let opstamp = writer
+                .lock()
+                .map_err(|_| TantivyError::Poisoned)?
+                .commit()?;
+            reader.reload()?;
+            Ok(proto::CommitResponse {
+                opstamp,
+                searcher_generation: reader.searcher().generation().generation_id(),
+            })
+        })
+        .await
+        .map(Response::new)
+    }
+}
+
+impl From<DocAddress> for proto::DocAddress {
+    fn from(doc_address: DocAddress) -> proto::DocAddress {
+        proto::DocAddress {
+            segment_ord: doc_address.segment_ord,
+            doc_id: doc_address.doc_id,
+        }
+    }
+}
+
+/// Runs `f` on the blocking thread pool.
+async fn run_blocking<T, F>(f: F) -> Result<T, Status>
+where
+    T: Send + 'static,
+    F: FnOnce() -> tantivy::Result<T> + Send + 'static,
+{
+    tokio::task::spawn_blocking(f)
+        .await
+        .map_err(|join_error| Status::internal(join_error.to_string()))?
+        .map_err(to_status)
+}
+
+fn to_status(tantivy_error: TantivyError) -> Status {
+    match tantivy_error {
+        TantivyError::FieldNotFound(_)
+        | TantivyError::InvalidArgument(_)
+        | TantivyError::SchemaError(_)
+        | TantivyError::AggregationError(_) => Status::invalid_argument(tantivy_error.to_string()),
+        _ => Status::internal(tantivy_error.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tantivy::schema::{Schema, FAST, STORED, STRING, TEXT};
+    use tantivy::Index;
+    use tonic::{Code, Request};
+
+    use super::proto::tantivy_server::Tantivy;
+    use super::{proto, IndexService};
+
+    fn query(query: &str) -> Option<proto::Query> {
+        Some(proto::Query {
+            query: query.to_string(),
+            default_fields: Vec::new(),
+        })
+    }
+
+    #[tokio::test]
+    async fn test_index_service() -> Result<(), Box<dyn std::error::Error>> {
+        let mut schema_builder = Schema::builder();
+        schema_builder.add_text_field("title", TEXT | STORED);
+        schema_builder.add_text_field("tag", STRING | FAST);
+        let index = Index::create_in_ram(schema_builder.build());
+        let writer = index.writer_with_num_threads(1, 15_000_000)?;
+        let service = IndexService::new(index, writer)?;
+
+        service
+            .add_documents(Request::new(proto::AddDocumentsRequest {
+                documents_json: vec![
+                    r#"{"title": "The Old Man and the Sea", "tag": "novel"}"#.to_string(),
+                    r#"{"title": "A Moveable Feast", "tag": "memoir"}"#.to_string(),
+                    r#"{"title": "The Sun Also Rises", "tag": "novel"}"#.to_string(),
+                ],
+            }))
+            .await?;
+        let commit_response = service
+            .commit(Request::new(proto::CommitRequest {}))
+            .await?
+            .into_inner();
+
+        let search_response = service
+            .search(Request::new(proto::SearchRequest {
+                query: query("the"),
+                limit: 1,
+                fetch_documents: true,
+                aggregations_json: Some(r#"{"tags": {"terms": {"field": "tag"}}}"#.to_string()),
+                ..Default::default()
+            }))
+            .await?
+            .into_inner();
+        assert_eq!(
+            search_response.searcher_generation,
+            commit_response.searcher_generation
+        );
+        assert_eq!(search_response.count, 2);
+        assert_eq!(search_response.hits.len(), 1);
+        let document_json = search_response.hits[0].document_json.as_ref().unwrap();
+        assert!(document_json.contains("\"title\""));
+        let aggregation_results: serde_json::Value =
+            serde_json::from_str(search_response.aggregation_results_json.as_ref().unwrap())?;
+        assert_eq!(aggregation_results["tags"]["buckets"][0]["key"], "novel");
+        assert_eq!(aggregation_results["tags"]["buckets"][0]["doc_count"], 2);
+
+        let get_documents_response = service
+            .get_documents(Request::new(proto::GetDocumentsRequest {
+                searcher_generation: search_response.searcher_generation,
+                addresses: vec![search_response.hits[0].address.clone().unwrap()],
+            }))
+            .await?
+            .into_inner();
+        assert_eq!(
+            get_documents_response.documents_json,
+            [document_json.clone()]
+        );
+
+        let invalid_query = service
+            .search(Request::new(proto::SearchRequest {
+                query: query("title:("),
+                ..Default::default()
+            }))
+            .await
+            .unwrap_err();
+        assert_eq!(invalid_query.code(), Code::InvalidArgument);
+
+        service
+            .delete_documents(Request::new(proto::DeleteDocumentsRequest {
+                query: query("tag:novel"),
+            }))
+            .await?;
+        service
+            .commit(Request::new(proto::CommitRequest {}))
+            .await?;
+        let search_response = service
+            .search(Request::new(proto::SearchRequest {
+                query: query("*"),
+                ..Default::default()
+            }))
+            .await?
+            .into_inner();
+        assert_eq!(search_response.count, 1);
+        // The doc addresses of the previous generation are not valid anymore.
+        let stale_generation = service
+            .get_documents(Request::new(proto::GetDocumentsRequest {
+                searcher_generation: commit_response.searcher_generation,
+                addresses: Vec::new(),
+            }))
+            .await
+            .unwrap_err();
+        assert_eq!(stale_generation.code(), Code::FailedPrecondition);
+        Ok(())
+    }
+}
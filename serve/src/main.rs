@@ -0,0 +1,29 @@
+//! Serves an index directory over gRPC.
+//!
+//! Usage: `tantivy-serve <index-dir> [<addr>]`
+
+use std::net::SocketAddr;
+
+use tantivy::{Index, IndexWriter};
+use tantivy_serve::IndexService;
+
+const DEFAULT_ADDR: &str = "127.0.0.1:50051";
+
+/// Memory budget of the index writer, shared by its indexing threads.
+const MEMORY_BUDGET_IN_BYTES: usize = 200_000_000;
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let mut args = std::env::args().skip(1);
+    let Some(index_dir) = args.next() else {
+        eprintln!("Usage: tantivy-serve <index-dir> [<addr>]");
+        std::process::exit(1);
+    };
+    let addr: SocketAddr = args.next().as_deref().unwrap_or(DEFAULT_ADDR).parse()?;
+    let index = Index::open_in_dir(&index_dir)?;
+    let writer: IndexWriter = index.writer(MEMORY_BUDGET_IN_BYTES)?;
+    let service = IndexService::new(index, writer)?;
+    println!("Serving {index_dir} on {addr}");
+    service.serve(addr).await?;
+    Ok(())
+}
@@ -1,3 +1,5 @@
+use std::fmt;
+
 use super::agg_req::Aggregations;
 use super::agg_req_with_accessor::AggregationsWithAccessor;
 use super::agg_result::AggregationResults;
@@ -25,6 +27,15 @@ pub struct AggregationCollector {
     limits: AggregationLimits,
 }
 
+impl fmt::Debug for AggregationCollector {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        // The keys of a JSON value are sorted, so that equal requests are represented the same
+        // way, as required by `IndexReader::search_cached`.
+        let agg = serde_json::to_value(&self.agg).map_err(|_| fmt::Error)?;
+        write!(f, "AggregationCollector({agg})")
+    }
+}
+
 impl AggregationCollector {
     /// Create collector from aggregation request.
     ///
@@ -48,6 +59,13 @@ pub struct DistributedAggregationCollector {
     limits: AggregationLimits,
 }
 
+impl fmt::Debug for DistributedAggregationCollector {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let agg = serde_json::to_value(&self.agg).map_err(|_| fmt::Error)?;
+        write!(f, "DistributedAggregationCollector({agg})")
+    }
+}
+
 impl DistributedAggregationCollector {
     /// Create collector from aggregation request.
     ///
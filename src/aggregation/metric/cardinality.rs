@@ -253,7 +253,7 @@ impl SegmentAggregationCollector for SegmentCardinalityCollector {
                     )
                 })?;
             for val in col_block_accessor.iter_vals() {
-                let val: u128 = compact_space_accessor.compact_to_u128(val as u32);
+                let val: u128 = compact_space_accessor.compact_to_u128(val);
                 self.cardinality.sketch.insert_any(&val);
             }
         } else {
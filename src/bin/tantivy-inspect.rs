@@ -0,0 +1,237 @@
+//! Inspects and maintains an index directory, built with the `inspect` feature.
+//!
+//! ```text
+//! tantivy-inspect segments <index-dir>
+//! tantivy-inspect terms <index-dir> <field> [<limit>]
+//! tantivy-inspect fast-fields <index-dir>
+//! tantivy-inspect validate <index-dir>
+//! tantivy-inspect merge <index-dir>
+//! tantivy-inspect gc <index-dir>
+//! ```
+//!
+//! `merge` and `gc` acquire the index writer lock, and fail if another process is writing to
+//! the index.
+
+use std::process::ExitCode;
+
+use tantivy::space_usage::PerFieldSpaceUsage;
+use tantivy::{Index, IndexWriter, Searcher, TantivyDocument};
+
+const USAGE: &str = "Usage:
+    tantivy-inspect segments <index-dir>
+    tantivy-inspect terms <index-dir> <field> [<limit>]
+    tantivy-inspect fast-fields <index-dir>
+    tantivy-inspect validate <index-dir>
+    tantivy-inspect merge <index-dir>
+    tantivy-inspect gc <index-dir>";
+
+/// Number of terms printed per segment by `terms` without a limit.
+const DEFAULT_NUM_TERMS: usize = 20;
+
+/// Memory budget of the index writer used by `merge` and `gc`, which do not index documents.
+const MEMORY_BUDGET_IN_BYTES: usize = 50_000_000;
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+    let result = match args.as_slice() {
+        ["segments", index_dir] => {
+            open_searcher(index_dir).and_then(|searcher| segments(&searcher))
+        }
+        ["terms", index_dir, field_name] => open_searcher(index_dir)
+            .and_then(|searcher| terms(&searcher, field_name, DEFAULT_NUM_TERMS)),
+        ["terms", index_dir, field_name, limit] => match limit.parse() {
+            Ok(limit) => {
+                open_searcher(index_dir).and_then(|searcher| terms(&searcher, field_name, limit))
+            }
+            Err(_) => {
+                eprintln!("Invalid limit: {limit}");
+                return ExitCode::FAILURE;
+            }
+        },
+        ["fast-fields", index_dir] => {
+            open_searcher(index_dir).and_then(|searcher| fast_fields(&searcher))
+        }
+        ["validate", index_dir] => validate(index_dir),
+        ["merge", index_dir] => merge(index_dir),
+        ["gc", index_dir] => garbage_collect(index_dir),
+        _ => {
+            eprintln!("{USAGE}");
+            return ExitCode::FAILURE;
+        }
+    };
+    match result {
+        Ok(true) => ExitCode::SUCCESS,
+        Ok(false) => ExitCode::FAILURE,
+        Err(err) => {
+            eprintln!("Error: {err}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn open_searcher(index_dir: &str) -> tantivy::Result<Searcher> {
+    Ok(Index::open_in_dir(index_dir)?.reader()?.searcher())
+}
+
+fn print_per_field(searcher: &Searcher, component: &str, space_usage: &PerFieldSpaceUsage) {
+    println!("    {component:<12} {}", space_usage.total());
+    for (field, field_usage) in space_usage.fields() {
+        println!(
+            "      {:<20} {}",
+            searcher.schema().get_field_name(*field),
+            field_usage.total()
+        );
+    }
+}
+
+/// Lists the segments, with the space usage of their components.
+fn segments(searcher: &Searcher) -> tantivy::Result<bool> {
+    let space_usage = searcher.space_usage()?;
+    println!(
+        "{} segments, {} documents, {}",
+        searcher.segment_readers().len(),
+        searcher.num_docs(),
+        space_usage.total()
+    );
+    for (segment_reader, segment_usage) in searcher
+        .segment_readers()
+        .iter()
+        .zip(space_usage.segments())
+    {
+        println!(
+            "{}: {} documents, {} deleted, {}",
+            segment_reader.segment_id().uuid_string(),
+            segment_reader.num_docs(),
+            segment_reader.num_deleted_docs(),
+            segment_usage.total()
+        );
+        print_per_field(searcher, "terms", segment_usage.termdict());
+        print_per_field(searcher, "postings", segment_usage.postings());
+        print_per_field(searcher, "positions", segment_usage.positions());
+        print_per_field(searcher, "fast fields", segment_usage.fast_fields());
+        print_per_field(searcher, "fieldnorms", segment_usage.fieldnorms());
+        print_per_field(searcher, "vectors", segment_usage.vector_index());
+        println!("    {:<12} {}", "store", segment_usage.store().total());
+        println!("    {:<12} {}", "blobs", segment_usage.blobs());
+        println!(
+            "    {:<12} {}",
+            "term vectors",
+            segment_usage.term_vectors()
+        );
+        println!("    {:<12} {}", "deletes", segment_usage.deletes());
+    }
+    Ok(true)
+}
+
+/// Prints the first terms of `field_name` in each segment, with their document frequency.
+fn terms(searcher: &Searcher, field_name: &str, limit: usize) -> tantivy::Result<bool> {
+    let field = searcher.schema().get_field(field_name)?;
+    for segment_reader in searcher.segment_readers() {
+        let inverted_index = segment_reader.inverted_index(field)?;
+        let term_dict = inverted_index.terms();
+        println!(
+            "{}: {} terms",
+            segment_reader.segment_id().uuid_string(),
+            term_dict.num_terms()
+        );
+        let mut term_stream = term_dict.stream()?;
+        let mut num_printed = 0;
+        while num_printed < limit && term_stream.advance() {
+            let key = term_stream.key();
+            match std::str::from_utf8(key) {
+                Ok(text) => print!("    {text:?}"),
+                Err(_) => print!("    {key:?}"),
+            }
+            println!(" doc_freq={}", term_stream.value().doc_freq);
+            num_printed += 1;
+        }
+    }
+    Ok(true)
+}
+
+/// Prints the columns of the fast fields, with their size and the codec of their values.
+fn fast_fields(searcher: &Searcher) -> tantivy::Result<bool> {
+    let schema = searcher.schema();
+    for segment_reader in searcher.segment_readers() {
+        println!("{}:", segment_reader.segment_id().uuid_string());
+        let fast_fields = segment_reader.fast_fields();
+        for (_, field_entry) in schema.fields() {
+            if !field_entry.is_fast() {
+                continue;
+            }
+            for column_handle in fast_fields.dynamic_column_handles(field_entry.name())? {
+                let column_type = format!("{:?}", column_handle.column_type());
+                let codec = match column_handle.values_codec()? {
+                    Some(codec_type) => format!("{codec_type:?}"),
+                    None => "-".to_string(),
+                };
+                println!(
+                    "    {:<20} {:<10} {:<16} {}",
+                    field_entry.name(),
+                    column_type,
+                    codec,
+                    column_handle.num_bytes()
+                );
+            }
+        }
+    }
+    Ok(true)
+}
+
+/// Validates the checksums of the files of the searchable segments.
+fn validate(index_dir: &str) -> tantivy::Result<bool> {
+    let index = Index::open_in_dir(index_dir)?;
+    let mut corrupted_files: Vec<_> = index.validate_checksum()?.into_iter().collect();
+    if corrupted_files.is_empty() {
+        println!("All checksums are valid.");
+        return Ok(true);
+    }
+    corrupted_files.sort();
+    for corrupted_file in corrupted_files {
+        println!("Invalid checksum: {}", corrupted_file.display());
+    }
+    Ok(false)
+}
+
+/// Merges all the searchable segments into one, then removes the files that are not used
+/// anymore.
+fn merge(index_dir: &str) -> tantivy::Result<bool> {
+    let index = Index::open_in_dir(index_dir)?;
+    let segment_ids = index.searchable_segment_ids()?;
+    if segment_ids.len() < 2 {
+        println!("Nothing to merge: {} segment(s).", segment_ids.len());
+        return Ok(true);
+    }
+    let mut index_writer: IndexWriter<TantivyDocument> = index.writer(MEMORY_BUDGET_IN_BYTES)?;
+    let segment_meta = index_writer.merge(&segment_ids).wait()?;
+    index_writer.garbage_collect_files().wait()?;
+    index_writer.wait_merging_threads()?;
+    match segment_meta {
+        Some(segment_meta) => println!(
+            "Merged {} segments into {}.",
+            segment_ids.len(),
+            segment_meta.id().uuid_string()
+        ),
+        None => println!(
+            "Merged {} segments, all documents were deleted.",
+            segment_ids.len()
+        ),
+    }
+    Ok(true)
+}
+
+/// Removes the files which are not used by the index anymore.
+fn garbage_collect(index_dir: &str) -> tantivy::Result<bool> {
+    let index = Index::open_in_dir(index_dir)?;
+    let index_writer: IndexWriter<TantivyDocument> = index.writer(MEMORY_BUDGET_IN_BYTES)?;
+    let gc_result = index_writer.garbage_collect_files().wait()?;
+    index_writer.wait_merging_threads()?;
+    for deleted_file in &gc_result.deleted_files {
+        println!("Deleted {}", deleted_file.display());
+    }
+    for failed_file in &gc_result.failed_to_delete_files {
+        println!("Failed to delete {}", failed_file.display());
+    }
+    Ok(gc_result.failed_to_delete_files.is_empty())
+}
@@ -0,0 +1,550 @@
+//! C API, enabled with the `capi` feature.
+//!
+//! The functions of this module have a C calling convention and are declared in
+//! `include/tantivy.h`. They make it possible to use tantivy from another language without
+//! wrapping its types: indexes, searchers and writers are exposed as opaque handles, while
+//! schemas, documents, search requests and search results are exchanged as JSON strings.
+//!
+//! Schemas use the format of the `schema` of `meta.json`, and documents the format of
+//! [`TantivyDocument::parse_json`]. A search request looks like
+//!
+//! ```json
+//! {
+//!   "query": "title:hello",
+//!   "default_fields": ["title", "body"],
+//!   "limit": 10,
+//!   "offset": 0,
+//!   "fetch_documents": true,
+//!   "aggregations": { "by_tag": { "terms": { "field": "tag" } } }
+//! }
+//! ```
+//!
+//! where all the fields but `query` are optional, and returns
+//! `{"count": 1, "hits": [{"score": 1.0, "segment_ord": 0, "doc_id": 3, "document": {...}}]}`.
+//!
+//! # Errors and memory
+//!
+//! Fallible functions take a `char **error` last argument. On failure, they return `NULL`, or
+//! `-1` for the functions returning an integer, and, if `error` is not `NULL`, set `*error` to
+//! a message describing the error. Panics are caught and reported the same way.
+//!
+//! Strings returned by the API, error messages included, must be freed with
+//! [`tantivy_string_free`], and handles with the `free` function of their type. String
+//! arguments must be valid, nul-terminated UTF-8, and handles must come from this API: the
+//! functions are `unsafe` to call for that reason.
+//!
+//! # Building
+//!
+//! The library to link against is built with `cargo rustc`, which is what sets its crate type:
+//!
+//! ```sh
+//! cargo rustc --release --lib --features capi --crate-type cdylib
+//! ```
+//!
+//! builds `target/release/libtantivy.so` (`.dylib` on macOS, `tantivy.dll` on Windows), and
+//! `--crate-type staticlib` builds `target/release/libtantivy.a` instead. A static library
+//! also needs the system libraries it depends on, which
+//! `cargo rustc ... --crate-type staticlib -- --print native-static-libs` lists.
+#![allow(clippy::missing_safety_doc)]
+
+use std::ffi::{c_char, CStr, CString};
+use std::panic::{self, AssertUnwindSafe};
+use std::path::Path;
+use std::ptr;
+
+use serde::{Deserialize, Serialize};
+
+use crate::aggregation::agg_req::Aggregations;
+use crate::aggregation::agg_result::AggregationResults;
+use crate::aggregation::{AggregationCollector, AggregationLimits};
+use crate::collector::{Count, TopDocs};
+use crate::query::{Query, QueryParser};
+use crate::schema::{FieldType, NamedFieldDocument, Schema};
+use crate::{
+    DocAddress, Document, Index, IndexReader, IndexWriter, ReloadPolicy, Searcher, TantivyDocument,
+    TantivyError,
+};
+
+/// Index handle, holding the reader shared by the searchers created from it.
+pub struct TantivyIndex {
+    index: Index,
+    reader: IndexReader,
+}
+
+/// Searcher handle, a snapshot of the index as of its creation.
+///
+/// Doc addresses returned by a search are only valid for the searcher that ran it.
+pub struct TantivySearcher {
+    searcher: Searcher,
+}
+
+/// Index writer handle.
+pub struct TantivyWriter {
+    writer: IndexWriter,
+    reader: IndexReader,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SearchRequest {
+    query: String,
+    #[serde(default)]
+    default_fields: Vec<String>,
+    #[serde(default = "default_limit")]
+    limit: usize,
+    #[serde(default)]
+    offset: usize,
+    #[serde(default)]
+    fetch_documents: bool,
+    #[serde(default)]
+    aggregations: Option<Aggregations>,
+}
+
+fn default_limit() -> usize {
+    10
+}
+
+#[derive(Serialize)]
+struct SearchResponse {
+    count: usize,
+    hits: Vec<Hit>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    aggregations: Option<AggregationResults>,
+}
+
+#[derive(Serialize)]
+struct Hit {
+    score: f32,
+    segment_ord: u32,
+    doc_id: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    document: Option<NamedFieldDocument>,
+}
+
+/// Runs `f`, reporting its error or panic through `error_out` and returning `on_error` then.
+unsafe fn ffi_call<T>(
+    error_out: *mut *mut c_char,
+    on_error: T,
+    f: impl FnOnce() -> crate::Result<T>,
+) -> T {
+    let message = match panic::catch_unwind(AssertUnwindSafe(f)) {
+        Ok(Ok(value)) => return value,
+        Ok(Err(error)) => error.to_string(),
+        Err(panic) => {
+            let panic_message = panic
+                .downcast_ref::<&str>()
+                .map(|message| message.to_string())
+                .or_else(|| panic.downcast_ref::<String>().cloned())
+                .unwrap_or_default();
+            format!("panic: {panic_message}")
+        }
+    };
+    if !error_out.is_null() {
+        // Nul bytes are stripped, so that the conversion cannot fail.
+        let message = CString::new(message.replace('\0', "")).unwrap_or_default();
+        *error_out = message.into_raw();
+    }
+    on_error
+}
+
+unsafe fn handle<'a, T>(ptr: *const T, name: &str) -> crate::Result<&'a T> {
+    ptr.as_ref()
+        .ok_or_else(|| TantivyError::InvalidArgument(format!("{name} is NULL")))
+}
+
+unsafe fn handle_mut<'a, T>(ptr: *mut T, name: &str) -> crate::Result<&'a mut T> {
+    ptr.as_mut()
+        .ok_or_else(|| TantivyError::InvalidArgument(format!("{name} is NULL")))
+}
+
+unsafe fn str_arg<'a>(arg: *const c_char, name: &str) -> crate::Result<&'a str> {
+    if arg.is_null() {
+        return Err(TantivyError::InvalidArgument(format!("{name} is NULL")));
+    }
+    CStr::from_ptr(arg)
+        .to_str()
+        .map_err(|_| TantivyError::InvalidArgument(format!("{name} is not valid UTF-8")))
+}
+
+fn into_c_string(string: String) -> crate::Result<*mut c_char> {
+    CString::new(string)
+        .map(CString::into_raw)
+        .map_err(|err| TantivyError::InternalError(err.to_string()))
+}
+
+fn new_index_handle(index: Index) -> crate::Result<*mut TantivyIndex> {
+    // Writers created through the API reload the reader on commit, the delay only matters
+    // for commits made by another process.
+    let reader = index
+        .reader_builder()
+        .reload_policy(ReloadPolicy::OnCommitWithDelay)
+        .try_into()?;
+    Ok(Box::into_raw(Box::new(TantivyIndex { index, reader })))
+}
+
+/// Creates an index in the directory at `path`, with the schema given in JSON.
+#[no_mangle]
+pub unsafe extern "C" fn tantivy_index_create_in_dir(
+    path: *const c_char,
+    schema_json: *const c_char,
+    error: *mut *mut c_char,
+) -> *mut TantivyIndex {
+    ffi_call(error, ptr::null_mut(), || {
+        let path = str_arg(path, "path")?;
+        let schema: Schema = serde_json::from_str(str_arg(schema_json, "schema_json")?)?;
+        new_index_handle(Index::create_in_dir(Path::new(path), schema)?)
+    })
+}
+
+/// Creates an index in memory, with the schema given in JSON.
+#[no_mangle]
+pub unsafe extern "C" fn tantivy_index_create_in_ram(
+    schema_json: *const c_char,
+    error: *mut *mut c_char,
+) -> *mut TantivyIndex {
+    ffi_call(error, ptr::null_mut(), || {
+        let schema: Schema = serde_json::from_str(str_arg(schema_json, "schema_json")?)?;
+        new_index_handle(Index::create_in_ram(schema))
+    })
+}
+
+/// Opens the index in the directory at `path`.
+#[no_mangle]
+pub unsafe extern "C" fn tantivy_index_open_in_dir(
+    path: *const c_char,
+    error: *mut *mut c_char,
+) -> *mut TantivyIndex {
+    ffi_call(error, ptr::null_mut(), || {
+        let path = str_arg(path, "path")?;
+        new_index_handle(Index::open_in_dir(Path::new(path))?)
+    })
+}
+
+/// Returns the schema of the index, in JSON.
+#[no_mangle]
+pub unsafe extern "C" fn tantivy_index_schema(
+    index: *const TantivyIndex,
+    error: *mut *mut c_char,
+) -> *mut c_char {
+    ffi_call(error, ptr::null_mut(), || {
+        let index = handle(index, "index")?;
+        into_c_string(serde_json::to_string(&index.index.schema())?)
+    })
+}
+
+/// Frees an index handle. Searchers and writers created from it remain valid.
+#[no_mangle]
+pub unsafe extern "C" fn tantivy_index_free(index: *mut TantivyIndex) {
+    if !index.is_null() {
+        drop(Box::from_raw(index));
+    }
+}
+
+/// Returns a searcher on the last commit of the index.
+#[no_mangle]
+pub unsafe extern "C" fn tantivy_index_searcher(
+    index: *const TantivyIndex,
+    error: *mut *mut c_char,
+) -> *mut TantivySearcher {
+    ffi_call(error, ptr::null_mut(), || {
+        let index = handle(index, "index")?;
+        let searcher = index.reader.searcher();
+        Ok(Box::into_raw(Box::new(TantivySearcher { searcher })))
+    })
+}
+
+/// Returns the number of documents of the searcher, or -1 on error.
+#[no_mangle]
+pub unsafe extern "C" fn tantivy_searcher_num_docs(
+    searcher: *const TantivySearcher,
+    error: *mut *mut c_char,
+) -> i64 {
+    ffi_call(error, -1, || {
+        let searcher = handle(searcher, "searcher")?;
+        Ok(searcher.searcher.num_docs() as i64)
+    })
+}
+
+fn parse_query(
+    searcher: &Searcher,
+    query: &str,
+    default_fields: &[String],
+) -> crate::Result<Box<dyn Query>> {
+    let schema = searcher.schema();
+    let default_fields = if default_fields.is_empty() {
+        schema
+            .fields()
+            .filter(|(_, field_entry)| {
+                field_entry.is_indexed()
+                    && matches!(
+                        field_entry.field_type(),
+                        FieldType::Str(_) | FieldType::JsonObject(_)
+                    )
+            })
+            .map(|(field, _)| field)
+            .collect()
+    } else {
+        default_fields
+            .iter()
+            .map(|field_name| schema.get_field(field_name))
+            .collect::<crate::Result<Vec<_>>>()?
+    };
+    let query_parser = QueryParser::for_index(searcher.index(), default_fields);
+    Ok(query_parser.parse_query(query)?)
+}
+
+fn search(searcher: &Searcher, request: SearchRequest) -> crate::Result<SearchResponse> {
+    let query = parse_query(searcher, &request.query, &request.default_fields)?;
+    let top_docs = TopDocs::with_limit(request.limit).and_offset(request.offset);
+    let aggregation_collector = request.aggregations.map(|aggregations| {
+        AggregationCollector::from_aggs(aggregations, AggregationLimits::default())
+    });
+    let (top_docs, count, aggregations) =
+        searcher.search(&query, &(top_docs, Count, aggregation_collector))?;
+    let hits = top_docs
+        .into_iter()
+        .map(|(score, doc_address)| {
+            let document = if request.fetch_documents {
+                let document: TantivyDocument = searcher.doc(doc_address)?;
+                Some(document.to_named_doc(searcher.schema()))
+            } else {
+                None
+            };
+            Ok(Hit {
+                score,
+                segment_ord: doc_address.segment_ord,
+                doc_id: doc_address.doc_id,
+                document,
+            })
+        })
+        .collect::<crate::Result<Vec<_>>>()?;
+    Ok(SearchResponse {
+        count,
+        hits,
+        aggregations,
+    })
+}
+
+/// Runs the search request given in JSON, and returns its results in JSON.
+#[no_mangle]
+pub unsafe extern "C" fn tantivy_searcher_search(
+    searcher: *const TantivySearcher,
+    request_json: *const c_char,
+    error: *mut *mut c_char,
+) -> *mut c_char {
+    ffi_call(error, ptr::null_mut(), || {
+        let searcher = handle(searcher, "searcher")?;
+        let request: SearchRequest = serde_json::from_str(str_arg(request_json, "request_json")?)?;
+        let response = search(&searcher.searcher, request)?;
+        into_c_string(serde_json::to_string(&response)?)
+    })
+}
+
+/// Returns the stored fields of the document at the given address, in JSON.
+#[no_mangle]
+pub unsafe extern "C" fn tantivy_searcher_doc(
+    searcher: *const TantivySearcher,
+    segment_ord: u32,
+    doc_id: u32,
+    error: *mut *mut c_char,
+) -> *mut c_char {
+    ffi_call(error, ptr::null_mut(), || {
+        let searcher = &handle(searcher, "searcher")?.searcher;
+        let is_valid = searcher
+            .segment_readers()
+            .get(segment_ord as usize)
+            .map_or(false, |segment_reader| doc_id < segment_reader.max_doc());
+        if !is_valid {
+            return Err(TantivyError::InvalidArgument(format!(
+                "invalid doc address ({segment_ord}, {doc_id})"
+            )));
+        }
+        let document: TantivyDocument = searcher.doc(DocAddress::new(segment_ord, doc_id))?;
+        into_c_string(document.to_json(searcher.schema()))
+    })
+}
+
+/// Frees a searcher handle.
+#[no_mangle]
+pub unsafe extern "C" fn tantivy_searcher_free(searcher: *mut TantivySearcher) {
+    if !searcher.is_null() {
+        drop(Box::from_raw(searcher));
+    }
+}
+
+/// Creates a writer for the index, with a memory budget shared by its indexing threads.
+#[no_mangle]
+pub unsafe extern "C" fn tantivy_writer_new(
+    index: *const TantivyIndex,
+    memory_budget_in_bytes: usize,
+    error: *mut *mut c_char,
+) -> *mut TantivyWriter {
+    ffi_call(error, ptr::null_mut(), || {
+        let index = handle(index, "index")?;
+        let writer = index.index.writer(memory_budget_in_bytes)?;
+        Ok(Box::into_raw(Box::new(TantivyWriter {
+            writer,
+            reader: index.reader.clone(),
+        })))
+    })
+}
+
+/// Adds the document given in JSON, and returns its opstamp, or -1 on error.
+#[no_mangle]
+pub unsafe extern "C" fn tantivy_writer_add_document(
+    writer: *mut TantivyWriter,
+    document_json: *const c_char,
+    error: *mut *mut c_char,
+) -> i64 {
+    ffi_call(error, -1, || {
+        let writer = &handle_mut(writer, "writer")?.writer;
+        let document_json = str_arg(document_json, "document_json")?;
+        let document = TantivyDocument::parse_json(&writer.index().schema(), document_json)?;
+        Ok(writer.add_document(document)? as i64)
+    })
+}
+
+/// Deletes the documents matching `query`, and returns the opstamp of the deletion, or -1 on
+/// error.
+///
+/// The query has no default field: its terms must be prefixed by their field, as in
+/// `id:42`.
+#[no_mangle]
+pub unsafe extern "C" fn tantivy_writer_delete_documents(
+    writer: *mut TantivyWriter,
+    query: *const c_char,
+    error: *mut *mut c_char,
+) -> i64 {
+    ffi_call(error, -1, || {
+        let writer = &handle_mut(writer, "writer")?.writer;
+        let query = QueryParser::for_index(writer.index(), Vec::new())
+            .parse_query(str_arg(query, "query")?)?;
+        Ok(writer.delete_query(query)? as i64)
+    })
+}
+
+/// Commits the pending changes, and returns the opstamp of the commit, or -1 on error.
+///
+/// Searchers created from the index afterwards see the changes.
+#[no_mangle]
+pub unsafe extern "C" fn tantivy_writer_commit(
+    writer: *mut TantivyWriter,
+    error: *mut *mut c_char,
+) -> i64 {
+    ffi_call(error, -1, || {
+        let writer = handle_mut(writer, "writer")?;
+        let opstamp = writer.writer.commit()?;
+        writer.reader.reload()?;
+        Ok(opstamp as i64)
+    })
+}
+
+/// Cancels the changes since the last commit, and returns the opstamp of that commit, or -1
+/// on error.
+#[no_mangle]
+pub unsafe extern "C" fn tantivy_writer_rollback(
+    writer: *mut TantivyWriter,
+    error: *mut *mut c_char,
+) -> i64 {
+    ffi_call(error, -1, || {
+        let writer = handle_mut(writer, "writer")?;
+        Ok(writer.writer.rollback()? as i64)
+    })
+}
+
+/// Frees a writer handle, waiting for its indexing threads. Uncommitted changes are lost.
+#[no_mangle]
+pub unsafe extern "C" fn tantivy_writer_free(writer: *mut TantivyWriter) {
+    if writer.is_null() {
+        return;
+    }
+    let writer = *Box::from_raw(writer);
+    // A panic must not unwind into the caller, there being no error to report it through.
+    match panic::catch_unwind(AssertUnwindSafe(|| writer.writer.wait_merging_threads())) {
+        Ok(Ok(())) => {}
+        Ok(Err(err)) => warn!("Failed to wait for the merging threads: {err}"),
+        Err(_) => warn!("Panic while waiting for the merging threads"),
+    }
+}
+
+/// Frees a string returned by the API.
+#[no_mangle]
+pub unsafe extern "C" fn tantivy_string_free(string: *mut c_char) {
+    if !string.is_null() {
+        drop(CString::from_raw(string));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ffi::{c_char, CStr, CString};
+    use std::ptr;
+
+    use super::*;
+    use crate::schema::{INDEXED, STORED, TEXT};
+
+    /// Takes ownership of a string returned by the API.
+    unsafe fn take_string(string: *mut c_char) -> String {
+        assert!(!string.is_null());
+        let owned = CStr::from_ptr(string).to_str().unwrap().to_string();
+        tantivy_string_free(string);
+        owned
+    }
+
+    #[test]
+    fn test_capi() {
+        let mut schema_builder = Schema::builder();
+        schema_builder.add_u64_field("id", INDEXED | STORED);
+        schema_builder.add_text_field("title", TEXT | STORED);
+        let schema_json = serde_json::to_string(&schema_builder.build()).unwrap();
+        let schema_json = CString::new(schema_json).unwrap();
+        unsafe {
+            let mut error: *mut c_char = ptr::null_mut();
+            let index = tantivy_index_create_in_ram(schema_json.as_ptr(), &mut error);
+            assert!(!index.is_null());
+            let writer = tantivy_writer_new(index, 15_000_000, &mut error);
+            assert!(!writer.is_null());
+            for document_json in [
+                r#"{"id": 1, "title": "The Old Man and the Sea"}"#,
+                r#"{"id": 2, "title": "The Sun Also Rises"}"#,
+            ] {
+                let document_json = CString::new(document_json).unwrap();
+                assert!(
+                    tantivy_writer_add_document(writer, document_json.as_ptr(), &mut error) >= 0
+                );
+            }
+            assert!(tantivy_writer_commit(writer, &mut error) >= 0);
+
+            let searcher = tantivy_index_searcher(index, &mut error);
+            assert_eq!(tantivy_searcher_num_docs(searcher, &mut error), 2);
+            let request = CString::new(r#"{"query": "sea", "fetch_documents": true}"#).unwrap();
+            let response = take_string(tantivy_searcher_search(
+                searcher,
+                request.as_ptr(),
+                &mut error,
+            ));
+            let response: serde_json::Value = serde_json::from_str(&response).unwrap();
+            assert_eq!(response["count"], 1);
+            assert_eq!(response["hits"][0]["document"]["id"][0], 1);
+            let doc_id = response["hits"][0]["doc_id"].as_u64().unwrap() as u32;
+            let document = take_string(tantivy_searcher_doc(searcher, 0, doc_id, &mut error));
+            assert!(document.contains("The Old Man and the Sea"));
+
+            let invalid_request = CString::new(r#"{"query": "title:("}"#).unwrap();
+            let response = tantivy_searcher_search(searcher, invalid_request.as_ptr(), &mut error);
+            assert!(response.is_null());
+            assert!(take_string(error).contains("Syntax Error"));
+            tantivy_searcher_free(searcher);
+
+            let query = CString::new("id:1").unwrap();
+            assert!(tantivy_writer_delete_documents(writer, query.as_ptr(), &mut error) >= 0);
+            assert!(tantivy_writer_commit(writer, &mut error) >= 0);
+            let searcher = tantivy_index_searcher(index, &mut error);
+            assert_eq!(tantivy_searcher_num_docs(searcher, &mut error), 1);
+
+            tantivy_searcher_free(searcher);
+            tantivy_writer_free(writer);
+            tantivy_index_free(index);
+        }
+    }
+}
@@ -1,5 +1,6 @@
 use super::Collector;
 use crate::collector::SegmentCollector;
+use crate::query::Weight;
 use crate::{DocId, Score, SegmentOrdinal, SegmentReader};
 
 /// `CountCollector` collector only counts how many
@@ -33,6 +34,7 @@ use crate::{DocId, Score, SegmentOrdinal, SegmentReader};
 ///
 /// assert_eq!(count, 2);
 /// ```
+#[derive(Debug)]
 pub struct Count;
 
 impl Collector for Count {
@@ -55,6 +57,17 @@ impl Collector for Count {
     fn merge_fruits(&self, segment_counts: Vec<usize>) -> crate::Result<usize> {
         Ok(segment_counts.into_iter().sum())
     }
+
+    fn collect_segment(
+        &self,
+        weight: &dyn Weight,
+        _segment_ord: SegmentOrdinal,
+        reader: &SegmentReader,
+    ) -> crate::Result<usize> {
+        // Some weights count their documents from the metadata of the segment, e.g. the doc
+        // freq of a term, without visiting them.
+        Ok(weight.count(reader)? as usize)
+    }
 }
 
 #[derive(Default)]
@@ -77,7 +90,10 @@ impl SegmentCollector for SegmentCountCollector {
 #[cfg(test)]
 mod tests {
     use super::{Count, SegmentCountCollector};
-    use crate::collector::{Collector, SegmentCollector};
+    use crate::collector::{Collector, DocSetCollector, SegmentCollector};
+    use crate::query::{AllQuery, Query, TermQuery};
+    use crate::schema::{IndexRecordOption, Schema, TEXT};
+    use crate::{doc, Index, IndexWriter, Term};
 
     #[test]
     fn test_count_collect_does_not_requires_scoring() {
@@ -107,4 +123,37 @@ mod tests {
             assert_eq!(count_collector.harvest(), 2);
         }
     }
+
+    #[test]
+    fn test_count_with_and_without_deletes() -> crate::Result<()> {
+        let mut schema_builder = Schema::builder();
+        let text = schema_builder.add_text_field("text", TEXT);
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer: IndexWriter = index.writer_for_tests()?;
+        index_writer.add_document(doc!(text => "apple"))?;
+        index_writer.add_document(doc!(text => "apple pie"))?;
+        index_writer.add_document(doc!(text => "pie"))?;
+        index_writer.commit()?;
+        let apple = TermQuery::new(
+            Term::from_field_text(text, "apple"),
+            IndexRecordOption::Basic,
+        );
+        let pie = TermQuery::new(Term::from_field_text(text, "pie"), IndexRecordOption::Basic);
+        let count_and_docs = |query: &dyn Query| -> crate::Result<(usize, usize)> {
+            let searcher = index.reader()?.searcher();
+            Ok((
+                searcher.search(query, &Count)?,
+                searcher.search(query, &DocSetCollector)?.len(),
+            ))
+        };
+        assert_eq!(count_and_docs(&apple)?, (2, 2));
+        assert_eq!(count_and_docs(&AllQuery)?, (3, 3));
+
+        index_writer.delete_term(Term::from_field_text(text, "apple"));
+        index_writer.commit()?;
+        assert_eq!(count_and_docs(&apple)?, (0, 0));
+        assert_eq!(count_and_docs(&pie)?, (1, 1));
+        assert_eq!(count_and_docs(&AllQuery)?, (1, 1));
+        Ok(())
+    }
 }
@@ -0,0 +1,224 @@
+use crate::query::{EnableScoring, Query, ScoreExpression, SegmentScoreExpression, Weight};
+use crate::{DocAddress, DocSet, Score, Searcher, SegmentReader};
+
+enum Feature {
+    Query(Box<dyn Query>),
+    Expression(ScoreExpression),
+}
+
+/// A [`Feature`] ready to be computed on the segments of a searcher.
+enum FeatureWeight<'a> {
+    Query(Box<dyn Weight>),
+    Expression(&'a ScoreExpression),
+}
+
+/// Computes named features for the top documents of a search, typically to export the training
+/// data of a learning-to-rank model.
+///
+/// A feature is either:
+/// - the score given to the document by a query, or `None` if the query does not match it,
+/// - a [`ScoreExpression`] computed for the document, `_score` being the score of the hit. It
+///   extracts the value of a fast field with an expression like `popularity`.
+///
+/// Only the given hits are visited: each query feature is scored once per segment containing
+/// hits, which is much cheaper than running one search per feature.
+///
+/// ```rust
+/// use tantivy::collector::{FeatureLogger, TopDocs};
+/// use tantivy::query::{QueryParser, ScoreExpression};
+/// use tantivy::schema::{Schema, FAST, TEXT};
+/// use tantivy::{doc, Index, IndexWriter};
+///
+/// # fn main() -> tantivy::Result<()> {
+/// let mut schema_builder = Schema::builder();
+/// let title = schema_builder.add_text_field("title", TEXT);
+/// let popularity = schema_builder.add_u64_field("popularity", FAST);
+/// let index = Index::create_in_ram(schema_builder.build());
+/// let mut index_writer: IndexWriter = index.writer(15_000_000)?;
+/// index_writer.add_document(doc!(title => "The Name of the Wind", popularity => 10u64))?;
+/// index_writer.add_document(doc!(title => "The Wind in the Willows", popularity => 1000u64))?;
+/// index_writer.commit()?;
+/// let searcher = index.reader()?.searcher();
+///
+/// let query_parser = QueryParser::for_index(&index, vec![title]);
+/// let hits = searcher.search(&query_parser.parse_query("wind")?, &TopDocs::with_limit(10))?;
+///
+/// let mut feature_logger = FeatureLogger::default();
+/// feature_logger.add_query_feature("title_name", query_parser.parse_query("name")?);
+/// feature_logger.add_expression_feature("popularity", ScoreExpression::parse("popularity")?);
+/// let features = feature_logger.log(&searcher, &hits)?;
+/// assert_eq!(features.len(), hits.len());
+/// assert_eq!(feature_logger.feature_names(), vec!["title_name", "popularity"]);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Default)]
+pub struct FeatureLogger {
+    features: Vec<(String, Feature)>,
+}
+
+impl FeatureLogger {
+    /// Adds a feature whose value is the score of `query`.
+    pub fn add_query_feature(&mut self, name: impl Into<String>, query: Box<dyn Query>) {
+        self.features.push((name.into(), Feature::Query(query)));
+    }
+
+    /// Adds a feature whose value is `expression`.
+    pub fn add_expression_feature(&mut self, name: impl Into<String>, expression: ScoreExpression) {
+        self.features
+            .push((name.into(), Feature::Expression(expression)));
+    }
+
+    /// Returns the names of the features, in the order of the feature vectors.
+    pub fn feature_names(&self) -> Vec<&str> {
+        self.features
+            .iter()
+            .map(|(name, _)| name.as_str())
+            .collect()
+    }
+
+    /// Computes the features of each of the `hits`, as returned by
+    /// [`TopDocs`](crate::collector::TopDocs).
+    ///
+    /// Returns a feature vector per hit, in the order of `hits`.
+    pub fn log(
+        &self,
+        searcher: &Searcher,
+        hits: &[(Score, DocAddress)],
+    ) -> crate::Result<Vec<Vec<Option<Score>>>> {
+        let enable_scoring = EnableScoring::enabled_from_searcher(searcher);
+        let feature_weights = self
+            .features
+            .iter()
+            .map(|(_, feature)| match feature {
+                Feature::Query(query) => query.weight(enable_scoring).map(FeatureWeight::Query),
+                Feature::Expression(expression) => Ok(FeatureWeight::Expression(expression)),
+            })
+            .collect::<crate::Result<Vec<FeatureWeight>>>()?;
+        let mut features = vec![vec![None; self.features.len()]; hits.len()];
+        // Scorers can only move forward, so that the hits are visited by increasing address.
+        let mut hit_ords: Vec<usize> = (0..hits.len()).collect();
+        hit_ords.sort_by_key(|&hit_ord| hits[hit_ord].1);
+        let mut start = 0;
+        while start < hit_ords.len() {
+            let segment_ord = hits[hit_ords[start]].1.segment_ord;
+            let num_segment_hits = hit_ords[start..]
+                .iter()
+                .take_while(|&&hit_ord| hits[hit_ord].1.segment_ord == segment_ord)
+                .count();
+            log_segment(
+                searcher.segment_reader(segment_ord),
+                &feature_weights,
+                hits,
+                &hit_ords[start..start + num_segment_hits],
+                &mut features,
+            )?;
+            start += num_segment_hits;
+        }
+        Ok(features)
+    }
+}
+
+/// Computes the features of the hits of a segment, given by their ordinal in `hits` sorted by
+/// doc id.
+fn log_segment(
+    segment_reader: &SegmentReader,
+    feature_weights: &[FeatureWeight],
+    hits: &[(Score, DocAddress)],
+    segment_hit_ords: &[usize],
+    features: &mut [Vec<Option<Score>>],
+) -> crate::Result<()> {
+    for (feature_ord, feature_weight) in feature_weights.iter().enumerate() {
+        match feature_weight {
+            FeatureWeight::Query(weight) => {
+                let mut scorer = weight.scorer(segment_reader, 1.0)?;
+                for &hit_ord in segment_hit_ords {
+                    let doc = hits[hit_ord].1.doc_id;
+                    if scorer.doc() <= doc && scorer.seek(doc) == doc {
+                        features[hit_ord][feature_ord] = Some(scorer.score());
+                    }
+                }
+            }
+            FeatureWeight::Expression(expression) => {
+                let mut expression = SegmentScoreExpression::open(expression, segment_reader)?;
+                for &hit_ord in segment_hit_ords {
+                    let (score, doc_address) = hits[hit_ord];
+                    features[hit_ord][feature_ord] =
+                        Some(expression.evaluate(doc_address.doc_id, score));
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::FeatureLogger;
+    use crate::collector::TopDocs;
+    use crate::query::{Query, ScoreExpression, TermQuery};
+    use crate::schema::{IndexRecordOption, Schema, FAST, TEXT};
+    use crate::{doc, Index, IndexWriter, Term};
+
+    #[test]
+    fn test_feature_logger() -> crate::Result<()> {
+        let mut schema_builder = Schema::builder();
+        let text = schema_builder.add_text_field("text", TEXT);
+        let popularity = schema_builder.add_u64_field("popularity", FAST);
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer: IndexWriter = index.writer_for_tests()?;
+        index_writer.add_document(doc!(text => "apple pie", popularity => 1u64))?;
+        index_writer.add_document(doc!(text => "apple"))?;
+        index_writer.commit()?;
+        index_writer.add_document(doc!(text => "apple apple pie", popularity => 3u64))?;
+        index_writer.add_document(doc!(text => "pie"))?;
+        index_writer.commit()?;
+        let searcher = index.reader()?.searcher();
+        assert_eq!(searcher.segment_readers().len(), 2);
+
+        let term_query = |token: &str| {
+            TermQuery::new(
+                Term::from_field_text(text, token),
+                IndexRecordOption::WithFreqs,
+            )
+        };
+        let hits = searcher.search(&term_query("apple"), &TopDocs::with_limit(10))?;
+        assert_eq!(hits.len(), 3);
+
+        let mut feature_logger = FeatureLogger::default();
+        feature_logger.add_query_feature("pie", Box::new(term_query("pie")));
+        feature_logger.add_expression_feature(
+            "boosted_popularity",
+            ScoreExpression::parse("_score * popularity")?,
+        );
+        let features = feature_logger.log(&searcher, &hits)?;
+        assert_eq!(
+            feature_logger.feature_names(),
+            vec!["pie", "boosted_popularity"]
+        );
+        assert_eq!(features.len(), 3);
+        for ((score, doc_address), doc_features) in hits.iter().zip(&features) {
+            let pie_score = term_query("pie")
+                .explain(&searcher, *doc_address)
+                .ok()
+                .map(|explanation| explanation.value());
+            let popularity_val = searcher
+                .segment_reader(doc_address.segment_ord)
+                .fast_fields()
+                .u64("popularity")?
+                .first(doc_address.doc_id)
+                .unwrap_or(0);
+            assert_eq!(
+                doc_features,
+                &vec![pie_score, Some(score * popularity_val as f32)]
+            );
+        }
+        // The document without "pie" has no value for the query feature.
+        let no_pie_features: Vec<&Vec<Option<f32>>> = features
+            .iter()
+            .filter(|doc_features| doc_features[0].is_none())
+            .collect();
+        assert_eq!(no_pie_features, vec![&vec![None, Some(0.0)]]);
+        Ok(())
+    }
+}
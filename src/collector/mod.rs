@@ -77,13 +77,23 @@
 //! you can rely on [`MultiCollector`]'s.
 //!
 //!
+//! # Merging the results of several queries
+//!
+//! [`RankFusion`] runs several queries, for instance a lexical query and a
+//! [`KnnQuery`](crate::query::KnnQuery), and merges their top documents into a single ranking.
+//!
+//! [`FeatureLogger`] computes named features for the top documents of a search, to train a
+//! learning-to-rank model.
+//!
 //! # Implementing your own collectors.
 //!
 //! See the `custom_collector` example.
 
+use std::ops::Range;
+
 use downcast_rs::impl_downcast;
 
-use crate::{DocId, Score, SegmentOrdinal, SegmentReader};
+use crate::{DocId, DocSet, Score, SegmentOrdinal, SegmentReader};
 
 mod count_collector;
 pub use self::count_collector::Count;
@@ -115,6 +125,12 @@ pub use self::docset_collector::DocSetCollector;
 mod filter_collector_wrapper;
 pub use self::filter_collector_wrapper::{BytesFilterCollector, FilterCollector};
 
+mod rank_fusion;
+pub use self::rank_fusion::{FusionMethod, RankFusion};
+
+mod feature_logger;
+pub use self::feature_logger::FeatureLogger;
+
 /// `Fruit` is the type for the result of our collection.
 /// e.g. `usize` for the `Count` collector.
 pub trait Fruit: Send + downcast_rs::Downcast {}
@@ -203,6 +219,44 @@ pub trait Collector: Sync + Send {
 
         Ok(segment_collector.harvest())
     }
+
+    /// Same as [`collect_segment`](Collector::collect_segment), restricted to the documents of
+    /// `doc_range`.
+    ///
+    /// This is used to split the search of a large segment into several tasks. Collecting the
+    /// whole segment falls back to `collect_segment`, otherwise the documents are scored one by
+    /// one, without the optimizations `collect_segment` may implement.
+    fn collect_segment_range(
+        &self,
+        weight: &dyn Weight,
+        segment_ord: u32,
+        reader: &SegmentReader,
+        doc_range: Range<DocId>,
+    ) -> crate::Result<<Self::Child as SegmentCollector>::Fruit> {
+        if doc_range.start == 0 && doc_range.end >= reader.max_doc() {
+            return self.collect_segment(weight, segment_ord, reader);
+        }
+        let mut segment_collector = self.for_segment(segment_ord, reader)?;
+        let mut scorer = weight.scorer(reader, 1.0)?;
+        let alive_bitset = reader.alive_bitset();
+        let requires_scoring = self.requires_scoring();
+        let mut doc = scorer.doc();
+        if doc < doc_range.start {
+            doc = scorer.seek(doc_range.start);
+        }
+        while doc < doc_range.end {
+            if alive_bitset.map_or(true, |alive_bitset| alive_bitset.is_alive(doc)) {
+                let score = if requires_scoring {
+                    scorer.score()
+                } else {
+                    0.0
+                };
+                segment_collector.collect(doc, score);
+            }
+            doc = scorer.advance();
+        }
+        Ok(segment_collector.harvest())
+    }
 }
 
 impl<TSegmentCollector: SegmentCollector> SegmentCollector for Option<TSegmentCollector> {
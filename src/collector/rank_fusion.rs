@@ -0,0 +1,283 @@
+use std::cmp::Ordering;
+use std::collections::HashMap;
+
+use crate::collector::TopDocs;
+use crate::query::Query;
+use crate::{DocAddress, Score, Searcher};
+
+/// How [`RankFusion`] merges the rankings of several queries.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum FusionMethod {
+    /// Reciprocal rank fusion.
+    ///
+    /// A document scores `weight / (rank_constant + rank)` for each ranking it appears in,
+    /// its rank starting at 1. Only the ranks matter, so that rankings whose scores have
+    /// different scales, like BM25 scores and vector similarities, are merged fairly. The
+    /// higher the rank constant, the more the documents ranked low weigh compared to the
+    /// top ones. It is usually set to 60.
+    ReciprocalRank {
+        /// The constant added to the ranks.
+        rank_constant: Score,
+    },
+    /// Weighted sum of the normalized scores.
+    ///
+    /// The scores of each ranking are scaled to `[0, 1]` with a min-max normalization, then
+    /// a document scores the weighted sum of its normalized scores. A document missing from a
+    /// ranking scores 0 for it.
+    NormalizedScore,
+}
+
+impl Default for FusionMethod {
+    fn default() -> Self {
+        FusionMethod::ReciprocalRank {
+            rank_constant: 60.0,
+        }
+    }
+}
+
+/// Runs several queries and merges their rankings into a single one, typically to combine
+/// a lexical query with a [`KnnQuery`](crate::query::KnnQuery) in hybrid search.
+///
+/// The scores of a lexical query and of a vector query do not have the same scale, so that
+/// neither summing them nor sorting them together gives a sensible ranking. Instead, the
+/// top documents of each query are merged with a [`FusionMethod`] that accounts for it.
+///
+/// ```rust
+/// use tantivy::collector::RankFusion;
+/// use tantivy::query::{KnnQuery, QueryParser};
+/// use tantivy::schema::{DenseVectorOptions, Schema, VectorMetric, TEXT};
+/// use tantivy::vector::encode_vector;
+/// use tantivy::{doc, Index, IndexWriter};
+///
+/// # fn main() -> tantivy::Result<()> {
+/// let mut schema_builder = Schema::builder();
+/// let title = schema_builder.add_text_field("title", TEXT);
+/// let embedding = schema_builder
+///     .add_dense_vector_field("embedding", DenseVectorOptions::new(2, VectorMetric::Cosine));
+/// let index = Index::create_in_ram(schema_builder.build());
+/// let mut index_writer: IndexWriter = index.writer(15_000_000)?;
+/// index_writer.add_document(doc!(
+///     title => "The Name of the Wind",
+///     embedding => encode_vector(&[1.0, 0.0]),
+/// ))?;
+/// index_writer.add_document(doc!(
+///     title => "The Diary of Muadib",
+///     embedding => encode_vector(&[0.0, 1.0]),
+/// ))?;
+/// index_writer.commit()?;
+/// let searcher = index.reader()?.searcher();
+///
+/// let lexical_query = QueryParser::for_index(&index, vec![title]).parse_query("diary")?;
+/// let knn_query = KnnQuery::new(embedding, vec![0.1, 1.0], 10);
+/// let top_docs = RankFusion::with_limit(10)
+///     .search(&searcher, &[(lexical_query.as_ref(), 1.0), (&knn_query, 1.0)])?;
+/// assert_eq!(top_docs.len(), 2);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Debug)]
+pub struct RankFusion {
+    method: FusionMethod,
+    limit: usize,
+    num_candidates: usize,
+}
+
+impl RankFusion {
+    /// Creates a rank fusion returning the `limit` best documents, merged with reciprocal
+    /// rank fusion.
+    ///
+    /// # Panics
+    /// The method panics if limit is 0
+    pub fn with_limit(limit: usize) -> RankFusion {
+        assert!(limit >= 1, "Limit must be strictly greater than 0.");
+        RankFusion {
+            method: FusionMethod::default(),
+            limit,
+            num_candidates: limit,
+        }
+    }
+
+    /// Sets the method used to merge the rankings.
+    #[must_use]
+    pub fn with_method(mut self, method: FusionMethod) -> RankFusion {
+        self.method = method;
+        self
+    }
+
+    /// Sets the number of top documents retrieved for each query, `limit` by default.
+    ///
+    /// Retrieving more documents than `limit` lets a document ranked a bit lower by each of
+    /// the queries make it to the merged ranking.
+    #[must_use]
+    pub fn with_num_candidates(mut self, num_candidates: usize) -> RankFusion {
+        self.num_candidates = num_candidates.max(self.limit);
+        self
+    }
+
+    /// Runs each of the `queries`, given with their weight, and merges their top documents.
+    ///
+    /// Returns the `limit` best documents, sorted by decreasing fused score.
+    pub fn search(
+        &self,
+        searcher: &Searcher,
+        queries: &[(&dyn Query, Score)],
+    ) -> crate::Result<Vec<(Score, DocAddress)>> {
+        let collector = TopDocs::with_limit(self.num_candidates);
+        let rankings = queries
+            .iter()
+            .map(|&(query, weight)| Ok((searcher.search(query, &collector)?, weight)))
+            .collect::<crate::Result<Vec<_>>>()?;
+        Ok(self.fuse(&rankings))
+    }
+
+    /// Merges rankings computed beforehand, given with their weight.
+    ///
+    /// Each ranking is expected to be sorted by decreasing score, as returned by
+    /// [`TopDocs`]. Returns the `limit` best documents, sorted by decreasing fused score.
+    pub fn fuse(&self, rankings: &[(Vec<(Score, DocAddress)>, Score)]) -> Vec<(Score, DocAddress)> {
+        let mut fused_scores: HashMap<DocAddress, Score> = HashMap::new();
+        for (ranking, weight) in rankings {
+            match self.method {
+                FusionMethod::ReciprocalRank { rank_constant } => {
+                    for (rank, &(_, doc_address)) in ranking.iter().enumerate() {
+                        *fused_scores.entry(doc_address).or_default() +=
+                            weight / (rank_constant + (rank + 1) as Score);
+                    }
+                }
+                FusionMethod::NormalizedScore => {
+                    let (min_score, max_score) = ranking.iter().fold(
+                        (Score::INFINITY, Score::NEG_INFINITY),
+                        |(min_score, max_score), &(score, _)| {
+                            (min_score.min(score), max_score.max(score))
+                        },
+                    );
+                    for &(score, doc_address) in ranking {
+                        // All of the documents are equally relevant if the scores are equal.
+                        let normalized_score = if max_score > min_score {
+                            (score - min_score) / (max_score - min_score)
+                        } else {
+                            1.0
+                        };
+                        *fused_scores.entry(doc_address).or_default() += weight * normalized_score;
+                    }
+                }
+            }
+        }
+        let mut top_docs: Vec<(Score, DocAddress)> = fused_scores
+            .into_iter()
+            .map(|(doc_address, score)| (score, doc_address))
+            .collect();
+        // Ties are broken by doc address, so that the result does not depend on the order of
+        // the hash map.
+        let cmp = |left: &(Score, DocAddress), right: &(Score, DocAddress)| {
+            right
+                .0
+                .partial_cmp(&left.0)
+                .unwrap_or(Ordering::Equal)
+                .then_with(|| left.1.cmp(&right.1))
+        };
+        if top_docs.len() > self.limit {
+            top_docs.select_nth_unstable_by(self.limit, cmp);
+            top_docs.truncate(self.limit);
+        }
+        top_docs.sort_unstable_by(cmp);
+        top_docs
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{FusionMethod, RankFusion};
+    use crate::query::{ExactKnnQuery, TermQuery};
+    use crate::schema::{DenseVectorOptions, IndexRecordOption, Schema, VectorMetric, TEXT};
+    use crate::vector::encode_vector;
+    use crate::{doc, DocAddress, Index, IndexWriter, Term};
+
+    fn doc_address(doc_id: u32) -> DocAddress {
+        DocAddress::new(0, doc_id)
+    }
+
+    #[test]
+    fn test_reciprocal_rank_fusion() {
+        // The scores have very different scales, only the ranks matter.
+        let lexical = vec![
+            (12.0, doc_address(1)),
+            (8.0, doc_address(2)),
+            (1.0, doc_address(3)),
+        ];
+        let knn = vec![(0.9, doc_address(3)), (0.8, doc_address(2))];
+        let fusion = RankFusion::with_limit(2)
+            .with_method(FusionMethod::ReciprocalRank { rank_constant: 1.0 });
+        assert_eq!(
+            fusion.fuse(&[(lexical.clone(), 1.0), (knn.clone(), 1.0)]),
+            vec![
+                (1.0 / 4.0 + 1.0 / 2.0, doc_address(3)),
+                (1.0 / 3.0 + 1.0 / 3.0, doc_address(2)),
+            ]
+        );
+        assert_eq!(
+            fusion.fuse(&[(lexical, 4.0), (knn, 1.0)]),
+            vec![
+                (4.0 / 2.0, doc_address(1)),
+                (4.0 / 3.0 + 1.0 / 3.0, doc_address(2)),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_normalized_score_fusion() {
+        let lexical = vec![
+            (12.0, doc_address(1)),
+            (8.0, doc_address(2)),
+            (4.0, doc_address(3)),
+        ];
+        let knn = vec![(0.75, doc_address(3)), (0.5, doc_address(4))];
+        let fusion = RankFusion::with_limit(10).with_method(FusionMethod::NormalizedScore);
+        assert_eq!(
+            fusion.fuse(&[(lexical, 1.0), (knn, 0.5)]),
+            vec![
+                (1.0, doc_address(1)),
+                (0.5, doc_address(2)),
+                (0.5, doc_address(3)),
+                (0.0, doc_address(4)),
+            ]
+        );
+        // A ranking whose scores are all equal.
+        let single = vec![(3.0, doc_address(5))];
+        assert_eq!(fusion.fuse(&[(single, 1.0)]), vec![(1.0, doc_address(5))]);
+    }
+
+    #[test]
+    fn test_rank_fusion_search() -> crate::Result<()> {
+        let mut schema_builder = Schema::builder();
+        let text = schema_builder.add_text_field("text", TEXT);
+        let embedding = schema_builder
+            .add_dense_vector_field("embedding", DenseVectorOptions::new(2, VectorMetric::L2));
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer: IndexWriter = index.writer_for_tests()?;
+        index_writer
+            .add_document(doc!(text => "apple", embedding => encode_vector(&[4.0, 0.0])))?;
+        index_writer
+            .add_document(doc!(text => "apple apple", embedding => encode_vector(&[1.0, 0.0])))?;
+        index_writer.add_document(doc!(text => "pear", embedding => encode_vector(&[0.0, 0.0])))?;
+        index_writer.commit()?;
+        let searcher = index.reader()?.searcher();
+
+        let lexical_query = TermQuery::new(
+            Term::from_field_text(text, "apple"),
+            IndexRecordOption::WithFreqs,
+        );
+        let knn_query = ExactKnnQuery::new(embedding, vec![0.0, 0.0], 3);
+        let top_docs = RankFusion::with_limit(2)
+            .with_num_candidates(3)
+            .search(&searcher, &[(&lexical_query, 1.0), (&knn_query, 1.0)])?;
+        // Doc 2 is the closest to the vector but does not match the lexical query, while doc 0
+        // and doc 1 are ranked well by both of them.
+        let doc_addresses: Vec<DocAddress> = top_docs
+            .into_iter()
+            .map(|(_, doc_address)| doc_address)
+            .collect();
+        assert_eq!(doc_addresses, vec![doc_address(1), doc_address(0)]);
+        Ok(())
+    }
+}
@@ -56,6 +56,10 @@ impl Executor {
             Executor::ThreadPool(pool) => {
                 let args: Vec<A> = args.collect();
                 let num_fruits = args.len();
+                // The tasks are traced as children of the span of the caller, e.g. the span of
+                // the search they belong to.
+                #[cfg(feature = "tracing")]
+                let span = tracing::Span::current();
                 let fruit_receiver = {
                     let (fruit_sender, fruit_receiver) = crossbeam_channel::unbounded();
                     pool.scope(|scope| {
@@ -64,7 +68,11 @@ impl Executor {
                             // want these two to be moved into the closure.
                             let f_ref = &f;
                             let fruit_sender_ref = &fruit_sender;
+                            #[cfg(feature = "tracing")]
+                            let span_ref = &span;
                             scope.spawn(move |_| {
+                                #[cfg(feature = "tracing")]
+                                let _entered = span_ref.enter();
                                 let fruit = f_ref(arg);
                                 if let Err(err) = fruit_sender_ref.send((idx, fruit)) {
                                     error!(
@@ -2,6 +2,7 @@ mod executor;
 #[doc(hidden)]
 pub mod json_utils;
 pub mod searcher;
+mod warmup_spec;
 
 use std::path::Path;
 
@@ -9,6 +10,7 @@ use once_cell::sync::Lazy;
 
 pub use self::executor::Executor;
 pub use self::searcher::{Searcher, SearcherGeneration};
+pub use self::warmup_spec::WarmupSpec;
 
 /// The meta file contains all the information about the list of segments and the schema
 /// of the index.
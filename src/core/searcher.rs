@@ -1,16 +1,31 @@
 use std::collections::BTreeMap;
+use std::ops::Range;
 use std::sync::Arc;
 use std::{fmt, io};
 
-use crate::collector::Collector;
+use crate::collector::{Collector, SegmentCollector};
 use crate::core::Executor;
+use crate::directory::{touch_pages, AccessHint, Directory, FileSlice};
+use crate::fastfield::{GlobalOrdinals, GlobalOrdinalsCache};
 use crate::index::{SegmentId, SegmentReader};
-use crate::query::{Bm25StatisticsProvider, EnableScoring, Query};
+use crate::query::{Bm25StatisticsProvider, EnableScoring, Query, Weight};
 use crate::schema::document::DocumentDeserialize;
-use crate::schema::{Schema, Term};
+use crate::schema::{Field, JsonPathFilter, Schema, Term};
 use crate::space_usage::SearcherSpaceUsage;
-use crate::store::{CacheStats, StoreReader};
-use crate::{DocAddress, Index, Opstamp, TrackedObject};
+use crate::store::{Blob, CacheStats, LazyDocument, StoreReader};
+use crate::{DocAddress, DocId, Index, Instant, Opstamp, TrackedObject, WarmupSpec};
+
+/// Segments are only split into slices of at least this number of documents by
+/// [`Searcher::search_with_concurrency`].
+const MIN_SLICE_NUM_DOCS: u32 = 50_000;
+
+/// Returns a new id for the span of a search, shared by the spans of its segments.
+#[cfg(feature = "tracing")]
+fn next_search_id() -> u64 {
+    use std::sync::atomic::{AtomicU64, Ordering};
+    static SEARCH_ID: AtomicU64 = AtomicU64::new(0);
+    SEARCH_ID.fetch_add(1, Ordering::Relaxed)
+}
 
 /// Identifies the searcher generation accessed by a [`Searcher`].
 ///
@@ -85,11 +100,177 @@ impl Searcher {
     ///
     /// The searcher uses the segment ordinal to route the
     /// request to the right `Segment`.
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip(self)))]
     pub fn doc<D: DocumentDeserialize>(&self, doc_address: DocAddress) -> crate::Result<D> {
         let store_reader = &self.inner.store_readers[doc_address.segment_ord as usize];
         store_reader.get(doc_address.doc_id)
     }
 
+    /// Fetches several documents, returned in the order of `doc_addresses`.
+    ///
+    /// This is cheaper than calling [`Searcher::doc`] for each address, e.g. to fetch the
+    /// top hits of a query: each doc store block is only decompressed once.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(level = "debug", skip_all, fields(num_docs = doc_addresses.len()))
+    )]
+    pub fn docs<D: DocumentDeserialize>(
+        &self,
+        doc_addresses: &[DocAddress],
+    ) -> crate::Result<Vec<D>> {
+        let mut docs = Vec::with_capacity(doc_addresses.len());
+        for (segment_ord, (positions, doc_ids)) in group_by_segment(doc_addresses) {
+            let store_reader = &self.inner.store_readers[segment_ord as usize];
+            let segment_docs = store_reader.get_many(&doc_ids)?;
+            docs.extend(positions.into_iter().zip(segment_docs));
+        }
+        docs.sort_by_key(|(pos, _)| *pos);
+        Ok(docs.into_iter().map(|(_, doc)| doc).collect())
+    }
+
+    /// Fetches the values of some of the stored fields of a document, given its
+    /// [`DocAddress`].
+    ///
+    /// This is cheaper than [`Searcher::doc`] when documents have large stored fields that
+    /// are not needed: the values of the other fields are skipped without being decoded.
+    pub fn doc_fields<D: DocumentDeserialize>(
+        &self,
+        doc_address: DocAddress,
+        fields: &[Field],
+    ) -> crate::Result<D> {
+        let store_reader = &self.inner.store_readers[doc_address.segment_ord as usize];
+        store_reader.get_fields(doc_address.doc_id, fields)
+    }
+
+    /// Returns the sequence number of a document, given its [`DocAddress`].
+    ///
+    /// Returns an error if the index does not record sequence numbers, see
+    /// [`IndexSettings::sequence_number_field`](crate::IndexSettings::sequence_number_field).
+    pub fn sequence_number(&self, doc_address: DocAddress) -> crate::Result<Option<u64>> {
+        let field_name = self
+            .inner
+            .index
+            .settings()
+            .sequence_number_field
+            .as_deref()
+            .ok_or_else(|| {
+                crate::TantivyError::InvalidArgument(
+                    "The index does not record sequence numbers.".to_string(),
+                )
+            })?;
+        let column = self
+            .segment_reader(doc_address.segment_ord)
+            .fast_fields()
+            .u64(field_name)?;
+        Ok(column.first(doc_address.doc_id))
+    }
+
+    /// Fetches a document given its [`DocAddress`], deferring the decoding of its values
+    /// until they are accessed.
+    ///
+    /// See [`LazyDocument`].
+    pub fn doc_lazy(&self, doc_address: DocAddress) -> crate::Result<LazyDocument> {
+        let store_reader = &self.inner.store_readers[doc_address.segment_ord as usize];
+        store_reader.get_lazy(doc_address.doc_id)
+    }
+
+    /// Fetches a document given its [`DocAddress`], only returning the parts of its JSON
+    /// fields accepted by `json_path_filter`.
+    ///
+    /// This avoids decoding entire JSON objects when only a few of their sub-paths are
+    /// needed, e.g. to render a list of hits.
+    pub fn doc_with_json_path_filter<D: DocumentDeserialize>(
+        &self,
+        doc_address: DocAddress,
+        json_path_filter: &JsonPathFilter,
+    ) -> crate::Result<D> {
+        let store_reader = &self.inner.store_readers[doc_address.segment_ord as usize];
+        store_reader.get_with_json_path_filter(doc_address.doc_id, json_path_filter, self.schema())
+    }
+
+    /// Returns the values of the blob field `field` of a document, given its [`DocAddress`].
+    ///
+    /// Blobs are not read when calling this method: their content can then be streamed
+    /// with [`Blob::reader`]. Fields that are not blob fields have no blobs.
+    pub fn blobs(&self, doc_address: DocAddress, field: Field) -> crate::Result<Vec<Blob>> {
+        let segment_reader = self.segment_reader(doc_address.segment_ord);
+        let blobs = segment_reader.blob_store().get(doc_address.doc_id, field)?;
+        Ok(blobs)
+    }
+
+    /// Returns the [`GlobalOrdinals`] of the string fast field `field_name`, mapping the term
+    /// ordinals of each segment of the searcher to ordinals shared by all of them.
+    ///
+    /// They are built on the first call and cached by the [`IndexReader`](crate::IndexReader).
+    /// After a reload adding segments, the cached terms are merged with the dictionaries of the
+    /// new segments rather than rebuilt from scratch.
+    pub fn global_ordinals(&self, field_name: &str) -> crate::Result<Arc<GlobalOrdinals>> {
+        self.inner
+            .global_ordinals_cache
+            .get_or_build(field_name, self.segment_readers())
+    }
+
+    /// Loads the data structures selected by `warmup_spec` in memory, so that the first
+    /// queries using them do not pay for cold reads.
+    ///
+    /// The data is read through the directory and every page of it is touched, so that the
+    /// pages of memory mapped files are actually loaded. The reads are spread over the search
+    /// executor. This returns once everything is loaded, typically before routing traffic to
+    /// the searcher of a freshly reloaded reader.
+    pub fn warm(&self, warmup_spec: &WarmupSpec) -> crate::Result<()> {
+        let file_slices = self.warmup_file_slices(warmup_spec)?;
+        self.inner.index.search_executor().map(
+            |file_slice| {
+                touch_pages(file_slice.read_bytes()?.as_slice());
+                Ok(())
+            },
+            file_slices.into_iter(),
+        )?;
+        Ok(())
+    }
+
+    /// Async version of [`Searcher::warm`], for directories supporting asynchronous reads.
+    ///
+    /// All of the data structures are read concurrently. Locating them still requires a few
+    /// synchronous reads of the footers of the files.
+    #[cfg(feature = "quickwit")]
+    pub async fn warm_async(&self, warmup_spec: &WarmupSpec) -> crate::Result<()> {
+        let file_slices = self.warmup_file_slices(warmup_spec)?;
+        futures_util::future::try_join_all(file_slices.iter().map(|file_slice| async move {
+            touch_pages(file_slice.read_bytes_async().await?.as_slice());
+            io::Result::Ok(())
+        }))
+        .await?;
+        Ok(())
+    }
+
+    fn warmup_file_slices(&self, warmup_spec: &WarmupSpec) -> crate::Result<Vec<FileSlice>> {
+        let mut file_slices = Vec::new();
+        for segment_reader in self.segment_readers() {
+            file_slices.extend(segment_reader.warmup_file_slices(warmup_spec)?);
+        }
+        Ok(file_slices)
+    }
+
+    /// Tells the directory that the files of the searcher's segments will not be read in
+    /// the near future, and that their pages can be evicted from memory.
+    ///
+    /// This is only a hint: directories that do not support it ignore it. See
+    /// [`Directory::advise`].
+    pub fn evict(&self) -> crate::Result<()> {
+        self.advise_segment_files(AccessHint::DontNeed)
+    }
+
+    fn advise_segment_files(&self, hint: AccessHint) -> crate::Result<()> {
+        let directory = self.index().directory();
+        for segment_reader in self.segment_readers() {
+            for path in segment_reader.list_files() {
+                directory.advise(&path, hint)?;
+            }
+        }
+        Ok(())
+    }
+
     /// The cache stats for the underlying store reader.
     ///
     /// Aggregates the sum for each segment store reader.
@@ -104,6 +285,10 @@ impl Searcher {
     }
 
     /// Fetches a document in an asynchronous manner.
+    ///
+    /// The doc store is read with [`FileHandle::read_bytes_async`](crate::directory::FileHandle),
+    /// and the block is decompressed on the blocking threads of the search executor. When
+    /// several documents are needed, [`Searcher::docs_async`] reads their blocks concurrently.
     #[cfg(feature = "quickwit")]
     pub async fn doc_async<D: DocumentDeserialize>(
         &self,
@@ -114,6 +299,32 @@ impl Searcher {
         store_reader.get_async(doc_address.doc_id, executor).await
     }
 
+    /// Fetches several documents in an asynchronous manner. Async version of
+    /// [`Searcher::docs`].
+    ///
+    /// All of the doc store blocks holding the documents, across all segments, are read
+    /// concurrently.
+    #[cfg(feature = "quickwit")]
+    pub async fn docs_async<D: DocumentDeserialize>(
+        &self,
+        doc_addresses: &[DocAddress],
+    ) -> crate::Result<Vec<D>> {
+        let executor = self.inner.index.search_executor();
+        let segments_docs =
+            futures_util::future::try_join_all(group_by_segment(doc_addresses).into_iter().map(
+                |(segment_ord, (positions, doc_ids))| async move {
+                    let store_reader = &self.inner.store_readers[segment_ord as usize];
+                    let segment_docs: Vec<D> =
+                        store_reader.get_many_async(&doc_ids, executor).await?;
+                    crate::Result::Ok(positions.into_iter().zip(segment_docs))
+                },
+            ))
+            .await?;
+        let mut docs: Vec<(usize, D)> = segments_docs.into_iter().flatten().collect();
+        docs.sort_by_key(|(pos, _)| *pos);
+        Ok(docs.into_iter().map(|(_, doc)| doc).collect())
+    }
+
     /// Access the schema associated with the index of this searcher.
     pub fn schema(&self) -> &Schema {
         &self.inner.schema
@@ -217,6 +428,18 @@ impl Searcher {
     /// Also, keep in my multithreading a single query on several
     /// threads will not improve your throughput. It can actually
     /// hurt it. It will however, decrease the average response time.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            name = "search",
+            skip_all,
+            fields(
+                search_id = next_search_id(),
+                generation_id = self.generation().generation_id(),
+                num_segments = self.segment_readers().len(),
+            )
+        )
+    )]
     pub fn search_with_executor<C: Collector>(
         &self,
         query: &dyn Query,
@@ -224,15 +447,100 @@ impl Searcher {
         executor: &Executor,
         enabled_scoring: EnableScoring,
     ) -> crate::Result<C::Fruit> {
+        let start = Instant::now();
         let weight = query.weight(enabled_scoring)?;
         let segment_readers = self.segment_readers();
         let fruits = executor.map(
             |(segment_ord, segment_reader)| {
-                collector.collect_segment(weight.as_ref(), segment_ord as u32, segment_reader)
+                collect_segment_range(
+                    collector,
+                    weight.as_ref(),
+                    segment_ord as u32,
+                    segment_reader,
+                    0..segment_reader.max_doc(),
+                )
             },
             segment_readers.iter().enumerate(),
         )?;
-        collector.merge_fruits(fruits)
+        let fruit = collector.merge_fruits(fruits)?;
+        self.record_search(start);
+        Ok(fruit)
+    }
+
+    /// Same as [`search(...)`](Searcher::search), splitting the search into about `concurrency`
+    /// tasks of similar sizes.
+    ///
+    /// Segments are searched by separate tasks, and the segments much larger than the others are
+    /// split into slices of consecutive documents, so that a query on an index made of a single
+    /// large segment can use several cores too. The tasks are run on the search executor of the
+    /// index, shared by all of the queries: with a thread pool (see
+    /// [`Index::set_multithread_executor`]), idle threads steal the pending tasks of the busy
+    /// ones. With the default single thread executor, the tasks are run one after the other.
+    ///
+    /// The fruits of the slices of a segment are merged like the fruits of different segments.
+    /// The slices are collected document by document, so that the optimizations of
+    /// [`Collector::collect_segment`], like the block-max pruning of
+    /// [`TopDocs`](crate::collector::TopDocs), are only applied to the segments which are not
+    /// split.
+    pub fn search_with_concurrency<C: Collector>(
+        &self,
+        query: &dyn Query,
+        collector: &C,
+        concurrency: usize,
+    ) -> crate::Result<C::Fruit> {
+        self.search_in_slices(query, collector, concurrency, MIN_SLICE_NUM_DOCS)
+    }
+
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            name = "search",
+            skip_all,
+            fields(
+                search_id = next_search_id(),
+                generation_id = self.generation().generation_id(),
+                num_segments = self.segment_readers().len(),
+                concurrency = concurrency,
+            )
+        )
+    )]
+    pub(crate) fn search_in_slices<C: Collector>(
+        &self,
+        query: &dyn Query,
+        collector: &C,
+        concurrency: usize,
+        min_slice_num_docs: u32,
+    ) -> crate::Result<C::Fruit> {
+        let enabled_scoring = if collector.requires_scoring() {
+            EnableScoring::enabled_from_searcher(self)
+        } else {
+            EnableScoring::disabled_from_searcher(self)
+        };
+        let start = Instant::now();
+        let weight = query.weight(enabled_scoring)?;
+        let segment_readers = self.segment_readers();
+        let slices = search_slices(segment_readers, concurrency, min_slice_num_docs);
+        let fruits = self.inner.index.search_executor().map(
+            |(segment_ord, doc_range)| {
+                collect_segment_range(
+                    collector,
+                    weight.as_ref(),
+                    segment_ord,
+                    &segment_readers[segment_ord as usize],
+                    doc_range,
+                )
+            },
+            slices.into_iter(),
+        )?;
+        let fruit = collector.merge_fruits(fruits)?;
+        self.record_search(start);
+        Ok(fruit)
+    }
+
+    fn record_search(&self, start: Instant) {
+        let metrics = self.inner.index.metrics();
+        metrics.searches.inc();
+        metrics.search_duration.observe(start.elapsed());
     }
 
     /// Summarize total space usage of this searcher.
@@ -245,6 +553,77 @@ impl Searcher {
     }
 }
 
+/// Scores and collects the documents of `doc_range` in a segment.
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument(
+        level = "debug",
+        name = "collect_segment",
+        skip_all,
+        fields(
+            segment_ord = segment_ord,
+            segment_id = %segment_reader.segment_id(),
+            start_doc = doc_range.start,
+            end_doc = doc_range.end,
+        )
+    )
+)]
+fn collect_segment_range<C: Collector>(
+    collector: &C,
+    weight: &dyn Weight,
+    segment_ord: u32,
+    segment_reader: &SegmentReader,
+    doc_range: Range<DocId>,
+) -> crate::Result<<C::Child as SegmentCollector>::Fruit> {
+    collector.collect_segment_range(weight, segment_ord, segment_reader, doc_range)
+}
+
+/// Splits the segments into `(segment_ord, doc_range)` slices of about `1 / concurrency` of the
+/// documents, and of at least `min_slice_num_docs` documents.
+fn search_slices(
+    segment_readers: &[SegmentReader],
+    concurrency: usize,
+    min_slice_num_docs: u32,
+) -> Vec<(u32, Range<DocId>)> {
+    let total_num_docs: u64 = segment_readers
+        .iter()
+        .map(|segment_reader| u64::from(segment_reader.max_doc()))
+        .sum();
+    let target_slice_num_docs = (total_num_docs / concurrency.max(1) as u64)
+        .max(u64::from(min_slice_num_docs))
+        .max(1);
+    let mut slices = Vec::new();
+    for (segment_ord, segment_reader) in segment_readers.iter().enumerate() {
+        let max_doc = segment_reader.max_doc();
+        let num_slices = (u64::from(max_doc) / target_slice_num_docs).max(1) as u32;
+        let slice_num_docs = (max_doc + num_slices - 1) / num_slices;
+        for slice_ord in 0..num_slices {
+            let start = slice_ord * slice_num_docs;
+            let end = if slice_ord + 1 == num_slices {
+                max_doc
+            } else {
+                start + slice_num_docs
+            };
+            slices.push((segment_ord as u32, start..end));
+        }
+    }
+    slices
+}
+
+/// Groups the doc ids of `doc_addresses` by segment, along with their position in
+/// `doc_addresses`.
+fn group_by_segment(doc_addresses: &[DocAddress]) -> BTreeMap<u32, (Vec<usize>, Vec<DocId>)> {
+    let mut doc_ids_per_segment: BTreeMap<u32, (Vec<usize>, Vec<DocId>)> = BTreeMap::new();
+    for (pos, doc_address) in doc_addresses.iter().enumerate() {
+        let (positions, doc_ids) = doc_ids_per_segment
+            .entry(doc_address.segment_ord)
+            .or_default();
+        positions.push(pos);
+        doc_ids.push(doc_address.doc_id);
+    }
+    doc_ids_per_segment
+}
+
 impl From<Arc<SearcherInner>> for Searcher {
     fn from(inner: Arc<SearcherInner>) -> Self {
         Searcher { inner }
@@ -261,6 +640,7 @@ pub(crate) struct SearcherInner {
     segment_readers: Vec<SegmentReader>,
     store_readers: Vec<StoreReader>,
     generation: TrackedObject<SearcherGeneration>,
+    global_ordinals_cache: Arc<GlobalOrdinalsCache>,
 }
 
 impl SearcherInner {
@@ -271,6 +651,7 @@ impl SearcherInner {
         segment_readers: Vec<SegmentReader>,
         generation: TrackedObject<SearcherGeneration>,
         doc_store_cache_num_blocks: usize,
+        global_ordinals_cache: Arc<GlobalOrdinalsCache>,
     ) -> io::Result<SearcherInner> {
         assert_eq!(
             &segment_readers
@@ -291,6 +672,7 @@ impl SearcherInner {
             segment_readers,
             store_readers,
             generation,
+            global_ordinals_cache,
         })
     }
 }
@@ -1,4 +1,4 @@
-use crate::collector::Count;
+use crate::collector::{Count, TopDocs};
 use crate::directory::{RamDirectory, WatchCallback};
 use crate::index::SegmentId;
 use crate::indexer::{LogMergePolicy, NoMergePolicy};
@@ -138,6 +138,7 @@ mod mmap_specific {
     use tempfile::TempDir;
 
     use super::*;
+    use crate::directory::MmapDirectory;
 
     #[test]
     fn test_index_on_commit_reload_policy_mmap() -> crate::Result<()> {
@@ -196,7 +197,86 @@ mod mmap_specific {
         assert_eq!(reader.searcher().num_docs(), 0);
         test_index_on_commit_reload_policy_aux(field, &write_index, &reader)
     }
+
+    #[test]
+    fn test_snapshot_hard_links_files() -> crate::Result<()> {
+        let schema = throw_away_schema();
+        let field = schema.get_field("num_likes").unwrap();
+        let index = Index::create_from_tempdir(schema)?;
+        let mut writer: IndexWriter = index.writer_for_tests()?;
+        writer.add_document(doc!(field => 1u64))?;
+        writer.commit()?;
+
+        let snapshot_tempdir = TempDir::new().unwrap();
+        let snapshot_directory = MmapDirectory::open(snapshot_tempdir.path())?;
+        let files = index.create_snapshot(&index.load_metas()?, &snapshot_directory)?;
+        for file in &files {
+            let src_path = index.directory().local_path(file).unwrap();
+            let dest_path = snapshot_directory.local_path(file).unwrap();
+            assert_eq!(
+                std::fs::read(src_path).unwrap(),
+                std::fs::read(dest_path).unwrap()
+            );
+        }
+        let snapshot = Index::open_snapshot(snapshot_directory)?;
+        assert_eq!(snapshot.reader()?.searcher().num_docs(), 1);
+        Ok(())
+    }
+}
+#[test]
+fn test_snapshot_pins_commit() -> crate::Result<()> {
+    let schema = throw_away_schema();
+    let field = schema.get_field("num_likes").unwrap();
+    let index = Index::create_in_ram(schema);
+    let mut writer: IndexWriter = index.writer_for_tests()?;
+    writer.set_merge_policy(Box::new(NoMergePolicy));
+    for i in 0u64..3u64 {
+        writer.add_document(doc!(field => i))?;
+        writer.commit()?;
+    }
+    writer.delete_term(Term::from_field_u64(field, 0u64));
+    writer.commit()?;
+    let commit = index.load_metas()?;
+
+    // Merging and garbage collecting must not remove the files of the pinned commit.
+    let segment_ids = index.searchable_segment_ids()?;
+    writer.merge(&segment_ids).wait()?;
+    writer.add_document(doc!(field => 3u64))?;
+    writer.commit()?;
+    writer.garbage_collect_files().wait()?;
+
+    let snapshot_directory = RamDirectory::create();
+    let files = index.create_snapshot(&commit, &snapshot_directory)?;
+    // 6 files per segment, and a single delete file.
+    assert_eq!(files.len(), 3 * 6 + 1);
+
+    let snapshot = Index::open_snapshot(snapshot_directory)?;
+    assert_eq!(snapshot.load_metas()?.opstamp, commit.opstamp);
+    let searcher = snapshot.reader()?.searcher();
+    assert_eq!(searcher.segment_readers().len(), 3);
+    assert_eq!(searcher.num_docs(), 2);
+    assert!(snapshot.validate_checksum()?.is_empty());
+    Ok(())
 }
+
+#[test]
+fn test_open_snapshot_missing_file() -> crate::Result<()> {
+    let schema = throw_away_schema();
+    let field = schema.get_field("num_likes").unwrap();
+    let index = Index::create_in_ram(schema);
+    let mut writer: IndexWriter = index.writer_for_tests()?;
+    writer.add_document(doc!(field => 1u64))?;
+    writer.commit()?;
+    let snapshot_directory = RamDirectory::create();
+    let files = index.create_snapshot(&index.load_metas()?, &snapshot_directory)?;
+    snapshot_directory.delete(&files[0])?;
+    assert!(matches!(
+        Index::open_snapshot(snapshot_directory),
+        Err(crate::TantivyError::DataCorruption(_))
+    ));
+    Ok(())
+}
+
 fn test_index_on_commit_reload_policy_aux(
     field: Field,
     index: &Index,
@@ -466,3 +546,48 @@ fn test_non_text_json_term_freq_bitpacked() {
         assert_eq!(postings.term_freq(), 1u32);
     }
 }
+
+#[test]
+fn test_search_in_slices() -> crate::Result<()> {
+    let mut schema_builder = Schema::builder();
+    let id = schema_builder.add_u64_field("id", INDEXED);
+    let text = schema_builder.add_text_field("text", TEXT);
+    let mut index = Index::create_in_ram(schema_builder.build());
+    index.set_multithread_executor(4)?;
+    let mut index_writer: IndexWriter = index.writer_for_tests()?;
+    for i in 0..100u64 {
+        let text_val = format!(
+            "{}{}",
+            "apple ".repeat(i as usize % 10 + 1),
+            "pie ".repeat(i as usize / 10)
+        );
+        index_writer.add_document(doc!(id => i, text => text_val))?;
+    }
+    index_writer.commit()?;
+    index_writer.delete_term(Term::from_field_u64(id, 9));
+    index_writer.commit()?;
+    let searcher = index.reader()?.searcher();
+    assert_eq!(searcher.segment_readers().len(), 1);
+    let query = TermQuery::new(
+        Term::from_field_text(text, "apple"),
+        IndexRecordOption::WithFreqs,
+    );
+
+    let top_docs = searcher.search(&query, &TopDocs::with_limit(3))?;
+    let all_docs = searcher.search(&query, &TopDocs::with_limit(100))?;
+    assert_eq!(all_docs.len(), 99);
+    for concurrency in [1, 3, 4, 8] {
+        let sliced_top_docs =
+            searcher.search_in_slices(&query, &TopDocs::with_limit(3), concurrency, 10)?;
+        assert_eq!(sliced_top_docs, top_docs);
+        let sliced_all_docs =
+            searcher.search_in_slices(&query, &TopDocs::with_limit(100), concurrency, 10)?;
+        assert_eq!(sliced_all_docs, all_docs);
+        assert_eq!(
+            searcher.search_in_slices(&query, &Count, concurrency, 10)?,
+            99
+        );
+    }
+    assert_eq!(searcher.search_with_concurrency(&query, &Count, 4)?, 99);
+    Ok(())
+}
@@ -0,0 +1,123 @@
+use crate::schema::{Field, Schema};
+
+/// Selects the data structures loaded ahead of time by [`Searcher::warm`](crate::Searcher::warm).
+///
+/// Data structures are read lazily: after a reload, the first queries touching a field pay for
+/// reading its term dictionary, its fieldnorms... from disk. Warming up a new searcher with the
+/// data structures used by the queries, before routing traffic to it, avoids the latency spike.
+///
+/// ```rust
+/// use tantivy::schema::{Schema, FAST, TEXT};
+/// use tantivy::WarmupSpec;
+///
+/// let mut schema_builder = Schema::builder();
+/// let title = schema_builder.add_text_field("title", TEXT);
+/// schema_builder.add_u64_field("popularity", FAST);
+/// let warmup_spec = WarmupSpec::default()
+///     .term_dictionary(title)
+///     .fieldnorms(title)
+///     .fast_field("popularity")
+///     .store_index();
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct WarmupSpec {
+    pub(crate) term_dictionaries: Vec<Field>,
+    pub(crate) fieldnorms: Vec<Field>,
+    pub(crate) fast_fields: Vec<String>,
+    pub(crate) store_index: bool,
+}
+
+impl WarmupSpec {
+    /// Selects the term dictionaries of the indexed fields, the fieldnorms, the fast fields and
+    /// the store index.
+    ///
+    /// The postings and the documents of the store are not loaded.
+    pub fn all(schema: &Schema) -> WarmupSpec {
+        let mut warmup_spec = WarmupSpec::default().store_index();
+        for (field, field_entry) in schema.fields() {
+            if field_entry.is_indexed() {
+                warmup_spec = warmup_spec.term_dictionary(field);
+            }
+            if field_entry.has_fieldnorms() {
+                warmup_spec = warmup_spec.fieldnorms(field);
+            }
+            if field_entry.is_fast() {
+                warmup_spec = warmup_spec.fast_field(field_entry.name());
+            }
+        }
+        warmup_spec
+    }
+
+    /// Loads the term dictionary of `field`.
+    #[must_use]
+    pub fn term_dictionary(mut self, field: Field) -> WarmupSpec {
+        self.term_dictionaries.push(field);
+        self
+    }
+
+    /// Loads the fieldnorms of `field`.
+    #[must_use]
+    pub fn fieldnorms(mut self, field: Field) -> WarmupSpec {
+        self.fieldnorms.push(field);
+        self
+    }
+
+    /// Loads the columns of the fast field `field_name`.
+    ///
+    /// As in [`FastFieldReaders`](crate::fastfield::FastFieldReaders), `field_name` can be the
+    /// path of a value of a JSON field.
+    #[must_use]
+    pub fn fast_field(mut self, field_name: impl Into<String>) -> WarmupSpec {
+        self.fast_fields.push(field_name.into());
+        self
+    }
+
+    /// Loads the index of the doc store, mapping the doc ids to the compressed blocks.
+    #[must_use]
+    pub fn store_index(mut self) -> WarmupSpec {
+        self.store_index = true;
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::WarmupSpec;
+    use crate::schema::{Schema, FAST, STORED, STRING, TEXT};
+    use crate::{doc, Index, IndexWriter};
+
+    #[test]
+    fn test_warmup_spec() -> crate::Result<()> {
+        let mut schema_builder = Schema::builder();
+        let title = schema_builder.add_text_field("title", TEXT | STORED);
+        let tag = schema_builder.add_text_field("tag", STRING | FAST);
+        let popularity = schema_builder.add_u64_field("popularity", FAST);
+        let schema = schema_builder.build();
+        let index = Index::create_in_ram(schema.clone());
+        let mut index_writer: IndexWriter = index.writer_for_tests()?;
+        index_writer
+            .add_document(doc!(title => "apple pie", tag => "dessert", popularity => 3u64))?;
+        index_writer.add_document(doc!(title => "apple"))?;
+        index_writer.commit()?;
+        let searcher = index.reader()?.searcher();
+
+        let warmup_spec = WarmupSpec::all(&schema);
+        assert_eq!(
+            warmup_spec,
+            WarmupSpec::default()
+                .store_index()
+                .term_dictionary(title)
+                .fieldnorms(title)
+                .term_dictionary(tag)
+                .fieldnorms(tag)
+                .fast_field("tag")
+                .fast_field("popularity")
+        );
+        // Term dictionaries, fieldnorms, fast field columns and store index.
+        let segment_reader = searcher.segment_reader(0);
+        assert_eq!(segment_reader.warmup_file_slices(&warmup_spec)?.len(), 7);
+        searcher.warm(&warmup_spec)?;
+        searcher.warm(&WarmupSpec::default().fast_field("missing"))?;
+        Ok(())
+    }
+}
@@ -0,0 +1,458 @@
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::{fmt, io};
+
+use common::HasLen;
+use lru::LruCache;
+use once_cell::sync::Lazy;
+
+use crate::directory::error::{DeleteError, LockError, OpenReadError, OpenWriteError};
+use crate::directory::{
+    AccessHint, Directory, DirectoryLock, FileHandle, FileKind, IoMetrics, Lock, OwnedBytes,
+    WatchCallback, WatchHandle, WritePtr,
+};
+use crate::store::CacheStats;
+
+const DEFAULT_CACHE_BLOCK_SIZE: usize = 1 << 16;
+
+/// Capacity of the process-wide cache returned by [`SharedBlockCache::global()`].
+const DEFAULT_GLOBAL_CACHE_CAPACITY: usize = 256 << 20;
+
+/// Reads spanning more blocks than this bypass the cache, so that scanning
+/// a large file does not evict the whole cache.
+const MAX_CACHED_BLOCKS_PER_READ: usize = 8;
+
+static GLOBAL_BLOCK_CACHE: Lazy<Arc<SharedBlockCache>> =
+    Lazy::new(|| Arc::new(SharedBlockCache::new(DEFAULT_GLOBAL_CACHE_CAPACITY)));
+
+/// Used to tell apart the files of directories that do not live on the local filesystem.
+static NEXT_DIRECTORY_ID: AtomicU64 = AtomicU64::new(1);
+
+/// Identifies a file across all of the directories sharing a cache.
+#[derive(Debug, PartialEq, Eq, Hash)]
+struct CachedFile {
+    // 0 when `path` is an absolute path on the local filesystem.
+    directory_id: u64,
+    path: PathBuf,
+}
+
+#[derive(Clone, PartialEq, Eq, Hash)]
+struct BlockKey {
+    file: Arc<CachedFile>,
+    block_id: usize,
+}
+
+struct CacheState {
+    blocks: LruCache<BlockKey, OwnedBytes>,
+    num_bytes: usize,
+    capacity: usize,
+}
+
+impl CacheState {
+    fn evict_to_capacity(&mut self) {
+        while self.num_bytes > self.capacity {
+            match self.blocks.pop_lru() {
+                Some((_, block)) => self.num_bytes -= block.len(),
+                None => break,
+            }
+        }
+    }
+}
+
+/// Size-bounded LRU cache of file blocks, meant to be shared by many
+/// [`CachingDirectory`] instances.
+///
+/// Processes opening a lot of small indexes (one per tenant for instance) can share
+/// a single cache across all of them, instead of having each index keep its own
+/// cache state.
+pub struct SharedBlockCache {
+    state: Mutex<CacheState>,
+    block_size: usize,
+    cache_hits: AtomicUsize,
+    cache_misses: AtomicUsize,
+}
+
+impl fmt::Debug for SharedBlockCache {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SharedBlockCache")
+            .field("block_size", &self.block_size)
+            .field("capacity", &self.capacity())
+            .field("num_bytes", &self.num_bytes())
+            .finish()
+    }
+}
+
+impl SharedBlockCache {
+    /// Creates a cache holding at most `capacity` bytes, with blocks of 64KB.
+    pub fn new(capacity: usize) -> SharedBlockCache {
+        SharedBlockCache::with_block_size(capacity, DEFAULT_CACHE_BLOCK_SIZE)
+    }
+
+    /// Creates a cache holding at most `capacity` bytes, with blocks of `block_size` bytes.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `block_size` is 0.
+    pub fn with_block_size(capacity: usize, block_size: usize) -> SharedBlockCache {
+        assert!(block_size > 0, "block size must be strictly positive");
+        SharedBlockCache {
+            state: Mutex::new(CacheState {
+                blocks: LruCache::unbounded(),
+                num_bytes: 0,
+                capacity,
+            }),
+            block_size,
+            cache_hits: AtomicUsize::default(),
+            cache_misses: AtomicUsize::default(),
+        }
+    }
+
+    /// Returns the process-wide cache.
+    ///
+    /// It holds 256MB by default, see [`SharedBlockCache::set_capacity()`].
+    pub fn global() -> Arc<SharedBlockCache> {
+        GLOBAL_BLOCK_CACHE.clone()
+    }
+
+    /// Size of the cached blocks, in bytes.
+    pub fn block_size(&self) -> usize {
+        self.block_size
+    }
+
+    /// Maximum number of bytes held by the cache.
+    pub fn capacity(&self) -> usize {
+        self.state.lock().unwrap().capacity
+    }
+
+    /// Changes the maximum number of bytes held by the cache, evicting blocks if needed.
+    pub fn set_capacity(&self, capacity: usize) {
+        let mut state = self.state.lock().unwrap();
+        state.capacity = capacity;
+        state.evict_to_capacity();
+    }
+
+    /// Number of bytes currently held by the cache.
+    pub fn num_bytes(&self) -> usize {
+        self.state.lock().unwrap().num_bytes
+    }
+
+    /// Returns the hit/miss statistics of the cache.
+    pub fn stats(&self) -> CacheStats {
+        CacheStats {
+            num_entries: self.state.lock().unwrap().blocks.len(),
+            cache_hits: self.cache_hits.load(Ordering::Relaxed),
+            cache_misses: self.cache_misses.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Removes all of the blocks from the cache.
+    pub fn clear(&self) {
+        let mut state = self.state.lock().unwrap();
+        state.blocks.clear();
+        state.num_bytes = 0;
+    }
+
+    fn get(&self, key: &BlockKey) -> Option<OwnedBytes> {
+        let block = self.state.lock().unwrap().blocks.get(key).cloned();
+        if block.is_some() {
+            self.cache_hits.fetch_add(1, Ordering::Relaxed);
+        } else {
+            self.cache_misses.fetch_add(1, Ordering::Relaxed);
+        }
+        block
+    }
+
+    fn put(&self, key: BlockKey, block: OwnedBytes) {
+        let mut state = self.state.lock().unwrap();
+        if block.len() > state.capacity {
+            return;
+        }
+        state.num_bytes += block.len();
+        if let Some(previous_block) = state.blocks.put(key, block) {
+            state.num_bytes -= previous_block.len();
+        }
+        state.evict_to_capacity();
+    }
+
+    fn evict_file(&self, file: &CachedFile) {
+        let mut state = self.state.lock().unwrap();
+        let keys: Vec<BlockKey> = state
+            .blocks
+            .iter()
+            .filter(|(key, _)| key.file.as_ref() == file)
+            .map(|(key, _)| key.clone())
+            .collect();
+        for key in keys {
+            if let Some(block) = state.blocks.pop(&key) {
+                state.num_bytes -= block.len();
+            }
+        }
+    }
+}
+
+/// File handle serving reads from a [`SharedBlockCache`], and falling back to the
+/// underlying file handle on cache misses.
+struct CachedFileHandle {
+    file: Arc<CachedFile>,
+    underlying: Arc<dyn FileHandle>,
+    cache: Arc<SharedBlockCache>,
+    file_kind: FileKind,
+    io_metrics: Option<IoMetrics>,
+}
+
+impl fmt::Debug for CachedFileHandle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "CachedFileHandle({:?})", self.file.path)
+    }
+}
+
+impl CachedFileHandle {
+    fn read_block(&self, block_id: usize) -> io::Result<OwnedBytes> {
+        let key = BlockKey {
+            file: self.file.clone(),
+            block_id,
+        };
+        let cached_block = self.cache.get(&key);
+        if let Some(io_metrics) = &self.io_metrics {
+            io_metrics.record_cache_access(self.file_kind, cached_block.is_some());
+        }
+        if let Some(block) = cached_block {
+            return Ok(block);
+        }
+        let block_size = self.cache.block_size();
+        let block_start = block_id * block_size;
+        let block_end = (block_start + block_size).min(self.len());
+        let block = self.underlying.read_bytes(block_start..block_end)?;
+        self.cache.put(key, block.clone());
+        Ok(block)
+    }
+}
+
+impl HasLen for CachedFileHandle {
+    fn len(&self) -> usize {
+        self.underlying.len()
+    }
+}
+
+impl FileHandle for CachedFileHandle {
+    fn read_bytes(&self, range: Range<usize>) -> io::Result<OwnedBytes> {
+        if range.is_empty() || range.end > self.len() {
+            return self.underlying.read_bytes(range);
+        }
+        let block_size = self.cache.block_size();
+        let first_block = range.start / block_size;
+        let last_block = (range.end - 1) / block_size;
+        if last_block - first_block >= MAX_CACHED_BLOCKS_PER_READ {
+            return self.underlying.read_bytes(range);
+        }
+        if first_block == last_block {
+            let block_start = first_block * block_size;
+            let block = self.read_block(first_block)?;
+            return Ok(block.slice(range.start - block_start..range.end - block_start));
+        }
+        let mut buffer = Vec::with_capacity(range.len());
+        for block_id in first_block..=last_block {
+            let block_start = block_id * block_size;
+            let block = self.read_block(block_id)?;
+            let start = range.start.max(block_start) - block_start;
+            let end = range.end.min(block_start + block.len()) - block_start;
+            buffer.extend_from_slice(&block.as_slice()[start..end]);
+        }
+        Ok(OwnedBytes::new(buffer))
+    }
+}
+
+/// Directory wrapper caching the blocks read from the underlying directory in a
+/// [`SharedBlockCache`].
+///
+/// This is mostly useful on top of directories that do not benefit from the OS page
+/// cache, such as object storage backed directories. Many directories can share the
+/// same cache, e.g. [`SharedBlockCache::global()`]. Files that the underlying directory
+/// exposes on the local filesystem are keyed by their absolute path, so that two
+/// `Index` instances opened on the same directory also share their cached blocks.
+#[derive(Clone)]
+pub struct CachingDirectory {
+    underlying: Box<dyn Directory>,
+    cache: Arc<SharedBlockCache>,
+    directory_id: u64,
+    io_metrics: Option<IoMetrics>,
+}
+
+impl fmt::Debug for CachingDirectory {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "CachingDirectory({:?})", self.underlying)
+    }
+}
+
+impl CachingDirectory {
+    /// Wraps a directory, caching its reads in `cache`.
+    pub fn wrap<D: Into<Box<dyn Directory>>>(
+        underlying: D,
+        cache: Arc<SharedBlockCache>,
+    ) -> CachingDirectory {
+        CachingDirectory {
+            underlying: underlying.into(),
+            cache,
+            directory_id: NEXT_DIRECTORY_ID.fetch_add(1, Ordering::Relaxed),
+            io_metrics: None,
+        }
+    }
+
+    /// Counts the cache hits and misses of this directory in `io_metrics`.
+    #[must_use]
+    pub fn set_io_metrics(mut self, io_metrics: IoMetrics) -> Self {
+        self.io_metrics = Some(io_metrics);
+        self
+    }
+
+    /// Returns the cache used by this directory.
+    pub fn cache(&self) -> &Arc<SharedBlockCache> {
+        &self.cache
+    }
+
+    fn cached_file(&self, path: &Path) -> CachedFile {
+        match self.underlying.local_path(path) {
+            Some(local_path) => CachedFile {
+                directory_id: 0,
+                path: local_path,
+            },
+            None => CachedFile {
+                directory_id: self.directory_id,
+                path: path.to_path_buf(),
+            },
+        }
+    }
+}
+
+impl Directory for CachingDirectory {
+    fn get_file_handle(&self, path: &Path) -> Result<Arc<dyn FileHandle>, OpenReadError> {
+        let underlying = self.underlying.get_file_handle(path)?;
+        Ok(Arc::new(CachedFileHandle {
+            file: Arc::new(self.cached_file(path)),
+            underlying,
+            cache: self.cache.clone(),
+            file_kind: FileKind::for_path(path),
+            io_metrics: self.io_metrics.clone(),
+        }))
+    }
+
+    fn delete(&self, path: &Path) -> Result<(), DeleteError> {
+        self.underlying.delete(path)?;
+        self.cache.evict_file(&self.cached_file(path));
+        Ok(())
+    }
+
+    fn exists(&self, path: &Path) -> Result<bool, OpenReadError> {
+        self.underlying.exists(path)
+    }
+
+    fn open_write(&self, path: &Path) -> Result<WritePtr, OpenWriteError> {
+        self.underlying.open_write(path)
+    }
+
+    fn atomic_read(&self, path: &Path) -> Result<Vec<u8>, OpenReadError> {
+        self.underlying.atomic_read(path)
+    }
+
+    fn atomic_write(&self, path: &Path, data: &[u8]) -> io::Result<()> {
+        self.underlying.atomic_write(path, data)
+    }
+
+    fn sync_directory(&self) -> io::Result<()> {
+        self.underlying.sync_directory()
+    }
+
+    fn advise(&self, path: &Path, hint: AccessHint) -> io::Result<()> {
+        self.underlying.advise(path, hint)
+    }
+
+    fn acquire_lock(&self, lock: &Lock) -> Result<DirectoryLock, LockError> {
+        self.underlying.acquire_lock(lock)
+    }
+
+    fn watch(&self, watch_callback: WatchCallback) -> crate::Result<WatchHandle> {
+        self.underlying.watch(watch_callback)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+    use std::path::Path;
+    use std::sync::Arc;
+
+    use super::{CachingDirectory, SharedBlockCache};
+    use crate::directory::{Directory, RamDirectory, TerminatingWrite};
+    use crate::schema::{Schema, STORED, TEXT};
+    use crate::{Index, IndexWriter, TantivyDocument};
+
+    fn write_file(directory: &dyn Directory, path: &Path, data: &[u8]) -> crate::Result<()> {
+        let mut wrt = directory.open_write(path)?;
+        wrt.write_all(data)?;
+        wrt.terminate()?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_caching_directory_reads() -> crate::Result<()> {
+        let cache = Arc::new(SharedBlockCache::with_block_size(1_000, 100));
+        let directory = CachingDirectory::wrap(RamDirectory::create(), cache.clone());
+        let path = Path::new("test");
+        let data: Vec<u8> = (0..1_000u32).map(|i| (i % 251) as u8).collect();
+        write_file(&directory, path, &data)?;
+        let file = directory.open_read(path)?;
+        assert_eq!(file.read_bytes_slice(50..250)?.as_slice(), &data[50..250]);
+        assert_eq!(cache.stats().cache_misses, 3);
+        assert_eq!(file.read_bytes_slice(120..180)?.as_slice(), &data[120..180]);
+        assert_eq!(cache.stats().cache_hits, 1);
+        assert_eq!(file.read_bytes()?.as_slice(), &data[..]);
+        assert_eq!(cache.num_bytes(), 300);
+
+        directory.delete(path)?;
+        assert_eq!(cache.num_bytes(), 0);
+        Ok(())
+    }
+
+    #[test]
+    fn test_shared_block_cache_is_bounded() -> crate::Result<()> {
+        let cache = Arc::new(SharedBlockCache::with_block_size(250, 100));
+        let directories: Vec<CachingDirectory> = (0..3)
+            .map(|_| CachingDirectory::wrap(RamDirectory::create(), cache.clone()))
+            .collect();
+        let path = Path::new("test");
+        for (i, directory) in directories.iter().enumerate() {
+            write_file(directory, path, &[i as u8; 100])?;
+            let file = directory.open_read(path)?;
+            assert_eq!(file.read_bytes()?.as_slice(), &[i as u8; 100]);
+        }
+        // Same path, but different directories.
+        assert_eq!(cache.stats().cache_misses, 3);
+        assert_eq!(cache.num_bytes(), 200);
+        cache.set_capacity(100);
+        assert_eq!(cache.num_bytes(), 100);
+        assert_eq!(cache.stats().num_entries, 1);
+        Ok(())
+    }
+
+    #[test]
+    fn test_caching_directory_index() -> crate::Result<()> {
+        let mut schema_builder = Schema::builder();
+        let text = schema_builder.add_text_field("text", TEXT | STORED);
+        let cache = Arc::new(SharedBlockCache::new(1 << 20));
+        let directory = CachingDirectory::wrap(RamDirectory::create(), cache.clone());
+        let index = Index::create(directory, schema_builder.build(), Default::default())?;
+        let mut writer: IndexWriter = index.writer_for_tests()?;
+        writer.add_document(doc!(text => "hello happy tax payer"))?;
+        writer.commit()?;
+        let searcher = index.reader()?.searcher();
+        let doc: TantivyDocument = searcher.doc(crate::DocAddress::new(0, 0))?;
+        assert_eq!(
+            doc.get_first(text).unwrap().as_value().as_str(),
+            Some("hello happy tax payer")
+        );
+        assert!(cache.num_bytes() > 0);
+        Ok(())
+    }
+}
@@ -0,0 +1,545 @@
+use std::io::{self, BufWriter, Write};
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::{fmt, mem};
+
+use common::{BinarySerializable, FixedSize, HasLen};
+use crc32fast::Hasher;
+
+use crate::directory::error::{DeleteError, LockError, OpenReadError, OpenWriteError};
+use crate::directory::{
+    AccessHint, AntiCallToken, Directory, DirectoryLock, FileHandle, FileSlice, Lock, OwnedBytes,
+    TerminatingWrite, WatchCallback, WatchHandle, WritePtr,
+};
+use crate::error::DataCorruption;
+
+/// Magic number identifying a file written by the [`ChecksumDirectory`].
+const CHECKSUM_FILE_MAGIC_NUMBER: u32 = 0x6b43_5243;
+
+/// Default size of the checksummed blocks.
+const DEFAULT_CHECKSUM_BLOCK_SIZE: usize = 1 << 16;
+
+/// Trailer appended at the end of each checksummed file.
+///
+/// The block checksums (one `u32` per block) are written right before the trailer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct ChecksumTrailer {
+    num_bytes: u64,
+    block_size: u32,
+}
+
+impl ChecksumTrailer {
+    fn num_blocks(&self) -> u64 {
+        let block_size = u64::from(self.block_size);
+        self.num_bytes / block_size + u64::from(self.num_bytes % block_size != 0)
+    }
+
+    /// Returns the length of the data followed by the checksums of its blocks, or `None` if it
+    /// overflows.
+    fn body_len(&self) -> Option<u64> {
+        self.num_blocks()
+            .checked_mul(mem::size_of::<u32>() as u64)?
+            .checked_add(self.num_bytes)
+    }
+}
+
+impl BinarySerializable for ChecksumTrailer {
+    fn serialize<W: io::Write + ?Sized>(&self, writer: &mut W) -> io::Result<()> {
+        self.num_bytes.serialize(writer)?;
+        self.block_size.serialize(writer)?;
+        CHECKSUM_FILE_MAGIC_NUMBER.serialize(writer)?;
+        Ok(())
+    }
+
+    fn deserialize<R: io::Read>(reader: &mut R) -> io::Result<Self> {
+        let num_bytes = u64::deserialize(reader)?;
+        let block_size = u32::deserialize(reader)?;
+        let magic_number = u32::deserialize(reader)?;
+        if magic_number != CHECKSUM_FILE_MAGIC_NUMBER || block_size == 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "Invalid checksum trailer.",
+            ));
+        }
+        Ok(ChecksumTrailer {
+            num_bytes,
+            block_size,
+        })
+    }
+}
+
+impl FixedSize for ChecksumTrailer {
+    const SIZE_IN_BYTES: usize = 16;
+}
+
+/// Returns true if the file ends with a checksum trailer.
+///
+/// A legacy file may end with the magic number by chance, so the trailer must also be consistent
+/// with the length of the file: the data and the checksums of its blocks fill the file up to the
+/// trailer.
+fn has_checksum_trailer(file: &FileSlice) -> io::Result<bool> {
+    if file.len() < ChecksumTrailer::SIZE_IN_BYTES {
+        return Ok(false);
+    }
+    let trailer_bytes = file
+        .slice_from_end(ChecksumTrailer::SIZE_IN_BYTES)
+        .read_bytes()?;
+    let Ok(trailer) = ChecksumTrailer::deserialize(&mut trailer_bytes.as_slice()) else {
+        return Ok(false);
+    };
+    let body_len = (file.len() - ChecksumTrailer::SIZE_IN_BYTES) as u64;
+    Ok(trailer.body_len() == Some(body_len))
+}
+
+fn block_checksum(block: &[u8]) -> u32 {
+    let mut hasher = Hasher::new();
+    hasher.update(block);
+    hasher.finalize()
+}
+
+/// Writer computing a crc32 checksum for every block of `block_size` bytes.
+struct ChecksumWriter {
+    underlying: WritePtr,
+    block_size: usize,
+    hasher: Hasher,
+    num_bytes_in_block: usize,
+    checksums: Vec<u32>,
+    num_bytes: u64,
+}
+
+impl ChecksumWriter {
+    fn new(underlying: WritePtr, block_size: usize) -> ChecksumWriter {
+        ChecksumWriter {
+            underlying,
+            block_size,
+            hasher: Hasher::new(),
+            num_bytes_in_block: 0,
+            checksums: Vec::new(),
+            num_bytes: 0u64,
+        }
+    }
+
+    fn close_block(&mut self) {
+        if self.num_bytes_in_block == 0 {
+            return;
+        }
+        let hasher = mem::replace(&mut self.hasher, Hasher::new());
+        self.checksums.push(hasher.finalize());
+        self.num_bytes_in_block = 0;
+    }
+}
+
+impl Write for ChecksumWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let available = self.block_size - self.num_bytes_in_block;
+        let num_bytes = self.underlying.write(&buf[..available.min(buf.len())])?;
+        self.hasher.update(&buf[..num_bytes]);
+        self.num_bytes_in_block += num_bytes;
+        self.num_bytes += num_bytes as u64;
+        if self.num_bytes_in_block == self.block_size {
+            self.close_block();
+        }
+        Ok(num_bytes)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.underlying.flush()
+    }
+}
+
+impl TerminatingWrite for ChecksumWriter {
+    fn terminate_ref(&mut self, token: AntiCallToken) -> io::Result<()> {
+        self.close_block();
+        for &checksum in &self.checksums {
+            checksum.serialize(&mut self.underlying)?;
+        }
+        let trailer = ChecksumTrailer {
+            num_bytes: self.num_bytes,
+            block_size: self.block_size as u32,
+        };
+        trailer.serialize(&mut self.underlying)?;
+        self.underlying.terminate_ref(token)
+    }
+}
+
+/// File handle checking the checksum of every block before returning its data.
+///
+/// Each block is only verified once for the lifetime of the handle.
+struct ChecksumFileHandle {
+    path: PathBuf,
+    data: FileSlice,
+    block_size: usize,
+    checksums: Vec<u32>,
+    verified: Vec<AtomicBool>,
+}
+
+impl fmt::Debug for ChecksumFileHandle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "ChecksumFileHandle({:?})", self.path)
+    }
+}
+
+impl HasLen for ChecksumFileHandle {
+    fn len(&self) -> usize {
+        self.data.len()
+    }
+}
+
+impl ChecksumFileHandle {
+    fn verify_blocks(&self, block_ids: Range<usize>, data: &[u8]) -> io::Result<()> {
+        let first_block_start = block_ids.start * self.block_size;
+        for block_id in block_ids {
+            if self.verified[block_id].load(Ordering::Relaxed) {
+                continue;
+            }
+            let block_start = block_id * self.block_size;
+            let block_end = (block_start + self.block_size).min(self.data.len());
+            let block = &data[block_start - first_block_start..block_end - first_block_start];
+            if block_checksum(block) != self.checksums[block_id] {
+                let data_corruption = DataCorruption::new(
+                    self.path.clone(),
+                    format!("Checksum mismatch for block {block_id}"),
+                )
+                .with_offset(block_start as u64);
+                return Err(io::Error::new(io::ErrorKind::InvalidData, data_corruption));
+            }
+            self.verified[block_id].store(true, Ordering::Relaxed);
+        }
+        Ok(())
+    }
+}
+
+impl FileHandle for ChecksumFileHandle {
+    fn read_bytes(&self, range: Range<usize>) -> io::Result<OwnedBytes> {
+        if range.end > self.data.len() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "Range {range:?} exceeds the length of file {:?} ({}).",
+                    self.path,
+                    self.data.len()
+                ),
+            ));
+        }
+        if range.is_empty() {
+            return Ok(OwnedBytes::empty());
+        }
+        let first_block = range.start / self.block_size;
+        let last_block = (range.end - 1) / self.block_size;
+        let start = first_block * self.block_size;
+        let end = ((last_block + 1) * self.block_size).min(self.data.len());
+        let bytes = self.data.read_bytes_slice(start..end)?;
+        self.verify_blocks(first_block..last_block + 1, bytes.as_slice())?;
+        Ok(bytes.slice(range.start - start..range.end - start))
+    }
+}
+
+/// Splits a checksummed file into its data and the checksum of its blocks.
+fn open_checksummed_file(
+    path: &Path,
+    file: FileSlice,
+) -> io::Result<(FileSlice, ChecksumTrailer, Vec<u32>)> {
+    let (body, trailer_slice) = file.split_from_end(ChecksumTrailer::SIZE_IN_BYTES);
+    let trailer = ChecksumTrailer::deserialize(&mut trailer_slice.read_bytes()?)?;
+    if trailer.body_len() != Some(body.len() as u64) {
+        let data_corruption = DataCorruption::new(
+            path.to_path_buf(),
+            format!(
+                "File length {} does not match the length recorded in its checksum trailer",
+                body.len()
+            ),
+        );
+        return Err(io::Error::new(io::ErrorKind::InvalidData, data_corruption));
+    }
+    // The checksums fit in the file, hence in memory.
+    let num_blocks = trailer.num_blocks() as usize;
+    let checksums_num_bytes = num_blocks * mem::size_of::<u32>();
+    let (data, checksums_slice) = body.split_from_end(checksums_num_bytes);
+    let checksums_bytes = checksums_slice.read_bytes()?;
+    let mut cursor = checksums_bytes.as_slice();
+    let checksums = (0..num_blocks)
+        .map(|_| u32::deserialize(&mut cursor))
+        .collect::<io::Result<Vec<u32>>>()?;
+    Ok((data, trailer, checksums))
+}
+
+/// Directory wrapper storing a checksum for every block of the files it writes.
+///
+/// Wrapping a directory in a `ChecksumDirectory` is opt-in. When enabled, the
+/// checksums of the blocks are verified the first time they are read, and
+/// corruption is reported as a [`DataCorruption`] error naming the file, the offset
+/// of the corrupted block and the segment. Bit rot is hence detected before corrupted
+/// bytes reach the decoders.
+///
+/// Verification on read can be disabled with
+/// [`ChecksumDirectory::set_verify_on_read`], in which case files can still be
+/// checked explicitly using [`ChecksumDirectory::verify_file`].
+///
+/// Files written without checksums (e.g. before the directory got wrapped) are
+/// passed through unchanged. Atomic writes and locks are passed through as well.
+#[derive(Clone)]
+pub struct ChecksumDirectory {
+    underlying: Box<dyn Directory>,
+    block_size: usize,
+    verify_on_read: bool,
+}
+
+impl fmt::Debug for ChecksumDirectory {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "ChecksumDirectory({:?})", self.underlying)
+    }
+}
+
+impl ChecksumDirectory {
+    /// Wraps a directory.
+    pub fn wrap<D: Into<Box<dyn Directory>>>(underlying: D) -> ChecksumDirectory {
+        ChecksumDirectory {
+            underlying: underlying.into(),
+            block_size: DEFAULT_CHECKSUM_BLOCK_SIZE,
+            verify_on_read: true,
+        }
+    }
+
+    /// Sets the size of the checksummed blocks.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `block_size` is 0 or does not fit in a `u32`.
+    #[must_use]
+    pub fn set_block_size(mut self, block_size: usize) -> ChecksumDirectory {
+        assert!(block_size > 0, "block size must be strictly positive");
+        assert!(
+            block_size <= u32::MAX as usize,
+            "block size must fit in a u32"
+        );
+        self.block_size = block_size;
+        self
+    }
+
+    /// Enables or disables the verification of checksums on read. (defaults: true)
+    #[must_use]
+    pub fn set_verify_on_read(mut self, verify_on_read: bool) -> ChecksumDirectory {
+        self.verify_on_read = verify_on_read;
+        self
+    }
+
+    /// Verifies the checksums of all of the blocks of a file.
+    ///
+    /// Returns `Ok(false)` if the file was not written with checksums.
+    /// If a block is corrupted, a [`TantivyError::DataCorruption`](crate::TantivyError)
+    /// is returned.
+    pub fn verify_file(&self, path: &Path) -> crate::Result<bool> {
+        let file_slice = self.underlying.open_read(path)?;
+        if !has_checksum_trailer(&file_slice)? {
+            return Ok(false);
+        }
+        let (data, trailer, checksums) = open_checksummed_file(path, file_slice)?;
+        let block_size = trailer.block_size as usize;
+        for (block_id, &checksum) in checksums.iter().enumerate() {
+            let block_start = block_id * block_size;
+            let block_end = (block_start + block_size).min(data.len());
+            let block = data.read_bytes_slice(block_start..block_end)?;
+            if block_checksum(block.as_slice()) != checksum {
+                return Err(DataCorruption::new(
+                    path.to_path_buf(),
+                    format!("Checksum mismatch for block {block_id}"),
+                )
+                .with_offset(block_start as u64)
+                .into());
+            }
+        }
+        Ok(true)
+    }
+}
+
+impl Directory for ChecksumDirectory {
+    fn get_file_handle(&self, path: &Path) -> Result<Arc<dyn FileHandle>, OpenReadError> {
+        let file_slice = self.underlying.open_read(path)?;
+        let has_checksums = has_checksum_trailer(&file_slice)
+            .map_err(|io_error| OpenReadError::wrap_io_error(io_error, path.to_path_buf()))?;
+        if !has_checksums {
+            return Ok(Arc::new(file_slice));
+        }
+        let (data, trailer, checksums) = open_checksummed_file(path, file_slice)
+            .map_err(|io_error| OpenReadError::wrap_io_error(io_error, path.to_path_buf()))?;
+        if !self.verify_on_read {
+            return Ok(Arc::new(data));
+        }
+        let verified = (0..checksums.len())
+            .map(|_| AtomicBool::new(false))
+            .collect();
+        Ok(Arc::new(ChecksumFileHandle {
+            path: path.to_path_buf(),
+            data,
+            block_size: trailer.block_size as usize,
+            checksums,
+            verified,
+        }))
+    }
+
+    fn delete(&self, path: &Path) -> Result<(), DeleteError> {
+        self.underlying.delete(path)
+    }
+
+    fn exists(&self, path: &Path) -> Result<bool, OpenReadError> {
+        self.underlying.exists(path)
+    }
+
+    fn open_write(&self, path: &Path) -> Result<WritePtr, OpenWriteError> {
+        let underlying_wrt = self.underlying.open_write(path)?;
+        let checksum_wrt = ChecksumWriter::new(underlying_wrt, self.block_size);
+        Ok(BufWriter::new(Box::new(checksum_wrt)))
+    }
+
+    fn atomic_read(&self, path: &Path) -> Result<Vec<u8>, OpenReadError> {
+        self.underlying.atomic_read(path)
+    }
+
+    fn atomic_write(&self, path: &Path, data: &[u8]) -> io::Result<()> {
+        self.underlying.atomic_write(path, data)
+    }
+
+    fn sync_directory(&self) -> io::Result<()> {
+        self.underlying.sync_directory()
+    }
+
+    fn advise(&self, path: &Path, hint: AccessHint) -> io::Result<()> {
+        self.underlying.advise(path, hint)
+    }
+
+    fn acquire_lock(&self, lock: &Lock) -> Result<DirectoryLock, LockError> {
+        self.underlying.acquire_lock(lock)
+    }
+
+    fn watch(&self, watch_callback: WatchCallback) -> crate::Result<WatchHandle> {
+        self.underlying.watch(watch_callback)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{self, Write};
+    use std::path::{Path, PathBuf};
+
+    use common::HasLen;
+
+    use super::{ChecksumDirectory, ChecksumTrailer, CHECKSUM_FILE_MAGIC_NUMBER};
+    use crate::directory::{Directory, RamDirectory, TerminatingWrite};
+    use crate::index::SegmentId;
+    use crate::TantivyError;
+
+    fn write_file(directory: &dyn Directory, path: &Path, data: &[u8]) -> crate::Result<()> {
+        let mut wrt = directory.open_write(path)?;
+        wrt.write_all(data)?;
+        wrt.terminate()?;
+        Ok(())
+    }
+
+    /// Flips a bit of the raw file at the given offset.
+    fn corrupt(ram_directory: &RamDirectory, path: &Path, offset: usize) -> crate::Result<()> {
+        let mut data = ram_directory
+            .open_read(path)?
+            .read_bytes()?
+            .as_slice()
+            .to_vec();
+        data[offset] ^= 1;
+        ram_directory.delete(path)?;
+        write_file(ram_directory, path, &data)
+    }
+
+    #[test]
+    fn test_checksum_directory_read() -> crate::Result<()> {
+        let ram_directory = RamDirectory::create();
+        let directory = ChecksumDirectory::wrap(ram_directory).set_block_size(10);
+        let path = Path::new("file");
+        let data: Vec<u8> = (0u8..95u8).collect();
+        write_file(&directory, path, &data)?;
+        let file = directory.open_read(path)?;
+        assert_eq!(file.len(), 95);
+        assert_eq!(file.read_bytes()?.as_slice(), &data[..]);
+        assert_eq!(file.read_bytes_slice(12..37)?.as_slice(), &data[12..37]);
+        assert_eq!(file.read_bytes_slice(90..95)?.as_slice(), &data[90..95]);
+        let file_handle = directory.get_file_handle(path)?;
+        let io_err = file_handle.read_bytes(90..96).unwrap_err();
+        assert_eq!(io_err.kind(), io::ErrorKind::InvalidInput);
+        assert!(directory.verify_file(path)?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_checksum_directory_detects_corruption() -> crate::Result<()> {
+        let ram_directory = RamDirectory::create();
+        let directory = ChecksumDirectory::wrap(ram_directory.clone()).set_block_size(10);
+        let segment_id = SegmentId::generate_random();
+        let path_buf: PathBuf = format!("{}.idx", segment_id.uuid_string()).into();
+        let path: &Path = &path_buf;
+        let data: Vec<u8> = (0u8..95u8).collect();
+        write_file(&directory, path, &data)?;
+        corrupt(&ram_directory, path, 42)?;
+        let file = directory.open_read(path)?;
+        assert_eq!(file.read_bytes_slice(0..40)?.as_slice(), &data[0..40]);
+        let io_err = file.read_bytes_slice(35..45).unwrap_err();
+        let TantivyError::DataCorruption(data_corruption) = TantivyError::from(io_err) else {
+            panic!("expected a data corruption error");
+        };
+        assert_eq!(data_corruption.filepath(), Some(path));
+        assert_eq!(data_corruption.offset(), Some(40));
+        assert_eq!(data_corruption.segment_id(), Some(segment_id));
+        assert!(matches!(
+            directory.verify_file(path),
+            Err(TantivyError::DataCorruption(_))
+        ));
+        let unverified_directory = directory.set_verify_on_read(false);
+        assert!(unverified_directory
+            .open_read(path)?
+            .read_bytes_slice(35..45)
+            .is_ok());
+        Ok(())
+    }
+
+    #[test]
+    fn test_checksum_directory_legacy_file_ending_with_magic_number() -> crate::Result<()> {
+        let ram_directory = RamDirectory::create();
+        let path = Path::new("legacy");
+        let mut data: Vec<u8> = (0u8..20u8).collect();
+        data.extend_from_slice(&CHECKSUM_FILE_MAGIC_NUMBER.to_le_bytes());
+        write_file(&ram_directory, path, &data)?;
+        let directory = ChecksumDirectory::wrap(ram_directory);
+        assert_eq!(
+            directory.open_read(path)?.read_bytes()?.as_slice(),
+            &data[..]
+        );
+        assert!(!directory.verify_file(path)?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_checksum_trailer_body_len_overflow() {
+        let trailer = ChecksumTrailer {
+            num_bytes: u64::MAX,
+            block_size: 1,
+        };
+        assert_eq!(trailer.body_len(), None);
+        let trailer = ChecksumTrailer {
+            num_bytes: 10,
+            block_size: 4,
+        };
+        assert_eq!(trailer.body_len(), Some(10 + 3 * 4));
+    }
+
+    #[test]
+    fn test_checksum_directory_legacy_file() -> crate::Result<()> {
+        let ram_directory = RamDirectory::create();
+        let path = Path::new("legacy");
+        write_file(&ram_directory, path, b"hello")?;
+        let directory = ChecksumDirectory::wrap(ram_directory);
+        assert_eq!(
+            directory.open_read(path)?.read_bytes()?.as_slice(),
+            b"hello"
+        );
+        assert!(!directory.verify_file(path)?);
+        Ok(())
+    }
+}
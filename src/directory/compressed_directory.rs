@@ -0,0 +1,680 @@
+use std::io::{self, BufWriter, Write};
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::{fmt, mem};
+
+use common::{BinarySerializable, FixedSize, HasLen};
+
+use crate::directory::error::{DeleteError, LockError, OpenReadError, OpenWriteError};
+use crate::directory::{
+    AntiCallToken, Directory, DirectoryLock, FileHandle, FileSlice, Lock, OwnedBytes,
+    TerminatingWrite, WatchCallback, WatchHandle, WritePtr,
+};
+use crate::error::DataCorruption;
+use crate::store::{Compressor, Decompressor};
+
+/// Magic number identifying a file written by the [`CompressedDirectory`].
+const COMPRESSED_FILE_MAGIC_NUMBER: u32 = 0x7a43_4f4d;
+
+/// Default size of the uncompressed blocks.
+const DEFAULT_COMPRESSED_BLOCK_SIZE: usize = 1 << 16;
+
+type CompressionFilter = Arc<dyn Fn(&Path) -> bool + Send + Sync>;
+
+/// Trailer appended at the end of each compressed file.
+///
+/// The block offsets (`num_blocks + 1` u64) are written right before the trailer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct CompressedFileTrailer {
+    num_bytes: u64,
+    block_size: u32,
+    decompressor: Decompressor,
+}
+
+impl CompressedFileTrailer {
+    fn num_blocks(&self) -> usize {
+        num_blocks(self.num_bytes, self.block_size) as usize
+    }
+}
+
+fn num_blocks(num_bytes: u64, block_size: u32) -> u64 {
+    let block_size = u64::from(block_size);
+    num_bytes / block_size + u64::from(num_bytes % block_size != 0)
+}
+
+impl BinarySerializable for CompressedFileTrailer {
+    fn serialize<W: io::Write + ?Sized>(&self, writer: &mut W) -> io::Result<()> {
+        self.num_bytes.serialize(writer)?;
+        self.block_size.serialize(writer)?;
+        self.decompressor.get_id().serialize(writer)?;
+        COMPRESSED_FILE_MAGIC_NUMBER.serialize(writer)?;
+        Ok(())
+    }
+
+    fn deserialize<R: io::Read>(reader: &mut R) -> io::Result<Self> {
+        let num_bytes = u64::deserialize(reader)?;
+        let block_size = u32::deserialize(reader)?;
+        let decompressor_id = u8::deserialize(reader)?;
+        let magic_number = u32::deserialize(reader)?;
+        if magic_number != COMPRESSED_FILE_MAGIC_NUMBER {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "Compressed file magic number mismatch.",
+            ));
+        }
+        if block_size == 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "Compressed file has a block size of 0.",
+            ));
+        }
+        Ok(CompressedFileTrailer {
+            num_bytes,
+            block_size,
+            decompressor: Decompressor::try_from_id(decompressor_id)?,
+        })
+    }
+}
+
+impl FixedSize for CompressedFileTrailer {
+    const SIZE_IN_BYTES: usize = 17;
+}
+
+/// Returns true if the file ends with a compressed file trailer.
+///
+/// A raw file may end with the magic number by chance, so the trailer must also be consistent
+/// with the length of the file: the block offsets fit before the trailer, and the last of them
+/// points right after the blocks.
+fn has_compressed_trailer(file: &FileSlice) -> io::Result<bool> {
+    if file.len() < CompressedFileTrailer::SIZE_IN_BYTES + mem::size_of::<u64>() {
+        return Ok(false);
+    }
+    let trailer_bytes = file
+        .slice_from_end(CompressedFileTrailer::SIZE_IN_BYTES)
+        .read_bytes()?;
+    let mut cursor = trailer_bytes.as_slice();
+    let num_bytes = u64::deserialize(&mut cursor)?;
+    let block_size = u32::deserialize(&mut cursor)?;
+    let _decompressor_id = u8::deserialize(&mut cursor)?;
+    let magic_number = u32::deserialize(&mut cursor)?;
+    if magic_number != COMPRESSED_FILE_MAGIC_NUMBER || block_size == 0 {
+        return Ok(false);
+    }
+    let body_len = (file.len() - CompressedFileTrailer::SIZE_IN_BYTES) as u64;
+    let offsets_num_bytes = (num_blocks(num_bytes, block_size) + 1)
+        .checked_mul(mem::size_of::<u64>() as u64)
+        .unwrap_or(u64::MAX);
+    if offsets_num_bytes > body_len {
+        return Ok(false);
+    }
+    let body_len = body_len as usize;
+    let last_offset_bytes = file
+        .slice(body_len - mem::size_of::<u64>()..body_len)
+        .read_bytes()?;
+    let last_offset = u64::deserialize(&mut last_offset_bytes.as_slice())?;
+    Ok(last_offset == body_len as u64 - offsets_num_bytes)
+}
+
+fn data_corruption(path: &Path, comment: String) -> io::Error {
+    let data_corruption = DataCorruption::new(path.to_path_buf(), comment);
+    io::Error::new(io::ErrorKind::InvalidData, data_corruption)
+}
+
+/// Writer compressing data block by block before handing it over to
+/// the underlying writer.
+struct CompressedWriter {
+    underlying: WritePtr,
+    compressor: Compressor,
+    block_size: usize,
+    uncompressed_block: Vec<u8>,
+    compressed_block: Vec<u8>,
+    block_offsets: Vec<u64>,
+    num_bytes: u64,
+}
+
+impl CompressedWriter {
+    fn new(underlying: WritePtr, compressor: Compressor, block_size: usize) -> CompressedWriter {
+        CompressedWriter {
+            underlying,
+            compressor,
+            block_size,
+            uncompressed_block: Vec::with_capacity(block_size),
+            compressed_block: Vec::new(),
+            block_offsets: vec![0u64],
+            num_bytes: 0u64,
+        }
+    }
+
+    fn write_block(&mut self) -> io::Result<()> {
+        if self.uncompressed_block.is_empty() {
+            return Ok(());
+        }
+        self.compressor
+            .compress_into(&self.uncompressed_block, &mut self.compressed_block)?;
+        self.underlying.write_all(&self.compressed_block)?;
+        let last_offset = *self.block_offsets.last().unwrap();
+        self.block_offsets
+            .push(last_offset + self.compressed_block.len() as u64);
+        self.uncompressed_block.clear();
+        Ok(())
+    }
+}
+
+impl Write for CompressedWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let available = self.block_size - self.uncompressed_block.len();
+        let num_bytes = available.min(buf.len());
+        self.uncompressed_block.extend_from_slice(&buf[..num_bytes]);
+        self.num_bytes += num_bytes as u64;
+        if self.uncompressed_block.len() == self.block_size {
+            self.write_block()?;
+        }
+        Ok(num_bytes)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        // Partial blocks are only compressed upon termination.
+        self.underlying.flush()
+    }
+}
+
+impl TerminatingWrite for CompressedWriter {
+    fn terminate_ref(&mut self, token: AntiCallToken) -> io::Result<()> {
+        self.write_block()?;
+        for &block_offset in &self.block_offsets {
+            block_offset.serialize(&mut self.underlying)?;
+        }
+        let trailer = CompressedFileTrailer {
+            num_bytes: self.num_bytes,
+            block_size: self.block_size as u32,
+            decompressor: Decompressor::from(self.compressor),
+        };
+        trailer.serialize(&mut self.underlying)?;
+        self.underlying.terminate_ref(token)
+    }
+}
+
+/// File handle decompressing the blocks overlapping the requested ranges.
+///
+/// The last decompressed block is kept in cache, so that sequential reads
+/// within a block only pay for decompression once.
+struct CompressedFileHandle {
+    path: PathBuf,
+    blocks: FileSlice,
+    block_offsets: Vec<u64>,
+    trailer: CompressedFileTrailer,
+    last_block: Mutex<Option<(usize, OwnedBytes)>>,
+}
+
+impl fmt::Debug for CompressedFileHandle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "CompressedFileHandle({:?})", self.path)
+    }
+}
+
+impl CompressedFileHandle {
+    fn open(path: &Path, file: FileSlice) -> io::Result<CompressedFileHandle> {
+        let (body, trailer_slice) = file.split_from_end(CompressedFileTrailer::SIZE_IN_BYTES);
+        let trailer = CompressedFileTrailer::deserialize(&mut trailer_slice.read_bytes()?)?;
+        let num_blocks = trailer.num_blocks();
+        let offsets_num_bytes = (num_blocks + 1) * mem::size_of::<u64>();
+        if body.len() < offsets_num_bytes {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                format!("Compressed file {path:?} is too small to contain its block offsets."),
+            ));
+        }
+        let (blocks, offsets_slice) = body.split_from_end(offsets_num_bytes);
+        let offsets_bytes = offsets_slice.read_bytes()?;
+        let mut cursor = offsets_bytes.as_slice();
+        let block_offsets = (0..=num_blocks)
+            .map(|_| u64::deserialize(&mut cursor))
+            .collect::<io::Result<Vec<u64>>>()?;
+        let offsets_are_sorted = block_offsets
+            .windows(2)
+            .all(|offsets| offsets[0] <= offsets[1]);
+        if block_offsets.first().copied() != Some(0u64)
+            || !offsets_are_sorted
+            || block_offsets.last().copied() != Some(blocks.len() as u64)
+        {
+            return Err(data_corruption(
+                path,
+                "Compressed file has inconsistent block offsets".to_string(),
+            ));
+        }
+        Ok(CompressedFileHandle {
+            path: path.to_path_buf(),
+            blocks,
+            block_offsets,
+            trailer,
+            last_block: Mutex::new(None),
+        })
+    }
+
+    fn block_size(&self) -> usize {
+        self.trailer.block_size as usize
+    }
+
+    /// Returns the number of bytes of the block once decompressed: the block size, except
+    /// for the last block.
+    fn block_len(&self, block_id: usize) -> usize {
+        let block_start = block_id * self.block_size();
+        self.block_size().min(self.len() - block_start)
+    }
+
+    fn read_block(&self, block_id: usize) -> io::Result<OwnedBytes> {
+        if let Some((cached_block_id, block)) = self.last_block.lock().unwrap().as_ref() {
+            if *cached_block_id == block_id {
+                return Ok(block.clone());
+            }
+        }
+        // The lock is not held while decompressing, so that concurrent reads of other blocks
+        // do not wait for each other.
+        let start = self.block_offsets[block_id] as usize;
+        let end = self.block_offsets[block_id + 1] as usize;
+        let compressed = self.blocks.read_bytes_slice(start..end)?;
+        let decompressed = self
+            .trailer
+            .decompressor
+            .decompress(compressed.as_slice())?;
+        let block_len = self.block_len(block_id);
+        if decompressed.len() != block_len {
+            return Err(data_corruption(
+                &self.path,
+                format!(
+                    "Block {block_id} decompresses to {} bytes instead of {block_len}",
+                    decompressed.len()
+                ),
+            ));
+        }
+        let block = OwnedBytes::new(decompressed);
+        *self.last_block.lock().unwrap() = Some((block_id, block.clone()));
+        Ok(block)
+    }
+}
+
+impl HasLen for CompressedFileHandle {
+    fn len(&self) -> usize {
+        self.trailer.num_bytes as usize
+    }
+}
+
+impl FileHandle for CompressedFileHandle {
+    fn read_bytes(&self, range: Range<usize>) -> io::Result<OwnedBytes> {
+        if range.is_empty() {
+            return Ok(OwnedBytes::empty());
+        }
+        if range.end > self.len() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "Range {range:?} exceeds the length of compressed file {:?} ({}).",
+                    self.path,
+                    self.len()
+                ),
+            ));
+        }
+        let block_size = self.block_size();
+        let first_block = range.start / block_size;
+        let last_block = (range.end - 1) / block_size;
+        if first_block == last_block {
+            let block_start = first_block * block_size;
+            let block = self.read_block(first_block)?;
+            return Ok(block.slice(range.start - block_start..range.end - block_start));
+        }
+        let mut buffer = Vec::with_capacity(range.len());
+        for block_id in first_block..=last_block {
+            let block_start = block_id * block_size;
+            let block = self.read_block(block_id)?;
+            let start = range.start.max(block_start) - block_start;
+            let end = range.end.min(block_start + block.len()) - block_start;
+            buffer.extend_from_slice(&block.as_slice()[start..end]);
+        }
+        Ok(OwnedBytes::new(buffer))
+    }
+}
+
+/// Directory wrapper transparently compressing files on write, and decompressing
+/// them on read.
+///
+/// Files are compressed in independent blocks, so that random access only requires
+/// to decompress the blocks overlapping the requested range.
+///
+/// Only the files accepted by the compression filter are compressed. By default,
+/// these are the doc store files (`.store`), which are typically only read
+/// to display the final results of a search.
+///
+/// Compressed files are identified by a trailer on read, regardless of the filter.
+/// Changing the filter of an existing index is therefore safe.
+///
+/// Atomic writes (e.g. `meta.json`) and locks are passed through unchanged.
+#[derive(Clone)]
+pub struct CompressedDirectory {
+    underlying: Box<dyn Directory>,
+    compressor: Compressor,
+    block_size: usize,
+    filter: CompressionFilter,
+}
+
+impl fmt::Debug for CompressedDirectory {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "CompressedDirectory({:?}, {:?})",
+            self.underlying, self.compressor
+        )
+    }
+}
+
+fn is_doc_store_file(path: &Path) -> bool {
+    path.extension().map(|ext| ext == "store").unwrap_or(false)
+}
+
+impl CompressedDirectory {
+    /// Wraps a directory, compressing the doc store files with the given compressor.
+    pub fn wrap<D: Into<Box<dyn Directory>>>(
+        underlying: D,
+        compressor: Compressor,
+    ) -> CompressedDirectory {
+        CompressedDirectory {
+            underlying: underlying.into(),
+            compressor,
+            block_size: DEFAULT_COMPRESSED_BLOCK_SIZE,
+            filter: Arc::new(is_doc_store_file),
+        }
+    }
+
+    /// Sets the size of the uncompressed blocks.
+    ///
+    /// Smaller blocks make random access cheaper, at the cost of a lower compression ratio.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `block_size` is 0 or does not fit in a `u32`.
+    #[must_use]
+    pub fn set_block_size(mut self, block_size: usize) -> CompressedDirectory {
+        assert!(block_size > 0, "block size must be strictly positive");
+        assert!(
+            block_size <= u32::MAX as usize,
+            "block size must fit in a u32"
+        );
+        self.block_size = block_size;
+        self
+    }
+
+    /// Sets the filter deciding which files get compressed.
+    #[must_use]
+    pub fn set_filter(
+        mut self,
+        filter: impl Fn(&Path) -> bool + Send + Sync + 'static,
+    ) -> CompressedDirectory {
+        self.filter = Arc::new(filter);
+        self
+    }
+
+    /// Returns true if the file at `path` will be compressed upon write.
+    pub fn should_compress(&self, path: &Path) -> bool {
+        (self.filter)(path)
+    }
+}
+
+impl Directory for CompressedDirectory {
+    fn get_file_handle(&self, path: &Path) -> Result<Arc<dyn FileHandle>, OpenReadError> {
+        let file_slice = self.underlying.open_read(path)?;
+        let is_compressed = has_compressed_trailer(&file_slice)
+            .map_err(|io_error| OpenReadError::wrap_io_error(io_error, path.to_path_buf()))?;
+        if !is_compressed {
+            return Ok(Arc::new(file_slice));
+        }
+        let file_handle = CompressedFileHandle::open(path, file_slice)
+            .map_err(|io_error| OpenReadError::wrap_io_error(io_error, path.to_path_buf()))?;
+        Ok(Arc::new(file_handle))
+    }
+
+    fn delete(&self, path: &Path) -> Result<(), DeleteError> {
+        self.underlying.delete(path)
+    }
+
+    fn exists(&self, path: &Path) -> Result<bool, OpenReadError> {
+        self.underlying.exists(path)
+    }
+
+    fn open_write(&self, path: &Path) -> Result<WritePtr, OpenWriteError> {
+        let underlying_wrt = self.underlying.open_write(path)?;
+        if !self.should_compress(path) {
+            return Ok(underlying_wrt);
+        }
+        let compressed_wrt =
+            CompressedWriter::new(underlying_wrt, self.compressor, self.block_size);
+        Ok(BufWriter::new(Box::new(compressed_wrt)))
+    }
+
+    fn atomic_read(&self, path: &Path) -> Result<Vec<u8>, OpenReadError> {
+        self.underlying.atomic_read(path)
+    }
+
+    fn atomic_write(&self, path: &Path, data: &[u8]) -> io::Result<()> {
+        self.underlying.atomic_write(path, data)
+    }
+
+    fn sync_directory(&self) -> io::Result<()> {
+        self.underlying.sync_directory()
+    }
+
+    fn acquire_lock(&self, lock: &Lock) -> Result<DirectoryLock, LockError> {
+        self.underlying.acquire_lock(lock)
+    }
+
+    fn watch(&self, watch_callback: WatchCallback) -> crate::Result<WatchHandle> {
+        self.underlying.watch(watch_callback)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{self, Write};
+    use std::path::Path;
+
+    use common::{FixedSize, HasLen};
+
+    use super::{CompressedDirectory, CompressedFileTrailer, COMPRESSED_FILE_MAGIC_NUMBER};
+    use crate::directory::error::OpenReadError;
+    use crate::directory::{Directory, RamDirectory, TerminatingWrite};
+    use crate::schema::{Schema, Value, STORED, TEXT};
+    use crate::store::Compressor;
+    use crate::{Index, IndexSettings, IndexWriter, TantivyDocument};
+
+    fn test_data(num_bytes: usize) -> Vec<u8> {
+        (0..num_bytes).map(|i| (i % 7 + i / 1_000) as u8).collect()
+    }
+
+    #[test]
+    fn test_compressed_directory_random_access() -> crate::Result<()> {
+        let ram_directory = RamDirectory::create();
+        let directory = CompressedDirectory::wrap(ram_directory.clone(), Compressor::default())
+            .set_block_size(100);
+        let path = Path::new("seg.store");
+        let data = test_data(1_050);
+        let mut wrt = directory.open_write(path)?;
+        wrt.write_all(&data)?;
+        wrt.terminate()?;
+        let file = directory.open_read(path)?;
+        assert_eq!(file.len(), data.len());
+        assert_eq!(file.read_bytes()?.as_slice(), &data[..]);
+        assert_eq!(file.read_bytes_slice(0..0)?.as_slice(), b"");
+        assert_eq!(file.read_bytes_slice(10..20)?.as_slice(), &data[10..20]);
+        assert_eq!(file.read_bytes_slice(95..305)?.as_slice(), &data[95..305]);
+        assert_eq!(
+            file.read_bytes_slice(1_000..1_050)?.as_slice(),
+            &data[1_000..1_050]
+        );
+        let raw_file = ram_directory.open_read(path)?;
+        assert_ne!(raw_file.len(), data.len());
+        Ok(())
+    }
+
+    #[test]
+    fn test_compressed_directory_passthrough() -> crate::Result<()> {
+        let ram_directory = RamDirectory::create();
+        let directory = CompressedDirectory::wrap(ram_directory.clone(), Compressor::default());
+        let path = Path::new("seg.idx");
+        assert!(!directory.should_compress(path));
+        let data = test_data(300);
+        let mut wrt = directory.open_write(path)?;
+        wrt.write_all(&data)?;
+        wrt.terminate()?;
+        assert_eq!(
+            ram_directory.open_read(path)?.read_bytes()?.as_slice(),
+            &data[..]
+        );
+        assert_eq!(
+            directory.open_read(path)?.read_bytes()?.as_slice(),
+            &data[..]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_compressed_directory_raw_file_ending_with_magic_number() -> crate::Result<()> {
+        let ram_directory = RamDirectory::create();
+        let directory = CompressedDirectory::wrap(ram_directory.clone(), Compressor::default());
+        let path = Path::new("seg.idx");
+        let mut data = test_data(300);
+        data.extend_from_slice(&COMPRESSED_FILE_MAGIC_NUMBER.to_le_bytes());
+        let mut wrt = directory.open_write(path)?;
+        wrt.write_all(&data)?;
+        wrt.terminate()?;
+        assert_eq!(
+            directory.open_read(path)?.read_bytes()?.as_slice(),
+            &data[..]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_compressed_directory_unknown_decompressor() -> crate::Result<()> {
+        let ram_directory = RamDirectory::create();
+        let directory = CompressedDirectory::wrap(ram_directory.clone(), Compressor::default());
+        let path = Path::new("seg.store");
+        let mut wrt = directory.open_write(path)?;
+        wrt.write_all(&test_data(300))?;
+        wrt.terminate()?;
+        let mut bytes = ram_directory
+            .open_read(path)?
+            .read_bytes()?
+            .as_slice()
+            .to_vec();
+        let decompressor_id_offset = bytes.len() - 5;
+        bytes[decompressor_id_offset] = 3;
+        ram_directory.delete(path)?;
+        let mut wrt = ram_directory.open_write(path)?;
+        wrt.write_all(&bytes)?;
+        wrt.terminate()?;
+        assert!(directory.open_read(path).is_err());
+        Ok(())
+    }
+
+    fn rewrite_compressed_file(
+        ram_directory: &RamDirectory,
+        path: &Path,
+        corrupt: impl FnOnce(&mut [u8]),
+    ) -> crate::Result<()> {
+        let directory = CompressedDirectory::wrap(ram_directory.clone(), Compressor::default())
+            .set_block_size(100);
+        let mut wrt = directory.open_write(path)?;
+        wrt.write_all(&test_data(300))?;
+        wrt.terminate()?;
+        let mut bytes = ram_directory
+            .open_read(path)?
+            .read_bytes()?
+            .as_slice()
+            .to_vec();
+        corrupt(&mut bytes);
+        ram_directory.delete(path)?;
+        let mut wrt = ram_directory.open_write(path)?;
+        wrt.write_all(&bytes)?;
+        wrt.terminate()?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_compressed_directory_unsorted_block_offsets() -> crate::Result<()> {
+        let ram_directory = RamDirectory::create();
+        let path = Path::new("seg.store");
+        rewrite_compressed_file(&ram_directory, path, |bytes| {
+            // Swaps the offsets of the second and third blocks, out of the 4 offsets.
+            let offsets_start = bytes.len() - CompressedFileTrailer::SIZE_IN_BYTES - 4 * 8;
+            let (second_offset, third_offset) =
+                bytes[offsets_start + 8..offsets_start + 24].split_at_mut(8);
+            second_offset.swap_with_slice(third_offset);
+        })?;
+        let directory = CompressedDirectory::wrap(ram_directory, Compressor::default());
+        assert!(matches!(
+            directory.open_read(path),
+            Err(OpenReadError::IoError { .. })
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_compressed_directory_wrong_decompressed_block_len() -> crate::Result<()> {
+        let ram_directory = RamDirectory::create();
+        let path = Path::new("seg.store");
+        rewrite_compressed_file(&ram_directory, path, |bytes| {
+            // The last block now holds 99 bytes instead of 100.
+            let num_bytes_start = bytes.len() - CompressedFileTrailer::SIZE_IN_BYTES;
+            bytes[num_bytes_start..num_bytes_start + 8].copy_from_slice(&299u64.to_le_bytes());
+        })?;
+        let directory = CompressedDirectory::wrap(ram_directory, Compressor::default());
+        let file = directory.open_read(path)?;
+        assert_eq!(
+            file.read_bytes_slice(0..150)?.as_slice(),
+            &test_data(150)[..]
+        );
+        let err = file.read_bytes_slice(250..299).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        assert!(
+            err.to_string().contains("decompresses to 100 bytes"),
+            "{err}"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_compressed_directory_empty_file() -> crate::Result<()> {
+        let directory = CompressedDirectory::wrap(RamDirectory::create(), Compressor::default());
+        let path = Path::new("empty.store");
+        let wrt = directory.open_write(path)?;
+        wrt.terminate()?;
+        let file = directory.open_read(path)?;
+        assert_eq!(file.len(), 0);
+        assert_eq!(file.read_bytes()?.as_slice(), b"");
+        Ok(())
+    }
+
+    #[test]
+    fn test_compressed_directory_index() -> crate::Result<()> {
+        let mut schema_builder = Schema::builder();
+        let text = schema_builder.add_text_field("text", TEXT | STORED);
+        let schema = schema_builder.build();
+        let directory = CompressedDirectory::wrap(RamDirectory::create(), Compressor::default())
+            .set_block_size(1_000)
+            .set_filter(|_| true);
+        let index = Index::create(directory, schema, IndexSettings::default())?;
+        let mut index_writer: IndexWriter = index.writer_for_tests()?;
+        for i in 0..100 {
+            index_writer.add_document(doc!(text => format!("hello happy tax payer {i}")))?;
+        }
+        index_writer.commit()?;
+        let searcher = index.reader()?.searcher();
+        assert_eq!(searcher.num_docs(), 100);
+        let doc: TantivyDocument = searcher.doc(crate::DocAddress::new(0, 42))?;
+        assert_eq!(
+            doc.get_first(text).unwrap().as_value().as_str(),
+            Some("hello happy tax payer 42")
+        );
+        assert!(index.validate_checksum()?.is_empty());
+        Ok(())
+    }
+}
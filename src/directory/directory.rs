@@ -97,6 +97,47 @@ fn retry_policy(is_blocking: bool) -> RetryPolicy {
     }
 }
 
+/// Acquires a lock by creating its lock file, which is deleted when the lock is released.
+///
+/// This only relies on `open_write` failing if the file already exists.
+pub(crate) fn acquire_lock_with_lock_file(
+    directory: &dyn Directory,
+    lock: &Lock,
+) -> Result<DirectoryLock, LockError> {
+    let mut retry_policy = retry_policy(lock.is_blocking);
+    loop {
+        match try_acquire_lock(&lock.filepath, directory) {
+            Ok(result) => {
+                return Ok(result);
+            }
+            Err(TryAcquireLockError::FileExists) => {
+                if !retry_policy.wait_and_retry() {
+                    return Err(LockError::LockBusy);
+                }
+            }
+            Err(TryAcquireLockError::IoError(io_error)) => {
+                return Err(LockError::IoError(io_error));
+            }
+        }
+    }
+}
+
+/// Hint about how a file is about to be accessed, see [`Directory::advise`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub enum AccessHint {
+    /// No particular access pattern: the directory goes back to its default behavior
+    /// for the file.
+    Normal,
+    /// The file is read at random offsets, read-ahead is wasteful.
+    Random,
+    /// The file is read sequentially, from its start to its end.
+    Sequential,
+    /// The file is about to be read, and should be loaded ahead of time.
+    WillNeed,
+    /// The file will not be read in the near future, its pages can be evicted.
+    DontNeed,
+}
+
 /// Write-once read many (WORM) abstraction for where
 /// tantivy's data should be stored.
 ///
@@ -192,22 +233,7 @@ pub trait Directory: DirectoryClone + fmt::Debug + Send + Sync + 'static {
     /// The method is blocking or not depending on the [`Lock`] object.
     fn acquire_lock(&self, lock: &Lock) -> Result<DirectoryLock, LockError> {
         let box_directory = self.box_clone();
-        let mut retry_policy = retry_policy(lock.is_blocking);
-        loop {
-            match try_acquire_lock(&lock.filepath, &*box_directory) {
-                Ok(result) => {
-                    return Ok(result);
-                }
-                Err(TryAcquireLockError::FileExists) => {
-                    if !retry_policy.wait_and_retry() {
-                        return Err(LockError::LockBusy);
-                    }
-                }
-                Err(TryAcquireLockError::IoError(io_error)) => {
-                    return Err(LockError::IoError(io_error));
-                }
-            }
-        }
+        acquire_lock_with_lock_file(&*box_directory, lock)
     }
 
     /// Registers a callback that will be called whenever a change on the `meta.json`
@@ -225,6 +251,23 @@ pub trait Directory: DirectoryClone + fmt::Debug + Send + Sync + 'static {
     /// `OnCommitWithDelay` `ReloadPolicy`. Not implementing watch in a `Directory` only prevents
     /// the `OnCommitWithDelay` `ReloadPolicy` to work properly.
     fn watch(&self, watch_callback: WatchCallback) -> crate::Result<WatchHandle>;
+
+    /// Gives a hint about how the file at `path` is going to be accessed.
+    ///
+    /// Hints are purely an optimization, and may be ignored. The default implementation
+    /// does nothing.
+    fn advise(&self, _path: &Path, _hint: AccessHint) -> io::Result<()> {
+        Ok(())
+    }
+
+    /// Returns the path of the file on the local filesystem, if the directory
+    /// stores its files as is on the local filesystem.
+    ///
+    /// This is used to hard link files rather than copying them, when creating
+    /// snapshots for instance. The default implementation returns `None`.
+    fn local_path(&self, _path: &Path) -> Option<PathBuf> {
+        None
+    }
 }
 
 /// DirectoryClone
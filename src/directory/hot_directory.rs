@@ -0,0 +1,433 @@
+use std::collections::HashMap;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, RwLock};
+use std::{fmt, io};
+
+use common::HasLen;
+use once_cell::sync::OnceCell;
+use serde::{Deserialize, Serialize};
+
+use crate::directory::error::{DeleteError, LockError, OpenReadError, OpenWriteError};
+use crate::directory::{
+    AccessHint, Directory, DirectoryLock, FileHandle, Lock, OwnedBytes, WatchCallback, WatchHandle,
+    WritePtr,
+};
+
+/// Byte ranges of a file that are part of a hotcache.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct HotcacheFile {
+    /// Path of the file, relative to the index directory.
+    pub path: PathBuf,
+    /// Size of the file in bytes, footer included.
+    pub num_bytes: usize,
+    /// Sorted, non-overlapping byte ranges of the file.
+    pub ranges: Vec<Range<usize>>,
+}
+
+/// Describes the byte ranges that are read when opening a searcher on an index.
+///
+/// The manifest is generated with [`Index::hotcache_manifest()`](crate::Index::hotcache_manifest),
+/// and can be serialized and stored alongside the index. A [`HotDirectory`] then preloads
+/// exactly those ranges, so that opening the index from a slow storage does not issue
+/// thousands of tiny reads.
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct HotcacheManifest {
+    files: Vec<HotcacheFile>,
+}
+
+impl HotcacheManifest {
+    /// Files of the hotcache, sorted by path.
+    pub fn files(&self) -> &[HotcacheFile] {
+        &self.files
+    }
+
+    /// Total number of bytes preloaded by the hotcache.
+    pub fn num_bytes(&self) -> usize {
+        self.files
+            .iter()
+            .flat_map(|file| file.ranges.iter())
+            .map(|range| range.len())
+            .sum()
+    }
+}
+
+/// Sorts the ranges and merges the ones that overlap or are contiguous.
+fn merge_ranges(mut ranges: Vec<Range<usize>>) -> Vec<Range<usize>> {
+    ranges.sort_by_key(|range| range.start);
+    let mut merged: Vec<Range<usize>> = Vec::with_capacity(ranges.len());
+    for range in ranges {
+        match merged.last_mut() {
+            Some(last) if range.start <= last.end => {
+                last.end = last.end.max(range.end);
+            }
+            _ => merged.push(range),
+        }
+    }
+    merged
+}
+
+#[derive(Default)]
+struct RecordedFile {
+    num_bytes: usize,
+    ranges: Vec<Range<usize>>,
+}
+
+type RecordedReads = Arc<Mutex<HashMap<PathBuf, RecordedFile>>>;
+
+struct RecordingFileHandle {
+    path: PathBuf,
+    underlying: Arc<dyn FileHandle>,
+    recorded_reads: RecordedReads,
+}
+
+impl fmt::Debug for RecordingFileHandle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "RecordingFileHandle({:?})", self.path)
+    }
+}
+
+impl HasLen for RecordingFileHandle {
+    fn len(&self) -> usize {
+        self.underlying.len()
+    }
+}
+
+impl FileHandle for RecordingFileHandle {
+    fn read_bytes(&self, range: Range<usize>) -> io::Result<OwnedBytes> {
+        if !range.is_empty() {
+            let mut recorded_reads = self.recorded_reads.lock().unwrap();
+            let recorded_file = recorded_reads.entry(self.path.clone()).or_default();
+            recorded_file.num_bytes = self.underlying.len();
+            recorded_file.ranges.push(range.clone());
+        }
+        self.underlying.read_bytes(range)
+    }
+}
+
+/// Directory wrapper recording the byte ranges read through its file handles.
+#[derive(Clone, Debug)]
+pub(crate) struct RecordingDirectory {
+    underlying: Box<dyn Directory>,
+    recorded_reads: RecordedReads,
+}
+
+impl RecordingDirectory {
+    pub fn wrap(underlying: Box<dyn Directory>) -> RecordingDirectory {
+        RecordingDirectory {
+            underlying,
+            recorded_reads: RecordedReads::default(),
+        }
+    }
+
+    /// Returns the ranges read so far, as a hotcache manifest.
+    pub fn manifest(&self) -> HotcacheManifest {
+        let recorded_reads = self.recorded_reads.lock().unwrap();
+        let mut files: Vec<HotcacheFile> = recorded_reads
+            .iter()
+            .map(|(path, recorded_file)| HotcacheFile {
+                path: path.clone(),
+                num_bytes: recorded_file.num_bytes,
+                ranges: merge_ranges(recorded_file.ranges.clone()),
+            })
+            .collect();
+        files.sort_by(|left, right| left.path.cmp(&right.path));
+        HotcacheManifest { files }
+    }
+
+    #[cfg(test)]
+    fn clear(&self) {
+        self.recorded_reads.lock().unwrap().clear();
+    }
+}
+
+impl Directory for RecordingDirectory {
+    fn get_file_handle(&self, path: &Path) -> Result<Arc<dyn FileHandle>, OpenReadError> {
+        let underlying = self.underlying.get_file_handle(path)?;
+        Ok(Arc::new(RecordingFileHandle {
+            path: path.to_path_buf(),
+            underlying,
+            recorded_reads: self.recorded_reads.clone(),
+        }))
+    }
+
+    fn delete(&self, path: &Path) -> Result<(), DeleteError> {
+        self.underlying.delete(path)
+    }
+
+    fn exists(&self, path: &Path) -> Result<bool, OpenReadError> {
+        self.underlying.exists(path)
+    }
+
+    fn open_write(&self, path: &Path) -> Result<WritePtr, OpenWriteError> {
+        self.underlying.open_write(path)
+    }
+
+    fn atomic_read(&self, path: &Path) -> Result<Vec<u8>, OpenReadError> {
+        self.underlying.atomic_read(path)
+    }
+
+    fn atomic_write(&self, path: &Path, data: &[u8]) -> io::Result<()> {
+        self.underlying.atomic_write(path, data)
+    }
+
+    fn sync_directory(&self) -> io::Result<()> {
+        self.underlying.sync_directory()
+    }
+
+    fn acquire_lock(&self, lock: &Lock) -> Result<DirectoryLock, LockError> {
+        self.underlying.acquire_lock(lock)
+    }
+
+    fn watch(&self, watch_callback: WatchCallback) -> crate::Result<WatchHandle> {
+        self.underlying.watch(watch_callback)
+    }
+
+    fn advise(&self, path: &Path, hint: AccessHint) -> io::Result<()> {
+        self.underlying.advise(path, hint)
+    }
+
+    fn local_path(&self, path: &Path) -> Option<PathBuf> {
+        self.underlying.local_path(path)
+    }
+}
+
+/// Preloaded ranges of a file, sorted by start offset.
+struct HotFile {
+    num_bytes: usize,
+    ranges: Vec<(usize, OwnedBytes)>,
+}
+
+impl HotFile {
+    fn get(&self, range: &Range<usize>) -> Option<OwnedBytes> {
+        let pos = self
+            .ranges
+            .partition_point(|(start, _)| *start <= range.start);
+        let (start, bytes) = self.ranges[..pos].last()?;
+        if range.end > start + bytes.len() {
+            return None;
+        }
+        Some(bytes.slice(range.start - start..range.end - start))
+    }
+}
+
+/// File handle serving the preloaded ranges from memory, and opening the underlying
+/// file on the first read falling outside of them.
+struct HotFileHandle {
+    path: PathBuf,
+    hot_file: Arc<HotFile>,
+    directory: Box<dyn Directory>,
+    underlying: OnceCell<Arc<dyn FileHandle>>,
+}
+
+impl fmt::Debug for HotFileHandle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "HotFileHandle({:?})", self.path)
+    }
+}
+
+impl HasLen for HotFileHandle {
+    fn len(&self) -> usize {
+        self.hot_file.num_bytes
+    }
+}
+
+impl FileHandle for HotFileHandle {
+    fn read_bytes(&self, range: Range<usize>) -> io::Result<OwnedBytes> {
+        if let Some(bytes) = self.hot_file.get(&range) {
+            return Ok(bytes);
+        }
+        let underlying = self.underlying.get_or_try_init(|| {
+            self.directory
+                .get_file_handle(&self.path)
+                .map_err(|open_read_error| io::Error::new(io::ErrorKind::Other, open_read_error))
+        })?;
+        underlying.read_bytes(range)
+    }
+}
+
+/// Directory wrapper preloading the ranges described by a [`HotcacheManifest`].
+///
+/// Reads falling within the preloaded ranges are served from memory, and the files of
+/// the hotcache are only opened in the underlying directory when a read falls outside
+/// of them. Opening a searcher on top of a `HotDirectory` built with a manifest
+/// generated for the same commit therefore does not touch the underlying directory,
+/// beyond reading `meta.json`.
+#[derive(Clone)]
+pub struct HotDirectory {
+    underlying: Box<dyn Directory>,
+    hot_files: Arc<RwLock<HashMap<PathBuf, Arc<HotFile>>>>,
+}
+
+impl fmt::Debug for HotDirectory {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "HotDirectory({:?})", self.underlying)
+    }
+}
+
+impl HotDirectory {
+    /// Wraps a directory, preloading the ranges of `manifest`.
+    ///
+    /// Files of the manifest that do not exist anymore, typically because their segment
+    /// was merged, are skipped.
+    pub fn open<D: Into<Box<dyn Directory>>>(
+        underlying: D,
+        manifest: &HotcacheManifest,
+    ) -> crate::Result<HotDirectory> {
+        let underlying = underlying.into();
+        let mut hot_files = HashMap::with_capacity(manifest.files.len());
+        for file in &manifest.files {
+            let file_handle = match underlying.get_file_handle(&file.path) {
+                Ok(file_handle) => file_handle,
+                Err(OpenReadError::FileDoesNotExist(_)) => {
+                    warn!(
+                        "Skipping {:?}, which is not in the directory anymore.",
+                        file.path
+                    );
+                    continue;
+                }
+                Err(open_read_error) => return Err(open_read_error.into()),
+            };
+            let ranges = file
+                .ranges
+                .iter()
+                .map(|range| Ok((range.start, file_handle.read_bytes(range.clone())?)))
+                .collect::<io::Result<Vec<_>>>()?;
+            let hot_file = HotFile {
+                num_bytes: file.num_bytes,
+                ranges,
+            };
+            hot_files.insert(file.path.clone(), Arc::new(hot_file));
+        }
+        Ok(HotDirectory {
+            underlying,
+            hot_files: Arc::new(RwLock::new(hot_files)),
+        })
+    }
+
+    /// Number of preloaded bytes.
+    pub fn num_bytes(&self) -> usize {
+        self.hot_files
+            .read()
+            .unwrap()
+            .values()
+            .flat_map(|hot_file| hot_file.ranges.iter())
+            .map(|(_, bytes)| bytes.len())
+            .sum()
+    }
+}
+
+impl Directory for HotDirectory {
+    fn get_file_handle(&self, path: &Path) -> Result<Arc<dyn FileHandle>, OpenReadError> {
+        let hot_file = self.hot_files.read().unwrap().get(path).cloned();
+        let Some(hot_file) = hot_file else {
+            return self.underlying.get_file_handle(path);
+        };
+        Ok(Arc::new(HotFileHandle {
+            path: path.to_path_buf(),
+            hot_file,
+            directory: self.underlying.box_clone(),
+            underlying: OnceCell::new(),
+        }))
+    }
+
+    fn delete(&self, path: &Path) -> Result<(), DeleteError> {
+        self.hot_files.write().unwrap().remove(path);
+        self.underlying.delete(path)
+    }
+
+    fn exists(&self, path: &Path) -> Result<bool, OpenReadError> {
+        self.underlying.exists(path)
+    }
+
+    fn open_write(&self, path: &Path) -> Result<WritePtr, OpenWriteError> {
+        self.underlying.open_write(path)
+    }
+
+    fn atomic_read(&self, path: &Path) -> Result<Vec<u8>, OpenReadError> {
+        self.underlying.atomic_read(path)
+    }
+
+    fn atomic_write(&self, path: &Path, data: &[u8]) -> io::Result<()> {
+        self.underlying.atomic_write(path, data)
+    }
+
+    fn sync_directory(&self) -> io::Result<()> {
+        self.underlying.sync_directory()
+    }
+
+    fn acquire_lock(&self, lock: &Lock) -> Result<DirectoryLock, LockError> {
+        self.underlying.acquire_lock(lock)
+    }
+
+    fn watch(&self, watch_callback: WatchCallback) -> crate::Result<WatchHandle> {
+        self.underlying.watch(watch_callback)
+    }
+
+    fn advise(&self, path: &Path, hint: AccessHint) -> io::Result<()> {
+        self.underlying.advise(path, hint)
+    }
+
+    fn local_path(&self, path: &Path) -> Option<PathBuf> {
+        self.underlying.local_path(path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{merge_ranges, HotDirectory, HotcacheManifest, RecordingDirectory};
+    use crate::directory::RamDirectory;
+    use crate::schema::{Schema, STORED, TEXT};
+    use crate::{DocAddress, Index, IndexWriter, TantivyDocument};
+
+    #[test]
+    fn test_merge_ranges() {
+        assert_eq!(
+            merge_ranges(vec![10..20, 0..5, 5..8, 15..30, 40..41]),
+            vec![0..8, 10..30, 40..41]
+        );
+        assert!(merge_ranges(Vec::new()).is_empty());
+    }
+
+    #[test]
+    fn test_hot_directory() -> crate::Result<()> {
+        let mut schema_builder = Schema::builder();
+        let text = schema_builder.add_text_field("text", TEXT | STORED);
+        let directory = RamDirectory::create();
+        let index = Index::create(
+            directory.clone(),
+            schema_builder.build(),
+            Default::default(),
+        )?;
+        let mut writer: IndexWriter = index.writer_for_tests()?;
+        for i in 0..100 {
+            writer.add_document(doc!(text => format!("hello {i}")))?;
+        }
+        writer.commit()?;
+        let manifest = index.hotcache_manifest()?;
+        assert!(!manifest.files().is_empty());
+        assert!(manifest.num_bytes() > 0);
+        let manifest_json = serde_json::to_string(&manifest)?;
+        let manifest: HotcacheManifest = serde_json::from_str(&manifest_json)?;
+
+        let recording_directory = RecordingDirectory::wrap(Box::new(directory));
+        let hot_directory = HotDirectory::open(recording_directory.clone(), &manifest)?;
+        assert_eq!(hot_directory.num_bytes(), manifest.num_bytes());
+        recording_directory.clear();
+
+        let hot_index = Index::open(hot_directory)?;
+        let searcher = hot_index.reader()?.searcher();
+        for segment_reader in searcher.segment_readers() {
+            segment_reader.inverted_index(text)?;
+        }
+        assert!(recording_directory.manifest().files().is_empty());
+
+        // Reads outside of the hotcache fall back to the underlying directory.
+        let doc: TantivyDocument = searcher.doc(DocAddress::new(0, 42))?;
+        assert_eq!(
+            doc.get_first(text).unwrap().as_value().as_str(),
+            Some("hello 42")
+        );
+        Ok(())
+    }
+}
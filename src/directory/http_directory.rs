@@ -0,0 +1,452 @@
+use std::collections::HashMap;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+use std::{fmt, io};
+
+use common::HasLen;
+
+use crate::directory::error::{DeleteError, OpenReadError, OpenWriteError};
+use crate::directory::{Directory, FileHandle, OwnedBytes, WatchCallback, WatchHandle, WritePtr};
+
+/// Client issuing the HTTP requests of an [`HttpDirectory`].
+///
+/// With the `http-directory` feature, [`HttpRangeClient`] implements it with blocking requests.
+/// Implementing this trait makes it possible to plug another client, or to add headers.
+pub trait RangeClient: fmt::Debug + Send + Sync + 'static {
+    /// Returns the length of the resource at `url`, or `None` if it does not exist.
+    fn content_length(&self, url: &str) -> io::Result<Option<usize>>;
+
+    /// Fetches the `range` of bytes of the resource at `url`.
+    fn get_range(&self, url: &str, range: Range<usize>) -> io::Result<Vec<u8>>;
+
+    /// Fetches the resource at `url`, or returns `None` if it does not exist.
+    fn get(&self, url: &str) -> io::Result<Option<Vec<u8>>>;
+}
+
+fn read_only_error() -> io::Error {
+    io::Error::new(
+        io::ErrorKind::PermissionDenied,
+        "HttpDirectory is read-only",
+    )
+}
+
+struct HttpFileHandle {
+    url: String,
+    num_bytes: usize,
+    client: Arc<dyn RangeClient>,
+}
+
+impl fmt::Debug for HttpFileHandle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "HttpFileHandle({})", self.url)
+    }
+}
+
+impl HasLen for HttpFileHandle {
+    fn len(&self) -> usize {
+        self.num_bytes
+    }
+}
+
+impl FileHandle for HttpFileHandle {
+    fn read_bytes(&self, range: Range<usize>) -> io::Result<OwnedBytes> {
+        if range.is_empty() {
+            return Ok(OwnedBytes::empty());
+        }
+        let num_bytes = range.len();
+        let bytes = self.client.get_range(&self.url, range)?;
+        if bytes.len() != num_bytes {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                format!(
+                    "expected {num_bytes} bytes from {}, got {}",
+                    self.url,
+                    bytes.len()
+                ),
+            ));
+        }
+        Ok(OwnedBytes::new(bytes))
+    }
+}
+
+/// Read-only directory fetching the files of an index with HTTP range requests.
+///
+/// The index files are expected to be served as is under a base URL, e.g. by a static file
+/// server, which makes it possible to search a pre-built index from a browser. Each read on a
+/// file handle issues one request: wrapping the directory in a
+/// [`HotDirectory`](crate::directory::HotDirectory) and a
+/// [`CachingDirectory`](crate::directory::CachingDirectory) keeps their number reasonable.
+///
+/// Since the directory cannot be watched, readers have to be reloaded manually to see new
+/// commits. Writing to the directory returns an error.
+#[derive(Clone)]
+pub struct HttpDirectory {
+    base_url: Arc<str>,
+    client: Arc<dyn RangeClient>,
+    /// Lengths of the files, `None` for the files that do not exist.
+    file_lens: Arc<RwLock<HashMap<PathBuf, Option<usize>>>>,
+}
+
+impl fmt::Debug for HttpDirectory {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "HttpDirectory({})", self.base_url)
+    }
+}
+
+impl HttpDirectory {
+    /// Creates a directory for the index served under `base_url`, using `client` to issue the
+    /// requests.
+    pub fn with_client<C: RangeClient>(base_url: &str, client: C) -> HttpDirectory {
+        HttpDirectory {
+            base_url: Arc::from(base_url.trim_end_matches('/')),
+            client: Arc::new(client),
+            file_lens: Arc::default(),
+        }
+    }
+
+    /// Creates a directory for the index served under `base_url`.
+    #[cfg(feature = "http-directory")]
+    pub fn new(base_url: &str) -> HttpDirectory {
+        HttpDirectory::with_client(base_url, HttpRangeClient::default())
+    }
+
+    fn url(&self, path: &Path) -> String {
+        format!("{}/{}", self.base_url, path.to_string_lossy())
+    }
+
+    fn file_len(&self, path: &Path) -> Result<Option<usize>, OpenReadError> {
+        if let Some(file_len) = self.file_lens.read().unwrap().get(path) {
+            return Ok(*file_len);
+        }
+        let file_len = self
+            .client
+            .content_length(&self.url(path))
+            .map_err(|io_error| OpenReadError::wrap_io_error(io_error, path.to_path_buf()))?;
+        self.file_lens
+            .write()
+            .unwrap()
+            .insert(path.to_path_buf(), file_len);
+        Ok(file_len)
+    }
+}
+
+impl Directory for HttpDirectory {
+    fn get_file_handle(&self, path: &Path) -> Result<Arc<dyn FileHandle>, OpenReadError> {
+        let num_bytes = self
+            .file_len(path)?
+            .ok_or_else(|| OpenReadError::FileDoesNotExist(path.to_path_buf()))?;
+        Ok(Arc::new(HttpFileHandle {
+            url: self.url(path),
+            num_bytes,
+            client: self.client.clone(),
+        }))
+    }
+
+    fn delete(&self, path: &Path) -> Result<(), DeleteError> {
+        Err(DeleteError::IoError {
+            io_error: Arc::new(read_only_error()),
+            filepath: path.to_path_buf(),
+        })
+    }
+
+    fn exists(&self, path: &Path) -> Result<bool, OpenReadError> {
+        Ok(self.file_len(path)?.is_some())
+    }
+
+    fn open_write(&self, path: &Path) -> Result<WritePtr, OpenWriteError> {
+        Err(OpenWriteError::wrap_io_error(
+            read_only_error(),
+            path.to_path_buf(),
+        ))
+    }
+
+    fn atomic_read(&self, path: &Path) -> Result<Vec<u8>, OpenReadError> {
+        // Files written with `atomic_write`, like `meta.json`, are not cached, so that
+        // reloading a reader picks up new commits.
+        self.client
+            .get(&self.url(path))
+            .map_err(|io_error| OpenReadError::wrap_io_error(io_error, path.to_path_buf()))?
+            .ok_or_else(|| OpenReadError::FileDoesNotExist(path.to_path_buf()))
+    }
+
+    fn atomic_write(&self, _path: &Path, _data: &[u8]) -> io::Result<()> {
+        Err(read_only_error())
+    }
+
+    fn sync_directory(&self) -> io::Result<()> {
+        Ok(())
+    }
+
+    fn watch(&self, _watch_callback: WatchCallback) -> crate::Result<WatchHandle> {
+        Ok(WatchHandle::empty())
+    }
+}
+
+/// Default [`RangeClient`], issuing blocking requests.
+///
+/// When compiled to wasm32, requests are synchronous `XMLHttpRequest`s, which browsers only
+/// allow for binary responses within a web worker.
+#[cfg(feature = "http-directory")]
+#[derive(Clone, Debug)]
+pub struct HttpRangeClient {
+    #[cfg(not(target_arch = "wasm32"))]
+    agent: ureq::Agent,
+}
+
+#[cfg(feature = "http-directory")]
+impl Default for HttpRangeClient {
+    fn default() -> HttpRangeClient {
+        HttpRangeClient {
+            #[cfg(not(target_arch = "wasm32"))]
+            agent: ureq::Agent::new(),
+        }
+    }
+}
+
+#[cfg(all(feature = "http-directory", not(target_arch = "wasm32")))]
+impl HttpRangeClient {
+    fn call(&self, request: ureq::Request) -> io::Result<Option<ureq::Response>> {
+        match request.call() {
+            Ok(response) => Ok(Some(response)),
+            Err(ureq::Error::Status(404, _)) => Ok(None),
+            Err(error) => Err(io::Error::new(io::ErrorKind::Other, error)),
+        }
+    }
+
+    fn read_body(response: ureq::Response, num_bytes: usize) -> io::Result<Vec<u8>> {
+        let mut bytes = Vec::with_capacity(num_bytes);
+        io::Read::read_to_end(&mut response.into_reader(), &mut bytes)?;
+        Ok(bytes)
+    }
+}
+
+#[cfg(all(feature = "http-directory", not(target_arch = "wasm32")))]
+impl RangeClient for HttpRangeClient {
+    fn content_length(&self, url: &str) -> io::Result<Option<usize>> {
+        let Some(response) = self.call(self.agent.head(url))? else {
+            return Ok(None);
+        };
+        content_length_from_header(url, response.header("Content-Length")).map(Some)
+    }
+
+    fn get_range(&self, url: &str, range: Range<usize>) -> io::Result<Vec<u8>> {
+        let num_bytes = range.len();
+        let request = self.agent.get(url).set("Range", &range_header(range));
+        let response = self
+            .call(request)?
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, url.to_string()))?;
+        check_partial_content(url, response.status())?;
+        HttpRangeClient::read_body(response, num_bytes)
+    }
+
+    fn get(&self, url: &str) -> io::Result<Option<Vec<u8>>> {
+        self.call(self.agent.get(url))?
+            .map(|response| HttpRangeClient::read_body(response, 0))
+            .transpose()
+    }
+}
+
+/// Converts a JavaScript exception into an `io::Error`.
+#[cfg(all(feature = "http-directory", target_arch = "wasm32"))]
+fn js_error<E: fmt::Debug>(error: E) -> io::Error {
+    io::Error::new(io::ErrorKind::Other, format!("{error:?}"))
+}
+
+#[cfg(all(feature = "http-directory", target_arch = "wasm32"))]
+impl HttpRangeClient {
+    fn send(
+        method: &str,
+        url: &str,
+        range: Option<Range<usize>>,
+    ) -> io::Result<web_sys::XmlHttpRequest> {
+        let request = web_sys::XmlHttpRequest::new().map_err(js_error)?;
+        request
+            .open_with_async(method, url, false)
+            .map_err(js_error)?;
+        request.set_response_type(web_sys::XmlHttpRequestResponseType::Arraybuffer);
+        if let Some(range) = range {
+            request
+                .set_request_header("Range", &range_header(range))
+                .map_err(js_error)?;
+        }
+        request.send().map_err(js_error)?;
+        Ok(request)
+    }
+
+    fn status(request: &web_sys::XmlHttpRequest) -> io::Result<u16> {
+        request.status().map_err(js_error)
+    }
+
+    fn body(request: &web_sys::XmlHttpRequest) -> io::Result<Vec<u8>> {
+        let response = request.response().map_err(js_error)?;
+        Ok(js_sys::Uint8Array::new(&response).to_vec())
+    }
+}
+
+#[cfg(all(feature = "http-directory", target_arch = "wasm32"))]
+impl RangeClient for HttpRangeClient {
+    fn content_length(&self, url: &str) -> io::Result<Option<usize>> {
+        let request = HttpRangeClient::send("HEAD", url, None)?;
+        if HttpRangeClient::status(&request)? == 404 {
+            return Ok(None);
+        }
+        let content_length = request
+            .get_response_header("Content-Length")
+            .map_err(js_error)?;
+        content_length_from_header(url, content_length.as_deref()).map(Some)
+    }
+
+    fn get_range(&self, url: &str, range: Range<usize>) -> io::Result<Vec<u8>> {
+        let request = HttpRangeClient::send("GET", url, Some(range))?;
+        check_partial_content(url, HttpRangeClient::status(&request)?)?;
+        HttpRangeClient::body(&request)
+    }
+
+    fn get(&self, url: &str) -> io::Result<Option<Vec<u8>>> {
+        let request = HttpRangeClient::send("GET", url, None)?;
+        match HttpRangeClient::status(&request)? {
+            404 => Ok(None),
+            200..=299 => HttpRangeClient::body(&request).map(Some),
+            status => Err(io::Error::new(
+                io::ErrorKind::Other,
+                format!("unexpected status {status} for {url}"),
+            )),
+        }
+    }
+}
+
+#[cfg(feature = "http-directory")]
+fn range_header(range: Range<usize>) -> String {
+    // HTTP ranges are inclusive.
+    format!("bytes={}-{}", range.start, range.end - 1)
+}
+
+#[cfg(feature = "http-directory")]
+fn content_length_from_header(url: &str, header: Option<&str>) -> io::Result<usize> {
+    header
+        .and_then(|content_length| content_length.parse().ok())
+        .ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("missing or invalid Content-Length for {url}"),
+            )
+        })
+}
+
+#[cfg(feature = "http-directory")]
+fn check_partial_content(url: &str, status: u16) -> io::Result<()> {
+    if status != 206 {
+        return Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            format!("expected a partial content response for {url}, got status {status}"),
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io;
+    use std::ops::Range;
+    use std::path::Path;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    use super::{HttpDirectory, RangeClient};
+    use crate::collector::TopDocs;
+    use crate::directory::error::OpenReadError;
+    use crate::directory::RamDirectory;
+    use crate::query::TermQuery;
+    use crate::schema::{IndexRecordOption, Schema, Value, STORED, STRING};
+    use crate::{Directory, Index, IndexWriter, ReloadPolicy, TantivyDocument, Term};
+
+    const BASE_URL: &str = "https://example.com/index";
+
+    /// Serves the files of a `RamDirectory`, counting the range requests.
+    #[derive(Debug)]
+    struct RamRangeClient {
+        directory: RamDirectory,
+        num_range_requests: Arc<AtomicUsize>,
+    }
+
+    impl RamRangeClient {
+        fn path<'a>(&self, url: &'a str) -> &'a Path {
+            Path::new(url.strip_prefix(BASE_URL).unwrap().trim_start_matches('/'))
+        }
+    }
+
+    impl RangeClient for RamRangeClient {
+        fn content_length(&self, url: &str) -> io::Result<Option<usize>> {
+            match self.directory.open_read(self.path(url)) {
+                Ok(file_slice) => Ok(Some(file_slice.len())),
+                Err(OpenReadError::FileDoesNotExist(_)) => Ok(None),
+                Err(error) => Err(io::Error::new(io::ErrorKind::Other, error)),
+            }
+        }
+
+        fn get_range(&self, url: &str, range: Range<usize>) -> io::Result<Vec<u8>> {
+            self.num_range_requests.fetch_add(1, Ordering::SeqCst);
+            let file_slice = self
+                .directory
+                .open_read(self.path(url))
+                .map_err(|error| io::Error::new(io::ErrorKind::Other, error))?;
+            Ok(file_slice.read_bytes_slice(range)?.as_slice().to_vec())
+        }
+
+        fn get(&self, url: &str) -> io::Result<Option<Vec<u8>>> {
+            match self.directory.atomic_read(self.path(url)) {
+                Ok(data) => Ok(Some(data)),
+                Err(OpenReadError::FileDoesNotExist(_)) => Ok(None),
+                Err(error) => Err(io::Error::new(io::ErrorKind::Other, error)),
+            }
+        }
+    }
+
+    #[test]
+    fn test_http_directory() -> crate::Result<()> {
+        let mut schema_builder = Schema::builder();
+        let title = schema_builder.add_text_field("title", STRING | STORED);
+        let directory = RamDirectory::create();
+        let index = Index::create(
+            directory.clone(),
+            schema_builder.build(),
+            Default::default(),
+        )?;
+        let mut writer: IndexWriter = index.writer_for_tests()?;
+        for i in 0..10 {
+            writer.add_document(doc!(title => format!("title {i}")))?;
+        }
+        writer.commit()?;
+
+        let num_range_requests = Arc::new(AtomicUsize::new(0));
+        let client = RamRangeClient {
+            directory,
+            num_range_requests: num_range_requests.clone(),
+        };
+        let http_directory = HttpDirectory::with_client(&format!("{BASE_URL}/"), client);
+        let http_index = Index::open(http_directory.clone())?;
+        let searcher = http_index
+            .reader_builder()
+            .reload_policy(ReloadPolicy::Manual)
+            .try_into()?
+            .searcher();
+        assert!(num_range_requests.load(Ordering::SeqCst) > 0);
+
+        let query = TermQuery::new(
+            Term::from_field_text(title, "title 7"),
+            IndexRecordOption::Basic,
+        );
+        let top_docs = searcher.search(&query, &TopDocs::with_limit(10))?;
+        assert_eq!(top_docs.len(), 1);
+        let doc: TantivyDocument = searcher.doc(top_docs[0].1)?;
+        assert_eq!(doc.get_first(title).unwrap().as_str(), Some("title 7"));
+
+        assert!(!http_directory.exists(Path::new("missing.idx"))?);
+        assert!(http_directory
+            .atomic_write(Path::new("meta.json"), b"{}")
+            .is_err());
+        assert!(http_index.writer::<TantivyDocument>(15_000_000).is_err());
+        Ok(())
+    }
+}
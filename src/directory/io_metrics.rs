@@ -0,0 +1,429 @@
+use std::io::{self, Write};
+use std::ops::{AddAssign, Range};
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use std::{fmt, slice};
+
+use common::HasLen;
+
+use crate::directory::{AntiCallToken, FileHandle, FileSlice, OwnedBytes, TerminatingWrite};
+use crate::Instant;
+
+/// Kind of file, used to attribute I/O to the different parts of an index.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub enum FileKind {
+    /// Postings lists (`.idx`).
+    Postings,
+    /// Positions (`.pos`).
+    Positions,
+    /// Term dictionaries (`.term`).
+    Terms,
+    /// Doc stores (`.store`).
+    Store,
+    /// Fast fields (`.fast`).
+    FastFields,
+    /// Field norms (`.fieldnorm`).
+    FieldNorms,
+    /// Alive bitsets (`.del`).
+    Delete,
+    /// Blobs (`.blob`).
+    Blobs,
+    /// Term vectors (`.tv`).
+    TermVectors,
+    /// Vector indexes (`.vec`).
+    VectorIndex,
+    /// Index metadata, such as `meta.json`.
+    Meta,
+    /// Any other file, including temporary doc stores.
+    Other,
+}
+
+const NUM_FILE_KINDS: usize = 12;
+
+static FILE_KINDS: [FileKind; NUM_FILE_KINDS] = [
+    FileKind::Postings,
+    FileKind::Positions,
+    FileKind::Terms,
+    FileKind::Store,
+    FileKind::FastFields,
+    FileKind::FieldNorms,
+    FileKind::Delete,
+    FileKind::Blobs,
+    FileKind::TermVectors,
+    FileKind::VectorIndex,
+    FileKind::Meta,
+    FileKind::Other,
+];
+
+impl FileKind {
+    /// Iterates through the file kinds.
+    pub fn iterator() -> slice::Iter<'static, FileKind> {
+        FILE_KINDS.iter()
+    }
+
+    /// Returns the kind of a file, given its path in the index directory.
+    pub fn for_path(path: &Path) -> FileKind {
+        match path.extension().and_then(|extension| extension.to_str()) {
+            Some("idx") => FileKind::Postings,
+            Some("pos") => FileKind::Positions,
+            Some("term") => FileKind::Terms,
+            Some("store") => FileKind::Store,
+            Some("fast") => FileKind::FastFields,
+            Some("fieldnorm") => FileKind::FieldNorms,
+            Some("del") => FileKind::Delete,
+            Some("blob") => FileKind::Blobs,
+            Some("tv") => FileKind::TermVectors,
+            Some("vec") => FileKind::VectorIndex,
+            Some("json") => FileKind::Meta,
+            _ => FileKind::Other,
+        }
+    }
+
+    fn ordinal(self) -> usize {
+        self as usize
+    }
+}
+
+/// I/O statistics of a given kind of file.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct FileKindIoStats {
+    /// Number of reads.
+    pub num_reads: u64,
+    /// Number of bytes read.
+    pub num_bytes_read: u64,
+    /// Cumulated time spent reading.
+    pub total_read_time: Duration,
+    /// Longest read.
+    pub max_read_time: Duration,
+    /// Number of bytes written.
+    pub num_bytes_written: u64,
+    /// Number of reads served by a cache.
+    pub cache_hits: u64,
+    /// Number of reads that could not be served by a cache.
+    pub cache_misses: u64,
+}
+
+impl FileKindIoStats {
+    /// Returns the average duration of a read, or zero if there was no read.
+    pub fn mean_read_time(&self) -> Duration {
+        if self.num_reads == 0 {
+            return Duration::ZERO;
+        }
+        Duration::from_nanos((self.total_read_time.as_nanos() / self.num_reads as u128) as u64)
+    }
+}
+
+impl AddAssign for FileKindIoStats {
+    fn add_assign(&mut self, other: Self) {
+        self.num_reads += other.num_reads;
+        self.num_bytes_read += other.num_bytes_read;
+        self.total_read_time += other.total_read_time;
+        self.max_read_time = self.max_read_time.max(other.max_read_time);
+        self.num_bytes_written += other.num_bytes_written;
+        self.cache_hits += other.cache_hits;
+        self.cache_misses += other.cache_misses;
+    }
+}
+
+/// Snapshot of the I/O statistics collected by an [`IoMetrics`].
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct IoStats {
+    per_kind: [FileKindIoStats; NUM_FILE_KINDS],
+}
+
+impl IoStats {
+    /// Returns the statistics for the given kind of file.
+    pub fn get(&self, file_kind: FileKind) -> &FileKindIoStats {
+        &self.per_kind[file_kind.ordinal()]
+    }
+
+    /// Iterates through the statistics of every kind of file.
+    pub fn iter(&self) -> impl Iterator<Item = (FileKind, &FileKindIoStats)> + '_ {
+        FileKind::iterator().copied().zip(self.per_kind.iter())
+    }
+
+    /// Returns the statistics summed over all kinds of file.
+    pub fn total(&self) -> FileKindIoStats {
+        let mut total = FileKindIoStats::default();
+        for stats in &self.per_kind {
+            total += *stats;
+        }
+        total
+    }
+}
+
+#[derive(Default)]
+struct FileKindCounters {
+    num_reads: AtomicU64,
+    num_bytes_read: AtomicU64,
+    total_read_nanos: AtomicU64,
+    max_read_nanos: AtomicU64,
+    num_bytes_written: AtomicU64,
+    cache_hits: AtomicU64,
+    cache_misses: AtomicU64,
+}
+
+impl FileKindCounters {
+    fn stats(&self) -> FileKindIoStats {
+        FileKindIoStats {
+            num_reads: self.num_reads.load(Ordering::Relaxed),
+            num_bytes_read: self.num_bytes_read.load(Ordering::Relaxed),
+            total_read_time: Duration::from_nanos(self.total_read_nanos.load(Ordering::Relaxed)),
+            max_read_time: Duration::from_nanos(self.max_read_nanos.load(Ordering::Relaxed)),
+            num_bytes_written: self.num_bytes_written.load(Ordering::Relaxed),
+            cache_hits: self.cache_hits.load(Ordering::Relaxed),
+            cache_misses: self.cache_misses.load(Ordering::Relaxed),
+        }
+    }
+
+    fn reset(&self) {
+        for counter in [
+            &self.num_reads,
+            &self.num_bytes_read,
+            &self.total_read_nanos,
+            &self.max_read_nanos,
+            &self.num_bytes_written,
+            &self.cache_hits,
+            &self.cache_misses,
+        ] {
+            counter.store(0, Ordering::Relaxed);
+        }
+    }
+}
+
+/// Counters of the I/O performed on an index, broken down by [`FileKind`].
+///
+/// `IoMetrics` is cheap to clone, and clones share the same counters. The same
+/// instance can be set on an [`Index`](crate::Index) with
+/// [`Index::set_io_metrics()`](crate::Index::set_io_metrics) to count reads and writes,
+/// and on a [`CachingDirectory`](crate::directory::CachingDirectory) to count cache hits.
+///
+/// Only the files opened after the metrics have been set are accounted for.
+#[derive(Clone, Default)]
+pub struct IoMetrics {
+    counters: Arc<[FileKindCounters; NUM_FILE_KINDS]>,
+}
+
+impl fmt::Debug for IoMetrics {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("IoMetrics")
+            .field("stats", &self.stats())
+            .finish()
+    }
+}
+
+impl IoMetrics {
+    /// Creates a new set of counters, all set to zero.
+    pub fn new() -> IoMetrics {
+        IoMetrics::default()
+    }
+
+    /// Returns a snapshot of the counters.
+    pub fn stats(&self) -> IoStats {
+        let mut stats = IoStats::default();
+        for (kind_stats, counters) in stats.per_kind.iter_mut().zip(self.counters.iter()) {
+            *kind_stats = counters.stats();
+        }
+        stats
+    }
+
+    /// Resets all of the counters to zero.
+    pub fn reset(&self) {
+        for counters in self.counters.iter() {
+            counters.reset();
+        }
+    }
+
+    fn counters(&self, file_kind: FileKind) -> &FileKindCounters {
+        &self.counters[file_kind.ordinal()]
+    }
+
+    pub(crate) fn record_read(&self, file_kind: FileKind, num_bytes: usize, elapsed: Duration) {
+        let counters = self.counters(file_kind);
+        let elapsed_nanos = elapsed.as_nanos() as u64;
+        counters.num_reads.fetch_add(1, Ordering::Relaxed);
+        counters
+            .num_bytes_read
+            .fetch_add(num_bytes as u64, Ordering::Relaxed);
+        counters
+            .total_read_nanos
+            .fetch_add(elapsed_nanos, Ordering::Relaxed);
+        counters
+            .max_read_nanos
+            .fetch_max(elapsed_nanos, Ordering::Relaxed);
+    }
+
+    pub(crate) fn record_write(&self, file_kind: FileKind, num_bytes: usize) {
+        self.counters(file_kind)
+            .num_bytes_written
+            .fetch_add(num_bytes as u64, Ordering::Relaxed);
+    }
+
+    pub(crate) fn record_cache_access(&self, file_kind: FileKind, is_hit: bool) {
+        let counters = self.counters(file_kind);
+        if is_hit {
+            counters.cache_hits.fetch_add(1, Ordering::Relaxed);
+        } else {
+            counters.cache_misses.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    /// Wraps a file slice so that the reads it performs are counted.
+    pub(crate) fn wrap_file_slice(&self, path: &Path, file_slice: FileSlice) -> FileSlice {
+        FileSlice::new(Arc::new(MeteredFileHandle {
+            file_kind: FileKind::for_path(path),
+            underlying: file_slice,
+            io_metrics: self.clone(),
+        }))
+    }
+}
+
+struct MeteredFileHandle {
+    file_kind: FileKind,
+    underlying: FileSlice,
+    io_metrics: IoMetrics,
+}
+
+impl fmt::Debug for MeteredFileHandle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "MeteredFileHandle({:?})", self.file_kind)
+    }
+}
+
+impl HasLen for MeteredFileHandle {
+    fn len(&self) -> usize {
+        self.underlying.len()
+    }
+}
+
+impl FileHandle for MeteredFileHandle {
+    fn read_bytes(&self, range: Range<usize>) -> io::Result<OwnedBytes> {
+        let start = Instant::now();
+        let bytes = self.underlying.read_bytes(range)?;
+        self.io_metrics
+            .record_read(self.file_kind, bytes.len(), start.elapsed());
+        Ok(bytes)
+    }
+}
+
+/// Writer counting the bytes written to a file.
+pub(crate) struct MeteredWrite<W: TerminatingWrite> {
+    file_kind: FileKind,
+    underlying: W,
+    io_metrics: IoMetrics,
+}
+
+impl<W: TerminatingWrite> MeteredWrite<W> {
+    pub fn new(path: &Path, underlying: W, io_metrics: IoMetrics) -> MeteredWrite<W> {
+        MeteredWrite {
+            file_kind: FileKind::for_path(path),
+            underlying,
+            io_metrics,
+        }
+    }
+}
+
+impl<W: TerminatingWrite> Write for MeteredWrite<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let num_bytes = self.underlying.write(buf)?;
+        self.io_metrics.record_write(self.file_kind, num_bytes);
+        Ok(num_bytes)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.underlying.flush()
+    }
+}
+
+impl<W: TerminatingWrite> TerminatingWrite for MeteredWrite<W> {
+    fn terminate_ref(&mut self, token: AntiCallToken) -> io::Result<()> {
+        self.underlying.terminate_ref(token)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+    use std::sync::Arc;
+
+    use super::{FileKind, IoMetrics};
+    use crate::directory::{CachingDirectory, RamDirectory, SharedBlockCache};
+    use crate::schema::{Schema, STORED, TEXT};
+    use crate::{DocAddress, Index, IndexWriter, TantivyDocument};
+
+    #[test]
+    fn test_file_kind_for_path() {
+        assert_eq!(
+            FileKind::for_path(Path::new("00000000000000000000000000000000.idx")),
+            FileKind::Postings
+        );
+        assert_eq!(
+            FileKind::for_path(Path::new("00000000000000000000000000000000.7.del")),
+            FileKind::Delete
+        );
+        assert_eq!(
+            FileKind::for_path(Path::new("00000000000000000000000000000000.store.temp")),
+            FileKind::Other
+        );
+        assert_eq!(
+            FileKind::for_path(Path::new("00000000000000000000000000000000.vec")),
+            FileKind::VectorIndex
+        );
+        assert_eq!(FileKind::for_path(Path::new("meta.json")), FileKind::Meta);
+    }
+
+    #[test]
+    fn test_index_io_stats() -> crate::Result<()> {
+        let mut schema_builder = Schema::builder();
+        let text = schema_builder.add_text_field("text", TEXT | STORED);
+        let mut index = Index::create_in_ram(schema_builder.build());
+        assert!(index.io_stats().is_none());
+        let io_metrics = IoMetrics::new();
+        index.set_io_metrics(io_metrics.clone());
+        let mut writer: IndexWriter = index.writer_for_tests()?;
+        writer.add_document(doc!(text => "hello happy tax payer"))?;
+        writer.commit()?;
+        let stats = index.io_stats().unwrap();
+        assert!(stats.get(FileKind::Store).num_bytes_written > 0);
+        assert!(stats.get(FileKind::Postings).num_bytes_written > 0);
+        assert!(stats.get(FileKind::Meta).num_bytes_written > 0);
+
+        io_metrics.reset();
+        let searcher = index.reader()?.searcher();
+        let _doc: TantivyDocument = searcher.doc(DocAddress::new(0, 0))?;
+        let stats = index.io_stats().unwrap();
+        assert!(stats.get(FileKind::Store).num_reads > 0);
+        assert!(stats.get(FileKind::Store).num_bytes_read > 0);
+        assert_eq!(stats.get(FileKind::Store).num_bytes_written, 0);
+        assert!(stats.total().num_reads >= stats.get(FileKind::Store).num_reads);
+        Ok(())
+    }
+
+    #[test]
+    fn test_caching_directory_io_metrics() -> crate::Result<()> {
+        let mut schema_builder = Schema::builder();
+        let text = schema_builder.add_text_field("text", TEXT | STORED);
+        let io_metrics = IoMetrics::new();
+        let directory = CachingDirectory::wrap(
+            RamDirectory::create(),
+            Arc::new(SharedBlockCache::new(1_000_000)),
+        )
+        .set_io_metrics(io_metrics.clone());
+        let index = Index::create(directory, schema_builder.build(), Default::default())?;
+        let mut writer: IndexWriter = index.writer_for_tests()?;
+        writer.add_document(doc!(text => "hello"))?;
+        writer.commit()?;
+        // Each reader has its own doc store cache, so that the second one has to read the
+        // store again, this time from the block cache.
+        for _ in 0..2 {
+            let searcher = index.reader()?.searcher();
+            let _doc: TantivyDocument = searcher.doc(DocAddress::new(0, 0))?;
+        }
+        let store_stats = *io_metrics.stats().get(FileKind::Store);
+        assert!(store_stats.cache_misses > 0);
+        assert!(store_stats.cache_hits > 0);
+        Ok(())
+    }
+}
@@ -0,0 +1,443 @@
+use std::cell::RefCell;
+use std::fs::File;
+use std::ops::Range;
+use std::os::unix::fs::FileExt;
+use std::os::unix::io::AsRawFd;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::{fmt, io};
+
+use common::HasLen;
+use io_uring::{opcode, types, IoUring};
+
+use crate::directory::error::{
+    DeleteError, LockError, OpenDirectoryError, OpenReadError, OpenWriteError,
+};
+use crate::directory::{
+    AccessHint, Directory, DirectoryLock, FileHandle, Lock, MmapDirectory, OwnedBytes,
+    WatchCallback, WatchHandle, WritePtr,
+};
+
+/// Number of entries of the submission queue.
+///
+/// Larger batches are split into several submissions.
+const QUEUE_DEPTH: usize = 256;
+
+thread_local! {
+    /// io_uring instance of the thread, created on its first read, or `None` if io_uring is not
+    /// usable.
+    ///
+    /// Each thread submitting to its own ring, concurrent readers never wait for one another.
+    static THREAD_RING: RefCell<Option<Option<ThreadRing>>> = RefCell::new(None);
+}
+
+struct ThreadRing {
+    ring: IoUring,
+    // Incremented by each call to `submit_reads`, and stored in the upper bits of the
+    // `user_data` of its entries, so that completions of an earlier call are told apart.
+    generation: u32,
+}
+
+fn user_data(generation: u32, read_id: usize) -> u64 {
+    (u64::from(generation) << 32) | read_id as u64
+}
+
+/// Reads all of the given ranges, submitting them to io_uring as a batch when
+/// `use_io_uring` is true and a ring is available, and using `pread` otherwise.
+fn read_ranges(use_io_uring: bool, reads: &[(&File, Range<usize>)]) -> io::Result<Vec<OwnedBytes>> {
+    let mut buffers: Vec<Vec<u8>> = reads
+        .iter()
+        .map(|(_, range)| vec![0u8; range.len()])
+        .collect();
+    let mut num_bytes_read = vec![0usize; reads.len()];
+    if use_io_uring {
+        THREAD_RING.with(|thread_ring| {
+            let mut thread_ring = thread_ring.borrow_mut();
+            let thread_ring = thread_ring.get_or_insert_with(|| {
+                IoUring::new(QUEUE_DEPTH as u32)
+                    .ok()
+                    .map(|ring| ThreadRing {
+                        ring,
+                        generation: 0,
+                    })
+            });
+            let Some(ring) = thread_ring.as_mut() else {
+                return Ok(());
+            };
+            let result = submit_reads(ring, reads, &mut buffers, &mut num_bytes_read);
+            if let Err(SubmitError::RingFailure(_)) = &result {
+                // The ring is not usable anymore: this thread now reads with `pread`.
+                *thread_ring = None;
+            }
+            result.map_err(io::Error::from)
+        })?;
+    }
+    for (read_id, (file, range)) in reads.iter().enumerate() {
+        // Completes short reads, or performs the whole read without io_uring.
+        let start = num_bytes_read[read_id];
+        if start < range.len() {
+            file.read_exact_at(&mut buffers[read_id][start..], (range.start + start) as u64)?;
+        }
+    }
+    Ok(buffers.into_iter().map(OwnedBytes::new).collect())
+}
+
+enum SubmitError {
+    /// One of the reads failed.
+    Read(io::Error),
+    /// Entries could not be submitted, or their completions could not be waited for.
+    RingFailure(io::Error),
+}
+
+impl From<SubmitError> for io::Error {
+    fn from(submit_error: SubmitError) -> io::Error {
+        match submit_error {
+            SubmitError::Read(io_error) | SubmitError::RingFailure(io_error) => io_error,
+        }
+    }
+}
+
+fn submit_reads(
+    thread_ring: &mut ThreadRing,
+    reads: &[(&File, Range<usize>)],
+    buffers: &mut [Vec<u8>],
+    num_bytes_read: &mut [usize],
+) -> Result<(), SubmitError> {
+    thread_ring.generation = thread_ring.generation.wrapping_add(1);
+    let generation = thread_ring.generation;
+    let ring = &mut thread_ring.ring;
+    let mut read_error = None;
+    for chunk_start in (0..reads.len()).step_by(QUEUE_DEPTH) {
+        let chunk_end = (chunk_start + QUEUE_DEPTH).min(reads.len());
+        for read_id in chunk_start..chunk_end {
+            let (file, range) = &reads[read_id];
+            let buffer = &mut buffers[read_id];
+            let entry = opcode::Read::new(
+                types::Fd(file.as_raw_fd()),
+                buffer.as_mut_ptr(),
+                buffer.len() as u32,
+            )
+            .offset(range.start as u64)
+            .build()
+            .user_data(user_data(generation, read_id));
+            // Safety: the buffers and the files outlive the reads, as we wait for all of
+            // the submitted entries to complete before returning.
+            unsafe {
+                ring.submission()
+                    .push(&entry)
+                    .expect("the submission queue is drained after each chunk");
+            }
+        }
+        let mut in_flight = vec![true; chunk_end - chunk_start];
+        let mut num_pending = in_flight.len();
+        // Completions are waited for even after a failed read, as the kernel may still be
+        // writing into the buffers of the other ones.
+        while num_pending > 0 {
+            let submit_result = ring.submit_and_wait(num_pending);
+            let mut num_completed = 0;
+            for completion in ring.completion() {
+                let read_user_data = completion.user_data();
+                if (read_user_data >> 32) as u32 != generation {
+                    continue;
+                }
+                let read_id = read_user_data as u32 as usize;
+                let result = completion.result();
+                if result < 0 {
+                    read_error.get_or_insert(io::Error::from_raw_os_error(-result));
+                } else {
+                    num_bytes_read[read_id] = result as usize;
+                }
+                in_flight[read_id - chunk_start] = false;
+                num_completed += 1;
+            }
+            num_pending -= num_completed;
+            match submit_result {
+                Ok(_) => {}
+                Err(io_error)
+                    if num_completed > 0
+                        || matches!(
+                            io_error.kind(),
+                            io::ErrorKind::Interrupted | io::ErrorKind::WouldBlock
+                        ) => {}
+                Err(io_error) => {
+                    // The ring is dropped by the caller, which discards the entries the kernel
+                    // has not consumed yet. The kernel holds its own reference to the files of
+                    // those it has, but their buffers must not be freed.
+                    for (offset, _) in in_flight
+                        .iter()
+                        .enumerate()
+                        .filter(|(_, pending)| **pending)
+                    {
+                        std::mem::forget(std::mem::take(&mut buffers[chunk_start + offset]));
+                    }
+                    return Err(SubmitError::RingFailure(io_error));
+                }
+            }
+        }
+        if let Some(io_error) = read_error {
+            return Err(SubmitError::Read(io_error));
+        }
+    }
+    Ok(())
+}
+
+struct IoUringFileHandle {
+    path: PathBuf,
+    file: File,
+    len: usize,
+    use_io_uring: bool,
+}
+
+impl fmt::Debug for IoUringFileHandle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "IoUringFileHandle({:?})", self.path)
+    }
+}
+
+impl HasLen for IoUringFileHandle {
+    fn len(&self) -> usize {
+        self.len
+    }
+}
+
+impl FileHandle for IoUringFileHandle {
+    fn read_bytes(&self, range: Range<usize>) -> io::Result<OwnedBytes> {
+        if range.end > self.len {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "Range {range:?} exceeds the length of file {:?} ({}).",
+                    self.path, self.len
+                ),
+            ));
+        }
+        if range.is_empty() {
+            return Ok(OwnedBytes::empty());
+        }
+        let mut blocks = read_ranges(self.use_io_uring, &[(&self.file, range)])?;
+        Ok(blocks.pop().unwrap())
+    }
+}
+
+/// Directory reading files with io_uring on Linux.
+///
+/// Everything but reads is delegated to an [`MmapDirectory`]. Rather than mapping
+/// files in memory, reads are submitted to an io_uring instance, and
+/// [`IoUringDirectory::read_batch()`] makes it possible to submit many random reads
+/// (postings blocks, doc store blocks...) at once, which helps saturating fast NVMe
+/// drives.
+///
+/// If io_uring is not available at runtime (old kernel, seccomp policy...), reads fall
+/// back to `pread`.
+#[derive(Clone)]
+pub struct IoUringDirectory {
+    inner: MmapDirectory,
+    use_io_uring: bool,
+}
+
+impl fmt::Debug for IoUringDirectory {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "IoUringDirectory({:?})", self.inner)
+    }
+}
+
+impl IoUringDirectory {
+    /// Opens an `IoUringDirectory` in a directory.
+    pub fn open(directory_path: impl AsRef<Path>) -> Result<IoUringDirectory, OpenDirectoryError> {
+        let inner = MmapDirectory::open(directory_path)?;
+        // Each reading thread creates its own ring: this one only checks that io_uring is
+        // available.
+        let use_io_uring = match IoUring::new(QUEUE_DEPTH as u32) {
+            Ok(_) => true,
+            Err(io_error) => {
+                warn!("io_uring is not available, falling back to pread: {io_error:?}");
+                false
+            }
+        };
+        Ok(IoUringDirectory {
+            inner,
+            use_io_uring,
+        })
+    }
+
+    /// Returns true if reads are performed with io_uring, as opposed to `pread`.
+    pub fn uses_io_uring(&self) -> bool {
+        self.use_io_uring
+    }
+
+    /// Reads many ranges, possibly from different files, in a single batch.
+    ///
+    /// The returned bytes are in the same order as `reads`.
+    pub fn read_batch(&self, reads: &[(&Path, Range<usize>)]) -> io::Result<Vec<OwnedBytes>> {
+        let files: Vec<File> = reads
+            .iter()
+            .map(|(path, _)| File::open(self.resolve_path(path)))
+            .collect::<io::Result<_>>()?;
+        let file_reads: Vec<(&File, Range<usize>)> = files
+            .iter()
+            .zip(reads)
+            .map(|(file, (_, range))| (file, range.clone()))
+            .collect();
+        read_ranges(self.use_io_uring, &file_reads)
+    }
+
+    fn resolve_path(&self, path: &Path) -> PathBuf {
+        self.inner
+            .local_path(path)
+            .expect("MmapDirectory files are on the local filesystem")
+    }
+}
+
+impl Directory for IoUringDirectory {
+    fn get_file_handle(&self, path: &Path) -> Result<Arc<dyn FileHandle>, OpenReadError> {
+        let file = File::open(self.resolve_path(path)).map_err(|io_error| {
+            if io_error.kind() == io::ErrorKind::NotFound {
+                OpenReadError::FileDoesNotExist(path.to_path_buf())
+            } else {
+                OpenReadError::wrap_io_error(io_error, path.to_path_buf())
+            }
+        })?;
+        let len = file
+            .metadata()
+            .map_err(|io_error| OpenReadError::wrap_io_error(io_error, path.to_path_buf()))?
+            .len() as usize;
+        Ok(Arc::new(IoUringFileHandle {
+            path: path.to_path_buf(),
+            file,
+            len,
+            use_io_uring: self.use_io_uring,
+        }))
+    }
+
+    fn delete(&self, path: &Path) -> Result<(), DeleteError> {
+        self.inner.delete(path)
+    }
+
+    fn exists(&self, path: &Path) -> Result<bool, OpenReadError> {
+        self.inner.exists(path)
+    }
+
+    fn open_write(&self, path: &Path) -> Result<WritePtr, OpenWriteError> {
+        self.inner.open_write(path)
+    }
+
+    fn atomic_read(&self, path: &Path) -> Result<Vec<u8>, OpenReadError> {
+        self.inner.atomic_read(path)
+    }
+
+    fn atomic_write(&self, path: &Path, data: &[u8]) -> io::Result<()> {
+        self.inner.atomic_write(path, data)
+    }
+
+    fn sync_directory(&self) -> io::Result<()> {
+        self.inner.sync_directory()
+    }
+
+    fn acquire_lock(&self, lock: &Lock) -> Result<DirectoryLock, LockError> {
+        self.inner.acquire_lock(lock)
+    }
+
+    fn watch(&self, watch_callback: WatchCallback) -> crate::Result<WatchHandle> {
+        self.inner.watch(watch_callback)
+    }
+
+    fn advise(&self, path: &Path, hint: AccessHint) -> io::Result<()> {
+        self.inner.advise(path, hint)
+    }
+
+    fn local_path(&self, path: &Path) -> Option<PathBuf> {
+        self.inner.local_path(path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+    use std::path::Path;
+
+    use tempfile::TempDir;
+
+    use super::IoUringDirectory;
+    use crate::directory::{Directory, TerminatingWrite};
+    use crate::schema::{Schema, STORED, TEXT};
+    use crate::{DocAddress, Index, IndexWriter, TantivyDocument};
+
+    #[test]
+    fn test_io_uring_directory_reads() -> crate::Result<()> {
+        let tempdir = TempDir::new().unwrap();
+        let directory = IoUringDirectory::open(tempdir.path())?;
+        let data: Vec<u8> = (0..10_000u32).map(|i| (i % 251) as u8).collect();
+        for name in ["a", "b"] {
+            let mut wrt = directory.open_write(Path::new(name))?;
+            wrt.write_all(&data)?;
+            wrt.terminate()?;
+        }
+        let file = directory.open_read(Path::new("a"))?;
+        assert_eq!(file.len(), data.len());
+        assert_eq!(
+            file.read_bytes_slice(100..5_000)?.as_slice(),
+            &data[100..5_000]
+        );
+        assert!(file.read_bytes_slice(0..0)?.is_empty());
+
+        let reads: Vec<(&Path, _)> = (0..1_000)
+            .map(|i| {
+                let path = Path::new(if i % 2 == 0 { "a" } else { "b" });
+                (path, i..i + 7 * i % 3_000)
+            })
+            .collect();
+        let blocks = directory.read_batch(&reads)?;
+        assert_eq!(blocks.len(), reads.len());
+        for ((_, range), block) in reads.iter().zip(&blocks) {
+            assert_eq!(block.as_slice(), &data[range.clone()]);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_io_uring_directory_concurrent_reads() -> crate::Result<()> {
+        let tempdir = TempDir::new().unwrap();
+        let directory = IoUringDirectory::open(tempdir.path())?;
+        let data: Vec<u8> = (0..100_000u32).map(|i| (i % 241) as u8).collect();
+        let mut wrt = directory.open_write(Path::new("a"))?;
+        wrt.write_all(&data)?;
+        wrt.terminate()?;
+        let file = directory.open_read(Path::new("a"))?;
+        std::thread::scope(|scope| {
+            for thread_id in 0..4 {
+                let (file, data) = (&file, &data);
+                scope.spawn(move || {
+                    for i in 0..500 {
+                        let start = (thread_id * 7_919 + i * 131) % 90_000;
+                        let range = start..start + i % 1_000;
+                        let bytes = file.read_bytes_slice(range.clone()).unwrap();
+                        assert_eq!(bytes.as_slice(), &data[range]);
+                    }
+                });
+            }
+        });
+        // Out of range reads are rejected before anything is submitted.
+        assert!(file.read_bytes_slice(99_000..100_001).is_err());
+        assert_eq!(file.read_bytes_slice(0..10)?.as_slice(), &data[..10]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_io_uring_directory_index() -> crate::Result<()> {
+        let mut schema_builder = Schema::builder();
+        let text = schema_builder.add_text_field("text", TEXT | STORED);
+        let tempdir = TempDir::new().unwrap();
+        let directory = IoUringDirectory::open(tempdir.path())?;
+        let index = Index::create(directory, schema_builder.build(), Default::default())?;
+        let mut writer: IndexWriter = index.writer_for_tests()?;
+        writer.add_document(doc!(text => "hello happy tax payer"))?;
+        writer.commit()?;
+        let searcher = index.reader()?.searcher();
+        let doc: TantivyDocument = searcher.doc(DocAddress::new(0, 0))?;
+        assert_eq!(
+            doc.get_first(text).unwrap().as_value().as_str(),
+            Some("hello happy tax payer")
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,216 @@
+use std::fmt;
+#[cfg(feature = "mmap")]
+use std::fs::{File, OpenOptions};
+#[cfg(feature = "mmap")]
+use std::path::PathBuf;
+
+#[cfg(feature = "mmap")]
+use fs4::FileExt;
+
+use crate::directory::directory::acquire_lock_with_lock_file;
+use crate::directory::error::LockError;
+use crate::directory::{Directory, DirectoryLock, Lock};
+
+/// Acquires the locks protecting an index, such as the [`INDEX_WRITER_LOCK`] and the
+/// [`META_LOCK`].
+///
+/// By default, locks are acquired through [`Directory::acquire_lock`]. Setting a
+/// `LockManager` on an index (see [`Index::set_lock_manager`]) makes it possible to rely
+/// on something else, typically when the index lives on a shared or network filesystem
+/// on which file locks are not reliable: an etcd or ZooKeeper lease, a database row...
+///
+/// The lock is held for as long as the returned [`DirectoryLock`] is alive. Any
+/// `Box<T: Send + Sync>` can be converted into a `DirectoryLock`, and its `Drop`
+/// implementation is in charge of releasing the lock.
+///
+/// [`INDEX_WRITER_LOCK`]: crate::directory::INDEX_WRITER_LOCK
+/// [`META_LOCK`]: crate::directory::META_LOCK
+/// [`Index::set_lock_manager`]: crate::Index::set_lock_manager
+pub trait LockManager: fmt::Debug + Send + Sync + 'static {
+    /// Acquires the given lock.
+    ///
+    /// Depending on [`Lock::is_blocking`], this should either wait for the lock to be
+    /// available, or return [`LockError::LockBusy`] right away.
+    fn acquire_lock(&self, lock: &Lock) -> Result<DirectoryLock, LockError>;
+}
+
+/// Lock manager relying on advisory file locks (`flock`) on the local filesystem.
+///
+/// This is what [`MmapDirectory`](crate::directory::MmapDirectory) uses. Advisory locks
+/// are released by the OS when the process dies, but are typically not honored by
+/// network filesystems.
+#[cfg(feature = "mmap")]
+#[derive(Debug, Clone)]
+pub struct AdvisoryLockManager {
+    root_path: PathBuf,
+}
+
+#[cfg(feature = "mmap")]
+impl AdvisoryLockManager {
+    /// Creates a lock manager locking files in the `root_path` directory.
+    pub fn new(root_path: PathBuf) -> AdvisoryLockManager {
+        AdvisoryLockManager { root_path }
+    }
+}
+
+/// The lock is actually released when the `File` object is dropped and its
+/// associated file descriptor is closed.
+#[cfg(feature = "mmap")]
+struct ReleaseLockFile {
+    _file: File,
+    path: PathBuf,
+}
+
+#[cfg(feature = "mmap")]
+impl Drop for ReleaseLockFile {
+    fn drop(&mut self) {
+        debug!("Releasing lock {:?}", self.path);
+    }
+}
+
+#[cfg(feature = "mmap")]
+impl LockManager for AdvisoryLockManager {
+    fn acquire_lock(&self, lock: &Lock) -> Result<DirectoryLock, LockError> {
+        let full_path = self.root_path.join(&lock.filepath);
+        // We make sure that the file exists.
+        let file: File = OpenOptions::new()
+            .write(true)
+            .create(true) //< if the file does not exist yet, create it.
+            .truncate(false)
+            .open(full_path)
+            .map_err(LockError::wrap_io_error)?;
+        if lock.is_blocking {
+            file.lock_exclusive().map_err(LockError::wrap_io_error)?;
+        } else {
+            file.try_lock_exclusive().map_err(|_| LockError::LockBusy)?
+        }
+        // dropping the file handle will release the lock.
+        Ok(DirectoryLock::from(Box::new(ReleaseLockFile {
+            path: lock.filepath.clone(),
+            _file: file,
+        })))
+    }
+}
+
+/// Lock manager representing a lock by the existence of its lock file.
+///
+/// The lock file is created with [`Directory::open_write`], which fails if the file
+/// already exists, and deleted when the lock is released. This works with any
+/// directory, including shared filesystems supporting exclusive file creation (e.g.
+/// NFSv3 and later).
+///
+/// If the process holding the lock dies, the lock file remains and must be removed
+/// manually.
+#[derive(Debug)]
+pub struct ExclusiveFileLockManager {
+    directory: Box<dyn Directory>,
+}
+
+impl ExclusiveFileLockManager {
+    /// Creates a lock manager creating its lock files in `directory`.
+    pub fn new<D: Into<Box<dyn Directory>>>(directory: D) -> ExclusiveFileLockManager {
+        ExclusiveFileLockManager {
+            directory: directory.into(),
+        }
+    }
+}
+
+impl LockManager for ExclusiveFileLockManager {
+    fn acquire_lock(&self, lock: &Lock) -> Result<DirectoryLock, LockError> {
+        acquire_lock_with_lock_file(self.directory.as_ref(), lock)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+    use std::path::{Path, PathBuf};
+    use std::sync::{Arc, Mutex};
+
+    use super::{ExclusiveFileLockManager, LockManager};
+    use crate::directory::error::LockError;
+    use crate::directory::{Directory, DirectoryLock, Lock, RamDirectory};
+    use crate::schema::{Schema, TEXT};
+    use crate::{Index, IndexWriter, TantivyDocument};
+
+    /// Lock manager keeping the held locks in memory, standing for an external
+    /// lock service.
+    #[derive(Debug, Default)]
+    struct InMemoryLockManager {
+        held_locks: Arc<Mutex<HashSet<PathBuf>>>,
+        num_acquired: Mutex<usize>,
+    }
+
+    struct InMemoryLock {
+        held_locks: Arc<Mutex<HashSet<PathBuf>>>,
+        path: PathBuf,
+    }
+
+    impl Drop for InMemoryLock {
+        fn drop(&mut self) {
+            self.held_locks.lock().unwrap().remove(&self.path);
+        }
+    }
+
+    impl LockManager for InMemoryLockManager {
+        fn acquire_lock(&self, lock: &Lock) -> Result<DirectoryLock, LockError> {
+            if !self
+                .held_locks
+                .lock()
+                .unwrap()
+                .insert(lock.filepath.clone())
+            {
+                return Err(LockError::LockBusy);
+            }
+            *self.num_acquired.lock().unwrap() += 1;
+            Ok(DirectoryLock::from(Box::new(InMemoryLock {
+                held_locks: self.held_locks.clone(),
+                path: lock.filepath.clone(),
+            })))
+        }
+    }
+
+    #[test]
+    fn test_exclusive_file_lock_manager() {
+        let directory = RamDirectory::create();
+        let lock_manager = ExclusiveFileLockManager::new(directory.clone());
+        let lock = Lock {
+            filepath: PathBuf::from("a.lock"),
+            is_blocking: false,
+        };
+        let directory_lock = lock_manager.acquire_lock(&lock).unwrap();
+        assert!(directory.exists(Path::new("a.lock")).unwrap());
+        assert!(matches!(
+            lock_manager.acquire_lock(&lock),
+            Err(LockError::LockBusy)
+        ));
+        drop(directory_lock);
+        assert!(!directory.exists(Path::new("a.lock")).unwrap());
+        assert!(lock_manager.acquire_lock(&lock).is_ok());
+    }
+
+    #[test]
+    fn test_index_with_custom_lock_manager() -> crate::Result<()> {
+        let mut schema_builder = Schema::builder();
+        let text = schema_builder.add_text_field("text", TEXT);
+        let directory = RamDirectory::create();
+        let mut index = Index::create(
+            directory.clone(),
+            schema_builder.build(),
+            Default::default(),
+        )?;
+        let lock_manager = Arc::new(InMemoryLockManager::default());
+        index.set_lock_manager(lock_manager.clone());
+        let mut writer: IndexWriter = index.writer_for_tests()?;
+        assert!(index.writer_for_tests::<TantivyDocument>().is_err());
+        writer.add_document(doc!(text => "hello"))?;
+        writer.commit()?;
+        // The writer lock, and the meta lock taken by the garbage collection.
+        assert!(*lock_manager.num_acquired.lock().unwrap() >= 2);
+        // Locks do not go through the directory anymore.
+        assert!(!directory.exists(Path::new(".tantivy-writer.lock"))?);
+        drop(writer);
+        assert!(lock_manager.held_locks.lock().unwrap().is_empty());
+        Ok(())
+    }
+}
@@ -10,11 +10,12 @@ use crate::core::MANAGED_FILEPATH;
 use crate::directory::error::{DeleteError, LockError, OpenReadError, OpenWriteError};
 use crate::directory::footer::{Footer, FooterProxy};
 use crate::directory::{
-    DirectoryLock, FileHandle, FileSlice, GarbageCollectionResult, Lock, WatchCallback,
-    WatchHandle, WritePtr, META_LOCK,
+    AccessHint, DirectoryLock, FileHandle, FileKind, FileSlice, GarbageCollectionResult, IoMetrics,
+    Lock, LockManager, MeteredWrite, TerminatingWrite, WatchCallback, WatchHandle, WritePtr,
+    META_LOCK,
 };
 use crate::error::DataCorruption;
-use crate::Directory;
+use crate::{Directory, Instant};
 
 /// Returns true if the file is "managed".
 /// Non-managed file are not subject to garbage collection.
@@ -40,6 +41,8 @@ fn is_managed(path: &Path) -> bool {
 pub struct ManagedDirectory {
     directory: Box<dyn Directory>,
     meta_informations: Arc<RwLock<MetaInformation>>,
+    lock_manager: Option<Arc<dyn LockManager>>,
+    io_metrics: Option<IoMetrics>,
 }
 
 #[derive(Debug, Default)]
@@ -53,12 +56,74 @@ fn save_managed_paths(
     directory: &dyn Directory,
     wlock: &RwLockWriteGuard<'_, MetaInformation>,
 ) -> io::Result<()> {
-    let mut w = serde_json::to_vec(&wlock.managed_paths)?;
+    write_managed_paths(directory, &wlock.managed_paths)
+}
+
+/// Writes the `.managed.json` file listing the given paths.
+pub(crate) fn write_managed_paths(
+    directory: &dyn Directory,
+    managed_paths: &HashSet<PathBuf>,
+) -> io::Result<()> {
+    let mut w = serde_json::to_vec(managed_paths)?;
     writeln!(&mut w)?;
     directory.atomic_write(&MANAGED_FILEPATH, &w[..])?;
     Ok(())
 }
 
+/// Copies a file as is from a directory to another.
+///
+/// The file is hard linked when both directories keep their files on the local
+/// filesystem, and copied otherwise.
+pub(crate) fn copy_file(
+    src: &dyn Directory,
+    path: &Path,
+    dest: &dyn Directory,
+) -> crate::Result<()> {
+    if let (Some(src_path), Some(dest_path)) = (src.local_path(path), dest.local_path(path)) {
+        match std::fs::hard_link(&src_path, &dest_path) {
+            Ok(()) => return Ok(()),
+            Err(io_error) => {
+                debug!("Failed to hard link {src_path:?}, copying instead: {io_error:?}");
+            }
+        }
+    }
+    let file_slice = src.open_read(path)?;
+    let mut wrt = dest.open_write(path)?;
+    for chunk in file_slice.stream_file_chunks() {
+        wrt.write_all(chunk?.as_slice())?;
+    }
+    wrt.terminate()?;
+    Ok(())
+}
+
+/// Returns the checksum recorded in the footer of a file written through a
+/// `ManagedDirectory`.
+///
+/// If `verify` is true, the checksum is also computed over the content of the file,
+/// and a `DataCorruption` error is returned if the two do not match.
+pub(crate) fn file_checksum(path: &Path, file: FileSlice, verify: bool) -> crate::Result<u32> {
+    let (footer, data) = Footer::extract_footer(file)
+        .map_err(|io_error| OpenReadError::wrap_io_error(io_error, path.to_path_buf()))?;
+    if verify {
+        let mut hasher = Hasher::new();
+        for chunk in data.stream_file_chunks() {
+            hasher.update(chunk?.as_slice());
+        }
+        let crc = hasher.finalize();
+        if crc != footer.crc() {
+            return Err(DataCorruption::new(
+                path.to_path_buf(),
+                format!(
+                    "Checksum mismatch: footer says {:#010x}, content hashes to {crc:#010x}.",
+                    footer.crc()
+                ),
+            )
+            .into());
+        }
+    }
+    Ok(footer.crc())
+}
+
 impl ManagedDirectory {
     /// Wraps a directory as managed directory.
     pub fn wrap(directory: Box<dyn Directory>) -> crate::Result<ManagedDirectory> {
@@ -77,11 +142,15 @@ impl ManagedDirectory {
                     meta_informations: Arc::new(RwLock::new(MetaInformation {
                         managed_paths: managed_files,
                     })),
+                    lock_manager: None,
+                    io_metrics: None,
                 })
             }
             Err(OpenReadError::FileDoesNotExist(_)) => Ok(ManagedDirectory {
                 directory,
                 meta_informations: Arc::default(),
+                lock_manager: None,
+                io_metrics: None,
             }),
             io_err @ Err(OpenReadError::IoError { .. }) => Err(io_err.err().unwrap().into()),
             Err(OpenReadError::IncompatibleIndex(incompatibility)) => {
@@ -92,6 +161,22 @@ impl ManagedDirectory {
         }
     }
 
+    /// Sets the [`LockManager`] used to acquire locks, instead of the wrapped
+    /// directory.
+    pub fn set_lock_manager(&mut self, lock_manager: Arc<dyn LockManager>) {
+        self.lock_manager = Some(lock_manager);
+    }
+
+    /// Counts the reads and writes going through this directory in `io_metrics`.
+    pub fn set_io_metrics(&mut self, io_metrics: IoMetrics) {
+        self.io_metrics = Some(io_metrics);
+    }
+
+    /// Returns the I/O metrics set with [`ManagedDirectory::set_io_metrics()`], if any.
+    pub fn io_metrics(&self) -> Option<&IoMetrics> {
+        self.io_metrics.as_ref()
+    }
+
     /// Garbage collect unused files.
     ///
     /// Removes the files that were created by `tantivy` and are not
@@ -256,6 +341,30 @@ impl ManagedDirectory {
         Ok(footer.crc() == crc)
     }
 
+    /// Returns a clone of the wrapped directory.
+    pub(crate) fn raw_directory(&self) -> Box<dyn Directory> {
+        self.directory.box_clone()
+    }
+
+    /// Opens a file without stripping its footer.
+    pub(crate) fn open_read_raw(&self, path: &Path) -> result::Result<FileSlice, OpenReadError> {
+        self.directory.open_read(path)
+    }
+
+    /// Copies a file, footer included, to another directory.
+    ///
+    /// `dest` is expected to be a raw directory, not a `ManagedDirectory`.
+    pub(crate) fn copy_raw_file(&self, path: &Path, dest: &dyn Directory) -> crate::Result<()> {
+        copy_file(self.directory.as_ref(), path, dest)
+    }
+
+    /// Registers a file as managed and copies it as is, footer included, from `src`.
+    pub(crate) fn import_raw_file(&self, src: &dyn Directory, path: &Path) -> crate::Result<()> {
+        self.register_file_as_managed(path)
+            .map_err(|io_error| OpenWriteError::wrap_io_error(io_error, path.to_path_buf()))?;
+        copy_file(src, path, self.directory.as_ref())
+    }
+
     /// List all managed files
     pub fn list_managed_files(&self) -> HashSet<PathBuf> {
         let managed_paths = self
@@ -279,28 +388,44 @@ impl Directory for ManagedDirectory {
         let (footer, reader) = Footer::extract_footer(file_slice)
             .map_err(|io_error| OpenReadError::wrap_io_error(io_error, path.to_path_buf()))?;
         footer.is_compatible()?;
+        if let Some(io_metrics) = &self.io_metrics {
+            return Ok(io_metrics.wrap_file_slice(path, reader));
+        }
         Ok(reader)
     }
 
     fn open_write(&self, path: &Path) -> result::Result<WritePtr, OpenWriteError> {
         self.register_file_as_managed(path)
             .map_err(|io_error| OpenWriteError::wrap_io_error(io_error, path.to_path_buf()))?;
-        Ok(io::BufWriter::new(Box::new(FooterProxy::new(
-            self.directory
-                .open_write(path)?
-                .into_inner()
-                .map_err(|_| ())
-                .expect("buffer should be empty"),
-        ))))
+        let writer = self
+            .directory
+            .open_write(path)?
+            .into_inner()
+            .map_err(|_| ())
+            .expect("buffer should be empty");
+        if let Some(io_metrics) = &self.io_metrics {
+            let writer = MeteredWrite::new(path, writer, io_metrics.clone());
+            return Ok(io::BufWriter::new(Box::new(FooterProxy::new(writer))));
+        }
+        Ok(io::BufWriter::new(Box::new(FooterProxy::new(writer))))
     }
 
     fn atomic_write(&self, path: &Path, data: &[u8]) -> io::Result<()> {
         self.register_file_as_managed(path)?;
-        self.directory.atomic_write(path, data)
+        self.directory.atomic_write(path, data)?;
+        if let Some(io_metrics) = &self.io_metrics {
+            io_metrics.record_write(FileKind::for_path(path), data.len());
+        }
+        Ok(())
     }
 
     fn atomic_read(&self, path: &Path) -> result::Result<Vec<u8>, OpenReadError> {
-        self.directory.atomic_read(path)
+        let start = Instant::now();
+        let data = self.directory.atomic_read(path)?;
+        if let Some(io_metrics) = &self.io_metrics {
+            io_metrics.record_read(FileKind::for_path(path), data.len(), start.elapsed());
+        }
+        Ok(data)
     }
 
     fn delete(&self, path: &Path) -> result::Result<(), DeleteError> {
@@ -312,6 +437,9 @@ impl Directory for ManagedDirectory {
     }
 
     fn acquire_lock(&self, lock: &Lock) -> result::Result<DirectoryLock, LockError> {
+        if let Some(lock_manager) = &self.lock_manager {
+            return lock_manager.acquire_lock(lock);
+        }
         self.directory.acquire_lock(lock)
     }
 
@@ -323,6 +451,14 @@ impl Directory for ManagedDirectory {
         self.directory.sync_directory()?;
         Ok(())
     }
+
+    fn advise(&self, path: &Path, hint: AccessHint) -> io::Result<()> {
+        self.directory.advise(path, hint)
+    }
+
+    fn local_path(&self, path: &Path) -> Option<PathBuf> {
+        self.directory.local_path(path)
+    }
 }
 
 impl Clone for ManagedDirectory {
@@ -330,6 +466,8 @@ impl Clone for ManagedDirectory {
         ManagedDirectory {
             directory: self.directory.box_clone(),
             meta_informations: Arc::clone(&self.meta_informations),
+            lock_manager: self.lock_manager.clone(),
+            io_metrics: self.io_metrics.clone(),
         }
     }
 }
@@ -1,4 +1,4 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::fmt;
 use std::fs::{self, File, OpenOptions};
 use std::io::{self, BufWriter, Read, Write};
@@ -7,10 +7,11 @@ use std::path::{Path, PathBuf};
 use std::sync::{Arc, RwLock, Weak};
 
 use common::StableDeref;
-use fs4::FileExt;
 #[cfg(all(feature = "mmap", unix))]
 pub use memmap2::Advice;
 use memmap2::Mmap;
+#[cfg(unix)]
+use memmap2::UncheckedAdvice;
 use serde::{Deserialize, Serialize};
 use tempfile::TempDir;
 
@@ -19,14 +20,14 @@ use crate::directory::error::{
     DeleteError, LockError, OpenDirectoryError, OpenReadError, OpenWriteError,
 };
 use crate::directory::file_watcher::FileWatcher;
+#[cfg(unix)]
+use crate::directory::AccessHint;
 use crate::directory::{
-    AntiCallToken, Directory, DirectoryLock, FileHandle, Lock, OwnedBytes, TerminatingWrite,
-    WatchCallback, WatchHandle, WritePtr,
+    touch_pages, AdvisoryLockManager, AntiCallToken, Directory, DirectoryLock, FileHandle,
+    FileKind, Lock, LockManager, OwnedBytes, TerminatingWrite, WatchCallback, WatchHandle,
+    WritePtr,
 };
 
-pub type ArcBytes = Arc<dyn Deref<Target = [u8]> + Send + Sync + 'static>;
-pub type WeakArcBytes = Weak<dyn Deref<Target = [u8]> + Send + Sync + 'static>;
-
 /// Create a default io error given a string.
 pub(crate) fn make_io_err(msg: String) -> io::Error {
     io::Error::new(io::ErrorKind::Other, msg)
@@ -68,6 +69,10 @@ pub struct CacheCounters {
     /// Number of time tantivy had to call `mmap`
     /// as no entry was in the cache.
     pub miss: usize,
+    /// Number of files whose pages were loaded after being mmapped,
+    /// as per [`MmapDirectory::set_populate_for_file_kind`].
+    #[serde(default)]
+    pub populated: usize,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -78,9 +83,12 @@ pub struct CacheInfo {
 
 struct MmapCache {
     counters: CacheCounters,
-    cache: HashMap<PathBuf, WeakArcBytes>,
+    cache: HashMap<PathBuf, Weak<Mmap>>,
     #[cfg(unix)]
     madvice_opt: Option<Advice>,
+    #[cfg(unix)]
+    madvice_per_file_kind: HashMap<FileKind, Advice>,
+    populated_file_kinds: HashSet<FileKind>,
 }
 
 impl MmapCache {
@@ -90,6 +98,9 @@ impl MmapCache {
             cache: HashMap::default(),
             #[cfg(unix)]
             madvice_opt: None,
+            #[cfg(unix)]
+            madvice_per_file_kind: HashMap::default(),
+            populated_file_kinds: HashSet::default(),
         }
     }
 
@@ -98,6 +109,39 @@ impl MmapCache {
         self.madvice_opt = Some(madvice);
     }
 
+    /// Returns the advice applied to the file when it is mmapped.
+    #[cfg(unix)]
+    fn advice_for(&self, full_path: &Path) -> Option<Advice> {
+        self.madvice_per_file_kind
+            .get(&FileKind::for_path(full_path))
+            .copied()
+            .or(self.madvice_opt)
+    }
+
+    #[cfg(unix)]
+    fn advise(&mut self, full_path: &Path, hint: AccessHint) -> Result<(), OpenReadError> {
+        let mmap_opt = if hint == AccessHint::WillNeed {
+            self.get_mmap(full_path)?
+        } else {
+            // There is nothing to do if the file is not mmapped.
+            self.cache.get(full_path).and_then(Weak::upgrade)
+        };
+        let Some(mmap) = mmap_opt else {
+            return Ok(());
+        };
+        let advise_res = match hint {
+            AccessHint::Normal => mmap.advise(self.advice_for(full_path).unwrap_or(Advice::Normal)),
+            AccessHint::Random => mmap.advise(Advice::Random),
+            AccessHint::Sequential => mmap.advise(Advice::Sequential),
+            AccessHint::WillNeed => mmap.advise(Advice::WillNeed),
+            // SAFETY: The file is mapped read-only and is never modified, so dropping its
+            // pages from the mapping cannot lose data: they are read again from the file
+            // on the next access.
+            AccessHint::DontNeed => unsafe { mmap.unchecked_advise(UncheckedAdvice::DontNeed) },
+        };
+        advise_res.map_err(|io_err| OpenReadError::wrap_io_error(io_err, full_path.to_path_buf()))
+    }
+
     fn get_info(&self) -> CacheInfo {
         let paths: Vec<PathBuf> = self.cache.keys().cloned().collect();
         CacheInfo {
@@ -121,7 +165,7 @@ impl MmapCache {
     fn open_mmap_impl(&self, full_path: &Path) -> Result<Option<Mmap>, OpenReadError> {
         let mmap_opt = open_mmap(full_path)?;
         #[cfg(unix)]
-        if let (Some(mmap), Some(madvice)) = (mmap_opt.as_ref(), self.madvice_opt) {
+        if let (Some(mmap), Some(madvice)) = (mmap_opt.as_ref(), self.advice_for(full_path)) {
             // We ignore madvise errors.
             let _ = mmap.advise(madvice);
         }
@@ -129,25 +173,52 @@ impl MmapCache {
     }
 
     // Returns None if the file exists but as a len of 0 (and hence is not mmappable).
-    fn get_mmap(&mut self, full_path: &Path) -> Result<Option<ArcBytes>, OpenReadError> {
+    fn get_mmap(&mut self, full_path: &Path) -> Result<Option<Arc<Mmap>>, OpenReadError> {
+        Ok(self.get_mmap_to_populate(full_path)?.0)
+    }
+
+    /// Same as [`MmapCache::get_mmap`], also returning whether the file was just mmapped and
+    /// its pages should be loaded.
+    ///
+    /// Loading them is left to the caller, so that it does not hold the lock of the cache
+    /// in the meantime.
+    fn get_mmap_to_populate(
+        &mut self,
+        full_path: &Path,
+    ) -> Result<(Option<Arc<Mmap>>, bool), OpenReadError> {
         if let Some(mmap_weak) = self.cache.get(full_path) {
             if let Some(mmap_arc) = mmap_weak.upgrade() {
                 self.counters.hit += 1;
-                return Ok(Some(mmap_arc));
+                return Ok((Some(mmap_arc), false));
             }
         }
         self.cache.remove(full_path);
         self.counters.miss += 1;
-        let mmap_opt = self.open_mmap_impl(full_path)?;
-        Ok(mmap_opt.map(|mmap| {
-            let mmap_arc: ArcBytes = Arc::new(mmap);
-            let mmap_weak = Arc::downgrade(&mmap_arc);
-            self.cache.insert(full_path.to_owned(), mmap_weak);
-            mmap_arc
-        }))
+        let Some(mmap) = self.open_mmap_impl(full_path)? else {
+            return Ok((None, false));
+        };
+        let mmap_arc = Arc::new(mmap);
+        let mmap_weak = Arc::downgrade(&mmap_arc);
+        self.cache.insert(full_path.to_owned(), mmap_weak);
+        let populate = self
+            .populated_file_kinds
+            .contains(&FileKind::for_path(full_path));
+        if populate {
+            self.counters.populated += 1;
+        }
+        Ok((Some(mmap_arc), populate))
     }
 }
 
+/// Loads the pages of a file that was just mmapped.
+fn populate_mmap(mmap: &Mmap) {
+    // Asking for the whole file first lets the kernel read it in large chunks,
+    // instead of one page per fault.
+    #[cfg(unix)]
+    let _ = mmap.advise(Advice::WillNeed);
+    touch_pages(mmap);
+}
+
 /// Directory storing data in files, read via mmap.
 ///
 /// The Mmap object are cached to limit the
@@ -227,6 +298,40 @@ impl MmapDirectory {
         Ok(dir)
     }
 
+    /// Sets the access pattern of a given kind of files, overriding the one given in
+    /// [`MmapDirectory::open_with_madvice`].
+    ///
+    /// The advice is applied to the files mmapped from then on. Search workloads typically
+    /// benefit from [`Advice::Random`] for the term dictionaries and postings lists, as
+    /// read-ahead mostly loads pages that will not be read.
+    ///
+    /// This is only supported on unix platforms.
+    #[cfg(unix)]
+    pub fn set_madvice_for_file_kind(&self, file_kind: FileKind, madvice: Advice) {
+        self.inner
+            .mmap_cache
+            .write()
+            .expect("mmap cache lock is poisoned")
+            .madvice_per_file_kind
+            .insert(file_kind, madvice);
+    }
+
+    /// Loads the files of a given kind in memory when they are mmapped, instead of on the
+    /// first access to each of their pages.
+    ///
+    /// Files are mmapped when a segment is opened, so the page faults are taken when the
+    /// searcher is (re)loaded rather than by the first queries. This is mostly useful for the
+    /// small and randomly accessed files, such as the term dictionaries and the fieldnorms, as
+    /// loading the postings or the doc store of a large index can take a while.
+    pub fn set_populate_for_file_kind(&self, file_kind: FileKind) {
+        self.inner
+            .mmap_cache
+            .write()
+            .expect("mmap cache lock is poisoned")
+            .populated_file_kinds
+            .insert(file_kind);
+    }
+
     /// Opens a MmapDirectory in a directory.
     ///
     /// Returns an error if the `directory_path` does not
@@ -293,21 +398,6 @@ impl MmapDirectory {
     }
 }
 
-/// We rely on fs2 for file locking. On Windows & MacOS this
-/// uses BSD locks (`flock`). The lock is actually released when
-/// the `File` object is dropped and its associated file descriptor
-/// is closed.
-struct ReleaseLockFile {
-    _file: File,
-    path: PathBuf,
-}
-
-impl Drop for ReleaseLockFile {
-    fn drop(&mut self) {
-        debug!("Releasing lock {:?}", self.path);
-    }
-}
-
 /// This Write wraps a File, but has the specificity of
 /// call `sync_all` on flush.
 struct SafeFileWriter(File);
@@ -372,14 +462,24 @@ impl Directory for MmapDirectory {
         debug!("Open Read {:?}", path);
         let full_path = self.resolve_path(path);
 
-        let mut mmap_cache = self.inner.mmap_cache.write().map_err(|_| {
-            let msg = format!("Failed to acquired write lock on mmap cache while reading {path:?}");
-            let io_err = make_io_err(msg);
-            OpenReadError::wrap_io_error(io_err, path.to_path_buf())
-        })?;
+        let (mmap_opt, populate) = self
+            .inner
+            .mmap_cache
+            .write()
+            .map_err(|_| {
+                let msg =
+                    format!("Failed to acquired write lock on mmap cache while reading {path:?}");
+                let io_err = make_io_err(msg);
+                OpenReadError::wrap_io_error(io_err, path.to_path_buf())
+            })?
+            .get_mmap_to_populate(&full_path)?;
+
+        // The lock is released: other files can be opened while the pages are loaded.
+        if let (Some(mmap), true) = (mmap_opt.as_ref(), populate) {
+            populate_mmap(mmap);
+        }
 
-        let owned_bytes = mmap_cache
-            .get_mmap(&full_path)?
+        let owned_bytes = mmap_opt
             .map(|mmap_arc| {
                 let mmap_arc_obj = MmapArc(mmap_arc);
                 OwnedBytes::new(mmap_arc_obj)
@@ -474,24 +574,7 @@ impl Directory for MmapDirectory {
     }
 
     fn acquire_lock(&self, lock: &Lock) -> Result<DirectoryLock, LockError> {
-        let full_path = self.resolve_path(&lock.filepath);
-        // We make sure that the file exists.
-        let file: File = OpenOptions::new()
-            .write(true)
-            .create(true) //< if the file does not exist yet, create it.
-            .truncate(false)
-            .open(full_path)
-            .map_err(LockError::wrap_io_error)?;
-        if lock.is_blocking {
-            file.lock_exclusive().map_err(LockError::wrap_io_error)?;
-        } else {
-            file.try_lock_exclusive().map_err(|_| LockError::LockBusy)?
-        }
-        // dropping the file handle will release the lock.
-        Ok(DirectoryLock::from(Box::new(ReleaseLockFile {
-            path: lock.filepath.clone(),
-            _file: file,
-        })))
+        AdvisoryLockManager::new(self.inner.root_path.clone()).acquire_lock(lock)
     }
 
     fn watch(&self, watch_callback: WatchCallback) -> crate::Result<WatchHandle> {
@@ -519,6 +602,24 @@ impl Directory for MmapDirectory {
         fd.sync_data()?;
         Ok(())
     }
+
+    #[cfg(unix)]
+    fn advise(&self, path: &Path, hint: AccessHint) -> io::Result<()> {
+        let full_path = self.resolve_path(path);
+        let mut mmap_cache = self.inner.mmap_cache.write().map_err(|_| {
+            make_io_err(format!(
+                "Failed to acquired write lock on mmap cache while advising {path:?}"
+            ))
+        })?;
+        match mmap_cache.advise(&full_path, hint) {
+            Ok(()) | Err(OpenReadError::FileDoesNotExist(_)) => Ok(()),
+            Err(err) => Err(io::Error::new(io::ErrorKind::Other, err)),
+        }
+    }
+
+    fn local_path(&self, path: &Path) -> Option<PathBuf> {
+        Some(self.resolve_path(path))
+    }
 }
 
 #[cfg(test)]
@@ -698,4 +799,97 @@ mod tests {
             }
         });
     }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_advise() -> crate::Result<()> {
+        let mmap_directory = MmapDirectory::create_from_tempdir()?;
+        mmap_directory.set_madvice_for_file_kind(FileKind::Postings, Advice::Random);
+        let path = Path::new("segment.idx");
+        let mut write = mmap_directory.open_write(path)?;
+        write.write_all(&[1u8; 10_000])?;
+        write.terminate()?;
+
+        // Advising a file that is not mmapped is a no-op, except for `WillNeed`.
+        mmap_directory.advise(path, AccessHint::Sequential)?;
+        assert_eq!(mmap_directory.get_cache_info().counters.miss, 0);
+        mmap_directory.advise(path, AccessHint::WillNeed)?;
+        assert_eq!(mmap_directory.get_cache_info().counters.miss, 1);
+
+        let file_slice = mmap_directory.open_read(path)?;
+        for hint in [
+            AccessHint::Random,
+            AccessHint::Sequential,
+            AccessHint::WillNeed,
+            AccessHint::DontNeed,
+            AccessHint::Normal,
+        ] {
+            mmap_directory.advise(path, hint)?;
+        }
+        // The content is still there after the pages were dropped.
+        assert_eq!(file_slice.read_bytes()?.as_slice(), &[1u8; 10_000][..]);
+        mmap_directory.advise(Path::new("missing.idx"), AccessHint::WillNeed)?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_populate_for_file_kind() -> crate::Result<()> {
+        let mut schema_builder = Schema::builder();
+        let text_field = schema_builder.add_text_field("text", TEXT);
+        let mmap_directory = MmapDirectory::create_from_tempdir()?;
+        mmap_directory.set_populate_for_file_kind(FileKind::Terms);
+        mmap_directory.set_populate_for_file_kind(FileKind::FieldNorms);
+        let index = Index::create(
+            mmap_directory.clone(),
+            schema_builder.build(),
+            IndexSettings::default(),
+        )?;
+        let mut index_writer: IndexWriter = index.writer_for_tests()?;
+        index_writer.add_document(doc!(text_field => "hello happy tax payer"))?;
+        index_writer.commit()?;
+        let searcher = index.reader()?.searcher();
+        let term_query = crate::query::TermQuery::new(
+            crate::Term::from_field_text(text_field, "happy"),
+            crate::schema::IndexRecordOption::Basic,
+        );
+        assert_eq!(searcher.search(&term_query, &crate::collector::Count)?, 1);
+        // Only the term dictionary and the fieldnorms of the segment were populated.
+        let cache_info = mmap_directory.get_cache_info();
+        let num_populated_files = cache_info
+            .mmapped
+            .iter()
+            .filter(|path| {
+                matches!(
+                    FileKind::for_path(path),
+                    FileKind::Terms | FileKind::FieldNorms
+                )
+            })
+            .count();
+        assert_eq!(num_populated_files, 2);
+        assert_eq!(cache_info.counters.populated, num_populated_files);
+        Ok(())
+    }
+
+    #[test]
+    fn test_searcher_warm_and_evict() -> crate::Result<()> {
+        let mut schema_builder = Schema::builder();
+        let text_field = schema_builder.add_text_field("text", TEXT);
+        let index = Index::create(
+            MmapDirectory::create_from_tempdir()?,
+            schema_builder.build(),
+            IndexSettings::default(),
+        )?;
+        let mut index_writer: IndexWriter = index.writer_for_tests()?;
+        index_writer.add_document(doc!(text_field => "hello happy tax payer"))?;
+        index_writer.commit()?;
+        let searcher = index.reader()?.searcher();
+        searcher.warm(&crate::WarmupSpec::all(searcher.schema()))?;
+        searcher.evict()?;
+        let term_query = crate::query::TermQuery::new(
+            crate::Term::from_field_text(text_field, "happy"),
+            crate::schema::IndexRecordOption::Basic,
+        );
+        assert_eq!(searcher.search(&term_query, &crate::collector::Count)?, 1);
+        Ok(())
+    }
 }
@@ -3,12 +3,23 @@
 #[cfg(feature = "mmap")]
 mod mmap_directory;
 
+mod caching_directory;
+mod checksum_directory;
+mod compressed_directory;
 mod directory;
 mod directory_lock;
 mod file_watcher;
 mod footer;
+mod hot_directory;
+mod http_directory;
+mod io_metrics;
+#[cfg(all(feature = "io-uring", target_os = "linux"))]
+mod io_uring_directory;
+mod lock_manager;
 mod managed_directory;
+mod quota_directory;
 mod ram_directory;
+mod tiered_directory;
 mod watch_event_router;
 
 /// Errors specific to the directory module.
@@ -22,10 +33,25 @@ use std::path::PathBuf;
 pub use common::file_slice::{FileHandle, FileSlice};
 pub use common::{AntiCallToken, OwnedBytes, TerminatingWrite};
 
+pub use self::caching_directory::{CachingDirectory, SharedBlockCache};
+pub use self::checksum_directory::ChecksumDirectory;
 pub(crate) use self::composite_file::{CompositeFile, CompositeWrite};
-pub use self::directory::{Directory, DirectoryClone, DirectoryLock};
+pub use self::compressed_directory::CompressedDirectory;
+pub use self::directory::{AccessHint, Directory, DirectoryClone, DirectoryLock};
 pub use self::directory_lock::{Lock, INDEX_WRITER_LOCK, META_LOCK};
+pub(crate) use self::hot_directory::RecordingDirectory;
+pub use self::hot_directory::{HotDirectory, HotcacheFile, HotcacheManifest};
+#[cfg(feature = "http-directory")]
+pub use self::http_directory::HttpRangeClient;
+pub use self::http_directory::{HttpDirectory, RangeClient};
+pub(crate) use self::io_metrics::MeteredWrite;
+pub use self::io_metrics::{FileKind, FileKindIoStats, IoMetrics, IoStats};
+#[cfg(feature = "mmap")]
+pub use self::lock_manager::AdvisoryLockManager;
+pub use self::lock_manager::{ExclusiveFileLockManager, LockManager};
+pub use self::quota_directory::{QuotaDirectory, QuotaExceeded};
 pub use self::ram_directory::RamDirectory;
+pub use self::tiered_directory::{Tier, TieredDirectory, TieringPolicy, TieringReport};
 pub use self::watch_event_router::{WatchCallback, WatchCallbackList, WatchHandle};
 
 /// Outcome of the Garbage collection
@@ -45,7 +71,20 @@ pub struct GarbageCollectionResult {
 #[cfg(all(feature = "mmap", unix))]
 pub use memmap2::Advice;
 
+/// Reads a byte of every page of `bytes`, so that the pages of a memory mapped file are loaded.
+pub(crate) fn touch_pages(bytes: &[u8]) {
+    const PAGE_NUM_BYTES: usize = 4_096;
+    let checksum = bytes
+        .iter()
+        .step_by(PAGE_NUM_BYTES)
+        .fold(0u8, |checksum, &byte| checksum ^ byte);
+    std::hint::black_box(checksum);
+}
+
+#[cfg(all(feature = "io-uring", target_os = "linux"))]
+pub use self::io_uring_directory::IoUringDirectory;
 pub use self::managed_directory::ManagedDirectory;
+pub(crate) use self::managed_directory::{copy_file, file_checksum, write_managed_paths};
 #[cfg(feature = "mmap")]
 pub use self::mmap_directory::MmapDirectory;
 
@@ -0,0 +1,380 @@
+use std::collections::{HashMap, HashSet};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::{error, fmt};
+
+use common::HasLen;
+
+use crate::core::MANAGED_FILEPATH;
+use crate::directory::error::{DeleteError, LockError, OpenReadError, OpenWriteError};
+use crate::directory::{
+    AccessHint, AntiCallToken, Directory, DirectoryLock, FileHandle, Lock, TerminatingWrite,
+    WatchCallback, WatchHandle, WritePtr,
+};
+use crate::error::DataCorruption;
+
+type QuotaCallback = Arc<dyn Fn(&QuotaExceeded) + Send + Sync>;
+
+/// Error returned when a write would exceed the quota of a [`QuotaDirectory`].
+///
+/// It is wrapped into an [`io::Error`], and can be retrieved by downcasting
+/// the error's inner error.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QuotaExceeded {
+    /// File being written.
+    pub path: PathBuf,
+    /// Number of bytes the directory would use after the write.
+    pub num_bytes: u64,
+    /// Maximum number of bytes allowed.
+    pub max_num_bytes: u64,
+}
+
+impl fmt::Display for QuotaExceeded {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Writing {:?} would exceed the directory quota ({} > {} bytes)",
+            self.path, self.num_bytes, self.max_num_bytes
+        )
+    }
+}
+
+impl error::Error for QuotaExceeded {}
+
+struct QuotaState {
+    max_num_bytes: u64,
+    num_bytes: u64,
+    file_sizes: HashMap<PathBuf, u64>,
+    enforce: bool,
+    on_exceeded: Option<QuotaCallback>,
+}
+
+#[derive(Clone)]
+struct Quota(Arc<Mutex<QuotaState>>);
+
+impl Quota {
+    fn state(&self) -> MutexGuard<'_, QuotaState> {
+        self.0.lock().unwrap()
+    }
+
+    /// Updates the size of the file at `path`, if the quota allows it.
+    ///
+    /// Shrinking a file is always allowed.
+    fn set_file_size(&self, path: &Path, file_size: impl FnOnce(u64) -> u64) -> io::Result<()> {
+        let (quota_exceeded, on_exceeded, enforce) = {
+            let mut state = self.state();
+            let previous_file_size = state.file_sizes.get(path).copied().unwrap_or(0);
+            let new_file_size = file_size(previous_file_size);
+            let new_num_bytes = state.num_bytes - previous_file_size + new_file_size;
+            let exceeds_quota =
+                new_file_size > previous_file_size && new_num_bytes > state.max_num_bytes;
+            if !exceeds_quota || !state.enforce {
+                state.num_bytes = new_num_bytes;
+                state.file_sizes.insert(path.to_path_buf(), new_file_size);
+            }
+            if !exceeds_quota {
+                return Ok(());
+            }
+            let quota_exceeded = QuotaExceeded {
+                path: path.to_path_buf(),
+                num_bytes: new_num_bytes,
+                max_num_bytes: state.max_num_bytes,
+            };
+            (quota_exceeded, state.on_exceeded.clone(), state.enforce)
+        };
+        // The callback is called without holding the lock, so that it can inspect the quota.
+        if let Some(on_exceeded) = on_exceeded {
+            on_exceeded(&quota_exceeded);
+        }
+        if enforce {
+            return Err(io::Error::new(io::ErrorKind::Other, quota_exceeded));
+        }
+        Ok(())
+    }
+
+    fn remove(&self, path: &Path) {
+        let mut state = self.state();
+        if let Some(file_size) = state.file_sizes.remove(path) {
+            state.num_bytes -= file_size;
+        }
+    }
+}
+
+/// Writer accounting for every byte written against the quota.
+struct QuotaWriter {
+    path: PathBuf,
+    underlying: WritePtr,
+    quota: Quota,
+}
+
+impl Write for QuotaWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let num_bytes = buf.len() as u64;
+        self.quota
+            .set_file_size(&self.path, |file_size| file_size + num_bytes)?;
+        self.underlying.write_all(buf)?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.underlying.flush()
+    }
+}
+
+impl TerminatingWrite for QuotaWriter {
+    fn terminate_ref(&mut self, token: AntiCallToken) -> io::Result<()> {
+        self.underlying.terminate_ref(token)
+    }
+}
+
+/// Directory wrapper enforcing a ceiling on the number of bytes stored in the directory.
+///
+/// Once the ceiling is reached, writes fail with an [`io::Error`] wrapping a
+/// [`QuotaExceeded`] error. Deletes are always allowed, so that an index over quota can
+/// still be merged and garbage collected.
+///
+/// Optionally, a callback can be called whenever a write exceeds the quota. Combined
+/// with [`QuotaDirectory::set_enforce(false)`](QuotaDirectory::set_enforce), this makes
+/// it possible to only monitor the disk usage, without failing writes.
+///
+/// The quota is shared between the clones of a `QuotaDirectory`.
+#[derive(Clone)]
+pub struct QuotaDirectory {
+    underlying: Box<dyn Directory>,
+    quota: Quota,
+}
+
+impl fmt::Debug for QuotaDirectory {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "QuotaDirectory({:?}, {}/{} bytes)",
+            self.underlying,
+            self.num_bytes(),
+            self.max_num_bytes()
+        )
+    }
+}
+
+impl QuotaDirectory {
+    /// Wraps a directory, allowing it to hold at most `max_num_bytes` bytes.
+    ///
+    /// If the directory already contains an index, the size of the files listed in
+    /// its `.managed.json` file is accounted for.
+    pub fn wrap<D: Into<Box<dyn Directory>>>(
+        underlying: D,
+        max_num_bytes: u64,
+    ) -> crate::Result<QuotaDirectory> {
+        let underlying = underlying.into();
+        let mut file_sizes = HashMap::new();
+        match underlying.atomic_read(&MANAGED_FILEPATH) {
+            Ok(data) => {
+                let managed_paths: HashSet<PathBuf> =
+                    serde_json::from_slice(&data).map_err(|err| {
+                        DataCorruption::new(
+                            MANAGED_FILEPATH.to_path_buf(),
+                            format!("Managed file cannot be deserialized: {err:?}."),
+                        )
+                    })?;
+                file_sizes.insert(MANAGED_FILEPATH.to_path_buf(), data.len() as u64);
+                for path in managed_paths {
+                    match underlying.get_file_handle(&path) {
+                        Ok(file_handle) => {
+                            file_sizes.insert(path, file_handle.len() as u64);
+                        }
+                        Err(OpenReadError::FileDoesNotExist(_)) => {}
+                        Err(err) => return Err(err.into()),
+                    }
+                }
+            }
+            Err(OpenReadError::FileDoesNotExist(_)) => {}
+            Err(err) => return Err(err.into()),
+        }
+        let num_bytes = file_sizes.values().sum();
+        Ok(QuotaDirectory {
+            underlying,
+            quota: Quota(Arc::new(Mutex::new(QuotaState {
+                max_num_bytes,
+                num_bytes,
+                file_sizes,
+                enforce: true,
+                on_exceeded: None,
+            }))),
+        })
+    }
+
+    /// Sets whether writes exceeding the quota should fail. Defaults to true.
+    #[must_use]
+    pub fn set_enforce(self, enforce: bool) -> QuotaDirectory {
+        self.quota.state().enforce = enforce;
+        self
+    }
+
+    /// Sets a callback, called on every write exceeding the quota.
+    #[must_use]
+    pub fn set_on_exceeded(
+        self,
+        on_exceeded: impl Fn(&QuotaExceeded) + Send + Sync + 'static,
+    ) -> QuotaDirectory {
+        self.quota.state().on_exceeded = Some(Arc::new(on_exceeded));
+        self
+    }
+
+    /// Returns the number of bytes currently used by the directory.
+    pub fn num_bytes(&self) -> u64 {
+        self.quota.state().num_bytes
+    }
+
+    /// Returns the maximum number of bytes the directory can hold.
+    pub fn max_num_bytes(&self) -> u64 {
+        self.quota.state().max_num_bytes
+    }
+
+    /// Changes the maximum number of bytes the directory can hold.
+    ///
+    /// Lowering the ceiling below the current usage does not remove any file,
+    /// but all subsequent writes will fail until enough files are deleted.
+    pub fn set_max_num_bytes(&self, max_num_bytes: u64) {
+        self.quota.state().max_num_bytes = max_num_bytes;
+    }
+}
+
+impl Directory for QuotaDirectory {
+    fn get_file_handle(&self, path: &Path) -> Result<Arc<dyn FileHandle>, OpenReadError> {
+        self.underlying.get_file_handle(path)
+    }
+
+    fn delete(&self, path: &Path) -> Result<(), DeleteError> {
+        self.underlying.delete(path)?;
+        self.quota.remove(path);
+        Ok(())
+    }
+
+    fn exists(&self, path: &Path) -> Result<bool, OpenReadError> {
+        self.underlying.exists(path)
+    }
+
+    fn open_write(&self, path: &Path) -> Result<WritePtr, OpenWriteError> {
+        let underlying = self.underlying.open_write(path)?;
+        Ok(io::BufWriter::new(Box::new(QuotaWriter {
+            path: path.to_path_buf(),
+            underlying,
+            quota: self.quota.clone(),
+        })))
+    }
+
+    fn atomic_read(&self, path: &Path) -> Result<Vec<u8>, OpenReadError> {
+        self.underlying.atomic_read(path)
+    }
+
+    fn atomic_write(&self, path: &Path, data: &[u8]) -> io::Result<()> {
+        self.quota.set_file_size(path, |_| data.len() as u64)?;
+        self.underlying.atomic_write(path, data)
+    }
+
+    fn sync_directory(&self) -> io::Result<()> {
+        self.underlying.sync_directory()
+    }
+
+    fn advise(&self, path: &Path, hint: AccessHint) -> io::Result<()> {
+        self.underlying.advise(path, hint)
+    }
+
+    fn acquire_lock(&self, lock: &Lock) -> Result<DirectoryLock, LockError> {
+        self.underlying.acquire_lock(lock)
+    }
+
+    fn watch(&self, watch_callback: WatchCallback) -> crate::Result<WatchHandle> {
+        self.underlying.watch(watch_callback)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+    use std::path::Path;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    use super::{QuotaDirectory, QuotaExceeded};
+    use crate::directory::{Directory, RamDirectory, TerminatingWrite};
+    use crate::schema::{Schema, TEXT};
+    use crate::{Index, IndexWriter};
+
+    fn write_file(directory: &dyn Directory, path: &Path, num_bytes: usize) -> std::io::Result<()> {
+        let mut wrt = directory.open_write(path).unwrap();
+        wrt.write_all(&vec![0u8; num_bytes])?;
+        wrt.terminate()
+    }
+
+    #[test]
+    fn test_quota_directory_rejects_writes() -> crate::Result<()> {
+        let directory = QuotaDirectory::wrap(RamDirectory::create(), 100)?;
+        write_file(&directory, Path::new("a"), 60)?;
+        assert_eq!(directory.num_bytes(), 60);
+        let io_error = write_file(&directory, Path::new("b"), 60).unwrap_err();
+        let quota_exceeded = io_error
+            .get_ref()
+            .and_then(|err| err.downcast_ref::<QuotaExceeded>())
+            .unwrap();
+        assert_eq!(quota_exceeded.max_num_bytes, 100);
+        assert_eq!(quota_exceeded.num_bytes, 120);
+
+        directory.delete(Path::new("a"))?;
+        assert_eq!(directory.num_bytes(), 0);
+        write_file(&directory, Path::new("c"), 60)?;
+        directory.atomic_write(Path::new("meta"), &[0u8; 30])?;
+        directory.atomic_write(Path::new("meta"), &[0u8; 40])?;
+        assert_eq!(directory.num_bytes(), 100);
+        assert!(directory
+            .atomic_write(Path::new("meta"), &[0u8; 41])
+            .is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_quota_directory_soft_limit() -> crate::Result<()> {
+        let num_exceeded = Arc::new(AtomicUsize::new(0));
+        let num_exceeded_clone = num_exceeded.clone();
+        let directory = QuotaDirectory::wrap(RamDirectory::create(), 100)?
+            .set_enforce(false)
+            .set_on_exceeded(move |_| {
+                num_exceeded_clone.fetch_add(1, Ordering::SeqCst);
+            });
+        write_file(&directory, Path::new("a"), 60)?;
+        assert_eq!(num_exceeded.load(Ordering::SeqCst), 0);
+        write_file(&directory, Path::new("b"), 60)?;
+        assert_eq!(num_exceeded.load(Ordering::SeqCst), 1);
+        assert_eq!(directory.num_bytes(), 120);
+        Ok(())
+    }
+
+    #[test]
+    fn test_quota_directory_index() -> crate::Result<()> {
+        let mut schema_builder = Schema::builder();
+        let text = schema_builder.add_text_field("text", TEXT);
+        let ram_directory = RamDirectory::create();
+        let directory = QuotaDirectory::wrap(ram_directory.clone(), 1_000_000)?;
+        let index = Index::create(
+            directory.clone(),
+            schema_builder.build(),
+            Default::default(),
+        )?;
+        let mut writer: IndexWriter = index.writer_for_tests()?;
+        writer.add_document(doc!(text => "hello"))?;
+        writer.commit()?;
+        let num_bytes = directory.num_bytes();
+        assert!(num_bytes > 0);
+        drop(writer);
+
+        // The usage of an existing index is accounted for on open.
+        let reopened_directory = QuotaDirectory::wrap(ram_directory, num_bytes)?;
+        assert_eq!(reopened_directory.num_bytes(), num_bytes);
+        let index = Index::open(reopened_directory)?;
+        let mut writer: IndexWriter = index.writer_for_tests()?;
+        writer.add_document(doc!(text => "happy"))?;
+        assert!(writer.commit().is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,561 @@
+use std::collections::{HashMap, HashSet};
+use std::io::{self, BufWriter, Write};
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::Duration;
+
+use common::HasLen;
+
+use crate::directory::error::{DeleteError, LockError, OpenReadError, OpenWriteError};
+use crate::directory::{
+    AntiCallToken, Directory, DirectoryLock, FileHandle, FileSlice, Lock, OwnedBytes,
+    TerminatingWrite, WatchCallback, WatchHandle, WritePtr,
+};
+use crate::Instant;
+
+/// Storage tier of a file in a [`TieredDirectory`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Tier {
+    /// Fast storage (e.g. local SSD).
+    Hot,
+    /// Slow storage (e.g. network attached storage).
+    Cold,
+}
+
+impl Tier {
+    fn other(self) -> Tier {
+        match self {
+            Tier::Hot => Tier::Cold,
+            Tier::Cold => Tier::Hot,
+        }
+    }
+}
+
+/// Policy deciding when files move from one tier to the other.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TieringPolicy {
+    /// Hot files younger than `min_age` are never demoted.
+    pub min_age: Duration,
+    /// Hot files that have not been read for `max_idle` are demoted.
+    pub max_idle: Duration,
+    /// Cold files that have been read at least `promotion_num_reads` times
+    /// since they were demoted are promoted back to the hot tier.
+    ///
+    /// `0` disables promotion.
+    pub promotion_num_reads: u64,
+}
+
+impl Default for TieringPolicy {
+    fn default() -> Self {
+        TieringPolicy {
+            min_age: Duration::from_secs(3_600),
+            max_idle: Duration::from_secs(3_600),
+            promotion_num_reads: 100,
+        }
+    }
+}
+
+/// Files that were moved by [`TieredDirectory::rebalance`].
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct TieringReport {
+    /// Files moved from the cold tier to the hot tier.
+    pub promoted: Vec<PathBuf>,
+    /// Files moved from the hot tier to the cold tier.
+    pub demoted: Vec<PathBuf>,
+}
+
+/// Access statistics of a file.
+struct FileStats {
+    created: Instant,
+    /// Last access, in milliseconds since `created`.
+    last_access_ms: AtomicU64,
+    /// Number of reads since the file last changed tier.
+    num_reads: AtomicU64,
+}
+
+impl FileStats {
+    fn new() -> FileStats {
+        FileStats {
+            created: Instant::now(),
+            last_access_ms: AtomicU64::new(0),
+            num_reads: AtomicU64::new(0),
+        }
+    }
+
+    fn record_read(&self) {
+        let elapsed_ms = self.created.elapsed().as_millis() as u64;
+        self.last_access_ms.fetch_max(elapsed_ms, Ordering::Relaxed);
+        self.num_reads.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn age(&self) -> Duration {
+        self.created.elapsed()
+    }
+
+    fn idle(&self) -> Duration {
+        let last_access = Duration::from_millis(self.last_access_ms.load(Ordering::Relaxed));
+        self.age().saturating_sub(last_access)
+    }
+}
+
+struct FileEntry {
+    tier: Tier,
+    stats: Arc<FileStats>,
+}
+
+/// File handle recording the reads in the file statistics.
+struct TrackedFileHandle {
+    file_slice: FileSlice,
+    stats: Arc<FileStats>,
+}
+
+impl std::fmt::Debug for TrackedFileHandle {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "TrackedFileHandle({:?})", self.file_slice)
+    }
+}
+
+impl HasLen for TrackedFileHandle {
+    fn len(&self) -> usize {
+        self.file_slice.len()
+    }
+}
+
+impl FileHandle for TrackedFileHandle {
+    fn read_bytes(&self, range: Range<usize>) -> io::Result<OwnedBytes> {
+        self.stats.record_read();
+        self.file_slice.read_bytes_slice(range)
+    }
+}
+
+/// Writer of a new hot file, registering it in the tiered directory once it is terminated.
+struct TieredWriter {
+    underlying: WritePtr,
+    path: PathBuf,
+    files: Arc<RwLock<HashMap<PathBuf, FileEntry>>>,
+    writing: Arc<Mutex<HashSet<PathBuf>>>,
+}
+
+impl Write for TieredWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.underlying.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.underlying.flush()
+    }
+}
+
+impl TerminatingWrite for TieredWriter {
+    fn terminate_ref(&mut self, token: AntiCallToken) -> io::Result<()> {
+        self.underlying.terminate_ref(token)?;
+        self.files.write().unwrap().insert(
+            self.path.clone(),
+            FileEntry {
+                tier: Tier::Hot,
+                stats: Arc::new(FileStats::new()),
+            },
+        );
+        self.writing.lock().unwrap().remove(&self.path);
+        Ok(())
+    }
+}
+
+impl Drop for TieredWriter {
+    fn drop(&mut self) {
+        // The file may have been dropped without being terminated.
+        self.writing.lock().unwrap().remove(&self.path);
+    }
+}
+
+/// Copies a file from one directory to the other.
+fn copy_file(src: &dyn Directory, dest: &dyn Directory, path: &Path) -> crate::Result<()> {
+    let file_slice = src.open_read(path)?;
+    let mut wrt = dest.open_write(path)?;
+    for chunk in file_slice.stream_file_chunks() {
+        wrt.write_all(chunk?.as_slice())?;
+    }
+    wrt.terminate()?;
+    dest.sync_directory()?;
+    Ok(())
+}
+
+/// Directory keeping recently written or frequently read files in a fast
+/// directory, and demoting the others to a slower backing directory.
+///
+/// New files are always written to the hot directory.
+/// Calling [`TieredDirectory::rebalance`] (typically periodically) demotes the hot files that
+/// are older than [`TieringPolicy::min_age`] and have not been read for
+/// [`TieringPolicy::max_idle`], and promotes back the cold files that are read frequently.
+///
+/// Reads are transparently served from whichever tier holds the file. Moving a file does not
+/// affect the `FileSlice`s that are already open.
+///
+/// Atomic files (e.g. `meta.json`) and locks always live in the hot directory.
+/// Access statistics are kept in memory, and are reset when the directory is reopened.
+#[derive(Clone)]
+pub struct TieredDirectory {
+    hot: Box<dyn Directory>,
+    cold: Box<dyn Directory>,
+    policy: TieringPolicy,
+    files: Arc<RwLock<HashMap<PathBuf, FileEntry>>>,
+    // Files being written, which are not moved until they are terminated.
+    writing: Arc<Mutex<HashSet<PathBuf>>>,
+}
+
+impl std::fmt::Debug for TieredDirectory {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "TieredDirectory(hot={:?}, cold={:?})",
+            self.hot, self.cold
+        )
+    }
+}
+
+impl TieredDirectory {
+    /// Creates a new `TieredDirectory`.
+    pub fn new<H, C>(hot: H, cold: C, policy: TieringPolicy) -> TieredDirectory
+    where
+        H: Into<Box<dyn Directory>>,
+        C: Into<Box<dyn Directory>>,
+    {
+        TieredDirectory {
+            hot: hot.into(),
+            cold: cold.into(),
+            policy,
+            files: Arc::default(),
+            writing: Arc::default(),
+        }
+    }
+
+    /// Returns the tiering policy.
+    pub fn policy(&self) -> &TieringPolicy {
+        &self.policy
+    }
+
+    fn directory(&self, tier: Tier) -> &dyn Directory {
+        match tier {
+            Tier::Hot => self.hot.as_ref(),
+            Tier::Cold => self.cold.as_ref(),
+        }
+    }
+
+    /// Returns the tier currently holding the file, or `None` if the file does not exist.
+    pub fn tier(&self, path: &Path) -> Result<Option<Tier>, OpenReadError> {
+        if let Some(entry) = self.files.read().unwrap().get(path) {
+            return Ok(Some(entry.tier));
+        }
+        if self.hot.exists(path)? {
+            return Ok(Some(Tier::Hot));
+        }
+        if self.cold.exists(path)? {
+            return Ok(Some(Tier::Cold));
+        }
+        Ok(None)
+    }
+
+    /// Returns the tier and the statistics of the file, registering it if necessary.
+    fn entry(&self, path: &Path) -> Result<Option<(Tier, Arc<FileStats>)>, OpenReadError> {
+        if let Some(entry) = self.files.read().unwrap().get(path) {
+            return Ok(Some((entry.tier, entry.stats.clone())));
+        }
+        let Some(tier) = self.tier(path)? else {
+            return Ok(None);
+        };
+        let mut files = self.files.write().unwrap();
+        let entry = files
+            .entry(path.to_path_buf())
+            .or_insert_with(|| FileEntry {
+                tier,
+                stats: Arc::new(FileStats::new()),
+            });
+        Ok(Some((entry.tier, entry.stats.clone())))
+    }
+
+    /// Moves a file to the given tier.
+    ///
+    /// Returns false if the file was already in that tier.
+    pub fn move_to(&self, path: &Path, tier: Tier) -> crate::Result<bool> {
+        let Some((current_tier, _)) = self.entry(path)? else {
+            return Err(OpenReadError::FileDoesNotExist(path.to_path_buf()).into());
+        };
+        if current_tier == tier {
+            return Ok(false);
+        }
+        // The copy is not visible to readers until the entry is updated.
+        copy_file(self.directory(current_tier), self.directory(tier), path)?;
+        {
+            let mut files = self.files.write().unwrap();
+            files.insert(
+                path.to_path_buf(),
+                FileEntry {
+                    tier,
+                    stats: Arc::new(FileStats::new()),
+                },
+            );
+        }
+        if let Err(err) = self.directory(current_tier).delete(path) {
+            warn!("Failed to delete {path:?} after moving it to the {tier:?} tier: {err:?}");
+        }
+        Ok(true)
+    }
+
+    /// Demotes and promotes files according to the tiering policy.
+    ///
+    /// Files that are still being written are left in the hot tier.
+    pub fn rebalance(&self) -> crate::Result<TieringReport> {
+        let mut to_demote = Vec::new();
+        let mut to_promote = Vec::new();
+        let writing = self.writing.lock().unwrap().clone();
+        for (path, entry) in self.files.read().unwrap().iter() {
+            if writing.contains(path) {
+                continue;
+            }
+            match entry.tier {
+                Tier::Hot => {
+                    if entry.stats.age() >= self.policy.min_age
+                        && entry.stats.idle() >= self.policy.max_idle
+                    {
+                        to_demote.push(path.clone());
+                    }
+                }
+                Tier::Cold => {
+                    let num_reads = entry.stats.num_reads.load(Ordering::Relaxed);
+                    if self.policy.promotion_num_reads > 0
+                        && num_reads >= self.policy.promotion_num_reads
+                    {
+                        to_promote.push(path.clone());
+                    }
+                }
+            }
+        }
+        let mut report = TieringReport::default();
+        for path in to_demote {
+            if self.move_to(&path, Tier::Cold)? {
+                report.demoted.push(path);
+            }
+        }
+        for path in to_promote {
+            if self.move_to(&path, Tier::Hot)? {
+                report.promoted.push(path);
+            }
+        }
+        Ok(report)
+    }
+}
+
+impl Directory for TieredDirectory {
+    fn get_file_handle(&self, path: &Path) -> Result<Arc<dyn FileHandle>, OpenReadError> {
+        let Some((tier, stats)) = self.entry(path)? else {
+            return Err(OpenReadError::FileDoesNotExist(path.to_path_buf()));
+        };
+        let file_slice = match self.directory(tier).open_read(path) {
+            // The file may have been moved concurrently.
+            Err(OpenReadError::FileDoesNotExist(_)) => {
+                self.directory(tier.other()).open_read(path)?
+            }
+            res => res?,
+        };
+        Ok(Arc::new(TrackedFileHandle { file_slice, stats }))
+    }
+
+    fn delete(&self, path: &Path) -> Result<(), DeleteError> {
+        let tier_opt = self
+            .files
+            .write()
+            .unwrap()
+            .remove(path)
+            .map(|entry| entry.tier);
+        let tier = match tier_opt {
+            Some(tier) => tier,
+            None => match self.tier(path) {
+                Ok(Some(tier)) => tier,
+                Ok(None) => return Err(DeleteError::FileDoesNotExist(path.to_path_buf())),
+                Err(OpenReadError::IoError { io_error, filepath }) => {
+                    return Err(DeleteError::IoError { io_error, filepath })
+                }
+                Err(_) => return Err(DeleteError::FileDoesNotExist(path.to_path_buf())),
+            },
+        };
+        self.directory(tier).delete(path)
+    }
+
+    fn exists(&self, path: &Path) -> Result<bool, OpenReadError> {
+        Ok(self.tier(path)?.is_some())
+    }
+
+    fn open_write(&self, path: &Path) -> Result<WritePtr, OpenWriteError> {
+        let exists_in_cold = self.cold.exists(path).map_err(|err| {
+            let io_err = io::Error::new(io::ErrorKind::Other, err);
+            OpenWriteError::wrap_io_error(io_err, path.to_path_buf())
+        })?;
+        if exists_in_cold {
+            return Err(OpenWriteError::FileAlreadyExists(path.to_path_buf()));
+        }
+        let underlying = self.hot.open_write(path)?;
+        self.writing.lock().unwrap().insert(path.to_path_buf());
+        let tiered_wrt = TieredWriter {
+            underlying,
+            path: path.to_path_buf(),
+            files: self.files.clone(),
+            writing: self.writing.clone(),
+        };
+        Ok(BufWriter::new(Box::new(tiered_wrt)))
+    }
+
+    fn atomic_read(&self, path: &Path) -> Result<Vec<u8>, OpenReadError> {
+        self.hot.atomic_read(path)
+    }
+
+    fn atomic_write(&self, path: &Path, data: &[u8]) -> io::Result<()> {
+        self.hot.atomic_write(path, data)
+    }
+
+    fn sync_directory(&self) -> io::Result<()> {
+        self.hot.sync_directory()?;
+        self.cold.sync_directory()
+    }
+
+    fn acquire_lock(&self, lock: &Lock) -> Result<DirectoryLock, LockError> {
+        self.hot.acquire_lock(lock)
+    }
+
+    fn watch(&self, watch_callback: WatchCallback) -> crate::Result<WatchHandle> {
+        self.hot.watch(watch_callback)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+    use std::path::Path;
+    use std::time::Duration;
+
+    use super::{Tier, TieredDirectory, TieringPolicy};
+    use crate::directory::{Directory, RamDirectory, TerminatingWrite};
+    use crate::schema::{Schema, TEXT};
+    use crate::{Index, IndexSettings, IndexWriter};
+
+    fn write_file(directory: &dyn Directory, path: &Path, data: &[u8]) -> crate::Result<()> {
+        let mut wrt = directory.open_write(path)?;
+        wrt.write_all(data)?;
+        wrt.terminate()?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_tiered_directory_demote_and_promote() -> crate::Result<()> {
+        let hot = RamDirectory::create();
+        let cold = RamDirectory::create();
+        let policy = TieringPolicy {
+            min_age: Duration::ZERO,
+            max_idle: Duration::ZERO,
+            promotion_num_reads: 2,
+        };
+        let directory = TieredDirectory::new(hot.clone(), cold.clone(), policy);
+        let path = Path::new("segment.idx");
+        write_file(&directory, path, b"hello")?;
+        assert_eq!(directory.tier(path)?, Some(Tier::Hot));
+
+        let report = directory.rebalance()?;
+        assert_eq!(report.demoted, vec![path.to_path_buf()]);
+        assert!(report.promoted.is_empty());
+        assert_eq!(directory.tier(path)?, Some(Tier::Cold));
+        assert!(!hot.exists(path)?);
+        assert!(cold.exists(path)?);
+
+        let file = directory.open_read(path)?;
+        assert_eq!(file.read_bytes()?.as_slice(), b"hello");
+        assert!(directory.rebalance()?.promoted.is_empty());
+        assert_eq!(file.read_bytes()?.as_slice(), b"hello");
+        let report = directory.rebalance()?;
+        assert_eq!(report.promoted, vec![path.to_path_buf()]);
+        assert_eq!(directory.tier(path)?, Some(Tier::Hot));
+        // The file slice opened before the promotion is still valid.
+        assert_eq!(file.read_bytes()?.as_slice(), b"hello");
+        Ok(())
+    }
+
+    #[test]
+    fn test_tiered_directory_min_age() -> crate::Result<()> {
+        let directory = TieredDirectory::new(
+            RamDirectory::create(),
+            RamDirectory::create(),
+            TieringPolicy::default(),
+        );
+        let path = Path::new("segment.idx");
+        write_file(&directory, path, b"hello")?;
+        assert!(directory.rebalance()?.demoted.is_empty());
+        assert_eq!(directory.tier(path)?, Some(Tier::Hot));
+        Ok(())
+    }
+
+    #[test]
+    fn test_tiered_directory_skips_files_being_written() -> crate::Result<()> {
+        let policy = TieringPolicy {
+            min_age: Duration::ZERO,
+            max_idle: Duration::ZERO,
+            promotion_num_reads: 0,
+        };
+        let directory =
+            TieredDirectory::new(RamDirectory::create(), RamDirectory::create(), policy);
+        let path = Path::new("segment.idx");
+        let mut wrt = directory.open_write(path)?;
+        wrt.write_all(b"hel")?;
+        wrt.flush()?;
+        // Reading the file registers it, but it is not demoted before it is terminated.
+        directory.open_read(path)?;
+        assert!(directory.rebalance()?.demoted.is_empty());
+        wrt.write_all(b"lo")?;
+        wrt.terminate()?;
+        assert_eq!(directory.rebalance()?.demoted, vec![path.to_path_buf()]);
+        assert_eq!(directory.tier(path)?, Some(Tier::Cold));
+        assert_eq!(
+            directory.open_read(path)?.read_bytes()?.as_slice(),
+            b"hello"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_tiered_directory_write_once() -> crate::Result<()> {
+        let directory = TieredDirectory::new(
+            RamDirectory::create(),
+            RamDirectory::create(),
+            TieringPolicy::default(),
+        );
+        let path = Path::new("segment.idx");
+        write_file(&directory, path, b"hello")?;
+        directory.move_to(path, Tier::Cold)?;
+        assert!(directory.open_write(path).is_err());
+        directory.delete(path)?;
+        assert!(!directory.exists(path)?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_tiered_directory_index() -> crate::Result<()> {
+        let mut schema_builder = Schema::builder();
+        let text = schema_builder.add_text_field("text", TEXT);
+        let schema = schema_builder.build();
+        let policy = TieringPolicy {
+            min_age: Duration::ZERO,
+            max_idle: Duration::ZERO,
+            promotion_num_reads: 0,
+        };
+        let directory =
+            TieredDirectory::new(RamDirectory::create(), RamDirectory::create(), policy);
+        let index = Index::create(directory.clone(), schema, IndexSettings::default())?;
+        let mut index_writer: IndexWriter = index.writer_for_tests()?;
+        index_writer.add_document(doc!(text => "hello"))?;
+        index_writer.commit()?;
+        let report = directory.rebalance()?;
+        assert!(!report.demoted.is_empty());
+        let searcher = index.reader()?.searcher();
+        assert_eq!(searcher.num_docs(), 1);
+        Ok(())
+    }
+}
@@ -1,6 +1,6 @@
 //! Definition of Tantivy's errors and results.
 
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::{Arc, PoisonError};
 use std::{fmt, io};
 
@@ -11,15 +11,20 @@ use crate::directory::error::{
     Incompatibility, LockError, OpenDirectoryError, OpenReadError, OpenWriteError,
 };
 use crate::fastfield::FastFieldNotAvailableError;
+use crate::index::SegmentId;
 use crate::schema::document::DeserializeError;
 use crate::{query, schema};
 
 /// Represents a `DataCorruption` error.
 ///
 /// When facing data corruption, tantivy actually panics or returns this error.
+///
+/// When known, the report names the corrupted file, the offset of the corrupted
+/// data within it, and the segment the file belongs to.
 #[derive(Clone)]
 pub struct DataCorruption {
     filepath: Option<PathBuf>,
+    offset: Option<u64>,
     comment: String,
 }
 
@@ -28,6 +33,7 @@ impl DataCorruption {
     pub fn new(filepath: PathBuf, comment: String) -> DataCorruption {
         DataCorruption {
             filepath: Some(filepath),
+            offset: None,
             comment,
         }
     }
@@ -36,22 +42,69 @@ impl DataCorruption {
     pub fn comment_only<TStr: ToString>(comment: TStr) -> DataCorruption {
         DataCorruption {
             filepath: None,
+            offset: None,
             comment: comment.to_string(),
         }
     }
+
+    /// Sets the byte offset at which the corruption was detected.
+    #[must_use]
+    pub fn with_offset(mut self, offset: u64) -> DataCorruption {
+        self.offset = Some(offset);
+        self
+    }
+
+    /// Returns the path of the corrupted file, if known.
+    pub fn filepath(&self) -> Option<&Path> {
+        self.filepath.as_deref()
+    }
+
+    /// Returns the byte offset within the file at which the corruption was detected, if known.
+    pub fn offset(&self) -> Option<u64> {
+        self.offset
+    }
+
+    /// Returns the segment the corrupted file belongs to, if it is a segment file.
+    ///
+    /// Segment files are named after their segment id, e.g. `{segment_uuid}.idx`.
+    pub fn segment_id(&self) -> Option<SegmentId> {
+        let filename = self.filepath.as_ref()?.file_name()?.to_str()?;
+        let uuid_str = filename.split('.').next()?;
+        SegmentId::from_uuid_string(uuid_str).ok()
+    }
+
+    /// Returns the comment describing the corruption.
+    pub fn comment(&self) -> &str {
+        &self.comment
+    }
 }
 
 impl fmt::Debug for DataCorruption {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
         write!(f, "Data corruption")?;
         if let Some(ref filepath) = &self.filepath {
-            write!(f, " (in file `{filepath:?}`)")?;
+            write!(f, " (in file `{filepath:?}`")?;
+            if let Some(segment_id) = self.segment_id() {
+                write!(f, ", segment {segment_id}")?;
+            }
+            if let Some(offset) = self.offset {
+                write!(f, ", at offset {offset}")?;
+            }
+            write!(f, ")")?;
         }
         write!(f, ": {}.", self.comment)?;
         Ok(())
     }
 }
 
+impl fmt::Display for DataCorruption {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+        fmt::Debug::fmt(self, f)
+    }
+}
+
+impl std::error::Error for DataCorruption {}
+
 /// The library's error enum
 #[derive(Debug, Clone, Error)]
 pub enum TantivyError {
@@ -114,6 +167,14 @@ pub enum TantivyError {
 
 impl From<io::Error> for TantivyError {
     fn from(io_err: io::Error) -> TantivyError {
+        // Corruption detected while reading a file is reported as an `io::Error`
+        // wrapping a `DataCorruption`.
+        if let Some(data_corruption) = io_err
+            .get_ref()
+            .and_then(|inner| inner.downcast_ref::<DataCorruption>())
+        {
+            return TantivyError::DataCorruption(data_corruption.clone());
+        }
         TantivyError::IoError(Arc::new(io_err))
     }
 }
@@ -0,0 +1,537 @@
+//! Export of search results to Arrow and Parquet.
+//!
+//! Analytics engines read columnar data natively: a [`ResultExporter`] materializes the hits of
+//! a search, with the fast fields and the stored fields they need, as Arrow
+//! [`RecordBatch`]es, or writes them as a Parquet file. The batches are built one segment at a
+//! time, so that the memory used does not grow with the number of segments.
+//!
+//! With the `datafusion` feature, an `IndexTableProvider` also exposes the fast fields of an
+//! index as a DataFusion table.
+//!
+//! ```rust
+//! use tantivy::collector::TopDocs;
+//! use tantivy::export::ResultExporter;
+//! use tantivy::query::AllQuery;
+//! use tantivy::schema::{Schema, FAST, STORED, TEXT};
+//! use tantivy::{doc, Index, IndexWriter};
+//!
+//! # fn main() -> tantivy::Result<()> {
+//! let mut schema_builder = Schema::builder();
+//! let title = schema_builder.add_text_field("title", TEXT | STORED);
+//! let price = schema_builder.add_u64_field("price", FAST);
+//! let schema = schema_builder.build();
+//! let index = Index::create_in_ram(schema.clone());
+//! let mut index_writer: IndexWriter = index.writer(15_000_000)?;
+//! index_writer.add_document(doc!(title => "The Old Man and the Sea", price => 12u64))?;
+//! index_writer.commit()?;
+//! let searcher = index.reader()?.searcher();
+//! let hits = searcher.search(&AllQuery, &TopDocs::with_limit(10))?;
+//!
+//! let exporter = ResultExporter::new(schema)
+//!     .fast_field("price")
+//!     .stored_field("title");
+//! let mut parquet_file = Vec::new();
+//! exporter.write_parquet(&searcher, &hits, &mut parquet_file)?;
+//! # Ok(())
+//! # }
+//! ```
+
+#[cfg(feature = "datafusion")]
+mod table_provider;
+
+use std::collections::BTreeMap;
+use std::io::{self, Write};
+use std::net::Ipv6Addr;
+use std::sync::Arc;
+
+use arrow::array::{
+    ArrayRef, BinaryArray, BooleanArray, Float32Array, Float64Array, Int64Array, StringArray,
+    TimestampNanosecondArray, UInt32Array, UInt64Array,
+};
+use arrow::datatypes::{DataType, Field as ArrowField, Schema as ArrowSchema, SchemaRef, TimeUnit};
+use arrow::record_batch::RecordBatch;
+use columnar::{BytesColumn, Column, StrColumn};
+use parquet::arrow::ArrowWriter;
+
+#[cfg(feature = "datafusion")]
+pub use self::table_provider::IndexTableProvider;
+use crate::fastfield::FastFieldReaders;
+use crate::schema::{Facet, FieldType, OwnedValue, Schema};
+use crate::{
+    DateTime, DocAddress, DocId, Score, Searcher, SegmentOrdinal, TantivyDocument, TantivyError,
+};
+
+/// Name of the column of the segment ordinals of the hits.
+pub const SEGMENT_ORD_COLUMN: &str = "segment_ord";
+/// Name of the column of the doc ids of the hits.
+pub const DOC_ID_COLUMN: &str = "doc_id";
+/// Name of the column of the scores of the hits.
+pub const SCORE_COLUMN: &str = "score";
+/// Number of columns describing the hits, preceding the columns of the fields.
+pub(crate) const NUM_HIT_COLUMNS: usize = 3;
+
+/// Where the values of an exported column are read from.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum ValueSource {
+    FastField,
+    Store,
+}
+
+#[derive(Clone, Debug)]
+struct ExportedColumn {
+    field_name: String,
+    source: ValueSource,
+}
+
+/// Materializes search results as Arrow record batches, or writes them as Parquet.
+///
+/// Each row is a hit, with its segment ordinal, its doc id and its score, followed by the
+/// selected fields, in the order they were added. A field with no value for a document is
+/// null, and only the first value of a multivalued field is exported.
+///
+/// The Arrow types follow the field types: `u64`, `i64`, `f64` and `bool` fields keep their
+/// type, dates are nanosecond timestamps, bytes are binary, and text, facets, IP addresses and
+/// JSON objects are strings.
+#[derive(Clone, Debug)]
+pub struct ResultExporter {
+    schema: Schema,
+    columns: Vec<ExportedColumn>,
+}
+
+impl ResultExporter {
+    /// Creates an exporter of the doc ids and scores of the hits, for an index of the given
+    /// schema.
+    pub fn new(schema: Schema) -> ResultExporter {
+        ResultExporter {
+            schema,
+            columns: Vec::new(),
+        }
+    }
+
+    /// Exports the values of the fast field `field_name`.
+    ///
+    /// Fast fields are read from their columns, which is much cheaper than decompressing the
+    /// documents of the doc store.
+    #[must_use]
+    pub fn fast_field(mut self, field_name: impl Into<String>) -> ResultExporter {
+        self.columns.push(ExportedColumn {
+            field_name: field_name.into(),
+            source: ValueSource::FastField,
+        });
+        self
+    }
+
+    /// Exports the values of the stored field `field_name`.
+    #[must_use]
+    pub fn stored_field(mut self, field_name: impl Into<String>) -> ResultExporter {
+        self.columns.push(ExportedColumn {
+            field_name: field_name.into(),
+            source: ValueSource::Store,
+        });
+        self
+    }
+
+    /// Returns the schema of the record batches.
+    ///
+    /// Returns an error if a field is missing from the schema, is not fast or not stored as
+    /// requested, or is a fast JSON field.
+    pub fn arrow_schema(&self) -> crate::Result<SchemaRef> {
+        let mut arrow_fields = vec![
+            ArrowField::new(SEGMENT_ORD_COLUMN, DataType::UInt32, false),
+            ArrowField::new(DOC_ID_COLUMN, DataType::UInt32, false),
+            ArrowField::new(SCORE_COLUMN, DataType::Float32, false),
+        ];
+        for column in &self.columns {
+            let field = self.schema.get_field(&column.field_name)?;
+            let field_entry = self.schema.get_field_entry(field);
+            match column.source {
+                ValueSource::FastField if !field_entry.is_fast() => {
+                    return Err(TantivyError::SchemaError(format!(
+                        "Field {:?} is not a fast field.",
+                        column.field_name
+                    )));
+                }
+                ValueSource::FastField
+                    if matches!(field_entry.field_type(), FieldType::JsonObject(_)) =>
+                {
+                    return Err(TantivyError::SchemaError(format!(
+                        "Fast JSON field {:?} cannot be exported.",
+                        column.field_name
+                    )));
+                }
+                ValueSource::Store if !field_entry.is_stored() => {
+                    return Err(TantivyError::SchemaError(format!(
+                        "Field {:?} is not stored.",
+                        column.field_name
+                    )));
+                }
+                _ => {}
+            }
+            arrow_fields.push(ArrowField::new(
+                column.field_name.clone(),
+                arrow_data_type(field_entry.field_type()),
+                true,
+            ));
+        }
+        Ok(Arc::new(ArrowSchema::new(arrow_fields)))
+    }
+
+    /// Returns the record batches of `hits`, one per segment with at least a hit.
+    ///
+    /// The batches are built lazily, as the iterator is consumed. Within a batch, the hits keep
+    /// the order of `hits`.
+    pub fn record_batches<'a>(
+        &'a self,
+        searcher: &'a Searcher,
+        hits: &[(Score, DocAddress)],
+    ) -> crate::Result<impl Iterator<Item = crate::Result<RecordBatch>> + 'a> {
+        let arrow_schema = self.arrow_schema()?;
+        let mut hits_per_segment: BTreeMap<SegmentOrdinal, Vec<(Score, DocId)>> = BTreeMap::new();
+        for &(score, doc_address) in hits {
+            hits_per_segment
+                .entry(doc_address.segment_ord)
+                .or_default()
+                .push((score, doc_address.doc_id));
+        }
+        Ok(hits_per_segment
+            .into_iter()
+            .map(move |(segment_ord, segment_hits)| {
+                self.segment_record_batch(
+                    searcher,
+                    arrow_schema.clone(),
+                    segment_ord,
+                    &segment_hits,
+                )
+            }))
+    }
+
+    /// Writes `hits` to `writer` as a Parquet file, with a row group per segment.
+    pub fn write_parquet<W: Write + Send>(
+        &self,
+        searcher: &Searcher,
+        hits: &[(Score, DocAddress)],
+        writer: W,
+    ) -> crate::Result<()> {
+        let mut parquet_writer =
+            ArrowWriter::try_new(writer, self.arrow_schema()?, None).map_err(parquet_error)?;
+        for record_batch_res in self.record_batches(searcher, hits)? {
+            parquet_writer
+                .write(&record_batch_res?)
+                .map_err(parquet_error)?;
+            // Flushing closes the row group, so that a segment is never buffered along with
+            // the next one.
+            parquet_writer.flush().map_err(parquet_error)?;
+        }
+        parquet_writer.close().map_err(parquet_error)?;
+        Ok(())
+    }
+
+    fn segment_record_batch(
+        &self,
+        searcher: &Searcher,
+        arrow_schema: SchemaRef,
+        segment_ord: SegmentOrdinal,
+        hits: &[(Score, DocId)],
+    ) -> crate::Result<RecordBatch> {
+        let segment_reader = searcher.segment_reader(segment_ord);
+        let mut arrays: Vec<ArrayRef> = vec![
+            Arc::new(UInt32Array::from(vec![segment_ord; hits.len()])),
+            Arc::new(UInt32Array::from_iter_values(
+                hits.iter().map(|&(_, doc)| doc),
+            )),
+            Arc::new(Float32Array::from_iter_values(
+                hits.iter().map(|&(score, _)| score),
+            )),
+        ];
+        let docs: Vec<TantivyDocument> = if self
+            .columns
+            .iter()
+            .any(|column| column.source == ValueSource::Store)
+        {
+            let store_reader = segment_reader.get_store_reader(1)?;
+            hits.iter()
+                .map(|&(_, doc)| store_reader.get(doc))
+                .collect::<crate::Result<_>>()?
+        } else {
+            Vec::new()
+        };
+        for (column, arrow_field) in self
+            .columns
+            .iter()
+            .zip(&arrow_schema.fields()[NUM_HIT_COLUMNS..])
+        {
+            let field = self.schema.get_field(&column.field_name)?;
+            let field_type = self.schema.get_field_entry(field).field_type();
+            let values: Vec<OwnedValue> = match column.source {
+                ValueSource::FastField => {
+                    let fast_column =
+                        FastColumn::open(segment_reader.fast_fields(), column, field_type)?;
+                    hits.iter()
+                        .map(|&(_, doc)| fast_column.first_value(doc))
+                        .collect::<io::Result<_>>()?
+                }
+                ValueSource::Store => docs
+                    .iter()
+                    .map(|doc| {
+                        doc.get_first(field)
+                            .map(OwnedValue::from)
+                            .unwrap_or(OwnedValue::Null)
+                    })
+                    .collect(),
+            };
+            arrays.push(to_arrow_array(arrow_field.data_type(), values));
+        }
+        RecordBatch::try_new(arrow_schema, arrays).map_err(|arrow_error| {
+            TantivyError::InternalError(format!("Failed to build record batch: {arrow_error}"))
+        })
+    }
+}
+
+fn parquet_error(parquet_error: parquet::errors::ParquetError) -> TantivyError {
+    io::Error::new(io::ErrorKind::Other, parquet_error).into()
+}
+
+fn arrow_data_type(field_type: &FieldType) -> DataType {
+    match field_type {
+        FieldType::U64(_) => DataType::UInt64,
+        FieldType::I64(_) => DataType::Int64,
+        FieldType::F64(_) => DataType::Float64,
+        FieldType::Bool(_) => DataType::Boolean,
+        FieldType::Date(_) => DataType::Timestamp(TimeUnit::Nanosecond, None),
+        FieldType::Bytes(_) => DataType::Binary,
+        FieldType::Str(_) | FieldType::Facet(_) | FieldType::IpAddr(_) => DataType::Utf8,
+        FieldType::JsonObject(_) => DataType::Utf8,
+    }
+}
+
+/// Converts the values of a column to an Arrow array of type `data_type`.
+///
+/// The values are expected to be of the type of the field the data type was derived from.
+fn to_arrow_array(data_type: &DataType, values: Vec<OwnedValue>) -> ArrayRef {
+    let values = values.into_iter();
+    match data_type {
+        DataType::UInt64 => Arc::new(UInt64Array::from_iter(values.map(|value| match value {
+            OwnedValue::U64(val) => Some(val),
+            _ => None,
+        }))),
+        DataType::Int64 => Arc::new(Int64Array::from_iter(values.map(|value| match value {
+            OwnedValue::I64(val) => Some(val),
+            _ => None,
+        }))),
+        DataType::Float64 => Arc::new(Float64Array::from_iter(values.map(|value| match value {
+            OwnedValue::F64(val) => Some(val),
+            _ => None,
+        }))),
+        DataType::Boolean => Arc::new(BooleanArray::from_iter(values.map(|value| match value {
+            OwnedValue::Bool(val) => Some(val),
+            _ => None,
+        }))),
+        DataType::Timestamp(TimeUnit::Nanosecond, None) => Arc::new(
+            TimestampNanosecondArray::from_iter(values.map(|value| match value {
+                OwnedValue::Date(date) => Some(date.into_timestamp_nanos()),
+                _ => None,
+            })),
+        ),
+        DataType::Binary => Arc::new(BinaryArray::from_iter(values.map(|value| match value {
+            OwnedValue::Bytes(bytes) => Some(bytes),
+            _ => None,
+        }))),
+        _ => Arc::new(StringArray::from_iter(values.map(|value| match value {
+            OwnedValue::Null => None,
+            OwnedValue::Str(text) => Some(text),
+            OwnedValue::PreTokStr(pre_tokenized) => Some(pre_tokenized.text),
+            OwnedValue::Facet(facet) => Some(facet.to_path_string()),
+            OwnedValue::IpAddr(ip_addr) => Some(ip_addr.to_string()),
+            other => serde_json::to_string(&other).ok(),
+        }))),
+    }
+}
+
+/// Column of a fast field, read as the field type.
+enum FastColumn {
+    U64(Column<u64>),
+    I64(Column<i64>),
+    F64(Column<f64>),
+    Bool(Column<bool>),
+    Date(Column<DateTime>),
+    IpAddr(Column<Ipv6Addr>),
+    Str(Option<StrColumn>),
+    Facet(Option<StrColumn>),
+    Bytes(Option<BytesColumn>),
+}
+
+impl FastColumn {
+    fn open(
+        fast_fields: &FastFieldReaders,
+        column: &ExportedColumn,
+        field_type: &FieldType,
+    ) -> crate::Result<FastColumn> {
+        let field_name = &column.field_name;
+        Ok(match field_type {
+            FieldType::U64(_) => FastColumn::U64(fast_fields.u64(field_name)?),
+            FieldType::I64(_) => FastColumn::I64(fast_fields.i64(field_name)?),
+            FieldType::F64(_) => FastColumn::F64(fast_fields.f64(field_name)?),
+            FieldType::Bool(_) => FastColumn::Bool(fast_fields.bool(field_name)?),
+            FieldType::Date(_) => FastColumn::Date(fast_fields.date(field_name)?),
+            FieldType::IpAddr(_) => FastColumn::IpAddr(fast_fields.ip_addr(field_name)?),
+            FieldType::Str(_) => FastColumn::Str(fast_fields.str(field_name)?),
+            FieldType::Facet(_) => FastColumn::Facet(fast_fields.str(field_name)?),
+            FieldType::Bytes(_) => FastColumn::Bytes(fast_fields.bytes(field_name)?),
+            FieldType::JsonObject(_) => {
+                return Err(TantivyError::SchemaError(format!(
+                    "Fast JSON field {field_name:?} cannot be exported."
+                )));
+            }
+        })
+    }
+
+    fn first_value(&self, doc: DocId) -> io::Result<OwnedValue> {
+        let value_opt = match self {
+            FastColumn::U64(column) => column.first(doc).map(OwnedValue::U64),
+            FastColumn::I64(column) => column.first(doc).map(OwnedValue::I64),
+            FastColumn::F64(column) => column.first(doc).map(OwnedValue::F64),
+            FastColumn::Bool(column) => column.first(doc).map(OwnedValue::Bool),
+            FastColumn::Date(column) => column.first(doc).map(OwnedValue::Date),
+            FastColumn::IpAddr(column) => column.first(doc).map(OwnedValue::IpAddr),
+            FastColumn::Str(column_opt) | FastColumn::Facet(column_opt) => {
+                let Some(column) = column_opt else {
+                    return Ok(OwnedValue::Null);
+                };
+                let Some(term_ord) = column.term_ords(doc).next() else {
+                    return Ok(OwnedValue::Null);
+                };
+                let mut text = String::new();
+                column.ord_to_str(term_ord, &mut text)?;
+                if matches!(self, FastColumn::Facet(_)) {
+                    Some(OwnedValue::Facet(Facet::from_encoded_string(text)))
+                } else {
+                    Some(OwnedValue::Str(text))
+                }
+            }
+            FastColumn::Bytes(column_opt) => {
+                let Some(column) = column_opt else {
+                    return Ok(OwnedValue::Null);
+                };
+                let Some(term_ord) = column.term_ords(doc).next() else {
+                    return Ok(OwnedValue::Null);
+                };
+                let mut bytes = Vec::new();
+                column.ord_to_bytes(term_ord, &mut bytes)?;
+                Some(OwnedValue::Bytes(bytes))
+            }
+        };
+        Ok(value_opt.unwrap_or(OwnedValue::Null))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use arrow::array::{Array, AsArray};
+    use arrow::datatypes::{DataType, Float64Type, UInt32Type, UInt64Type};
+
+    use super::ResultExporter;
+    use crate::collector::TopDocs;
+    use crate::query::AllQuery;
+    use crate::schema::{Schema, Value, FAST, STORED, STRING, TEXT};
+    use crate::{doc, DocAddress, Index, IndexWriter};
+
+    #[test]
+    fn test_result_exporter() -> crate::Result<()> {
+        let mut schema_builder = Schema::builder();
+        let title = schema_builder.add_text_field("title", TEXT | STORED);
+        let tag = schema_builder.add_text_field("tag", STRING | FAST);
+        let price = schema_builder.add_u64_field("price", FAST);
+        let rating = schema_builder.add_f64_field("rating", FAST | STORED);
+        let schema = schema_builder.build();
+        let index = Index::create_in_ram(schema.clone());
+        let mut index_writer: IndexWriter = index.writer_for_tests()?;
+        index_writer.add_document(doc!(title => "apple", tag => "fruit", price => 3u64))?;
+        index_writer.commit()?;
+        index_writer.add_document(doc!(title => "pie", price => 12u64, rating => 4.5f64))?;
+        index_writer.add_document(doc!(title => "tart", tag => "dessert"))?;
+        index_writer.commit()?;
+        let searcher = index.reader()?.searcher();
+        let hits = searcher.search(&AllQuery, &TopDocs::with_limit(10))?;
+        assert_eq!(hits.len(), 3);
+
+        let exporter = ResultExporter::new(schema.clone())
+            .fast_field("price")
+            .fast_field("tag")
+            .stored_field("title")
+            .stored_field("rating");
+        let arrow_schema = exporter.arrow_schema()?;
+        let data_types: Vec<&DataType> = arrow_schema
+            .fields()
+            .iter()
+            .map(|field| field.data_type())
+            .collect();
+        assert_eq!(
+            data_types,
+            [
+                &DataType::UInt32,
+                &DataType::UInt32,
+                &DataType::Float32,
+                &DataType::UInt64,
+                &DataType::Utf8,
+                &DataType::Utf8,
+                &DataType::Float64,
+            ]
+        );
+
+        let record_batches: Vec<_> = exporter
+            .record_batches(&searcher, &hits)?
+            .collect::<crate::Result<_>>()?;
+        assert_eq!(record_batches.len(), 2);
+        assert_eq!(
+            record_batches
+                .iter()
+                .map(|batch| batch.num_rows())
+                .sum::<usize>(),
+            3
+        );
+        let batch = record_batches
+            .iter()
+            .find(|batch| batch.num_rows() == 2)
+            .unwrap();
+        let segment_ord = batch.column(0).as_primitive::<UInt32Type>().value(0);
+        let doc_ids = batch.column(1).as_primitive::<UInt32Type>();
+        let prices = batch.column(3).as_primitive::<UInt64Type>();
+        let tags = batch.column(4).as_string::<i32>();
+        let titles = batch.column(5).as_string::<i32>();
+        let ratings = batch.column(6).as_primitive::<Float64Type>();
+        for row in 0..2 {
+            let doc_address = DocAddress::new(segment_ord, doc_ids.value(row));
+            let doc: crate::TantivyDocument = searcher.doc(doc_address)?;
+            let title_value = doc.get_first(title).and_then(|value| value.as_str());
+            assert_eq!(Some(titles.value(row)), title_value);
+            if titles.value(row) == "pie" {
+                assert_eq!(prices.value(row), 12);
+                assert!(tags.is_null(row));
+                assert_eq!(ratings.value(row), 4.5);
+            } else {
+                assert!(prices.is_null(row));
+                assert_eq!(tags.value(row), "dessert");
+                assert!(ratings.is_null(row));
+            }
+        }
+
+        let mut parquet_file = Vec::new();
+        exporter.write_parquet(&searcher, &hits, &mut parquet_file)?;
+        assert_eq!(&parquet_file[..4], b"PAR1");
+
+        // The fields are checked against the schema.
+        assert!(ResultExporter::new(schema.clone())
+            .fast_field("title")
+            .arrow_schema()
+            .is_err());
+        assert!(ResultExporter::new(schema.clone())
+            .stored_field("tag")
+            .arrow_schema()
+            .is_err());
+        assert!(ResultExporter::new(schema)
+            .stored_field("missing")
+            .arrow_schema()
+            .is_err());
+        Ok(())
+    }
+}
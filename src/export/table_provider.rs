@@ -0,0 +1,485 @@
+use std::any::Any;
+use std::ops::Bound;
+use std::sync::Arc;
+
+use arrow::datatypes::{Schema as ArrowSchema, SchemaRef};
+use arrow::record_batch::RecordBatch;
+use async_trait::async_trait;
+use datafusion::datasource::TableProvider;
+use datafusion::error::{DataFusionError, Result as DataFusionResult};
+use datafusion::execution::context::SessionState;
+use datafusion::logical_expr::expr::InList;
+use datafusion::logical_expr::{
+    Between, BinaryExpr, Expr, Operator, TableProviderFilterPushDown, TableType,
+};
+use datafusion::physical_plan::memory::MemoryExec;
+use datafusion::physical_plan::ExecutionPlan;
+use datafusion::scalar::ScalarValue;
+
+use super::{ResultExporter, NUM_HIT_COLUMNS};
+use crate::collector::DocSetCollector;
+use crate::query::{AllQuery, BooleanQuery, ExistsQuery, Query, RangeQuery};
+use crate::schema::{FieldType, Schema, Term};
+use crate::{DateTime, DocAddress, IndexReader, Score, TantivyError};
+
+/// Exposes an index as a DataFusion table, so that it can be queried with SQL.
+///
+/// The columns of the table are the fast fields of the index, except the JSON ones, with the
+/// types of [`ResultExporter`]. Only the projected columns are read.
+///
+/// The comparisons of a column with a literal, `BETWEEN`, `IN` lists and `IS NOT NULL`
+/// filters, and their conjunctions and disjunctions, are translated into tantivy queries, so
+/// that only the matching documents are read. These filters are pushed down as inexact: a
+/// document matches if any of the values of a multivalued field does, and DataFusion checks
+/// the filters again on the first value exported.
+///
+/// Scans search the latest searcher of the reader, and build the record batches of every
+/// segment before returning: they are not meant to read a large part of a large index.
+///
+/// ```rust
+/// use std::sync::Arc;
+///
+/// use datafusion::prelude::SessionContext;
+/// use tantivy::export::IndexTableProvider;
+/// use tantivy::schema::{Schema, FAST, INDEXED};
+/// use tantivy::{doc, Index, IndexWriter};
+///
+/// # #[tokio::main]
+/// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let mut schema_builder = Schema::builder();
+/// let price = schema_builder.add_u64_field("price", INDEXED | FAST);
+/// let index = Index::create_in_ram(schema_builder.build());
+/// let mut index_writer: IndexWriter = index.writer(15_000_000)?;
+/// index_writer.add_document(doc!(price => 12u64))?;
+/// index_writer.add_document(doc!(price => 30u64))?;
+/// index_writer.commit()?;
+///
+/// let context = SessionContext::new();
+/// let table_provider = IndexTableProvider::new(index.reader()?)?;
+/// context.register_table("books", Arc::new(table_provider))?;
+/// let batches = context
+///     .sql("SELECT price FROM books WHERE price > 20")
+///     .await?
+///     .collect()
+///     .await?;
+/// assert_eq!(batches.iter().map(|batch| batch.num_rows()).sum::<usize>(), 1);
+/// # Ok(())
+/// # }
+/// ```
+pub struct IndexTableProvider {
+    reader: IndexReader,
+    schema: Schema,
+    field_names: Vec<String>,
+    arrow_schema: SchemaRef,
+}
+
+impl IndexTableProvider {
+    /// Creates a table of the fast fields of the index of `reader`.
+    pub fn new(reader: IndexReader) -> crate::Result<IndexTableProvider> {
+        let schema = reader.searcher().schema().clone();
+        let field_names: Vec<String> = schema
+            .fields()
+            .map(|(_, field_entry)| field_entry)
+            .filter(|field_entry| {
+                field_entry.is_fast()
+                    && !matches!(field_entry.field_type(), FieldType::JsonObject(_))
+            })
+            .map(|field_entry| field_entry.name().to_string())
+            .collect();
+        let arrow_schema = exporter_arrow_schema(&exporter(&schema, &field_names))?;
+        Ok(IndexTableProvider {
+            reader,
+            schema,
+            field_names,
+            arrow_schema,
+        })
+    }
+
+    /// Returns the record batches of the documents matching the filters, one partition per
+    /// segment.
+    fn record_batches(
+        &self,
+        projection: Option<&Vec<usize>>,
+        filters: &[Expr],
+        limit: Option<usize>,
+    ) -> crate::Result<Vec<Vec<RecordBatch>>> {
+        let field_names: Vec<String> = match projection {
+            Some(column_ords) => column_ords
+                .iter()
+                .map(|&column_ord| self.field_names[column_ord].clone())
+                .collect(),
+            None => self.field_names.clone(),
+        };
+        let exporter = exporter(&self.schema, &field_names);
+        let queries: Vec<Box<dyn Query>> = filters
+            .iter()
+            .filter_map(|filter| self.filter_to_query(filter))
+            .collect();
+        let query: Box<dyn Query> = if queries.is_empty() {
+            Box::new(AllQuery)
+        } else {
+            Box::new(BooleanQuery::intersection(queries))
+        };
+        let searcher = self.reader.searcher();
+        let mut doc_addresses: Vec<DocAddress> = searcher
+            .search(query.as_ref(), &DocSetCollector)?
+            .into_iter()
+            .collect();
+        doc_addresses.sort();
+        if let Some(limit) = limit {
+            doc_addresses.truncate(limit);
+        }
+        let hits: Vec<(Score, DocAddress)> = doc_addresses
+            .into_iter()
+            .map(|doc_address| (0.0, doc_address))
+            .collect();
+        let field_column_ords: Vec<usize> =
+            (NUM_HIT_COLUMNS..NUM_HIT_COLUMNS + field_names.len()).collect();
+        let mut partitions = Vec::new();
+        for record_batch_res in exporter.record_batches(&searcher, &hits)? {
+            let record_batch = record_batch_res?
+                .project(&field_column_ords)
+                .map_err(|arrow_error| TantivyError::InternalError(arrow_error.to_string()))?;
+            partitions.push(vec![record_batch]);
+        }
+        if partitions.is_empty() {
+            partitions.push(Vec::new());
+        }
+        Ok(partitions)
+    }
+
+    /// Translates a filter into a query matching at least the documents it accepts.
+    ///
+    /// Returns `None` if the filter is not supported.
+    fn filter_to_query(&self, filter: &Expr) -> Option<Box<dyn Query>> {
+        match filter {
+            Expr::BinaryExpr(BinaryExpr {
+                left,
+                op: Operator::And,
+                right,
+            }) => Some(Box::new(BooleanQuery::intersection(vec![
+                self.filter_to_query(left)?,
+                self.filter_to_query(right)?,
+            ]))),
+            Expr::BinaryExpr(BinaryExpr {
+                left,
+                op: Operator::Or,
+                right,
+            }) => Some(Box::new(BooleanQuery::union(vec![
+                self.filter_to_query(left)?,
+                self.filter_to_query(right)?,
+            ]))),
+            Expr::BinaryExpr(BinaryExpr { left, op, right }) => {
+                let (column_name, op, value) = match (left.as_ref(), right.as_ref()) {
+                    (Expr::Column(column), Expr::Literal(value)) => (&column.name, *op, value),
+                    (Expr::Literal(value), Expr::Column(column)) => {
+                        (&column.name, op.swap()?, value)
+                    }
+                    _ => return None,
+                };
+                let term = self.literal_term(column_name, value)?;
+                let (lower_bound, upper_bound) = match op {
+                    Operator::Eq => (Bound::Included(term.clone()), Bound::Included(term)),
+                    Operator::Lt => (Bound::Unbounded, Bound::Excluded(term)),
+                    Operator::LtEq => (Bound::Unbounded, Bound::Included(term)),
+                    Operator::Gt => (Bound::Excluded(term), Bound::Unbounded),
+                    Operator::GtEq => (Bound::Included(term), Bound::Unbounded),
+                    _ => return None,
+                };
+                self.range_query(column_name, lower_bound, upper_bound)
+            }
+            Expr::Between(Between {
+                expr,
+                negated: false,
+                low,
+                high,
+            }) => {
+                let (Expr::Column(column), Expr::Literal(low), Expr::Literal(high)) =
+                    (expr.as_ref(), low.as_ref(), high.as_ref())
+                else {
+                    return None;
+                };
+                self.range_query(
+                    &column.name,
+                    Bound::Included(self.literal_term(&column.name, low)?),
+                    Bound::Included(self.literal_term(&column.name, high)?),
+                )
+            }
+            Expr::InList(InList {
+                expr,
+                list,
+                negated: false,
+            }) => {
+                let Expr::Column(column) = expr.as_ref() else {
+                    return None;
+                };
+                let queries = list
+                    .iter()
+                    .map(|value| {
+                        let Expr::Literal(value) = value else {
+                            return None;
+                        };
+                        let term = self.literal_term(&column.name, value)?;
+                        self.range_query(
+                            &column.name,
+                            Bound::Included(term.clone()),
+                            Bound::Included(term),
+                        )
+                    })
+                    .collect::<Option<Vec<Box<dyn Query>>>>()?;
+                Some(Box::new(BooleanQuery::union(queries)))
+            }
+            Expr::IsNotNull(expr) => {
+                let Expr::Column(column) = expr.as_ref() else {
+                    return None;
+                };
+                self.field_names.contains(&column.name).then(|| {
+                    Box::new(ExistsQuery::new_exists_query(column.name.clone())) as Box<dyn Query>
+                })
+            }
+            _ => None,
+        }
+    }
+
+    fn range_query(
+        &self,
+        column_name: &str,
+        lower_bound: Bound<Term>,
+        upper_bound: Bound<Term>,
+    ) -> Option<Box<dyn Query>> {
+        let field = self.schema.get_field(column_name).ok()?;
+        let value_type = self.schema.get_field_entry(field).field_type().value_type();
+        Some(Box::new(RangeQuery::new_term_bounds(
+            column_name.to_string(),
+            value_type,
+            &lower_bound,
+            &upper_bound,
+        )))
+    }
+
+    /// Converts a literal compared to the column `column_name` into a term of its field.
+    ///
+    /// Text columns are only supported if they are indexed, as the ranges of text are searched
+    /// in the term dictionary.
+    fn literal_term(&self, column_name: &str, value: &ScalarValue) -> Option<Term> {
+        if !self
+            .field_names
+            .iter()
+            .any(|field_name| field_name == column_name)
+        {
+            return None;
+        }
+        let field = self.schema.get_field(column_name).ok()?;
+        let field_entry = self.schema.get_field_entry(field);
+        let term = match (field_entry.field_type(), value) {
+            (FieldType::U64(_), _) => {
+                Term::from_field_u64(field, u64::try_from(scalar_to_i128(value)?).ok()?)
+            }
+            (FieldType::I64(_), _) => {
+                Term::from_field_i64(field, i64::try_from(scalar_to_i128(value)?).ok()?)
+            }
+            (FieldType::F64(_), ScalarValue::Float64(Some(val))) => {
+                Term::from_field_f64(field, *val)
+            }
+            (FieldType::F64(_), ScalarValue::Float32(Some(val))) => {
+                Term::from_field_f64(field, f64::from(*val))
+            }
+            (FieldType::Bool(_), ScalarValue::Boolean(Some(val))) => {
+                Term::from_field_bool(field, *val)
+            }
+            (FieldType::Date(_), _) => Term::from_field_date(field, scalar_to_date(value)?),
+            (FieldType::Str(_), ScalarValue::Utf8(Some(text)))
+            | (FieldType::Str(_), ScalarValue::LargeUtf8(Some(text)))
+                if field_entry.is_indexed() =>
+            {
+                Term::from_field_text(field, text)
+            }
+            _ => return None,
+        };
+        Some(term)
+    }
+}
+
+fn exporter(schema: &Schema, field_names: &[String]) -> ResultExporter {
+    field_names.iter().fold(
+        ResultExporter::new(schema.clone()),
+        |exporter, field_name| exporter.fast_field(field_name.clone()),
+    )
+}
+
+/// Returns the schema of the field columns of the exporter.
+fn exporter_arrow_schema(exporter: &ResultExporter) -> crate::Result<SchemaRef> {
+    let arrow_schema = exporter.arrow_schema()?;
+    let field_columns = arrow_schema.fields()[NUM_HIT_COLUMNS..].to_vec();
+    Ok(Arc::new(ArrowSchema::new(field_columns)))
+}
+
+fn scalar_to_i128(value: &ScalarValue) -> Option<i128> {
+    match *value {
+        ScalarValue::Int8(Some(val)) => Some(i128::from(val)),
+        ScalarValue::Int16(Some(val)) => Some(i128::from(val)),
+        ScalarValue::Int32(Some(val)) => Some(i128::from(val)),
+        ScalarValue::Int64(Some(val)) => Some(i128::from(val)),
+        ScalarValue::UInt8(Some(val)) => Some(i128::from(val)),
+        ScalarValue::UInt16(Some(val)) => Some(i128::from(val)),
+        ScalarValue::UInt32(Some(val)) => Some(i128::from(val)),
+        ScalarValue::UInt64(Some(val)) => Some(i128::from(val)),
+        _ => None,
+    }
+}
+
+fn scalar_to_date(value: &ScalarValue) -> Option<DateTime> {
+    match *value {
+        ScalarValue::TimestampSecond(Some(secs), _) => Some(DateTime::from_timestamp_secs(secs)),
+        ScalarValue::TimestampMillisecond(Some(millis), _) => {
+            Some(DateTime::from_timestamp_millis(millis))
+        }
+        ScalarValue::TimestampMicrosecond(Some(micros), _) => {
+            Some(DateTime::from_timestamp_micros(micros))
+        }
+        ScalarValue::TimestampNanosecond(Some(nanos), _) => {
+            Some(DateTime::from_timestamp_nanos(nanos))
+        }
+        _ => None,
+    }
+}
+
+fn datafusion_error(tantivy_error: TantivyError) -> DataFusionError {
+    DataFusionError::External(Box::new(tantivy_error))
+}
+
+#[async_trait]
+impl TableProvider for IndexTableProvider {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn schema(&self) -> SchemaRef {
+        self.arrow_schema.clone()
+    }
+
+    fn table_type(&self) -> TableType {
+        TableType::Base
+    }
+
+    async fn scan(
+        &self,
+        _state: &SessionState,
+        projection: Option<&Vec<usize>>,
+        filters: &[Expr],
+        limit: Option<usize>,
+    ) -> DataFusionResult<Arc<dyn ExecutionPlan>> {
+        let partitions = self
+            .record_batches(projection, filters, limit)
+            .map_err(datafusion_error)?;
+        let projected_schema = match projection {
+            Some(column_ords) => Arc::new(self.arrow_schema.project(column_ords)?),
+            None => self.arrow_schema.clone(),
+        };
+        Ok(Arc::new(MemoryExec::try_new(
+            &partitions,
+            projected_schema,
+            None,
+        )?))
+    }
+
+    fn supports_filters_pushdown(
+        &self,
+        filters: &[&Expr],
+    ) -> DataFusionResult<Vec<TableProviderFilterPushDown>> {
+        Ok(filters
+            .iter()
+            .map(|filter| {
+                if self.filter_to_query(filter).is_some() {
+                    TableProviderFilterPushDown::Inexact
+                } else {
+                    TableProviderFilterPushDown::Unsupported
+                }
+            })
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use datafusion::arrow::array::AsArray;
+    use datafusion::arrow::datatypes::{Int64Type, UInt64Type};
+    use datafusion::prelude::SessionContext;
+
+    use super::IndexTableProvider;
+    use crate::schema::{Schema, FAST, INDEXED, STRING};
+    use crate::{doc, Index, IndexWriter};
+
+    #[tokio::test]
+    async fn test_index_table_provider() -> Result<(), Box<dyn std::error::Error>> {
+        let mut schema_builder = Schema::builder();
+        let price = schema_builder.add_u64_field("price", INDEXED | FAST);
+        let tag = schema_builder.add_text_field("tag", STRING | FAST);
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer: IndexWriter = index.writer_for_tests()?;
+        index_writer.add_document(doc!(price => 12u64, tag => "novel"))?;
+        index_writer.add_document(doc!(price => 30u64, tag => "essay"))?;
+        index_writer.commit()?;
+        index_writer.add_document(doc!(price => 25u64, tag => "novel"))?;
+        index_writer.add_document(doc!(tag => "novel"))?;
+        index_writer.commit()?;
+
+        let table_provider = IndexTableProvider::new(index.reader()?)?;
+        assert_eq!(table_provider.field_names, ["price", "tag"]);
+        let context = SessionContext::new();
+        context.register_table("books", Arc::new(table_provider))?;
+        assert_eq!(
+            prices(&context, "SELECT price FROM books WHERE price > 20").await?,
+            [25, 30]
+        );
+        assert_eq!(
+            prices(
+                &context,
+                "SELECT price FROM books WHERE tag = 'novel' AND price IS NOT NULL"
+            )
+            .await?,
+            [12, 25]
+        );
+        assert_eq!(
+            prices(
+                &context,
+                "SELECT price FROM books WHERE price BETWEEN 10 AND 25 OR tag IN ('essay')"
+            )
+            .await?,
+            [12, 25, 30]
+        );
+        // Filters which cannot be pushed down are applied by DataFusion.
+        assert_eq!(
+            prices(&context, "SELECT price FROM books WHERE price % 2 = 0").await?,
+            [12, 30]
+        );
+        let batches = context
+            .sql("SELECT COUNT(*) FROM books WHERE tag = 'novel'")
+            .await?
+            .collect()
+            .await?;
+        assert_eq!(batches[0].column(0).as_primitive::<Int64Type>().value(0), 3);
+        Ok(())
+    }
+
+    async fn prices(
+        context: &SessionContext,
+        sql: &str,
+    ) -> Result<Vec<u64>, Box<dyn std::error::Error>> {
+        let batches = context.sql(sql).await?.collect().await?;
+        let mut prices: Vec<u64> = batches
+            .iter()
+            .flat_map(|batch| {
+                batch
+                    .column(0)
+                    .as_primitive::<UInt64Type>()
+                    .iter()
+                    .flatten()
+            })
+            .collect();
+        prices.sort();
+        Ok(prices)
+    }
+}
@@ -1,37 +1,98 @@
 use std::io;
 use std::io::Write;
+use std::sync::Arc;
 
 use common::{intersect_bitsets, BitSet, ByteCount, OwnedBytes, ReadOnlyBitSet};
+use itertools::Either;
+use once_cell::sync::OnceCell;
 
 use crate::DocId;
 
+/// Value of the first 4 bytes of a sparse alive bitset file.
+///
+/// Dense files start with the max doc instead, which is lower than `TERMINATED`.
+const SPARSE_MARKER: u32 = u32::MAX;
+
+/// Maximum number of deleted documents of a sparse alive bitset.
+///
+/// Checking if a document is alive requires a binary search in the deleted documents.
+const MAX_SPARSE_NUM_DELETED_DOCS: usize = 256;
+
 /// Write an alive `BitSet`
 ///
 /// where `alive_bitset` is the set of alive `DocId`.
+///
+/// The alive documents are stored with one bit per document, or as the sorted list of the
+/// deleted documents if there are at most [`MAX_SPARSE_NUM_DELETED_DOCS`] of them and that is
+/// smaller: a segment with a handful of deletes then gets a delete file of a few bytes,
+/// whatever its number of documents.
+///
 /// Warning: this function does not call terminate. The caller is in charge of
 /// closing the writer properly.
 pub fn write_alive_bitset<T: Write>(alive_bitset: &BitSet, writer: &mut T) -> io::Result<()> {
-    alive_bitset.serialize(writer)?;
-    Ok(())
+    let max_doc = alive_bitset.max_value();
+    let num_deleted_docs = max_doc as usize - alive_bitset.len();
+    if !is_sparse_smaller(max_doc, num_deleted_docs) {
+        return alive_bitset.serialize(writer);
+    }
+    writer.write_all(&SPARSE_MARKER.to_le_bytes())?;
+    writer.write_all(&max_doc.to_le_bytes())?;
+    for bucket in 0..(max_doc + 63) / 64 {
+        if alive_bitset.tinyset(bucket).len() == 64 {
+            continue;
+        }
+        let bucket_docs = bucket * 64..(bucket * 64 + 64).min(max_doc);
+        for doc in bucket_docs.filter(|&doc| !alive_bitset.contains(doc)) {
+            writer.write_all(&doc.to_le_bytes())?;
+        }
+    }
+    writer.flush()
+}
+
+/// Returns true if the list of the deleted documents is short, and takes less space than a
+/// bitset.
+fn is_sparse_smaller(max_doc: u32, num_deleted_docs: usize) -> bool {
+    if num_deleted_docs > MAX_SPARSE_NUM_DELETED_DOCS {
+        return false;
+    }
+    let dense_num_bytes = (max_doc as usize + 63) / 64 * 8;
+    let sparse_num_bytes = 4 + num_deleted_docs * 4;
+    sparse_num_bytes < dense_num_bytes
 }
 
 /// Set of alive `DocId`s.
 #[derive(Clone)]
 pub struct AliveBitSet {
     num_alive_docs: usize,
-    bitset: ReadOnlyBitSet,
+    repr: AliveBitSetRepr,
+}
+
+#[derive(Clone)]
+enum AliveBitSetRepr {
+    /// One bit per document, set if the document is alive.
+    Dense(ReadOnlyBitSet),
+    /// The sorted list of the deleted documents.
+    Sparse {
+        max_doc: DocId,
+        deleted_docs: Arc<[DocId]>,
+        // Expanded on the first call to the deprecated `AliveBitSet::bitset`.
+        bitset: Arc<OnceCell<ReadOnlyBitSet>>,
+    },
 }
 
 /// Intersects two AliveBitSets in a new one.
 /// The two bitsets need to have the same max_value.
 pub fn intersect_alive_bitsets(left: AliveBitSet, right: AliveBitSet) -> AliveBitSet {
-    assert_eq!(left.bitset().max_value(), right.bitset().max_value());
-    let bitset = intersect_bitsets(left.bitset(), right.bitset());
-    let num_alive_docs = bitset.len();
-    AliveBitSet {
-        num_alive_docs,
-        bitset,
+    assert_eq!(left.max_doc(), right.max_doc());
+    if let (AliveBitSetRepr::Dense(left_bitset), AliveBitSetRepr::Dense(right_bitset)) =
+        (&left.repr, &right.repr)
+    {
+        return AliveBitSet::from(intersect_bitsets(left_bitset, right_bitset));
     }
+    let mut bitset = BitSet::with_max_value_and_full(left.max_doc());
+    left.remove_deleted_docs(&mut bitset);
+    right.remove_deleted_docs(&mut bitset);
+    AliveBitSet::from_bitset(&bitset)
 }
 
 impl AliveBitSet {
@@ -42,22 +103,68 @@ impl AliveBitSet {
         for &doc in deleted_docs {
             bitset.remove(doc);
         }
+        Self::from_bitset(&bitset)
+    }
+
+    /// Builds the alive bitset of the documents of `bitset`, in its smallest representation.
+    pub(crate) fn from_bitset(bitset: &BitSet) -> AliveBitSet {
         let mut alive_bitset_buffer = Vec::new();
-        write_alive_bitset(&bitset, &mut alive_bitset_buffer).unwrap();
-        let alive_bitset_bytes = OwnedBytes::new(alive_bitset_buffer);
-        Self::open(alive_bitset_bytes)
+        write_alive_bitset(bitset, &mut alive_bitset_buffer)
+            .expect("serializing into a buffer should never fail");
+        Self::open(OwnedBytes::new(alive_bitset_buffer))
+            .expect("the alive bitset was just serialized")
     }
 
     /// Opens an alive bitset given its file.
-    pub fn open(bytes: OwnedBytes) -> AliveBitSet {
-        let bitset = ReadOnlyBitSet::open(bytes);
-        AliveBitSet::from(bitset)
+    ///
+    /// Returns an error if the list of the deleted documents of a sparse alive bitset is not
+    /// sorted, or contains documents beyond the max doc.
+    pub fn open(bytes: OwnedBytes) -> io::Result<AliveBitSet> {
+        let invalid_data_err = |msg: &str| io::Error::new(io::ErrorKind::InvalidData, msg);
+        if bytes.len() < 4 {
+            return Err(invalid_data_err("Alive bitset file is truncated"));
+        }
+        let (marker_bytes, data) = bytes.clone().split(4);
+        if u32::from_le_bytes(marker_bytes.as_slice().try_into().unwrap()) != SPARSE_MARKER {
+            return Ok(AliveBitSet::from(ReadOnlyBitSet::open(bytes)));
+        }
+        if data.len() < 4 || data.len() % 4 != 0 {
+            return Err(invalid_data_err("Sparse alive bitset file is truncated"));
+        }
+        let (max_doc_bytes, deleted_docs_bytes) = data.split(4);
+        let max_doc = u32::from_le_bytes(max_doc_bytes.as_slice().try_into().unwrap());
+        let deleted_docs: Arc<[DocId]> = deleted_docs_bytes
+            .as_slice()
+            .chunks_exact(4)
+            .map(|doc_bytes| u32::from_le_bytes(doc_bytes.try_into().unwrap()))
+            .collect();
+        let is_strictly_increasing = deleted_docs.windows(2).all(|docs| docs[0] < docs[1]);
+        let is_below_max_doc = deleted_docs.last().map_or(true, |&doc| doc < max_doc);
+        if !is_strictly_increasing || !is_below_max_doc {
+            return Err(invalid_data_err(
+                "Deleted documents of the sparse alive bitset are not sorted, or exceed its max \
+                 doc",
+            ));
+        }
+        Ok(AliveBitSet {
+            num_alive_docs: max_doc as usize - deleted_docs.len(),
+            repr: AliveBitSetRepr::Sparse {
+                max_doc,
+                deleted_docs,
+                bitset: Arc::default(),
+            },
+        })
     }
 
     /// Returns true if the document is still "alive". In other words, if it has not been deleted.
     #[inline]
     pub fn is_alive(&self, doc: DocId) -> bool {
-        self.bitset.contains(doc)
+        match &self.repr {
+            AliveBitSetRepr::Dense(bitset) => bitset.contains(doc),
+            AliveBitSetRepr::Sparse { deleted_docs, .. } => {
+                deleted_docs.binary_search(&doc).is_err()
+            }
+        }
     }
 
     /// Returns true if the document has been marked as deleted.
@@ -69,13 +176,76 @@ impl AliveBitSet {
     /// Iterate over the alive doc_ids.
     #[inline]
     pub fn iter_alive(&self) -> impl Iterator<Item = DocId> + '_ {
-        self.bitset.iter()
+        match &self.repr {
+            AliveBitSetRepr::Dense(bitset) => Either::Left(bitset.iter()),
+            AliveBitSetRepr::Sparse {
+                max_doc,
+                deleted_docs,
+                ..
+            } => {
+                // The deleted documents being sorted, they are skipped as they are reached.
+                let mut deleted_docs = deleted_docs.iter().copied().peekable();
+                Either::Right((0..*max_doc).filter(move |&doc| {
+                    if deleted_docs.peek() == Some(&doc) {
+                        deleted_docs.next();
+                        return false;
+                    }
+                    true
+                }))
+            }
+        }
     }
 
     /// Get underlying bitset.
+    ///
+    /// Sparse alive bitsets, storing the deleted documents, are expanded to one bit per
+    /// document on the first call, and keep that bitset in memory.
+    #[deprecated(
+        since = "0.23.0",
+        note = "alive bitsets may store their deleted documents instead of a bitset: use \
+                `to_bitset`, `iter_alive` or `is_alive` instead"
+    )]
     #[inline]
     pub fn bitset(&self) -> &ReadOnlyBitSet {
-        &self.bitset
+        match &self.repr {
+            AliveBitSetRepr::Dense(bitset) => bitset,
+            AliveBitSetRepr::Sparse { bitset, .. } => bitset.get_or_init(|| self.to_bitset()),
+        }
+    }
+
+    /// Returns the alive documents as a bitset.
+    ///
+    /// Sparse alive bitsets, storing the deleted documents, are expanded to one bit per
+    /// document.
+    pub fn to_bitset(&self) -> ReadOnlyBitSet {
+        match &self.repr {
+            AliveBitSetRepr::Dense(bitset) => bitset.clone(),
+            AliveBitSetRepr::Sparse { max_doc, .. } => {
+                let mut bitset = BitSet::with_max_value_and_full(*max_doc);
+                self.remove_deleted_docs(&mut bitset);
+                ReadOnlyBitSet::from(&bitset)
+            }
+        }
+    }
+
+    /// Removes the deleted documents from `bitset`.
+    pub(crate) fn remove_deleted_docs(&self, bitset: &mut BitSet) {
+        match &self.repr {
+            AliveBitSetRepr::Dense(alive_bitset) => bitset.intersect_update(alive_bitset),
+            AliveBitSetRepr::Sparse { deleted_docs, .. } => {
+                for &doc in deleted_docs.iter() {
+                    bitset.remove(doc);
+                }
+            }
+        }
+    }
+
+    /// The number of documents of the segment, alive or deleted.
+    pub fn max_doc(&self) -> DocId {
+        match &self.repr {
+            AliveBitSetRepr::Dense(bitset) => bitset.max_value(),
+            AliveBitSetRepr::Sparse { max_doc, .. } => *max_doc,
+        }
     }
 
     /// The number of alive documents.
@@ -85,7 +255,12 @@ impl AliveBitSet {
 
     /// Summarize total space usage of this bitset.
     pub fn space_usage(&self) -> ByteCount {
-        self.bitset().num_bytes()
+        match &self.repr {
+            AliveBitSetRepr::Dense(bitset) => bitset.num_bytes(),
+            AliveBitSetRepr::Sparse { deleted_docs, .. } => {
+                (deleted_docs.len() * std::mem::size_of::<DocId>()).into()
+            }
+        }
     }
 }
 
@@ -94,7 +269,7 @@ impl From<ReadOnlyBitSet> for AliveBitSet {
         let num_alive_docs = bitset.len();
         AliveBitSet {
             num_alive_docs,
-            bitset,
+            repr: AliveBitSetRepr::Dense(bitset),
         }
     }
 }
@@ -102,7 +277,9 @@ impl From<ReadOnlyBitSet> for AliveBitSet {
 #[cfg(test)]
 mod tests {
 
-    use super::AliveBitSet;
+    use common::OwnedBytes;
+
+    use super::{intersect_alive_bitsets, AliveBitSet};
 
     #[test]
     fn test_alive_bitset_empty() {
@@ -156,6 +333,79 @@ mod tests {
         let data: Vec<_> = alive_bitset.iter_alive().collect();
         assert_eq!(data, (2..=999).collect::<Vec<_>>());
     }
+
+    #[test]
+    fn test_alive_bitset_sparse() {
+        let max_doc = 1_000_000;
+        let sparse = AliveBitSet::for_test_from_deleted_docs(&[3, 64, 999_999], max_doc);
+        assert_eq!(sparse.space_usage().get_bytes(), 12);
+        assert_eq!(sparse.max_doc(), max_doc);
+        assert_eq!(sparse.num_alive_docs(), 999_997);
+        assert!(sparse.is_deleted(3));
+        assert!(sparse.is_alive(4));
+        assert!(sparse.is_deleted(999_999));
+        assert_eq!(
+            sparse.iter_alive().take(4).collect::<Vec<_>>(),
+            vec![0, 1, 2, 4]
+        );
+        let bitset = sparse.to_bitset();
+        assert_eq!(bitset.max_value(), max_doc);
+        assert_eq!(bitset.len(), 999_997);
+        assert!(!bitset.contains(64));
+
+        let deleted_evens: Vec<u32> = (0..max_doc).step_by(2).collect();
+        let dense = AliveBitSet::for_test_from_deleted_docs(&deleted_evens, max_doc);
+        assert_eq!(dense.space_usage().get_bytes(), 125_000);
+        let intersection = intersect_alive_bitsets(sparse.clone(), dense);
+        assert_eq!(intersection.num_alive_docs(), 499_998);
+        assert!(intersection.is_deleted(2));
+        assert!(intersection.is_deleted(999_999));
+        assert!(intersection.is_alive(1));
+        let other_sparse = AliveBitSet::for_test_from_deleted_docs(&[3, 5], max_doc);
+        let intersection = intersect_alive_bitsets(sparse.clone(), other_sparse);
+        assert_eq!(intersection.space_usage().get_bytes(), 16);
+        assert_eq!(intersection.num_alive_docs(), 999_996);
+        #[allow(deprecated)]
+        let bitset = sparse.bitset();
+        assert_eq!(bitset.len(), 999_997);
+        assert!(!bitset.contains(999_999));
+
+        // Segments with many deletes get a bitset, even if the list would be smaller.
+        let many_deleted_docs: Vec<u32> = (0..1_000).map(|i| i * 997).collect();
+        let dense = AliveBitSet::for_test_from_deleted_docs(&many_deleted_docs, max_doc);
+        assert_eq!(dense.space_usage().get_bytes(), 125_000);
+        assert_eq!(dense.iter_alive().count(), 999_000);
+    }
+
+    #[test]
+    fn test_alive_bitset_sparse_iter() {
+        let deleted_docs = [0, 1, 5, 63, 64, 99];
+        let alive_bitset = AliveBitSet::for_test_from_deleted_docs(&deleted_docs, 10_000);
+        assert!(alive_bitset.space_usage().get_bytes() < 100);
+        let alive_docs: Vec<u32> = alive_bitset.iter_alive().collect();
+        let expected_alive_docs: Vec<u32> = (0..10_000)
+            .filter(|doc| !deleted_docs.contains(doc))
+            .collect();
+        assert_eq!(alive_docs, expected_alive_docs);
+    }
+
+    #[test]
+    fn test_alive_bitset_open_invalid_sparse() {
+        let sparse_bytes = |max_doc: u32, deleted_docs: &[u32]| {
+            let mut bytes = Vec::new();
+            for val in [u32::MAX, max_doc].iter().chain(deleted_docs) {
+                bytes.extend_from_slice(&val.to_le_bytes());
+            }
+            OwnedBytes::new(bytes)
+        };
+        assert!(AliveBitSet::open(sparse_bytes(10, &[2, 9])).is_ok());
+        // A deleted document beyond the max doc would make the number of alive docs underflow.
+        assert!(AliveBitSet::open(sparse_bytes(10, &[2, 10])).is_err());
+        assert!(AliveBitSet::open(sparse_bytes(1, &[0, 1, 2])).is_err());
+        assert!(AliveBitSet::open(sparse_bytes(10, &[3, 2])).is_err());
+        assert!(AliveBitSet::open(sparse_bytes(10, &[3, 3])).is_err());
+        assert!(AliveBitSet::open(OwnedBytes::new(vec![255u8; 6])).is_err());
+    }
 }
 
 #[cfg(all(test, feature = "unstable"))]
@@ -0,0 +1,368 @@
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
+use std::sync::{Arc, Mutex};
+
+use columnar::StrColumn;
+
+use crate::index::{SegmentId, SegmentReader};
+use crate::termdict::TermOrdinal;
+
+/// Maps the term ordinals of a string fast field in each segment of a
+/// [`Searcher`](crate::Searcher) to global ordinals, shared by all of its segments.
+///
+/// The term ordinals of a segment follow the order of its own dictionary, so that the same term
+/// has a different ordinal in each segment. Global ordinals follow the order of the union of the
+/// dictionaries: a collector can accumulate its buckets per global ordinal across segments, and
+/// compare terms of different segments by comparing integers, only resolving the terms it
+/// eventually outputs.
+///
+/// See [`Searcher::global_ordinals`](crate::Searcher::global_ordinals).
+pub struct GlobalOrdinals {
+    segment_ids: Vec<SegmentId>,
+    /// For each segment, the global ordinal of each of its term ordinals.
+    segment_global_ords: Vec<Vec<TermOrdinal>>,
+    terms: GlobalTerms,
+}
+
+impl GlobalOrdinals {
+    /// Builds the global ordinals of the string fast field `field_name` for `segment_readers`.
+    ///
+    /// If all of the segments of `previous` are still part of `segment_readers`, the terms of
+    /// `previous` are merged with the dictionaries of the new segments only.
+    fn build(
+        field_name: &str,
+        segment_readers: &[SegmentReader],
+        previous: Option<&GlobalOrdinals>,
+    ) -> crate::Result<GlobalOrdinals> {
+        let segment_ids: Vec<SegmentId> = segment_readers
+            .iter()
+            .map(SegmentReader::segment_id)
+            .collect();
+        let previous = previous.filter(|previous| {
+            previous
+                .segment_ids
+                .iter()
+                .all(|segment_id| segment_ids.contains(segment_id))
+        });
+        let is_new_segment = |segment_id: &SegmentId| {
+            previous.map_or(true, |previous| !previous.segment_ids.contains(segment_id))
+        };
+        let mut columns: Vec<StrColumn> = Vec::new();
+        let mut column_segment_ords: Vec<usize> = Vec::new();
+        for (segment_ord, segment_reader) in segment_readers.iter().enumerate() {
+            if !is_new_segment(&segment_reader.segment_id()) {
+                continue;
+            }
+            if let Some(column) = segment_reader.fast_fields().str(field_name)? {
+                columns.push(column);
+                column_segment_ords.push(segment_ord);
+            }
+        }
+        let mut sources: Vec<Box<dyn Iterator<Item = Vec<u8>> + '_>> = Vec::new();
+        if let Some(previous) = previous {
+            let previous_terms =
+                (0..previous.num_terms()).map(move |global_ord| previous.term(global_ord).to_vec());
+            sources.push(Box::new(previous_terms));
+        }
+        for column in &columns {
+            let mut streamer = column.dictionary().stream()?;
+            sources.push(Box::new(std::iter::from_fn(move || {
+                streamer.advance().then(|| streamer.key().to_vec())
+            })));
+        }
+        let (terms, mut source_global_ords) = merge_sorted_terms(sources);
+
+        let mut segment_global_ords: Vec<Vec<TermOrdinal>> =
+            vec![Vec::new(); segment_readers.len()];
+        for (segment_ord, global_ords) in column_segment_ords
+            .into_iter()
+            .zip(source_global_ords.split_off(usize::from(previous.is_some())))
+        {
+            segment_global_ords[segment_ord] = global_ords;
+        }
+        if let Some(previous) = previous {
+            // The global ordinals of the previous segments are remapped to the new ones.
+            let remapping = &source_global_ords[0];
+            for (previous_segment_id, previous_global_ords) in previous
+                .segment_ids
+                .iter()
+                .zip(&previous.segment_global_ords)
+            {
+                let segment_ord = segment_ids
+                    .iter()
+                    .position(|segment_id| segment_id == previous_segment_id)
+                    .expect("the previous segments are part of the new ones");
+                segment_global_ords[segment_ord] = previous_global_ords
+                    .iter()
+                    .map(|&previous_global_ord| remapping[previous_global_ord as usize])
+                    .collect();
+            }
+        }
+        Ok(GlobalOrdinals {
+            segment_ids,
+            segment_global_ords,
+            terms,
+        })
+    }
+
+    /// Returns the number of distinct terms across all of the segments.
+    pub fn num_terms(&self) -> u64 {
+        self.terms.num_terms() as u64
+    }
+
+    /// Returns the global ordinal of the term with the ordinal `term_ord` in the segment
+    /// `segment_ord`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `term_ord` is not a term ordinal of the segment.
+    pub fn global_ord(&self, segment_ord: u32, term_ord: TermOrdinal) -> TermOrdinal {
+        self.segment_global_ords[segment_ord as usize][term_ord as usize]
+    }
+
+    /// Returns the global ordinals of the terms of the segment `segment_ord`, indexed by their
+    /// term ordinal in the segment.
+    ///
+    /// The slice is empty if the segment has no value for the field.
+    pub fn segment_global_ords(&self, segment_ord: u32) -> &[TermOrdinal] {
+        &self.segment_global_ords[segment_ord as usize]
+    }
+
+    /// Returns the term associated with the global ordinal `global_ord`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `global_ord` is greater than or equal to [`GlobalOrdinals::num_terms`].
+    pub fn term(&self, global_ord: TermOrdinal) -> &[u8] {
+        self.terms.term(global_ord as usize)
+    }
+
+    /// Returns the term associated with the global ordinal `global_ord` as a `str`.
+    ///
+    /// Returns `None` if the term is not valid UTF-8.
+    pub fn term_str(&self, global_ord: TermOrdinal) -> Option<&str> {
+        std::str::from_utf8(self.term(global_ord)).ok()
+    }
+
+    /// Returns the global ordinal of `term`, if any of the segments has it.
+    pub fn term_ord(&self, term: &[u8]) -> Option<TermOrdinal> {
+        self.terms
+            .term_ord(term)
+            .map(|global_ord| global_ord as TermOrdinal)
+    }
+}
+
+/// The terms of the global ordinals, concatenated in the order of their ordinals.
+struct GlobalTerms {
+    bytes: Vec<u8>,
+    /// The offset of the end of each term in `bytes`.
+    end_offsets: Vec<usize>,
+}
+
+impl GlobalTerms {
+    fn num_terms(&self) -> usize {
+        self.end_offsets.len()
+    }
+
+    fn term(&self, ord: usize) -> &[u8] {
+        let start = if ord == 0 {
+            0
+        } else {
+            self.end_offsets[ord - 1]
+        };
+        &self.bytes[start..self.end_offsets[ord]]
+    }
+
+    fn push(&mut self, term: &[u8]) {
+        self.bytes.extend_from_slice(term);
+        self.end_offsets.push(self.bytes.len());
+    }
+
+    fn term_ord(&self, term: &[u8]) -> Option<usize> {
+        let (mut low, mut high) = (0, self.num_terms());
+        while low < high {
+            let mid = low + (high - low) / 2;
+            match self.term(mid).cmp(term) {
+                std::cmp::Ordering::Less => low = mid + 1,
+                std::cmp::Ordering::Greater => high = mid,
+                std::cmp::Ordering::Equal => return Some(mid),
+            }
+        }
+        None
+    }
+}
+
+/// Merges sorted lists of distinct terms.
+///
+/// Returns the union of the terms and, for each list, the ordinal in the union of each of its
+/// terms.
+fn merge_sorted_terms(
+    sources: Vec<Box<dyn Iterator<Item = Vec<u8>> + '_>>,
+) -> (GlobalTerms, Vec<Vec<TermOrdinal>>) {
+    let mut terms = GlobalTerms {
+        bytes: Vec::new(),
+        end_offsets: Vec::new(),
+    };
+    let mut source_global_ords: Vec<Vec<TermOrdinal>> = vec![Vec::new(); sources.len()];
+    let mut sources = sources;
+    let mut heap: BinaryHeap<Reverse<(Vec<u8>, usize)>> = sources
+        .iter_mut()
+        .enumerate()
+        .filter_map(|(source_ord, source)| Some(Reverse((source.next()?, source_ord))))
+        .collect();
+    while let Some(Reverse((term, source_ord))) = heap.pop() {
+        let num_terms = terms.num_terms();
+        if num_terms == 0 || terms.term(num_terms - 1) != term.as_slice() {
+            terms.push(&term);
+        }
+        source_global_ords[source_ord].push((terms.num_terms() - 1) as TermOrdinal);
+        if let Some(next_term) = sources[source_ord].next() {
+            heap.push(Reverse((next_term, source_ord)));
+        }
+    }
+    (terms, source_global_ords)
+}
+
+/// Keeps the last [`GlobalOrdinals`] built for each field, shared by the searchers of an
+/// [`IndexReader`](crate::IndexReader).
+#[derive(Default)]
+pub(crate) struct GlobalOrdinalsCache {
+    global_ordinals: Mutex<HashMap<String, Arc<GlobalOrdinals>>>,
+}
+
+impl GlobalOrdinalsCache {
+    /// Returns the global ordinals of `field_name` for `segment_readers`, building them from the
+    /// cached ones if needed.
+    pub fn get_or_build(
+        &self,
+        field_name: &str,
+        segment_readers: &[SegmentReader],
+    ) -> crate::Result<Arc<GlobalOrdinals>> {
+        let previous = self
+            .global_ordinals
+            .lock()
+            .unwrap()
+            .get(field_name)
+            .cloned();
+        if let Some(previous) = &previous {
+            let is_up_to_date = previous
+                .segment_ids
+                .iter()
+                .copied()
+                .eq(segment_readers.iter().map(SegmentReader::segment_id));
+            if is_up_to_date {
+                return Ok(previous.clone());
+            }
+        }
+        // The lock is not held while building: searchers of other fields are not blocked.
+        let global_ordinals = Arc::new(GlobalOrdinals::build(
+            field_name,
+            segment_readers,
+            previous.as_deref(),
+        )?);
+        self.global_ordinals
+            .lock()
+            .unwrap()
+            .insert(field_name.to_string(), global_ordinals.clone());
+        Ok(global_ordinals)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::GlobalOrdinals;
+    use crate::schema::{Schema, FAST, STRING};
+    use crate::{doc, Index, IndexWriter, ReloadPolicy, Searcher};
+
+    fn check_global_ordinals(
+        searcher: &Searcher,
+        global_ordinals: &GlobalOrdinals,
+    ) -> crate::Result<()> {
+        for global_ord in 1..global_ordinals.num_terms() {
+            assert!(global_ordinals.term(global_ord - 1) < global_ordinals.term(global_ord));
+        }
+        for (segment_ord, segment_reader) in searcher.segment_readers().iter().enumerate() {
+            let global_ords = global_ordinals.segment_global_ords(segment_ord as u32);
+            let Some(column) = segment_reader.fast_fields().str("tag")? else {
+                assert!(global_ords.is_empty());
+                continue;
+            };
+            assert_eq!(global_ords.len(), column.num_terms());
+            let mut term = Vec::new();
+            for (term_ord, &global_ord) in global_ords.iter().enumerate() {
+                assert!(column.ord_to_bytes(term_ord as u64, &mut term)?);
+                assert_eq!(global_ordinals.term(global_ord), &term[..]);
+                assert_eq!(global_ordinals.term_ord(&term), Some(global_ord));
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_global_ordinals() -> crate::Result<()> {
+        let mut schema_builder = Schema::builder();
+        let tag = schema_builder.add_text_field("tag", STRING | FAST);
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer: IndexWriter = index.writer_for_tests()?;
+        index_writer.add_document(doc!(tag => "cherry"))?;
+        index_writer.add_document(doc!(tag => "apple", tag => "pear"))?;
+        index_writer.commit()?;
+        index_writer.add_document(doc!(tag => "banana"))?;
+        index_writer.add_document(doc!(tag => "pear"))?;
+        index_writer.add_document(doc!())?;
+        index_writer.commit()?;
+        let reader = index
+            .reader_builder()
+            .reload_policy(ReloadPolicy::Manual)
+            .try_into()?;
+        let searcher = reader.searcher();
+        assert_eq!(searcher.segment_readers().len(), 2);
+
+        let global_ordinals = searcher.global_ordinals("tag")?;
+        assert_eq!(global_ordinals.num_terms(), 4);
+        assert_eq!(global_ordinals.term_str(0), Some("apple"));
+        assert_eq!(global_ordinals.term_str(3), Some("pear"));
+        assert_eq!(global_ordinals.term_ord(b"kiwi"), None);
+        check_global_ordinals(&searcher, &global_ordinals)?;
+        // The global ordinals are cached.
+        assert!(Arc::ptr_eq(
+            &global_ordinals,
+            &reader.searcher().global_ordinals("tag")?
+        ));
+
+        // A new segment is merged with the previous global ordinals.
+        index_writer.add_document(doc!(tag => "kiwi"))?;
+        index_writer.add_document(doc!(tag => "apple"))?;
+        index_writer.commit()?;
+        reader.reload()?;
+        let searcher = reader.searcher();
+        let global_ordinals = searcher.global_ordinals("tag")?;
+        assert_eq!(global_ordinals.num_terms(), 5);
+        assert_eq!(global_ordinals.term_ord(b"kiwi"), Some(3));
+        check_global_ordinals(&searcher, &global_ordinals)?;
+        let rebuilt_global_ordinals =
+            GlobalOrdinals::build("tag", searcher.segment_readers(), None)?;
+        for segment_ord in 0..searcher.segment_readers().len() as u32 {
+            assert_eq!(
+                global_ordinals.segment_global_ords(segment_ord),
+                rebuilt_global_ordinals.segment_global_ords(segment_ord)
+            );
+        }
+
+        // After a merge, the global ordinals are rebuilt.
+        let segment_ids = index.searchable_segment_ids()?;
+        index_writer.merge(&segment_ids).wait()?;
+        reader.reload()?;
+        let searcher = reader.searcher();
+        let global_ordinals = searcher.global_ordinals("tag")?;
+        assert_eq!(global_ordinals.num_terms(), 5);
+        check_global_ordinals(&searcher, &global_ordinals)?;
+
+        let missing = searcher.global_ordinals("missing")?;
+        assert_eq!(missing.num_terms(), 0);
+        assert!(missing.segment_global_ords(0).is_empty());
+        Ok(())
+    }
+}
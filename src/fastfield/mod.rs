@@ -25,7 +25,10 @@ use columnar::MonotonicallyMappableToU64;
 pub use self::alive_bitset::{intersect_alive_bitsets, write_alive_bitset, AliveBitSet};
 pub use self::error::{FastFieldNotAvailableError, Result};
 pub use self::facet_reader::FacetReader;
+pub use self::global_ordinals::GlobalOrdinals;
+pub(crate) use self::global_ordinals::GlobalOrdinalsCache;
 pub use self::readers::FastFieldReaders;
+pub use self::values_block::FastFieldValuesBlock;
 pub use self::writer::FastFieldsWriter;
 use crate::schema::Type;
 use crate::DateTime;
@@ -33,7 +36,9 @@ use crate::DateTime;
 mod alive_bitset;
 mod error;
 mod facet_reader;
+mod global_ordinals;
 mod readers;
+mod values_block;
 mod writer;
 
 /// Trait for types that are allowed for fast fields:
@@ -79,6 +84,7 @@ mod tests {
     use std::ops::{Range, RangeInclusive};
     use std::path::Path;
 
+    use columnar::column_values::CodecType;
     use columnar::StrColumn;
     use common::{ByteCount, DateTimePrecision, HasLen, TerminatingWrite};
     use once_cell::sync::Lazy;
@@ -91,8 +97,8 @@ mod tests {
     use crate::index::SegmentId;
     use crate::merge_policy::NoMergePolicy;
     use crate::schema::{
-        DateOptions, Facet, FacetOptions, Field, JsonObjectOptions, Schema, SchemaBuilder,
-        TantivyDocument, TextOptions, FAST, INDEXED, STORED, STRING, TEXT,
+        DateOptions, Facet, FacetOptions, FastFieldCodec, Field, JsonObjectOptions, NumericOptions,
+        Schema, SchemaBuilder, TantivyDocument, TextOptions, FAST, INDEXED, STORED, STRING, TEXT,
     };
     use crate::time::OffsetDateTime;
     use crate::tokenizer::{LowerCaser, RawTokenizer, TextAnalyzer, TokenizerManager};
@@ -132,7 +138,7 @@ mod tests {
         }
         let file = directory.open_read(path).unwrap();
 
-        assert_eq!(file.len(), 80);
+        assert_eq!(file.len(), 119);
         let fast_field_readers = FastFieldReaders::open(file, SCHEMA.clone()).unwrap();
         let column = fast_field_readers
             .u64("field")
@@ -182,7 +188,7 @@ mod tests {
             write.terminate().unwrap();
         }
         let file = directory.open_read(path).unwrap();
-        assert_eq!(file.len(), 108);
+        assert_eq!(file.len(), 195);
         let fast_field_readers = FastFieldReaders::open(file, SCHEMA.clone()).unwrap();
         let col = fast_field_readers
             .u64("field")
@@ -215,7 +221,7 @@ mod tests {
             write.terminate().unwrap();
         }
         let file = directory.open_read(path).unwrap();
-        assert_eq!(file.len(), 81);
+        assert_eq!(file.len(), 151);
         let fast_field_readers = FastFieldReaders::open(file, SCHEMA.clone()).unwrap();
         let fast_field_reader = fast_field_readers
             .u64("field")
@@ -247,7 +253,7 @@ mod tests {
             write.terminate().unwrap();
         }
         let file = directory.open_read(path).unwrap();
-        assert_eq!(file.len(), 4476);
+        assert_eq!(file.len(), 5739);
         {
             let fast_field_readers = FastFieldReaders::open(file, SCHEMA.clone()).unwrap();
             let col = fast_field_readers
@@ -280,7 +286,7 @@ mod tests {
             write.terminate().unwrap();
         }
         let file = directory.open_read(path).unwrap();
-        assert_eq!(file.len(), 252);
+        assert_eq!(file.len(), 1384);
 
         {
             let fast_field_readers = FastFieldReaders::open(file, schema).unwrap();
@@ -682,6 +688,40 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_fastfield_pinned_codec() -> crate::Result<()> {
+        let mut schema_builder = Schema::builder();
+        let id_field = schema_builder.add_u64_field(
+            "id",
+            NumericOptions::from(FAST).set_fastfield_codec(FastFieldCodec::Bitpacked),
+        );
+        let other_id_field = schema_builder.add_u64_field("other_id", FAST);
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer: IndexWriter = index.writer_for_tests()?;
+        index_writer.set_merge_policy(Box::new(NoMergePolicy));
+        for segment_ord in 0..2u64 {
+            for i in segment_ord * 1_000..(segment_ord + 1) * 1_000 {
+                index_writer.add_document(doc!(id_field => i, other_id_field => i))?;
+            }
+            index_writer.commit()?;
+        }
+        let segment_ids = index.searchable_segment_ids()?;
+        index_writer.merge(&segment_ids).wait()?;
+        index_writer.wait_merging_threads()?;
+
+        let searcher = index.reader()?.searcher();
+        assert_eq!(searcher.segment_readers().len(), 1);
+        let columnar = searcher.segment_reader(0).fast_fields().columnar();
+        let values_codec = |column_name: &str| {
+            columnar.read_columns(column_name).unwrap()[0]
+                .values_codec()
+                .unwrap()
+        };
+        assert_eq!(values_codec("id"), Some(CodecType::Bitpacked));
+        assert_eq!(values_codec("other_id"), Some(CodecType::BlockwiseLinear));
+        Ok(())
+    }
+
     #[test]
     fn test_datefastfield() -> crate::Result<()> {
         let mut schema_builder = Schema::builder();
@@ -774,7 +814,7 @@ mod tests {
             write.terminate().unwrap();
         }
         let file = directory.open_read(path).unwrap();
-        assert_eq!(file.len(), 84);
+        assert_eq!(file.len(), 115);
         let fast_field_readers = FastFieldReaders::open(file, schema).unwrap();
         let bool_col = fast_field_readers.bool("field_bool").unwrap();
         assert_eq!(bool_col.first(0), Some(true));
@@ -806,7 +846,7 @@ mod tests {
             write.terminate().unwrap();
         }
         let file = directory.open_read(path).unwrap();
-        assert_eq!(file.len(), 96);
+        assert_eq!(file.len(), 127);
         let readers = FastFieldReaders::open(file, schema).unwrap();
         let bool_col = readers.bool("field_bool").unwrap();
         for i in 0..25 {
@@ -831,7 +871,7 @@ mod tests {
             write.terminate().unwrap();
         }
         let file = directory.open_read(path).unwrap();
-        assert_eq!(file.len(), 86);
+        assert_eq!(file.len(), 101);
         let fastfield_readers = FastFieldReaders::open(file, schema).unwrap();
         let col = fastfield_readers.bool("field_bool").unwrap();
         assert_eq!(col.first(0), None);
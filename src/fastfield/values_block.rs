@@ -0,0 +1,112 @@
+use columnar::{Column, DynamicColumn, HasAssociatedColumnType};
+
+use super::FastFieldReaders;
+use crate::DocId;
+
+/// The first values of several fast fields for a list of documents, laid out column by
+/// column.
+///
+/// The values of each field are stored contiguously, in the order of the documents given
+/// to [`FastFieldReaders::values_block`], so that they can be handed as is to code
+/// processing one feature at a time.
+#[derive(Clone, Debug, PartialEq)]
+pub struct FastFieldValuesBlock<T> {
+    num_docs: usize,
+    values: Vec<Option<T>>,
+}
+
+impl<T> FastFieldValuesBlock<T> {
+    /// Returns the number of documents in the block.
+    pub fn num_docs(&self) -> usize {
+        self.num_docs
+    }
+
+    /// Returns the number of fields in the block.
+    pub fn num_columns(&self) -> usize {
+        if self.num_docs == 0 {
+            0
+        } else {
+            self.values.len() / self.num_docs
+        }
+    }
+
+    /// Returns the values of the `column_ord`-th requested field, one per document.
+    ///
+    /// A document without any value for the field gets `None`.
+    pub fn column(&self, column_ord: usize) -> &[Option<T>] {
+        &self.values[column_ord * self.num_docs..(column_ord + 1) * self.num_docs]
+    }
+
+    /// Iterates over the columns of the block, in the order of the requested fields.
+    pub fn columns(&self) -> impl Iterator<Item = &[Option<T>]> + '_ {
+        (0..self.num_columns()).map(move |column_ord| self.column(column_ord))
+    }
+}
+
+impl FastFieldReaders {
+    /// Fetches, for each of the given documents, the first value of each of the fast
+    /// fields `field_names`.
+    ///
+    /// All of the fields are read as `T`: the values of a field that has no column of
+    /// that type are all `None`. Each field is read with a single call over all of the
+    /// documents, which is much faster than reading the values document by document.
+    pub fn values_block<T>(
+        &self,
+        field_names: &[&str],
+        doc_ids: &[DocId],
+    ) -> crate::Result<FastFieldValuesBlock<T>>
+    where
+        T: HasAssociatedColumnType,
+        DynamicColumn: Into<Option<Column<T>>>,
+    {
+        let num_docs = doc_ids.len();
+        let mut values = vec![None; field_names.len() * num_docs];
+        if num_docs > 0 {
+            for (field_name, column_values) in field_names.iter().zip(values.chunks_mut(num_docs)) {
+                if let Some(column) = self.column_opt::<T>(field_name)? {
+                    column.first_vals(doc_ids, column_values);
+                }
+            }
+        }
+        Ok(FastFieldValuesBlock { num_docs, values })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::schema::{Schema, FAST, INDEXED};
+    use crate::{doc, Index, IndexWriter};
+
+    #[test]
+    fn test_values_block() -> crate::Result<()> {
+        let mut schema_builder = Schema::builder();
+        let price = schema_builder.add_f64_field("price", FAST);
+        let rating = schema_builder.add_f64_field("rating", FAST);
+        let tags = schema_builder.add_f64_field("tags", FAST);
+        schema_builder.add_f64_field("not_fast", INDEXED);
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer: IndexWriter = index.writer_for_tests()?;
+        index_writer.add_document(doc!(price => 1.0, rating => 4.5, tags => 1.0, tags => 2.0))?;
+        index_writer.add_document(doc!(price => 2.0))?;
+        index_writer.add_document(doc!(price => 3.0, rating => 3.0))?;
+        index_writer.commit()?;
+        let searcher = index.reader()?.searcher();
+        let fast_fields = searcher.segment_reader(0).fast_fields();
+
+        let block = fast_fields.values_block::<f64>(&["price", "rating", "tags"], &[2, 0, 1])?;
+        assert_eq!(block.num_docs(), 3);
+        assert_eq!(block.num_columns(), 3);
+        assert_eq!(block.column(0), &[Some(3.0), Some(1.0), Some(2.0)]);
+        assert_eq!(block.column(1), &[Some(3.0), Some(4.5), None]);
+        assert_eq!(block.column(2), &[None, Some(1.0), None]);
+        assert_eq!(block.columns().count(), 3);
+
+        // Fields without a column of the requested type have no values.
+        let block = fast_fields.values_block::<u64>(&["price"], &[0])?;
+        assert_eq!(block.column(0), &[None]);
+        assert!(fast_fields
+            .values_block::<f64>(&["not_fast"], &[0])
+            .is_err());
+        Ok(())
+    }
+}
@@ -20,6 +20,10 @@ pub struct FastFieldsWriter {
     per_field_tokenizer: Vec<Option<TextAnalyzer>>,
     date_precisions: Vec<DateTimePrecision>,
     expand_dots: Vec<bool>,
+    /// Field in which the sequence numbers of the documents are recorded.
+    sequence_number_field: Option<Field>,
+    /// Number of threads encoding the columns in `serialize`.
+    num_serialization_threads: usize,
     num_docs: DocId,
     // Buffer that we recycle to avoid allocation.
     json_path_buffer: JsonPathWriter,
@@ -80,6 +84,10 @@ impl FastFieldsWriter {
                 }
             }
 
+            if let Some(fastfield_codec) = field_entry.field_type().fastfield_codec() {
+                columnar_writer.set_column_codec(field_entry.name(), fastfield_codec.codec_type());
+            }
+
             let sort_values_within_row = value_type == Type::Facet;
             if let Some(column_type) = value_type_to_column_type(value_type) {
                 columnar_writer.record_column_type(
@@ -96,19 +104,55 @@ impl FastFieldsWriter {
             num_docs: 0u32,
             date_precisions,
             expand_dots,
+            sequence_number_field: None,
+            num_serialization_threads: 1,
             json_path_buffer: JsonPathWriter::default(),
         })
     }
 
+    /// Records the sequence numbers passed to
+    /// [`add_document_with_sequence_number`](Self::add_document_with_sequence_number) in
+    /// `field`, instead of the values of the documents.
+    pub(crate) fn set_sequence_number_field(&mut self, field: Field) {
+        self.sequence_number_field = Some(field);
+    }
+
+    /// Encodes the columns concurrently on up to `num_threads` threads in
+    /// [`serialize`](Self::serialize).
+    pub(crate) fn set_num_serialization_threads(&mut self, num_threads: usize) {
+        self.num_serialization_threads = num_threads;
+    }
+
     /// The memory used (inclusive childs)
     pub fn mem_usage(&self) -> usize {
         self.columnar_writer.mem_usage()
     }
 
+    /// Indexes all of the fastfields of a new document, along with its sequence number.
+    pub(crate) fn add_document_with_sequence_number<D: Document>(
+        &mut self,
+        doc: &D,
+        sequence_number: u64,
+    ) -> crate::Result<()> {
+        if let Some(field) = self.sequence_number_field {
+            if let Some(field_name) = &self.fast_field_names[field.field_id() as usize] {
+                self.columnar_writer.record_numerical(
+                    self.num_docs,
+                    field_name,
+                    NumericalValue::from(sequence_number),
+                );
+            }
+        }
+        self.add_document(doc)
+    }
+
     /// Indexes all of the fastfields of a new document.
     pub fn add_document<D: Document>(&mut self, doc: &D) -> crate::Result<()> {
         let doc_id = self.num_docs;
         for (field, value) in doc.iter_fields_and_values() {
+            if Some(field) == self.sequence_number_field {
+                continue;
+            }
             let value_access = value as D::Value<'_>;
 
             self.add_doc_value(doc_id, field, value_access)?;
@@ -224,7 +268,11 @@ impl FastFieldsWriter {
     /// order to the fast field serializer.
     pub fn serialize(mut self, wrt: &mut dyn io::Write) -> io::Result<()> {
         let num_docs = self.num_docs;
-        self.columnar_writer.serialize(num_docs, wrt)?;
+        self.columnar_writer.serialize_with_num_threads(
+            num_docs,
+            self.num_serialization_threads,
+            wrt,
+        )?;
         Ok(())
     }
 }
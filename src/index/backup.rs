@@ -0,0 +1,248 @@
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use super::index::snapshot_files;
+use super::IndexMeta;
+use crate::core::META_FILEPATH;
+use crate::directory::{
+    copy_file, file_checksum, write_managed_paths, Directory, ManagedDirectory,
+};
+use crate::error::DataCorruption;
+use crate::Opstamp;
+
+/// Path of the manifest of the most recent backup.
+static LATEST_MANIFEST_FILEPATH: &str = "backup.json";
+
+/// A file shipped as part of a backup.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct BackupFile {
+    /// Path of the file, relative to the index directory.
+    pub path: PathBuf,
+    /// Size of the file in bytes, footer included.
+    pub num_bytes: u64,
+    /// Checksum of the file, as recorded in its footer.
+    pub crc: u32,
+    /// Opstamp of the backup that shipped the file.
+    pub backup_opstamp: Opstamp,
+}
+
+/// Describes the content of a backup.
+///
+/// A backup location is a directory that accumulates segment files across backups.
+/// Each backup writes a manifest listing the files of the backed up commit. Since
+/// segment files are immutable, a file that was already shipped by the previous backup
+/// is not shipped again.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct BackupManifest {
+    opstamp: Opstamp,
+    files: Vec<BackupFile>,
+    meta: serde_json::Value,
+}
+
+impl BackupManifest {
+    /// Returns the path of the manifest of the backup of a given commit.
+    pub fn manifest_path(opstamp: Opstamp) -> PathBuf {
+        PathBuf::from(format!("backup.{opstamp}.json"))
+    }
+
+    /// Opstamp of the backed up commit.
+    pub fn opstamp(&self) -> Opstamp {
+        self.opstamp
+    }
+
+    /// Files that are part of the backup, including those shipped by previous backups.
+    pub fn files(&self) -> &[BackupFile] {
+        &self.files
+    }
+
+    /// Loads the manifest of the backup of the commit with the given opstamp.
+    pub fn load(backup_directory: &dyn Directory, opstamp: Opstamp) -> crate::Result<Self> {
+        load_manifest(backup_directory, &Self::manifest_path(opstamp))
+    }
+
+    /// Loads the manifest of the most recent backup, if any.
+    pub fn load_latest(backup_directory: &dyn Directory) -> crate::Result<Option<Self>> {
+        let path = Path::new(LATEST_MANIFEST_FILEPATH);
+        if !backup_directory.exists(path)? {
+            return Ok(None);
+        }
+        load_manifest(backup_directory, path).map(Some)
+    }
+
+    /// Restores the backup into `dest`, which then contains a regular index.
+    ///
+    /// The checksum of every restored file is verified, and a `DataCorruption` error
+    /// is returned on mismatch. `dest` should be an empty, raw directory.
+    pub fn restore(
+        &self,
+        backup_directory: &dyn Directory,
+        dest: &dyn Directory,
+    ) -> crate::Result<()> {
+        let mut managed_paths = HashSet::new();
+        for file in &self.files {
+            copy_file(backup_directory, &file.path, dest)?;
+            let crc = file_checksum(&file.path, dest.open_read(&file.path)?, true)?;
+            if crc != file.crc {
+                return Err(DataCorruption::new(
+                    file.path.clone(),
+                    format!(
+                        "Checksum mismatch: backup manifest says {:#010x}, file says {crc:#010x}.",
+                        file.crc
+                    ),
+                )
+                .into());
+            }
+            managed_paths.insert(file.path.clone());
+        }
+        managed_paths.insert(META_FILEPATH.to_path_buf());
+        write_managed_paths(dest, &managed_paths)?;
+        let mut meta = serde_json::to_vec_pretty(&self.meta)?;
+        meta.push(b'\n');
+        dest.sync_directory()?;
+        dest.atomic_write(&META_FILEPATH, &meta)?;
+        Ok(())
+    }
+}
+
+fn load_manifest(backup_directory: &dyn Directory, path: &Path) -> crate::Result<BackupManifest> {
+    let data = backup_directory.atomic_read(path)?;
+    serde_json::from_slice(&data).map_err(|err| {
+        DataCorruption::new(
+            path.to_path_buf(),
+            format!("Backup manifest cannot be deserialized: {err:?}."),
+        )
+        .into()
+    })
+}
+
+pub(crate) fn create_backup(
+    directory: &ManagedDirectory,
+    commit: &IndexMeta,
+    backup_directory: &dyn Directory,
+    previous: Option<&BackupManifest>,
+) -> crate::Result<BackupManifest> {
+    let previous_files: HashMap<&Path, &BackupFile> = previous
+        .map(|manifest| {
+            manifest
+                .files
+                .iter()
+                .map(|file| (file.path.as_path(), file))
+                .collect()
+        })
+        .unwrap_or_default();
+    let mut files = Vec::new();
+    for segment_meta in &commit.segments {
+        for path in snapshot_files(segment_meta, &commit.schema) {
+            let file_slice = directory.open_read_raw(&path)?;
+            let num_bytes = file_slice.len() as u64;
+            let crc = file_checksum(&path, file_slice, false)?;
+            let backup_opstamp = match previous_files.get(path.as_path()) {
+                Some(previous_file) if previous_file.crc == crc => previous_file.backup_opstamp,
+                _ => {
+                    directory.copy_raw_file(&path, backup_directory)?;
+                    commit.opstamp
+                }
+            };
+            files.push(BackupFile {
+                path,
+                num_bytes,
+                crc,
+                backup_opstamp,
+            });
+        }
+    }
+    let manifest = BackupManifest {
+        opstamp: commit.opstamp,
+        files,
+        meta: serde_json::to_value(commit)?,
+    };
+    let mut manifest_json = serde_json::to_vec_pretty(&manifest)?;
+    manifest_json.push(b'\n');
+    backup_directory.sync_directory()?;
+    backup_directory.atomic_write(
+        &BackupManifest::manifest_path(commit.opstamp),
+        &manifest_json,
+    )?;
+    backup_directory.atomic_write(Path::new(LATEST_MANIFEST_FILEPATH), &manifest_json)?;
+    Ok(manifest)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::BackupManifest;
+    use crate::directory::{Directory, RamDirectory, TerminatingWrite};
+    use crate::indexer::NoMergePolicy;
+    use crate::schema::{Schema, INDEXED};
+    use crate::{Index, IndexWriter};
+
+    #[test]
+    fn test_incremental_backup_and_restore() -> crate::Result<()> {
+        let mut schema_builder = Schema::builder();
+        let field = schema_builder.add_u64_field("num_likes", INDEXED);
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut writer: IndexWriter = index.writer_for_tests()?;
+        writer.set_merge_policy(Box::new(NoMergePolicy));
+        writer.add_document(doc!(field => 1u64))?;
+        writer.commit()?;
+
+        let backup_directory = RamDirectory::create();
+        assert!(BackupManifest::load_latest(&backup_directory)?.is_none());
+        let first = index.backup(&index.load_metas()?, &backup_directory, None)?;
+        assert_eq!(first.files().len(), 6);
+
+        writer.add_document(doc!(field => 2u64))?;
+        writer.commit()?;
+        let previous = BackupManifest::load_latest(&backup_directory)?.unwrap();
+        assert_eq!(previous.opstamp(), first.opstamp());
+        let second = index.backup(&index.load_metas()?, &backup_directory, Some(&previous))?;
+        assert_eq!(second.files().len(), 12);
+        let num_shipped = second
+            .files()
+            .iter()
+            .filter(|file| file.backup_opstamp == second.opstamp())
+            .count();
+        assert_eq!(num_shipped, 6);
+
+        for (manifest, num_docs) in [(first, 1), (second, 2)] {
+            let restore_directory = RamDirectory::create();
+            BackupManifest::load(&backup_directory, manifest.opstamp())?
+                .restore(&backup_directory, &restore_directory)?;
+            let restored = Index::open(restore_directory)?;
+            assert_eq!(restored.reader()?.searcher().num_docs(), num_docs);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_restore_detects_corruption() -> crate::Result<()> {
+        let mut schema_builder = Schema::builder();
+        let field = schema_builder.add_u64_field("num_likes", INDEXED);
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut writer: IndexWriter = index.writer_for_tests()?;
+        writer.add_document(doc!(field => 1u64))?;
+        writer.commit()?;
+
+        let backup_directory = RamDirectory::create();
+        let manifest = index.backup(&index.load_metas()?, &backup_directory, None)?;
+        let path = &manifest.files()[0].path;
+        let mut data = backup_directory
+            .open_read(path)?
+            .read_bytes()?
+            .as_slice()
+            .to_vec();
+        data[0] ^= 1;
+        backup_directory.delete(path)?;
+        let mut wrt = backup_directory.open_write(path)?;
+        std::io::Write::write_all(&mut wrt, &data)?;
+        wrt.terminate()?;
+
+        let restore_directory = RamDirectory::create();
+        assert!(matches!(
+            manifest.restore(&backup_directory, &restore_directory),
+            Err(crate::TantivyError::DataCorruption(_))
+        ));
+        Ok(())
+    }
+}
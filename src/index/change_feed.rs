@@ -0,0 +1,272 @@
+use std::collections::HashMap;
+use std::ops::Range;
+use std::sync::{Arc, Mutex};
+
+use serde::{Deserialize, Serialize};
+
+use super::{Index, IndexMeta, SegmentId, SegmentMeta};
+use crate::directory::{WatchCallback, WatchHandle};
+use crate::Opstamp;
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+struct CheckpointSegment {
+    segment_id: SegmentId,
+    max_doc: u32,
+    num_deleted_docs: u32,
+}
+
+/// Position in the history of the commits of an index, from which changes are computed.
+///
+/// The default checkpoint is the empty index: the changes since then are all the segments of
+/// the index. Checkpoints implement `Serialize`, so that a downstream system can persist the
+/// position it has consumed changes up to.
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ChangeCheckpoint {
+    opstamp: Opstamp,
+    segments: Vec<CheckpointSegment>,
+}
+
+impl ChangeCheckpoint {
+    /// Returns the checkpoint of a commit.
+    pub fn for_commit(commit: &IndexMeta) -> ChangeCheckpoint {
+        let segments = commit
+            .segments
+            .iter()
+            .map(|segment_meta| CheckpointSegment {
+                segment_id: segment_meta.id(),
+                max_doc: segment_meta.max_doc(),
+                num_deleted_docs: segment_meta.num_deleted_docs(),
+            })
+            .collect();
+        ChangeCheckpoint {
+            opstamp: commit.opstamp,
+            segments,
+        }
+    }
+
+    /// Opstamp of the commit of the checkpoint.
+    pub fn opstamp(&self) -> Opstamp {
+        self.opstamp
+    }
+}
+
+/// Deletes committed on a segment that was already part of the checkpoint.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SegmentDeletes {
+    /// Segment the documents were deleted from.
+    pub segment_id: SegmentId,
+    /// Number of deleted documents of the segment as of the checkpoint.
+    pub previous_num_deleted_docs: u32,
+    /// Number of deleted documents of the segment as of the new commit.
+    pub num_deleted_docs: u32,
+}
+
+/// Changes of an index between a checkpoint and a commit.
+///
+/// Merges show up as removed segments plus an added segment holding their documents, without
+/// a change of opstamp if no commit happened in between.
+#[derive(Clone, Debug)]
+pub struct IndexChanges {
+    previous_opstamp: Opstamp,
+    added_segments: Vec<SegmentMeta>,
+    removed_segments: Vec<SegmentId>,
+    new_deletes: Vec<SegmentDeletes>,
+    payload: Option<String>,
+    checkpoint: ChangeCheckpoint,
+}
+
+impl IndexChanges {
+    /// Computes the changes between `checkpoint` and `commit`.
+    pub fn between(checkpoint: &ChangeCheckpoint, commit: &IndexMeta) -> IndexChanges {
+        let previous_segments: HashMap<SegmentId, &CheckpointSegment> = checkpoint
+            .segments
+            .iter()
+            .map(|segment| (segment.segment_id, segment))
+            .collect();
+        let mut added_segments = Vec::new();
+        let mut new_deletes = Vec::new();
+        for segment_meta in &commit.segments {
+            match previous_segments.get(&segment_meta.id()) {
+                None => added_segments.push(segment_meta.clone()),
+                Some(previous_segment)
+                    if previous_segment.num_deleted_docs != segment_meta.num_deleted_docs() =>
+                {
+                    new_deletes.push(SegmentDeletes {
+                        segment_id: segment_meta.id(),
+                        previous_num_deleted_docs: previous_segment.num_deleted_docs,
+                        num_deleted_docs: segment_meta.num_deleted_docs(),
+                    });
+                }
+                Some(_) => {}
+            }
+        }
+        let removed_segments = checkpoint
+            .segments
+            .iter()
+            .map(|segment| segment.segment_id)
+            .filter(|segment_id| {
+                !commit
+                    .segments
+                    .iter()
+                    .any(|segment_meta| segment_meta.id() == *segment_id)
+            })
+            .collect();
+        IndexChanges {
+            previous_opstamp: checkpoint.opstamp,
+            added_segments,
+            removed_segments,
+            new_deletes,
+            payload: commit.payload.clone(),
+            checkpoint: ChangeCheckpoint::for_commit(commit),
+        }
+    }
+
+    /// Range of the opstamps of the operations committed since the checkpoint.
+    pub fn opstamps(&self) -> Range<Opstamp> {
+        self.previous_opstamp..self.checkpoint.opstamp
+    }
+
+    /// Segments that were not part of the checkpoint, created by a commit or a merge.
+    pub fn added_segments(&self) -> &[SegmentMeta] {
+        &self.added_segments
+    }
+
+    /// Segments of the checkpoint that are not part of the index anymore, typically because
+    /// they were merged or all of their documents were deleted.
+    pub fn removed_segments(&self) -> &[SegmentId] {
+        &self.removed_segments
+    }
+
+    /// Segments of the checkpoint on which documents were deleted.
+    pub fn new_deletes(&self) -> &[SegmentDeletes] {
+        &self.new_deletes
+    }
+
+    /// Payload of the new commit.
+    pub fn payload(&self) -> Option<&str> {
+        self.payload.as_deref()
+    }
+
+    /// Checkpoint of the new commit, from which the next changes are to be computed.
+    pub fn checkpoint(&self) -> &ChangeCheckpoint {
+        &self.checkpoint
+    }
+
+    /// Returns true if the index did not change since the checkpoint.
+    pub fn is_empty(&self) -> bool {
+        self.added_segments.is_empty()
+            && self.removed_segments.is_empty()
+            && self.new_deletes.is_empty()
+            && self.previous_opstamp == self.checkpoint.opstamp
+    }
+}
+
+/// Registers `callback`, called with the changes of the index since `checkpoint`, once right
+/// away and then whenever `meta.json` changes.
+pub(crate) fn watch_changes<F>(
+    index: &Index,
+    checkpoint: ChangeCheckpoint,
+    callback: F,
+) -> crate::Result<WatchHandle>
+where
+    F: Fn(&IndexChanges) + Send + Sync + 'static,
+{
+    let checkpoint = Arc::new(Mutex::new(checkpoint));
+    let poll = {
+        let index = index.clone();
+        move || -> crate::Result<()> {
+            // The lock also prevents the callback from being called concurrently.
+            let mut checkpoint = checkpoint.lock().unwrap();
+            let changes = index.changes_since(&checkpoint)?;
+            if !changes.is_empty() {
+                callback(&changes);
+                *checkpoint = changes.checkpoint;
+            }
+            Ok(())
+        }
+    };
+    poll()?;
+    let watch_callback = WatchCallback::new(move || {
+        if let Err(err) = poll() {
+            warn!("Failed to compute the changes of the index: {err:?}");
+        }
+    });
+    index.directory().watch(watch_callback)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::mpsc;
+    use std::time::Duration;
+
+    use super::ChangeCheckpoint;
+    use crate::indexer::NoMergePolicy;
+    use crate::schema::{Schema, INDEXED};
+    use crate::{Index, IndexWriter, Term};
+
+    #[test]
+    fn test_changes_since() -> crate::Result<()> {
+        let mut schema_builder = Schema::builder();
+        let id = schema_builder.add_u64_field("id", INDEXED);
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut writer: IndexWriter = index.writer_for_tests()?;
+        writer.set_merge_policy(Box::new(NoMergePolicy));
+
+        let changes = index.changes_since(&ChangeCheckpoint::default())?;
+        assert!(changes.is_empty());
+
+        writer.add_document(doc!(id => 1u64))?;
+        writer.add_document(doc!(id => 2u64))?;
+        writer.commit()?;
+        let changes = index.changes_since(&ChangeCheckpoint::default())?;
+        assert_eq!(changes.opstamps(), 0..2);
+        assert_eq!(changes.added_segments().len(), 1);
+        let first_segment_id = changes.added_segments()[0].id();
+        let checkpoint = changes.checkpoint().clone();
+        assert_eq!(checkpoint, index.change_checkpoint()?);
+        assert!(index.changes_since(&checkpoint)?.is_empty());
+
+        writer.delete_term(Term::from_field_u64(id, 1));
+        writer.add_document(doc!(id => 3u64))?;
+        writer.commit()?;
+        let changes = index.changes_since(&checkpoint)?;
+        assert_eq!(changes.opstamps(), 2..4);
+        assert_eq!(changes.added_segments().len(), 1);
+        assert!(changes.removed_segments().is_empty());
+        assert_eq!(changes.new_deletes().len(), 1);
+        assert_eq!(changes.new_deletes()[0].segment_id, first_segment_id);
+        assert_eq!(changes.new_deletes()[0].previous_num_deleted_docs, 0);
+        assert_eq!(changes.new_deletes()[0].num_deleted_docs, 1);
+
+        let checkpoint_json = serde_json::to_string(changes.checkpoint())?;
+        let checkpoint: ChangeCheckpoint = serde_json::from_str(&checkpoint_json)?;
+        let segment_ids = index.searchable_segment_ids()?;
+        writer.merge(&segment_ids).wait()?;
+        let changes = index.changes_since(&checkpoint)?;
+        assert!(changes.opstamps().is_empty());
+        assert_eq!(changes.added_segments().len(), 1);
+        assert_eq!(changes.removed_segments().len(), 2);
+        Ok(())
+    }
+
+    #[test]
+    fn test_watch_changes() -> crate::Result<()> {
+        let mut schema_builder = Schema::builder();
+        let id = schema_builder.add_u64_field("id", INDEXED);
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut writer: IndexWriter = index.writer_for_tests()?;
+        writer.add_document(doc!(id => 1u64))?;
+        writer.commit()?;
+
+        let (sender, receiver) = mpsc::channel();
+        let sender = std::sync::Mutex::new(sender);
+        let _watch_handle = index.watch_changes(ChangeCheckpoint::default(), move |changes| {
+            let _ = sender.lock().unwrap().send(changes.opstamps());
+        })?;
+        assert_eq!(receiver.recv_timeout(Duration::from_secs(1)).unwrap(), 0..1);
+        writer.add_document(doc!(id => 2u64))?;
+        writer.commit()?;
+        assert_eq!(receiver.recv_timeout(Duration::from_secs(5)).unwrap(), 1..2);
+        Ok(())
+    }
+}
@@ -3,22 +3,31 @@ use std::fmt;
 #[cfg(feature = "mmap")]
 use std::path::Path;
 use std::path::PathBuf;
+use std::sync::Arc;
 use std::thread::available_parallelism;
 
+use super::backup::create_backup;
 use super::segment::Segment;
 use super::segment_reader::merge_field_meta_data;
-use super::{FieldMetadata, IndexSettings};
+use super::{change_feed, FieldMetadata, IndexSettings};
 use crate::core::{Executor, META_FILEPATH};
 use crate::directory::error::OpenReadError;
 #[cfg(feature = "mmap")]
 use crate::directory::MmapDirectory;
-use crate::directory::{Directory, ManagedDirectory, RamDirectory, INDEX_WRITER_LOCK};
+use crate::directory::{
+    write_managed_paths, Directory, HotcacheManifest, IoMetrics, IoStats, LockManager,
+    ManagedDirectory, RamDirectory, RecordingDirectory, WatchHandle, INDEX_WRITER_LOCK,
+};
 use crate::error::{DataCorruption, TantivyError};
-use crate::index::{IndexMeta, SegmentId, SegmentMeta, SegmentMetaInventory};
+use crate::index::{
+    BackupManifest, ChangeCheckpoint, IndexChanges, IndexMeta, ReplicationManifest,
+    SegmentComponent, SegmentId, SegmentMeta, SegmentMetaInventory,
+};
 use crate::indexer::index_writer::{MAX_NUM_THREAD, MEMORY_BUDGET_NUM_BYTES_MIN};
 use crate::indexer::segment_updater::save_metas;
 use crate::indexer::{IndexWriter, SingleSegmentIndexWriter};
-use crate::reader::{IndexReader, IndexReaderBuilder};
+use crate::metrics::IndexMetrics;
+use crate::reader::{IndexReader, IndexReaderBuilder, ReloadPolicy};
 use crate::schema::document::Document;
 use crate::schema::{Field, FieldType, Schema};
 use crate::tokenizer::{TextAnalyzer, TokenizerManager};
@@ -46,6 +55,26 @@ fn load_metas(
         .map_err(From::from)
 }
 
+/// Lists the files of a committed segment, as they should appear in a snapshot.
+pub(crate) fn snapshot_files<'a>(
+    segment_meta: &'a SegmentMeta,
+    schema: &Schema,
+) -> impl Iterator<Item = PathBuf> + 'a {
+    let has_blobs = schema.has_blob_fields();
+    let has_term_vectors = schema.has_term_vector_fields();
+    let has_vector_index = schema.has_dense_vector_fields();
+    SegmentComponent::iterator()
+        .filter(move |component| match component {
+            SegmentComponent::TempStore => false,
+            SegmentComponent::Delete => segment_meta.has_deletes(),
+            SegmentComponent::Blobs => has_blobs,
+            SegmentComponent::TermVectors => has_term_vectors,
+            SegmentComponent::VectorIndex => has_vector_index,
+            _ => true,
+        })
+        .map(move |component| segment_meta.relative_path(*component))
+}
+
 /// Save the index meta file.
 /// This operation is atomic :
 /// Either
@@ -232,7 +261,8 @@ impl IndexBuilder {
     }
 
     fn validate(&self) -> crate::Result<()> {
-        if let Some(_schema) = self.schema.as_ref() {
+        if let Some(schema) = self.schema.as_ref() {
+            self.index_settings.resolve_sequence_number_field(schema)?;
             Ok(())
         } else {
             Err(TantivyError::InvalidArgument(
@@ -272,6 +302,7 @@ pub struct Index {
     tokenizers: TokenizerManager,
     fast_field_tokenizers: TokenizerManager,
     inventory: SegmentMetaInventory,
+    metrics: Arc<IndexMetrics>,
 }
 
 impl Index {
@@ -303,6 +334,27 @@ impl Index {
         Ok(())
     }
 
+    /// Sets the [`LockManager`] used to acquire the index locks, such as the
+    /// [`INDEX_WRITER_LOCK`].
+    ///
+    /// All of the processes accessing the index should use the same lock manager.
+    pub fn set_lock_manager(&mut self, lock_manager: Arc<dyn LockManager>) {
+        self.directory.set_lock_manager(lock_manager);
+    }
+
+    /// Sets the [`IoMetrics`] counting the reads and writes performed on this index.
+    ///
+    /// Readers and writers created after this call are accounted for.
+    pub fn set_io_metrics(&mut self, io_metrics: IoMetrics) {
+        self.directory.set_io_metrics(io_metrics);
+    }
+
+    /// Returns the I/O statistics of this index, broken down by kind of file, or `None`
+    /// if no [`IoMetrics`] were set with [`Index::set_io_metrics()`].
+    pub fn io_stats(&self) -> Option<IoStats> {
+        self.directory.io_metrics().map(IoMetrics::stats)
+    }
+
     /// Custom thread pool by a outer thread pool.
     pub fn set_executor(&mut self, executor: Executor) {
         self.executor = executor;
@@ -391,9 +443,16 @@ impl Index {
             fast_field_tokenizers: TokenizerManager::default(),
             executor: Executor::single_thread(),
             inventory,
+            metrics: Arc::default(),
         }
     }
 
+    /// Returns the metrics of the activity of the index, shared by its clones and by the
+    /// readers and writers opened on them.
+    pub fn metrics(&self) -> &IndexMetrics {
+        &self.metrics
+    }
+
     /// Setter for the tokenizer manager.
     pub fn set_tokenizers(&mut self, tokenizers: TokenizerManager) {
         self.tokenizers = tokenizers;
@@ -516,11 +575,146 @@ impl Index {
         Ok(index)
     }
 
+    /// Generates a [`HotcacheManifest`] listing the byte ranges that are read when opening a
+    /// searcher on the last commit, term dictionaries of the indexed fields included.
+    ///
+    /// See [`HotDirectory`](crate::directory::HotDirectory) to preload those ranges.
+    pub fn hotcache_manifest(&self) -> crate::Result<HotcacheManifest> {
+        let recording_directory = RecordingDirectory::wrap(self.directory.raw_directory());
+        let index = Index::open(recording_directory.clone())?;
+        let reader: IndexReader = index
+            .reader_builder()
+            .reload_policy(ReloadPolicy::Manual)
+            .try_into()?;
+        let searcher = reader.searcher();
+        let schema = index.schema();
+        for segment_reader in searcher.segment_readers() {
+            for (field, field_entry) in schema.fields() {
+                if field_entry.is_indexed() {
+                    segment_reader.inverted_index(field)?;
+                }
+            }
+        }
+        Ok(recording_directory.manifest())
+    }
+
     /// Reads the index meta file from the directory.
     pub fn load_metas(&self) -> crate::Result<IndexMeta> {
         load_metas(self.directory(), &self.inventory)
     }
 
+    /// Copies exactly the files referenced by a commit into `dest`, and returns
+    /// the list of the segment files that were copied.
+    ///
+    /// Files are hard linked whenever both directories live on the local filesystem,
+    /// and copied otherwise. The snapshot gets its own `meta.json`, so it can be opened
+    /// as a regular index with [`Index::open_snapshot`].
+    ///
+    /// The commit is typically obtained with [`Index::load_metas`]. As long as it is
+    /// alive, the garbage collector will not remove the files it references, so
+    /// the index can keep being written to while the snapshot is being created.
+    ///
+    /// `dest` should be an empty, raw directory (as opposed to the directory of another
+    /// `Index`).
+    pub fn create_snapshot(
+        &self,
+        commit: &IndexMeta,
+        dest: &dyn Directory,
+    ) -> crate::Result<Vec<PathBuf>> {
+        let mut files = Vec::new();
+        for segment_meta in &commit.segments {
+            for path in snapshot_files(segment_meta, &commit.schema) {
+                self.directory.copy_raw_file(&path, dest)?;
+                files.push(path);
+            }
+        }
+        let mut managed_paths: HashSet<PathBuf> = files.iter().cloned().collect();
+        managed_paths.insert(META_FILEPATH.to_path_buf());
+        write_managed_paths(dest, &managed_paths)?;
+        save_metas(commit, dest)?;
+        Ok(files)
+    }
+
+    /// Backs up a commit into `backup_directory`, and returns the manifest of the backup.
+    ///
+    /// When the manifest of the `previous` backup made in the same directory is given,
+    /// the backup is incremental: files that were already shipped are not copied again.
+    /// The backup can then be restored with [`BackupManifest::restore`].
+    ///
+    /// As with [`Index::create_snapshot`], the files of the commit cannot be garbage
+    /// collected while the backup is running.
+    pub fn backup(
+        &self,
+        commit: &IndexMeta,
+        backup_directory: &dyn Directory,
+        previous: Option<&BackupManifest>,
+    ) -> crate::Result<BackupManifest> {
+        create_backup(&self.directory, commit, backup_directory, previous)
+    }
+
+    /// Publishes a commit for replication, by listing the files it is made of.
+    ///
+    /// The returned manifest is meant to be applied on replicas with
+    /// [`ReplicationManifest::apply`]. The files of the commit cannot be garbage
+    /// collected as long as `commit` is alive, so the primary should keep it around
+    /// until the replicas have caught up.
+    pub fn replication_manifest(&self, commit: &IndexMeta) -> crate::Result<ReplicationManifest> {
+        ReplicationManifest::for_commit(&self.directory, commit)
+    }
+
+    /// Returns the checkpoint of the last commit, from which the changes of the index can be
+    /// computed later on with [`Index::changes_since`].
+    pub fn change_checkpoint(&self) -> crate::Result<ChangeCheckpoint> {
+        Ok(ChangeCheckpoint::for_commit(&self.load_metas()?))
+    }
+
+    /// Returns the changes of the index between `checkpoint` and the last commit.
+    ///
+    /// Polling with the checkpoint of the returned changes acts as a cursor over the commits
+    /// of the index. Commits happening in between two polls are reported together.
+    pub fn changes_since(&self, checkpoint: &ChangeCheckpoint) -> crate::Result<IndexChanges> {
+        Ok(IndexChanges::between(checkpoint, &self.load_metas()?))
+    }
+
+    /// Calls `callback` with the changes of the index since `checkpoint`, right away and then
+    /// whenever a new commit or merge is published, for as long as the returned handle is
+    /// alive.
+    ///
+    /// The callback is not called when the index did not change. It relies on
+    /// [`Directory::watch`], and is therefore never called on directories that cannot be
+    /// watched.
+    pub fn watch_changes<F>(
+        &self,
+        checkpoint: ChangeCheckpoint,
+        callback: F,
+    ) -> crate::Result<WatchHandle>
+    where
+        F: Fn(&IndexChanges) + Send + Sync + 'static,
+    {
+        change_feed::watch_changes(self, checkpoint, callback)
+    }
+
+    /// Opens a snapshot created by [`Index::create_snapshot`].
+    ///
+    /// On top of what [`Index::open`] does, this checks that all of the files
+    /// referenced by the snapshotted commit are present.
+    pub fn open_snapshot<T: Into<Box<dyn Directory>>>(directory: T) -> crate::Result<Index> {
+        let index = Index::open(directory)?;
+        let schema = index.schema();
+        for segment_meta in index.searchable_segment_metas()? {
+            for path in snapshot_files(&segment_meta, &schema) {
+                if !index.directory.exists(&path)? {
+                    return Err(DataCorruption::new(
+                        path,
+                        "File referenced by the snapshot is missing.".to_string(),
+                    )
+                    .into());
+                }
+            }
+        }
+        Ok(index)
+    }
+
     /// Open a new index writer. Attempts to acquire a lockfile.
     ///
     /// The lockfile should be deleted on drop, but it is possible
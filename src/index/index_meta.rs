@@ -8,9 +8,9 @@ use serde::{Deserialize, Serialize};
 
 use super::SegmentComponent;
 use crate::index::SegmentId;
-use crate::schema::Schema;
+use crate::schema::{Field, FieldType, Schema};
 use crate::store::Compressor;
-use crate::{Inventory, Opstamp, TrackedObject};
+use crate::{Inventory, Opstamp, TantivyError, TrackedObject};
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
 struct DeleteMeta {
@@ -39,6 +39,7 @@ impl SegmentMetaInventory {
             max_doc,
             include_temp_doc_store: Arc::new(AtomicBool::new(true)),
             deletes: None,
+            store_opstamp: None,
         };
         SegmentMeta::from(self.inventory.track(inner))
     }
@@ -132,18 +133,7 @@ impl SegmentMeta {
     /// It just joins the segment id with the extension
     /// associated with a segment component.
     pub fn relative_path(&self, component: SegmentComponent) -> PathBuf {
-        let mut path = self.id().uuid_string();
-        path.push_str(&match component {
-            SegmentComponent::Postings => ".idx".to_string(),
-            SegmentComponent::Positions => ".pos".to_string(),
-            SegmentComponent::Terms => ".term".to_string(),
-            SegmentComponent::Store => ".store".to_string(),
-            SegmentComponent::TempStore => ".store.temp".to_string(),
-            SegmentComponent::FastFields => ".fast".to_string(),
-            SegmentComponent::FieldNorms => ".fieldnorm".to_string(),
-            SegmentComponent::Delete => format!(".{}.del", self.delete_opstamp().unwrap_or(0)),
-        });
-        PathBuf::from(path)
+        component.relative_path(self.id(), self.delete_opstamp(), self.store_opstamp())
     }
 
     /// Return the highest doc id + 1
@@ -169,6 +159,12 @@ impl SegmentMeta {
             .map(|delete_meta| delete_meta.opstamp)
     }
 
+    /// Returns the `Opstamp` of the last compaction of the doc store of this segment, if
+    /// its doc store was ever compacted.
+    pub fn store_opstamp(&self) -> Option<Opstamp> {
+        self.tracked.store_opstamp
+    }
+
     /// Returns true iff the segment meta contains
     /// delete information.
     pub fn has_deletes(&self) -> bool {
@@ -187,6 +183,7 @@ impl SegmentMeta {
             max_doc,
             deletes: None,
             include_temp_doc_store: Arc::new(AtomicBool::new(true)),
+            store_opstamp: None,
         });
         SegmentMeta { tracked }
     }
@@ -207,6 +204,19 @@ impl SegmentMeta {
             max_doc: inner_meta.max_doc,
             include_temp_doc_store: Arc::new(AtomicBool::new(true)),
             deletes: Some(delete_meta),
+            store_opstamp: inner_meta.store_opstamp,
+        });
+        SegmentMeta { tracked }
+    }
+
+    /// Updates the opstamp of the compaction of the doc store.
+    pub(crate) fn with_store_opstamp(self, store_opstamp: Opstamp) -> SegmentMeta {
+        let tracked = self.tracked.map(move |inner_meta| InnerSegmentMeta {
+            segment_id: inner_meta.segment_id,
+            max_doc: inner_meta.max_doc,
+            include_temp_doc_store: Arc::new(AtomicBool::new(true)),
+            deletes: inner_meta.deletes.clone(),
+            store_opstamp: Some(store_opstamp),
         });
         SegmentMeta { tracked }
     }
@@ -217,6 +227,9 @@ struct InnerSegmentMeta {
     segment_id: SegmentId,
     max_doc: u32,
     deletes: Option<DeleteMeta>,
+    /// Set once the doc store was compacted, independently of the other components.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    store_opstamp: Option<Opstamp>,
     /// If you want to avoid the SegmentComponent::TempStore file to be covered by
     /// garbage collection and deleted, set this to true. This is used during merge.
     #[serde(skip)]
@@ -247,9 +260,14 @@ fn is_true(val: &bool) -> bool {
 ///
 /// Contains settings which are applied on the whole
 /// index, like presort documents.
+///
+/// The settings are persisted in the index `meta.json`, so that each index can tune its doc
+/// store: larger blocks and a high zstd compression level (e.g.
+/// `Compressor::Zstd(ZstdCompressor { compression_level: Some(19), .. })`) for a better
+/// compression ratio, or small lz4 blocks for a lower fetch latency.
 #[derive(Clone, Debug, Serialize, Deserialize, Eq, PartialEq)]
 pub struct IndexSettings {
-    /// The `Compressor` used to compress the doc store.
+    /// The `Compressor` used to compress the doc store, including its compression level.
     #[serde(default)]
     pub docstore_compression: Compressor,
     /// If set to true, docstore compression will happen on a dedicated thread.
@@ -260,7 +278,77 @@ pub struct IndexSettings {
     pub docstore_compress_dedicated_thread: bool,
     #[serde(default = "default_docstore_blocksize")]
     /// The size of each block that will be compressed and written to disk
+    ///
+    /// Larger blocks compress better, but more data has to be decompressed to fetch a
+    /// single document.
     pub docstore_blocksize: usize,
+    /// Name of a `u64` fast field in which the `IndexWriter` records the sequence number of
+    /// each document: the opstamp of the operation that added it.
+    ///
+    /// Sequence numbers are unique, and increase with each operation across writer sessions,
+    /// so that they can be used for optimistic concurrency control, or to consume the
+    /// documents added after a given commit. The field must be fast, but neither indexed nor
+    /// stored: the values documents may have for this field are ignored.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sequence_number_field: Option<String>,
+    /// Number of threads used to build the vector index of a segment, when it is written or
+    /// merged. (defaults: 1)
+    ///
+    /// Building the HNSW graph or training the IVF-PQ codebooks of a segment with many
+    /// vectors is CPU bound, so that merges of large segments finish sooner with a few
+    /// threads.
+    #[serde(
+        default = "default_vector_index_build_threads",
+        skip_serializing_if = "is_default_vector_index_build_threads"
+    )]
+    pub vector_index_build_threads: usize,
+    /// Number of threads writing the segment resulting from a merge. (defaults: 1)
+    ///
+    /// With several threads, the postings, the doc store, the fast fields and the other
+    /// components of the merged segment are written concurrently instead of one after the other,
+    /// so that a merge of large segments is not bound to a single core.
+    #[serde(
+        default = "default_merge_threads",
+        skip_serializing_if = "is_default_merge_threads"
+    )]
+    pub merge_threads: usize,
+    /// Number of threads serializing the fast fields of a new segment. (defaults: 1)
+    ///
+    /// With several threads, the columns of the fast fields are encoded concurrently, so that
+    /// the commit latency of schemas with many fast fields scales with the number of cores.
+    /// The encoded columns are buffered in memory until they are written in order.
+    #[serde(
+        default = "default_fast_field_serialization_threads",
+        skip_serializing_if = "is_default_fast_field_serialization_threads"
+    )]
+    pub fast_field_serialization_threads: usize,
+}
+
+impl IndexSettings {
+    /// Returns the field holding the sequence numbers, if any.
+    ///
+    /// Returns an error if the field is missing from the schema, or does not have the
+    /// expected options.
+    pub(crate) fn resolve_sequence_number_field(
+        &self,
+        schema: &Schema,
+    ) -> crate::Result<Option<Field>> {
+        let Some(field_name) = self.sequence_number_field.as_deref() else {
+            return Ok(None);
+        };
+        let field = schema.get_field(field_name)?;
+        match schema.get_field_entry(field).field_type() {
+            FieldType::U64(options)
+                if options.is_fast() && !options.is_indexed() && !options.is_stored() =>
+            {
+                Ok(Some(field))
+            }
+            _ => Err(TantivyError::SchemaError(format!(
+                "The sequence number field {field_name:?} must be a u64 fast field, neither \
+                 indexed nor stored."
+            ))),
+        }
+    }
 }
 
 /// Must be a function to be compatible with serde defaults
@@ -268,12 +356,40 @@ fn default_docstore_blocksize() -> usize {
     16_384
 }
 
+fn default_vector_index_build_threads() -> usize {
+    1
+}
+
+fn is_default_vector_index_build_threads(num_threads: &usize) -> bool {
+    *num_threads == default_vector_index_build_threads()
+}
+
+fn default_merge_threads() -> usize {
+    1
+}
+
+fn is_default_merge_threads(num_threads: &usize) -> bool {
+    *num_threads == default_merge_threads()
+}
+
+fn default_fast_field_serialization_threads() -> usize {
+    1
+}
+
+fn is_default_fast_field_serialization_threads(num_threads: &usize) -> bool {
+    *num_threads == default_fast_field_serialization_threads()
+}
+
 impl Default for IndexSettings {
     fn default() -> Self {
         Self {
             docstore_compression: Compressor::default(),
             docstore_blocksize: default_docstore_blocksize(),
             docstore_compress_dedicated_thread: true,
+            sequence_number_field: None,
+            vector_index_build_threads: default_vector_index_build_threads(),
+            merge_threads: default_merge_threads(),
+            fast_field_serialization_threads: default_fast_field_serialization_threads(),
         }
     }
 }
@@ -436,9 +552,14 @@ mod tests {
             index_settings: IndexSettings {
                 docstore_compression: crate::store::Compressor::Zstd(ZstdCompressor {
                     compression_level: Some(4),
+                    dict_size: None,
                 }),
                 docstore_blocksize: 1_000_000,
                 docstore_compress_dedicated_thread: true,
+                sequence_number_field: None,
+                vector_index_build_threads: 1,
+                merge_threads: 1,
+                fast_field_serialization_threads: 1,
             },
             segments: Vec::new(),
             schema,
@@ -502,7 +623,11 @@ mod tests {
             IndexSettings {
                 docstore_compression: Compressor::default(),
                 docstore_compress_dedicated_thread: true,
-                docstore_blocksize: 16_384
+                docstore_blocksize: 16_384,
+                sequence_number_field: None,
+                vector_index_build_threads: 1,
+                merge_threads: 1,
+                fast_field_serialization_threads: 1,
             }
         );
         {
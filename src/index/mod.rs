@@ -2,18 +2,24 @@
 //!
 //! It contains `Index` and `Segment`, where a `Index` consists of one or more `Segment`s.
 
+mod backup;
+mod change_feed;
 mod index;
 mod index_meta;
 mod inverted_index_reader;
+mod replication;
 mod segment;
 mod segment_component;
 mod segment_id;
 mod segment_reader;
 
+pub use self::backup::{BackupFile, BackupManifest};
+pub use self::change_feed::{ChangeCheckpoint, IndexChanges, SegmentDeletes};
 pub use self::index::{Index, IndexBuilder};
 pub(crate) use self::index_meta::SegmentMetaInventory;
 pub use self::index_meta::{IndexMeta, IndexSettings, Order, SegmentMeta};
 pub use self::inverted_index_reader::InvertedIndexReader;
+pub use self::replication::{ReplicatedFile, ReplicationManifest};
 pub use self::segment::Segment;
 pub use self::segment_component::SegmentComponent;
 pub use self::segment_id::SegmentId;
@@ -0,0 +1,231 @@
+use std::collections::HashSet;
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+
+use super::index::snapshot_files;
+use super::IndexMeta;
+use crate::core::META_FILEPATH;
+use crate::directory::{file_checksum, Directory, ManagedDirectory};
+use crate::error::DataCorruption;
+use crate::Opstamp;
+
+/// A file that is part of a replicated commit.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ReplicatedFile {
+    /// Path of the file, relative to the index directory.
+    pub path: PathBuf,
+    /// Size of the file in bytes, footer included.
+    pub num_bytes: u64,
+    /// Checksum of the file, as recorded in its footer.
+    pub crc: u32,
+}
+
+/// Describes a commit published by a primary, so that replicas can catch up with it.
+///
+/// The manifest is created on the primary with
+/// [`Index::replication_manifest`](crate::Index::replication_manifest), shipped to the
+/// replicas by whatever means (it implements `Serialize`), and applied on each replica
+/// with [`ReplicationManifest::apply`].
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ReplicationManifest {
+    opstamp: Opstamp,
+    files: Vec<ReplicatedFile>,
+    meta: serde_json::Value,
+}
+
+impl ReplicationManifest {
+    pub(crate) fn for_commit(
+        directory: &ManagedDirectory,
+        commit: &IndexMeta,
+    ) -> crate::Result<ReplicationManifest> {
+        let mut files = Vec::new();
+        for segment_meta in &commit.segments {
+            for path in snapshot_files(segment_meta, &commit.schema) {
+                let file_slice = directory.open_read_raw(&path)?;
+                let num_bytes = file_slice.len() as u64;
+                let crc = file_checksum(&path, file_slice, false)?;
+                files.push(ReplicatedFile {
+                    path,
+                    num_bytes,
+                    crc,
+                });
+            }
+        }
+        Ok(ReplicationManifest {
+            opstamp: commit.opstamp,
+            files,
+            meta: serde_json::to_value(commit)?,
+        })
+    }
+
+    /// Opstamp of the published commit.
+    pub fn opstamp(&self) -> Opstamp {
+        self.opstamp
+    }
+
+    /// Files of the published commit, delete files included.
+    pub fn files(&self) -> &[ReplicatedFile] {
+        &self.files
+    }
+
+    /// Returns the files of the commit that the replica does not have yet.
+    pub fn missing_files(&self, replica: &dyn Directory) -> crate::Result<Vec<&ReplicatedFile>> {
+        let replica = ManagedDirectory::wrap(replica.box_clone())?;
+        let mut missing_files = Vec::new();
+        for file in &self.files {
+            if !has_file(&replica, file)? {
+                missing_files.push(file);
+            }
+        }
+        Ok(missing_files)
+    }
+
+    /// Fetches the files the replica is missing from `source`, and advances the replica
+    /// to the published commit. Returns the list of fetched files.
+    ///
+    /// `source` is a raw view over the files of the primary, and `replica` the raw
+    /// directory of the replica. Fetched files are checked against their checksum, and
+    /// `meta.json` is only written once all of them are durably stored, so that readers
+    /// of the replica never observe a partial commit. If anything fails, the replica
+    /// stays on its previous commit.
+    ///
+    /// Once the replica has advanced, the files that are not part of the new commit
+    /// are garbage collected. The replica must not be written to by an `IndexWriter`.
+    pub fn apply(
+        &self,
+        source: &dyn Directory,
+        replica: &dyn Directory,
+    ) -> crate::Result<Vec<PathBuf>> {
+        let mut replica = ManagedDirectory::wrap(replica.box_clone())?;
+        let mut fetched_files = Vec::new();
+        for file in &self.files {
+            if has_file(&replica, file)? {
+                continue;
+            }
+            if replica.exists(&file.path)? {
+                // Leftover of an interrupted replication.
+                replica.delete(&file.path)?;
+            }
+            replica.import_raw_file(source, &file.path)?;
+            let crc = file_checksum(&file.path, replica.open_read_raw(&file.path)?, true)?;
+            if crc != file.crc {
+                return Err(DataCorruption::new(
+                    file.path.clone(),
+                    format!(
+                        "Checksum mismatch: manifest says {:#010x}, fetched file says {crc:#010x}.",
+                        file.crc
+                    ),
+                )
+                .into());
+            }
+            fetched_files.push(file.path.clone());
+        }
+        let mut meta = serde_json::to_vec_pretty(&self.meta)?;
+        meta.push(b'\n');
+        replica.sync_directory()?;
+        replica.atomic_write(&META_FILEPATH, &meta)?;
+
+        let mut living_files: HashSet<PathBuf> =
+            self.files.iter().map(|file| file.path.clone()).collect();
+        living_files.insert(META_FILEPATH.to_path_buf());
+        replica.garbage_collect(move || living_files)?;
+        Ok(fetched_files)
+    }
+}
+
+/// Returns true if the replica already has an intact copy of the file.
+///
+/// Only the size and the checksum recorded in the footer are checked.
+fn has_file(replica: &ManagedDirectory, file: &ReplicatedFile) -> crate::Result<bool> {
+    if !replica.exists(&file.path)? {
+        return Ok(false);
+    }
+    let file_slice = replica.open_read_raw(&file.path)?;
+    if file_slice.len() as u64 != file.num_bytes {
+        return Ok(false);
+    }
+    Ok(file_checksum(&file.path, file_slice, false)? == file.crc)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::directory::{Directory, RamDirectory};
+    use crate::indexer::NoMergePolicy;
+    use crate::schema::{Schema, INDEXED};
+    use crate::{Index, IndexSettings, IndexWriter, Term};
+
+    #[test]
+    fn test_replication() -> crate::Result<()> {
+        let mut schema_builder = Schema::builder();
+        let field = schema_builder.add_u64_field("num_likes", INDEXED);
+        let primary_directory = RamDirectory::create();
+        let primary = Index::create(
+            primary_directory.clone(),
+            schema_builder.build(),
+            IndexSettings::default(),
+        )?;
+        let mut writer: IndexWriter = primary.writer_for_tests()?;
+        writer.set_merge_policy(Box::new(NoMergePolicy));
+        writer.add_document(doc!(field => 1u64))?;
+        writer.add_document(doc!(field => 2u64))?;
+        writer.commit()?;
+
+        let replica_directory = RamDirectory::create();
+        let manifest = primary.replication_manifest(&primary.load_metas()?)?;
+        assert_eq!(manifest.missing_files(&replica_directory)?.len(), 6);
+        let fetched_files = manifest.apply(&primary_directory, &replica_directory)?;
+        assert_eq!(fetched_files.len(), 6);
+        assert!(manifest.missing_files(&replica_directory)?.is_empty());
+        let replica = Index::open(replica_directory.clone())?;
+        let reader = replica.reader()?;
+        assert_eq!(reader.searcher().num_docs(), 2);
+
+        writer.delete_term(Term::from_field_u64(field, 1u64));
+        writer.add_document(doc!(field => 3u64))?;
+        writer.commit()?;
+        let manifest = primary.replication_manifest(&primary.load_metas()?)?;
+        let fetched_files = manifest.apply(&primary_directory, &replica_directory)?;
+        // The new segment, and the delete file of the first one.
+        assert_eq!(fetched_files.len(), 7);
+        assert!(fetched_files
+            .iter()
+            .any(|path| path.to_string_lossy().ends_with(".del")));
+        reader.reload()?;
+        assert_eq!(reader.searcher().num_docs(), 2);
+        assert_eq!(replica.load_metas()?.opstamp, manifest.opstamp());
+        Ok(())
+    }
+
+    #[test]
+    fn test_replication_failure_keeps_previous_commit() -> crate::Result<()> {
+        let mut schema_builder = Schema::builder();
+        let field = schema_builder.add_u64_field("num_likes", INDEXED);
+        let primary_directory = RamDirectory::create();
+        let primary = Index::create(
+            primary_directory.clone(),
+            schema_builder.build(),
+            IndexSettings::default(),
+        )?;
+        let mut writer: IndexWriter = primary.writer_for_tests()?;
+        writer.set_merge_policy(Box::new(NoMergePolicy));
+        writer.add_document(doc!(field => 1u64))?;
+        writer.commit()?;
+        let replica_directory = RamDirectory::create();
+        let first_manifest = primary.replication_manifest(&primary.load_metas()?)?;
+        first_manifest.apply(&primary_directory, &replica_directory)?;
+
+        writer.add_document(doc!(field => 2u64))?;
+        writer.commit()?;
+        let manifest = primary.replication_manifest(&primary.load_metas()?)?;
+        let missing_path = manifest.missing_files(&replica_directory)?[0].path.clone();
+        primary_directory.delete(&missing_path)?;
+        assert!(manifest
+            .apply(&primary_directory, &replica_directory)
+            .is_err());
+        let replica = Index::open(replica_directory)?;
+        assert_eq!(replica.load_metas()?.opstamp, first_manifest.opstamp());
+        assert_eq!(replica.reader()?.searcher().num_docs(), 1);
+        Ok(())
+    }
+}
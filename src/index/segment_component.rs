@@ -1,9 +1,15 @@
+use std::path::PathBuf;
 use std::slice;
 
+use crate::index::SegmentId;
+use crate::Opstamp;
+
 /// Enum describing each component of a tantivy segment.
 /// Each component is stored in its own file,
 /// using the pattern `segment_uuid`.`component_extension`,
-/// except the delete component that takes an `segment_uuid`.`delete_opstamp`.`component_extension`
+/// except the delete component that takes an `segment_uuid`.`delete_opstamp`.`component_extension`,
+/// and the store component of a segment whose doc store was compacted, that takes an
+/// `segment_uuid`.`store_opstamp`.`component_extension`
 #[derive(Copy, Clone, Eq, PartialEq)]
 pub enum SegmentComponent {
     /// Postings (or inverted list). Sorted lists of document ids, associated with terms
@@ -27,12 +33,21 @@ pub enum SegmentComponent {
     /// Bitset describing which document of the segment is alive.
     /// (It was representing deleted docs but changed to represent alive docs from v0.17)
     Delete,
+    /// Binary payloads of the blob fields, stored outside of the doc store.
+    /// The file only exists if the schema has blob fields.
+    Blobs,
+    /// Term vectors of the fields for which they are enabled.
+    /// The file only exists if the schema has such fields.
+    TermVectors,
+    /// Vectors of the dense vector fields and their HNSW graphs.
+    /// The file only exists if the schema has dense vector fields.
+    VectorIndex,
 }
 
 impl SegmentComponent {
     /// Iterates through the components.
     pub fn iterator() -> slice::Iter<'static, SegmentComponent> {
-        static SEGMENT_COMPONENTS: [SegmentComponent; 8] = [
+        static SEGMENT_COMPONENTS: [SegmentComponent; 11] = [
             SegmentComponent::Postings,
             SegmentComponent::Positions,
             SegmentComponent::FastFields,
@@ -41,7 +56,38 @@ impl SegmentComponent {
             SegmentComponent::Store,
             SegmentComponent::TempStore,
             SegmentComponent::Delete,
+            SegmentComponent::Blobs,
+            SegmentComponent::TermVectors,
+            SegmentComponent::VectorIndex,
         ];
         SEGMENT_COMPONENTS.iter()
     }
+
+    /// Returns the relative path of the component of a segment, given the opstamp of the
+    /// deletes of the segment and the opstamp of the compaction of its doc store.
+    pub(crate) fn relative_path(
+        self,
+        segment_id: SegmentId,
+        delete_opstamp: Option<Opstamp>,
+        store_opstamp: Option<Opstamp>,
+    ) -> PathBuf {
+        let mut path = segment_id.uuid_string();
+        path.push_str(&match self {
+            SegmentComponent::Postings => ".idx".to_string(),
+            SegmentComponent::Positions => ".pos".to_string(),
+            SegmentComponent::Terms => ".term".to_string(),
+            SegmentComponent::Store => match store_opstamp {
+                Some(store_opstamp) => format!(".{store_opstamp}.store"),
+                None => ".store".to_string(),
+            },
+            SegmentComponent::TempStore => ".store.temp".to_string(),
+            SegmentComponent::FastFields => ".fast".to_string(),
+            SegmentComponent::FieldNorms => ".fieldnorm".to_string(),
+            SegmentComponent::Delete => format!(".{}.del", delete_opstamp.unwrap_or(0)),
+            SegmentComponent::Blobs => ".blob".to_string(),
+            SegmentComponent::TermVectors => ".tv".to_string(),
+            SegmentComponent::VectorIndex => ".vec".to_string(),
+        });
+        PathBuf::from(path)
+    }
 }
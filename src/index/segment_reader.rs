@@ -1,5 +1,6 @@
 use std::collections::HashMap;
 use std::ops::BitOrAssign;
+use std::path::PathBuf;
 use std::sync::{Arc, RwLock};
 use std::{fmt, io};
 
@@ -14,9 +15,11 @@ use crate::index::{InvertedIndexReader, Segment, SegmentComponent, SegmentId};
 use crate::json_utils::json_path_sep_to_dot;
 use crate::schema::{Field, IndexRecordOption, Schema, Type};
 use crate::space_usage::SegmentSpaceUsage;
-use crate::store::StoreReader;
+use crate::store::{BlobStoreReader, StoreReader};
 use crate::termdict::TermDictionary;
-use crate::{DocId, Opstamp};
+use crate::termvector::TermVectorsReader;
+use crate::vector::VectorIndexReader;
+use crate::{DocId, Opstamp, WarmupSpec};
 
 /// Entry point to access all of the datastructures of the `Segment`
 ///
@@ -34,6 +37,7 @@ pub struct SegmentReader {
 
     segment_id: SegmentId,
     delete_opstamp: Option<Opstamp>,
+    store_opstamp: Option<Opstamp>,
 
     max_doc: DocId,
     num_docs: DocId,
@@ -45,6 +49,9 @@ pub struct SegmentReader {
     fieldnorm_readers: FieldNormReaders,
 
     store_file: FileSlice,
+    blob_store: BlobStoreReader,
+    term_vectors: TermVectorsReader,
+    vector_index: VectorIndexReader,
     alive_bitset_opt: Option<AliveBitSet>,
     schema: Schema,
 }
@@ -139,6 +146,68 @@ impl SegmentReader {
         StoreReader::open(self.store_file.clone(), cache_num_blocks)
     }
 
+    /// Accessor to the segment's [`BlobStoreReader`](crate::store::BlobStoreReader), holding
+    /// the values of the blob fields.
+    pub fn blob_store(&self) -> &BlobStoreReader {
+        &self.blob_store
+    }
+
+    /// Accessor to the segment's [`TermVectorsReader`](crate::termvector::TermVectorsReader).
+    pub fn term_vectors(&self) -> &TermVectorsReader {
+        &self.term_vectors
+    }
+
+    /// Accessor to the segment's [`VectorIndexReader`](crate::vector::VectorIndexReader),
+    /// holding the vectors of the dense vector fields.
+    pub fn vector_index(&self) -> &VectorIndexReader {
+        &self.vector_index
+    }
+
+    /// Returns the relative paths of the files read by the segment reader.
+    pub(crate) fn list_files(&self) -> Vec<PathBuf> {
+        SegmentComponent::iterator()
+            .filter(|component| match component {
+                SegmentComponent::TempStore => false,
+                SegmentComponent::Delete => self.delete_opstamp.is_some(),
+                SegmentComponent::Blobs => self.schema.has_blob_fields(),
+                SegmentComponent::TermVectors => self.schema.has_term_vector_fields(),
+                SegmentComponent::VectorIndex => self.schema.has_dense_vector_fields(),
+                _ => true,
+            })
+            .map(|component| {
+                component.relative_path(self.segment_id, self.delete_opstamp, self.store_opstamp)
+            })
+            .collect()
+    }
+
+    /// Returns the parts of the segment files holding the data structures selected by
+    /// `warmup_spec`.
+    pub(crate) fn warmup_file_slices(
+        &self,
+        warmup_spec: &WarmupSpec,
+    ) -> crate::Result<Vec<FileSlice>> {
+        let mut file_slices = Vec::new();
+        for &field in &warmup_spec.term_dictionaries {
+            file_slices.extend(self.termdict_composite.open_read(field));
+        }
+        let fieldnorms_composite = self.fieldnorm_readers.get_inner_file();
+        for &field in &warmup_spec.fieldnorms {
+            file_slices.extend(fieldnorms_composite.open_read(field));
+        }
+        for field_name in &warmup_spec.fast_fields {
+            for column_handle in self
+                .fast_fields_readers
+                .dynamic_column_handles(field_name)?
+            {
+                file_slices.push(column_handle.file_slice().clone());
+            }
+        }
+        if warmup_spec.store_index {
+            file_slices.push(StoreReader::index_file_slice(self.store_file.clone())?);
+        }
+        Ok(file_slices)
+    }
+
     /// Open a new segment for reading.
     pub fn open(segment: &Segment) -> crate::Result<SegmentReader> {
         Self::open_with_custom_alive_set(segment, None)
@@ -174,10 +243,31 @@ impl SegmentReader {
         let fieldnorm_data = segment.open_read(SegmentComponent::FieldNorms)?;
         let fieldnorm_readers = FieldNormReaders::open(fieldnorm_data)?;
 
+        let blob_store = if schema.has_blob_fields() {
+            BlobStoreReader::open(segment.open_read(SegmentComponent::Blobs)?)?
+        } else {
+            BlobStoreReader::empty()
+        };
+
+        let term_vectors = if schema.has_term_vector_fields() {
+            TermVectorsReader::open(segment.open_read(SegmentComponent::TermVectors)?)?
+        } else {
+            TermVectorsReader::empty()
+        };
+
+        let vector_index = if schema.has_dense_vector_fields() {
+            VectorIndexReader::open(
+                segment.open_read(SegmentComponent::VectorIndex)?,
+                schema.clone(),
+            )?
+        } else {
+            VectorIndexReader::empty(schema.clone())
+        };
+
         let original_bitset = if segment.meta().has_deletes() {
             let alive_doc_file_slice = segment.open_read(SegmentComponent::Delete)?;
             let alive_doc_data = alive_doc_file_slice.read_bytes()?;
-            Some(AliveBitSet::open(alive_doc_data))
+            Some(AliveBitSet::open(alive_doc_data)?)
         } else {
             None
         };
@@ -200,7 +290,11 @@ impl SegmentReader {
             fieldnorm_readers,
             segment_id: segment.id(),
             delete_opstamp: segment.meta().delete_opstamp(),
+            store_opstamp: segment.meta().store_opstamp(),
             store_file,
+            blob_store,
+            term_vectors,
+            vector_index,
             alive_bitset_opt,
             positions_composite,
             schema,
@@ -424,6 +518,9 @@ impl SegmentReader {
             self.fast_fields_readers.space_usage(self.schema())?,
             self.fieldnorm_readers.space_usage(),
             self.get_store_reader(0)?.space_usage(),
+            self.blob_store.space_usage(),
+            self.term_vectors.space_usage(),
+            self.vector_index.space_usage(),
             self.alive_bitset_opt
                 .as_ref()
                 .map(AliveBitSet::space_usage)
@@ -497,7 +594,7 @@ fn intersect_alive_bitset(
 ) -> Option<AliveBitSet> {
     match (left_opt, right_opt) {
         (Some(left), Some(right)) => {
-            assert_eq!(left.bitset().max_value(), right.bitset().max_value());
+            assert_eq!(left.max_doc(), right.max_doc());
             Some(intersect_alive_bitsets(left, right))
         }
         (Some(left), None) => Some(left),
@@ -19,10 +19,12 @@ use crate::indexer::index_writer_status::IndexWriterStatus;
 use crate::indexer::operation::DeleteOperation;
 use crate::indexer::stamper::Stamper;
 use crate::indexer::{MergePolicy, SegmentEntry, SegmentWriter};
+use crate::postings::IndexingContextPool;
 use crate::query::{EnableScoring, Query, TermQuery};
 use crate::schema::document::Document;
 use crate::schema::{IndexRecordOption, TantivyDocument, Term};
-use crate::{FutureResult, Opstamp};
+use crate::vector::check_document_vectors;
+use crate::{FutureResult, Instant, Opstamp};
 
 // Size of the margin for the `memory_arena`. A segment is closed when the remaining memory
 // in the `memory_arena` goes below MARGIN_IN_BYTES.
@@ -61,6 +63,9 @@ pub struct IndexWriter<D: Document = TantivyDocument> {
     // The memory budget per thread, after which a commit is triggered.
     memory_budget_in_bytes_per_thread: usize,
 
+    // The memory arenas of the segments written, reused by the next ones.
+    indexing_context_pool: Arc<IndexingContextPool>,
+
     workers_join_handle: Vec<JoinHandle<crate::Result<()>>>,
 
     index_writer_status: IndexWriterStatus<D>,
@@ -148,7 +153,7 @@ pub(crate) fn advance_deletes(
     )?;
 
     if let Some(seg_alive_bitset) = segment_reader.alive_bitset() {
-        alive_bitset.intersect_update(seg_alive_bitset.bitset());
+        seg_alive_bitset.remove_deleted_docs(&mut alive_bitset);
     }
 
     let num_alive_docs: u32 = alive_bitset.len() as u32;
@@ -170,9 +175,13 @@ fn index_documents<D: Document>(
     segment: Segment,
     grouped_document_iterator: &mut dyn Iterator<Item = AddBatch<D>>,
     segment_updater: &SegmentUpdater,
+    indexing_context_pool: &IndexingContextPool,
     mut delete_cursor: DeleteCursor,
 ) -> crate::Result<()> {
-    let mut segment_writer = SegmentWriter::for_segment(memory_budget, segment.clone())?;
+    let mut segment_writer = match indexing_context_pool.acquire() {
+        Some(ctx) => SegmentWriter::for_segment_with_context(segment.clone(), ctx)?,
+        None => SegmentWriter::for_segment(memory_budget, segment.clone())?,
+    };
     for document_group in grouped_document_iterator {
         for doc in document_group {
             segment_writer.add_document(doc)?;
@@ -197,7 +206,8 @@ fn index_documents<D: Document>(
     // the worker thread.
     assert!(max_doc > 0);
 
-    let doc_opstamps: Vec<Opstamp> = segment_writer.finalize()?;
+    let (doc_opstamps, ctx) = segment_writer.finalize_and_take_context()?;
+    indexing_context_pool.release(ctx);
 
     let segment_with_max_doc = segment.with_max_doc(max_doc);
 
@@ -298,6 +308,7 @@ impl<D: Document> IndexWriter<D> {
             _directory_lock: Some(directory_lock),
 
             memory_budget_in_bytes_per_thread,
+            indexing_context_pool: Arc::default(),
             index: index.clone(),
             index_writer_status: IndexWriterStatus::from(document_receiver),
             operation_sender: document_sender,
@@ -399,6 +410,7 @@ impl<D: Document> IndexWriter<D> {
         let mut delete_cursor = self.delete_queue.cursor();
 
         let mem_budget = self.memory_budget_in_bytes_per_thread;
+        let indexing_context_pool = self.indexing_context_pool.clone();
         let index = self.index.clone();
         let join_handle: JoinHandle<crate::Result<()>> = thread::Builder::new()
             .name(format!("thrd-tantivy-index{}", self.worker_id))
@@ -431,6 +443,7 @@ impl<D: Document> IndexWriter<D> {
                         index.new_segment(),
                         &mut document_iterator,
                         &segment_updater,
+                        &indexing_context_pool,
                         delete_cursor.clone(),
                     )?;
                 }
@@ -450,6 +463,17 @@ impl<D: Document> IndexWriter<D> {
         self.segment_updater.set_merge_policy(merge_policy);
     }
 
+    /// Enables or disables the recycling of the indexing memory. It is disabled by default.
+    ///
+    /// When enabled, the memory arenas and the term hash table in which an indexing thread
+    /// builds the inverted index of a segment are kept once the segment is written, and reused
+    /// for the next segments, across commits, rather than reallocated and zeroed. The price is
+    /// that up to the memory budget of each thread stays allocated while the writer is idle.
+    /// Disabling recycling frees this memory.
+    pub fn set_recycle_indexing_memory(&self, enabled: bool) {
+        self.indexing_context_pool.set_enabled(enabled);
+    }
+
     fn start_workers(&mut self) -> crate::Result<()> {
         for _ in 0..self.num_threads {
             self.add_indexing_worker()?;
@@ -517,6 +541,19 @@ impl<D: Document> IndexWriter<D> {
         segment_updater.start_merge(merge_operation)
     }
 
+    /// Compacts the doc store of a segment, without merging it.
+    ///
+    /// The doc store is rewritten without the stored values of the deleted documents,
+    /// using the current doc store settings of the index. Postings, fast fields and other
+    /// components are left untouched. This reclaims the space used by deleted documents in
+    /// the doc store, which otherwise remains until the segment is merged.
+    ///
+    /// Only the deletes committed when the compaction starts are taken in account. If the
+    /// segment is committed, the new doc store is committed as the compaction ends.
+    pub fn compact_doc_store(&mut self, segment_id: SegmentId) -> FutureResult<SegmentMeta> {
+        self.segment_updater.start_doc_store_compaction(segment_id)
+    }
+
     /// Closes the current document channel send.
     /// and replace all the channels by new ones.
     ///
@@ -611,6 +648,7 @@ impl<D: Document> IndexWriter<D> {
         // This will move uncommitted segments to the state of
         // committed segments.
         info!("Preparing commit");
+        let start = Instant::now();
 
         // this will drop the current document channel
         // and recreate a new one.
@@ -627,7 +665,7 @@ impl<D: Document> IndexWriter<D> {
         }
 
         let commit_opstamp = self.stamper.stamp();
-        let prepared_commit = PreparedCommit::new(self, commit_opstamp);
+        let prepared_commit = PreparedCommit::new(self, commit_opstamp, start);
         info!("Prepared commit {}", commit_opstamp);
         Ok(prepared_commit)
     }
@@ -645,6 +683,7 @@ impl<D: Document> IndexWriter<D> {
     ///
     /// Commit returns the `opstamp` of the last document
     /// that made it in the commit.
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all))]
     pub fn commit(&mut self) -> crate::Result<Opstamp> {
         self.prepare_commit()?.commit()
     }
@@ -708,12 +747,26 @@ impl<D: Document> IndexWriter<D> {
     /// The opstamp is an increasing `u64` that can
     /// be used by the client to align commits with its own
     /// document queue.
+    ///
+    /// Returns an error if a vector of the document does not have the
+    /// dimensions of its dense vector field.
     pub fn add_document(&self, document: D) -> crate::Result<Opstamp> {
+        self.check_document(&document)?;
         let opstamp = self.stamper.stamp();
         self.send_add_documents_batch(smallvec![AddOperation { opstamp, document }])?;
         Ok(opstamp)
     }
 
+    /// Checks the document before it is sent to the indexing threads, where
+    /// an invalid document would fail the indexing of its whole segment.
+    fn check_document(&self, document: &D) -> crate::Result<()> {
+        let schema = self.index.schema();
+        if schema.has_dense_vector_fields() {
+            check_document_vectors(&schema, document)?;
+        }
+        Ok(())
+    }
+
     /// Gets a range of stamps from the stamper and "pops" the last stamp
     /// from the range returning a tuple of the last optstamp and the popped
     /// range.
@@ -770,6 +823,7 @@ impl<D: Document> IndexWriter<D> {
                     self.delete_queue.push(delete_operation);
                 }
                 UserOperation::Add(document) => {
+                    self.check_document(&document)?;
                     let add_operation = AddOperation { opstamp, document };
                     adds.push(add_operation);
                 }
@@ -780,7 +834,9 @@ impl<D: Document> IndexWriter<D> {
     }
 
     fn send_add_documents_batch(&self, add_ops: AddBatch<D>) -> crate::Result<()> {
+        let num_docs = add_ops.len() as u64;
         if self.index_writer_status.is_alive() && self.operation_sender.send(add_ops).is_ok() {
+            self.index.metrics().documents_added.inc_by(num_docs);
             Ok(())
         } else {
             Err(error_in_index_worker_thread("An index writer was killed."))
@@ -848,6 +904,58 @@ mod tests {
         assert_eq!(batch_opstamp1, 2u64);
     }
 
+    #[test]
+    fn test_sequence_numbers() -> crate::Result<()> {
+        let mut schema_builder = schema::Schema::builder();
+        let id_field = schema_builder.add_u64_field("id", INDEXED | STORED);
+        schema_builder.add_u64_field("seq", FAST);
+        let schema = schema_builder.build();
+        let invalid_settings = IndexSettings {
+            sequence_number_field: Some("id".to_string()),
+            ..Default::default()
+        };
+        assert!(Index::builder()
+            .schema(schema.clone())
+            .settings(invalid_settings)
+            .create_in_ram()
+            .is_err());
+        let settings = IndexSettings {
+            sequence_number_field: Some("seq".to_string()),
+            ..Default::default()
+        };
+        let index = Index::builder()
+            .schema(schema)
+            .settings(settings)
+            .create_in_ram()?;
+        let mut index_writer: IndexWriter = index.writer_for_tests()?;
+        let opstamp_0 = index_writer.add_document(doc!(id_field => 0u64))?;
+        let opstamp_1 = index_writer.add_document(doc!(id_field => 1u64))?;
+        index_writer.commit()?;
+        // Updating a document gives it a new sequence number.
+        index_writer.delete_term(Term::from_field_u64(id_field, 0));
+        let opstamp_0_updated = index_writer.add_document(doc!(id_field => 0u64))?;
+        index_writer.commit()?;
+        // Sequence numbers survive merges.
+        index_writer
+            .merge(&index.searchable_segment_ids()?)
+            .wait()?;
+        index_writer.wait_merging_threads()?;
+
+        let searcher = index.reader()?.searcher();
+        assert_eq!(searcher.segment_readers().len(), 1);
+        let sequence_number = |id: u64| -> crate::Result<Option<u64>> {
+            let query =
+                TermQuery::new(Term::from_field_u64(id_field, id), IndexRecordOption::Basic);
+            let top_docs = searcher.search(&query, &TopDocs::with_limit(1))?;
+            searcher.sequence_number(top_docs[0].1)
+        };
+        assert_eq!(sequence_number(0)?, Some(opstamp_0_updated));
+        assert_eq!(sequence_number(1)?, Some(opstamp_1));
+        assert!(opstamp_0 < opstamp_1);
+        assert!(opstamp_1 < opstamp_0_updated);
+        Ok(())
+    }
+
     #[test]
     fn test_no_need_to_rewrite_delete_file_if_no_new_deletes() {
         let mut schema_builder = schema::Schema::builder();
@@ -997,6 +1105,45 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_recycle_indexing_memory() -> crate::Result<()> {
+        let mut schema_builder = schema::Schema::builder();
+        let text_field = schema_builder.add_text_field("text", TEXT);
+        let json_field = schema_builder.add_json_field("json", TEXT);
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer: IndexWriter = index.writer_for_tests()?;
+        index_writer.set_merge_policy(Box::new(NoMergePolicy));
+        index_writer.set_recycle_indexing_memory(true);
+        index_writer.add_document(doc!(
+            text_field => "apple pie",
+            json_field => json!({"fruit": "apple"}),
+        ))?;
+        index_writer.commit()?;
+        assert_eq!(index_writer.indexing_context_pool.len(), 1);
+        index_writer.add_document(doc!(
+            text_field => "pear pie",
+            json_field => json!({"color": "green", "fruit": "pear"}),
+        ))?;
+        index_writer.commit()?;
+        assert_eq!(index_writer.indexing_context_pool.len(), 1);
+
+        let searcher = index.reader()?.searcher();
+        assert_eq!(searcher.segment_readers().len(), 2);
+        let query_parser = QueryParser::for_index(&index, vec![text_field, json_field]);
+        let count = |query: &str| -> crate::Result<usize> {
+            searcher.search(query_parser.parse_query(query)?.as_ref(), &Count)
+        };
+        assert_eq!(count("text:apple")?, 1);
+        assert_eq!(count("text:pie")?, 2);
+        assert_eq!(count("json.fruit:apple")?, 1);
+        assert_eq!(count("json.fruit:pear")?, 1);
+        assert_eq!(count("json.color:green")?, 1);
+
+        index_writer.set_recycle_indexing_memory(false);
+        assert_eq!(index_writer.indexing_context_pool.len(), 0);
+        Ok(())
+    }
+
     #[test]
     fn test_lockfile_released_on_drop() {
         let schema_builder = schema::Schema::builder();
@@ -7,7 +7,7 @@ mod tests {
     use crate::query::QueryParser;
     use crate::schema::{
         self, BytesOptions, Facet, FacetOptions, IndexRecordOption, NumericOptions,
-        TextFieldIndexing, TextOptions,
+        TantivyDocument, TextFieldIndexing, TextOptions, Value,
     };
     use crate::{DocAddress, DocSet, IndexSettings, IndexWriter, Term};
 
@@ -86,10 +86,29 @@ mod tests {
 
     #[test]
     fn test_merge_index() {
-        let index = create_test_index(Some(IndexSettings {
+        test_merge_index_aux(IndexSettings {
             ..Default::default()
-        }))
-        .unwrap();
+        });
+    }
+
+    #[test]
+    fn test_merge_index_with_merge_threads() {
+        test_merge_index_aux(IndexSettings {
+            merge_threads: 4,
+            ..Default::default()
+        });
+    }
+
+    #[test]
+    fn test_merge_index_with_fast_field_serialization_threads() {
+        test_merge_index_aux(IndexSettings {
+            fast_field_serialization_threads: 4,
+            ..Default::default()
+        });
+    }
+
+    fn test_merge_index_aux(index_settings: IndexSettings) {
+        let index = create_test_index(Some(index_settings)).unwrap();
 
         let reader = index.reader().unwrap();
         let searcher = reader.searcher();
@@ -115,6 +134,22 @@ mod tests {
             assert_eq!(do_search("biggest"), vec![4]);
         }
 
+        // doc store and fast fields
+        {
+            let int_field = index.schema().get_field("intval").unwrap();
+            let doc: TantivyDocument = searcher.doc(DocAddress::new(0, 4)).unwrap();
+            assert_eq!(
+                doc.get_first(int_field).and_then(|value| value.as_u64()),
+                Some(1_000)
+            );
+            let int_column = segment_reader.fast_fields().u64("intval").unwrap();
+            let mut int_vals: Vec<u64> = (0..segment_reader.max_doc())
+                .filter_map(|doc| int_column.first(doc))
+                .collect();
+            int_vals.sort();
+            assert_eq!(int_vals, vec![1, 2, 3, 10, 20, 1_000]);
+        }
+
         // postings file
         {
             let my_text_field = index.schema().get_field("text_field").unwrap();
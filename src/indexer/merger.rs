@@ -1,5 +1,6 @@
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 
+use columnar::column_values::CodecType;
 use columnar::{
     ColumnType, ColumnarReader, MergeRowOrder, RowAddr, ShuffleMergeOrder, StackMergeOrder,
 };
@@ -17,8 +18,10 @@ use crate::indexer::doc_id_mapping::{MappingType, SegmentDocIdMapping};
 use crate::indexer::SegmentSerializer;
 use crate::postings::{InvertedIndexSerializer, Postings, SegmentPostings};
 use crate::schema::{value_type_to_column_type, Field, FieldType, Schema};
-use crate::store::StoreWriter;
+use crate::store::{BlobStoreWriter, StoreWriter};
 use crate::termdict::{TermMerger, TermOrdinal};
+use crate::termvector::TermVectorsWriter;
+use crate::vector::VectorIndexWriter;
 use crate::{DocAddress, DocId, InvertedIndexReader};
 
 /// Segment's max doc must be `< MAX_DOC_LIMIT`.
@@ -144,6 +147,18 @@ fn extract_fast_field_required_columns(schema: &Schema) -> Vec<(String, ColumnTy
         .collect()
 }
 
+fn extract_fast_field_codecs(schema: &Schema) -> Vec<(String, CodecType)> {
+    schema
+        .fields()
+        .map(|(_, field_entry)| field_entry)
+        .filter(|field_entry| field_entry.is_fast())
+        .filter_map(|field_entry| {
+            let fastfield_codec = field_entry.field_type().fastfield_codec()?;
+            Some((field_entry.name().to_string(), fastfield_codec.codec_type()))
+        })
+        .collect()
+}
+
 impl IndexMerger {
     pub fn open(schema: Schema, segments: &[Segment]) -> crate::Result<IndexMerger> {
         let alive_bitset = segments.iter().map(|_| None).collect_vec();
@@ -229,10 +244,12 @@ impl IndexMerger {
             .iter()
             .map(|reader| reader.fast_fields().columnar())
             .collect();
+        let fast_field_codecs = extract_fast_field_codecs(&self.schema);
         let merge_row_order = convert_to_merge_order(&columnars[..], doc_id_mapping);
-        columnar::merge_columnar(
+        columnar::merge_columnar_with_codecs(
             &columnars[..],
             &required_columns,
+            &fast_field_codecs,
             merge_row_order,
             fast_field_wrt,
         )?;
@@ -273,7 +290,7 @@ impl IndexMerger {
             .iter()
             .map(|reader| {
                 let alive_bitset = reader.alive_bitset()?;
-                Some(alive_bitset.bitset().clone())
+                Some(alive_bitset.to_bitset())
             })
             .collect();
         Ok(SegmentDocIdMapping::new(
@@ -508,6 +525,9 @@ impl IndexMerger {
                     // take 7 in order to not walk over all checkpoints.
                     || store_reader.block_checkpoints().take(7).count() < 6
                     || store_reader.decompressor() != store_writer.compressor().into()
+                    // Blocks compressed with a dictionary have to be recompressed.
+                    || store_reader.has_dictionary()
+                    || store_writer.compressor().dict_size().is_some()
             {
                 for doc_bytes_res in store_reader.iter_raw(reader.alive_bitset()) {
                     let doc_bytes = doc_bytes_res?;
@@ -520,6 +540,91 @@ impl IndexMerger {
         Ok(())
     }
 
+    fn write_blobs(
+        &self,
+        blob_store_writer: &mut BlobStoreWriter,
+        doc_id_mapping: &SegmentDocIdMapping,
+    ) -> crate::Result<()> {
+        debug_time!("write-blobs");
+        for (new_doc_id, old_doc_addr) in doc_id_mapping.iter_old_doc_addrs().enumerate() {
+            let blob_store = self.readers[old_doc_addr.segment_ord as usize].blob_store();
+            for (field, blob) in blob_store.get_all(old_doc_addr.doc_id)? {
+                blob_store_writer.add_blob(new_doc_id as DocId, field, blob.reader())?;
+            }
+        }
+        Ok(())
+    }
+
+    fn write_term_vectors(
+        &self,
+        term_vectors_writer: &mut TermVectorsWriter,
+        doc_id_mapping: &SegmentDocIdMapping,
+    ) -> crate::Result<()> {
+        debug_time!("write-term-vectors");
+        for (new_doc_id, old_doc_addr) in doc_id_mapping.iter_old_doc_addrs().enumerate() {
+            let term_vectors = self.readers[old_doc_addr.segment_ord as usize].term_vectors();
+            for (field, serialized) in term_vectors.get_all_serialized(old_doc_addr.doc_id)? {
+                term_vectors_writer.add_serialized_term_vector(
+                    new_doc_id as DocId,
+                    field,
+                    serialized.reader(),
+                )?;
+            }
+        }
+        Ok(())
+    }
+
+    fn write_vector_index(
+        &self,
+        vector_index_writer: &mut VectorIndexWriter,
+        doc_id_mapping: &SegmentDocIdMapping,
+    ) -> crate::Result<()> {
+        debug_time!("write-vector-index");
+        let fields: Vec<Field> = self
+            .schema
+            .fields()
+            .filter(|(_, field_entry)| field_entry.dense_vector_options().is_some())
+            .map(|(field, _)| field)
+            .collect();
+        for field in fields {
+            let field_indexes = self
+                .readers
+                .iter()
+                .map(|reader| reader.vector_index().field_index(field))
+                .collect::<crate::Result<Vec<_>>>()?;
+            // The index of the segment with the most vectors is reused.
+            let reused = field_indexes
+                .iter()
+                .enumerate()
+                .filter_map(|(segment_ord, field_index)| Some((segment_ord, field_index.as_ref()?)))
+                .max_by_key(|(_, field_index)| field_index.num_vectors());
+            let mut reused_new_ords: Vec<Option<u32>> = reused
+                .map_or(Vec::new(), |(_, field_index)| {
+                    vec![None; field_index.num_vectors()]
+                });
+            let reused_segment_ord = reused.map(|(segment_ord, _)| segment_ord);
+            let mut num_vectors = 0u32;
+            for (new_doc_id, old_doc_addr) in doc_id_mapping.iter_old_doc_addrs().enumerate() {
+                let segment_ord = old_doc_addr.segment_ord as usize;
+                let Some(field_index) = &field_indexes[segment_ord] else {
+                    continue;
+                };
+                let ords = field_index.ords(old_doc_addr.doc_id);
+                for (ord, vector) in ords.zip(field_index.vectors(old_doc_addr.doc_id)) {
+                    vector_index_writer.add_vector(new_doc_id as DocId, field, &vector?)?;
+                    if reused_segment_ord == Some(segment_ord) {
+                        reused_new_ords[ord as usize] = Some(num_vectors);
+                    }
+                    num_vectors += 1;
+                }
+            }
+            if let Some((_, field_index)) = reused {
+                vector_index_writer.reuse_index(field, field_index, &reused_new_ords)?;
+            }
+        }
+        Ok(())
+    }
+
     /// Writes the merged segment by pushing information
     /// to the `SegmentSerializer`.
     ///
@@ -531,21 +636,67 @@ impl IndexMerger {
         if let Some(fieldnorms_serializer) = serializer.extract_fieldnorms_serializer() {
             self.write_fieldnorms(fieldnorms_serializer, &doc_id_mapping)?;
         }
-        debug!("write-postings");
         let fieldnorm_data = serializer
             .segment()
             .open_read(SegmentComponent::FieldNorms)?;
         let fieldnorm_readers = FieldNormReaders::open(fieldnorm_data)?;
-        self.write_postings(
-            serializer.get_postings_serializer(),
-            fieldnorm_readers,
-            &doc_id_mapping,
-        )?;
-
-        debug!("write-storagefields");
-        self.write_storable_fields(serializer.get_store_writer())?;
-        debug!("write-fastfields");
-        self.write_fast_fields(serializer.get_fast_field_write(), doc_id_mapping)?;
+        let merge_threads = serializer.segment().index().settings().merge_threads;
+
+        // The components are independent from each other once the fieldnorms are written.
+        let SegmentSerializer {
+            store_writer,
+            blob_store_writer,
+            term_vectors_writer,
+            vector_index_writer,
+            fast_field_write,
+            postings_serializer,
+            ..
+        } = &mut serializer;
+        let doc_id_mapping = Arc::new(doc_id_mapping);
+        let mut tasks: Vec<MergeTask> = Vec::new();
+        let postings_doc_id_mapping = doc_id_mapping.clone();
+        tasks.push(Box::new(move || {
+            debug!("write-postings");
+            self.write_postings(
+                postings_serializer,
+                fieldnorm_readers,
+                &postings_doc_id_mapping,
+            )
+        }));
+        tasks.push(Box::new(move || {
+            debug!("write-storagefields");
+            self.write_storable_fields(store_writer)
+        }));
+        if let Some(blob_store_writer) = blob_store_writer {
+            let doc_id_mapping = doc_id_mapping.clone();
+            tasks.push(Box::new(move || {
+                debug!("write-blobs");
+                self.write_blobs(blob_store_writer, &doc_id_mapping)
+            }));
+        }
+        if let Some(term_vectors_writer) = term_vectors_writer {
+            let doc_id_mapping = doc_id_mapping.clone();
+            tasks.push(Box::new(move || {
+                debug!("write-term-vectors");
+                self.write_term_vectors(term_vectors_writer, &doc_id_mapping)
+            }));
+        }
+        if let Some(vector_index_writer) = vector_index_writer {
+            let doc_id_mapping = doc_id_mapping.clone();
+            tasks.push(Box::new(move || {
+                debug!("write-vector-index");
+                self.write_vector_index(vector_index_writer, &doc_id_mapping)
+            }));
+        }
+        tasks.push(Box::new(move || {
+            debug!("write-fastfields");
+            // When the tasks run one after the other, the other tasks are done and the mapping
+            // is not copied.
+            let doc_id_mapping = Arc::try_unwrap(doc_id_mapping)
+                .unwrap_or_else(|doc_id_mapping| doc_id_mapping.as_ref().clone());
+            self.write_fast_fields(fast_field_write, doc_id_mapping)
+        }));
+        run_merge_tasks(tasks, merge_threads)?;
 
         debug!("close-serializer");
         serializer.close()?;
@@ -553,6 +704,42 @@ impl IndexMerger {
     }
 }
 
+/// Writes one of the components of a merged segment.
+type MergeTask<'a> = Box<dyn FnOnce() -> crate::Result<()> + Send + 'a>;
+
+/// Runs the `tasks` in order if `num_threads <= 1`, or concurrently on up to `num_threads`
+/// threads otherwise.
+fn run_merge_tasks(tasks: Vec<MergeTask>, num_threads: usize) -> crate::Result<()> {
+    if num_threads <= 1 || tasks.len() <= 1 {
+        for task in tasks {
+            task()?;
+        }
+        return Ok(());
+    }
+    let num_threads = num_threads.min(tasks.len());
+    let tasks = Mutex::new(tasks.into_iter());
+    let tasks = &tasks;
+    std::thread::scope(|scope| {
+        let handles: Vec<_> = (0..num_threads)
+            .map(|_| {
+                scope.spawn(move || -> crate::Result<()> {
+                    loop {
+                        let Some(task) = tasks.lock().unwrap().next() else {
+                            return Ok(());
+                        };
+                        task()?;
+                    }
+                })
+            })
+            .collect();
+        let results: Vec<crate::Result<()>> = handles
+            .into_iter()
+            .map(|handle| handle.join().expect("merge thread panicked"))
+            .collect();
+        results.into_iter().collect()
+    })
+}
+
 #[cfg(test)]
 mod tests {
 
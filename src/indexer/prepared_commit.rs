@@ -1,20 +1,26 @@
 use super::IndexWriter;
 use crate::schema::document::Document;
-use crate::{FutureResult, Opstamp, TantivyDocument};
+use crate::{FutureResult, Instant, Opstamp, TantivyDocument};
 
 /// A prepared commit
 pub struct PreparedCommit<'a, D: Document = TantivyDocument> {
     index_writer: &'a mut IndexWriter<D>,
     payload: Option<String>,
     opstamp: Opstamp,
+    start: Instant,
 }
 
 impl<'a, D: Document> PreparedCommit<'a, D> {
-    pub(crate) fn new(index_writer: &'a mut IndexWriter<D>, opstamp: Opstamp) -> Self {
+    pub(crate) fn new(
+        index_writer: &'a mut IndexWriter<D>,
+        opstamp: Opstamp,
+        start: Instant,
+    ) -> Self {
         Self {
             index_writer,
             payload: None,
             opstamp,
+            start,
         }
     }
 
@@ -36,7 +42,13 @@ impl<'a, D: Document> PreparedCommit<'a, D> {
     /// Proceeds to commit.
     /// See `.commit_future()`.
     pub fn commit(self) -> crate::Result<Opstamp> {
-        self.commit_future().wait()
+        let index = self.index_writer.index().clone();
+        let start = self.start;
+        let opstamp = self.commit_future().wait()?;
+        let metrics = index.metrics();
+        metrics.commits.inc();
+        metrics.commit_duration.observe(start.elapsed());
+        Ok(opstamp)
     }
 
     /// Proceeds to commit.
@@ -7,6 +7,7 @@ use crate::error::TantivyError;
 use crate::index::{SegmentId, SegmentMeta};
 use crate::indexer::delete_queue::DeleteCursor;
 use crate::indexer::SegmentEntry;
+use crate::Opstamp;
 
 #[derive(Default)]
 struct SegmentRegisters {
@@ -214,6 +215,41 @@ impl SegmentManager {
         Ok(segments_status)
     }
 
+    /// Points a segment to its compacted doc store.
+    ///
+    /// The deletes that happened during the compaction are kept. Returns the updated segment
+    /// meta.
+    pub(crate) fn end_doc_store_compaction(
+        &self,
+        segment_id: SegmentId,
+        store_opstamp: Opstamp,
+    ) -> crate::Result<(SegmentsStatus, SegmentMeta)> {
+        let mut registers_lock = self.write();
+        let segments_status = registers_lock
+            .segments_status(&[segment_id])
+            .ok_or_else(|| {
+                crate::TantivyError::InvalidArgument(
+                    "The segment whose doc store was compacted could not be found in the \
+                     SegmentManager. It may have been merged, or a rollback may have happened."
+                        .to_string(),
+                )
+            })?;
+        let target_register: &mut SegmentRegister = match segments_status {
+            SegmentsStatus::Uncommitted => &mut registers_lock.uncommitted,
+            SegmentsStatus::Committed => &mut registers_lock.committed,
+        };
+        let mut segment_entry = target_register
+            .get(&segment_id)
+            .expect("Segment id not found. Should never happen because of the status check.");
+        let segment_meta = segment_entry
+            .meta()
+            .clone()
+            .with_store_opstamp(store_opstamp);
+        segment_entry.set_meta(segment_meta.clone());
+        target_register.add_segment_entry(segment_entry);
+        Ok((segments_status, segment_meta))
+    }
+
     pub fn committed_segment_metas(&self) -> Vec<SegmentMeta> {
         self.remove_empty_segments();
         let registers_lock = self.read();
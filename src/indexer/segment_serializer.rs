@@ -4,16 +4,21 @@ use crate::directory::WritePtr;
 use crate::fieldnorm::FieldNormsSerializer;
 use crate::index::{Segment, SegmentComponent};
 use crate::postings::InvertedIndexSerializer;
-use crate::store::StoreWriter;
+use crate::store::{BlobStoreWriter, StoreWriter};
+use crate::termvector::TermVectorsWriter;
+use crate::vector::VectorIndexWriter;
 
 /// Segment serializer is in charge of laying out on disk
 /// the data accumulated and sorted by the `SegmentWriter`.
 pub struct SegmentSerializer {
     segment: Segment,
     pub(crate) store_writer: StoreWriter,
-    fast_field_write: WritePtr,
+    pub(crate) blob_store_writer: Option<BlobStoreWriter>,
+    pub(crate) term_vectors_writer: Option<TermVectorsWriter>,
+    pub(crate) vector_index_writer: Option<VectorIndexWriter>,
+    pub(crate) fast_field_write: WritePtr,
     fieldnorms_serializer: Option<FieldNormsSerializer>,
-    postings_serializer: InvertedIndexSerializer,
+    pub(crate) postings_serializer: InvertedIndexSerializer,
 }
 
 impl SegmentSerializer {
@@ -30,6 +35,31 @@ impl SegmentSerializer {
             )?
         };
 
+        let blob_store_writer = if segment.schema().has_blob_fields() {
+            let blob_write = segment.open_write(SegmentComponent::Blobs)?;
+            Some(BlobStoreWriter::new(blob_write))
+        } else {
+            None
+        };
+
+        let term_vectors_writer = if segment.schema().has_term_vector_fields() {
+            let term_vectors_write = segment.open_write(SegmentComponent::TermVectors)?;
+            Some(TermVectorsWriter::new(term_vectors_write))
+        } else {
+            None
+        };
+
+        let vector_index_writer = if segment.schema().has_dense_vector_fields() {
+            let vector_index_write = segment.open_write(SegmentComponent::VectorIndex)?;
+            Some(VectorIndexWriter::new(
+                vector_index_write,
+                &segment.schema(),
+                settings.vector_index_build_threads,
+            ))
+        } else {
+            None
+        };
+
         let fast_field_write = segment.open_write(SegmentComponent::FastFields)?;
 
         let fieldnorms_write = segment.open_write(SegmentComponent::FieldNorms)?;
@@ -39,6 +69,9 @@ impl SegmentSerializer {
         Ok(SegmentSerializer {
             segment,
             store_writer,
+            blob_store_writer,
+            term_vectors_writer,
+            vector_index_writer,
             fast_field_write,
             fieldnorms_serializer: Some(fieldnorms_serializer),
             postings_serializer,
@@ -48,6 +81,18 @@ impl SegmentSerializer {
     /// The memory used (inclusive childs)
     pub fn mem_usage(&self) -> usize {
         self.store_writer.mem_usage()
+            + self
+                .blob_store_writer
+                .as_ref()
+                .map_or(0, BlobStoreWriter::mem_usage)
+            + self
+                .term_vectors_writer
+                .as_ref()
+                .map_or(0, TermVectorsWriter::mem_usage)
+            + self
+                .vector_index_writer
+                .as_ref()
+                .map_or(0, VectorIndexWriter::mem_usage)
     }
 
     pub fn segment(&self) -> &Segment {
@@ -76,6 +121,21 @@ impl SegmentSerializer {
         &mut self.store_writer
     }
 
+    /// Accessor to the `BlobStoreWriter`, if the schema has blob fields.
+    pub fn get_blob_store_writer(&mut self) -> Option<&mut BlobStoreWriter> {
+        self.blob_store_writer.as_mut()
+    }
+
+    /// Accessor to the `TermVectorsWriter`, if the schema has fields with term vectors.
+    pub fn get_term_vectors_writer(&mut self) -> Option<&mut TermVectorsWriter> {
+        self.term_vectors_writer.as_mut()
+    }
+
+    /// Accessor to the `VectorIndexWriter`, if the schema has dense vector fields.
+    pub fn get_vector_index_writer(&mut self) -> Option<&mut VectorIndexWriter> {
+        self.vector_index_writer.as_mut()
+    }
+
     /// Finalize the segment serialization.
     pub fn close(mut self) -> crate::Result<()> {
         if let Some(fieldnorms_serializer) = self.extract_fieldnorms_serializer() {
@@ -84,6 +144,15 @@ impl SegmentSerializer {
         self.fast_field_write.terminate()?;
         self.postings_serializer.close()?;
         self.store_writer.close()?;
+        if let Some(blob_store_writer) = self.blob_store_writer {
+            blob_store_writer.close()?;
+        }
+        if let Some(term_vectors_writer) = self.term_vectors_writer {
+            term_vectors_writer.close()?;
+        }
+        if let Some(vector_index_writer) = self.vector_index_writer {
+            vector_index_writer.close()?;
+        }
         Ok(())
     }
 }
@@ -6,13 +6,17 @@ use std::path::PathBuf;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, RwLock};
 
+use common::{BinarySerializable, VInt};
 use rayon::{ThreadPool, ThreadPoolBuilder};
 
 use super::segment_manager::SegmentManager;
 use crate::core::META_FILEPATH;
 use crate::directory::{Directory, DirectoryClone, GarbageCollectionResult};
 use crate::fastfield::AliveBitSet;
-use crate::index::{Index, IndexMeta, IndexSettings, Segment, SegmentId, SegmentMeta};
+use crate::index::{
+    Index, IndexMeta, IndexSettings, Segment, SegmentComponent, SegmentId, SegmentMeta,
+    SegmentReader,
+};
 use crate::indexer::delete_queue::DeleteCursor;
 use crate::indexer::index_writer::advance_deletes;
 use crate::indexer::merge_operation::MergeOperationInventory;
@@ -23,7 +27,8 @@ use crate::indexer::{
     DefaultMergePolicy, MergeCandidate, MergeOperation, MergePolicy, SegmentEntry,
     SegmentSerializer,
 };
-use crate::{FutureResult, Opstamp};
+use crate::store::StoreWriter;
+use crate::{DocId, FutureResult, Instant, Opstamp};
 
 const NUM_MERGE_THREADS: usize = 4;
 
@@ -85,6 +90,13 @@ fn garbage_collect_files(
 
 /// Merges a list of segments the list of segment givens in the `segment_entries`.
 /// This function happens in the calling thread and is computationally expensive.
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument(
+        skip_all,
+        fields(num_segments = segment_entries.len(), target_opstamp = target_opstamp)
+    )
+)]
 fn merge(
     index: &Index,
     mut segment_entries: Vec<SegmentEntry>,
@@ -98,6 +110,8 @@ fn merge(
         return Ok(None);
     }
 
+    let start = Instant::now();
+
     // first we need to apply deletes to our segment.
     let merged_segment = index.new_segment();
 
@@ -125,9 +139,48 @@ fn merge(
     let merged_segment_id = merged_segment.id();
 
     let segment_meta = index.new_segment_meta(merged_segment_id, num_docs);
+    let metrics = index.metrics();
+    metrics.merges.inc();
+    metrics.merge_duration.observe(start.elapsed());
     Ok(Some(SegmentEntry::new(segment_meta, delete_cursor, None)))
 }
 
+/// Rewrites the doc store of a segment, replacing its deleted documents by empty documents.
+///
+/// Doc ids are preserved, so that the other components of the segment remain valid. The
+/// new doc store is written with the current doc store settings of the index, to a new file
+/// identified by `store_opstamp`.
+fn compact_doc_store(
+    index: &Index,
+    segment_meta: SegmentMeta,
+    store_opstamp: Opstamp,
+) -> crate::Result<()> {
+    let segment_reader = SegmentReader::open(&index.segment(segment_meta.clone()))?;
+    let store_reader = segment_reader.get_store_reader(1)?;
+
+    // Holding the new segment meta protects the new doc store from garbage collection.
+    let mut compacted_segment = index.segment(segment_meta.with_store_opstamp(store_opstamp));
+    let settings = index.settings();
+    let mut store_writer = StoreWriter::new(
+        compacted_segment.open_write(SegmentComponent::Store)?,
+        settings.docstore_compression,
+        settings.docstore_blocksize,
+        settings.docstore_compress_dedicated_thread,
+    )?;
+    let mut empty_doc_bytes = Vec::new();
+    VInt(0).serialize(&mut empty_doc_bytes)?;
+    for (doc_id, doc_bytes_res) in store_reader.iter_raw(None).enumerate() {
+        let doc_bytes = doc_bytes_res?;
+        if segment_reader.is_deleted(doc_id as DocId) {
+            store_writer.store_bytes(&empty_doc_bytes)?;
+        } else {
+            store_writer.store_bytes(&doc_bytes)?;
+        }
+    }
+    store_writer.close()?;
+    Ok(())
+}
+
 /// Advanced: Merges a list of segments from different indices in a new index.
 ///
 /// Returns `TantivyError` if the indices list is empty or their
@@ -535,6 +588,65 @@ impl SegmentUpdater {
         scheduled_result
     }
 
+    /// Starts the compaction of the doc store of a segment, on a merging thread.
+    ///
+    /// While the compaction runs, the segment is considered as being merged, so that the
+    /// merge policy does not pick it.
+    pub(crate) fn start_doc_store_compaction(
+        &self,
+        segment_id: SegmentId,
+    ) -> FutureResult<SegmentMeta> {
+        let segment_meta = match self.segment_manager.start_merge(&[segment_id]) {
+            Ok(mut segment_entries) => segment_entries.pop().unwrap().meta().clone(),
+            Err(err) => return err.into(),
+        };
+        let merge_operation = self.make_merge_operation(&[segment_id]);
+        let store_opstamp = self.stamper.stamp();
+
+        info!("Starting doc store compaction - {segment_id:?}");
+
+        let (scheduled_result, compaction_future_send) =
+            FutureResult::create("Doc store compaction failed.");
+        let segment_updater = self.clone();
+        self.merge_thread_pool.spawn(move || {
+            let res = compact_doc_store(&segment_updater.index, segment_meta, store_opstamp)
+                .and_then(|()| {
+                    segment_updater.end_doc_store_compaction(merge_operation, store_opstamp)
+                });
+            if let Err(err) = &res {
+                warn!("Doc store compaction of {segment_id:?} was cancelled: {err:?}");
+            }
+            let _send_result = compaction_future_send.send(res);
+        });
+        scheduled_result
+    }
+
+    /// Queues the replacement of the doc store of a segment by its compacted version in the
+    /// segment updater and blocks until it is processed.
+    fn end_doc_store_compaction(
+        &self,
+        merge_operation: MergeOperation,
+        store_opstamp: Opstamp,
+    ) -> crate::Result<SegmentMeta> {
+        let segment_updater = self.clone();
+        self.schedule_task(move || {
+            let segment_id = merge_operation.segment_ids()[0];
+            let previous_metas = segment_updater.load_meta();
+            let (segments_status, segment_meta) = segment_updater
+                .segment_manager
+                .end_doc_store_compaction(segment_id, store_opstamp)?;
+            if segments_status == SegmentsStatus::Committed {
+                segment_updater
+                    .save_metas(previous_metas.opstamp, previous_metas.payload.clone())?;
+            }
+            // The segment can be merged again.
+            drop(merge_operation);
+            let _ = garbage_collect_files(segment_updater);
+            Ok(segment_meta)
+        })
+        .wait()
+    }
+
     pub(crate) fn get_mergeable_segments(&self) -> (Vec<SegmentMeta>, Vec<SegmentMeta>) {
         let merge_segment_ids: HashSet<SegmentId> = self.merge_operations.segment_in_merge();
         self.segment_manager
@@ -668,6 +780,7 @@ mod tests {
     use crate::collector::TopDocs;
     use crate::directory::RamDirectory;
     use crate::fastfield::AliveBitSet;
+    use crate::index::SegmentComponent;
     use crate::indexer::merge_policy::tests::MergeWheneverPossible;
     use crate::indexer::merger::IndexMerger;
     use crate::indexer::segment_updater::merge_filtered_segments;
@@ -836,6 +949,66 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_compact_doc_store() -> crate::Result<()> {
+        let mut schema_builder = Schema::builder();
+        let id_field = schema_builder.add_u64_field("id", INDEXED | STORED);
+        let text_field = schema_builder.add_text_field("text", TEXT | STORED);
+        let index = Index::create_in_ram(schema_builder.build());
+
+        let mut index_writer = index.writer_for_tests()?;
+        for id in 0..1_000u64 {
+            index_writer.add_document(doc!(
+                id_field => id,
+                text_field => format!("document {id} with some text to store"),
+            ))?;
+        }
+        index_writer.commit()?;
+        for id in 0..900u64 {
+            index_writer.delete_term(Term::from_field_u64(id_field, id));
+        }
+        index_writer.commit()?;
+
+        let reader = index.reader()?;
+        let store_num_bytes_before = reader
+            .searcher()
+            .segment_reader(0)
+            .space_usage()?
+            .store()
+            .total();
+        let segment_id = reader.searcher().segment_reader(0).segment_id();
+        let segment_meta = index_writer.compact_doc_store(segment_id).wait()?;
+        assert_eq!(segment_meta.id(), segment_id);
+        assert!(segment_meta.store_opstamp().is_some());
+        assert_eq!(segment_meta.num_deleted_docs(), 900);
+        assert!(index
+            .directory()
+            .exists(&segment_meta.relative_path(SegmentComponent::Store))?);
+
+        reader.reload()?;
+        let searcher = reader.searcher();
+        let segment_reader = searcher.segment_reader(0);
+        assert_eq!(segment_reader.segment_id(), segment_id);
+        assert!(segment_reader.space_usage()?.store().total() < store_num_bytes_before);
+        let store_reader = segment_reader.get_store_reader(1)?;
+        for doc_id in 900..1_000u32 {
+            let doc: TantivyDocument = store_reader.get(doc_id)?;
+            assert_eq!(
+                doc.get_first(id_field).unwrap().as_u64(),
+                Some(doc_id as u64)
+            );
+        }
+        let deleted_doc: TantivyDocument = store_reader.get(0)?;
+        assert_eq!(deleted_doc.field_values().count(), 0);
+
+        // The compacted doc store is committed.
+        assert_eq!(
+            index.searchable_segment_metas()?[0].store_opstamp(),
+            segment_meta.store_opstamp()
+        );
+        Ok(())
+    }
+
     #[test]
     fn test_merge_segments() -> crate::Result<()> {
         let mut indices = vec![];
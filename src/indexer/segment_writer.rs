@@ -15,6 +15,7 @@ use crate::postings::{
 };
 use crate::schema::document::{Document, Value};
 use crate::schema::{FieldEntry, FieldType, Schema, Term, DATE_TIME_PRECISION_INDEXED};
+use crate::termvector::{TermVectorBuilder, TermVectorRecorder};
 use crate::tokenizer::{FacetTokenizer, PreTokenizedStream, TextAnalyzer, Tokenizer};
 use crate::{DocId, Opstamp, TantivyError};
 
@@ -70,10 +71,22 @@ impl SegmentWriter {
     /// - segment: The segment being written
     /// - schema
     pub fn for_segment(memory_budget_in_bytes: usize, segment: Segment) -> crate::Result<Self> {
+        let table_size = compute_initial_table_size(memory_budget_in_bytes)?;
+        Self::for_segment_with_context(segment, IndexingContext::new(table_size))
+    }
+
+    /// Creates a new `SegmentWriter` building the inverted index in `ctx`, typically the
+    /// recycled context of a previous segment.
+    pub(crate) fn for_segment_with_context(
+        segment: Segment,
+        ctx: IndexingContext,
+    ) -> crate::Result<Self> {
         let schema = segment.schema();
         let tokenizer_manager = segment.index().tokenizers().clone();
         let tokenizer_manager_fast_field = segment.index().fast_field_tokenizer().clone();
-        let table_size = compute_initial_table_size(memory_budget_in_bytes)?;
+        let settings = segment.index().settings();
+        let sequence_number_field = settings.resolve_sequence_number_field(&schema)?;
+        let fast_field_serialization_threads = settings.fast_field_serialization_threads;
         let segment_serializer = SegmentSerializer::for_segment(segment)?;
         let per_field_postings_writers = PerFieldPostingsWriter::for_schema(&schema);
         let per_field_text_analyzers = schema
@@ -98,18 +111,23 @@ impl SegmentWriter {
                 })
             })
             .collect::<Result<Vec<_>, _>>()?;
+        let mut fast_field_writers = FastFieldsWriter::from_schema_and_tokenizer_manager(
+            &schema,
+            tokenizer_manager_fast_field,
+        )?;
+        if let Some(sequence_number_field) = sequence_number_field {
+            fast_field_writers.set_sequence_number_field(sequence_number_field);
+        }
+        fast_field_writers.set_num_serialization_threads(fast_field_serialization_threads);
         Ok(Self {
             max_doc: 0,
-            ctx: IndexingContext::new(table_size),
+            ctx,
             per_field_postings_writers,
             fieldnorms_writer: FieldNormsWriter::for_schema(&schema),
             json_path_writer: JsonPathWriter::default(),
             json_positions_per_path: IndexingPositionsPerPath::default(),
             segment_serializer,
-            fast_field_writers: FastFieldsWriter::from_schema_and_tokenizer_manager(
-                &schema,
-                tokenizer_manager_fast_field,
-            )?,
+            fast_field_writers,
             doc_opstamps: Vec::with_capacity(1_000),
             per_field_text_analyzers,
             term_buffer: Term::with_capacity(16),
@@ -121,17 +139,26 @@ impl SegmentWriter {
     ///
     /// Finalize consumes the `SegmentWriter`, so that it cannot
     /// be used afterwards.
-    pub fn finalize(mut self) -> crate::Result<Vec<u64>> {
+    pub fn finalize(self) -> crate::Result<Vec<u64>> {
+        let (doc_opstamps, _ctx) = self.finalize_and_take_context()?;
+        Ok(doc_opstamps)
+    }
+
+    /// Same as [`SegmentWriter::finalize`], also returning the indexing context so that its
+    /// memory can be reused.
+    pub(crate) fn finalize_and_take_context(
+        mut self,
+    ) -> crate::Result<(Vec<u64>, IndexingContext)> {
         self.fieldnorms_writer.fill_up_to_max_doc(self.max_doc);
         remap_and_write(
             self.schema,
             &self.per_field_postings_writers,
-            self.ctx,
+            &self.ctx,
             self.fast_field_writers,
             &self.fieldnorms_writer,
             self.segment_serializer,
         )?;
-        Ok(self.doc_opstamps)
+        Ok((self.doc_opstamps, self.ctx))
     }
 
     /// Returns an estimation of the current memory usage of the segment writer.
@@ -192,7 +219,10 @@ impl SegmentWriter {
                 }
                 FieldType::Str(_) => {
                     let mut indexing_position = IndexingPosition::default();
-                    for value in values {
+                    let mut term_vector_builder = field_entry
+                        .has_term_vectors()
+                        .then(TermVectorBuilder::default);
+                    for (value_ord, value) in values.enumerate() {
                         let value = value.as_value();
 
                         let mut token_stream = if let Some(text) = value.as_str() {
@@ -206,18 +236,47 @@ impl SegmentWriter {
                         };
 
                         assert!(term_buffer.is_empty());
-                        postings_writer.index_text(
-                            doc_id,
-                            &mut *token_stream,
-                            term_buffer,
-                            ctx,
-                            &mut indexing_position,
-                        );
+                        if let Some(term_vector_builder) = term_vector_builder.as_mut() {
+                            let start_position = indexing_position.end_position;
+                            postings_writer.index_text(
+                                doc_id,
+                                &mut TermVectorRecorder::new(
+                                    &mut *token_stream,
+                                    term_vector_builder,
+                                    value_ord as u32,
+                                    start_position,
+                                ),
+                                term_buffer,
+                                ctx,
+                                &mut indexing_position,
+                            );
+                        } else {
+                            postings_writer.index_text(
+                                doc_id,
+                                &mut *token_stream,
+                                term_buffer,
+                                ctx,
+                                &mut indexing_position,
+                            );
+                        }
                     }
                     if field_entry.has_fieldnorms() {
                         self.fieldnorms_writer
                             .record(doc_id, field, indexing_position.num_tokens);
                     }
+                    if let Some(term_vector_builder) = term_vector_builder {
+                        if !term_vector_builder.is_empty() {
+                            let term_vectors_writer = self
+                                .segment_serializer
+                                .get_term_vectors_writer()
+                                .expect("the schema has fields with term vectors");
+                            term_vectors_writer.add_term_vector(
+                                doc_id,
+                                field,
+                                &term_vector_builder.build(),
+                            )?;
+                        }
+                    }
                 }
                 FieldType::U64(_) => {
                     let mut num_vals = 0;
@@ -351,10 +410,17 @@ impl SegmentWriter {
     ) -> crate::Result<()> {
         let AddOperation { document, opstamp } = add_operation;
         self.doc_opstamps.push(opstamp);
-        self.fast_field_writers.add_document(&document)?;
+        self.fast_field_writers
+            .add_document_with_sequence_number(&document, opstamp)?;
         self.index_document(&document)?;
         let doc_writer = self.segment_serializer.get_store_writer();
         doc_writer.store(&document, &self.schema)?;
+        if let Some(blob_writer) = self.segment_serializer.get_blob_store_writer() {
+            blob_writer.store(self.max_doc, &document, &self.schema)?;
+        }
+        if let Some(vector_index_writer) = self.segment_serializer.get_vector_index_writer() {
+            vector_index_writer.add_document(self.max_doc, &document)?;
+        }
         self.max_doc += 1;
         Ok(())
     }
@@ -388,7 +454,7 @@ impl SegmentWriter {
 fn remap_and_write(
     schema: Schema,
     per_field_postings_writers: &PerFieldPostingsWriter,
-    ctx: IndexingContext,
+    ctx: &IndexingContext,
     fast_field_writers: FastFieldsWriter,
     fieldnorms_writer: &FieldNormsWriter,
     mut serializer: SegmentSerializer,
@@ -165,8 +165,17 @@ mod macros;
 mod future_result;
 
 // Re-exports
+// `std::time::Instant::now()` panics on `wasm32-unknown-unknown`, where the clock has to be
+// read through the browser.
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) use std::time::Instant;
+
+pub use columnar;
 pub use common::DateTime;
-pub use {columnar, query_grammar, time};
+pub use query_grammar;
+pub use time;
+#[cfg(target_arch = "wasm32")]
+pub(crate) use web_time::Instant;
 
 pub use crate::error::TantivyError;
 pub use crate::future_result::FutureResult;
@@ -186,12 +195,17 @@ pub mod error;
 pub mod tokenizer;
 
 pub mod aggregation;
+#[cfg(feature = "capi")]
+pub mod capi;
 pub mod collector;
 pub mod directory;
+#[cfg(feature = "arrow")]
+pub mod export;
 pub mod fastfield;
 pub mod fieldnorm;
 #[allow(deprecated)] // Remove with index sorting
 pub mod index;
+pub mod metrics;
 pub mod positions;
 pub mod postings;
 
@@ -201,6 +215,8 @@ pub mod schema;
 pub mod space_usage;
 pub mod store;
 pub mod termdict;
+pub mod termvector;
+pub mod vector;
 
 mod reader;
 
@@ -218,7 +234,7 @@ use serde::{Deserialize, Serialize};
 pub use self::docset::{DocSet, COLLECT_BLOCK_BUFFER_LEN, TERMINATED};
 #[doc(hidden)]
 pub use crate::core::json_utils;
-pub use crate::core::{Executor, Searcher, SearcherGeneration};
+pub use crate::core::{Executor, Searcher, SearcherGeneration, WarmupSpec};
 pub use crate::directory::Directory;
 #[allow(deprecated)] // Remove with index sorting
 pub use crate::index::{
@@ -229,7 +245,7 @@ pub use crate::indexer::{IndexWriter, SingleSegmentIndexWriter};
 pub use crate::schema::{Document, TantivyDocument, Term};
 
 /// Index format version.
-const INDEX_FORMAT_VERSION: u32 = 6;
+const INDEX_FORMAT_VERSION: u32 = 7;
 /// Oldest index format version this tantivy version can read.
 const INDEX_FORMAT_OLDEST_SUPPORTED_VERSION: u32 = 4;
 
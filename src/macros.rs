@@ -12,7 +12,8 @@
 /// )
 /// ```
 ///
-/// The value can be a `u64`, a `&str`, a `i64`, or a `String`.
+/// The value can be a `u64`, a `&str`, a `i64`, or a `String`. The value of a dense vector
+/// field is a [`DenseVector`](crate::vector::DenseVector).
 ///
 /// # Warning
 ///
@@ -0,0 +1,365 @@
+//! Metrics of the activity of an index.
+//!
+//! Each [`Index`](crate::Index) records counters and histograms about its searches, its
+//! indexing, its commits and its merges in an [`IndexMetrics`], shared by its clones and by the
+//! readers and writers opened on it. The metrics are pulled with [`IndexMetrics::gather`],
+//! to be exported to a monitoring system, or formatted directly in the Prometheus text format
+//! with [`IndexMetrics::to_prometheus_text`].
+//!
+//! The hits and misses of the doc store cache are reported by
+//! [`Searcher::doc_store_cache_stats`](crate::Searcher::doc_store_cache_stats).
+//!
+//! ```rust
+//! use tantivy::schema::{Schema, TEXT};
+//! use tantivy::{doc, Index, IndexWriter};
+//!
+//! # fn main() -> tantivy::Result<()> {
+//! let mut schema_builder = Schema::builder();
+//! let title = schema_builder.add_text_field("title", TEXT);
+//! let index = Index::create_in_ram(schema_builder.build());
+//! let mut index_writer: IndexWriter = index.writer(15_000_000)?;
+//! index_writer.add_document(doc!(title => "The Old Man and the Sea"))?;
+//! index_writer.commit()?;
+//!
+//! assert_eq!(index.metrics().documents_added.get(), 1);
+//! let prometheus_text = index.metrics().to_prometheus_text();
+//! assert!(prometheus_text.contains("tantivy_commits_total 1"));
+//! # Ok(())
+//! # }
+//! ```
+
+use std::fmt::Write;
+use std::sync::atomic::{AtomicI64, AtomicU64, Ordering};
+use std::time::Duration;
+
+/// Upper bounds, in seconds, of the buckets of the duration histograms.
+const DURATION_BUCKETS: &[f64] = &[
+    0.0005, 0.001, 0.0025, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0,
+    60.0,
+];
+
+/// Monotonic counter.
+#[derive(Debug, Default)]
+pub struct Counter(AtomicU64);
+
+impl Counter {
+    /// Returns the value of the counter.
+    pub fn get(&self) -> u64 {
+        self.0.load(Ordering::Relaxed)
+    }
+
+    pub(crate) fn inc(&self) {
+        self.inc_by(1);
+    }
+
+    pub(crate) fn inc_by(&self, value: u64) {
+        self.0.fetch_add(value, Ordering::Relaxed);
+    }
+}
+
+/// Value going up and down.
+#[derive(Debug, Default)]
+pub struct Gauge(AtomicI64);
+
+impl Gauge {
+    /// Returns the value of the gauge.
+    pub fn get(&self) -> i64 {
+        self.0.load(Ordering::Relaxed)
+    }
+
+    pub(crate) fn set(&self, value: i64) {
+        self.0.store(value, Ordering::Relaxed);
+    }
+}
+
+/// Distribution of durations, in seconds.
+#[derive(Debug)]
+pub struct Histogram {
+    bucket_counts: Box<[AtomicU64]>,
+    count: AtomicU64,
+    sum_micros: AtomicU64,
+}
+
+impl Default for Histogram {
+    fn default() -> Histogram {
+        Histogram {
+            bucket_counts: DURATION_BUCKETS.iter().map(|_| AtomicU64::new(0)).collect(),
+            count: AtomicU64::new(0),
+            sum_micros: AtomicU64::new(0),
+        }
+    }
+}
+
+impl Histogram {
+    pub(crate) fn observe(&self, duration: Duration) {
+        let seconds = duration.as_secs_f64();
+        if let Some(bucket_ord) = DURATION_BUCKETS
+            .iter()
+            .position(|&upper_bound| seconds <= upper_bound)
+        {
+            self.bucket_counts[bucket_ord].fetch_add(1, Ordering::Relaxed);
+        }
+        self.count.fetch_add(1, Ordering::Relaxed);
+        self.sum_micros
+            .fetch_add(duration.as_micros() as u64, Ordering::Relaxed);
+    }
+
+    /// Returns the current state of the histogram.
+    pub fn snapshot(&self) -> HistogramSnapshot {
+        let mut cumulative_count = 0;
+        let buckets = DURATION_BUCKETS
+            .iter()
+            .zip(self.bucket_counts.iter())
+            .map(|(&upper_bound, bucket_count)| {
+                cumulative_count += bucket_count.load(Ordering::Relaxed);
+                (upper_bound, cumulative_count)
+            })
+            .collect();
+        HistogramSnapshot {
+            buckets,
+            count: self.count.load(Ordering::Relaxed),
+            sum: self.sum_micros.load(Ordering::Relaxed) as f64 / 1_000_000.0,
+        }
+    }
+}
+
+/// State of a [`Histogram`] at some point.
+#[derive(Clone, Debug, PartialEq)]
+pub struct HistogramSnapshot {
+    /// Upper bounds of the buckets, in seconds, with the number of values lower or equal, as in
+    /// Prometheus. The values greater than the last bound are only counted in `count`.
+    pub buckets: Vec<(f64, u64)>,
+    /// Number of values.
+    pub count: u64,
+    /// Sum of the values, in seconds.
+    pub sum: f64,
+}
+
+/// Value of a metric gathered by [`IndexMetrics::gather`].
+#[derive(Clone, Debug, PartialEq)]
+pub enum MetricValue {
+    /// Value of a [`Counter`].
+    Counter(u64),
+    /// Value of a [`Gauge`].
+    Gauge(i64),
+    /// State of a [`Histogram`].
+    Histogram(HistogramSnapshot),
+}
+
+/// Metric gathered by [`IndexMetrics::gather`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct Metric {
+    /// Name of the metric, following the Prometheus conventions.
+    pub name: &'static str,
+    /// Description of the metric.
+    pub help: &'static str,
+    /// Value of the metric.
+    pub value: MetricValue,
+}
+
+/// Metrics of an index.
+#[derive(Debug, Default)]
+pub struct IndexMetrics {
+    /// Number of searches run by the searchers of the index.
+    pub searches: Counter,
+    /// Duration of the searches, from the creation of the weight to the merge of the fruits.
+    pub search_duration: Histogram,
+    /// Number of searches answered by the query cache of a reader.
+    pub query_cache_hits: Counter,
+    /// Number of searches run because their result was not in the query cache of a reader.
+    pub query_cache_misses: Counter,
+    /// Number of segments of the last searcher loaded by a reader.
+    pub searcher_segments: Gauge,
+    /// Number of documents added by the index writers.
+    pub documents_added: Counter,
+    /// Number of commits.
+    pub commits: Counter,
+    /// Duration of the commits, from the preparation of the commit to its end.
+    pub commit_duration: Histogram,
+    /// Number of merges.
+    pub merges: Counter,
+    /// Duration of the merges.
+    pub merge_duration: Histogram,
+}
+
+impl IndexMetrics {
+    /// Returns the current value of every metric.
+    pub fn gather(&self) -> Vec<Metric> {
+        let counter = |name, help, counter: &Counter| Metric {
+            name,
+            help,
+            value: MetricValue::Counter(counter.get()),
+        };
+        let histogram = |name, help, histogram: &Histogram| Metric {
+            name,
+            help,
+            value: MetricValue::Histogram(histogram.snapshot()),
+        };
+        vec![
+            counter(
+                "tantivy_searches_total",
+                "Number of searches.",
+                &self.searches,
+            ),
+            histogram(
+                "tantivy_search_duration_seconds",
+                "Duration of the searches.",
+                &self.search_duration,
+            ),
+            counter(
+                "tantivy_query_cache_hits_total",
+                "Number of searches answered by the query cache.",
+                &self.query_cache_hits,
+            ),
+            counter(
+                "tantivy_query_cache_misses_total",
+                "Number of searches missing the query cache.",
+                &self.query_cache_misses,
+            ),
+            Metric {
+                name: "tantivy_searcher_segments",
+                help: "Number of segments of the last searcher loaded.",
+                value: MetricValue::Gauge(self.searcher_segments.get()),
+            },
+            counter(
+                "tantivy_documents_added_total",
+                "Number of documents added.",
+                &self.documents_added,
+            ),
+            counter("tantivy_commits_total", "Number of commits.", &self.commits),
+            histogram(
+                "tantivy_commit_duration_seconds",
+                "Duration of the commits.",
+                &self.commit_duration,
+            ),
+            counter("tantivy_merges_total", "Number of merges.", &self.merges),
+            histogram(
+                "tantivy_merge_duration_seconds",
+                "Duration of the merges.",
+                &self.merge_duration,
+            ),
+        ]
+    }
+
+    /// Formats the metrics in the Prometheus text exposition format.
+    pub fn to_prometheus_text(&self) -> String {
+        let mut text = String::new();
+        for metric in self.gather() {
+            let metric_type = match metric.value {
+                MetricValue::Counter(_) => "counter",
+                MetricValue::Gauge(_) => "gauge",
+                MetricValue::Histogram(_) => "histogram",
+            };
+            // Writing to a `String` cannot fail.
+            let _ = writeln!(text, "# HELP {} {}", metric.name, metric.help);
+            let _ = writeln!(text, "# TYPE {} {metric_type}", metric.name);
+            match metric.value {
+                MetricValue::Counter(value) => {
+                    let _ = writeln!(text, "{} {value}", metric.name);
+                }
+                MetricValue::Gauge(value) => {
+                    let _ = writeln!(text, "{} {value}", metric.name);
+                }
+                MetricValue::Histogram(snapshot) => {
+                    for (upper_bound, count) in &snapshot.buckets {
+                        let _ = writeln!(
+                            text,
+                            "{}_bucket{{le=\"{upper_bound}\"}} {count}",
+                            metric.name
+                        );
+                    }
+                    let _ = writeln!(
+                        text,
+                        "{}_bucket{{le=\"+Inf\"}} {}",
+                        metric.name, snapshot.count
+                    );
+                    let _ = writeln!(text, "{}_sum {}", metric.name, snapshot.sum);
+                    let _ = writeln!(text, "{}_count {}", metric.name, snapshot.count);
+                }
+            }
+        }
+        text
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::{Histogram, MetricValue};
+    use crate::collector::Count;
+    use crate::indexer::NoMergePolicy;
+    use crate::query::AllQuery;
+    use crate::schema::{Schema, TEXT};
+    use crate::{doc, Index, IndexWriter, ReloadPolicy};
+
+    #[test]
+    fn test_histogram() {
+        let histogram = Histogram::default();
+        histogram.observe(Duration::from_micros(200));
+        histogram.observe(Duration::from_millis(20));
+        histogram.observe(Duration::from_secs(100));
+        let snapshot = histogram.snapshot();
+        assert_eq!(snapshot.count, 3);
+        assert!((snapshot.sum - 100.0202).abs() < 1e-9);
+        assert_eq!(snapshot.buckets[0], (0.0005, 1));
+        assert_eq!(snapshot.buckets[5], (0.025, 2));
+        assert_eq!(snapshot.buckets.last(), Some(&(60.0, 2)));
+    }
+
+    #[test]
+    fn test_index_metrics() -> crate::Result<()> {
+        let mut schema_builder = Schema::builder();
+        let text = schema_builder.add_text_field("text", TEXT);
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer: IndexWriter = index.writer_for_tests()?;
+        index_writer.set_merge_policy(Box::new(NoMergePolicy));
+        index_writer.add_document(doc!(text => "apple"))?;
+        index_writer.commit()?;
+        index_writer.add_document(doc!(text => "apple pie"))?;
+        index_writer.add_document(doc!(text => "tart"))?;
+        index_writer.commit()?;
+
+        let reader = index
+            .reader_builder()
+            .reload_policy(ReloadPolicy::Manual)
+            .query_cache_num_entries(10)
+            .try_into()?;
+        let searcher = reader.searcher();
+        assert_eq!(searcher.search(&AllQuery, &Count)?, 3);
+        assert_eq!(reader.search_cached(&searcher, &AllQuery, &Count)?, 3);
+        assert_eq!(reader.search_cached(&searcher, &AllQuery, &Count)?, 3);
+
+        let segment_ids = index.searchable_segment_ids()?;
+        index_writer.merge(&segment_ids).wait()?;
+
+        let metrics = index.metrics();
+        assert_eq!(metrics.documents_added.get(), 3);
+        assert_eq!(metrics.commits.get(), 2);
+        assert_eq!(metrics.commit_duration.snapshot().count, 2);
+        assert_eq!(metrics.searcher_segments.get(), 2);
+        assert_eq!(metrics.searches.get(), 2);
+        assert_eq!(metrics.query_cache_hits.get(), 1);
+        assert_eq!(metrics.query_cache_misses.get(), 1);
+        assert_eq!(metrics.merges.get(), 1);
+        let merge_duration = metrics
+            .gather()
+            .into_iter()
+            .find(|metric| metric.name == "tantivy_merge_duration_seconds")
+            .unwrap();
+        assert!(matches!(
+            merge_duration.value,
+            MetricValue::Histogram(snapshot) if snapshot.count == 1
+        ));
+
+        let prometheus_text = metrics.to_prometheus_text();
+        assert!(prometheus_text.contains("# TYPE tantivy_searches_total counter\n"));
+        assert!(prometheus_text.contains("\ntantivy_searches_total 2\n"));
+        assert!(prometheus_text.contains("\ntantivy_searcher_segments 2\n"));
+        assert!(prometheus_text.contains("\ntantivy_commit_duration_seconds_count 2\n"));
+        assert!(
+            prometheus_text.contains("\ntantivy_commit_duration_seconds_bucket{le=\"+Inf\"} 2\n")
+        );
+        Ok(())
+    }
+}
@@ -73,6 +73,10 @@ impl BlockEncoder {
     }
 }
 
+/// Decodes the blocks of the posting lists.
+///
+/// The SIMD kernels of [`BitPacker4x`] are selected at runtime, the delta decoding of the doc ids
+/// being done by the same kernels.
 #[derive(Clone)]
 pub struct BlockDecoder {
     bitpacker: BitPacker4x,
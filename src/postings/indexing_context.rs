@@ -1,3 +1,5 @@
+use std::sync::Mutex;
+
 use stacker::{ArenaHashMap, MemoryArena};
 
 use crate::indexer::path_to_unordered_id::PathToUnorderedId;
@@ -28,4 +30,51 @@ impl IndexingContext {
     pub(crate) fn mem_usage(&self) -> usize {
         self.term_index.mem_usage() + self.arena.mem_usage()
     }
+
+    /// Empties the context, keeping its memory to index another segment.
+    pub(crate) fn clear(&mut self) {
+        self.term_index.clear();
+        self.arena.clear();
+        self.path_to_unordered_id = PathToUnorderedId::default();
+    }
+}
+
+/// The indexing contexts of the segments written by an `IndexWriter`, kept to be reused by the
+/// next segments when recycling is enabled.
+#[derive(Default)]
+pub(crate) struct IndexingContextPool {
+    /// `None` if recycling is disabled.
+    contexts: Mutex<Option<Vec<IndexingContext>>>,
+}
+
+impl IndexingContextPool {
+    /// Enables or disables recycling. Disabling it frees the contexts kept so far.
+    pub(crate) fn set_enabled(&self, enabled: bool) {
+        let mut contexts = self.contexts.lock().unwrap();
+        if enabled != contexts.is_some() {
+            *contexts = if enabled { Some(Vec::new()) } else { None };
+        }
+    }
+
+    /// Returns an empty context, if one was released.
+    pub(crate) fn acquire(&self) -> Option<IndexingContext> {
+        self.contexts.lock().unwrap().as_mut()?.pop()
+    }
+
+    /// Keeps `ctx` for a next segment, if recycling is enabled.
+    pub(crate) fn release(&self, mut ctx: IndexingContext) {
+        if self.contexts.lock().unwrap().is_none() {
+            return;
+        }
+        // Clearing the context zeroes its term table: do not hold the lock meanwhile.
+        ctx.clear();
+        if let Some(contexts) = self.contexts.lock().unwrap().as_mut() {
+            contexts.push(ctx);
+        }
+    }
+
+    #[cfg(test)]
+    pub(crate) fn len(&self) -> usize {
+        self.contexts.lock().unwrap().as_ref().map_or(0, Vec::len)
+    }
 }
@@ -20,7 +20,7 @@ mod term_info;
 pub(crate) use stacker::compute_table_memory_size;
 
 pub use self::block_segment_postings::BlockSegmentPostings;
-pub(crate) use self::indexing_context::IndexingContext;
+pub(crate) use self::indexing_context::{IndexingContext, IndexingContextPool};
 pub(crate) use self::per_field_postings_writer::PerFieldPostingsWriter;
 pub use self::postings::Postings;
 pub(crate) use self::postings_writer::{serialize_postings, IndexingPosition, PostingsWriter};
@@ -66,11 +66,16 @@ impl SegmentPostings {
     pub fn create_from_docs(docs: &[u32]) -> SegmentPostings {
         use crate::directory::FileSlice;
         use crate::postings::serializer::PostingsSerializer;
-        use crate::schema::IndexRecordOption;
+        use crate::schema::{Bm25Options, IndexRecordOption};
         let mut buffer = Vec::new();
         {
-            let mut postings_serializer =
-                PostingsSerializer::new(&mut buffer, 0.0, IndexRecordOption::Basic, None);
+            let mut postings_serializer = PostingsSerializer::new(
+                &mut buffer,
+                0.0,
+                Bm25Options::default(),
+                IndexRecordOption::Basic,
+                None,
+            );
             postings_serializer.new_term(docs.len() as u32, false);
             for &doc in docs {
                 postings_serializer.write_doc(doc, 1u32);
@@ -98,7 +103,7 @@ impl SegmentPostings {
         use crate::directory::FileSlice;
         use crate::fieldnorm::FieldNormReader;
         use crate::postings::serializer::PostingsSerializer;
-        use crate::schema::IndexRecordOption;
+        use crate::schema::{Bm25Options, IndexRecordOption};
         use crate::Score;
         let mut buffer: Vec<u8> = Vec::new();
         let fieldnorm_reader = fieldnorms.map(FieldNormReader::for_test);
@@ -117,6 +122,7 @@ impl SegmentPostings {
         let mut postings_serializer = PostingsSerializer::new(
             &mut buffer,
             average_field_norm,
+            Bm25Options::default(),
             IndexRecordOption::WithFreqs,
             fieldnorm_reader,
         );
@@ -11,7 +11,7 @@ use crate::positions::PositionSerializer;
 use crate::postings::compression::{BlockEncoder, VIntEncoder, COMPRESSION_BLOCK_SIZE};
 use crate::postings::skip::SkipSerializer;
 use crate::query::Bm25Weight;
-use crate::schema::{Field, FieldEntry, FieldType, IndexRecordOption, Schema};
+use crate::schema::{Bm25Options, Field, FieldEntry, FieldType, IndexRecordOption, Schema};
 use crate::termdict::TermDictionaryBuilder;
 use crate::{DocId, Score};
 
@@ -131,6 +131,7 @@ impl<'a> FieldSerializer<'a> {
         let postings_serializer = PostingsSerializer::new(
             postings_write,
             average_fieldnorm,
+            field_type.bm25_options(),
             index_record_option,
             fieldnorm_reader,
         );
@@ -307,6 +308,7 @@ pub struct PostingsSerializer<W: Write> {
     bm25_weight: Option<Bm25Weight>,
     avg_fieldnorm: Score, /* Average number of term in the field for that segment.
                            * this value is used to compute the block wand information. */
+    bm25_options: Bm25Options,
     term_has_freq: bool,
 }
 
@@ -314,6 +316,7 @@ impl<W: Write> PostingsSerializer<W> {
     pub fn new(
         write: W,
         avg_fieldnorm: Score,
+        bm25_options: Bm25Options,
         mode: IndexRecordOption,
         fieldnorm_reader: Option<FieldNormReader>,
     ) -> PostingsSerializer<W> {
@@ -332,6 +335,7 @@ impl<W: Write> PostingsSerializer<W> {
             fieldnorm_reader,
             bm25_weight: None,
             avg_fieldnorm,
+            bm25_options,
             term_has_freq: false,
         }
     }
@@ -355,10 +359,11 @@ impl<W: Write> PostingsSerializer<W> {
             return;
         }
 
-        self.bm25_weight = Some(Bm25Weight::for_one_term_without_explain(
+        self.bm25_weight = Some(Bm25Weight::for_one_term_without_explain_with_options(
             term_doc_freq as u64,
             num_docs_in_segment,
             self.avg_fieldnorm,
+            self.bm25_options,
         ));
     }
 
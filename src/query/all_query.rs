@@ -15,6 +15,10 @@ impl Query for AllQuery {
     fn weight(&self, _: EnableScoring<'_>) -> crate::Result<Box<dyn Weight>> {
         Ok(Box::new(AllWeight))
     }
+
+    fn cache_key(&self) -> Option<String> {
+        Some(format!("{self:?}"))
+    }
 }
 
 /// Weight associated with the `AllQuery` query.
@@ -35,6 +39,10 @@ impl Weight for AllWeight {
         }
         Ok(Explanation::new("AllQuery", 1.0))
     }
+
+    fn count(&self, reader: &SegmentReader) -> crate::Result<u32> {
+        Ok(reader.num_docs())
+    }
 }
 
 /// Scorer associated with the `AllQuery` query.
@@ -2,12 +2,9 @@ use serde::{Deserialize, Serialize};
 
 use crate::fieldnorm::FieldNormReader;
 use crate::query::Explanation;
-use crate::schema::Field;
+use crate::schema::{Bm25Options, Field};
 use crate::{Score, Searcher, Term};
 
-const K1: Score = 1.2;
-const B: Score = 0.75;
-
 /// An interface to compute the statistics needed in BM25 scoring.
 ///
 /// The standard implementation is a [Searcher] but you can also
@@ -22,6 +19,14 @@ pub trait Bm25StatisticsProvider {
 
     /// The number of documents containing the given term.
     fn doc_freq(&self, term: &Term) -> crate::Result<u64>;
+
+    /// The BM25 parameters of the given field.
+    ///
+    /// The default implementation returns the default parameters, while the [Searcher] returns
+    /// the ones set in the schema.
+    fn bm25_options(&self, _field: Field) -> Bm25Options {
+        Bm25Options::default()
+    }
 }
 
 impl Bm25StatisticsProvider for Searcher {
@@ -47,6 +52,13 @@ impl Bm25StatisticsProvider for Searcher {
     fn doc_freq(&self, term: &Term) -> crate::Result<u64> {
         self.doc_freq(term)
     }
+
+    fn bm25_options(&self, field: Field) -> Bm25Options {
+        self.schema()
+            .get_field_entry(field)
+            .field_type()
+            .bm25_options()
+    }
 }
 
 pub(crate) fn idf(doc_freq: u64, doc_count: u64) -> Score {
@@ -55,15 +67,16 @@ pub(crate) fn idf(doc_freq: u64, doc_count: u64) -> Score {
     (1.0 + x).ln()
 }
 
-fn cached_tf_component(fieldnorm: u32, average_fieldnorm: Score) -> Score {
-    K1 * (1.0 - B + B * fieldnorm as Score / average_fieldnorm)
+fn tf_component(field_length: Score, average_fieldnorm: Score, options: Bm25Options) -> Score {
+    let (k1, b) = (options.k1(), options.b());
+    k1 * (1.0 - b + b * field_length / average_fieldnorm)
 }
 
-fn compute_tf_cache(average_fieldnorm: Score) -> [Score; 256] {
+fn compute_tf_cache(average_fieldnorm: Score, options: Bm25Options) -> [Score; 256] {
     let mut cache: [Score; 256] = [0.0; 256];
     for (fieldnorm_id, cache_mut) in cache.iter_mut().enumerate() {
         let fieldnorm = FieldNormReader::id_to_fieldnorm(fieldnorm_id as u8);
-        *cache_mut = cached_tf_component(fieldnorm, average_fieldnorm);
+        *cache_mut = tf_component(fieldnorm as Score, average_fieldnorm, options);
     }
     cache
 }
@@ -81,6 +94,7 @@ pub struct Bm25Weight {
     weight: Score,
     cache: [Score; 256],
     average_fieldnorm: Score,
+    options: Bm25Options,
 }
 
 impl Bm25Weight {
@@ -91,10 +105,14 @@ impl Bm25Weight {
             weight: self.weight * boost,
             cache: self.cache,
             average_fieldnorm: self.average_fieldnorm,
+            options: self.options,
         }
     }
 
     /// Construct a [Bm25Weight] for a phrase of terms.
+    ///
+    /// The BM25 parameters of the field are given by
+    /// [`Bm25StatisticsProvider::bm25_options`].
     pub fn for_terms(
         statistics: &dyn Bm25StatisticsProvider,
         terms: &[Term],
@@ -112,13 +130,15 @@ impl Bm25Weight {
         let total_num_tokens = statistics.total_num_tokens(field)?;
         let total_num_docs = statistics.total_num_docs()?;
         let average_fieldnorm = total_num_tokens as Score / total_num_docs as Score;
+        let options = statistics.bm25_options(field);
 
         if terms.len() == 1 {
             let term_doc_freq = statistics.doc_freq(&terms[0])?;
-            Ok(Bm25Weight::for_one_term(
+            Ok(Bm25Weight::for_one_term_with_options(
                 term_doc_freq,
                 total_num_docs,
                 average_fieldnorm,
+                options,
             ))
         } else {
             let mut idf_sum: Score = 0.0;
@@ -127,15 +147,30 @@ impl Bm25Weight {
                 idf_sum += idf(term_doc_freq, total_num_docs);
             }
             let idf_explain = Explanation::new("idf", idf_sum);
-            Ok(Bm25Weight::new(idf_explain, average_fieldnorm))
+            Ok(Bm25Weight::new(idf_explain, average_fieldnorm, options))
         }
     }
 
-    /// Construct a [Bm25Weight] for a single term.
+    /// Construct a [Bm25Weight] for a single term, with the default BM25 parameters.
     pub fn for_one_term(
         term_doc_freq: u64,
         total_num_docs: u64,
         avg_fieldnorm: Score,
+    ) -> Bm25Weight {
+        Bm25Weight::for_one_term_with_options(
+            term_doc_freq,
+            total_num_docs,
+            avg_fieldnorm,
+            Bm25Options::default(),
+        )
+    }
+
+    /// Construct a [Bm25Weight] for a single term, with the given BM25 parameters.
+    pub fn for_one_term_with_options(
+        term_doc_freq: u64,
+        total_num_docs: u64,
+        avg_fieldnorm: Score,
+        options: Bm25Options,
     ) -> Bm25Weight {
         let idf = idf(term_doc_freq, total_num_docs);
         let mut idf_explain =
@@ -145,35 +180,61 @@ impl Bm25Weight {
             term_doc_freq as Score,
         );
         idf_explain.add_const("N, total number of docs", total_num_docs as Score);
-        Bm25Weight::new(idf_explain, avg_fieldnorm)
+        Bm25Weight::new(idf_explain, avg_fieldnorm, options)
     }
-    /// Construct a [Bm25Weight] for a single term.
+    /// Construct a [Bm25Weight] for a single term, with the default BM25 parameters.
     /// This method does not carry the [Explanation] for the idf.
     pub fn for_one_term_without_explain(
         term_doc_freq: u64,
         total_num_docs: u64,
         avg_fieldnorm: Score,
+    ) -> Bm25Weight {
+        Bm25Weight::for_one_term_without_explain_with_options(
+            term_doc_freq,
+            total_num_docs,
+            avg_fieldnorm,
+            Bm25Options::default(),
+        )
+    }
+
+    /// Construct a [Bm25Weight] for a single term, with the given BM25 parameters.
+    /// This method does not carry the [Explanation] for the idf.
+    pub fn for_one_term_without_explain_with_options(
+        term_doc_freq: u64,
+        total_num_docs: u64,
+        avg_fieldnorm: Score,
+        options: Bm25Options,
     ) -> Bm25Weight {
         let idf = idf(term_doc_freq, total_num_docs);
-        Bm25Weight::new_without_explain(idf, avg_fieldnorm)
+        Bm25Weight::new_without_explain(idf, avg_fieldnorm, options)
     }
 
-    pub(crate) fn new(idf_explain: Explanation, average_fieldnorm: Score) -> Bm25Weight {
-        let weight = idf_explain.value() * (1.0 + K1);
+    pub(crate) fn new(
+        idf_explain: Explanation,
+        average_fieldnorm: Score,
+        options: Bm25Options,
+    ) -> Bm25Weight {
+        let weight = idf_explain.value() * (1.0 + options.k1());
         Bm25Weight {
             idf_explain: Some(idf_explain),
             weight,
-            cache: compute_tf_cache(average_fieldnorm),
+            cache: compute_tf_cache(average_fieldnorm, options),
             average_fieldnorm,
+            options,
         }
     }
-    pub(crate) fn new_without_explain(idf: f32, average_fieldnorm: Score) -> Bm25Weight {
-        let weight = idf * (1.0 + K1);
+    pub(crate) fn new_without_explain(
+        idf: f32,
+        average_fieldnorm: Score,
+        options: Bm25Options,
+    ) -> Bm25Weight {
+        let weight = idf * (1.0 + options.k1());
         Bm25Weight {
             idf_explain: None,
             weight,
-            cache: compute_tf_cache(average_fieldnorm),
+            cache: compute_tf_cache(average_fieldnorm, options),
             average_fieldnorm,
+            options,
         }
     }
 
@@ -183,6 +244,11 @@ impl Bm25Weight {
         self.weight * self.tf_factor(fieldnorm_id, term_freq)
     }
 
+    /// Returns the BM25 parameters of the weight.
+    pub(crate) fn options(&self) -> Bm25Options {
+        self.options
+    }
+
     /// Compute the maximum possible BM25 score given this weight.
     pub fn max_score(&self) -> Score {
         self.score(255u8, 2_013_265_944)
@@ -195,15 +261,36 @@ impl Bm25Weight {
         term_freq / (term_freq + norm)
     }
 
+    /// Compute the BM25 score of a document given the exact length of its field, rather than its
+    /// fieldnorm id.
+    ///
+    /// The term frequency and the field length do not have to be integers, which is what BM25F
+    /// needs to weight the fields it combines.
+    #[inline]
+    pub(crate) fn score_with_field_length(&self, field_length: Score, term_freq: Score) -> Score {
+        let norm = tf_component(field_length, self.average_fieldnorm, self.options);
+        self.weight * (term_freq / (term_freq + norm))
+    }
+
     /// Produce an [Explanation] of a BM25 score.
     pub fn explain(&self, fieldnorm_id: u8, term_freq: u32) -> Explanation {
+        self.explain_with_field_length(
+            FieldNormReader::id_to_fieldnorm(fieldnorm_id) as Score,
+            term_freq as Score,
+        )
+    }
+
+    /// Produce an [Explanation] of a score computed by [`Bm25Weight::score_with_field_length`].
+    pub(crate) fn explain_with_field_length(
+        &self,
+        field_length: Score,
+        term_freq: Score,
+    ) -> Explanation {
         // The explain format is directly copied from Lucene's.
         // (So, Kudos to Lucene)
-        let score = self.score(fieldnorm_id, term_freq);
-
-        let norm = self.cache[fieldnorm_id as usize];
-        let term_freq = term_freq as Score;
+        let norm = tf_component(field_length, self.average_fieldnorm, self.options);
         let right_factor = term_freq / (term_freq + norm);
+        let score = self.weight * right_factor;
 
         let mut tf_explanation = Explanation::new(
             "freq / (freq + k1 * (1 - b + b * dl / avgdl))",
@@ -211,16 +298,13 @@ impl Bm25Weight {
         );
 
         tf_explanation.add_const("freq, occurrences of term within document", term_freq);
-        tf_explanation.add_const("k1, term saturation parameter", K1);
-        tf_explanation.add_const("b, length normalization parameter", B);
-        tf_explanation.add_const(
-            "dl, length of field",
-            FieldNormReader::id_to_fieldnorm(fieldnorm_id) as Score,
-        );
+        tf_explanation.add_const("k1, term saturation parameter", self.options.k1());
+        tf_explanation.add_const("b, length normalization parameter", self.options.b());
+        tf_explanation.add_const("dl, length of field", field_length);
         tf_explanation.add_const("avgdl, average length of field", self.average_fieldnorm);
 
         let mut explanation = Explanation::new("TermQuery, product of...", score);
-        explanation.add_detail(Explanation::new("(K1+1)", K1 + 1.0));
+        explanation.add_detail(Explanation::new("(K1+1)", self.options.k1() + 1.0));
         if let Some(idf_explain) = &self.idf_explain {
             explanation.add_detail(idf_explain.clone());
         }
@@ -233,11 +317,44 @@ impl Bm25Weight {
 mod tests {
 
     use super::idf;
-    use crate::{assert_nearly_equals, Score};
+    use crate::collector::TopDocs;
+    use crate::query::{Query, TermQuery};
+    use crate::schema::{Bm25Options, IndexRecordOption, Schema, TextFieldIndexing, TextOptions};
+    use crate::{assert_nearly_equals, doc, Index, IndexWriter, Score, Term};
 
     #[test]
     fn test_idf() {
         let score: Score = 2.0;
         assert_nearly_equals!(idf(1, 2), score.ln());
     }
+
+    #[test]
+    fn test_bm25_options_from_schema() -> crate::Result<()> {
+        let mut schema_builder = Schema::builder();
+        // Without length normalization, only the term frequency matters.
+        let text_options = TextOptions::default().set_indexing_options(
+            TextFieldIndexing::default()
+                .set_index_option(IndexRecordOption::WithFreqs)
+                .set_bm25(Bm25Options::new(2.0, 0.0)),
+        );
+        let text = schema_builder.add_text_field("text", text_options);
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer: IndexWriter = index.writer_for_tests()?;
+        index_writer.add_document(doc!(text => "a b c d e f"))?;
+        index_writer.add_document(doc!(text => "a a"))?;
+        index_writer.add_document(doc!(text => "b"))?;
+        index_writer.commit()?;
+        let searcher = index.reader()?.searcher();
+        let query = TermQuery::new(
+            Term::from_field_text(text, "a"),
+            IndexRecordOption::WithFreqs,
+        );
+        let top_docs = searcher.search(&query, &TopDocs::with_limit(2))?;
+        let idf = idf(2, 3);
+        assert_nearly_equals!(top_docs[0].0, idf * 3.0 * 2.0 / (2.0 + 2.0));
+        assert_nearly_equals!(top_docs[1].0, idf * 3.0 * 1.0 / (1.0 + 2.0));
+        let explanation = query.explain(&searcher, top_docs[1].1)?;
+        assert_nearly_equals!(explanation.value(), top_docs[1].0);
+        Ok(())
+    }
 }
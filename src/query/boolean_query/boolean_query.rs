@@ -136,6 +136,25 @@ use crate::schema::{IndexRecordOption, Term};
 pub struct BooleanQuery {
     subqueries: Vec<(Occur, Box<dyn Query>)>,
     minimum_number_should_match: usize,
+    should_score_mode: ShouldScoreMode,
+}
+
+/// How a [`BooleanQuery`] combines the scores of the `Should` clauses matching a document.
+///
+/// The scores of the `Must` clauses are always summed, and the combined score of the `Should`
+/// clauses is added to them.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ShouldScoreMode {
+    /// Sum of the scores.
+    #[default]
+    Sum,
+    /// Maximum of the scores, like a
+    /// [`DisjunctionMaxQuery`](crate::query::DisjunctionMaxQuery) without tie breaker.
+    Max,
+    /// Average of the scores.
+    Avg,
+    /// Score of the first matching clause, in the order of the clauses.
+    First,
 }
 
 impl Clone for BooleanQuery {
@@ -148,6 +167,7 @@ impl Clone for BooleanQuery {
         Self {
             subqueries,
             minimum_number_should_match: self.minimum_number_should_match,
+            should_score_mode: self.should_score_mode,
         }
     }
 }
@@ -165,12 +185,14 @@ impl Query for BooleanQuery {
             .iter()
             .map(|(occur, subquery)| Ok((*occur, subquery.weight(enable_scoring)?)))
             .collect::<crate::Result<_>>()?;
-        Ok(Box::new(BooleanWeight::with_minimum_number_should_match(
+        let mut weight = BooleanWeight::with_minimum_number_should_match(
             sub_weights,
             self.minimum_number_should_match,
             enable_scoring.is_scoring_enabled(),
             Box::new(SumWithCoordsCombiner::default),
-        )))
+        );
+        weight.set_should_score_mode(self.should_score_mode);
+        Ok(Box::new(weight))
     }
 
     fn query_terms<'a>(&'a self, visitor: &mut dyn FnMut(&'a Term, bool)) {
@@ -178,6 +200,25 @@ impl Query for BooleanQuery {
             subquery.query_terms(visitor);
         }
     }
+
+    fn query_phrases<'a>(&'a self, visitor: &mut dyn FnMut(&'a [(usize, Term)], u32)) {
+        for (_occur, subquery) in &self.subqueries {
+            subquery.query_phrases(visitor);
+        }
+    }
+
+    fn cache_key(&self) -> Option<String> {
+        let subqueries = self
+            .subqueries
+            .iter()
+            .map(|(occur, subquery)| Some((*occur, subquery.cache_key()?)))
+            .collect::<Option<Vec<_>>>()?;
+        Some(format!(
+            "BooleanQuery(subqueries={subqueries:?}, minimum_number_should_match={}, \
+             should_score_mode={:?})",
+            self.minimum_number_should_match, self.should_score_mode
+        ))
+    }
 }
 
 impl BooleanQuery {
@@ -207,6 +248,7 @@ impl BooleanQuery {
         BooleanQuery {
             subqueries,
             minimum_number_should_match,
+            should_score_mode: ShouldScoreMode::default(),
         }
     }
 
@@ -220,6 +262,16 @@ impl BooleanQuery {
         self.minimum_number_should_match = minimum_number_should_match;
     }
 
+    /// Getter for `should_score_mode`
+    pub fn get_should_score_mode(&self) -> ShouldScoreMode {
+        self.should_score_mode
+    }
+
+    /// Setter for `should_score_mode`, the way the scores of the `Should` clauses are combined.
+    pub fn set_should_score_mode(&mut self, should_score_mode: ShouldScoreMode) {
+        self.should_score_mode = should_score_mode;
+    }
+
     /// Returns the intersection of the queries.
     pub fn intersection(queries: Vec<Box<dyn Query>>) -> BooleanQuery {
         let subqueries = queries.into_iter().map(|s| (Occur::Must, s)).collect();
@@ -268,11 +320,11 @@ impl BooleanQuery {
 mod tests {
     use std::collections::HashSet;
 
-    use super::BooleanQuery;
-    use crate::collector::{Count, DocSetCollector};
-    use crate::query::{Query, QueryClone, QueryParser, TermQuery};
+    use super::{BooleanQuery, ShouldScoreMode};
+    use crate::collector::{Count, DocSetCollector, TopDocs};
+    use crate::query::{Occur, Query, QueryClone, QueryParser, TermQuery};
     use crate::schema::{Field, IndexRecordOption, Schema, TEXT};
-    use crate::{DocAddress, DocId, Index, Term};
+    use crate::{assert_nearly_equals, DocAddress, DocId, Index, Score, Searcher, Term};
 
     fn create_test_index() -> crate::Result<Index> {
         let mut schema_builder = Schema::builder();
@@ -415,6 +467,66 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_should_score_mode() -> crate::Result<()> {
+        let index = create_test_index()?;
+        let searcher = index.reader()?.searcher();
+        let text = index.schema().get_field("text").unwrap();
+        let term_query = |token: &str| -> Box<dyn Query> {
+            Box::new(TermQuery::new(
+                Term::from_field_text(text, token),
+                IndexRecordOption::WithFreqs,
+            ))
+        };
+        // The scores of the matching documents, by doc id.
+        let doc_scores =
+            |searcher: &Searcher, query: &dyn Query| -> crate::Result<Vec<(DocId, Score)>> {
+                let mut doc_scores: Vec<(DocId, Score)> = searcher
+                    .search(query, &TopDocs::with_limit(10))?
+                    .into_iter()
+                    .map(|(score, doc_address)| (doc_address.doc_id, score))
+                    .collect();
+                doc_scores.sort_by_key(|&(doc, _)| doc);
+                Ok(doc_scores)
+            };
+        let term_score = |token: &str, doc: DocId| -> crate::Result<Score> {
+            let scores = doc_scores(&searcher, term_query(token).as_ref())?;
+            Ok(scores.into_iter().find(|&(d, _)| d == doc).unwrap().1)
+        };
+        let (a2, b2) = (term_score("a", 2)?, term_score("b", 2)?);
+        assert!(a2 != b2);
+
+        let mut query = BooleanQuery::union(vec![term_query("b"), term_query("a")]);
+        for (score_mode, expected) in [
+            (ShouldScoreMode::Sum, a2 + b2),
+            (ShouldScoreMode::Max, a2.max(b2)),
+            (ShouldScoreMode::Avg, (a2 + b2) / 2.0),
+            (ShouldScoreMode::First, b2),
+        ] {
+            query.set_should_score_mode(score_mode);
+            let scores = doc_scores(&searcher, &query)?;
+            assert_eq!(scores.len(), 4);
+            assert_nearly_equals!(scores[2].1, expected);
+            assert_nearly_equals!(scores[1].1, term_score("a", 1)?);
+            let explanation = query.explain(&searcher, DocAddress::new(0, 2))?;
+            assert_nearly_equals!(explanation.value(), expected);
+        }
+
+        // The scores of the must clauses are still summed.
+        let mut query = BooleanQuery::new(vec![
+            (Occur::Must, term_query("a")),
+            (Occur::Should, term_query("b")),
+            (Occur::Should, term_query("c")),
+            (Occur::Should, term_query("a")),
+        ]);
+        query.set_should_score_mode(ShouldScoreMode::First);
+        let scores = doc_scores(&searcher, &query)?;
+        assert_eq!(scores.len(), 3);
+        assert_nearly_equals!(scores[1].1, a2 + b2);
+        assert_nearly_equals!(scores[2].1, 2.0 * term_score("a", 3)?);
+        Ok(())
+    }
+
     #[test]
     pub fn test_json_array_pitfall_bag_of_terms() -> crate::Result<()> {
         let mut schema_builder = Schema::builder();
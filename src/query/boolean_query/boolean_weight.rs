@@ -1,11 +1,16 @@
+use std::any::TypeId;
 use std::collections::HashMap;
 
+use super::score_mode_union::ScoreModeUnion;
+use super::ShouldScoreMode;
 use crate::docset::COLLECT_BLOCK_BUFFER_LEN;
 use crate::index::SegmentReader;
 use crate::postings::FreqReadingOption;
 use crate::query::disjunction::Disjunction;
 use crate::query::explanation::does_not_match;
-use crate::query::score_combiner::{DoNothingCombiner, ScoreCombiner};
+use crate::query::score_combiner::{
+    DoNothingCombiner, ScoreCombiner, SumCombiner, SumWithCoordsCombiner,
+};
 use crate::query::term_query::TermScorer;
 use crate::query::weight::{for_each_docset_buffered, for_each_pruning_scorer, for_each_scorer};
 use crate::query::{
@@ -75,6 +80,37 @@ where
     SpecializedScorer::Other(Box::new(Union::build(scorers, score_combiner_fn)))
 }
 
+/// Returns true if `TScoreCombiner` sums the scores of the clauses, so that the clauses of nested
+/// sums can be summed directly.
+fn is_sum_combiner<TScoreCombiner: ScoreCombiner>() -> bool {
+    let type_id = TypeId::of::<TScoreCombiner>();
+    type_id == TypeId::of::<SumCombiner>() || type_id == TypeId::of::<SumWithCoordsCombiner>()
+}
+
+/// Pushes the scorer of the should clause `weight` to `scorers`.
+///
+/// If the clause is itself a [`BooleanQuery`](crate::query::BooleanQuery) summing the scores of
+/// its should clauses, their scorers are pushed instead. The scores are the same, but the
+/// disjunctions of terms nested in a disjunction, like the ones the query parser produces for
+/// several default fields, become a single union of terms to which block-max WAND applies.
+fn push_should_scorers(
+    weight: &dyn Weight,
+    reader: &SegmentReader,
+    boost: Score,
+    scorers: &mut Vec<Box<dyn Scorer>>,
+) -> crate::Result<()> {
+    if let Some(boolean_weight) = weight.downcast_ref::<BooleanWeight<SumWithCoordsCombiner>>() {
+        if boolean_weight.is_sum_disjunction() {
+            for (_, sub_weight) in &boolean_weight.weights {
+                push_should_scorers(sub_weight.as_ref(), reader, boost, scorers)?;
+            }
+            return Ok(());
+        }
+    }
+    scorers.push(weight.scorer(reader, boost)?);
+    Ok(())
+}
+
 fn into_box_scorer<TScoreCombiner: ScoreCombiner>(
     scorer: SpecializedScorer,
     score_combiner_fn: impl Fn() -> TScoreCombiner,
@@ -92,6 +128,7 @@ fn into_box_scorer<TScoreCombiner: ScoreCombiner>(
 pub struct BooleanWeight<TScoreCombiner: ScoreCombiner> {
     weights: Vec<(Occur, Box<dyn Weight>)>,
     minimum_number_should_match: usize,
+    should_score_mode: ShouldScoreMode,
     scoring_enabled: bool,
     score_combiner_fn: Box<dyn Fn() -> TScoreCombiner + Sync + Send>,
 }
@@ -108,6 +145,7 @@ impl<TScoreCombiner: ScoreCombiner> BooleanWeight<TScoreCombiner> {
             scoring_enabled,
             score_combiner_fn,
             minimum_number_should_match: 1,
+            should_score_mode: ShouldScoreMode::Sum,
         }
     }
 
@@ -121,23 +159,53 @@ impl<TScoreCombiner: ScoreCombiner> BooleanWeight<TScoreCombiner> {
         BooleanWeight {
             weights,
             minimum_number_should_match,
+            should_score_mode: ShouldScoreMode::Sum,
             scoring_enabled,
             score_combiner_fn,
         }
     }
 
+    /// Sets how the scores of the should clauses are combined.
+    ///
+    /// With [`ShouldScoreMode::Sum`], the default, they are combined with the score combiner of
+    /// the weight.
+    pub fn set_should_score_mode(&mut self, should_score_mode: ShouldScoreMode) {
+        self.should_score_mode = should_score_mode;
+    }
+
+    /// Returns true if the score of a document is the sum of the scores of the should clauses
+    /// it matches, and matching any of them is enough.
+    fn sums_should_clauses(&self) -> bool {
+        self.scoring_enabled
+            && self.minimum_number_should_match <= 1
+            && self.should_score_mode == ShouldScoreMode::Sum
+            && is_sum_combiner::<TScoreCombiner>()
+    }
+
+    /// Returns true if the weight only has should clauses, whose scores are summed.
+    fn is_sum_disjunction(&self) -> bool {
+        self.sums_should_clauses()
+            && !self.weights.is_empty()
+            && self
+                .weights
+                .iter()
+                .all(|(occur, _)| *occur == Occur::Should)
+    }
+
     fn per_occur_scorers(
         &self,
         reader: &SegmentReader,
         boost: Score,
     ) -> crate::Result<HashMap<Occur, Vec<Box<dyn Scorer>>>> {
         let mut per_occur_scorers: HashMap<Occur, Vec<Box<dyn Scorer>>> = HashMap::new();
+        let flatten_should_clauses = self.sums_should_clauses();
         for (occur, subweight) in &self.weights {
-            let sub_scorer: Box<dyn Scorer> = subweight.scorer(reader, boost)?;
-            per_occur_scorers
-                .entry(*occur)
-                .or_default()
-                .push(sub_scorer);
+            let occur_scorers = per_occur_scorers.entry(*occur).or_default();
+            if *occur == Occur::Should && flatten_should_clauses {
+                push_should_scorers(subweight.as_ref(), reader, boost, occur_scorers)?;
+            } else {
+                occur_scorers.push(subweight.scorer(reader, boost)?);
+            }
         }
         Ok(per_occur_scorers)
     }
@@ -164,29 +232,49 @@ impl<TScoreCombiner: ScoreCombiner> BooleanWeight<TScoreCombiner> {
             if self.minimum_number_should_match > num_of_should_scorers {
                 return Ok(SpecializedScorer::Other(Box::new(EmptyScorer)));
             }
-            match self.minimum_number_should_match {
-                0 => CombinationMethod::Optional(scorer_union(should_scorers, &score_combiner_fn)),
-                1 => CombinationMethod::Required(into_box_scorer(
-                    scorer_union(should_scorers, &score_combiner_fn),
-                    &score_combiner_fn,
-                )),
-                n if num_of_should_scorers == n => {
-                    // When num_of_should_scorers equals the number of should clauses,
-                    // they are no different from must clauses.
-                    must_scorers = match must_scorers.take() {
-                        Some(mut must_scorers) => {
-                            must_scorers.append(&mut should_scorers);
-                            Some(must_scorers)
-                        }
-                        None => Some(should_scorers),
-                    };
-                    CombinationMethod::Ignored
-                }
-                _ => CombinationMethod::Required(scorer_disjunction(
+            if self.scoring_enabled
+                && self.should_score_mode != ShouldScoreMode::Sum
+                && num_of_should_scorers > 1
+            {
+                // The union and block-max WAND only know how to sum the scores.
+                let should_scorer: Box<dyn Scorer> = Box::new(ScoreModeUnion::new(
                     should_scorers,
-                    score_combiner_fn(),
-                    self.minimum_number_should_match,
-                )),
+                    self.should_score_mode,
+                    self.minimum_number_should_match.max(1),
+                ));
+                if self.minimum_number_should_match == 0 {
+                    CombinationMethod::Optional(SpecializedScorer::Other(should_scorer))
+                } else {
+                    CombinationMethod::Required(should_scorer)
+                }
+            } else {
+                match self.minimum_number_should_match {
+                    0 => CombinationMethod::Optional(scorer_union(
+                        should_scorers,
+                        &score_combiner_fn,
+                    )),
+                    1 => CombinationMethod::Required(into_box_scorer(
+                        scorer_union(should_scorers, &score_combiner_fn),
+                        &score_combiner_fn,
+                    )),
+                    n if num_of_should_scorers == n => {
+                        // When num_of_should_scorers equals the number of should clauses,
+                        // they are no different from must clauses.
+                        must_scorers = match must_scorers.take() {
+                            Some(mut must_scorers) => {
+                                must_scorers.append(&mut should_scorers);
+                                Some(must_scorers)
+                            }
+                            None => Some(should_scorers),
+                        };
+                        CombinationMethod::Ignored
+                    }
+                    _ => CombinationMethod::Required(scorer_disjunction(
+                        should_scorers,
+                        score_combiner_fn(),
+                        self.minimum_number_should_match,
+                    )),
+                }
             }
         } else {
             // None of should clauses are provided.
@@ -277,7 +365,19 @@ impl<TScoreCombiner: ScoreCombiner + Sync> Weight for BooleanWeight<TScoreCombin
             return Ok(Explanation::new("BooleanQuery with no scoring", 1.0));
         }
 
-        let mut explanation = Explanation::new("BooleanClause. sum of ...", scorer.score());
+        let description = match self.should_score_mode {
+            ShouldScoreMode::Sum => "BooleanClause. sum of ...",
+            ShouldScoreMode::Max => {
+                "BooleanClause. sum of must clauses and max of should clauses ..."
+            }
+            ShouldScoreMode::Avg => {
+                "BooleanClause. sum of must clauses and average of should clauses ..."
+            }
+            ShouldScoreMode::First => {
+                "BooleanClause. sum of must clauses and first matching should clause ..."
+            }
+        };
+        let mut explanation = Explanation::new(description, scorer.score());
         for (occur, subweight) in &self.weights {
             if is_positive_occur(*occur) {
                 if let Ok(child_explanation) = subweight.explain(reader, doc) {
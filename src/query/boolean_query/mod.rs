@@ -1,9 +1,10 @@
 mod block_wand;
 mod boolean_query;
 mod boolean_weight;
+mod score_mode_union;
 
 pub(crate) use self::block_wand::{block_wand, block_wand_single_scorer};
-pub use self::boolean_query::BooleanQuery;
+pub use self::boolean_query::{BooleanQuery, ShouldScoreMode};
 pub use self::boolean_weight::BooleanWeight;
 
 #[cfg(test)]
@@ -16,7 +17,7 @@ mod tests {
     use crate::query::term_query::TermScorer;
     use crate::query::{
         EnableScoring, Intersection, Occur, Query, QueryParser, RequiredOptionalScorer, Scorer,
-        TermQuery,
+        TermQuery, Union,
     };
     use crate::schema::*;
     use crate::{assert_nearly_equals, DocAddress, DocId, Index, IndexWriter, Score};
@@ -315,4 +316,89 @@ mod tests {
         assert_nearly_equals!(explanation.value(), std::f32::consts::LN_2);
         Ok(())
     }
+
+    #[test]
+    pub fn test_nested_disjunction_block_wand() -> crate::Result<()> {
+        let mut schema_builder = Schema::builder();
+        let title = schema_builder.add_text_field("title", TEXT);
+        let body = schema_builder.add_text_field("body", TEXT);
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer: IndexWriter = index.writer_for_tests()?;
+        let words = ["a", "b", "c", "d", "e"];
+        let text = |doc_id: usize, multiplier: usize, len: usize| {
+            (0..len)
+                .map(|i| words[(doc_id * multiplier + i * i) % words.len()])
+                .collect::<Vec<_>>()
+                .join(" ")
+        };
+        for doc_id in 0..1_000 {
+            index_writer.add_document(doc!(
+                title => text(doc_id, 7, 1 + doc_id % 3),
+                body => text(doc_id, 13, 1 + doc_id % 11),
+            ))?;
+        }
+        index_writer.commit()?;
+        let searcher = index.reader()?.searcher();
+
+        // Each word is a disjunction over the two fields, nested in the disjunction of the words.
+        let query = QueryParser::for_index(&index, vec![title, body]).parse_query("a b")?;
+        let weight = query.weight(EnableScoring::enabled_from_searcher(&searcher))?;
+        let scorer = weight.scorer(searcher.segment_reader(0u32), 1.0)?;
+        assert!(scorer.is::<Union<TermScorer, SumWithCoordsCombiner>>());
+
+        let top_docs = searcher.search(&query, &TopDocs::with_limit(10))?;
+        let mut all_scores = Vec::new();
+        weight.for_each(searcher.segment_reader(0u32), &mut |_, score| {
+            all_scores.push(score)
+        })?;
+        all_scores.sort_by(|left, right| right.total_cmp(left));
+        assert_eq!(top_docs.len(), 10);
+        for ((score, doc_address), expected_score) in top_docs.iter().zip(&all_scores) {
+            assert_nearly_equals!(*score, *expected_score);
+            assert_nearly_equals!(query.explain(&searcher, *doc_address)?.value(), *score);
+        }
+        Ok(())
+    }
+
+    #[test]
+    pub fn test_mixed_boolean_top_docs_max_score_pruning() -> crate::Result<()> {
+        let mut schema_builder = Schema::builder();
+        let text_field = schema_builder.add_text_field("text", TEXT);
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer: IndexWriter = index.writer_for_tests()?;
+        let words = ["a", "b", "c", "d", "e", "f"];
+        for doc_id in 0..2_000 {
+            let text = (0..1 + doc_id % 7)
+                .map(|i| words[(doc_id * 11 + i * i * 3) % words.len()])
+                .collect::<Vec<_>>()
+                .join(" ");
+            index_writer.add_document(doc!(text_field => text))?;
+        }
+        index_writer.commit()?;
+        let searcher = index.reader()?.searcher();
+        let query_parser = QueryParser::for_index(&index, vec![text_field]);
+        for query_str in [
+            "+a (b c)",
+            "+a +b",
+            "+a +(b c)",
+            "+(a b) c^3",
+            "+a b -d",
+            "+a^0.5 (b^2 +c)",
+        ] {
+            let query = query_parser.parse_query(query_str)?;
+            let weight = query.weight(EnableScoring::enabled_from_searcher(&searcher))?;
+            let mut all_scores = Vec::new();
+            weight.for_each(searcher.segment_reader(0u32), &mut |_, score| {
+                all_scores.push(score)
+            })?;
+            all_scores.sort_by(|left, right| right.total_cmp(left));
+            let top_docs = searcher.search(&query, &TopDocs::with_limit(10))?;
+            assert_eq!(top_docs.len(), all_scores.len().min(10), "{query_str}");
+            for ((score, doc_address), expected_score) in top_docs.iter().zip(&all_scores) {
+                assert_nearly_equals!(*score, *expected_score);
+                assert_nearly_equals!(query.explain(&searcher, *doc_address)?.value(), *score);
+            }
+        }
+        Ok(())
+    }
 }
@@ -0,0 +1,183 @@
+use super::ShouldScoreMode;
+use crate::query::Scorer;
+use crate::{DocId, DocSet, Score, TERMINATED};
+
+/// Union of the scorers of the `Should` clauses, combining their scores with a
+/// [`ShouldScoreMode`].
+///
+/// Unlike [`Union`](crate::query::Union), the scorers are kept in the order of the clauses, so
+/// that the first matching clause is known. Each document costs a pass over all of the scorers,
+/// which is fine for the handful of clauses these modes are used with.
+pub(crate) struct ScoreModeUnion {
+    scorers: Vec<Box<dyn Scorer>>,
+    score_mode: ShouldScoreMode,
+    minimum_matches_required: usize,
+    doc: DocId,
+}
+
+impl ScoreModeUnion {
+    pub fn new(
+        scorers: Vec<Box<dyn Scorer>>,
+        score_mode: ShouldScoreMode,
+        minimum_matches_required: usize,
+    ) -> ScoreModeUnion {
+        debug_assert!(minimum_matches_required >= 1);
+        let mut union = ScoreModeUnion {
+            scorers,
+            score_mode,
+            minimum_matches_required,
+            doc: 0,
+        };
+        union.doc = union.next_match();
+        union
+    }
+
+    /// Returns the first document, from the current position of the scorers, that is matched by
+    /// enough of them.
+    fn next_match(&mut self) -> DocId {
+        loop {
+            let doc = self
+                .scorers
+                .iter()
+                .map(|scorer| scorer.doc())
+                .min()
+                .unwrap_or(TERMINATED);
+            if doc == TERMINATED {
+                return TERMINATED;
+            }
+            let num_matches = self
+                .scorers
+                .iter()
+                .filter(|scorer| scorer.doc() == doc)
+                .count();
+            if num_matches >= self.minimum_matches_required {
+                return doc;
+            }
+            for scorer in &mut self.scorers {
+                if scorer.doc() == doc {
+                    scorer.advance();
+                }
+            }
+        }
+    }
+}
+
+impl DocSet for ScoreModeUnion {
+    fn advance(&mut self) -> DocId {
+        if self.doc == TERMINATED {
+            return TERMINATED;
+        }
+        for scorer in &mut self.scorers {
+            if scorer.doc() == self.doc {
+                scorer.advance();
+            }
+        }
+        self.doc = self.next_match();
+        self.doc
+    }
+
+    fn seek(&mut self, target: DocId) -> DocId {
+        if self.doc >= target {
+            return self.doc;
+        }
+        for scorer in &mut self.scorers {
+            if scorer.doc() < target {
+                scorer.seek(target);
+            }
+        }
+        self.doc = self.next_match();
+        self.doc
+    }
+
+    fn doc(&self) -> DocId {
+        self.doc
+    }
+
+    fn size_hint(&self) -> u32 {
+        self.scorers
+            .iter()
+            .map(|scorer| scorer.size_hint())
+            .max()
+            .unwrap_or(0)
+    }
+
+    fn cost(&self) -> u64 {
+        self.scorers.iter().map(|scorer| scorer.cost()).sum()
+    }
+}
+
+impl Scorer for ScoreModeUnion {
+    fn score(&mut self) -> Score {
+        let doc = self.doc;
+        let mut matching_scores = self
+            .scorers
+            .iter_mut()
+            .filter(|scorer| scorer.doc() == doc)
+            .map(|scorer| scorer.score());
+        match self.score_mode {
+            ShouldScoreMode::Sum => matching_scores.sum(),
+            ShouldScoreMode::Max => matching_scores.fold(Score::NEG_INFINITY, Score::max),
+            ShouldScoreMode::Avg => {
+                let (sum, count) =
+                    matching_scores.fold((0.0, 0), |(sum, count), score| (sum + score, count + 1));
+                sum / count as Score
+            }
+            ShouldScoreMode::First => matching_scores.next().unwrap_or(0.0),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ScoreModeUnion;
+    use crate::query::{ConstScorer, Scorer, ShouldScoreMode, VecDocSet};
+    use crate::{DocSet, Score, TERMINATED};
+
+    fn score_mode_union(score_mode: ShouldScoreMode, minimum_matches: usize) -> ScoreModeUnion {
+        let scorers: Vec<Box<dyn Scorer>> = vec![
+            Box::new(ConstScorer::new(VecDocSet::from(vec![1, 2, 5]), 1.0)),
+            Box::new(ConstScorer::new(VecDocSet::from(vec![2, 3, 5]), 2.0)),
+            Box::new(ConstScorer::new(VecDocSet::from(vec![2, 7]), 6.0)),
+        ];
+        ScoreModeUnion::new(scorers, score_mode, minimum_matches)
+    }
+
+    fn docs_and_scores(mut union: ScoreModeUnion) -> Vec<(u32, Score)> {
+        let mut docs_and_scores = Vec::new();
+        while union.doc() != TERMINATED {
+            docs_and_scores.push((union.doc(), union.score()));
+            union.advance();
+        }
+        docs_and_scores
+    }
+
+    #[test]
+    fn test_score_mode_union() {
+        assert_eq!(
+            docs_and_scores(score_mode_union(ShouldScoreMode::Sum, 1)),
+            vec![(1, 1.0), (2, 9.0), (3, 2.0), (5, 3.0), (7, 6.0)]
+        );
+        assert_eq!(
+            docs_and_scores(score_mode_union(ShouldScoreMode::Max, 1)),
+            vec![(1, 1.0), (2, 6.0), (3, 2.0), (5, 2.0), (7, 6.0)]
+        );
+        assert_eq!(
+            docs_and_scores(score_mode_union(ShouldScoreMode::Avg, 1)),
+            vec![(1, 1.0), (2, 3.0), (3, 2.0), (5, 1.5), (7, 6.0)]
+        );
+        assert_eq!(
+            docs_and_scores(score_mode_union(ShouldScoreMode::First, 2)),
+            vec![(2, 1.0), (5, 1.0)]
+        );
+    }
+
+    #[test]
+    fn test_score_mode_union_seek() {
+        let mut union = score_mode_union(ShouldScoreMode::Max, 1);
+        assert_eq!(union.seek(3), 3);
+        assert_eq!(union.seek(3), 3);
+        assert_eq!(union.seek(4), 5);
+        assert_eq!(union.score(), 2.0);
+        assert_eq!(union.seek(8), TERMINATED);
+    }
+}
@@ -51,6 +51,18 @@ impl Query for BoostQuery {
     fn query_terms<'a>(&'a self, visitor: &mut dyn FnMut(&'a Term, bool)) {
         self.query.query_terms(visitor)
     }
+
+    fn query_phrases<'a>(&'a self, visitor: &mut dyn FnMut(&'a [(usize, Term)], u32)) {
+        self.query.query_phrases(visitor)
+    }
+
+    fn cache_key(&self) -> Option<String> {
+        Some(format!(
+            "Boost(query={}, boost={})",
+            self.query.cache_key()?,
+            self.boost
+        ))
+    }
 }
 
 /// Weight associated to the BoostQuery.
@@ -117,6 +129,10 @@ impl<S: Scorer> DocSet for BoostScorer<S> {
         self.underlying.size_hint()
     }
 
+    fn cost(&self) -> u64 {
+        self.underlying.cost()
+    }
+
     fn count(&mut self, alive_bitset: &AliveBitSet) -> u32 {
         self.underlying.count(alive_bitset)
     }
@@ -130,6 +146,14 @@ impl<S: Scorer> Scorer for BoostScorer<S> {
     fn score(&mut self) -> Score {
         self.underlying.score() * self.boost
     }
+
+    fn max_score(&self) -> Score {
+        if self.boost >= 0.0 {
+            self.underlying.max_score() * self.boost
+        } else {
+            Score::INFINITY
+        }
+    }
 }
 
 #[cfg(test)]
@@ -0,0 +1,480 @@
+use crate::fieldnorm::FieldNormReader;
+use crate::postings::{Postings, SegmentPostings};
+use crate::query::explanation::does_not_match;
+use crate::query::score_combiner::SumCombiner;
+use crate::query::{
+    Bm25StatisticsProvider, Bm25Weight, EmptyScorer, EnableScoring, Explanation, Query, Scorer,
+    Union, Weight,
+};
+use crate::schema::{Field, FieldType, IndexRecordOption, Schema};
+use crate::{DocId, DocSet, Score, SegmentReader, TantivyError, Term, TERMINATED};
+
+/// `CombinedFieldsQuery` matches the documents containing any of its terms in any of its
+/// fields, and scores them with BM25F.
+///
+/// Instead of summing the BM25 scores of the fields, as a
+/// [`BooleanQuery`](crate::query::BooleanQuery) over the same terms would, BM25F scores the fields
+/// as if they were a single field in which the content of each field is repeated as many times as
+/// its weight:
+/// - the frequency of a term is the weighted sum of its frequencies in the fields,
+/// - the length of a document is the weighted sum of the lengths of its fields, and the average
+///   length is computed the same way over the whole index,
+/// - the document frequency of a term is its highest document frequency among the fields.
+///
+/// A term appearing in the title and the body of a document is hence not scored twice, and a
+/// term frequent in one of the fields does not dominate the score because it is rare in the
+/// others.
+///
+/// The terms are used as is in each field, so that the fields should share the same tokenizer.
+/// The BM25 parameters are the ones of the first field.
+///
+/// ```rust
+/// use tantivy::collector::TopDocs;
+/// use tantivy::query::CombinedFieldsQuery;
+/// use tantivy::schema::{Schema, TEXT};
+/// use tantivy::{doc, Index, IndexWriter};
+///
+/// # fn main() -> tantivy::Result<()> {
+/// let mut schema_builder = Schema::builder();
+/// let title = schema_builder.add_text_field("title", TEXT);
+/// let body = schema_builder.add_text_field("body", TEXT);
+/// let index = Index::create_in_ram(schema_builder.build());
+/// let mut index_writer: IndexWriter = index.writer(15_000_000)?;
+/// index_writer.add_document(doc!(title => "The Name of the Wind", body => "A fantasy novel"))?;
+/// index_writer.add_document(doc!(title => "Dune", body => "Sand, spice and wind"))?;
+/// index_writer.commit()?;
+/// let searcher = index.reader()?.searcher();
+///
+/// let fields = vec![(title, 2.0), (body, 1.0)];
+/// let query = CombinedFieldsQuery::new(fields, vec!["wind".to_string()]);
+/// let top_docs = searcher.search(&query, &TopDocs::with_limit(10))?;
+/// assert_eq!(top_docs.len(), 2);
+/// assert_eq!(top_docs[0].1.doc_id, 0);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Debug)]
+pub struct CombinedFieldsQuery {
+    fields: Vec<(Field, Score)>,
+    texts: Vec<String>,
+    terms: Vec<Term>,
+}
+
+impl CombinedFieldsQuery {
+    /// Creates a new `CombinedFieldsQuery` searching the `texts`, already tokenized, in the
+    /// `fields` given with their weights.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `fields` is empty or if a weight is not strictly positive.
+    pub fn new(fields: Vec<(Field, Score)>, texts: Vec<String>) -> CombinedFieldsQuery {
+        assert!(
+            !fields.is_empty(),
+            "CombinedFieldsQuery requires at least one field"
+        );
+        for &(_, weight) in &fields {
+            assert!(
+                weight.is_finite() && weight > 0.0,
+                "Field weights must be strictly positive, got {weight}"
+            );
+        }
+        let terms = texts
+            .iter()
+            .flat_map(|text| {
+                fields
+                    .iter()
+                    .map(move |&(field, _)| Term::from_field_text(field, text))
+            })
+            .collect();
+        CombinedFieldsQuery {
+            fields,
+            texts,
+            terms,
+        }
+    }
+
+    /// The fields of the query, with their weights.
+    pub fn fields(&self) -> &[(Field, Score)] {
+        &self.fields
+    }
+
+    /// The terms of `text`, one per field.
+    fn text_terms(&self, text_ord: usize) -> &[Term] {
+        let num_fields = self.fields.len();
+        &self.terms[text_ord * num_fields..(text_ord + 1) * num_fields]
+    }
+
+    fn check_fields(&self, schema: &Schema) -> crate::Result<()> {
+        for &(field, _) in &self.fields {
+            let field_entry = schema.get_field_entry(field);
+            let is_indexed_text =
+                matches!(field_entry.field_type(), FieldType::Str(_)) && field_entry.is_indexed();
+            if !is_indexed_text {
+                return Err(TantivyError::SchemaError(format!(
+                    "Field {:?} is not an indexed text field.",
+                    field_entry.name()
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// Computes the BM25 weight of the text, as if the fields were a single field.
+    fn text_bm25_weight(
+        &self,
+        statistics: &dyn Bm25StatisticsProvider,
+        text_ord: usize,
+    ) -> crate::Result<Bm25Weight> {
+        let total_num_docs = statistics.total_num_docs()?;
+        let mut weighted_num_tokens: Score = 0.0;
+        for &(field, weight) in &self.fields {
+            weighted_num_tokens += weight * statistics.total_num_tokens(field)? as Score;
+        }
+        let average_field_length = weighted_num_tokens / total_num_docs as Score;
+        let mut doc_freq = 0;
+        for term in self.text_terms(text_ord) {
+            doc_freq = doc_freq.max(statistics.doc_freq(term)?);
+        }
+        Ok(Bm25Weight::for_one_term_with_options(
+            doc_freq,
+            total_num_docs,
+            average_field_length,
+            statistics.bm25_options(self.fields[0].0),
+        ))
+    }
+}
+
+impl Query for CombinedFieldsQuery {
+    fn weight(&self, enable_scoring: EnableScoring<'_>) -> crate::Result<Box<dyn Weight>> {
+        self.check_fields(enable_scoring.schema())?;
+        let bm25_weights = (0..self.texts.len())
+            .map(|text_ord| match enable_scoring {
+                EnableScoring::Enabled {
+                    statistics_provider,
+                    ..
+                } => self
+                    .text_bm25_weight(statistics_provider, text_ord)
+                    .map(Some),
+                EnableScoring::Disabled { .. } => Ok(None),
+            })
+            .collect::<crate::Result<Vec<_>>>()?;
+        Ok(Box::new(CombinedFieldsWeight {
+            query: self.clone(),
+            bm25_weights,
+        }))
+    }
+
+    fn query_terms<'a>(&'a self, visitor: &mut dyn FnMut(&'a Term, bool)) {
+        for term in &self.terms {
+            visitor(term, false);
+        }
+    }
+
+    fn cache_key(&self) -> Option<String> {
+        Some(format!("{self:?}"))
+    }
+}
+
+struct CombinedFieldsWeight {
+    query: CombinedFieldsQuery,
+    bm25_weights: Vec<Option<Bm25Weight>>,
+}
+
+impl CombinedFieldsWeight {
+    /// Returns the scorers of the texts, in the order of the texts of the query.
+    fn text_scorers(
+        &self,
+        reader: &SegmentReader,
+        boost: Score,
+    ) -> crate::Result<Vec<CombinedFieldsScorer>> {
+        let scoring_enabled = self.bm25_weights.iter().any(Option::is_some);
+        let mut fieldnorm_readers = Vec::with_capacity(self.query.fields.len());
+        if scoring_enabled {
+            for &(field, weight) in &self.query.fields {
+                let fieldnorm_reader = reader
+                    .fieldnorms_readers()
+                    .get_field(field)?
+                    .unwrap_or_else(|| FieldNormReader::constant(reader.max_doc(), 1));
+                fieldnorm_readers.push((weight, fieldnorm_reader));
+            }
+        }
+        let index_record_option = if scoring_enabled {
+            IndexRecordOption::WithFreqs
+        } else {
+            IndexRecordOption::Basic
+        };
+        let mut scorers = Vec::with_capacity(self.bm25_weights.len());
+        for (text_ord, bm25_weight) in self.bm25_weights.iter().enumerate() {
+            let mut postings = Vec::new();
+            for (term, &(field, weight)) in self
+                .query
+                .text_terms(text_ord)
+                .iter()
+                .zip(&self.query.fields)
+            {
+                let inverted_index = reader.inverted_index(field)?;
+                if let Some(segment_postings) =
+                    inverted_index.read_postings(term, index_record_option)?
+                {
+                    postings.push((weight, segment_postings));
+                }
+            }
+            scorers.push(CombinedFieldsScorer::new(
+                postings,
+                fieldnorm_readers.clone(),
+                bm25_weight
+                    .as_ref()
+                    .map(|bm25_weight| bm25_weight.boost_by(boost)),
+            ));
+        }
+        Ok(scorers)
+    }
+}
+
+impl Weight for CombinedFieldsWeight {
+    fn scorer(&self, reader: &SegmentReader, boost: Score) -> crate::Result<Box<dyn Scorer>> {
+        let mut scorers = self.text_scorers(reader, boost)?;
+        match scorers.len() {
+            0 => Ok(Box::new(EmptyScorer)),
+            1 => Ok(Box::new(scorers.pop().unwrap())),
+            _ => Ok(Box::new(Union::build(scorers, SumCombiner::default))),
+        }
+    }
+
+    fn explain(&self, reader: &SegmentReader, doc: DocId) -> crate::Result<Explanation> {
+        let mut text_explanations = Vec::new();
+        for (text, mut scorer) in self.query.texts.iter().zip(self.text_scorers(reader, 1.0)?) {
+            if scorer.doc() <= doc && scorer.seek(doc) == doc {
+                let mut explanation = scorer.explain();
+                explanation.add_context(format!("Text={text:?}"));
+                text_explanations.push(explanation);
+            }
+        }
+        if text_explanations.is_empty() {
+            return Err(does_not_match(doc));
+        }
+        let score = text_explanations.iter().map(Explanation::value).sum();
+        let mut explanation = Explanation::new("CombinedFieldsQuery, sum of ...", score);
+        for text_explanation in text_explanations {
+            explanation.add_detail(text_explanation);
+        }
+        Ok(explanation)
+    }
+}
+
+/// Scores the documents containing a text in any of the fields of a [`CombinedFieldsQuery`].
+struct CombinedFieldsScorer {
+    /// The postings of the text in the fields containing it, with the weight of the field.
+    postings: Vec<(Score, SegmentPostings)>,
+    /// The fieldnorm readers of all of the fields, with their weight. Empty if scoring is
+    /// disabled.
+    fieldnorm_readers: Vec<(Score, FieldNormReader)>,
+    bm25_weight: Option<Bm25Weight>,
+    doc: DocId,
+}
+
+impl CombinedFieldsScorer {
+    fn new(
+        postings: Vec<(Score, SegmentPostings)>,
+        fieldnorm_readers: Vec<(Score, FieldNormReader)>,
+        bm25_weight: Option<Bm25Weight>,
+    ) -> CombinedFieldsScorer {
+        let mut scorer = CombinedFieldsScorer {
+            postings,
+            fieldnorm_readers,
+            bm25_weight,
+            doc: 0,
+        };
+        scorer.doc = scorer.min_doc();
+        scorer
+    }
+
+    fn min_doc(&self) -> DocId {
+        self.postings
+            .iter()
+            .map(|(_, postings)| postings.doc())
+            .min()
+            .unwrap_or(TERMINATED)
+    }
+
+    /// Returns the weighted length and the weighted term frequency of the current document.
+    fn field_length_and_term_freq(&self) -> (Score, Score) {
+        let field_length = self
+            .fieldnorm_readers
+            .iter()
+            .map(|(weight, fieldnorm_reader)| {
+                weight * fieldnorm_reader.fieldnorm(self.doc) as Score
+            })
+            .sum();
+        let term_freq = self
+            .postings
+            .iter()
+            .filter(|(_, postings)| postings.doc() == self.doc)
+            .map(|(weight, postings)| weight * postings.term_freq() as Score)
+            .sum();
+        (field_length, term_freq)
+    }
+
+    fn explain(&self) -> Explanation {
+        match &self.bm25_weight {
+            Some(bm25_weight) => {
+                let (field_length, term_freq) = self.field_length_and_term_freq();
+                bm25_weight.explain_with_field_length(field_length, term_freq)
+            }
+            None => Explanation::new("<no score>", 1.0),
+        }
+    }
+}
+
+impl DocSet for CombinedFieldsScorer {
+    fn advance(&mut self) -> DocId {
+        if self.doc == TERMINATED {
+            return TERMINATED;
+        }
+        for (_, postings) in &mut self.postings {
+            if postings.doc() == self.doc {
+                postings.advance();
+            }
+        }
+        self.doc = self.min_doc();
+        self.doc
+    }
+
+    fn seek(&mut self, target: DocId) -> DocId {
+        if self.doc >= target {
+            return self.doc;
+        }
+        for (_, postings) in &mut self.postings {
+            if postings.doc() < target {
+                postings.seek(target);
+            }
+        }
+        self.doc = self.min_doc();
+        self.doc
+    }
+
+    fn doc(&self) -> DocId {
+        self.doc
+    }
+
+    fn size_hint(&self) -> u32 {
+        self.postings
+            .iter()
+            .map(|(_, postings)| postings.size_hint())
+            .max()
+            .unwrap_or(0)
+    }
+}
+
+impl Scorer for CombinedFieldsScorer {
+    fn score(&mut self) -> Score {
+        match &self.bm25_weight {
+            Some(bm25_weight) => {
+                let (field_length, term_freq) = self.field_length_and_term_freq();
+                bm25_weight.score_with_field_length(field_length, term_freq)
+            }
+            None => 1.0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::CombinedFieldsQuery;
+    use crate::collector::{Count, TopDocs};
+    use crate::query::bm25::idf;
+    use crate::query::{Query, TermQuery};
+    use crate::schema::{IndexRecordOption, Schema, STORED, STRING, TEXT};
+    use crate::{
+        assert_nearly_equals, doc, DocAddress, Index, IndexWriter, Score, TantivyError, Term,
+    };
+
+    #[test]
+    fn test_combined_fields_query() -> crate::Result<()> {
+        let mut schema_builder = Schema::builder();
+        let title = schema_builder.add_text_field("title", TEXT);
+        let body = schema_builder.add_text_field("body", TEXT);
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer: IndexWriter = index.writer_for_tests()?;
+        index_writer.add_document(doc!(title => "apple pie", body => "a pie with apples"))?;
+        index_writer.add_document(doc!(title => "cherry", body => "apple apple cherry"))?;
+        index_writer.add_document(doc!(title => "pear", body => "pear tart"))?;
+        index_writer.commit()?;
+        let searcher = index.reader()?.searcher();
+
+        let query = CombinedFieldsQuery::new(
+            vec![(title, 2.0), (body, 1.0)],
+            vec!["apple".to_string(), "pie".to_string()],
+        );
+        assert_eq!(searcher.search(&query, &Count)?, 2);
+        let top_docs = searcher.search(&query, &TopDocs::with_limit(10))?;
+        assert_eq!(top_docs.len(), 2);
+
+        // The weighted lengths are 2 * 2 + 4, 2 * 1 + 3 and 2 * 1 + 2.
+        let average_length = (8.0 + 5.0 + 4.0) / 3.0;
+        let bm25 = |doc_freq: u64, term_freq: Score, length: Score| {
+            let (k1, b) = (1.2, 0.75);
+            idf(doc_freq, 3) * (k1 + 1.0) * term_freq
+                / (term_freq + k1 * (1.0 - b + b * length / average_length))
+        };
+        // "apple" is in one title and in one body, "pie" in one title and one body.
+        let expected_scores = [bm25(1, 2.0, 8.0) + bm25(1, 3.0, 8.0), bm25(1, 2.0, 5.0)];
+        for (score, doc_address) in &top_docs {
+            assert_nearly_equals!(*score, expected_scores[doc_address.doc_id as usize]);
+            let explanation = query.explain(&searcher, *doc_address)?;
+            assert_nearly_equals!(explanation.value(), *score);
+        }
+        assert_eq!(top_docs[0].1, DocAddress::new(0, 0));
+        assert!(query.explain(&searcher, DocAddress::new(0, 2)).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_combined_fields_query_single_field() -> crate::Result<()> {
+        let mut schema_builder = Schema::builder();
+        let text = schema_builder.add_text_field("text", TEXT);
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer: IndexWriter = index.writer_for_tests()?;
+        index_writer.add_document(doc!(text => "apple"))?;
+        index_writer.add_document(doc!(text => "apple apple pie"))?;
+        index_writer.add_document(doc!(text => "pie"))?;
+        index_writer.commit()?;
+        let searcher = index.reader()?.searcher();
+
+        // A single field with a weight of 1 is scored like a term query.
+        let query = CombinedFieldsQuery::new(vec![(text, 1.0)], vec!["apple".to_string()]);
+        let term_query = TermQuery::new(
+            Term::from_field_text(text, "apple"),
+            IndexRecordOption::WithFreqs,
+        );
+        let top_docs = searcher.search(&query, &TopDocs::with_limit(10))?;
+        let expected_top_docs = searcher.search(&term_query, &TopDocs::with_limit(10))?;
+        assert_eq!(top_docs.len(), expected_top_docs.len());
+        for ((score, doc_address), (expected_score, expected_doc_address)) in
+            top_docs.iter().zip(&expected_top_docs)
+        {
+            assert_eq!(doc_address, expected_doc_address);
+            assert_nearly_equals!(*score, *expected_score);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_combined_fields_query_requires_text_fields() -> crate::Result<()> {
+        let mut schema_builder = Schema::builder();
+        let title = schema_builder.add_text_field("title", TEXT);
+        let stored = schema_builder.add_text_field("stored", STORED);
+        let id = schema_builder.add_text_field("id", STRING);
+        let index = Index::create_in_ram(schema_builder.build());
+        let searcher = index.reader()?.searcher();
+        let query = CombinedFieldsQuery::new(vec![(title, 1.0), (id, 1.0)], vec!["a".to_string()]);
+        assert_eq!(searcher.search(&query, &Count)?, 0);
+        let query =
+            CombinedFieldsQuery::new(vec![(title, 1.0), (stored, 1.0)], vec!["a".to_string()]);
+        assert!(matches!(
+            searcher.search(&query, &Count),
+            Err(TantivyError::SchemaError(_))
+        ));
+        Ok(())
+    }
+}
@@ -49,6 +49,18 @@ impl Query for ConstScoreQuery {
     fn query_terms<'a>(&'a self, visitor: &mut dyn FnMut(&'a Term, bool)) {
         self.query.query_terms(visitor);
     }
+
+    fn query_phrases<'a>(&'a self, visitor: &mut dyn FnMut(&'a [(usize, Term)], u32)) {
+        self.query.query_phrases(visitor);
+    }
+
+    fn cache_key(&self) -> Option<String> {
+        Some(format!(
+            "Const(score={}, query={})",
+            self.score,
+            self.query.cache_key()?
+        ))
+    }
 }
 
 struct ConstWeight {
@@ -130,12 +142,20 @@ impl<TDocSet: DocSet> DocSet for ConstScorer<TDocSet> {
     fn size_hint(&self) -> u32 {
         self.docset.size_hint()
     }
+
+    fn cost(&self) -> u64 {
+        self.docset.cost()
+    }
 }
 
 impl<TDocSet: DocSet + 'static> Scorer for ConstScorer<TDocSet> {
     fn score(&mut self) -> Score {
         self.score
     }
+
+    fn max_score(&self) -> Score {
+        self.score
+    }
 }
 
 #[cfg(test)]
@@ -0,0 +1,320 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::query::{Bm25StatisticsProvider, Query};
+use crate::schema::{Bm25Options, Field};
+use crate::{Searcher, TantivyError, Term};
+
+/// The statistics BM25 needs about the terms of a query, gathered over a whole corpus.
+///
+/// When a corpus is split into shards searched independently, each shard computes BM25 scores
+/// from its own statistics by default, so that a term rare in a small shard gets a higher
+/// score there, and the scores of different shards cannot be compared to merge their results.
+/// Scoring every shard with the statistics of the whole corpus fixes this:
+/// 1. each shard computes its statistics for the query with [`CorpusStatistics::for_query`],
+/// 2. they are sent to a single place, serialized with serde, and [merged](Self::merge),
+/// 3. each shard runs the query with the merged statistics, by passing
+///    [`CorpusStatistics::provider`] to [`Searcher::search_with_statistics_provider`].
+///
+/// ```rust
+/// use tantivy::collector::TopDocs;
+/// use tantivy::query::{CorpusStatistics, QueryParser};
+/// use tantivy::schema::{Schema, TEXT};
+/// use tantivy::{doc, Index, IndexWriter};
+///
+/// # fn main() -> tantivy::Result<()> {
+/// let mut schema_builder = Schema::builder();
+/// let title = schema_builder.add_text_field("title", TEXT);
+/// let schema = schema_builder.build();
+/// let mut shards = Vec::new();
+/// for titles in [&["The Name of the Wind"][..], &["The Wind in the Willows", "Dune"]] {
+///     let index = Index::create_in_ram(schema.clone());
+///     let mut index_writer: IndexWriter = index.writer(15_000_000)?;
+///     for &text in titles {
+///         index_writer.add_document(doc!(title => text))?;
+///     }
+///     index_writer.commit()?;
+///     shards.push(index.reader()?.searcher());
+/// }
+///
+/// let query = QueryParser::for_index(shards[0].index(), vec![title]).parse_query("wind")?;
+/// let mut statistics = CorpusStatistics::default();
+/// for searcher in &shards {
+///     statistics.merge(&CorpusStatistics::for_query(searcher, query.as_ref())?);
+/// }
+/// assert_eq!(statistics.total_num_docs(), 3);
+/// for searcher in &shards {
+///     let provider = statistics.provider(searcher);
+///     let top_docs =
+///         searcher.search_with_statistics_provider(&query, &TopDocs::with_limit(10), &provider)?;
+///     assert_eq!(top_docs.len(), 1);
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(into = "CorpusStatisticsSerde", try_from = "CorpusStatisticsSerde")]
+pub struct CorpusStatistics {
+    total_num_docs: u64,
+    total_num_tokens: BTreeMap<Field, u64>,
+    doc_freqs: BTreeMap<Term, u64>,
+}
+
+impl CorpusStatistics {
+    /// Computes the statistics of the terms of `query` in the documents of `searcher`.
+    pub fn for_query(searcher: &Searcher, query: &dyn Query) -> crate::Result<CorpusStatistics> {
+        let mut terms = Vec::new();
+        query.query_terms(&mut |term, _| terms.push(term.clone()));
+        let mut statistics = CorpusStatistics {
+            total_num_docs: Bm25StatisticsProvider::total_num_docs(searcher)?,
+            ..Default::default()
+        };
+        for term in terms {
+            let field = term.field();
+            if !statistics.total_num_tokens.contains_key(&field) {
+                let total_num_tokens = searcher.total_num_tokens(field)?;
+                statistics.total_num_tokens.insert(field, total_num_tokens);
+            }
+            if !statistics.doc_freqs.contains_key(&term) {
+                let doc_freq = searcher.doc_freq(&term)?;
+                statistics.doc_freqs.insert(term, doc_freq);
+            }
+        }
+        Ok(statistics)
+    }
+
+    /// Adds the statistics of another part of the corpus.
+    pub fn merge(&mut self, other: &CorpusStatistics) {
+        self.total_num_docs += other.total_num_docs;
+        for (&field, &total_num_tokens) in &other.total_num_tokens {
+            *self.total_num_tokens.entry(field).or_default() += total_num_tokens;
+        }
+        for (term, &doc_freq) in &other.doc_freqs {
+            *self.doc_freqs.entry(term.clone()).or_default() += doc_freq;
+        }
+    }
+
+    /// Returns the number of documents of the corpus.
+    pub fn total_num_docs(&self) -> u64 {
+        self.total_num_docs
+    }
+
+    /// Returns the number of documents of the corpus containing `term`, if known.
+    pub fn doc_freq(&self, term: &Term) -> Option<u64> {
+        self.doc_freqs.get(term).copied()
+    }
+
+    /// Returns a statistics provider scoring the documents of `searcher` with these statistics.
+    ///
+    /// The BM25 parameters are still read from the schema of `searcher`. Scoring a term or a
+    /// field missing from the statistics fails with an error.
+    pub fn provider<'a>(&'a self, searcher: &'a Searcher) -> impl Bm25StatisticsProvider + 'a {
+        CorpusStatisticsProvider {
+            statistics: self,
+            searcher,
+        }
+    }
+}
+
+struct CorpusStatisticsProvider<'a> {
+    statistics: &'a CorpusStatistics,
+    searcher: &'a Searcher,
+}
+
+impl<'a> Bm25StatisticsProvider for CorpusStatisticsProvider<'a> {
+    fn total_num_tokens(&self, field: Field) -> crate::Result<u64> {
+        self.statistics
+            .total_num_tokens
+            .get(&field)
+            .copied()
+            .ok_or_else(|| {
+                TantivyError::InvalidArgument(format!(
+                    "No corpus statistics for field `{}`",
+                    self.searcher.schema().get_field_name(field)
+                ))
+            })
+    }
+
+    fn total_num_docs(&self) -> crate::Result<u64> {
+        Ok(self.statistics.total_num_docs)
+    }
+
+    fn doc_freq(&self, term: &Term) -> crate::Result<u64> {
+        self.statistics.doc_freq(term).ok_or_else(|| {
+            TantivyError::InvalidArgument(format!("No corpus statistics for term {term:?}"))
+        })
+    }
+
+    fn bm25_options(&self, field: Field) -> Bm25Options {
+        Bm25StatisticsProvider::bm25_options(self.searcher, field)
+    }
+}
+
+/// Serialized form of [`CorpusStatistics`], terms being serialized as bytes.
+///
+/// The byte representation of the terms is not stable across versions, so that the statistics
+/// are only meant to be exchanged between processes running the same version.
+#[derive(Serialize, Deserialize)]
+struct CorpusStatisticsSerde {
+    total_num_docs: u64,
+    total_num_tokens: Vec<(Field, u64)>,
+    doc_freqs: Vec<(Vec<u8>, u64)>,
+}
+
+impl From<CorpusStatistics> for CorpusStatisticsSerde {
+    fn from(statistics: CorpusStatistics) -> Self {
+        CorpusStatisticsSerde {
+            total_num_docs: statistics.total_num_docs,
+            total_num_tokens: statistics.total_num_tokens.into_iter().collect(),
+            doc_freqs: statistics
+                .doc_freqs
+                .into_iter()
+                .map(|(term, doc_freq)| (term.serialized_term().to_vec(), doc_freq))
+                .collect(),
+        }
+    }
+}
+
+impl TryFrom<CorpusStatisticsSerde> for CorpusStatistics {
+    type Error = String;
+
+    fn try_from(statistics: CorpusStatisticsSerde) -> Result<Self, Self::Error> {
+        let doc_freqs = statistics
+            .doc_freqs
+            .into_iter()
+            .map(|(term_bytes, doc_freq)| {
+                // A term starts with its field and its type.
+                if term_bytes.len() < 5 {
+                    return Err(format!("Invalid serialized term {term_bytes:?}"));
+                }
+                Ok((Term::wrap(term_bytes), doc_freq))
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(CorpusStatistics {
+            total_num_docs: statistics.total_num_docs,
+            total_num_tokens: statistics.total_num_tokens.into_iter().collect(),
+            doc_freqs,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::CorpusStatistics;
+    use crate::collector::TopDocs;
+    use crate::query::{Query, QueryParser};
+    use crate::schema::{Schema, TEXT};
+    use crate::{assert_nearly_equals, doc, Index, IndexWriter, Searcher, TantivyError};
+
+    fn create_searcher(schema: &Schema, texts: &[&str]) -> crate::Result<Searcher> {
+        let text = schema.get_field("text").unwrap();
+        let index = Index::create_in_ram(schema.clone());
+        let mut index_writer: IndexWriter = index.writer_for_tests()?;
+        for &text_val in texts {
+            index_writer.add_document(doc!(text => text_val))?;
+        }
+        index_writer.commit()?;
+        Ok(index.reader()?.searcher())
+    }
+
+    #[test]
+    fn test_corpus_statistics() -> crate::Result<()> {
+        let mut schema_builder = Schema::builder();
+        let text = schema_builder.add_text_field("text", TEXT);
+        let schema = schema_builder.build();
+        let small_shard = create_searcher(&schema, &["apple pear", "pear"])?;
+        let large_shard = create_searcher(
+            &schema,
+            &[
+                "apple",
+                "apple apple pie",
+                "cherry",
+                "pear pie",
+                "cherry pie",
+            ],
+        )?;
+        let all_docs = create_searcher(
+            &schema,
+            &[
+                "apple pear",
+                "pear",
+                "apple",
+                "apple apple pie",
+                "cherry",
+                "pear pie",
+                "cherry pie",
+            ],
+        )?;
+        let query =
+            QueryParser::for_index(small_shard.index(), vec![text]).parse_query("apple pie")?;
+        let top_docs = |searcher: &Searcher, statistics: Option<&CorpusStatistics>| {
+            let collector = TopDocs::with_limit(10);
+            match statistics {
+                Some(statistics) => searcher.search_with_statistics_provider(
+                    &query,
+                    &collector,
+                    &statistics.provider(searcher),
+                ),
+                None => searcher.search(&query, &collector),
+            }
+        };
+
+        let mut statistics = CorpusStatistics::default();
+        for shard in [&small_shard, &large_shard] {
+            statistics.merge(&CorpusStatistics::for_query(shard, &query)?);
+        }
+        assert_eq!(statistics, CorpusStatistics::for_query(&all_docs, &query)?);
+        let expected_scores = top_docs(&all_docs, None)?;
+        let expected_score = |doc_id: u32| {
+            expected_scores
+                .iter()
+                .find(|(_, doc_address)| doc_address.doc_id == doc_id)
+                .unwrap()
+                .0
+        };
+        // "apple pear" is the first document of the small shard and of the whole corpus.
+        let small_shard_score = top_docs(&small_shard, None)?[0].0;
+        let corpus_score = top_docs(&small_shard, Some(&statistics))?[0].0;
+        assert_nearly_equals!(corpus_score, expected_score(0));
+        assert!((small_shard_score - corpus_score).abs() > 0.1);
+        let large_shard_top_docs = top_docs(&large_shard, Some(&statistics))?;
+        assert_eq!(large_shard_top_docs.len(), 4);
+        for (score, doc_address) in large_shard_top_docs {
+            assert_nearly_equals!(score, expected_score(doc_address.doc_id + 2));
+        }
+
+        // Missing statistics are reported.
+        let other_query =
+            QueryParser::for_index(small_shard.index(), vec![text]).parse_query("cherry")?;
+        assert!(matches!(
+            small_shard.search_with_statistics_provider(
+                other_query.as_ref(),
+                &TopDocs::with_limit(10),
+                &statistics.provider(&small_shard)
+            ),
+            Err(TantivyError::InvalidArgument(_))
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_corpus_statistics_serde() -> crate::Result<()> {
+        let mut schema_builder = Schema::builder();
+        let text = schema_builder.add_text_field("text", TEXT);
+        let schema = schema_builder.build();
+        let searcher = create_searcher(&schema, &["apple pear", "pear"])?;
+        let query =
+            QueryParser::for_index(searcher.index(), vec![text]).parse_query("apple pear")?;
+        let statistics = CorpusStatistics::for_query(&searcher, &query)?;
+        assert_eq!(statistics.total_num_docs(), 2);
+        let json = serde_json::to_string(&statistics).unwrap();
+        let deserialized: CorpusStatistics = serde_json::from_str(&json).unwrap();
+        assert_eq!(deserialized, statistics);
+        assert!(serde_json::from_str::<CorpusStatistics>(
+            r#"{"total_num_docs": 1, "total_num_tokens": [], "doc_freqs": [[[0], 1]]}"#
+        )
+        .is_err());
+        Ok(())
+    }
+}
@@ -16,6 +16,7 @@ pub struct Disjunction<TScorer, TScoreCombiner = DoNothingCombiner> {
 
     current_doc: DocId,
     current_score: Score,
+    max_score: Score,
 }
 
 /// A wrapper around a `Scorer` that caches the current `doc_id` and implements the `DocSet` trait.
@@ -70,6 +71,10 @@ impl<T: Scorer> DocSet for ScorerWrapper<T> {
     fn size_hint(&self) -> u32 {
         self.scorer.size_hint()
     }
+
+    fn cost(&self) -> u64 {
+        self.scorer.cost()
+    }
 }
 
 impl<TScorer: Scorer, TScoreCombiner: ScoreCombiner> Disjunction<TScorer, TScoreCombiner> {
@@ -82,6 +87,9 @@ impl<TScorer: Scorer, TScoreCombiner: ScoreCombiner> Disjunction<TScorer, TScore
             minimum_matches_required > 1,
             "union scorer works better if just one matches required"
         );
+        let docsets: Vec<TScorer> = docsets.into_iter().collect();
+        let max_scores: Vec<Score> = docsets.iter().map(Scorer::max_score).collect();
+        let max_score = score_combiner.max_score(&max_scores);
         let chains = docsets
             .into_iter()
             .map(|doc| ScorerWrapper::new(doc))
@@ -92,6 +100,7 @@ impl<TScorer: Scorer, TScoreCombiner: ScoreCombiner> Disjunction<TScorer, TScore
             current_doc: TERMINATED,
             minimum_matches_required,
             current_score: 0.0,
+            max_score,
         };
         if minimum_matches_required > disjunction.chains.len() {
             return disjunction;
@@ -146,6 +155,10 @@ impl<TScorer: Scorer, TScoreCombiner: ScoreCombiner> DocSet
             .max()
             .unwrap_or(0u32)
     }
+
+    fn cost(&self) -> u64 {
+        self.chains.iter().map(|docset| docset.cost()).sum()
+    }
 }
 
 impl<TScorer: Scorer, TScoreCombiner: ScoreCombiner> Scorer
@@ -154,6 +167,10 @@ impl<TScorer: Scorer, TScoreCombiner: ScoreCombiner> Scorer
     fn score(&mut self) -> Score {
         self.current_score
     }
+
+    fn max_score(&self) -> Score {
+        self.max_score
+    }
 }
 
 #[cfg(test)]
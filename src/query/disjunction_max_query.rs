@@ -109,6 +109,24 @@ impl Query for DisjunctionMaxQuery {
             disjunct.query_terms(visitor);
         }
     }
+
+    fn query_phrases<'a>(&'a self, visitor: &mut dyn FnMut(&'a [(usize, Term)], u32)) {
+        for disjunct in &self.disjuncts {
+            disjunct.query_phrases(visitor);
+        }
+    }
+
+    fn cache_key(&self) -> Option<String> {
+        let disjuncts = self
+            .disjuncts
+            .iter()
+            .map(|disjunct| disjunct.cache_key())
+            .collect::<Option<Vec<_>>>()?;
+        Some(format!(
+            "DisjunctionMaxQuery(disjuncts={disjuncts:?}, tie_breaker={})",
+            self.tie_breaker
+        ))
+    }
 }
 
 impl DisjunctionMaxQuery {
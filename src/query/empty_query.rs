@@ -19,6 +19,10 @@ impl Query for EmptyQuery {
     fn count(&self, _searcher: &Searcher) -> crate::Result<usize> {
         Ok(0)
     }
+
+    fn cache_key(&self) -> Option<String> {
+        Some(format!("{self:?}"))
+    }
 }
 
 /// `EmptyWeight` is a dummy `Weight` in which no document matches.
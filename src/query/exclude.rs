@@ -77,6 +77,10 @@ where
     fn size_hint(&self) -> u32 {
         self.underlying_docset.size_hint()
     }
+
+    fn cost(&self) -> u64 {
+        self.underlying_docset.cost()
+    }
 }
 
 impl<TScorer, TDocSetExclude> Scorer for Exclude<TScorer, TDocSetExclude>
@@ -87,6 +91,14 @@ where
     fn score(&mut self) -> Score {
         self.underlying_docset.score()
     }
+
+    fn max_score(&self) -> Score {
+        self.underlying_docset.max_score()
+    }
+
+    fn set_min_competitive_score(&mut self, min_score: Score) {
+        self.underlying_docset.set_min_competitive_score(min_score);
+    }
 }
 
 #[cfg(test)]
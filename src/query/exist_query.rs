@@ -45,6 +45,10 @@ impl Query for ExistsQuery {
             field_name: self.field_name.clone(),
         }))
     }
+
+    fn cache_key(&self) -> Option<String> {
+        Some(format!("{self:?}"))
+    }
 }
 
 /// Weight associated with the `ExistsQuery` query.
@@ -126,6 +130,11 @@ impl DocSet for ExistsDocSet {
         0
     }
 
+    /// The columns are tested for every document.
+    fn cost(&self) -> u64 {
+        u64::from(self.max_doc)
+    }
+
     fn doc(&self) -> DocId {
         self.doc
     }
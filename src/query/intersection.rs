@@ -1,4 +1,5 @@
 use crate::docset::{DocSet, TERMINATED};
+use crate::query::score_combiner::sum_upper_bound;
 use crate::query::term_query::TermScorer;
 use crate::query::{EmptyScorer, Scorer};
 use crate::{DocId, Score};
@@ -8,9 +9,14 @@ use crate::{DocId, Score};
 /// The score associated with the documents is the sum of the
 /// score of the `Scorer`s given in argument.
 ///
+/// The intersection is driven by the scorers with the lowest [`cost`](DocSet::cost), and the
+/// other scorers are reordered as the search goes, so that the ones rejecting the most
+/// candidates are tested first. The order of the scorers given in argument hence does not
+/// matter.
+///
 /// For better performance, the function uses a
 /// specialized implementation if the two
-/// shortest scorers are `TermScorer`s.
+/// cheapest scorers are `TermScorer`s.
 pub fn intersect_scorers(mut scorers: Vec<Box<dyn Scorer>>) -> Box<dyn Scorer> {
     if scorers.is_empty() {
         return Box::new(EmptyScorer);
@@ -18,7 +24,7 @@ pub fn intersect_scorers(mut scorers: Vec<Box<dyn Scorer>>) -> Box<dyn Scorer> {
     if scorers.len() == 1 {
         return scorers.pop().unwrap();
     }
-    scorers.sort_by_key(|scorer| scorer.size_hint());
+    scorers.sort_by_key(|scorer| scorer.cost());
     let doc = go_to_first_doc(&mut scorers[..]);
     if doc == TERMINATED {
         return Box::new(EmptyScorer);
@@ -34,12 +40,14 @@ pub fn intersect_scorers(mut scorers: Vec<Box<dyn Scorer>>) -> Box<dyn Scorer> {
             left: *(left.downcast::<TermScorer>().map_err(|_| ()).unwrap()),
             right: *(right.downcast::<TermScorer>().map_err(|_| ()).unwrap()),
             others: scorers,
+            reorder_others: true,
         });
     }
     Box::new(Intersection {
         left,
         right,
         others: scorers,
+        reorder_others: true,
     })
 }
 
@@ -48,6 +56,9 @@ pub struct Intersection<TDocSet: DocSet, TOtherDocSet: DocSet = Box<dyn Scorer>>
     left: TDocSet,
     right: TDocSet,
     others: Vec<TOtherDocSet>,
+    /// If true, a docset of `others` rejecting a candidate is moved to the front, so that
+    /// the most selective ones are tested first even if their cost was misestimated.
+    reorder_others: bool,
 }
 
 fn go_to_first_doc<TDocSet: DocSet>(docsets: &mut [TDocSet]) -> DocId {
@@ -69,14 +80,17 @@ impl<TDocSet: DocSet> Intersection<TDocSet, TDocSet> {
     pub(crate) fn new(mut docsets: Vec<TDocSet>) -> Intersection<TDocSet, TDocSet> {
         let num_docsets = docsets.len();
         assert!(num_docsets >= 2);
-        docsets.sort_by_key(|docset| docset.size_hint());
+        docsets.sort_by_key(|docset| docset.cost());
         go_to_first_doc(&mut docsets);
         let left = docsets.remove(0);
         let right = docsets.remove(0);
+        // The docsets are accessed by ordinal with `docset_mut_specialized`, so their order
+        // must not change.
         Intersection {
             left,
             right,
             others: docsets,
+            reorder_others: false,
         }
     }
 }
@@ -110,9 +124,12 @@ impl<TDocSet: DocSet, TOtherDocSet: DocSet> DocSet for Intersection<TDocSet, TOt
 
             debug_assert_eq!(left.doc(), right.doc());
             // test the remaining scorers;
-            for docset in self.others.iter_mut() {
-                let seek_doc = docset.seek(candidate);
+            for ord in 0..self.others.len() {
+                let seek_doc = self.others[ord].seek(candidate);
                 if seek_doc > candidate {
+                    if self.reorder_others && ord > 0 {
+                        self.others[..=ord].rotate_right(1);
+                    }
                     candidate = left.seek(seek_doc);
                     continue 'outer;
                 }
@@ -143,6 +160,10 @@ impl<TDocSet: DocSet, TOtherDocSet: DocSet> DocSet for Intersection<TDocSet, TOt
     fn size_hint(&self) -> u32 {
         self.left.size_hint()
     }
+
+    fn cost(&self) -> u64 {
+        self.left.cost()
+    }
 }
 
 impl<TScorer, TOtherScorer> Scorer for Intersection<TScorer, TOtherScorer>
@@ -155,14 +176,22 @@ where
             + self.right.score()
             + self.others.iter_mut().map(Scorer::score).sum::<Score>()
     }
+
+    fn max_score(&self) -> Score {
+        let max_scores: Vec<Score> = [self.left.max_score(), self.right.max_score()]
+            .into_iter()
+            .chain(self.others.iter().map(Scorer::max_score))
+            .collect();
+        sum_upper_bound(&max_scores)
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::Intersection;
+    use super::{intersect_scorers, Intersection};
     use crate::docset::{DocSet, TERMINATED};
     use crate::postings::tests::test_skip_against_unoptimized;
-    use crate::query::VecDocSet;
+    use crate::query::{ConstScorer, Scorer, SumCombiner, Union, VecDocSet};
 
     #[test]
     fn test_intersection() {
@@ -237,6 +266,41 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_intersect_scorers_by_cost() {
+        let scorer = |docs: Vec<u32>| -> Box<dyn Scorer> {
+            Box::new(ConstScorer::new(VecDocSet::from(docs), 1.0))
+        };
+        let evens: Vec<u32> = (0..1_000).step_by(2).collect();
+        let multiples_of_three: Vec<u32> = (0..1_000).step_by(3).collect();
+        // The union is cheap according to its size hint, but costs as much as its two docsets.
+        let union: Box<dyn Scorer> = Box::new(Union::build(
+            vec![scorer(evens.clone()), scorer(multiples_of_three.clone())],
+            SumCombiner::default,
+        ));
+        assert_eq!(union.size_hint(), 500);
+        assert_eq!(union.cost(), 834);
+        let multiples_of_five: Vec<u32> = (0..1_000).step_by(5).collect();
+        let multiples_of_seven: Vec<u32> = (0..1_000).step_by(7).collect();
+        // The intersection is driven by the multiples of seven, and the other docsets are
+        // reordered as they reject candidates.
+        let mut intersection = intersect_scorers(vec![
+            scorer(evens.clone()),
+            scorer(multiples_of_three),
+            union,
+            scorer(multiples_of_five),
+            scorer((0..1_000).collect()),
+            scorer(multiples_of_seven),
+        ]);
+        assert_eq!(intersection.cost(), 143);
+        let mut docs = Vec::new();
+        while intersection.doc() != TERMINATED {
+            docs.push(intersection.doc());
+            intersection.advance();
+        }
+        assert_eq!(docs, (0..1_000).step_by(210).collect::<Vec<u32>>());
+    }
+
     #[test]
     fn test_intersection_empty() {
         let a = VecDocSet::from(vec![1, 3]);
@@ -0,0 +1,673 @@
+use std::fmt;
+
+use common::BitSet;
+
+use super::EmptyScorer;
+use crate::docset::{DocSet, TERMINATED};
+use crate::index::SegmentReader;
+use crate::query::explanation::does_not_match;
+use crate::query::{EnableScoring, Explanation, Query, Scorer, Weight};
+use crate::schema::{Field, Schema};
+use crate::vector::score_from_distance;
+use crate::{DocId, Score, TantivyError};
+
+/// Query that matches the `k` documents whose vector is the closest to a given vector,
+/// in each segment.
+///
+/// The field has to be a dense vector field. The documents are scored by their similarity
+/// to the vector, according to the metric of the field: the closer, the higher.
+///
+/// The search goes through the index of the field in each segment, an HNSW graph or an
+/// IVF-PQ index, so that the neighbors returned are approximate. Since `k` documents are matched
+/// per segment, collect the results with a [`TopDocs`](crate::collector::TopDocs) collector of
+/// limit `k` to get the `k` nearest neighbors of the whole index.
+///
+/// The search can be restricted to the documents matching a filter, see
+/// [`KnnQuery::with_filter`].
+#[derive(Debug)]
+pub struct KnnQuery {
+    field: Field,
+    vector: Vec<f32>,
+    k: usize,
+    ef_search: usize,
+    filter: Option<Box<dyn Query>>,
+}
+
+impl KnnQuery {
+    /// Creates a query matching the `k` documents whose vector of the field `field` is the
+    /// closest to `vector`.
+    pub fn new(field: Field, vector: Vec<f32>, k: usize) -> KnnQuery {
+        KnnQuery {
+            field,
+            vector,
+            k,
+            ef_search: k.max(64),
+            filter: None,
+        }
+    }
+
+    /// Sets the number of candidates considered while searching the graph of a segment.
+    ///
+    /// Higher values improve the recall, at the cost of a slower search. Default is the
+    /// largest of `k` and 64.
+    #[must_use]
+    pub fn with_ef_search(mut self, ef_search: usize) -> KnnQuery {
+        self.ef_search = ef_search;
+        self
+    }
+
+    /// Only matches the documents matched by `filter`.
+    ///
+    /// The filter is applied while searching the index rather than on its results, so
+    /// that `k` documents are matched as long as the filter matches `k` documents with a
+    /// vector. When the filter matches at most `ef_search` documents of a segment, the
+    /// distance to each of them is computed instead of searching the index.
+    #[must_use]
+    pub fn with_filter(mut self, filter: Box<dyn Query>) -> KnnQuery {
+        self.filter = Some(filter);
+        self
+    }
+}
+
+impl Clone for KnnQuery {
+    fn clone(&self) -> Self {
+        KnnQuery {
+            field: self.field,
+            vector: self.vector.clone(),
+            k: self.k,
+            ef_search: self.ef_search,
+            filter: self.filter.as_ref().map(|filter| filter.box_clone()),
+        }
+    }
+}
+
+/// Checks that `field` is a dense vector field of vectors of the dimensions of `vector`.
+fn check_vector_field(schema: &Schema, field: Field, vector: &[f32]) -> crate::Result<()> {
+    let field_entry = schema.get_field_entry(field);
+    let Some(options) = field_entry.dense_vector_options() else {
+        return Err(TantivyError::SchemaError(format!(
+            "Field `{}` is not a dense vector field",
+            field_entry.name()
+        )));
+    };
+    if vector.len() != options.dimensions() as usize {
+        return Err(TantivyError::InvalidArgument(format!(
+            "Field `{}` expects vectors of {} dimensions, got {}",
+            field_entry.name(),
+            options.dimensions(),
+            vector.len()
+        )));
+    }
+    Ok(())
+}
+
+/// Returns the weight of a filter, which only selects documents: its scores are not needed.
+fn filter_weight(
+    filter: Option<&dyn Query>,
+    enable_scoring: &EnableScoring<'_>,
+) -> crate::Result<Option<Box<dyn Weight>>> {
+    let Some(filter) = filter else {
+        return Ok(None);
+    };
+    let filter_scoring = match enable_scoring.searcher() {
+        Some(searcher) => EnableScoring::disabled_from_searcher(searcher),
+        None => EnableScoring::disabled_from_schema(enable_scoring.schema()),
+    };
+    filter.weight(filter_scoring).map(Some)
+}
+
+/// Returns the cache key of an optional filter, or `None` if the filter is not cacheable.
+fn filter_cache_key(filter: Option<&dyn Query>) -> Option<Option<String>> {
+    match filter {
+        Some(filter) => filter.cache_key().map(Some),
+        None => Some(None),
+    }
+}
+
+/// Returns the alive documents of the segment matched by the filter, in increasing order.
+fn filtered_docs(filter_weight: &dyn Weight, reader: &SegmentReader) -> crate::Result<Vec<DocId>> {
+    let mut docs = Vec::new();
+    filter_weight.for_each_no_score(reader, &mut |matched_docs| {
+        docs.extend_from_slice(matched_docs);
+    })?;
+    if let Some(alive_bitset) = reader.alive_bitset() {
+        docs.retain(|&doc| alive_bitset.is_alive(doc));
+    }
+    Ok(docs)
+}
+
+impl Query for KnnQuery {
+    fn weight(&self, enable_scoring: EnableScoring<'_>) -> crate::Result<Box<dyn Weight>> {
+        check_vector_field(enable_scoring.schema(), self.field, &self.vector)?;
+        Ok(Box::new(KnnWeight {
+            field: self.field,
+            vector: self.vector.clone(),
+            k: self.k,
+            ef_search: self.ef_search,
+            filter_weight: filter_weight(self.filter.as_deref(), &enable_scoring)?,
+        }))
+    }
+
+    fn cache_key(&self) -> Option<String> {
+        Some(format!(
+            "Knn(field={:?}, vector={:?}, k={}, ef_search={}, filter={:?})",
+            self.field,
+            self.vector,
+            self.k,
+            self.ef_search,
+            filter_cache_key(self.filter.as_deref())?
+        ))
+    }
+}
+
+/// Weight associated with the [`KnnQuery`].
+struct KnnWeight {
+    field: Field,
+    vector: Vec<f32>,
+    k: usize,
+    ef_search: usize,
+    filter_weight: Option<Box<dyn Weight>>,
+}
+
+impl KnnWeight {
+    /// Returns the nearest neighbors of the segment, sorted by doc id.
+    fn hits(&self, reader: &SegmentReader) -> crate::Result<Vec<(DocId, Score)>> {
+        let Some(field_index) = reader.vector_index().field_index(self.field)? else {
+            return Ok(Vec::new());
+        };
+        let hits = if let Some(filter_weight) = &self.filter_weight {
+            let docs = filtered_docs(filter_weight.as_ref(), reader)?;
+            if docs.len() <= self.ef_search.max(self.k) {
+                // Cheaper than traversing the index, and exact.
+                field_index.exact_search(&self.vector, self.k, docs.into_iter())?
+            } else {
+                let mut filter_bitset = BitSet::with_max_value(reader.max_doc());
+                for doc in docs {
+                    filter_bitset.insert(doc);
+                }
+                field_index.search(&self.vector, self.k, self.ef_search, |doc| {
+                    filter_bitset.contains(doc)
+                })?
+            }
+        } else {
+            let alive_bitset = reader.alive_bitset();
+            field_index.search(&self.vector, self.k, self.ef_search, |doc| {
+                alive_bitset.map_or(true, |alive_bitset| alive_bitset.is_alive(doc))
+            })?
+        };
+        let metric = field_index.metric();
+        let mut hits: Vec<(DocId, Score)> = hits
+            .into_iter()
+            .map(|(doc, distance)| (doc, score_from_distance(metric, distance)))
+            .collect();
+        hits.sort_unstable_by_key(|&(doc, _)| doc);
+        Ok(hits)
+    }
+}
+
+impl Weight for KnnWeight {
+    fn scorer(&self, reader: &SegmentReader, boost: Score) -> crate::Result<Box<dyn Scorer>> {
+        Ok(KnnScorer::new_boxed(self.hits(reader)?, boost))
+    }
+
+    fn explain(&self, reader: &SegmentReader, doc: DocId) -> crate::Result<Explanation> {
+        explain_hits(&self.hits(reader)?, doc, "KnnQuery similarity")
+    }
+}
+
+fn explain_hits(
+    hits: &[(DocId, Score)],
+    doc: DocId,
+    description: &'static str,
+) -> crate::Result<Explanation> {
+    let Ok(ord) = hits.binary_search_by_key(&doc, |&(hit_doc, _)| hit_doc) else {
+        return Err(does_not_match(doc));
+    };
+    Ok(Explanation::new(description, hits[ord].1))
+}
+
+/// Query that matches the `k` documents whose vector is the closest to a given vector,
+/// in each segment, computing the distance to all of the candidate vectors.
+///
+/// Unlike [`KnnQuery`], which searches the index of the field, the result is exact. The
+/// candidates are all of the documents of the segment, or only the ones matching a filter,
+/// see [`ExactKnnQuery::with_filter`]. When the filter matches few documents, this is both
+/// faster and more accurate than an approximate search. It can also rescore the results of
+/// an approximate search, by using a [`KnnQuery`] with a larger `k` as filter.
+///
+/// The distances are computed with SIMD instructions when the CPU supports them.
+pub struct ExactKnnQuery {
+    field: Field,
+    vector: Vec<f32>,
+    k: usize,
+    filter: Option<Box<dyn Query>>,
+}
+
+impl ExactKnnQuery {
+    /// Creates a query matching the `k` documents whose vector of the field `field` is the
+    /// closest to `vector`.
+    pub fn new(field: Field, vector: Vec<f32>, k: usize) -> ExactKnnQuery {
+        ExactKnnQuery {
+            field,
+            vector,
+            k,
+            filter: None,
+        }
+    }
+
+    /// Only considers the documents matched by `filter`.
+    #[must_use]
+    pub fn with_filter(mut self, filter: Box<dyn Query>) -> ExactKnnQuery {
+        self.filter = Some(filter);
+        self
+    }
+}
+
+impl Clone for ExactKnnQuery {
+    fn clone(&self) -> Self {
+        ExactKnnQuery {
+            field: self.field,
+            vector: self.vector.clone(),
+            k: self.k,
+            filter: self.filter.as_ref().map(|filter| filter.box_clone()),
+        }
+    }
+}
+
+impl fmt::Debug for ExactKnnQuery {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "ExactKnn(field={:?}, k={}, filter={:?})",
+            self.field, self.k, self.filter
+        )
+    }
+}
+
+impl Query for ExactKnnQuery {
+    fn weight(&self, enable_scoring: EnableScoring<'_>) -> crate::Result<Box<dyn Weight>> {
+        check_vector_field(enable_scoring.schema(), self.field, &self.vector)?;
+        Ok(Box::new(ExactKnnWeight {
+            field: self.field,
+            vector: self.vector.clone(),
+            k: self.k,
+            filter_weight: filter_weight(self.filter.as_deref(), &enable_scoring)?,
+        }))
+    }
+
+    fn cache_key(&self) -> Option<String> {
+        Some(format!(
+            "ExactKnn(field={:?}, vector={:?}, k={}, filter={:?})",
+            self.field,
+            self.vector,
+            self.k,
+            filter_cache_key(self.filter.as_deref())?
+        ))
+    }
+}
+
+/// Weight associated with the [`ExactKnnQuery`].
+struct ExactKnnWeight {
+    field: Field,
+    vector: Vec<f32>,
+    k: usize,
+    filter_weight: Option<Box<dyn Weight>>,
+}
+
+impl ExactKnnWeight {
+    /// Returns the nearest neighbors of the segment, sorted by doc id.
+    fn hits(&self, reader: &SegmentReader) -> crate::Result<Vec<(DocId, Score)>> {
+        let Some(field_index) = reader.vector_index().field_index(self.field)? else {
+            return Ok(Vec::new());
+        };
+        let hits = if let Some(filter_weight) = &self.filter_weight {
+            let docs = filtered_docs(filter_weight.as_ref(), reader)?;
+            field_index.exact_search(&self.vector, self.k, docs.into_iter())?
+        } else {
+            field_index.exact_search(&self.vector, self.k, reader.doc_ids_alive())?
+        };
+        let metric = field_index.metric();
+        let mut hits: Vec<(DocId, Score)> = hits
+            .into_iter()
+            .map(|(doc, distance)| (doc, score_from_distance(metric, distance)))
+            .collect();
+        hits.sort_unstable_by_key(|&(doc, _)| doc);
+        Ok(hits)
+    }
+}
+
+impl Weight for ExactKnnWeight {
+    fn scorer(&self, reader: &SegmentReader, boost: Score) -> crate::Result<Box<dyn Scorer>> {
+        Ok(KnnScorer::new_boxed(self.hits(reader)?, boost))
+    }
+
+    fn explain(&self, reader: &SegmentReader, doc: DocId) -> crate::Result<Explanation> {
+        explain_hits(&self.hits(reader)?, doc, "ExactKnnQuery similarity")
+    }
+}
+
+/// Query that matches the `k` documents the most similar to a multi-vector query, in each
+/// segment, as in late interaction retrieval models like ColBERT.
+///
+/// Each document can have several vectors for the field, for instance one per token or per
+/// passage. The similarity of a document is the sum, over the query vectors, of the
+/// similarity of its closest vector ("max-sim"). The candidates are the nearest neighbors of
+/// each query vector, found by searching the index of the field, and they are then scored
+/// exactly with all of their vectors.
+#[derive(Clone, Debug)]
+pub struct MaxSimQuery {
+    field: Field,
+    vectors: Vec<Vec<f32>>,
+    k: usize,
+    ef_search: usize,
+}
+
+impl MaxSimQuery {
+    /// Creates a query matching the `k` documents the most similar to the query vectors
+    /// `vectors`, for the field `field`.
+    pub fn new(field: Field, vectors: Vec<Vec<f32>>, k: usize) -> MaxSimQuery {
+        MaxSimQuery {
+            field,
+            vectors,
+            k,
+            ef_search: k.max(64),
+        }
+    }
+
+    /// Sets the number of nearest neighbors retrieved for each of the query vectors, which
+    /// are then scored exactly.
+    ///
+    /// Higher values improve the recall, at the cost of a slower search. Default is the
+    /// largest of `k` and 64.
+    #[must_use]
+    pub fn with_ef_search(mut self, ef_search: usize) -> MaxSimQuery {
+        self.ef_search = ef_search;
+        self
+    }
+}
+
+impl Query for MaxSimQuery {
+    fn weight(&self, enable_scoring: EnableScoring<'_>) -> crate::Result<Box<dyn Weight>> {
+        for vector in &self.vectors {
+            check_vector_field(enable_scoring.schema(), self.field, vector)?;
+        }
+        Ok(Box::new(MaxSimWeight {
+            query: self.clone(),
+        }))
+    }
+
+    fn cache_key(&self) -> Option<String> {
+        Some(format!("{self:?}"))
+    }
+}
+
+/// Weight associated with the [`MaxSimQuery`].
+struct MaxSimWeight {
+    query: MaxSimQuery,
+}
+
+impl MaxSimWeight {
+    /// Returns the most similar documents of the segment, sorted by doc id.
+    fn hits(&self, reader: &SegmentReader) -> crate::Result<Vec<(DocId, Score)>> {
+        let Some(field_index) = reader.vector_index().field_index(self.query.field)? else {
+            return Ok(Vec::new());
+        };
+        let alive_bitset = reader.alive_bitset();
+        let mut hits = field_index.max_sim_search(
+            &self.query.vectors,
+            self.query.k,
+            self.query.ef_search,
+            |doc| alive_bitset.map_or(true, |alive_bitset| alive_bitset.is_alive(doc)),
+        )?;
+        hits.sort_unstable_by_key(|&(doc, _)| doc);
+        Ok(hits)
+    }
+}
+
+impl Weight for MaxSimWeight {
+    fn scorer(&self, reader: &SegmentReader, boost: Score) -> crate::Result<Box<dyn Scorer>> {
+        Ok(KnnScorer::new_boxed(self.hits(reader)?, boost))
+    }
+
+    fn explain(&self, reader: &SegmentReader, doc: DocId) -> crate::Result<Explanation> {
+        explain_hits(&self.hits(reader)?, doc, "MaxSimQuery similarity")
+    }
+}
+
+/// Scorer iterating over precomputed nearest neighbors.
+struct KnnScorer {
+    /// Sorted by doc id.
+    hits: Vec<(DocId, Score)>,
+    cursor: usize,
+    boost: Score,
+}
+
+impl KnnScorer {
+    fn new_boxed(hits: Vec<(DocId, Score)>, boost: Score) -> Box<dyn Scorer> {
+        if hits.is_empty() {
+            return Box::new(EmptyScorer);
+        }
+        Box::new(KnnScorer {
+            hits,
+            cursor: 0,
+            boost,
+        })
+    }
+}
+
+impl DocSet for KnnScorer {
+    fn advance(&mut self) -> DocId {
+        if self.cursor < self.hits.len() {
+            self.cursor += 1;
+        }
+        self.doc()
+    }
+
+    fn seek(&mut self, target: DocId) -> DocId {
+        let remaining = &self.hits[self.cursor..];
+        self.cursor += remaining.partition_point(|&(doc, _)| doc < target);
+        self.doc()
+    }
+
+    fn doc(&self) -> DocId {
+        self.hits
+            .get(self.cursor)
+            .map_or(TERMINATED, |&(doc, _)| doc)
+    }
+
+    fn size_hint(&self) -> u32 {
+        (self.hits.len() - self.cursor) as u32
+    }
+}
+
+impl Scorer for KnnScorer {
+    fn score(&mut self) -> Score {
+        self.hits
+            .get(self.cursor)
+            .map_or(0.0, |&(_, score)| score * self.boost)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ExactKnnQuery, KnnQuery, MaxSimQuery};
+    use crate::collector::TopDocs;
+    use crate::query::{Query, TermQuery};
+    use crate::schema::{DenseVectorOptions, IndexRecordOption, Schema, VectorMetric, TEXT};
+    use crate::vector::encode_vector;
+    use crate::{doc, DocAddress, Index, IndexWriter};
+
+    #[test]
+    fn test_knn_query_weight() -> crate::Result<()> {
+        let mut schema_builder = Schema::builder();
+        let text = schema_builder.add_text_field("text", TEXT);
+        let embedding = schema_builder.add_dense_vector_field(
+            "embedding",
+            DenseVectorOptions::new(2, VectorMetric::Cosine),
+        );
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer: IndexWriter = index.writer_for_tests()?;
+        index_writer.add_document(doc!(embedding => encode_vector(&[1.0, 0.0])))?;
+        index_writer.add_document(doc!(embedding => encode_vector(&[0.0, 2.0])))?;
+        index_writer.commit()?;
+        let searcher = index.reader()?.searcher();
+
+        let query = KnnQuery::new(embedding, vec![0.0, 1.0], 1);
+        let explanation = query.explain(&searcher, DocAddress::new(0, 1))?;
+        assert_eq!(explanation.value(), 1.0);
+        assert!(query.explain(&searcher, DocAddress::new(0, 0)).is_err());
+        assert_eq!(query.count(&searcher)?, 1);
+
+        assert!(KnnQuery::new(embedding, vec![1.0], 1)
+            .count(&searcher)
+            .is_err());
+        assert!(KnnQuery::new(text, vec![1.0, 0.0], 1)
+            .count(&searcher)
+            .is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_exact_knn_query() -> crate::Result<()> {
+        let mut schema_builder = Schema::builder();
+        let text = schema_builder.add_text_field("text", TEXT);
+        let embedding = schema_builder
+            .add_dense_vector_field("embedding", DenseVectorOptions::new(2, VectorMetric::L2));
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer: IndexWriter = index.writer_for_tests()?;
+        index_writer.add_document(doc!(text => "a", embedding => encode_vector(&[0.0, 0.0])))?;
+        index_writer.add_document(doc!(text => "b", embedding => encode_vector(&[1.0, 0.0])))?;
+        // Several vectors: the closest one counts.
+        index_writer.add_document(doc!(
+            text => "a",
+            embedding => encode_vector(&[5.0, 5.0]),
+            embedding => encode_vector(&[2.0, 0.0]),
+        ))?;
+        index_writer.add_document(doc!(text => "a"))?;
+        index_writer.commit()?;
+        let searcher = index.reader()?.searcher();
+        let top_docs = |query: &ExactKnnQuery| -> crate::Result<Vec<(f32, u32)>> {
+            Ok(searcher
+                .search(query, &TopDocs::with_limit(10))?
+                .into_iter()
+                .map(|(score, doc_address)| (score, doc_address.doc_id))
+                .collect())
+        };
+
+        let query = ExactKnnQuery::new(embedding, vec![1.75, 0.0], 2);
+        assert_eq!(
+            top_docs(&query)?,
+            vec![(1.0 / 1.0625, 2), (1.0 / 1.5625, 1)]
+        );
+
+        let filter = TermQuery::new(
+            crate::Term::from_field_text(text, "a"),
+            IndexRecordOption::Basic,
+        );
+        let query = query.with_filter(Box::new(filter));
+        assert_eq!(
+            top_docs(&query)?,
+            vec![(1.0 / 1.0625, 2), (1.0 / 4.0625, 0)]
+        );
+        assert_eq!(
+            query.explain(&searcher, DocAddress::new(0, 0))?.value(),
+            1.0 / 4.0625
+        );
+        assert!(query.explain(&searcher, DocAddress::new(0, 1)).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_knn_query_filter() -> crate::Result<()> {
+        let mut schema_builder = Schema::builder();
+        let text = schema_builder.add_text_field("text", TEXT);
+        let embedding = schema_builder
+            .add_dense_vector_field("embedding", DenseVectorOptions::new(2, VectorMetric::L2));
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer: IndexWriter = index.writer_for_tests()?;
+        for i in 0..200u32 {
+            let parity = if i % 2 == 0 { "even" } else { "odd" };
+            let tags = if i % 10 == 0 {
+                format!("{parity} ten")
+            } else {
+                parity.to_string()
+            };
+            index_writer.add_document(doc!(
+                text => tags,
+                embedding => encode_vector(&[i as f32, 0.0]),
+            ))?;
+        }
+        index_writer.commit()?;
+        let searcher = index.reader()?.searcher();
+        let top_docs = |query: &KnnQuery| -> crate::Result<Vec<u32>> {
+            Ok(searcher
+                .search(query, &TopDocs::with_limit(10))?
+                .into_iter()
+                .map(|(_, doc_address)| doc_address.doc_id)
+                .collect())
+        };
+        let filter = |tag: &str| {
+            Box::new(TermQuery::new(
+                crate::Term::from_field_text(text, tag),
+                IndexRecordOption::Basic,
+            ))
+        };
+
+        // The filter matches more than `ef_search` documents: the graph is traversed.
+        let query = KnnQuery::new(embedding, vec![0.0, 0.0], 3)
+            .with_ef_search(16)
+            .with_filter(filter("odd"));
+        assert_eq!(top_docs(&query)?, vec![1, 3, 5]);
+
+        // The filter matches few documents: their distance is computed instead.
+        let query = KnnQuery::new(embedding, vec![93.0, 0.0], 2).with_filter(filter("ten"));
+        assert_eq!(top_docs(&query)?, vec![90, 100]);
+        assert_eq!(query.count(&searcher)?, 2);
+        Ok(())
+    }
+
+    #[test]
+    fn test_max_sim_query() -> crate::Result<()> {
+        let mut schema_builder = Schema::builder();
+        let embedding = schema_builder
+            .add_dense_vector_field("embedding", DenseVectorOptions::new(2, VectorMetric::L2));
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer: IndexWriter = index.writer_for_tests()?;
+        index_writer.add_document(doc!(
+            embedding => encode_vector(&[0.0, 0.0]),
+            embedding => encode_vector(&[10.0, 0.0]),
+        ))?;
+        index_writer.add_document(doc!(
+            embedding => encode_vector(&[1.0, 0.0]),
+            embedding => encode_vector(&[9.0, 0.0]),
+        ))?;
+        index_writer.add_document(doc!(embedding => encode_vector(&[5.0, 0.0])))?;
+        index_writer.commit()?;
+        let searcher = index.reader()?.searcher();
+        let top_docs = |query: &MaxSimQuery| -> crate::Result<Vec<(f32, u32)>> {
+            Ok(searcher
+                .search(query, &TopDocs::with_limit(10))?
+                .into_iter()
+                .map(|(score, doc_address)| (score, doc_address.doc_id))
+                .collect())
+        };
+
+        // Each query vector is matched with the closest vector of the document.
+        let query = MaxSimQuery::new(embedding, vec![vec![0.0, 0.0], vec![10.0, 0.0]], 2);
+        assert_eq!(top_docs(&query)?, vec![(2.0, 0), (1.0, 1)]);
+        assert_eq!(
+            query.explain(&searcher, DocAddress::new(0, 1))?.value(),
+            1.0
+        );
+
+        let query = MaxSimQuery::new(embedding, vec![vec![0.0, 0.0]], 3);
+        assert_eq!(top_docs(&query)?, vec![(1.0, 0), (0.5, 1), (1.0 / 26.0, 2)]);
+
+        assert!(
+            MaxSimQuery::new(embedding, vec![vec![0.0, 0.0], vec![0.0]], 1)
+                .count(&searcher)
+                .is_err()
+        );
+        Ok(())
+    }
+}
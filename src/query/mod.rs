@@ -4,7 +4,9 @@ mod bitset;
 mod bm25;
 mod boolean_query;
 mod boost_query;
+mod combined_fields_query;
 mod const_score_query;
+mod corpus_statistics;
 mod disjunction;
 mod disjunction_max_query;
 mod empty_query;
@@ -13,6 +15,7 @@ mod exist_query;
 mod explanation;
 mod fuzzy_query;
 mod intersection;
+mod knn_query;
 mod more_like_this;
 mod phrase_prefix_query;
 mod phrase_query;
@@ -21,8 +24,10 @@ mod query_parser;
 mod range_query;
 mod regex_query;
 mod reqopt_scorer;
+mod score_expression;
 mod scorer;
 mod set_query;
+mod similarity_query;
 mod term_query;
 mod union;
 mod weight;
@@ -37,9 +42,11 @@ pub use self::all_query::{AllQuery, AllScorer, AllWeight};
 pub use self::automaton_weight::AutomatonWeight;
 pub use self::bitset::BitSetDocSet;
 pub use self::bm25::{Bm25StatisticsProvider, Bm25Weight};
-pub use self::boolean_query::{BooleanQuery, BooleanWeight};
+pub use self::boolean_query::{BooleanQuery, BooleanWeight, ShouldScoreMode};
 pub use self::boost_query::{BoostQuery, BoostWeight};
+pub use self::combined_fields_query::CombinedFieldsQuery;
 pub use self::const_score_query::{ConstScoreQuery, ConstScorer};
+pub use self::corpus_statistics::CorpusStatistics;
 pub use self::disjunction_max_query::DisjunctionMaxQuery;
 pub use self::empty_query::{EmptyQuery, EmptyScorer, EmptyWeight};
 pub use self::exclude::Exclude;
@@ -49,19 +56,23 @@ pub use self::explanation::Explanation;
 pub(crate) use self::fuzzy_query::DfaWrapper;
 pub use self::fuzzy_query::FuzzyTermQuery;
 pub use self::intersection::{intersect_scorers, Intersection};
+pub use self::knn_query::{ExactKnnQuery, KnnQuery, MaxSimQuery};
 pub use self::more_like_this::{MoreLikeThisQuery, MoreLikeThisQueryBuilder};
 pub use self::phrase_prefix_query::PhrasePrefixQuery;
 pub use self::phrase_query::PhraseQuery;
 pub use self::query::{EnableScoring, Query, QueryClone};
-pub use self::query_parser::{QueryParser, QueryParserError};
+pub use self::query_parser::{QueryDslParser, QueryParser, QueryParserError};
 pub use self::range_query::{FastFieldRangeWeight, IPFastFieldRangeWeight, RangeQuery};
 pub use self::regex_query::RegexQuery;
 pub use self::reqopt_scorer::RequiredOptionalScorer;
 pub use self::score_combiner::{
     DisjunctionMaxCombiner, ScoreCombiner, SumCombiner, SumWithCoordsCombiner,
 };
+pub(crate) use self::score_expression::SegmentScoreExpression;
+pub use self::score_expression::{ExpressionScoreQuery, ScoreExpression};
 pub use self::scorer::Scorer;
 pub use self::set_query::TermSetQuery;
+pub use self::similarity_query::{Similarity, SimilarityQuery};
 pub use self::term_query::TermQuery;
 pub use self::union::Union;
 #[cfg(test)]
@@ -111,9 +111,10 @@ impl PhrasePrefixQuery {
         }
         let terms = self.phrase_terms();
         let bm25_weight_opt = match enable_scoring {
-            EnableScoring::Enabled { searcher, .. } => {
-                Some(Bm25Weight::for_terms(searcher, &terms)?)
-            }
+            EnableScoring::Enabled {
+                statistics_provider,
+                ..
+            } => Some(Bm25Weight::for_terms(statistics_provider, &terms)?),
             EnableScoring::Disabled { .. } => None,
         };
         let weight = PhrasePrefixWeight::new(
@@ -164,4 +165,13 @@ impl Query for PhrasePrefixQuery {
             visitor(term, true);
         }
     }
+
+    /// Only the terms preceding the prefix are reported.
+    fn query_phrases<'a>(&'a self, visitor: &mut dyn FnMut(&'a [(usize, Term)], u32)) {
+        visitor(&self.phrase_terms, 0);
+    }
+
+    fn cache_key(&self) -> Option<String> {
+        Some(format!("{self:?}"))
+    }
 }
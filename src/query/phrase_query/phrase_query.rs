@@ -146,4 +146,12 @@ impl Query for PhraseQuery {
             visitor(term, true);
         }
     }
+
+    fn query_phrases<'a>(&'a self, visitor: &mut dyn FnMut(&'a [(usize, Term)], u32)) {
+        visitor(&self.phrase_terms, self.slop);
+    }
+
+    fn cache_key(&self) -> Option<String> {
+        Some(format!("{self:?}"))
+    }
 }
@@ -535,6 +535,11 @@ impl<TPostings: Postings> DocSet for PhraseScorer<TPostings> {
     fn size_hint(&self) -> u32 {
         self.intersection_docset.size_hint()
     }
+
+    /// The positions of all of the terms are read for each document of the intersection.
+    fn cost(&self) -> u64 {
+        self.intersection_docset.cost() * (1 + self.num_terms as u64)
+    }
 }
 
 impl<TPostings: Postings> Scorer for PhraseScorer<TPostings> {
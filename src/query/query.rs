@@ -160,6 +160,23 @@ pub trait Query: QueryClone + Send + Sync + downcast_rs::Downcast + fmt::Debug {
     /// Note that there can be multiple instances of any given term
     /// in a query and deduplication must be handled by the visitor.
     fn query_terms<'a>(&'a self, _visitor: &mut dyn FnMut(&'a Term, bool)) {}
+
+    /// Extract all of the phrases of the query and pass them to the given closure,
+    /// along with their slop.
+    ///
+    /// Each phrase is given as its terms, with their offset in the phrase. The terms
+    /// of the phrases are also passed to [`Query::query_terms`].
+    fn query_phrases<'a>(&'a self, _visitor: &mut dyn FnMut(&'a [(usize, Term)], u32)) {}
+
+    /// Returns a key identifying the query in the cache of search results, or `None` if the
+    /// results of the query must not be cached, which is the default.
+    ///
+    /// Two queries with the same key must match the same documents with the same scores. The
+    /// key must hence reflect all of the parameters of the query: its debug representation,
+    /// which may omit some of them, is not enough in general.
+    fn cache_key(&self) -> Option<String> {
+        None
+    }
 }
 
 /// Implements `box_clone`.
@@ -188,6 +205,14 @@ impl Query for Box<dyn Query> {
     fn query_terms<'a>(&'a self, visitor: &mut dyn FnMut(&'a Term, bool)) {
         self.as_ref().query_terms(visitor);
     }
+
+    fn query_phrases<'a>(&'a self, visitor: &mut dyn FnMut(&'a [(usize, Term)], u32)) {
+        self.as_ref().query_phrases(visitor);
+    }
+
+    fn cache_key(&self) -> Option<String> {
+        self.as_ref().cache_key()
+    }
 }
 
 impl QueryClone for Box<dyn Query> {
@@ -1,4 +1,6 @@
+mod query_dsl;
 mod query_parser;
 
 pub mod logical_ast;
+pub use self::query_dsl::QueryDslParser;
 pub use self::query_parser::{QueryParser, QueryParserError};
@@ -0,0 +1,624 @@
+use query_grammar::{Delimiter, UserInputAst, UserInputBound, UserInputLeaf, UserInputLiteral};
+use serde_json::{Map, Value};
+
+use super::{QueryParser, QueryParserError};
+use crate::index::Index;
+use crate::query::{
+    AllQuery, BooleanQuery, BoostQuery, ConstScoreQuery, EmptyQuery, ExistsQuery, Occur, Query,
+    RegexQuery, TermQuery, TermSetQuery,
+};
+use crate::schema::{Field, FieldType, IndexRecordOption, Schema, Term};
+use crate::tokenizer::{TextAnalyzer, TokenizerManager};
+use crate::Score;
+
+/// Parses queries written in a subset of the Elasticsearch query DSL.
+///
+/// The supported queries are `bool` (with `must`, `filter`, `should`, `must_not` and an integer
+/// `minimum_should_match`), `term`, `terms`, `match` (with an `or` or `and` operator),
+/// `match_phrase` (with a `slop`), `range`, `regexp`, `exists`, `match_all` and `match_none`.
+/// All of them accept a `boost`. Other queries and parameters are rejected with
+/// [`QueryParserError::UnsupportedQuery`].
+///
+/// As in Elasticsearch, the values of `term`, `terms` and `regexp` queries on text fields are
+/// not analyzed, whereas `match` and `match_phrase` queries are analyzed with the tokenizer of
+/// the field. On the other fields, the values are parsed as in the [`QueryParser`]: dates must
+/// be RFC 3339 strings, and JSON fields are addressed with their full path, e.g.
+/// `attributes.color`. `exists` queries require a fast field.
+///
+/// ```rust
+/// use tantivy::query::QueryDslParser;
+/// use tantivy::schema::{Schema, FAST, INDEXED, TEXT};
+/// use tantivy::Index;
+///
+/// let mut schema_builder = Schema::builder();
+/// schema_builder.add_text_field("title", TEXT);
+/// schema_builder.add_u64_field("year", INDEXED | FAST);
+/// let index = Index::create_in_ram(schema_builder.build());
+///
+/// let query_dsl_parser = QueryDslParser::for_index(&index);
+/// let query = query_dsl_parser.parse_query(
+///     r#"{
+///         "bool": {
+///             "must": { "match": { "title": "old man" } },
+///             "filter": [{ "range": { "year": { "gte": 1950 } } }]
+///         }
+///     }"#,
+/// );
+/// assert!(query.is_ok());
+/// let unsupported = query_dsl_parser.parse_query(r#"{"fuzzy": {"title": "mna"}}"#);
+/// assert!(unsupported.is_err());
+/// ```
+#[derive(Clone)]
+pub struct QueryDslParser {
+    schema: Schema,
+    tokenizer_manager: TokenizerManager,
+    query_parser: QueryParser,
+}
+
+impl QueryDslParser {
+    /// Creates a `QueryDslParser` for the `schema`, analyzing the text with the tokenizers of
+    /// `tokenizer_manager`.
+    pub fn new(schema: Schema, tokenizer_manager: TokenizerManager) -> QueryDslParser {
+        let query_parser = QueryParser::new(schema.clone(), Vec::new(), tokenizer_manager.clone());
+        QueryDslParser {
+            schema,
+            tokenizer_manager,
+            query_parser,
+        }
+    }
+
+    /// Creates a `QueryDslParser` for the schema and the tokenizers of `index`.
+    pub fn for_index(index: &Index) -> QueryDslParser {
+        QueryDslParser::new(index.schema(), index.tokenizers().clone())
+    }
+
+    /// Parses a query given as JSON text.
+    ///
+    /// This is the content of the `query` key of a search request, e.g.
+    /// `{"term": {"tag": "novel"}}`.
+    pub fn parse_query(&self, query_json: &str) -> Result<Box<dyn Query>, QueryParserError> {
+        let query: Value = serde_json::from_str(query_json)
+            .map_err(|err| QueryParserError::SyntaxError(format!("invalid JSON: {err}")))?;
+        self.parse_query_value(&query)
+    }
+
+    /// Parses a query given as a JSON value.
+    pub fn parse_query_value(&self, query: &Value) -> Result<Box<dyn Query>, QueryParserError> {
+        let (query_type, params) = single_entry(query, "query")?;
+        match query_type {
+            "bool" => self.parse_bool(params),
+            "term" => self.parse_term(params),
+            "terms" => self.parse_terms(params),
+            "match" => self.parse_match(params),
+            "match_phrase" => self.parse_match_phrase(params),
+            "range" => self.parse_range(params),
+            "regexp" => self.parse_regexp(params),
+            "exists" => self.parse_exists(params),
+            "match_all" => {
+                let params = object(params, "match_all")?;
+                check_params("match_all", params, &["boost"])?;
+                with_boost(Box::new(AllQuery), params)
+            }
+            "match_none" => {
+                check_params("match_none", object(params, "match_none")?, &["boost"])?;
+                Ok(Box::new(EmptyQuery))
+            }
+            _ => Err(QueryParserError::UnsupportedQuery(format!(
+                "unsupported query type '{query_type}'"
+            ))),
+        }
+    }
+
+    fn parse_bool(&self, params: &Value) -> Result<Box<dyn Query>, QueryParserError> {
+        let params = object(params, "bool")?;
+        check_params(
+            "bool",
+            params,
+            &[
+                "must",
+                "filter",
+                "should",
+                "must_not",
+                "minimum_should_match",
+                "boost",
+            ],
+        )?;
+        let mut clauses: Vec<(Occur, Box<dyn Query>)> = Vec::new();
+        for (occur_name, occur) in [
+            ("must", Occur::Must),
+            ("filter", Occur::Must),
+            ("should", Occur::Should),
+            ("must_not", Occur::MustNot),
+        ] {
+            let Some(sub_queries) = params.get(occur_name) else {
+                continue;
+            };
+            let sub_queries = match sub_queries {
+                Value::Array(sub_queries) => &sub_queries[..],
+                sub_query => std::slice::from_ref(sub_query),
+            };
+            for sub_query in sub_queries {
+                let mut sub_query = self.parse_query_value(sub_query)?;
+                if occur_name == "filter" {
+                    // Filters do not contribute to the score.
+                    sub_query = Box::new(ConstScoreQuery::new(sub_query, 0.0));
+                }
+                clauses.push((occur, sub_query));
+            }
+        }
+        // As in Elasticsearch, a query with no positive clause matches all the documents but
+        // the excluded ones.
+        if clauses.iter().all(|(occur, _)| *occur == Occur::MustNot) {
+            clauses.push((Occur::Must, Box::new(AllQuery)));
+        }
+        let minimum_should_match = match params.get("minimum_should_match") {
+            None => None,
+            Some(Value::Number(number)) => Some(number.as_u64().ok_or_else(|| {
+                QueryParserError::UnsupportedQuery(format!(
+                    "unsupported minimum_should_match {number}, only positive integers are \
+                     supported"
+                ))
+            })? as usize),
+            Some(value) => {
+                return Err(QueryParserError::UnsupportedQuery(format!(
+                    "unsupported minimum_should_match {value}, only positive integers are \
+                     supported"
+                )))
+            }
+        };
+        let bool_query = match minimum_should_match {
+            Some(minimum_should_match) => {
+                BooleanQuery::with_minimum_required_clauses(clauses, minimum_should_match)
+            }
+            None => BooleanQuery::new(clauses),
+        };
+        with_boost(Box::new(bool_query), params)
+    }
+
+    fn parse_term(&self, params: &Value) -> Result<Box<dyn Query>, QueryParserError> {
+        let (field_name, value) = single_entry(params, "term query")?;
+        let (value, params) = value_and_params(value, "term", "value", &["boost"])?;
+        let query = self.term_query(field_name, value)?;
+        with_boost(query, &params)
+    }
+
+    fn parse_terms(&self, params: &Value) -> Result<Box<dyn Query>, QueryParserError> {
+        let mut params = object(params, "terms")?.clone();
+        let boost_params: Map<String, Value> = params
+            .remove("boost")
+            .map(|boost| ("boost".to_string(), boost))
+            .into_iter()
+            .collect();
+        let (field_name, values) = single_entry_of_map(&params, "terms query")?;
+        let Value::Array(values) = values else {
+            return Err(QueryParserError::SyntaxError(format!(
+                "the values of the terms query on '{field_name}' must be an array"
+            )));
+        };
+        let values = values
+            .iter()
+            .map(value_to_string)
+            .collect::<Result<Vec<String>, QueryParserError>>()?;
+        let query: Box<dyn Query> = if let Some(field) = self.text_field(field_name)? {
+            let terms = values
+                .iter()
+                .map(|value| Term::from_field_text(field, value))
+                .collect::<Vec<Term>>();
+            Box::new(TermSetQuery::new(terms))
+        } else {
+            self.build_leaf(UserInputLeaf::Set {
+                field: Some(field_name.to_string()),
+                elements: values,
+            })?
+        };
+        with_boost(query, &boost_params)
+    }
+
+    fn parse_match(&self, params: &Value) -> Result<Box<dyn Query>, QueryParserError> {
+        let (field_name, value) = single_entry(params, "match query")?;
+        let (value, params) = value_and_params(value, "match", "query", &["operator", "boost"])?;
+        let occur = match params.get("operator").map(|operator| operator.as_str()) {
+            None | Some(Some("or")) | Some(Some("OR")) => Occur::Should,
+            Some(Some("and")) | Some(Some("AND")) => Occur::Must,
+            Some(_) => {
+                return Err(QueryParserError::SyntaxError(format!(
+                    "the operator of the match query on '{field_name}' must be 'or' or 'and'"
+                )))
+            }
+        };
+        let Some(field) = self.text_field(field_name)? else {
+            // The other fields are not tokenized: matching their values amounts to a term
+            // query.
+            let query = self.term_query(field_name, value)?;
+            return with_boost(query, &params);
+        };
+        let text = value_to_string(value)?;
+        let mut text_analyzer = self.text_analyzer(field)?;
+        let mut token_stream = text_analyzer.token_stream(&text);
+        let mut terms: Vec<Term> = Vec::new();
+        token_stream.process(&mut |token| {
+            terms.push(Term::from_field_text(field, &token.text));
+        });
+        let mut term_queries: Vec<(Occur, Box<dyn Query>)> = terms
+            .into_iter()
+            .map(|term| {
+                let term_query: Box<dyn Query> =
+                    Box::new(TermQuery::new(term, IndexRecordOption::WithFreqs));
+                (occur, term_query)
+            })
+            .collect();
+        let query: Box<dyn Query> = match term_queries.len() {
+            0 => Box::new(EmptyQuery),
+            1 => term_queries.pop().unwrap().1,
+            _ => Box::new(BooleanQuery::new(term_queries)),
+        };
+        with_boost(query, &params)
+    }
+
+    fn parse_match_phrase(&self, params: &Value) -> Result<Box<dyn Query>, QueryParserError> {
+        let (field_name, value) = single_entry(params, "match_phrase query")?;
+        let (value, params) = value_and_params(value, "match_phrase", "query", &["slop", "boost"])?;
+        let slop = match params.get("slop") {
+            None => 0,
+            Some(slop) => slop
+                .as_u64()
+                .and_then(|slop| u32::try_from(slop).ok())
+                .ok_or_else(|| QueryParserError::SyntaxError(format!("invalid slop {slop}")))?,
+        };
+        let query = self.build_leaf(UserInputLeaf::Literal(UserInputLiteral {
+            field_name: Some(field_name.to_string()),
+            phrase: value_to_string(value)?,
+            delimiter: Delimiter::DoubleQuotes,
+            slop,
+            prefix: false,
+        }))?;
+        with_boost(query, &params)
+    }
+
+    fn parse_range(&self, params: &Value) -> Result<Box<dyn Query>, QueryParserError> {
+        let (field_name, params) = single_entry(params, "range query")?;
+        let params = object(params, "range")?;
+        check_params("range", params, &["gt", "gte", "lt", "lte", "boost"])?;
+        let bound = |exclusive_key: &str,
+                     inclusive_key: &str|
+         -> Result<UserInputBound, QueryParserError> {
+            match (params.get(exclusive_key), params.get(inclusive_key)) {
+                (None, None) => Ok(UserInputBound::Unbounded),
+                (Some(value), None) => Ok(UserInputBound::Exclusive(value_to_string(value)?)),
+                (None, Some(value)) => Ok(UserInputBound::Inclusive(value_to_string(value)?)),
+                (Some(_), Some(_)) => Err(QueryParserError::SyntaxError(format!(
+                    "the range query on '{field_name}' cannot have both '{exclusive_key}' and \
+                     '{inclusive_key}'"
+                ))),
+            }
+        };
+        let query = self.build_leaf(UserInputLeaf::Range {
+            field: Some(field_name.to_string()),
+            lower: bound("gt", "gte")?,
+            upper: bound("lt", "lte")?,
+        })?;
+        with_boost(query, params)
+    }
+
+    fn parse_regexp(&self, params: &Value) -> Result<Box<dyn Query>, QueryParserError> {
+        let (field_name, value) = single_entry(params, "regexp query")?;
+        let (value, params) = value_and_params(value, "regexp", "value", &["boost"])?;
+        let Some(field) = self.text_field(field_name)? else {
+            return Err(QueryParserError::UnsupportedQuery(format!(
+                "regexp queries are only supported on text fields, '{field_name}' is not one"
+            )));
+        };
+        let regex_query = RegexQuery::from_pattern(&value_to_string(value)?, field)
+            .map_err(|err| QueryParserError::SyntaxError(err.to_string()))?;
+        with_boost(Box::new(regex_query), &params)
+    }
+
+    fn parse_exists(&self, params: &Value) -> Result<Box<dyn Query>, QueryParserError> {
+        let params = object(params, "exists")?;
+        check_params("exists", params, &["field", "boost"])?;
+        let Some(Value::String(field_name)) = params.get("field") else {
+            return Err(QueryParserError::SyntaxError(
+                "the exists query requires a 'field' string".to_string(),
+            ));
+        };
+        let (field, _) = self
+            .schema
+            .find_field(field_name)
+            .ok_or_else(|| QueryParserError::FieldDoesNotExist(field_name.clone()))?;
+        if !self.schema.get_field_entry(field).is_fast() {
+            return Err(QueryParserError::UnsupportedQuery(format!(
+                "exists queries are only supported on fast fields, '{field_name}' is not one"
+            )));
+        }
+        let exists_query = ExistsQuery::new_exists_query(field_name.clone());
+        with_boost(Box::new(exists_query), params)
+    }
+
+    /// Returns a query matching the exact `value` in the field `field_name`.
+    fn term_query(
+        &self,
+        field_name: &str,
+        value: &Value,
+    ) -> Result<Box<dyn Query>, QueryParserError> {
+        let text = value_to_string(value)?;
+        if let Some(field) = self.text_field(field_name)? {
+            let term = Term::from_field_text(field, &text);
+            return Ok(Box::new(TermQuery::new(term, IndexRecordOption::WithFreqs)));
+        }
+        self.build_leaf(UserInputLeaf::Literal(UserInputLiteral {
+            field_name: Some(field_name.to_string()),
+            phrase: text,
+            delimiter: Delimiter::None,
+            slop: 0,
+            prefix: false,
+        }))
+    }
+
+    /// Returns the field if `field_name` is an indexed text field, `None` if it is another kind
+    /// of field, or a path in a JSON field.
+    fn text_field(&self, field_name: &str) -> Result<Option<Field>, QueryParserError> {
+        let (field, json_path) = self
+            .schema
+            .find_field(field_name)
+            .ok_or_else(|| QueryParserError::FieldDoesNotExist(field_name.to_string()))?;
+        if !json_path.is_empty() {
+            return Ok(None);
+        }
+        let FieldType::Str(ref text_options) = self.schema.get_field_entry(field).field_type()
+        else {
+            return Ok(None);
+        };
+        if text_options.get_indexing_options().is_none() {
+            return Err(QueryParserError::FieldNotIndexed(field_name.to_string()));
+        }
+        Ok(Some(field))
+    }
+
+    fn text_analyzer(&self, field: Field) -> Result<TextAnalyzer, QueryParserError> {
+        let field_entry = self.schema.get_field_entry(field);
+        let tokenizer_name = match field_entry.field_type() {
+            FieldType::Str(text_options) => text_options
+                .get_indexing_options()
+                .map(|indexing_options| indexing_options.tokenizer()),
+            _ => None,
+        }
+        .ok_or_else(|| QueryParserError::FieldNotIndexed(field_entry.name().to_string()))?;
+        self.tokenizer_manager.get(tokenizer_name).ok_or_else(|| {
+            QueryParserError::UnknownTokenizer {
+                tokenizer: tokenizer_name.to_string(),
+                field: field_entry.name().to_string(),
+            }
+        })
+    }
+
+    fn build_leaf(&self, leaf: UserInputLeaf) -> Result<Box<dyn Query>, QueryParserError> {
+        self.query_parser
+            .build_query_from_user_input_ast(UserInputAst::Leaf(Box::new(leaf)))
+    }
+}
+
+fn object<'a>(
+    value: &'a Value,
+    query_type: &str,
+) -> Result<&'a Map<String, Value>, QueryParserError> {
+    value.as_object().ok_or_else(|| {
+        QueryParserError::SyntaxError(format!("the {query_type} query must be an object"))
+    })
+}
+
+/// Returns the key and the value of an object with a single entry, such as `{"term": {...}}`.
+fn single_entry<'a>(
+    value: &'a Value,
+    what: &str,
+) -> Result<(&'a str, &'a Value), QueryParserError> {
+    let map = value.as_object().ok_or_else(|| {
+        QueryParserError::SyntaxError(format!("expected an object for the {what}, got {value}"))
+    })?;
+    single_entry_of_map(map, what)
+}
+
+fn single_entry_of_map<'a>(
+    map: &'a Map<String, Value>,
+    what: &str,
+) -> Result<(&'a str, &'a Value), QueryParserError> {
+    let mut entries = map.iter();
+    match (entries.next(), entries.next()) {
+        (Some((key, value)), None) => Ok((key, value)),
+        _ => Err(QueryParserError::SyntaxError(format!(
+            "expected an object with a single key for the {what}, got {} keys",
+            map.len()
+        ))),
+    }
+}
+
+/// Splits the short form `{"field": value}` and the long form
+/// `{"field": {"<value_key>": value, ...params}}` of field queries into the value and the
+/// other parameters.
+fn value_and_params<'a>(
+    value: &'a Value,
+    query_type: &str,
+    value_key: &str,
+    param_keys: &[&str],
+) -> Result<(&'a Value, Map<String, Value>), QueryParserError> {
+    let Value::Object(params) = value else {
+        return Ok((value, Map::new()));
+    };
+    let mut params = params.clone();
+    let value = value.get(value_key).ok_or_else(|| {
+        QueryParserError::SyntaxError(format!(
+            "the {query_type} query is missing its '{value_key}'"
+        ))
+    })?;
+    params.remove(value_key);
+    check_params(query_type, &params, param_keys)?;
+    Ok((value, params))
+}
+
+fn check_params(
+    query_type: &str,
+    params: &Map<String, Value>,
+    param_keys: &[&str],
+) -> Result<(), QueryParserError> {
+    if let Some(key) = params
+        .keys()
+        .find(|key| !param_keys.contains(&key.as_str()))
+    {
+        return Err(QueryParserError::UnsupportedQuery(format!(
+            "unsupported parameter '{key}' in {query_type} query"
+        )));
+    }
+    Ok(())
+}
+
+fn with_boost(
+    query: Box<dyn Query>,
+    params: &Map<String, Value>,
+) -> Result<Box<dyn Query>, QueryParserError> {
+    match params.get("boost") {
+        None => Ok(query),
+        Some(boost) => {
+            let boost = boost
+                .as_f64()
+                .ok_or_else(|| QueryParserError::SyntaxError(format!("invalid boost {boost}")))?;
+            Ok(Box::new(BoostQuery::new(query, boost as Score)))
+        }
+    }
+}
+
+fn value_to_string(value: &Value) -> Result<String, QueryParserError> {
+    match value {
+        Value::String(text) => Ok(text.clone()),
+        Value::Number(number) => Ok(number.to_string()),
+        Value::Bool(val) => Ok(val.to_string()),
+        _ => Err(QueryParserError::SyntaxError(format!(
+            "expected a string, a number or a bool, got {value}"
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::QueryDslParser;
+    use crate::collector::Count;
+    use crate::query::QueryParserError;
+    use crate::schema::{Schema, FAST, INDEXED, STRING, TEXT};
+    use crate::{doc, Index, IndexWriter};
+
+    #[test]
+    fn test_query_dsl_parser() -> crate::Result<()> {
+        let mut schema_builder = Schema::builder();
+        let title = schema_builder.add_text_field("title", TEXT);
+        let tag = schema_builder.add_text_field("tag", STRING | FAST);
+        let year = schema_builder.add_u64_field("year", INDEXED | FAST);
+        let attributes = schema_builder.add_json_field("attributes", TEXT);
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer: IndexWriter = index.writer_for_tests()?;
+        index_writer.add_document(doc!(
+            title => "The Old Man and the Sea",
+            tag => "Novel",
+            year => 1952u64,
+            attributes => serde_json::json!({"color": "blue"}),
+        ))?;
+        index_writer.add_document(doc!(
+            title => "A Moveable Feast",
+            tag => "Memoir",
+            year => 1964u64,
+        ))?;
+        index_writer.add_document(doc!(title => "The Sun Also Rises", year => 1926u64))?;
+        index_writer.commit()?;
+        let searcher = index.reader()?.searcher();
+        let query_dsl_parser = QueryDslParser::for_index(&index);
+        let count = |query_json: &str| -> crate::Result<usize> {
+            let query = query_dsl_parser.parse_query(query_json).unwrap();
+            searcher.search(query.as_ref(), &Count)
+        };
+
+        assert_eq!(count(r#"{"match_all": {}}"#)?, 3);
+        assert_eq!(count(r#"{"match_none": {}}"#)?, 0);
+        // Term values are not analyzed, match values are.
+        assert_eq!(count(r#"{"term": {"tag": "Novel"}}"#)?, 1);
+        assert_eq!(count(r#"{"term": {"tag": "novel"}}"#)?, 0);
+        assert_eq!(count(r#"{"term": {"title": "Sea"}}"#)?, 0);
+        assert_eq!(
+            count(r#"{"term": {"year": {"value": 1964, "boost": 2.0}}}"#)?,
+            1
+        );
+        assert_eq!(count(r#"{"terms": {"tag": ["Novel", "Memoir"]}}"#)?, 2);
+        assert_eq!(
+            count(r#"{"terms": {"year": [1926, 1964], "boost": 0.5}}"#)?,
+            2
+        );
+        assert_eq!(count(r#"{"match": {"title": "the sea"}}"#)?, 2);
+        assert_eq!(
+            count(r#"{"match": {"title": {"query": "the sea", "operator": "and"}}}"#)?,
+            1
+        );
+        assert_eq!(count(r#"{"match": {"attributes.color": "blue"}}"#)?, 1);
+        assert_eq!(count(r#"{"match_phrase": {"title": "the sun"}}"#)?, 1);
+        assert_eq!(
+            count(r#"{"match_phrase": {"title": {"query": "the rises", "slop": 2}}}"#)?,
+            1
+        );
+        assert_eq!(
+            count(r#"{"range": {"year": {"gte": 1926, "lt": 1964}}}"#)?,
+            2
+        );
+        assert_eq!(count(r#"{"range": {"year": {"gt": 1926}}}"#)?, 2);
+        assert_eq!(count(r#"{"regexp": {"title": "s[eu].*"}}"#)?, 2);
+        assert_eq!(count(r#"{"exists": {"field": "tag"}}"#)?, 2);
+        assert_eq!(
+            count(
+                r#"{"bool": {
+                    "must": {"match": {"title": "the"}},
+                    "filter": [{"range": {"year": {"lt": 1960}}}],
+                    "must_not": [{"term": {"tag": "Novel"}}]
+                }}"#
+            )?,
+            1
+        );
+        assert_eq!(
+            count(r#"{"bool": {"must_not": {"exists": {"field": "tag"}}}}"#)?,
+            1
+        );
+        assert_eq!(
+            count(
+                r#"{"bool": {
+                    "should": [
+                        {"match": {"title": "sea"}},
+                        {"term": {"tag": "Novel"}},
+                        {"range": {"year": {"lt": 1960}}}
+                    ],
+                    "minimum_should_match": 2
+                }}"#
+            )?,
+            1
+        );
+
+        let parse_err = |query_json: &str| query_dsl_parser.parse_query(query_json).err().unwrap();
+        assert!(matches!(
+            parse_err(r#"{"fuzzy": {"title": "mna"}}"#),
+            QueryParserError::UnsupportedQuery(_)
+        ));
+        assert!(matches!(
+            parse_err(r#"{"match": {"title": {"query": "sea", "fuzziness": 2}}}"#),
+            QueryParserError::UnsupportedQuery(_)
+        ));
+        assert!(matches!(
+            parse_err(r#"{"term": {"author": "hemingway"}}"#),
+            QueryParserError::FieldDoesNotExist(_)
+        ));
+        assert!(matches!(
+            parse_err(r#"{"term": {"year": "nineteen"}}"#),
+            QueryParserError::ExpectedInt(_)
+        ));
+        assert!(matches!(
+            parse_err(r#"{"term": {"tag": "Novel"}, "match": {"title": "sea"}}"#),
+            QueryParserError::SyntaxError(_)
+        ));
+        assert!(matches!(
+            parse_err(r#"{"exists": {"field": "title"}}"#),
+            QueryParserError::UnsupportedQuery(_)
+        ));
+        Ok(())
+    }
+}
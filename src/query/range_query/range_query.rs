@@ -387,6 +387,10 @@ impl Query for RangeQuery {
             }))
         }
     }
+
+    fn cache_key(&self) -> Option<String> {
+        Some(format!("{self:?}"))
+    }
 }
 
 pub struct RangeWeight {
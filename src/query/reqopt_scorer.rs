@@ -1,6 +1,6 @@
 use std::marker::PhantomData;
 
-use crate::docset::DocSet;
+use crate::docset::{DocSet, TERMINATED};
 use crate::query::score_combiner::ScoreCombiner;
 use crate::query::Scorer;
 use crate::{DocId, Score};
@@ -11,11 +11,15 @@ use crate::{DocId, Score};
 ///
 /// This is useful for queries like `+somethingrequired somethingoptional`.
 ///
-/// Note that `somethingoptional` has no impact on the `DocSet`.
+/// Note that `somethingoptional` has no impact on the `DocSet`, unless the caller declares
+/// through [`Scorer::set_min_competitive_score`] that the documents scored by
+/// `somethingrequired` alone are not competitive. The scorer then skips to the documents matching
+/// both.
 pub struct RequiredOptionalScorer<TReqScorer, TOptScorer, TScoreCombiner: ScoreCombiner> {
     req_scorer: TReqScorer,
     opt_scorer: TOptScorer,
     score_cache: Option<Score>,
+    opt_is_required: bool,
     _phantom: PhantomData<TScoreCombiner>,
 }
 
@@ -34,11 +38,40 @@ where
             req_scorer,
             opt_scorer,
             score_cache: None,
+            opt_is_required: false,
             _phantom: PhantomData,
         }
     }
 }
 
+impl<TReqScorer, TOptScorer, TScoreCombiner>
+    RequiredOptionalScorer<TReqScorer, TOptScorer, TScoreCombiner>
+where
+    TReqScorer: DocSet,
+    TOptScorer: DocSet,
+    TScoreCombiner: ScoreCombiner,
+{
+    /// Moves to the first document from `doc` matching the optional scorer, if the documents
+    /// matching only the required scorer are not competitive.
+    fn skip_non_competitive(&mut self, mut doc: DocId) -> DocId {
+        if !self.opt_is_required {
+            return doc;
+        }
+        while doc != TERMINATED {
+            let opt_doc = if self.opt_scorer.doc() < doc {
+                self.opt_scorer.seek(doc)
+            } else {
+                self.opt_scorer.doc()
+            };
+            if opt_doc == doc {
+                break;
+            }
+            doc = self.req_scorer.seek(opt_doc);
+        }
+        doc
+    }
+}
+
 impl<TReqScorer, TOptScorer, TScoreCombiner> DocSet
     for RequiredOptionalScorer<TReqScorer, TOptScorer, TScoreCombiner>
 where
@@ -48,12 +81,14 @@ where
 {
     fn advance(&mut self) -> DocId {
         self.score_cache = None;
-        self.req_scorer.advance()
+        let doc = self.req_scorer.advance();
+        self.skip_non_competitive(doc)
     }
 
     fn seek(&mut self, target: DocId) -> DocId {
         self.score_cache = None;
-        self.req_scorer.seek(target)
+        let doc = self.req_scorer.seek(target);
+        self.skip_non_competitive(doc)
     }
 
     fn doc(&self) -> DocId {
@@ -63,6 +98,10 @@ where
     fn size_hint(&self) -> u32 {
         self.req_scorer.size_hint()
     }
+
+    fn cost(&self) -> u64 {
+        self.req_scorer.cost()
+    }
 }
 
 impl<TReqScorer, TOptScorer, TScoreCombiner> Scorer
@@ -86,11 +125,22 @@ where
         self.score_cache = Some(score);
         score
     }
+
+    fn max_score(&self) -> Score {
+        TScoreCombiner::default()
+            .max_score(&[self.req_scorer.max_score(), self.opt_scorer.max_score()])
+    }
+
+    fn set_min_competitive_score(&mut self, min_score: Score) {
+        let req_max_score = TScoreCombiner::default().max_score(&[self.req_scorer.max_score()]);
+        self.opt_is_required = req_max_score <= min_score;
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::RequiredOptionalScorer;
+    use crate::assert_nearly_equals;
     use crate::docset::{DocSet, TERMINATED};
     use crate::postings::tests::test_skip_against_unoptimized;
     use crate::query::score_combiner::{DoNothingCombiner, SumCombiner};
@@ -196,4 +246,23 @@ mod tests {
             assert_eq!(reqoptscorer.score(), 1.0);
         }
     }
+
+    #[test]
+    fn test_reqopt_scorer_min_competitive_score() {
+        let mut reqoptscorer: RequiredOptionalScorer<_, _, SumCombiner> =
+            RequiredOptionalScorer::new(
+                ConstScorer::new(VecDocSet::from(vec![1, 3, 7, 8, 9, 10, 13, 15]), 1.0),
+                ConstScorer::new(VecDocSet::from(vec![2, 7, 11, 12, 15]), 1.0),
+            );
+        assert_nearly_equals!(reqoptscorer.max_score(), 2.0);
+        reqoptscorer.set_min_competitive_score(0.5);
+        assert_eq!(reqoptscorer.advance(), 3);
+        // The documents matching only the required scorer are not competitive anymore.
+        reqoptscorer.set_min_competitive_score(1.5);
+        assert_eq!(reqoptscorer.advance(), 7);
+        assert_eq!(reqoptscorer.score(), 2.0);
+        assert_eq!(reqoptscorer.seek(8), 15);
+        assert_eq!(reqoptscorer.score(), 2.0);
+        assert_eq!(reqoptscorer.advance(), TERMINATED);
+    }
 }
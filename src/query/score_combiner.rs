@@ -15,6 +15,25 @@ pub trait ScoreCombiner: Default + Clone + Send + Copy + 'static {
 
     /// Returns the aggregate score.
     fn score(&self) -> Score;
+
+    /// Returns an upper bound of the aggregate score, given an upper bound of the score of each
+    /// of the scorers.
+    ///
+    /// The default implementation returns `Score::INFINITY`.
+    fn max_score(&self, _max_scores: &[Score]) -> Score {
+        Score::INFINITY
+    }
+}
+
+/// Returns an upper bound of the sum of scores bounded by `max_scores`.
+///
+/// The sum of the scores is not necessarily computed in the same order as the one of the
+/// bounds, so that the result is inflated by the maximum relative error of the floating point
+/// additions.
+pub(crate) fn sum_upper_bound(max_scores: &[Score]) -> Score {
+    let sum: Score = max_scores.iter().sum();
+    let relative_error = 2.0 * max_scores.len() as Score * Score::EPSILON;
+    sum + sum.abs() * relative_error
 }
 
 /// Just ignores scores. The `DoNothingCombiner` does not
@@ -32,6 +51,10 @@ impl ScoreCombiner for DoNothingCombiner {
     fn score(&self) -> Score {
         1.0
     }
+
+    fn max_score(&self, _max_scores: &[Score]) -> Score {
+        1.0
+    }
 }
 
 /// Sums the score of different scorers.
@@ -52,6 +75,10 @@ impl ScoreCombiner for SumCombiner {
     fn score(&self) -> Score {
         self.score
     }
+
+    fn max_score(&self, max_scores: &[Score]) -> Score {
+        sum_upper_bound(max_scores)
+    }
 }
 
 /// Sums the score of different scorers and keeps the count
@@ -76,6 +103,10 @@ impl ScoreCombiner for SumWithCoordsCombiner {
     fn score(&self) -> Score {
         self.score
     }
+
+    fn max_score(&self, max_scores: &[Score]) -> Score {
+        sum_upper_bound(max_scores)
+    }
 }
 
 /// Take max score of different scorers
@@ -113,4 +144,19 @@ impl ScoreCombiner for DisjunctionMaxCombiner {
     fn score(&self) -> Score {
         self.max + (self.sum - self.max) * self.tie_breaker
     }
+
+    fn max_score(&self, max_scores: &[Score]) -> Score {
+        if !(0.0..=1.0).contains(&self.tie_breaker) {
+            return Score::INFINITY;
+        }
+        // The score is `max * (1 - tie_breaker) + sum * tie_breaker`.
+        let max = max_scores
+            .iter()
+            .copied()
+            .fold(Score::NEG_INFINITY, Score::max);
+        sum_upper_bound(&[
+            max * (1.0 - self.tie_breaker),
+            sum_upper_bound(max_scores) * self.tie_breaker,
+        ])
+    }
 }
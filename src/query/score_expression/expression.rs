@@ -0,0 +1,608 @@
+use std::fmt;
+use std::sync::Arc;
+
+use columnar::{Column, ColumnType};
+
+use crate::aggregation::f64_from_fastfield_u64;
+use crate::index::SegmentReader;
+use crate::{DocId, Score, TantivyError};
+
+/// The name standing for the score of the wrapped query in an expression.
+const SCORE_VARIABLE: &str = "_score";
+
+/// Maximum nesting depth of an expression, bounding the recursion of the parser and of the
+/// evaluation.
+const MAX_DEPTH: usize = 128;
+
+/// The column types an expression can read values from.
+const NUMERICAL_COLUMN_TYPES: [ColumnType; 5] = [
+    ColumnType::U64,
+    ColumnType::I64,
+    ColumnType::F64,
+    ColumnType::Bool,
+    ColumnType::DateTime,
+];
+
+/// An arithmetic formula computing the score of a document from the score given by a query
+/// and the values of the document in numerical fast fields.
+///
+/// The expression supports:
+/// - numbers, like `0.3`, `2` or `1e-3`,
+/// - `_score`, the score given by the query,
+/// - the name of a fast field, for the value of the document in this field. A document without any
+///   value counts as `0`, and the first value is used for a multivalued field. JSON fields are read
+///   with their full path, like `attributes.popularity`.
+/// - the operators `+`, `-`, `*`, `/` and `^` (power), with the usual precedence, and parentheses,
+/// - the functions `abs`, `exp`, `ln`, `log10`, `log1p`, `sqrt` of one argument and `min`, `max`,
+///   `pow` of two arguments.
+///
+/// Expressions cannot be nested more than 128 levels deep, counting parentheses, function calls
+/// and unary minus signs.
+///
+/// Computations are done in `f64`. Invalid operations, like the logarithm of a negative
+/// number, give NaN as in Rust.
+///
+/// ```rust
+/// use tantivy::query::ScoreExpression;
+///
+/// let expression = ScoreExpression::parse("_score * log1p(popularity) + 0.3 * freshness")?;
+/// assert_eq!(expression.fields(), &["popularity", "freshness"]);
+/// # Ok::<(), tantivy::TantivyError>(())
+/// ```
+#[derive(Clone)]
+pub struct ScoreExpression {
+    source: String,
+    expr: Arc<Expr>,
+    fields: Vec<String>,
+}
+
+impl ScoreExpression {
+    /// Parses an expression.
+    ///
+    /// Returns an [`TantivyError::InvalidArgument`] error if the expression is not valid.
+    pub fn parse(source: &str) -> crate::Result<ScoreExpression> {
+        let tokens = tokenize(source).map_err(|error| error.into_tantivy_error(source))?;
+        let mut parser = Parser {
+            tokens,
+            pos: 0,
+            end: source.len(),
+            depth: 0,
+            fields: Vec::new(),
+        };
+        let expr = parser
+            .parse_complete()
+            .map_err(|error| error.into_tantivy_error(source))?;
+        Ok(ScoreExpression {
+            source: source.to_string(),
+            expr: Arc::new(expr),
+            fields: parser.fields,
+        })
+    }
+
+    /// Returns the fast fields read by the expression, in order of first appearance.
+    pub fn fields(&self) -> &[String] {
+        &self.fields
+    }
+
+    /// Computes the expression for a document, given its score and its values in each of the
+    /// [fields](ScoreExpression::fields).
+    pub fn evaluate(&self, score: Score, field_values: &[f64]) -> Score {
+        self.expr.evaluate(score as f64, field_values) as Score
+    }
+}
+
+impl fmt::Debug for ScoreExpression {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "ScoreExpression({:?})", self.source)
+    }
+}
+
+impl fmt::Display for ScoreExpression {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.source)
+    }
+}
+
+/// A [`ScoreExpression`] bound to the fast field columns of a segment.
+pub(crate) struct SegmentScoreExpression {
+    expression: ScoreExpression,
+    columns: Vec<Option<(Column<u64>, ColumnType)>>,
+    field_values: Vec<f64>,
+}
+
+impl SegmentScoreExpression {
+    pub fn open(
+        expression: &ScoreExpression,
+        reader: &SegmentReader,
+    ) -> crate::Result<SegmentScoreExpression> {
+        let fast_fields = reader.fast_fields();
+        let columns = expression
+            .fields
+            .iter()
+            .map(|field| fast_fields.u64_lenient_for_type(Some(&NUMERICAL_COLUMN_TYPES), field))
+            .collect::<crate::Result<Vec<_>>>()?;
+        Ok(SegmentScoreExpression {
+            expression: expression.clone(),
+            field_values: vec![0.0; columns.len()],
+            columns,
+        })
+    }
+
+    /// Computes the expression for `doc`, whose score is `score`.
+    pub fn evaluate(&mut self, doc: DocId, score: Score) -> Score {
+        for (field_value, column_opt) in self.field_values.iter_mut().zip(&self.columns) {
+            *field_value = column_opt
+                .as_ref()
+                .and_then(|(column, column_type)| {
+                    let val = column.first(doc)?;
+                    Some(f64_from_fastfield_u64(val, column_type))
+                })
+                .unwrap_or(0.0);
+        }
+        self.expression.evaluate(score, &self.field_values)
+    }
+
+    /// Returns the fields of the expression and their values for the document last evaluated.
+    pub fn field_values(&self) -> impl Iterator<Item = (&str, f64)> {
+        self.expression
+            .fields
+            .iter()
+            .map(String::as_str)
+            .zip(self.field_values.iter().copied())
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum BinaryOperator {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Pow,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Function {
+    Abs,
+    Exp,
+    Ln,
+    Log10,
+    Log1p,
+    Sqrt,
+    Min,
+    Max,
+    Pow,
+}
+
+impl Function {
+    fn from_name(name: &str) -> Option<Function> {
+        let function = match name {
+            "abs" => Function::Abs,
+            "exp" => Function::Exp,
+            "ln" => Function::Ln,
+            "log10" => Function::Log10,
+            "log1p" => Function::Log1p,
+            "sqrt" => Function::Sqrt,
+            "min" => Function::Min,
+            "max" => Function::Max,
+            "pow" => Function::Pow,
+            _ => return None,
+        };
+        Some(function)
+    }
+
+    fn num_args(self) -> usize {
+        match self {
+            Function::Min | Function::Max | Function::Pow => 2,
+            _ => 1,
+        }
+    }
+}
+
+#[derive(Debug)]
+enum Expr {
+    Number(f64),
+    Score,
+    /// The value of the field of the given ordinal in [`ScoreExpression::fields`].
+    Field(usize),
+    Neg(Box<Expr>),
+    Binary(BinaryOperator, Box<Expr>, Box<Expr>),
+    Call(Function, Vec<Expr>),
+}
+
+impl Expr {
+    fn evaluate(&self, score: f64, field_values: &[f64]) -> f64 {
+        match self {
+            Expr::Number(val) => *val,
+            Expr::Score => score,
+            Expr::Field(ord) => field_values[*ord],
+            Expr::Neg(expr) => -expr.evaluate(score, field_values),
+            Expr::Binary(operator, left, right) => {
+                let left = left.evaluate(score, field_values);
+                let right = right.evaluate(score, field_values);
+                match operator {
+                    BinaryOperator::Add => left + right,
+                    BinaryOperator::Sub => left - right,
+                    BinaryOperator::Mul => left * right,
+                    BinaryOperator::Div => left / right,
+                    BinaryOperator::Pow => left.powf(right),
+                }
+            }
+            Expr::Call(function, args) => {
+                let arg = |i: usize| args[i].evaluate(score, field_values);
+                match function {
+                    Function::Abs => arg(0).abs(),
+                    Function::Exp => arg(0).exp(),
+                    Function::Ln => arg(0).ln(),
+                    Function::Log10 => arg(0).log10(),
+                    Function::Log1p => arg(0).ln_1p(),
+                    Function::Sqrt => arg(0).sqrt(),
+                    Function::Min => arg(0).min(arg(1)),
+                    Function::Max => arg(0).max(arg(1)),
+                    Function::Pow => arg(0).powf(arg(1)),
+                }
+            }
+        }
+    }
+}
+
+/// An error in an expression, at the given byte offset.
+struct ParseError {
+    pos: usize,
+    message: String,
+}
+
+impl ParseError {
+    fn new(pos: usize, message: impl Into<String>) -> ParseError {
+        ParseError {
+            pos,
+            message: message.into(),
+        }
+    }
+
+    fn into_tantivy_error(self, source: &str) -> TantivyError {
+        TantivyError::InvalidArgument(format!(
+            "Invalid score expression `{source}`: {} at position {}",
+            self.message, self.pos
+        ))
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    Number(f64),
+    Identifier(String),
+    Operator(BinaryOperator),
+    OpenParenthesis,
+    CloseParenthesis,
+    Comma,
+}
+
+fn is_identifier_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_' || c == '.'
+}
+
+/// Returns the end of the number starting at `start`: digits and dots, optionally followed by
+/// an exponent like `e-3`.
+fn number_end(source: &str, start: usize) -> usize {
+    let bytes = source.as_bytes();
+    let digits_end = |from: usize, accept_dot: bool| {
+        from + bytes[from..]
+            .iter()
+            .take_while(|&&b| b.is_ascii_digit() || (accept_dot && b == b'.'))
+            .count()
+    };
+    let end = digits_end(start, true);
+    if !matches!(bytes.get(end), Some(b'e' | b'E')) {
+        return end;
+    }
+    let mut exponent_start = end + 1;
+    if matches!(bytes.get(exponent_start), Some(b'+' | b'-')) {
+        exponent_start += 1;
+    }
+    let exponent_end = digits_end(exponent_start.min(bytes.len()), false);
+    if exponent_end > exponent_start {
+        exponent_end
+    } else {
+        end
+    }
+}
+
+fn tokenize(source: &str) -> Result<Vec<(usize, Token)>, ParseError> {
+    let mut tokens = Vec::new();
+    let mut chars = source.char_indices().peekable();
+    while let Some((pos, c)) = chars.next() {
+        let token = match c {
+            c if c.is_whitespace() => continue,
+            '+' => Token::Operator(BinaryOperator::Add),
+            '-' => Token::Operator(BinaryOperator::Sub),
+            '*' => Token::Operator(BinaryOperator::Mul),
+            '/' => Token::Operator(BinaryOperator::Div),
+            '^' => Token::Operator(BinaryOperator::Pow),
+            '(' => Token::OpenParenthesis,
+            ')' => Token::CloseParenthesis,
+            ',' => Token::Comma,
+            c if c.is_ascii_digit() || c == '.' => {
+                let end = number_end(source, pos);
+                while chars.next_if(|&(next_pos, _)| next_pos < end).is_some() {}
+                let number = source[pos..end]
+                    .parse()
+                    .map_err(|_| ParseError::new(pos, "invalid number"))?;
+                Token::Number(number)
+            }
+            c if c.is_ascii_alphabetic() || c == '_' => {
+                let mut end = pos + c.len_utf8();
+                while let Some(&(next_pos, next_c)) = chars.peek() {
+                    if !is_identifier_char(next_c) {
+                        break;
+                    }
+                    end = next_pos + next_c.len_utf8();
+                    chars.next();
+                }
+                Token::Identifier(source[pos..end].to_string())
+            }
+            c => return Err(ParseError::new(pos, format!("unexpected character `{c}`"))),
+        };
+        tokens.push((pos, token));
+    }
+    Ok(tokens)
+}
+
+/// Recursive descent parser, with the grammar:
+///
+/// ```text
+/// sum     = product (("+" | "-") product)*
+/// product = unary (("*" | "/") unary)*
+/// unary   = "-" unary | power
+/// power   = primary ("^" unary)?
+/// primary = number | "(" sum ")" | identifier "(" sum ("," sum)* ")" | identifier
+/// ```
+struct Parser {
+    tokens: Vec<(usize, Token)>,
+    pos: usize,
+    // Position of the end of the expression, for errors.
+    end: usize,
+    // Number of nested `unary` rules being parsed.
+    depth: usize,
+    fields: Vec<String>,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos).map(|(_, token)| token)
+    }
+
+    fn current_pos(&self) -> usize {
+        self.tokens
+            .get(self.pos)
+            .map(|&(pos, _)| pos)
+            .unwrap_or(self.end)
+    }
+
+    fn next_operator(&mut self, operators: &[BinaryOperator]) -> Option<BinaryOperator> {
+        match self.peek() {
+            Some(Token::Operator(operator)) if operators.contains(operator) => {
+                let operator = *operator;
+                self.pos += 1;
+                Some(operator)
+            }
+            _ => None,
+        }
+    }
+
+    fn expect(&mut self, expected: Token, description: &str) -> Result<(), ParseError> {
+        if self.peek() != Some(&expected) {
+            return Err(ParseError::new(
+                self.current_pos(),
+                format!("expected {description}"),
+            ));
+        }
+        self.pos += 1;
+        Ok(())
+    }
+
+    fn parse_complete(&mut self) -> Result<Expr, ParseError> {
+        let expr = self.parse_sum()?;
+        if self.pos < self.tokens.len() {
+            return Err(ParseError::new(self.current_pos(), "unexpected token"));
+        }
+        Ok(expr)
+    }
+
+    fn parse_sum(&mut self) -> Result<Expr, ParseError> {
+        let mut expr = self.parse_product()?;
+        while let Some(operator) = self.next_operator(&[BinaryOperator::Add, BinaryOperator::Sub]) {
+            let right = self.parse_product()?;
+            expr = Expr::Binary(operator, Box::new(expr), Box::new(right));
+        }
+        Ok(expr)
+    }
+
+    fn parse_product(&mut self) -> Result<Expr, ParseError> {
+        let mut expr = self.parse_unary()?;
+        while let Some(operator) = self.next_operator(&[BinaryOperator::Mul, BinaryOperator::Div]) {
+            let right = self.parse_unary()?;
+            expr = Expr::Binary(operator, Box::new(expr), Box::new(right));
+        }
+        Ok(expr)
+    }
+
+    fn parse_unary(&mut self) -> Result<Expr, ParseError> {
+        // All of the recursive rules go through this one.
+        if self.depth == MAX_DEPTH {
+            return Err(ParseError::new(
+                self.current_pos(),
+                format!("expression is nested more than {MAX_DEPTH} levels deep"),
+            ));
+        }
+        self.depth += 1;
+        let expr_res = self.parse_unary_inner();
+        self.depth -= 1;
+        expr_res
+    }
+
+    fn parse_unary_inner(&mut self) -> Result<Expr, ParseError> {
+        if self.next_operator(&[BinaryOperator::Sub]).is_some() {
+            return Ok(Expr::Neg(Box::new(self.parse_unary()?)));
+        }
+        let expr = self.parse_primary()?;
+        if self.next_operator(&[BinaryOperator::Pow]).is_some() {
+            let exponent = self.parse_unary()?;
+            return Ok(Expr::Binary(
+                BinaryOperator::Pow,
+                Box::new(expr),
+                Box::new(exponent),
+            ));
+        }
+        Ok(expr)
+    }
+
+    fn parse_primary(&mut self) -> Result<Expr, ParseError> {
+        let pos = self.current_pos();
+        let Some((_, token)) = self.tokens.get(self.pos).cloned() else {
+            return Err(ParseError::new(pos, "unexpected end of expression"));
+        };
+        self.pos += 1;
+        match token {
+            Token::Number(val) => Ok(Expr::Number(val)),
+            Token::OpenParenthesis => {
+                let expr = self.parse_sum()?;
+                self.expect(Token::CloseParenthesis, "`)`")?;
+                Ok(expr)
+            }
+            Token::Identifier(name) if self.peek() == Some(&Token::OpenParenthesis) => {
+                self.pos += 1;
+                let function = Function::from_name(&name)
+                    .ok_or_else(|| ParseError::new(pos, format!("unknown function `{name}`")))?;
+                let mut args = vec![self.parse_sum()?];
+                while self.peek() == Some(&Token::Comma) {
+                    self.pos += 1;
+                    args.push(self.parse_sum()?);
+                }
+                self.expect(Token::CloseParenthesis, "`)`")?;
+                if args.len() != function.num_args() {
+                    return Err(ParseError::new(
+                        pos,
+                        format!(
+                            "`{name}` expects {} argument(s), got {}",
+                            function.num_args(),
+                            args.len()
+                        ),
+                    ));
+                }
+                Ok(Expr::Call(function, args))
+            }
+            Token::Identifier(name) if name == SCORE_VARIABLE => Ok(Expr::Score),
+            Token::Identifier(name) => {
+                let ord = self
+                    .fields
+                    .iter()
+                    .position(|field| field == &name)
+                    .unwrap_or_else(|| {
+                        self.fields.push(name);
+                        self.fields.len() - 1
+                    });
+                Ok(Expr::Field(ord))
+            }
+            Token::Operator(_) | Token::CloseParenthesis | Token::Comma => {
+                Err(ParseError::new(pos, "unexpected token"))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ScoreExpression;
+    use crate::{assert_nearly_equals, TantivyError};
+
+    fn evaluate(source: &str, score: f32, field_values: &[f64]) -> f32 {
+        ScoreExpression::parse(source)
+            .unwrap()
+            .evaluate(score, field_values)
+    }
+
+    fn parse_error(source: &str) -> String {
+        match ScoreExpression::parse(source) {
+            Err(TantivyError::InvalidArgument(message)) => message,
+            other => panic!("expected an error, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_score_expression_evaluate() {
+        assert_nearly_equals!(evaluate("1 + 2 * 3", 0.0, &[]), 7.0);
+        assert_nearly_equals!(evaluate("(1 + 2) * 3", 0.0, &[]), 9.0);
+        assert_nearly_equals!(evaluate("8 / 4 / 2", 0.0, &[]), 1.0);
+        assert_nearly_equals!(evaluate("10 - 4 - 3", 0.0, &[]), 3.0);
+        assert_nearly_equals!(evaluate("2 ^ 3 ^ 2", 0.0, &[]), 512.0);
+        assert_nearly_equals!(evaluate("-2 ^ 2", 0.0, &[]), -4.0);
+        assert_nearly_equals!(evaluate("2 * -_score", 1.5, &[]), -3.0);
+        assert_nearly_equals!(evaluate("max(abs(-3), sqrt(4)) + min(1, 2)", 0.0, &[]), 4.0);
+        assert_nearly_equals!(evaluate("pow(2, 10) + ln(exp(1.5))", 0.0, &[]), 1025.5);
+        assert_nearly_equals!(evaluate("log10(1000) + log1p(0)", 0.0, &[]), 3.0);
+        assert_nearly_equals!(evaluate("1e3 + 2.5E-1 + 1e+1 - 3", 0.0, &[]), 1007.25);
+        assert_nearly_equals!(evaluate("2e-3 * 1000", 0.0, &[]), 2.0);
+        assert_nearly_equals!(
+            evaluate(
+                "_score * log1p(popularity) + 0.3 * freshness",
+                2.0,
+                &[9.0, 0.5]
+            ),
+            2.0 * 10f32.ln() + 0.15
+        );
+    }
+
+    #[test]
+    fn test_score_expression_fields() -> crate::Result<()> {
+        let expression = ScoreExpression::parse("a * b + attributes.c / a + _score")?;
+        assert_eq!(expression.fields(), &["a", "b", "attributes.c"]);
+        assert_nearly_equals!(expression.evaluate(1.0, &[2.0, 3.0, 4.0]), 9.0);
+        assert_eq!(expression.to_string(), "a * b + attributes.c / a + _score");
+        Ok(())
+    }
+
+    #[test]
+    fn test_score_expression_parse_errors() {
+        assert_eq!(
+            parse_error("1 +"),
+            "Invalid score expression `1 +`: unexpected end of expression at position 3"
+        );
+        assert_eq!(
+            parse_error("(1 + 2"),
+            "Invalid score expression `(1 + 2`: expected `)` at position 6"
+        );
+        assert_eq!(
+            parse_error("1 2"),
+            "Invalid score expression `1 2`: unexpected token at position 2"
+        );
+        assert_eq!(
+            parse_error("log(2)"),
+            "Invalid score expression `log(2)`: unknown function `log` at position 0"
+        );
+        assert_eq!(
+            parse_error("1 + max(2)"),
+            "Invalid score expression `1 + max(2)`: `max` expects 2 argument(s), got 1 at \
+             position 4"
+        );
+        assert_eq!(
+            parse_error("a % 2"),
+            "Invalid score expression `a % 2`: unexpected character `%` at position 2"
+        );
+        assert_eq!(
+            parse_error("1.2.3"),
+            "Invalid score expression `1.2.3`: invalid number at position 0"
+        );
+        assert_eq!(
+            parse_error("1e"),
+            "Invalid score expression `1e`: unexpected token at position 1"
+        );
+        let nested_source = format!("{}1{}", "(".repeat(200), ")".repeat(200));
+        assert!(parse_error(&nested_source).contains("nested more than 128 levels deep"));
+        let negated_source = format!("{}1", "-".repeat(200));
+        assert!(parse_error(&negated_source).contains("nested more than 128 levels deep"));
+        assert!(
+            ScoreExpression::parse(&format!("{}1{}", "(".repeat(100), ")".repeat(100))).is_ok()
+        );
+    }
+}
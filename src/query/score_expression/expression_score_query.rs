@@ -0,0 +1,261 @@
+use std::fmt;
+
+use super::expression::{ScoreExpression, SegmentScoreExpression};
+use crate::docset::COLLECT_BLOCK_BUFFER_LEN;
+use crate::fastfield::AliveBitSet;
+use crate::query::{EnableScoring, Explanation, Query, Scorer, Weight};
+use crate::schema::Schema;
+use crate::{DocId, DocSet, Score, SegmentReader, TantivyError, Term};
+
+/// `ExpressionScoreQuery` is a wrapper over a query computing the score of the documents with
+/// a [`ScoreExpression`].
+///
+/// The document set matched by the `ExpressionScoreQuery` is strictly the same as the
+/// underlying query. The score of each document is the expression, computed from the score of
+/// the underlying query and the values of the document in the fast fields of the expression.
+///
+/// ```rust
+/// use tantivy::collector::TopDocs;
+/// use tantivy::query::{ExpressionScoreQuery, QueryParser, ScoreExpression};
+/// use tantivy::schema::{Schema, FAST, TEXT};
+/// use tantivy::{doc, Index, IndexWriter};
+///
+/// # fn main() -> tantivy::Result<()> {
+/// let mut schema_builder = Schema::builder();
+/// let title = schema_builder.add_text_field("title", TEXT);
+/// let popularity = schema_builder.add_u64_field("popularity", FAST);
+/// let index = Index::create_in_ram(schema_builder.build());
+/// let mut index_writer: IndexWriter = index.writer(15_000_000)?;
+/// index_writer.add_document(doc!(title => "The Name of the Wind", popularity => 10u64))?;
+/// index_writer.add_document(doc!(title => "The Wind in the Willows", popularity => 1000u64))?;
+/// index_writer.commit()?;
+/// let searcher = index.reader()?.searcher();
+///
+/// let query = QueryParser::for_index(&index, vec![title]).parse_query("wind")?;
+/// let expression = ScoreExpression::parse("_score * log1p(popularity)")?;
+/// let query = ExpressionScoreQuery::new(query, expression);
+/// let top_docs = searcher.search(&query, &TopDocs::with_limit(1))?;
+/// assert_eq!(top_docs[0].1.doc_id, 1);
+/// # Ok(())
+/// # }
+/// ```
+pub struct ExpressionScoreQuery {
+    query: Box<dyn Query>,
+    expression: ScoreExpression,
+}
+
+impl ExpressionScoreQuery {
+    /// Builds an expression score query.
+    pub fn new(query: Box<dyn Query>, expression: ScoreExpression) -> ExpressionScoreQuery {
+        ExpressionScoreQuery { query, expression }
+    }
+}
+
+impl Clone for ExpressionScoreQuery {
+    fn clone(&self) -> Self {
+        ExpressionScoreQuery {
+            query: self.query.box_clone(),
+            expression: self.expression.clone(),
+        }
+    }
+}
+
+impl fmt::Debug for ExpressionScoreQuery {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "ExpressionScore(query={:?}, expression={})",
+            self.query, self.expression
+        )
+    }
+}
+
+/// Checks that all of the fields read by `expression` are fast fields of the schema.
+fn check_expression_fields(schema: &Schema, expression: &ScoreExpression) -> crate::Result<()> {
+    for field_name in expression.fields() {
+        let is_fast = schema
+            .find_field(field_name)
+            .map(|(field, _)| schema.get_field_entry(field).is_fast())
+            .unwrap_or(false);
+        if !is_fast {
+            return Err(TantivyError::SchemaError(format!(
+                "Field `{field_name}` is missing or is not configured as a fast field."
+            )));
+        }
+    }
+    Ok(())
+}
+
+impl Query for ExpressionScoreQuery {
+    fn weight(&self, enable_scoring: EnableScoring<'_>) -> crate::Result<Box<dyn Weight>> {
+        let weight = self.query.weight(enable_scoring)?;
+        if !enable_scoring.is_scoring_enabled() {
+            return Ok(weight);
+        }
+        check_expression_fields(enable_scoring.schema(), &self.expression)?;
+        Ok(Box::new(ExpressionScoreWeight {
+            weight,
+            expression: self.expression.clone(),
+        }))
+    }
+
+    fn query_terms<'a>(&'a self, visitor: &mut dyn FnMut(&'a Term, bool)) {
+        self.query.query_terms(visitor)
+    }
+
+    fn query_phrases<'a>(&'a self, visitor: &mut dyn FnMut(&'a [(usize, Term)], u32)) {
+        self.query.query_phrases(visitor)
+    }
+}
+
+struct ExpressionScoreWeight {
+    weight: Box<dyn Weight>,
+    expression: ScoreExpression,
+}
+
+impl Weight for ExpressionScoreWeight {
+    fn scorer(&self, reader: &SegmentReader, boost: Score) -> crate::Result<Box<dyn Scorer>> {
+        let underlying = self.weight.scorer(reader, 1.0)?;
+        let expression = SegmentScoreExpression::open(&self.expression, reader)?;
+        Ok(Box::new(ExpressionScorer {
+            underlying,
+            expression,
+            boost,
+        }))
+    }
+
+    fn explain(&self, reader: &SegmentReader, doc: DocId) -> crate::Result<Explanation> {
+        let underlying_explanation = self.weight.explain(reader, doc)?;
+        let mut expression = SegmentScoreExpression::open(&self.expression, reader)?;
+        let score = expression.evaluate(doc, underlying_explanation.value());
+        let mut explanation =
+            Explanation::new_with_string(format!("Expression `{}`", self.expression), score);
+        for (field_name, value) in expression.field_values() {
+            explanation.add_context(format!("{field_name} = {value}"));
+        }
+        explanation.add_detail(underlying_explanation);
+        Ok(explanation)
+    }
+
+    fn count(&self, reader: &SegmentReader) -> crate::Result<u32> {
+        self.weight.count(reader)
+    }
+}
+
+struct ExpressionScorer {
+    underlying: Box<dyn Scorer>,
+    expression: SegmentScoreExpression,
+    boost: Score,
+}
+
+impl DocSet for ExpressionScorer {
+    fn advance(&mut self) -> DocId {
+        self.underlying.advance()
+    }
+
+    fn seek(&mut self, target: DocId) -> DocId {
+        self.underlying.seek(target)
+    }
+
+    fn fill_buffer(&mut self, buffer: &mut [DocId; COLLECT_BLOCK_BUFFER_LEN]) -> usize {
+        self.underlying.fill_buffer(buffer)
+    }
+
+    fn doc(&self) -> DocId {
+        self.underlying.doc()
+    }
+
+    fn size_hint(&self) -> u32 {
+        self.underlying.size_hint()
+    }
+
+    fn count(&mut self, alive_bitset: &AliveBitSet) -> u32 {
+        self.underlying.count(alive_bitset)
+    }
+
+    fn count_including_deleted(&mut self) -> u32 {
+        self.underlying.count_including_deleted()
+    }
+}
+
+impl Scorer for ExpressionScorer {
+    fn score(&mut self) -> Score {
+        let doc = self.underlying.doc();
+        let score = self.underlying.score();
+        self.expression.evaluate(doc, score) * self.boost
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ExpressionScoreQuery;
+    use crate::collector::{Count, TopDocs};
+    use crate::query::{Query, ScoreExpression, TermQuery};
+    use crate::schema::{IndexRecordOption, Schema, FAST, STRING, TEXT};
+    use crate::{assert_nearly_equals, doc, DocAddress, Index, IndexWriter, TantivyError, Term};
+
+    #[test]
+    fn test_expression_score_query() -> crate::Result<()> {
+        let mut schema_builder = Schema::builder();
+        let text = schema_builder.add_text_field("text", TEXT);
+        let popularity = schema_builder.add_u64_field("popularity", FAST);
+        let freshness = schema_builder.add_f64_field("freshness", FAST);
+        let attributes = schema_builder.add_json_field("attributes", FAST);
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer: IndexWriter = index.writer_for_tests()?;
+        index_writer.add_document(doc!(
+            text => "apple",
+            popularity => 3u64,
+            freshness => 1.0f64,
+            attributes => serde_json::json!({"boost": 2}),
+        ))?;
+        // Missing values count as 0.
+        index_writer.add_document(doc!(text => "apple", popularity => 0u64))?;
+        index_writer.add_document(doc!(text => "pear", popularity => 100u64))?;
+        index_writer.commit()?;
+        let searcher = index.reader()?.searcher();
+
+        let term_query = TermQuery::new(
+            Term::from_field_text(text, "apple"),
+            IndexRecordOption::WithFreqs,
+        );
+        let bm25_score = searcher.search(&term_query, &TopDocs::with_limit(1))?[0].0;
+        let expression = ScoreExpression::parse(
+            "_score * log1p(popularity) + 0.3 * freshness + attributes.boost",
+        )?;
+        let query = ExpressionScoreQuery::new(Box::new(term_query), expression);
+        let top_docs = searcher.search(&query, &TopDocs::with_limit(10))?;
+        assert_eq!(top_docs.len(), 2);
+        assert_eq!(top_docs[0].1, DocAddress::new(0, 0));
+        assert_nearly_equals!(top_docs[0].0, bm25_score * 4f32.ln() + 0.3 + 2.0);
+        assert_eq!(top_docs[1], (0.0, DocAddress::new(0, 1)));
+        assert_eq!(searcher.search(&query, &Count)?, 2);
+
+        let explanation = query.explain(&searcher, DocAddress::new(0, 0))?;
+        assert_nearly_equals!(explanation.value(), top_docs[0].0);
+        Ok(())
+    }
+
+    #[test]
+    fn test_expression_score_query_requires_fast_fields() -> crate::Result<()> {
+        let mut schema_builder = Schema::builder();
+        let text = schema_builder.add_text_field("text", STRING);
+        let index = Index::create_in_ram(schema_builder.build());
+        let searcher = index.reader()?.searcher();
+        let term_query = TermQuery::new(
+            Term::from_field_text(text, "apple"),
+            IndexRecordOption::Basic,
+        );
+        for source in ["_score * text", "_score * missing"] {
+            let query = ExpressionScoreQuery::new(
+                Box::new(term_query.clone()),
+                ScoreExpression::parse(source)?,
+            );
+            assert!(matches!(
+                searcher.search(&query, &TopDocs::with_limit(1)),
+                Err(TantivyError::SchemaError(_))
+            ));
+        }
+        Ok(())
+    }
+}
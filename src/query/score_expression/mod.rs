@@ -0,0 +1,6 @@
+mod expression;
+mod expression_score_query;
+
+pub use self::expression::ScoreExpression;
+pub(crate) use self::expression::SegmentScoreExpression;
+pub use self::expression_score_query::ExpressionScoreQuery;
@@ -1,4 +1,4 @@
-use std::ops::DerefMut;
+use std::ops::{Deref, DerefMut};
 
 use downcast_rs::impl_downcast;
 
@@ -13,6 +13,21 @@ pub trait Scorer: downcast_rs::Downcast + DocSet + 'static {
     ///
     /// This method will perform a bit of computation and is not cached.
     fn score(&mut self) -> Score;
+
+    /// Returns an upper bound of the scores of the documents of this scorer.
+    ///
+    /// The default implementation returns `Score::INFINITY`, which disables the pruning based on
+    /// the scores for this scorer.
+    fn max_score(&self) -> Score {
+        Score::INFINITY
+    }
+
+    /// Informs the scorer that the documents whose score is lower than or equal to `min_score`
+    /// will be ignored by the caller.
+    ///
+    /// The scorer may then skip these documents while advancing, but it never has to: scoring a
+    /// document below `min_score` is still correct.
+    fn set_min_competitive_score(&mut self, _min_score: Score) {}
 }
 
 impl_downcast!(Scorer);
@@ -21,4 +36,12 @@ impl Scorer for Box<dyn Scorer> {
     fn score(&mut self) -> Score {
         self.deref_mut().score()
     }
+
+    fn max_score(&self) -> Score {
+        self.deref().max_score()
+    }
+
+    fn set_min_competitive_score(&mut self, min_score: Score) {
+        self.deref_mut().set_min_competitive_score(min_score);
+    }
 }
@@ -1,4 +1,4 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 
 use tantivy_fst::raw::CompiledAddr;
 use tantivy_fst::{Automaton, Map};
@@ -80,6 +80,12 @@ impl Query for TermSetQuery {
             }
         }
     }
+
+    fn cache_key(&self) -> Option<String> {
+        // The fields are sorted, so that the key does not depend on the order of the hash map.
+        let terms_map: BTreeMap<&Field, &Vec<Term>> = self.terms_map.iter().collect();
+        Some(format!("TermSetQuery({terms_map:?})"))
+    }
 }
 
 struct SetDfaWrapper(Map<Vec<u8>>);
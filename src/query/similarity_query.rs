@@ -0,0 +1,317 @@
+use std::fmt;
+
+use crate::query::{
+    Bm25StatisticsProvider, ConstScoreQuery, EnableScoring, Explanation, Query, Scorer, Weight,
+};
+use crate::schema::{Bm25Options, Field};
+use crate::{DocId, Score, SegmentReader, Term};
+
+/// The scoring model used by a [`SimilarityQuery`] for the queries it wraps.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Similarity {
+    /// BM25 with the given parameters, for all of the fields, instead of the ones of the schema.
+    Bm25(Bm25Options),
+    /// The same score for every matching document.
+    Constant(Score),
+}
+
+/// `SimilarityQuery` is a wrapper over a query overriding the scoring model of the whole
+/// subtree of queries it wraps.
+///
+/// By default, the documents are scored with BM25, with the parameters set in the schema for
+/// each field. Overriding them at search time makes it possible to experiment with other
+/// parameters on a single clause, without reindexing.
+///
+/// The document set matched by the `SimilarityQuery` is strictly the same as the underlying
+/// query. A `SimilarityQuery` nested in another one overrides its similarity.
+///
+/// Block-max pruning relies on the maximum BM25 score of the blocks of the postings, which is
+/// computed at indexing time with the parameters of the schema. The term queries scored with
+/// other parameters bound the score of all of their blocks with the maximum score of the term
+/// instead, including when the `SimilarityQuery` is nested in a boolean query.
+///
+/// ```rust
+/// use tantivy::collector::TopDocs;
+/// use tantivy::query::{QueryParser, Similarity, SimilarityQuery};
+/// use tantivy::schema::{Bm25Options, Schema, TEXT};
+/// use tantivy::{doc, Index, IndexWriter};
+///
+/// # fn main() -> tantivy::Result<()> {
+/// let mut schema_builder = Schema::builder();
+/// let title = schema_builder.add_text_field("title", TEXT);
+/// let index = Index::create_in_ram(schema_builder.build());
+/// let mut index_writer: IndexWriter = index.writer(15_000_000)?;
+/// index_writer.add_document(doc!(title => "Wind"))?;
+/// index_writer.add_document(doc!(title => "The Wind in the Willows and the wind in the trees"))?;
+/// index_writer.commit()?;
+/// let searcher = index.reader()?.searcher();
+///
+/// let query = QueryParser::for_index(&index, vec![title]).parse_query("wind")?;
+/// // Without length normalization, the longer title wins thanks to its two occurrences.
+/// let query = SimilarityQuery::new(query, Similarity::Bm25(Bm25Options::new(1.2, 0.0)));
+/// let top_docs = searcher.search(&query, &TopDocs::with_limit(1))?;
+/// assert_eq!(top_docs[0].1.doc_id, 1);
+/// # Ok(())
+/// # }
+/// ```
+pub struct SimilarityQuery {
+    query: Box<dyn Query>,
+    similarity: Similarity,
+}
+
+impl SimilarityQuery {
+    /// Builds a similarity query.
+    pub fn new(query: Box<dyn Query>, similarity: Similarity) -> SimilarityQuery {
+        SimilarityQuery { query, similarity }
+    }
+
+    /// Returns the similarity used for the wrapped query.
+    pub fn similarity(&self) -> Similarity {
+        self.similarity
+    }
+}
+
+impl Clone for SimilarityQuery {
+    fn clone(&self) -> Self {
+        SimilarityQuery {
+            query: self.query.box_clone(),
+            similarity: self.similarity,
+        }
+    }
+}
+
+impl fmt::Debug for SimilarityQuery {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "Similarity(similarity={:?}, query={:?})",
+            self.similarity, self.query
+        )
+    }
+}
+
+impl Query for SimilarityQuery {
+    fn weight(&self, enable_scoring: EnableScoring<'_>) -> crate::Result<Box<dyn Weight>> {
+        let EnableScoring::Enabled {
+            searcher,
+            statistics_provider,
+        } = enable_scoring
+        else {
+            return self.query.weight(enable_scoring);
+        };
+        let weight = match self.similarity {
+            Similarity::Bm25(bm25_options) => {
+                let statistics_provider = Bm25OptionsOverride {
+                    statistics_provider,
+                    bm25_options,
+                };
+                self.query
+                    .weight(EnableScoring::enabled_from_statistics_provider(
+                        &statistics_provider,
+                        searcher,
+                    ))?
+            }
+            Similarity::Constant(score) => {
+                ConstScoreQuery::new(self.query.box_clone(), score).weight(enable_scoring)?
+            }
+        };
+        Ok(Box::new(SimilarityWeight { weight }))
+    }
+
+    fn query_terms<'a>(&'a self, visitor: &mut dyn FnMut(&'a Term, bool)) {
+        self.query.query_terms(visitor);
+    }
+
+    fn query_phrases<'a>(&'a self, visitor: &mut dyn FnMut(&'a [(usize, Term)], u32)) {
+        self.query.query_phrases(visitor);
+    }
+
+    fn cache_key(&self) -> Option<String> {
+        Some(format!(
+            "Similarity(similarity={:?}, query={})",
+            self.similarity,
+            self.query.cache_key()?
+        ))
+    }
+}
+
+/// Statistics provider returning the same BM25 parameters for all of the fields.
+struct Bm25OptionsOverride<'a> {
+    statistics_provider: &'a dyn Bm25StatisticsProvider,
+    bm25_options: Bm25Options,
+}
+
+impl<'a> Bm25StatisticsProvider for Bm25OptionsOverride<'a> {
+    fn total_num_tokens(&self, field: Field) -> crate::Result<u64> {
+        self.statistics_provider.total_num_tokens(field)
+    }
+
+    fn total_num_docs(&self) -> crate::Result<u64> {
+        self.statistics_provider.total_num_docs()
+    }
+
+    fn doc_freq(&self, term: &Term) -> crate::Result<u64> {
+        self.statistics_provider.doc_freq(term)
+    }
+
+    fn bm25_options(&self, _field: Field) -> Bm25Options {
+        self.bm25_options
+    }
+}
+
+/// Weight of the wrapped query, for which `for_each_pruning` falls back to the default
+/// implementation, as the block-max scores do not help pruning anyway.
+struct SimilarityWeight {
+    weight: Box<dyn Weight>,
+}
+
+impl Weight for SimilarityWeight {
+    fn scorer(&self, reader: &SegmentReader, boost: Score) -> crate::Result<Box<dyn Scorer>> {
+        self.weight.scorer(reader, boost)
+    }
+
+    fn explain(&self, reader: &SegmentReader, doc: DocId) -> crate::Result<Explanation> {
+        self.weight.explain(reader, doc)
+    }
+
+    fn count(&self, reader: &SegmentReader) -> crate::Result<u32> {
+        self.weight.count(reader)
+    }
+
+    fn for_each(
+        &self,
+        reader: &SegmentReader,
+        callback: &mut dyn FnMut(DocId, Score),
+    ) -> crate::Result<()> {
+        self.weight.for_each(reader, callback)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Similarity, SimilarityQuery};
+    use crate::collector::{Count, TopDocs};
+    use crate::query::{BooleanQuery, EnableScoring, Occur, Query, TermQuery};
+    use crate::schema::{
+        Bm25Options, IndexRecordOption, Schema, TextFieldIndexing, TextOptions, TEXT,
+    };
+    use crate::{assert_nearly_equals, doc, DocAddress, Index, IndexWriter, Term};
+
+    #[test]
+    fn test_similarity_query() -> crate::Result<()> {
+        let mut schema_builder = Schema::builder();
+        let text_options = TextOptions::default().set_indexing_options(
+            TextFieldIndexing::default()
+                .set_index_option(IndexRecordOption::WithFreqs)
+                .set_bm25(Bm25Options::new(2.0, 0.0)),
+        );
+        let tuned = schema_builder.add_text_field("tuned", text_options);
+        let text = schema_builder.add_text_field("text", TEXT);
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer: IndexWriter = index.writer_for_tests()?;
+        index_writer.add_document(doc!(text => "apple", tuned => "apple"))?;
+        index_writer.add_document(doc!(text => "apple apple pie pie pie", tuned => "pear"))?;
+        index_writer.add_document(doc!(text => "pear", tuned => "pear pie"))?;
+        index_writer.commit()?;
+        let searcher = index.reader()?.searcher();
+        let term_query = |field, token: &str| -> Box<dyn Query> {
+            Box::new(TermQuery::new(
+                Term::from_field_text(field, token),
+                IndexRecordOption::WithFreqs,
+            ))
+        };
+        let top_docs = |query: &dyn Query| searcher.search(query, &TopDocs::with_limit(10));
+
+        // The short document wins with the default parameters, the one with two occurrences
+        // wins without length normalization.
+        assert_eq!(
+            top_docs(term_query(text, "apple").as_ref())?[0].1,
+            DocAddress::new(0, 0)
+        );
+        let no_length_normalization = Similarity::Bm25(Bm25Options::new(1.2, 0.0));
+        let query = SimilarityQuery::new(term_query(text, "apple"), no_length_normalization);
+        let similarity_top_docs = top_docs(&query)?;
+        assert_eq!(similarity_top_docs.len(), 2);
+        assert_eq!(similarity_top_docs[0].1, DocAddress::new(0, 1));
+        let explanation = query.explain(&searcher, DocAddress::new(0, 1))?;
+        assert_nearly_equals!(explanation.value(), similarity_top_docs[0].0);
+
+        // The parameters of the schema are overridden as well.
+        let default_similarity = Similarity::Bm25(Bm25Options::default());
+        let tuned_query = SimilarityQuery::new(term_query(tuned, "pear"), default_similarity);
+        let tuned_top_docs = top_docs(&tuned_query)?;
+        assert_eq!(tuned_top_docs.len(), 2);
+        assert_eq!(tuned_top_docs[0].1, DocAddress::new(0, 1));
+        assert!(tuned_top_docs[0].0 > tuned_top_docs[1].0);
+        let schema_top_docs = top_docs(term_query(tuned, "pear").as_ref())?;
+        assert_nearly_equals!(schema_top_docs[0].0, schema_top_docs[1].0);
+
+        // Only the wrapped clause is affected.
+        let query = BooleanQuery::new(vec![
+            (Occur::Should, term_query(text, "pie")),
+            (
+                Occur::Should,
+                Box::new(SimilarityQuery::new(
+                    term_query(text, "pear"),
+                    Similarity::Constant(10.0),
+                )),
+            ),
+        ]);
+        let boolean_top_docs = top_docs(&query)?;
+        assert_eq!(boolean_top_docs[0], (10.0, DocAddress::new(0, 2)));
+        assert_eq!(
+            boolean_top_docs[1].0,
+            top_docs(term_query(text, "pie").as_ref())?[0].0
+        );
+        assert_eq!(searcher.search(&query, &Count)?, 2);
+        Ok(())
+    }
+
+    #[test]
+    fn test_similarity_query_nested_block_wand() -> crate::Result<()> {
+        let mut schema_builder = Schema::builder();
+        let text = schema_builder.add_text_field("text", TEXT);
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer: IndexWriter = index.writer_for_tests()?;
+        for i in 0..2_000usize {
+            // Short documents with a single occurrence, and long ones with many occurrences,
+            // the latter being the best ones without length normalization.
+            let (num_occurrences, num_fillers) = if i % 7 == 0 { (1 + i % 5, 0) } else { (8, 200) };
+            let mut text_value = "a ".repeat(num_occurrences) + &"x ".repeat(num_fillers);
+            if i % 3 == 0 {
+                text_value += &"b ".repeat(1 + i % 4);
+            }
+            index_writer.add_document(doc!(text => text_value))?;
+        }
+        index_writer.commit()?;
+        let searcher = index.reader()?.searcher();
+        let term_query = |token: &str| -> Box<dyn Query> {
+            Box::new(TermQuery::new(
+                Term::from_field_text(text, token),
+                IndexRecordOption::WithFreqs,
+            ))
+        };
+        let query = BooleanQuery::new(vec![
+            (
+                Occur::Should,
+                Box::new(SimilarityQuery::new(
+                    term_query("a"),
+                    Similarity::Bm25(Bm25Options::new(5.0, 0.0)),
+                )),
+            ),
+            (Occur::Should, term_query("b")),
+        ]);
+        let weight = query.weight(EnableScoring::enabled_from_searcher(&searcher))?;
+        let mut all_scores = Vec::new();
+        for segment_reader in searcher.segment_readers() {
+            weight.for_each(segment_reader, &mut |_, score| all_scores.push(score))?;
+        }
+        all_scores.sort_by(|left, right| right.partial_cmp(left).unwrap());
+        let top_docs = searcher.search(&query, &TopDocs::with_limit(20))?;
+        assert_eq!(top_docs.len(), 20);
+        for ((score, _), expected_score) in top_docs.iter().zip(&all_scores) {
+            assert_nearly_equals!(*score, *expected_score);
+        }
+        Ok(())
+    }
+}
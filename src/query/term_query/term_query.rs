@@ -3,7 +3,7 @@ use std::fmt;
 use super::term_weight::TermWeight;
 use crate::query::bm25::Bm25Weight;
 use crate::query::{EnableScoring, Explanation, Query, Weight};
-use crate::schema::IndexRecordOption;
+use crate::schema::{Bm25Options, IndexRecordOption};
 use crate::Term;
 
 /// A Term query matches all of the documents
@@ -100,9 +100,11 @@ impl TermQuery {
                 statistics_provider,
                 ..
             } => Bm25Weight::for_terms(statistics_provider, &[self.term.clone()])?,
-            EnableScoring::Disabled { .. } => {
-                Bm25Weight::new(Explanation::new("<no score>", 1.0f32), 1.0f32)
-            }
+            EnableScoring::Disabled { .. } => Bm25Weight::new(
+                Explanation::new("<no score>", 1.0f32),
+                1.0f32,
+                Bm25Options::default(),
+            ),
         };
         let scoring_enabled = enable_scoring.is_scoring_enabled();
         let index_record_option = if scoring_enabled {
@@ -127,6 +129,13 @@ impl Query for TermQuery {
     fn query_terms<'a>(&'a self, visitor: &mut dyn FnMut(&'a Term, bool)) {
         visitor(&self.term, false);
     }
+
+    fn cache_key(&self) -> Option<String> {
+        Some(format!(
+            "TermQuery({:?}, {:?})",
+            self.term, self.index_record_option
+        ))
+    }
 }
 
 #[cfg(test)]
@@ -10,6 +10,8 @@ pub struct TermScorer {
     postings: SegmentPostings,
     fieldnorm_reader: FieldNormReader,
     similarity_weight: Bm25Weight,
+    // False if the block-max metadata of the postings was computed with other BM25 parameters.
+    block_max_scores_valid: bool,
 }
 
 impl TermScorer {
@@ -22,9 +24,17 @@ impl TermScorer {
             postings,
             fieldnorm_reader,
             similarity_weight,
+            block_max_scores_valid: true,
         }
     }
 
+    /// Makes [`TermScorer::block_max_score()`] return the maximum score of the term, for
+    /// scorers whose BM25 parameters differ from the ones the postings were written with.
+    pub(crate) fn without_block_max_scores(mut self) -> TermScorer {
+        self.block_max_scores_valid = false;
+        self
+    }
+
     pub(crate) fn shallow_seek(&mut self, target_doc: DocId) {
         self.postings.block_cursor.shallow_seek(target_doc);
     }
@@ -68,7 +78,13 @@ impl TermScorer {
     /// specific is achieved on a different document.
     ///
     /// (The result is on the other hand guaranteed to be correct if there is only one segment).
+    ///
+    /// If the BM25 parameters of the scorer are not the ones of the schema, the stored pair may
+    /// not be the one maximizing the score, and the maximum score of the term is returned.
     pub fn block_max_score(&mut self) -> Score {
+        if !self.block_max_scores_valid {
+            return self.max_score();
+        }
         self.postings
             .block_cursor
             .block_max_score(&self.fieldnorm_reader, &self.similarity_weight)
@@ -121,6 +137,10 @@ impl Scorer for TermScorer {
         let term_freq = self.term_freq();
         self.similarity_weight.score(fieldnorm_id, term_freq)
     }
+
+    fn max_score(&self) -> Score {
+        TermScorer::max_score(self)
+    }
 }
 
 #[cfg(test)]
@@ -127,18 +127,19 @@ impl TermWeight {
         let similarity_weight = self.similarity_weight.boost_by(boost);
         let postings_opt: Option<SegmentPostings> =
             inverted_index.read_postings(&self.term, self.index_record_option)?;
-        if let Some(segment_postings) = postings_opt {
-            Ok(TermScorer::new(
-                segment_postings,
-                fieldnorm_reader,
-                similarity_weight,
-            ))
-        } else {
-            Ok(TermScorer::new(
-                SegmentPostings::empty(),
-                fieldnorm_reader,
-                similarity_weight,
-            ))
+        let term_scorer = TermScorer::new(
+            postings_opt.unwrap_or_else(SegmentPostings::empty),
+            fieldnorm_reader,
+            similarity_weight,
+        );
+        let schema_bm25_options = reader
+            .schema()
+            .get_field_entry(field)
+            .field_type()
+            .bm25_options();
+        if self.similarity_weight.options() != schema_bm25_options {
+            return Ok(term_scorer.without_block_max_scores());
         }
+        Ok(term_scorer)
     }
 }
@@ -34,6 +34,7 @@ pub struct Union<TScorer, TScoreCombiner = DoNothingCombiner> {
     offset: DocId,
     doc: DocId,
     score: Score,
+    max_score: Score,
 }
 
 fn refill<TScorer: Scorer, TScoreCombiner: ScoreCombiner>(
@@ -70,14 +71,19 @@ impl<TScorer: Scorer, TScoreCombiner: ScoreCombiner> Union<TScorer, TScoreCombin
             .into_iter()
             .filter(|docset| docset.doc() != TERMINATED)
             .collect();
+        let score_combiner = score_combiner_fn();
+        // Computed upfront, as the docsets are dropped once they are consumed.
+        let max_scores: Vec<Score> = non_empty_docsets.iter().map(Scorer::max_score).collect();
+        let max_score = score_combiner.max_score(&max_scores);
         let mut union = Union {
             docsets: non_empty_docsets,
             bitsets: Box::new([TinySet::empty(); HORIZON_NUM_TINYBITSETS]),
-            scores: Box::new([score_combiner_fn(); HORIZON as usize]),
+            scores: Box::new([score_combiner; HORIZON as usize]),
             cursor: HORIZON_NUM_TINYBITSETS,
             offset: 0,
             doc: 0,
             score: 0.0,
+            max_score,
         };
         if union.refill() {
             union.advance();
@@ -207,6 +213,10 @@ where
             .unwrap_or(0u32)
     }
 
+    fn cost(&self) -> u64 {
+        self.docsets.iter().map(|docset| docset.cost()).sum()
+    }
+
     fn count_including_deleted(&mut self) -> u32 {
         if self.doc == TERMINATED {
             return 0;
@@ -238,6 +248,10 @@ where
     fn score(&mut self) -> Score {
         self.score
     }
+
+    fn max_score(&self) -> Score {
+        self.max_score
+    }
 }
 
 #[cfg(test)]
@@ -1,3 +1,5 @@
+use downcast_rs::impl_downcast;
+
 use super::Scorer;
 use crate::docset::COLLECT_BLOCK_BUFFER_LEN;
 use crate::index::SegmentReader;
@@ -44,16 +46,29 @@ pub(crate) fn for_each_docset_buffered<T: DocSet + ?Sized>(
 ///
 /// More importantly, it makes it possible for scorers to implement
 /// important optimization (e.g. BlockWAND for union).
+///
+/// The threshold is passed down to the scorer with [`Scorer::set_min_competitive_score`] each
+/// time it increases, and the iteration stops as soon as it reaches the
+/// [`Scorer::max_score`] of the scorer.
 pub(crate) fn for_each_pruning_scorer<TScorer: Scorer + ?Sized>(
     scorer: &mut TScorer,
     mut threshold: Score,
     callback: &mut dyn FnMut(DocId, Score) -> Score,
 ) {
+    let max_score = scorer.max_score();
+    if max_score <= threshold {
+        return;
+    }
+    scorer.set_min_competitive_score(threshold);
     let mut doc = scorer.doc();
     while doc != TERMINATED {
         let score = scorer.score();
         if score > threshold {
             threshold = callback(doc, score);
+            if max_score <= threshold {
+                return;
+            }
+            scorer.set_min_competitive_score(threshold);
         }
         doc = scorer.advance();
     }
@@ -63,7 +78,7 @@ pub(crate) fn for_each_pruning_scorer<TScorer: Scorer + ?Sized>(
 /// for a given set of segments.
 ///
 /// See [`Query`](crate::query::Query).
-pub trait Weight: Send + Sync + 'static {
+pub trait Weight: downcast_rs::Downcast + Send + Sync + 'static {
     /// Returns the scorer for the given segment.
     ///
     /// `boost` is a multiplier to apply to the score.
@@ -131,3 +146,5 @@ pub trait Weight: Send + Sync + 'static {
         Ok(())
     }
 }
+
+impl_downcast!(Weight);
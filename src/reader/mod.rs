@@ -1,14 +1,21 @@
+mod query_cache;
 mod warming;
 
+use std::fmt;
+use std::num::NonZeroUsize;
 use std::sync::atomic::AtomicU64;
 use std::sync::{atomic, Arc, Weak};
 
 use arc_swap::ArcSwap;
 pub use warming::Warmer;
 
+use self::query_cache::QueryCache;
 use self::warming::WarmingState;
+use crate::collector::Collector;
 use crate::core::searcher::{SearcherGeneration, SearcherInner};
 use crate::directory::{Directory, WatchCallback, WatchHandle, META_LOCK};
+use crate::fastfield::GlobalOrdinalsCache;
+use crate::query::Query;
 use crate::store::DOCSTORE_CACHE_CAPACITY;
 use crate::{Index, Inventory, Searcher, SegmentReader, TrackedObject};
 
@@ -37,6 +44,7 @@ pub enum ReloadPolicy {
 /// - [`Warmer`] implementations
 /// - number of warming threads, for parallelizing warming work
 /// - The cache size of the underlying doc store readers.
+/// - The size of the cache of the search results.
 #[derive(Clone)]
 pub struct IndexReaderBuilder {
     reload_policy: ReloadPolicy,
@@ -44,6 +52,7 @@ pub struct IndexReaderBuilder {
     warmers: Vec<Weak<dyn Warmer>>,
     num_warming_threads: usize,
     doc_store_cache_num_blocks: usize,
+    query_cache_num_entries: usize,
 }
 
 impl IndexReaderBuilder {
@@ -55,6 +64,7 @@ impl IndexReaderBuilder {
             warmers: Vec::new(),
             num_warming_threads: 1,
             doc_store_cache_num_blocks: DOCSTORE_CACHE_CAPACITY,
+            query_cache_num_entries: 0,
         }
     }
 
@@ -75,6 +85,7 @@ impl IndexReaderBuilder {
             self.index,
             warming_state,
             searcher_generation_inventory,
+            NonZeroUsize::new(self.query_cache_num_entries).map(QueryCache::new),
         )?;
         let inner_reader_arc = Arc::new(inner_reader);
         let watch_handle_opt: Option<WatchHandle> = match self.reload_policy {
@@ -126,6 +137,15 @@ impl IndexReaderBuilder {
         self
     }
 
+    /// Sets the number of search results cached by [`IndexReader::search_cached`].
+    ///
+    /// The cache is disabled by default.
+    #[must_use]
+    pub fn query_cache_num_entries(mut self, query_cache_num_entries: usize) -> IndexReaderBuilder {
+        self.query_cache_num_entries = query_cache_num_entries;
+        self
+    }
+
     /// Set the [`Warmer`]s that are invoked when reloading searchable segments.
     #[must_use]
     pub fn warmers(mut self, warmers: Vec<Weak<dyn Warmer>>) -> IndexReaderBuilder {
@@ -159,6 +179,8 @@ struct InnerIndexReader {
     searcher: arc_swap::ArcSwap<SearcherInner>,
     searcher_generation_counter: Arc<AtomicU64>,
     searcher_generation_inventory: Inventory<SearcherGeneration>,
+    query_cache: Option<QueryCache>,
+    global_ordinals_cache: Arc<GlobalOrdinalsCache>,
 }
 
 impl InnerIndexReader {
@@ -169,8 +191,10 @@ impl InnerIndexReader {
         // The searcher_generation_inventory is not used as source, but as target to track the
         // loaded segments.
         searcher_generation_inventory: Inventory<SearcherGeneration>,
+        query_cache: Option<QueryCache>,
     ) -> crate::Result<Self> {
         let searcher_generation_counter: Arc<AtomicU64> = Default::default();
+        let global_ordinals_cache: Arc<GlobalOrdinalsCache> = Default::default();
 
         let searcher = Self::create_searcher(
             &index,
@@ -178,6 +202,7 @@ impl InnerIndexReader {
             &warming_state,
             &searcher_generation_counter,
             &searcher_generation_inventory,
+            &global_ordinals_cache,
         )?;
         Ok(InnerIndexReader {
             doc_store_cache_num_blocks,
@@ -186,6 +211,8 @@ impl InnerIndexReader {
             searcher: ArcSwap::from(searcher),
             searcher_generation_counter,
             searcher_generation_inventory,
+            query_cache,
+            global_ordinals_cache,
         })
     }
     /// Opens the freshest segments [`SegmentReader`].
@@ -220,8 +247,13 @@ impl InnerIndexReader {
         warming_state: &WarmingState,
         searcher_generation_counter: &Arc<AtomicU64>,
         searcher_generation_inventory: &Inventory<SearcherGeneration>,
+        global_ordinals_cache: &Arc<GlobalOrdinalsCache>,
     ) -> crate::Result<Arc<SearcherInner>> {
         let segment_readers = Self::open_segment_readers(index)?;
+        index
+            .metrics()
+            .searcher_segments
+            .set(segment_readers.len() as i64);
         let searcher_generation = Self::track_segment_readers_in_inventory(
             &segment_readers,
             searcher_generation_counter,
@@ -235,6 +267,7 @@ impl InnerIndexReader {
             segment_readers,
             searcher_generation,
             doc_store_cache_num_blocks,
+            global_ordinals_cache.clone(),
         )?);
 
         warming_state.warm_new_searcher_generation(&searcher.clone().into())?;
@@ -248,8 +281,13 @@ impl InnerIndexReader {
             &self.warming_state,
             &self.searcher_generation_counter,
             &self.searcher_generation_inventory,
+            &self.global_ordinals_cache,
         )?;
 
+        if let Some(query_cache) = &self.query_cache {
+            let new_searcher = Searcher::from(searcher.clone());
+            query_cache.retain_segments(new_searcher.generation().segments());
+        }
         self.searcher.store(searcher);
 
         Ok(())
@@ -301,4 +339,28 @@ impl IndexReader {
     pub fn searcher(&self) -> Searcher {
         self.inner.searcher()
     }
+
+    /// Searches `query` with `collector` on `searcher`, like [`Searcher::search`], reusing the
+    /// result of the same search if it is in the cache configured with
+    /// [`IndexReaderBuilder::query_cache_num_entries`].
+    ///
+    /// Two searches are the same if their queries have the same [cache key](Query::cache_key),
+    /// if their collectors have the same type and debug representation, and if they run on the
+    /// same segments: a commit changing the segments, or their deletes, hence invalidates the
+    /// results cached before it. The results of queries without a cache key are not cached.
+    pub fn search_cached<C>(
+        &self,
+        searcher: &Searcher,
+        query: &dyn Query,
+        collector: &C,
+    ) -> crate::Result<C::Fruit>
+    where
+        C: Collector + fmt::Debug + 'static,
+        C::Fruit: Clone + Sync,
+    {
+        match &self.inner.query_cache {
+            Some(query_cache) => query_cache.search(searcher, query, collector),
+            None => searcher.search(query, collector),
+        }
+    }
 }
@@ -0,0 +1,249 @@
+use std::any::{Any, TypeId};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::num::NonZeroUsize;
+use std::sync::Mutex;
+
+use lru::LruCache;
+
+use crate::collector::Collector;
+use crate::index::SegmentId;
+use crate::query::Query;
+use crate::{Opstamp, Searcher};
+
+#[derive(Clone, PartialEq, Eq, Hash)]
+struct QueryCacheKey {
+    query: String,
+    collector_type: TypeId,
+    collector: String,
+    /// The segments searched, with their delete opstamp.
+    segments: BTreeMap<SegmentId, Option<Opstamp>>,
+}
+
+/// LRU cache of the results of searches, shared by the searchers of an
+/// [`IndexReader`](super::IndexReader).
+///
+/// A result is identified by the [cache key](Query::cache_key) of the query, the debug
+/// representation of the collector, and the segments it was computed on. Searching a different set
+/// of segments, or the same segments after some deletes, hence never returns a stale result.
+pub(crate) struct QueryCache {
+    entries: Mutex<LruCache<QueryCacheKey, Box<dyn Any + Send + Sync>>>,
+}
+
+impl QueryCache {
+    pub fn new(num_entries: NonZeroUsize) -> QueryCache {
+        QueryCache {
+            entries: Mutex::new(LruCache::new(num_entries)),
+        }
+    }
+
+    /// Searches `query` with `collector`, or returns the cached result of the same search.
+    ///
+    /// Queries without a cache key are always searched.
+    pub fn search<C>(
+        &self,
+        searcher: &Searcher,
+        query: &dyn Query,
+        collector: &C,
+    ) -> crate::Result<C::Fruit>
+    where
+        C: Collector + fmt::Debug + 'static,
+        C::Fruit: Clone + Sync,
+    {
+        let Some(query_key) = query.cache_key() else {
+            return searcher.search(query, collector);
+        };
+        let key = QueryCacheKey {
+            query: query_key,
+            collector_type: TypeId::of::<C>(),
+            collector: format!("{collector:?}"),
+            segments: searcher.generation().segments().clone(),
+        };
+        let metrics = searcher.index().metrics();
+        if let Some(fruit) = self.entries.lock().unwrap().get(&key) {
+            if let Some(fruit) = fruit.downcast_ref::<C::Fruit>() {
+                metrics.query_cache_hits.inc();
+                return Ok(fruit.clone());
+            }
+        }
+        metrics.query_cache_misses.inc();
+        // The lock is not held during the search: identical searches running concurrently all
+        // compute their result.
+        let fruit = searcher.search(query, collector)?;
+        self.entries
+            .lock()
+            .unwrap()
+            .put(key, Box::new(fruit.clone()));
+        Ok(fruit)
+    }
+
+    /// Removes the results computed on other segments than `segments`.
+    pub fn retain_segments(&self, segments: &BTreeMap<SegmentId, Option<Opstamp>>) {
+        let mut entries = self.entries.lock().unwrap();
+        let stale_keys: Vec<QueryCacheKey> = entries
+            .iter()
+            .filter(|(key, _)| &key.segments != segments)
+            .map(|(key, _)| key.clone())
+            .collect();
+        for key in stale_keys {
+            entries.pop(&key);
+        }
+    }
+
+    #[cfg(test)]
+    fn len(&self) -> usize {
+        self.entries.lock().unwrap().len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    use crate::collector::{Collector, Count, SegmentCollector};
+    use crate::query::{AllQuery, ExactKnnQuery, Query, QueryParser, RegexQuery};
+    use crate::schema::{Schema, TEXT};
+    use crate::{doc, DocId, Index, IndexWriter, ReloadPolicy, Score, SegmentReader};
+
+    /// Counts the documents, and the number of segments it was run on.
+    #[derive(Debug, Default)]
+    struct SpyCount {
+        num_segments_searched: Arc<AtomicUsize>,
+    }
+
+    impl Collector for SpyCount {
+        type Fruit = usize;
+        type Child = SpySegmentCount;
+
+        fn for_segment(
+            &self,
+            _segment_local_id: u32,
+            _reader: &SegmentReader,
+        ) -> crate::Result<SpySegmentCount> {
+            self.num_segments_searched.fetch_add(1, Ordering::SeqCst);
+            Ok(SpySegmentCount(0))
+        }
+
+        fn requires_scoring(&self) -> bool {
+            false
+        }
+
+        fn merge_fruits(&self, segment_counts: Vec<usize>) -> crate::Result<usize> {
+            Ok(segment_counts.into_iter().sum())
+        }
+    }
+
+    struct SpySegmentCount(usize);
+
+    impl SegmentCollector for SpySegmentCount {
+        type Fruit = usize;
+
+        fn collect(&mut self, _doc: DocId, _score: Score) {
+            self.0 += 1;
+        }
+
+        fn harvest(self) -> usize {
+            self.0
+        }
+    }
+
+    #[test]
+    fn test_query_cache() -> crate::Result<()> {
+        let mut schema_builder = Schema::builder();
+        let text = schema_builder.add_text_field("text", TEXT);
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer: IndexWriter = index.writer_for_tests()?;
+        index_writer.add_document(doc!(text => "apple"))?;
+        index_writer.add_document(doc!(text => "apple pie"))?;
+        index_writer.commit()?;
+        let reader = index
+            .reader_builder()
+            .reload_policy(ReloadPolicy::Manual)
+            .query_cache_num_entries(10)
+            .try_into()?;
+        let query_cache = reader.inner.query_cache.as_ref().unwrap();
+        let collector = SpyCount::default();
+        let num_segments_searched = || collector.num_segments_searched.load(Ordering::SeqCst);
+        let query_parser = QueryParser::for_index(&index, vec![text]);
+        let apple = query_parser.parse_query("apple")?;
+        let pie = query_parser.parse_query("pie")?;
+
+        let searcher = reader.searcher();
+        assert_eq!(
+            reader.search_cached(&searcher, apple.as_ref(), &collector)?,
+            2
+        );
+        assert_eq!(num_segments_searched(), 1);
+        assert_eq!(
+            reader.search_cached(&searcher, apple.as_ref(), &collector)?,
+            2
+        );
+        assert_eq!(num_segments_searched(), 1);
+        // The query and the collector are part of the key.
+        assert_eq!(
+            reader.search_cached(&searcher, pie.as_ref(), &collector)?,
+            1
+        );
+        assert_eq!(num_segments_searched(), 2);
+        assert_eq!(reader.search_cached(&searcher, apple.as_ref(), &Count)?, 2);
+        assert_eq!(query_cache.len(), 3);
+
+        // A new commit invalidates the results on reload.
+        index_writer.add_document(doc!(text => "apple tart"))?;
+        index_writer.commit()?;
+        reader.reload()?;
+        assert_eq!(query_cache.len(), 0);
+        let searcher = reader.searcher();
+        assert_eq!(
+            reader.search_cached(&searcher, apple.as_ref(), &collector)?,
+            3
+        );
+        assert_eq!(num_segments_searched(), 4);
+
+        // Deletes too.
+        index_writer.delete_all_documents()?;
+        index_writer.commit()?;
+        reader.reload()?;
+        let searcher = reader.searcher();
+        assert_eq!(reader.search_cached(&searcher, &AllQuery, &collector)?, 0);
+        Ok(())
+    }
+
+    #[test]
+    fn test_query_cache_key() -> crate::Result<()> {
+        let mut schema_builder = Schema::builder();
+        let text = schema_builder.add_text_field("text", TEXT);
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer: IndexWriter = index.writer_for_tests()?;
+        index_writer.add_document(doc!(text => "apple"))?;
+        index_writer.commit()?;
+        let reader = index
+            .reader_builder()
+            .query_cache_num_entries(10)
+            .try_into()?;
+        let query_cache = reader.inner.query_cache.as_ref().unwrap();
+        let collector = SpyCount::default();
+        let num_segments_searched = || collector.num_segments_searched.load(Ordering::SeqCst);
+
+        // The debug representation of the query omits its vector, unlike its key.
+        let query = ExactKnnQuery::new(text, vec![1.0, 0.0], 10);
+        let other_query = ExactKnnQuery::new(text, vec![0.0, 1.0], 10);
+        assert_eq!(format!("{query:?}"), format!("{other_query:?}"));
+        assert_ne!(query.cache_key(), other_query.cache_key());
+
+        // Queries without a key are not cached.
+        let regex_query = RegexQuery::from_pattern("app.*", text)?;
+        assert!(regex_query.cache_key().is_none());
+        let searcher = reader.searcher();
+        for expected_num_segments_searched in 1..=2 {
+            assert_eq!(
+                reader.search_cached(&searcher, &regex_query, &collector)?,
+                1
+            );
+            assert_eq!(num_segments_searched(), expected_num_segments_searched);
+        }
+        assert_eq!(query_cache.len(), 0);
+        Ok(())
+    }
+}
@@ -3,6 +3,7 @@ use std::ops::BitOr;
 use serde::{Deserialize, Serialize};
 
 use super::flags::{FastFlag, IndexedFlag, SchemaFlagList, StoredFlag};
+use super::DenseVectorOptions;
 /// Define how a bytes field should be handled by tantivy.
 #[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(from = "BytesOptionsDeser")]
@@ -11,6 +12,14 @@ pub struct BytesOptions {
     fieldnorms: bool,
     fast: bool,
     stored: bool,
+    #[serde(skip_serializing_if = "is_false")]
+    blob: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    dense_vector: Option<DenseVectorOptions>,
+}
+
+fn is_false(val: &bool) -> bool {
+    !val
 }
 
 /// For backward compatibility we add an intermediary to interpret the
@@ -25,6 +34,10 @@ struct BytesOptionsDeser {
     fieldnorms: Option<bool>,
     fast: bool,
     stored: bool,
+    #[serde(default)]
+    blob: bool,
+    #[serde(default)]
+    dense_vector: Option<DenseVectorOptions>,
 }
 
 impl From<BytesOptionsDeser> for BytesOptions {
@@ -34,6 +47,8 @@ impl From<BytesOptionsDeser> for BytesOptions {
             fieldnorms: deser.fieldnorms.unwrap_or(deser.indexed),
             fast: deser.fast,
             stored: deser.stored,
+            blob: deser.blob,
+            dense_vector: deser.dense_vector,
         }
     }
 }
@@ -63,6 +78,18 @@ impl BytesOptions {
         self.stored
     }
 
+    /// Returns true if the values are written to the segment blob file.
+    #[inline]
+    pub fn is_blob(&self) -> bool {
+        self.blob
+    }
+
+    /// Returns the options of the vectors of the field, if it is a dense vector field.
+    #[inline]
+    pub fn dense_vector_options(&self) -> Option<&DenseVectorOptions> {
+        self.dense_vector.as_ref()
+    }
+
     /// Set the field as indexed.
     ///
     /// Setting an integer as indexed will generate
@@ -101,6 +128,33 @@ impl BytesOptions {
         self.stored = true;
         self
     }
+
+    /// Set the field as a blob field.
+    ///
+    /// The values of blob fields are written as is to a dedicated file of the segment,
+    /// outside of the compressed doc store blocks. This is meant for large payloads
+    /// (attachments, images...), which can then be streamed with
+    /// [`Searcher::blobs()`](crate::Searcher::blobs) without being loaded into memory.
+    ///
+    /// Unless the field is also set as stored, its values are not part of the
+    /// documents returned by [`Searcher::doc()`](crate::Searcher::doc).
+    #[must_use]
+    pub fn set_blob(mut self) -> BytesOptions {
+        self.blob = true;
+        self
+    }
+
+    /// Set the field as a dense vector field.
+    ///
+    /// Each value of the field must then be a vector with the number of dimensions of
+    /// `dense_vector_options`, encoded with [`encode_vector`](crate::vector::encode_vector).
+    /// The vectors are written to the vector index of the segment, see
+    /// [vector](crate::vector).
+    #[must_use]
+    pub fn set_dense_vector(mut self, dense_vector_options: DenseVectorOptions) -> BytesOptions {
+        self.dense_vector = Some(dense_vector_options);
+        self
+    }
 }
 
 impl<T: Into<BytesOptions>> BitOr<T> for BytesOptions {
@@ -113,6 +167,8 @@ impl<T: Into<BytesOptions>> BitOr<T> for BytesOptions {
             fieldnorms: self.fieldnorms | other.fieldnorms,
             stored: self.stored | other.stored,
             fast: self.fast | other.fast,
+            blob: self.blob | other.blob,
+            dense_vector: self.dense_vector.or(other.dense_vector),
         }
     }
 }
@@ -130,6 +186,8 @@ impl From<FastFlag> for BytesOptions {
             fieldnorms: false,
             stored: false,
             fast: true,
+            blob: false,
+            dense_vector: None,
         }
     }
 }
@@ -141,6 +199,8 @@ impl From<StoredFlag> for BytesOptions {
             fieldnorms: false,
             stored: true,
             fast: false,
+            blob: false,
+            dense_vector: None,
         }
     }
 }
@@ -152,6 +212,8 @@ impl From<IndexedFlag> for BytesOptions {
             fieldnorms: true,
             stored: false,
             fast: false,
+            blob: false,
+            dense_vector: None,
         }
     }
 }
@@ -208,10 +270,27 @@ mod tests {
         assert!(!BytesOptions::default().is_fast());
         assert!(!BytesOptions::default().is_indexed());
         assert!(!BytesOptions::default().fieldnorms());
+        assert!(!BytesOptions::default().is_blob());
         assert!(BytesOptions::default().set_stored().is_stored());
         assert!(BytesOptions::default().set_fast().is_fast());
         assert!(BytesOptions::default().set_indexed().is_indexed());
         assert!(BytesOptions::default().set_fieldnorms().fieldnorms());
+        assert!(BytesOptions::default().set_blob().is_blob());
+    }
+
+    #[test]
+    fn test_bytes_options_blob_serialization() {
+        let bytes_options = BytesOptions::default().set_blob();
+        let json = serde_json::to_string(&bytes_options).unwrap();
+        assert_eq!(
+            json,
+            r#"{"indexed":false,"fieldnorms":false,"fast":false,"stored":false,"blob":true}"#
+        );
+        let bytes_options_deser: BytesOptions = serde_json::from_str(&json).unwrap();
+        assert_eq!(bytes_options_deser, bytes_options);
+        // The attribute is omitted for non blob fields.
+        let json = serde_json::to_string(&BytesOptions::default()).unwrap();
+        assert!(!json.contains("blob"));
     }
 
     #[test]
@@ -228,7 +307,9 @@ mod tests {
                 indexed: true,
                 fieldnorms: true,
                 fast: false,
-                stored: false
+                stored: false,
+                blob: false,
+                dense_vector: None,
             }
         );
     }
@@ -247,7 +328,9 @@ mod tests {
                 indexed: false,
                 fieldnorms: false,
                 fast: false,
-                stored: false
+                stored: false,
+                blob: false,
+                dense_vector: None,
             }
         );
     }
@@ -267,7 +350,9 @@ mod tests {
                 indexed: true,
                 fieldnorms: false,
                 fast: false,
-                stored: false
+                stored: false,
+                blob: false,
+                dense_vector: None,
             }
         );
     }
@@ -288,7 +373,9 @@ mod tests {
                 indexed: false,
                 fieldnorms: true,
                 fast: false,
-                stored: false
+                stored: false,
+                blob: false,
+                dense_vector: None,
             }
         );
     }
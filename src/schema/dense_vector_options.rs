@@ -0,0 +1,572 @@
+use serde::{Deserialize, Serialize};
+
+/// Metric used to compare the vectors of a dense vector field.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum VectorMetric {
+    /// Euclidean distance.
+    #[default]
+    L2,
+    /// Cosine similarity. The vectors are normalized when they are indexed.
+    Cosine,
+    /// Dot product, for vectors that are already normalized or for which the magnitude is
+    /// meaningful.
+    DotProduct,
+}
+
+/// Returns an error naming the first of the parameters of `index` lower than its minimum.
+///
+/// The setters of the options clamp the parameters, but deserialized options are checked
+/// instead, as out of range values would make building the index panic.
+fn check_min_values(index: &str, params: &[(&str, u32, u32)]) -> Result<(), String> {
+    for &(name, value, min_value) in params {
+        if value < min_value {
+            return Err(format!(
+                "`{name}` of the {index} options must be at least {min_value}, got {value}"
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Parameters of the HNSW graph of a dense vector field.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "HnswOptionsDeser")]
+pub struct HnswOptions {
+    m: u32,
+    ef_construction: u32,
+}
+
+#[derive(Deserialize)]
+struct HnswOptionsDeser {
+    m: u32,
+    ef_construction: u32,
+}
+
+impl TryFrom<HnswOptionsDeser> for HnswOptions {
+    type Error = String;
+
+    fn try_from(deser: HnswOptionsDeser) -> Result<HnswOptions, String> {
+        check_min_values(
+            "HNSW",
+            &[
+                ("m", deser.m, 2),
+                ("ef_construction", deser.ef_construction, 1),
+            ],
+        )?;
+        Ok(HnswOptions {
+            m: deser.m,
+            ef_construction: deser.ef_construction,
+        })
+    }
+}
+
+impl Default for HnswOptions {
+    fn default() -> Self {
+        HnswOptions {
+            m: 16,
+            ef_construction: 100,
+        }
+    }
+}
+
+impl HnswOptions {
+    /// Returns the maximum number of neighbors of a node on the upper layers of the graph.
+    /// The bottom layer allows twice as many.
+    pub fn m(&self) -> u32 {
+        self.m
+    }
+
+    /// Returns the number of candidates considered when linking a new node to the graph.
+    pub fn ef_construction(&self) -> u32 {
+        self.ef_construction
+    }
+
+    /// Sets the maximum number of neighbors of a node. Default is 16.
+    ///
+    /// Higher values improve the recall, at the cost of a larger and slower to build graph.
+    #[must_use]
+    pub fn set_m(mut self, m: u32) -> HnswOptions {
+        self.m = m.max(2);
+        self
+    }
+
+    /// Sets the number of candidates considered when linking a new node to the graph.
+    /// Default is 100.
+    #[must_use]
+    pub fn set_ef_construction(mut self, ef_construction: u32) -> HnswOptions {
+        self.ef_construction = ef_construction.max(1);
+        self
+    }
+}
+
+/// Parameters of the IVF-PQ index of a dense vector field.
+///
+/// The vectors are partitioned into lists around centroids (the inverted file), and
+/// the difference between each vector and its centroid is compressed with product
+/// quantization into one byte per subquantizer. The centroids and the codebooks are
+/// trained on the vectors of each segment, and stored in the segment.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "IvfPqOptionsDeser")]
+pub struct IvfPqOptions {
+    num_lists: u32,
+    num_subquantizers: u32,
+    num_probes: u32,
+}
+
+#[derive(Deserialize)]
+struct IvfPqOptionsDeser {
+    num_lists: u32,
+    num_subquantizers: u32,
+    num_probes: u32,
+}
+
+impl TryFrom<IvfPqOptionsDeser> for IvfPqOptions {
+    type Error = String;
+
+    fn try_from(deser: IvfPqOptionsDeser) -> Result<IvfPqOptions, String> {
+        check_min_values(
+            "IVF-PQ",
+            &[
+                ("num_lists", deser.num_lists, 1),
+                ("num_subquantizers", deser.num_subquantizers, 1),
+                ("num_probes", deser.num_probes, 1),
+            ],
+        )?;
+        Ok(IvfPqOptions {
+            num_lists: deser.num_lists,
+            num_subquantizers: deser.num_subquantizers,
+            num_probes: deser.num_probes,
+        })
+    }
+}
+
+impl Default for IvfPqOptions {
+    fn default() -> Self {
+        IvfPqOptions {
+            num_lists: 1_024,
+            num_subquantizers: 16,
+            num_probes: 16,
+        }
+    }
+}
+
+impl IvfPqOptions {
+    /// Returns the maximum number of lists of a segment.
+    pub fn num_lists(&self) -> u32 {
+        self.num_lists
+    }
+
+    /// Returns the number of subquantizers, that is the number of bytes of the code of a
+    /// vector.
+    pub fn num_subquantizers(&self) -> u32 {
+        self.num_subquantizers
+    }
+
+    /// Returns the number of lists searched by a query.
+    pub fn num_probes(&self) -> u32 {
+        self.num_probes
+    }
+
+    /// Sets the maximum number of lists of a segment. Default is 1024.
+    ///
+    /// A segment never has more lists than the square root of its number of vectors.
+    #[must_use]
+    pub fn set_num_lists(mut self, num_lists: u32) -> IvfPqOptions {
+        self.num_lists = num_lists.max(1);
+        self
+    }
+
+    /// Sets the number of subquantizers. Default is 16.
+    ///
+    /// More subquantizers give more precise distances, at the cost of larger codes. The
+    /// number is rounded down to a divisor of the number of dimensions.
+    #[must_use]
+    pub fn set_num_subquantizers(mut self, num_subquantizers: u32) -> IvfPqOptions {
+        self.num_subquantizers = num_subquantizers.max(1);
+        self
+    }
+
+    /// Sets the number of lists searched by a query. Default is 16.
+    ///
+    /// Higher values improve the recall, at the cost of a slower search.
+    #[must_use]
+    pub fn set_num_probes(mut self, num_probes: u32) -> IvfPqOptions {
+        self.num_probes = num_probes.max(1);
+        self
+    }
+}
+
+/// Parameters of the SPANN index of a dense vector field.
+///
+/// The vectors are partitioned into lists around centroids, and each list is stored on
+/// disk with the full vectors it holds. Only the centroids are held in memory, so that
+/// the index scales to vector sets much larger than the memory, and a query reads the
+/// lists of its closest centroids, one sequential read each. A vector close to the
+/// boundary between lists is stored in several of them, so that it is found whichever of
+/// them is probed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "SpannOptionsDeser")]
+pub struct SpannOptions {
+    list_size: u32,
+    num_replicas: u32,
+    num_probes: u32,
+}
+
+#[derive(Deserialize)]
+struct SpannOptionsDeser {
+    list_size: u32,
+    num_replicas: u32,
+    num_probes: u32,
+}
+
+impl TryFrom<SpannOptionsDeser> for SpannOptions {
+    type Error = String;
+
+    fn try_from(deser: SpannOptionsDeser) -> Result<SpannOptions, String> {
+        check_min_values(
+            "SPANN",
+            &[
+                ("list_size", deser.list_size, 1),
+                ("num_replicas", deser.num_replicas, 1),
+                ("num_probes", deser.num_probes, 1),
+            ],
+        )?;
+        Ok(SpannOptions {
+            list_size: deser.list_size,
+            num_replicas: deser.num_replicas,
+            num_probes: deser.num_probes,
+        })
+    }
+}
+
+impl Default for SpannOptions {
+    fn default() -> Self {
+        SpannOptions {
+            list_size: 128,
+            num_replicas: 2,
+            num_probes: 32,
+        }
+    }
+}
+
+impl SpannOptions {
+    /// Returns the average number of vectors of a list.
+    pub fn list_size(&self) -> u32 {
+        self.list_size
+    }
+
+    /// Returns the maximum number of lists a vector is stored in.
+    pub fn num_replicas(&self) -> u32 {
+        self.num_replicas
+    }
+
+    /// Returns the number of lists searched by a query.
+    pub fn num_probes(&self) -> u32 {
+        self.num_probes
+    }
+
+    /// Sets the average number of vectors of a list. Default is 128.
+    ///
+    /// Smaller lists make the centroids held in memory more numerous, and the reads of a
+    /// query smaller.
+    #[must_use]
+    pub fn set_list_size(mut self, list_size: u32) -> SpannOptions {
+        self.list_size = list_size.max(1);
+        self
+    }
+
+    /// Sets the maximum number of lists a vector is stored in. Default is 2.
+    ///
+    /// Storing the vectors close to the boundary between lists in several of them improves
+    /// the recall, at the cost of a larger index.
+    #[must_use]
+    pub fn set_num_replicas(mut self, num_replicas: u32) -> SpannOptions {
+        self.num_replicas = num_replicas.max(1);
+        self
+    }
+
+    /// Sets the number of lists searched by a query. Default is 32.
+    ///
+    /// Higher values improve the recall, at the cost of more data read by a query.
+    #[must_use]
+    pub fn set_num_probes(mut self, num_probes: u32) -> SpannOptions {
+        self.num_probes = num_probes.max(1);
+        self
+    }
+}
+
+/// Index built over the vectors of a dense vector field, in each segment.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum VectorIndexOptions {
+    /// Hierarchical navigable small world graph over the raw vectors.
+    Hnsw(HnswOptions),
+    /// Inverted file over product quantized vectors, for when the vectors of a segment do
+    /// not fit in memory. The raw vectors are only read to rescore the best candidates.
+    IvfPq(IvfPqOptions),
+    /// Lists of full precision vectors stored on disk around centroids held in memory, for
+    /// vector sets much larger than the memory. The distances are exact.
+    Spann(SpannOptions),
+}
+
+impl Default for VectorIndexOptions {
+    fn default() -> Self {
+        VectorIndexOptions::Hnsw(HnswOptions::default())
+    }
+}
+
+/// Quantization of the vectors an HNSW graph is searched with.
+///
+/// Only the vectors held in memory to search the graph are quantized. The raw vectors are
+/// still stored in full precision in the vector index file, as they are read to rescore the
+/// candidates and to build the graphs of merged segments: quantization makes the memory
+/// footprint of a segment smaller, not its files.
+///
+/// It only applies to the [`VectorIndexOptions::Hnsw`] index. The IVF-PQ index already
+/// quantizes its vectors, and SPANN reads its full precision vectors from disk: setting a
+/// quantization along with them is rejected.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum VectorQuantization {
+    /// The vectors are held in memory as `f32`.
+    #[default]
+    None,
+    /// Each dimension of the vectors is quantized to a byte, mapping the range of its values
+    /// in the segment to `[0, 255]`, which makes the vectors 4 times smaller.
+    Int8 {
+        /// If true, the candidates found in the graph are rescored with their full precision
+        /// vector, read from the vector index file, so that the distances are exact.
+        rescore: bool,
+    },
+}
+
+impl VectorQuantization {
+    fn is_none(&self) -> bool {
+        *self == VectorQuantization::None
+    }
+}
+
+/// Defines a bytes field as holding dense vectors of `f32`.
+///
+/// Each value of the field is a vector encoded with
+/// [`encode_vector`](crate::vector::encode_vector). The vectors are indexed for nearest
+/// neighbor search with a [`KnnQuery`](crate::query::KnnQuery).
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "DenseVectorOptionsDeser")]
+pub struct DenseVectorOptions {
+    dimensions: u32,
+    #[serde(default)]
+    metric: VectorMetric,
+    #[serde(default)]
+    index: VectorIndexOptions,
+    #[serde(default, skip_serializing_if = "VectorQuantization::is_none")]
+    quantization: VectorQuantization,
+}
+
+#[derive(Deserialize)]
+struct DenseVectorOptionsDeser {
+    dimensions: u32,
+    #[serde(default)]
+    metric: VectorMetric,
+    #[serde(default)]
+    index: VectorIndexOptions,
+    #[serde(default)]
+    quantization: VectorQuantization,
+}
+
+/// Returns an error if `quantization` does not apply to `index`.
+fn check_quantization(
+    index: &VectorIndexOptions,
+    quantization: VectorQuantization,
+) -> Result<(), String> {
+    if quantization.is_none() || matches!(index, VectorIndexOptions::Hnsw(_)) {
+        return Ok(());
+    }
+    Err(format!(
+        "Quantization {quantization:?} only applies to the HNSW index, not to {index:?}"
+    ))
+}
+
+impl TryFrom<DenseVectorOptionsDeser> for DenseVectorOptions {
+    type Error = String;
+
+    fn try_from(deser: DenseVectorOptionsDeser) -> Result<DenseVectorOptions, String> {
+        check_quantization(&deser.index, deser.quantization)?;
+        Ok(DenseVectorOptions {
+            dimensions: deser.dimensions,
+            metric: deser.metric,
+            index: deser.index,
+            quantization: deser.quantization,
+        })
+    }
+}
+
+impl DenseVectorOptions {
+    /// Creates the options of a field of vectors with `dimensions` dimensions, compared
+    /// with `metric`.
+    pub fn new(dimensions: u32, metric: VectorMetric) -> DenseVectorOptions {
+        DenseVectorOptions {
+            dimensions,
+            metric,
+            index: VectorIndexOptions::default(),
+            quantization: VectorQuantization::None,
+        }
+    }
+
+    /// Returns the number of dimensions of the vectors.
+    pub fn dimensions(&self) -> u32 {
+        self.dimensions
+    }
+
+    /// Returns the metric used to compare the vectors.
+    pub fn metric(&self) -> VectorMetric {
+        self.metric
+    }
+
+    /// Returns the options of the vector index.
+    pub fn index(&self) -> &VectorIndexOptions {
+        &self.index
+    }
+
+    /// Sets the options of the vector index.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a quantization is set and the index is not an HNSW index.
+    #[must_use]
+    pub fn set_index(mut self, index: VectorIndexOptions) -> DenseVectorOptions {
+        if let Err(msg) = check_quantization(&index, self.quantization) {
+            panic!("{msg}");
+        }
+        self.index = index;
+        self
+    }
+
+    /// Returns the quantization of the vectors the HNSW graph is searched with.
+    pub fn quantization(&self) -> VectorQuantization {
+        self.quantization
+    }
+
+    /// Sets the quantization of the vectors the HNSW graph is searched with. Default is
+    /// [`VectorQuantization::None`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if the quantization is not [`VectorQuantization::None`] and the index is not an
+    /// HNSW index.
+    #[must_use]
+    pub fn set_quantization(mut self, quantization: VectorQuantization) -> DenseVectorOptions {
+        if let Err(msg) = check_quantization(&self.index, quantization) {
+            panic!("{msg}");
+        }
+        self.quantization = quantization;
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dense_vector_options_serialization() {
+        let options = DenseVectorOptions::new(3, VectorMetric::Cosine)
+            .set_index(VectorIndexOptions::Hnsw(HnswOptions::default().set_m(8)));
+        let json = serde_json::to_string(&options).unwrap();
+        assert_eq!(
+            json,
+            r#"{"dimensions":3,"metric":"cosine","index":{"type":"hnsw","m":8,"ef_construction":100}}"#
+        );
+        let options_deser: DenseVectorOptions = serde_json::from_str(&json).unwrap();
+        assert_eq!(options_deser, options);
+        let options_deser: DenseVectorOptions =
+            serde_json::from_str(r#"{"dimensions":3}"#).unwrap();
+        assert_eq!(options_deser, DenseVectorOptions::new(3, VectorMetric::L2));
+
+        let options = DenseVectorOptions::new(8, VectorMetric::L2).set_index(
+            VectorIndexOptions::IvfPq(IvfPqOptions::default().set_num_probes(4)),
+        );
+        let json = serde_json::to_string(&options).unwrap();
+        assert_eq!(
+            json,
+            r#"{"dimensions":8,"metric":"l2","index":{"type":"ivf_pq","num_lists":1024,"num_subquantizers":16,"num_probes":4}}"#
+        );
+        let options_deser: DenseVectorOptions = serde_json::from_str(&json).unwrap();
+        assert_eq!(options_deser, options);
+
+        let options = DenseVectorOptions::new(8, VectorMetric::Cosine).set_index(
+            VectorIndexOptions::Spann(SpannOptions::default().set_list_size(64)),
+        );
+        let json = serde_json::to_string(&options).unwrap();
+        assert_eq!(
+            json,
+            r#"{"dimensions":8,"metric":"cosine","index":{"type":"spann","list_size":64,"num_replicas":2,"num_probes":32}}"#
+        );
+        let options_deser: DenseVectorOptions = serde_json::from_str(&json).unwrap();
+        assert_eq!(options_deser, options);
+
+        let options = DenseVectorOptions::new(2, VectorMetric::L2)
+            .set_quantization(VectorQuantization::Int8 { rescore: true });
+        let json = serde_json::to_string(&options).unwrap();
+        assert_eq!(
+            json,
+            r#"{"dimensions":2,"metric":"l2","index":{"type":"hnsw","m":16,"ef_construction":100},"quantization":{"type":"int8","rescore":true}}"#
+        );
+        let options_deser: DenseVectorOptions = serde_json::from_str(&json).unwrap();
+        assert_eq!(options_deser, options);
+    }
+
+    #[test]
+    fn test_dense_vector_options_deserialization_rejects_invalid_values() {
+        for index_json in [
+            r#"{"type":"hnsw","m":1,"ef_construction":100}"#,
+            r#"{"type":"hnsw","m":16,"ef_construction":0}"#,
+            r#"{"type":"ivf_pq","num_lists":0,"num_subquantizers":16,"num_probes":4}"#,
+            r#"{"type":"ivf_pq","num_lists":16,"num_subquantizers":0,"num_probes":4}"#,
+            r#"{"type":"ivf_pq","num_lists":16,"num_subquantizers":16,"num_probes":0}"#,
+            r#"{"type":"spann","list_size":0,"num_replicas":2,"num_probes":32}"#,
+            r#"{"type":"spann","list_size":64,"num_replicas":0,"num_probes":32}"#,
+            r#"{"type":"spann","list_size":64,"num_replicas":2,"num_probes":0}"#,
+        ] {
+            let json = format!(r#"{{"dimensions":8,"index":{index_json}}}"#);
+            let err = serde_json::from_str::<DenseVectorOptions>(&json).unwrap_err();
+            assert!(err.to_string().contains("must be at least"), "{err}");
+        }
+    }
+
+    #[test]
+    fn test_dense_vector_options_quantization_only_applies_to_hnsw() {
+        for index_json in [
+            r#"{"type":"ivf_pq","num_lists":16,"num_subquantizers":16,"num_probes":4}"#,
+            r#"{"type":"spann","list_size":64,"num_replicas":2,"num_probes":32}"#,
+        ] {
+            let json = format!(
+                r#"{{"dimensions":8,"index":{index_json},"quantization":{{"type":"int8","rescore":true}}}}"#
+            );
+            let err = serde_json::from_str::<DenseVectorOptions>(&json).unwrap_err();
+            assert!(
+                err.to_string().contains("only applies to the HNSW index"),
+                "{err}"
+            );
+            let json = format!(r#"{{"dimensions":8,"index":{index_json}}}"#);
+            assert!(serde_json::from_str::<DenseVectorOptions>(&json).is_ok());
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "only applies to the HNSW index")]
+    fn test_dense_vector_options_set_quantization_rejects_ivf_pq() {
+        let _ = DenseVectorOptions::new(8, VectorMetric::L2)
+            .set_index(VectorIndexOptions::IvfPq(IvfPqOptions::default()))
+            .set_quantization(VectorQuantization::Int8 { rescore: false });
+    }
+
+    #[test]
+    #[should_panic(expected = "only applies to the HNSW index")]
+    fn test_dense_vector_options_set_index_rejects_quantized_spann() {
+        let _ = DenseVectorOptions::new(8, VectorMetric::L2)
+            .set_quantization(VectorQuantization::Int8 { rescore: false })
+            .set_index(VectorIndexOptions::Spann(SpannOptions::default()));
+    }
+}
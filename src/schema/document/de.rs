@@ -13,15 +13,17 @@ use std::io;
 use std::io::Read;
 use std::marker::PhantomData;
 use std::net::Ipv6Addr;
+use std::ops::Range;
 use std::sync::Arc;
 
 use columnar::MonotonicallyMappableToU128;
 use common::{u64_to_f64, BinarySerializable, DateTime, VInt};
 
+use super::json_path_filter::{JsonPathFilter, PathFilterResult};
 use super::se::BinaryObjectSerializer;
 use super::{OwnedValue, Value};
 use crate::schema::document::type_codes;
-use crate::schema::{Facet, Field};
+use crate::schema::{Facet, Field, Schema};
 use crate::tokenizer::PreTokenizedString;
 
 #[derive(Debug, thiserror::Error, Clone)]
@@ -292,6 +294,11 @@ pub struct BinaryDocumentDeserializer<'de, R> {
     length: usize,
     position: usize,
     reader: &'de mut R,
+    /// If set, the values of the other fields are skipped.
+    fields: Option<&'de [Field]>,
+    /// If set, the values of the JSON fields are filtered. The schema is used to get the
+    /// names of the fields.
+    json_path_filter: Option<(&'de JsonPathFilter, &'de Schema)>,
 }
 
 impl<'de, R> BinaryDocumentDeserializer<'de, R>
@@ -305,9 +312,40 @@ where R: Read
             length: length.val() as usize,
             position: 0,
             reader,
+            fields: None,
+            json_path_filter: None,
         })
     }
 
+    /// Attempts to create a new document deserializer from a given reader, only
+    /// deserializing the values of the given fields.
+    ///
+    /// The values of the other fields are skipped without being decoded.
+    pub(crate) fn from_reader_with_fields(
+        reader: &'de mut R,
+        fields: &'de [Field],
+    ) -> Result<Self, DeserializeError> {
+        let mut deserializer = Self::from_reader(reader)?;
+        deserializer.fields = Some(fields);
+        Ok(deserializer)
+    }
+
+    /// Attempts to create a new document deserializer from a given reader, only
+    /// deserializing the parts of the JSON fields accepted by `json_path_filter`.
+    ///
+    /// The values that are filtered out are skipped without being decoded.
+    pub(crate) fn from_reader_with_json_path_filter(
+        reader: &'de mut R,
+        json_path_filter: &'de JsonPathFilter,
+        schema: &'de Schema,
+    ) -> Result<Self, DeserializeError> {
+        let mut deserializer = Self::from_reader(reader)?;
+        if !json_path_filter.is_empty() {
+            deserializer.json_path_filter = Some((json_path_filter, schema));
+        }
+        Ok(deserializer)
+    }
+
     /// Returns true if the deserializer has deserialized all the entries
     /// within the document.
     fn is_complete(&self) -> bool {
@@ -315,6 +353,33 @@ where R: Read
     }
 }
 
+/// Lists the values of a serialized document, without decoding them.
+///
+/// Returns the field of each value, and the range of the bytes of the value within
+/// `doc_bytes`. The value can then be decoded with [`deserialize_value`].
+pub(crate) fn index_values(
+    doc_bytes: &[u8],
+) -> Result<Vec<(Field, Range<usize>)>, DeserializeError> {
+    let mut reader = doc_bytes;
+    let num_values = VInt::deserialize(&mut reader)?.val() as usize;
+    let mut values = Vec::with_capacity(num_values);
+    for _ in 0..num_values {
+        let field = Field::deserialize(&mut reader)?;
+        let start = doc_bytes.len() - reader.len();
+        BinaryValueDeserializer::from_reader(&mut reader)?.skip()?;
+        let end = doc_bytes.len() - reader.len();
+        values.push((field, start..end));
+    }
+    Ok(values)
+}
+
+/// Decodes a value listed by [`index_values`].
+pub(crate) fn deserialize_value<V: ValueDeserialize>(
+    mut value_bytes: &[u8],
+) -> Result<V, DeserializeError> {
+    V::deserialize(BinaryValueDeserializer::from_reader(&mut value_bytes)?)
+}
+
 impl<'de, R> DocumentDeserializer<'de> for BinaryDocumentDeserializer<'de, R>
 where R: Read
 {
@@ -324,18 +389,55 @@ where R: Read
     }
 
     fn next_field<V: ValueDeserialize>(&mut self) -> Result<Option<(Field, V)>, DeserializeError> {
-        if self.is_complete() {
-            return Ok(None);
-        }
+        loop {
+            if self.is_complete() {
+                return Ok(None);
+            }
 
-        let field = Field::deserialize(self.reader).map_err(DeserializeError::from)?;
+            let field = Field::deserialize(self.reader).map_err(DeserializeError::from)?;
 
-        let deserializer = BinaryValueDeserializer::from_reader(self.reader)?;
-        let value = V::deserialize(deserializer)?;
+            let deserializer = BinaryValueDeserializer::from_reader(self.reader)?;
+            self.position += 1;
 
-        self.position += 1;
+            if let Some(fields) = self.fields {
+                if !fields.contains(&field) {
+                    deserializer.skip()?;
+                    continue;
+                }
+            }
+
+            // Only JSON fields have object values.
+            if let (Some((json_path_filter, schema)), ValueType::Object) =
+                (self.json_path_filter, deserializer.value_type)
+            {
+                let mut path = schema.get_field_name(field).to_string();
+                match json_path_filter.filter_path(&path) {
+                    PathFilterResult::Drop => {
+                        deserializer.skip()?;
+                        continue;
+                    }
+                    PathFilterResult::Keep => {}
+                    PathFilterResult::Filter { .. } => {
+                        let mut filtered = Vec::new();
+                        if !deserializer.filter_object(
+                            &mut path,
+                            json_path_filter,
+                            &mut filtered,
+                        )? {
+                            continue;
+                        }
+                        let mut filtered_reader = &filtered[..];
+                        let value = V::deserialize(BinaryValueDeserializer::from_reader(
+                            &mut filtered_reader,
+                        )?)?;
+                        return Ok(Some((field, value)));
+                    }
+                }
+            }
 
-        Ok(Some((field, value)))
+            let value = V::deserialize(deserializer)?;
+            return Ok(Some((field, value)));
+        }
     }
 }
 
@@ -394,6 +496,180 @@ where R: Read
         Ok(Self { value_type, reader })
     }
 
+    /// Skips the value, without decoding it.
+    fn skip(self) -> Result<(), DeserializeError> {
+        let num_bytes: u64 = match self.value_type {
+            ValueType::Null => 0,
+            ValueType::Bool => 1,
+            ValueType::U64 | ValueType::I64 | ValueType::F64 | ValueType::DateTime => 8,
+            ValueType::IpAddr => 16,
+            ValueType::String | ValueType::Facet | ValueType::Bytes | ValueType::PreTokStr => {
+                VInt::deserialize(self.reader)?.val()
+            }
+            ValueType::Array | ValueType::Object => {
+                // Objects are stored as arrays of keys and values.
+                let num_values = VInt::deserialize(self.reader)?.val();
+                for _ in 0..num_values {
+                    BinaryValueDeserializer::from_reader(&mut *self.reader)?.skip()?;
+                }
+                0
+            }
+            #[allow(deprecated)]
+            ValueType::JSONObject => {
+                let mut de = serde_json::Deserializer::from_reader(&mut *self.reader);
+                <serde::de::IgnoredAny as serde::Deserialize>::deserialize(&mut de)
+                    .map_err(|err| DeserializeError::Custom(err.to_string()))?;
+                0
+            }
+        };
+        let num_bytes_skipped =
+            io::copy(&mut self.reader.by_ref().take(num_bytes), &mut io::sink())?;
+        if num_bytes_skipped != num_bytes {
+            return Err(DeserializeError::from(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "value is truncated",
+            )));
+        }
+        Ok(())
+    }
+
+    /// Writes the type code of the value to `out`.
+    fn write_type_code(&self, out: &mut Vec<u8>) {
+        #[allow(deprecated)]
+        let type_code = match self.value_type {
+            ValueType::Null => type_codes::NULL_CODE,
+            ValueType::String => type_codes::TEXT_CODE,
+            ValueType::U64 => type_codes::U64_CODE,
+            ValueType::I64 => type_codes::I64_CODE,
+            ValueType::F64 => type_codes::F64_CODE,
+            ValueType::DateTime => type_codes::DATE_CODE,
+            ValueType::Facet => type_codes::HIERARCHICAL_FACET_CODE,
+            ValueType::Bytes => type_codes::BYTES_CODE,
+            ValueType::IpAddr => type_codes::IP_CODE,
+            ValueType::Bool => type_codes::BOOL_CODE,
+            ValueType::PreTokStr => {
+                out.push(type_codes::EXT_CODE);
+                type_codes::TOK_STR_EXT_CODE
+            }
+            ValueType::Array => type_codes::ARRAY_CODE,
+            ValueType::Object => type_codes::OBJECT_CODE,
+            ValueType::JSONObject => type_codes::JSON_OBJ_CODE,
+        };
+        out.push(type_code);
+    }
+
+    /// Copies the serialized value to `out`, without decoding it.
+    fn copy_to(self, out: &mut Vec<u8>) -> Result<(), DeserializeError> {
+        self.write_type_code(out);
+        let mut reader = TeeReader {
+            reader: self.reader,
+            out,
+        };
+        BinaryValueDeserializer {
+            value_type: self.value_type,
+            reader: &mut reader,
+        }
+        .skip()
+    }
+
+    /// Writes the parts of the value at `path` accepted by `filter` to `out`.
+    ///
+    /// Returns false, without writing anything, if the value is dropped entirely.
+    fn filter_value(
+        self,
+        path: &mut String,
+        filter: &JsonPathFilter,
+        out: &mut Vec<u8>,
+    ) -> Result<bool, DeserializeError> {
+        match (filter.filter_path(path), self.value_type) {
+            (PathFilterResult::Keep, _) => {
+                self.copy_to(out)?;
+                Ok(true)
+            }
+            (PathFilterResult::Filter { .. }, ValueType::Object) => {
+                self.filter_object(path, filter, out)
+            }
+            (PathFilterResult::Filter { .. }, ValueType::Array) => {
+                self.filter_array(path, filter, out)
+            }
+            (PathFilterResult::Filter { keep_leaf: true }, _) => {
+                self.copy_to(out)?;
+                Ok(true)
+            }
+            (PathFilterResult::Filter { keep_leaf: false }, _) | (PathFilterResult::Drop, _) => {
+                self.skip()?;
+                Ok(false)
+            }
+        }
+    }
+
+    /// Writes the entries of the object at `path` accepted by `filter` to `out`.
+    ///
+    /// Returns false, without writing anything, if no entry is kept.
+    fn filter_object(
+        self,
+        path: &mut String,
+        filter: &JsonPathFilter,
+        out: &mut Vec<u8>,
+    ) -> Result<bool, DeserializeError> {
+        // Objects are stored as arrays of keys and values.
+        let num_values = VInt::deserialize(self.reader)?.val();
+        let mut entries = Vec::new();
+        let mut num_entries_kept = 0u64;
+        for _ in 0..num_values / 2 {
+            let key =
+                BinaryValueDeserializer::from_reader(&mut *self.reader)?.deserialize_string()?;
+            let value = BinaryValueDeserializer::from_reader(&mut *self.reader)?;
+            let entry_start = entries.len();
+            entries.push(type_codes::TEXT_CODE);
+            key.serialize(&mut entries)?;
+            let path_len = path.len();
+            path.push('.');
+            path.push_str(&key);
+            let is_kept = value.filter_value(path, filter, &mut entries)?;
+            path.truncate(path_len);
+            if is_kept {
+                num_entries_kept += 1;
+            } else {
+                entries.truncate(entry_start);
+            }
+        }
+        if num_entries_kept == 0 {
+            return Ok(false);
+        }
+        out.push(type_codes::OBJECT_CODE);
+        VInt(num_entries_kept * 2).serialize(out)?;
+        out.extend_from_slice(&entries);
+        Ok(true)
+    }
+
+    /// Writes the elements of the array at `path` accepted by `filter` to `out`.
+    ///
+    /// Returns false, without writing anything, if no element is kept.
+    fn filter_array(
+        self,
+        path: &mut String,
+        filter: &JsonPathFilter,
+        out: &mut Vec<u8>,
+    ) -> Result<bool, DeserializeError> {
+        let num_values = VInt::deserialize(self.reader)?.val();
+        let mut elements = Vec::new();
+        let mut num_elements_kept = 0u64;
+        for _ in 0..num_values {
+            let element = BinaryValueDeserializer::from_reader(&mut *self.reader)?;
+            if element.filter_value(path, filter, &mut elements)? {
+                num_elements_kept += 1;
+            }
+        }
+        if num_elements_kept == 0 {
+            return Ok(false);
+        }
+        out.push(type_codes::ARRAY_CODE);
+        VInt(num_elements_kept).serialize(out)?;
+        out.extend_from_slice(&elements);
+        Ok(true)
+    }
+
     fn validate_type(&self, expected_type: ValueType) -> Result<(), DeserializeError> {
         if self.value_type == expected_type {
             Ok(())
@@ -406,6 +682,20 @@ where R: Read
     }
 }
 
+/// A reader copying the bytes it reads to `out`.
+struct TeeReader<'a, R> {
+    reader: &'a mut R,
+    out: &'a mut Vec<u8>,
+}
+
+impl<'a, R: Read> Read for TeeReader<'a, R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let num_bytes = self.reader.read(buf)?;
+        self.out.extend_from_slice(&buf[..num_bytes]);
+        Ok(num_bytes)
+    }
+}
+
 impl<'de, R> ValueDeserializer<'de> for BinaryValueDeserializer<'de, R>
 where R: Read
 {
@@ -1095,4 +1385,49 @@ mod tests {
             OwnedValue::Object(expected_object.into_iter().collect())
         );
     }
+
+    #[test]
+    fn test_skip_values() {
+        let mut object = serde_json::Map::new();
+        object.insert(
+            "my-array".into(),
+            serde_json::Value::Array(vec![
+                serde_json::Value::Null,
+                serde_json::Value::String(String::from("bobby of the sea")),
+            ]),
+        );
+        object.insert("my-bool".into(), serde_json::Value::Bool(true));
+        let pre_tok_str = PreTokenizedString {
+            text: "hello, world".to_string(),
+            tokens: vec![Token::default()],
+        };
+        let facet = Facet::from_text("/hello/world").unwrap();
+        let values: Vec<Vec<u8>> = vec![
+            serialize_value(ReferenceValueLeaf::Null.into()),
+            serialize_value(ReferenceValueLeaf::Str("hello").into()),
+            serialize_value(ReferenceValueLeaf::I64(-3).into()),
+            serialize_value(ReferenceValueLeaf::Bool(true).into()),
+            serialize_value(ReferenceValueLeaf::Date(DateTime::from_timestamp_micros(1)).into()),
+            serialize_value(ReferenceValueLeaf::Facet(facet.encoded_str()).into()),
+            serialize_value(ReferenceValueLeaf::Bytes(&[1, 2, 3]).into()),
+            serialize_value(ReferenceValueLeaf::IpAddr(Ipv6Addr::LOCALHOST).into()),
+            serialize_value(ReferenceValueLeaf::PreTokStr(pre_tok_str.into()).into()),
+            serialize_value(ReferenceValue::Object(JsonObjectIter(object.iter()))),
+            // A legacy JSON object.
+            vec![
+                8, 123, 34, 107, 101, 121, 97, 58, 34, 58, 34, 98, 108, 117, 98, 34, 125,
+            ],
+        ];
+        for value in values {
+            let mut buffer = value;
+            buffer.extend(serialize_value(ReferenceValueLeaf::U64(42).into()));
+            let mut cursor = Cursor::new(buffer);
+            BinaryValueDeserializer::from_reader(&mut cursor)
+                .unwrap()
+                .skip()
+                .unwrap();
+            let deserializer = BinaryValueDeserializer::from_reader(&mut cursor).unwrap();
+            assert_eq!(deserializer.deserialize_u64().unwrap(), 42);
+        }
+    }
 }
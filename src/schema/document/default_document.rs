@@ -14,6 +14,7 @@ use crate::schema::document::{
 use crate::schema::field_type::ValueParsingError;
 use crate::schema::{Facet, Field, NamedFieldDocument, OwnedValue, Schema};
 use crate::tokenizer::PreTokenizedString;
+use crate::vector::encode_vector;
 
 #[repr(packed)]
 #[derive(Debug, Clone)]
@@ -118,6 +119,11 @@ impl CompactDoc {
         self.add_leaf_field_value(field, value);
     }
 
+    /// Add a vector to a dense vector field
+    pub fn add_vector(&mut self, field: Field, vector: &[f32]) {
+        self.add_bytes(field, &encode_vector(vector));
+    }
+
     /// Add a dynamic object field
     pub fn add_object(&mut self, field: Field, object: BTreeMap<String, OwnedValue>) {
         self.add_field_value(field, &OwnedValue::from(object));
@@ -202,6 +208,9 @@ impl CompactDoc {
     }
 
     /// Build a document object from a json-object.
+    ///
+    /// The value of a dense vector field is an array of numbers, or the vector encoded in
+    /// base64. Several vectors are given as an array of such values.
     pub fn from_json_object(
         schema: &Schema,
         json_obj: Map<String, serde_json::Value>,
@@ -211,8 +220,12 @@ impl CompactDoc {
             if let Ok(field) = schema.get_field(&field_name) {
                 let field_entry = schema.get_field_entry(field);
                 let field_type = field_entry.field_type();
+                // A single vector is an array of numbers, not an array of values.
+                let is_single_vector = field_entry.dense_vector_options().is_some()
+                    && matches!(&json_value, serde_json::Value::Array(json_items)
+                        if json_items.first().map_or(false, serde_json::Value::is_number));
                 match json_value {
-                    serde_json::Value::Array(json_items) => {
+                    serde_json::Value::Array(json_items) if !is_single_vector => {
                         for json_item in json_items {
                             let value = field_type
                                 .value_from_json(json_item)
@@ -717,7 +730,9 @@ impl DocParsingError {
 
 #[cfg(test)]
 mod tests {
+    use crate::schema::field_type::ValueParsingError;
     use crate::schema::*;
+    use crate::vector::decode_vector;
 
     #[test]
     fn test_doc() {
@@ -761,6 +776,39 @@ mod tests {
         assert_eq!(actual_json["json"][0], expected_json);
     }
 
+    #[test]
+    fn test_parse_json_dense_vectors() {
+        let mut schema_builder = Schema::builder();
+        let embedding = schema_builder
+            .add_dense_vector_field("embedding", DenseVectorOptions::new(2, VectorMetric::L2));
+        let schema = schema_builder.build();
+        let vectors = |doc: &TantivyDocument| -> Vec<Vec<f32>> {
+            doc.get_all(embedding)
+                .map(|value| decode_vector(value.as_bytes().unwrap()).unwrap())
+                .collect()
+        };
+
+        let doc = TantivyDocument::parse_json(&schema, r#"{"embedding": [0.5, 1]}"#).unwrap();
+        assert_eq!(vectors(&doc), vec![vec![0.5, 1.0]]);
+        // Several vectors, the second one encoded in base64.
+        let doc =
+            TantivyDocument::parse_json(&schema, r#"{"embedding": [[0.5, 1], "AAAAPwAAAD8="]}"#)
+                .unwrap();
+        assert_eq!(vectors(&doc), vec![vec![0.5, 1.0], vec![0.5, 0.5]]);
+
+        let mut doc = TantivyDocument::default();
+        doc.add_vector(embedding, &[0.5, 1.0]);
+        assert_eq!(vectors(&doc), vec![vec![0.5, 1.0]]);
+
+        assert!(matches!(
+            TantivyDocument::parse_json(&schema, r#"{"embedding": [0.5, 1, 2]}"#),
+            Err(DocParsingError::ValueError(
+                _,
+                ValueParsingError::DimensionError { expected: 2, .. }
+            ))
+        ));
+    }
+
     // TODO: Should this be re-added with the serialize method
     //       technically this is no longer useful since the doc types
     //       do not implement BinarySerializable due to orphan rules.
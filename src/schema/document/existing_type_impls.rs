@@ -21,6 +21,7 @@ use crate::schema::document::{
 };
 use crate::schema::Field;
 use crate::tokenizer::PreTokenizedString;
+use crate::vector::DenseVector;
 
 // Serde compatibility support.
 pub fn can_be_rfc3339_date_time(text: &str) -> bool {
@@ -169,6 +170,15 @@ impl<'a> Value<'a> for &'a Vec<u8> {
     }
 }
 
+impl<'a> Value<'a> for &'a DenseVector {
+    type ArrayIter = Empty<&'a DenseVector>;
+    type ObjectIter = Empty<(&'a str, &'a DenseVector)>;
+    #[inline]
+    fn as_value(&self) -> ReferenceValue<'a, Self> {
+        ReferenceValue::Leaf(ReferenceValueLeaf::Bytes(self.as_bytes()))
+    }
+}
+
 impl<'a> Value<'a> for &'a DateTime {
     type ArrayIter = Empty<&'a DateTime>;
     type ObjectIter = Empty<(&'a str, &'a DateTime)>;
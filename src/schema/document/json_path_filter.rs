@@ -0,0 +1,177 @@
+/// Include and exclude patterns selecting the parts of the stored JSON fields to
+/// return when fetching a document.
+///
+/// Patterns are matched against the dotted path of the values, starting with the
+/// name of the JSON field: the value `{"user": {"name": "Paul"}}` of the field
+/// `attributes` has the path `attributes.user.name`. In patterns, `*` matches any
+/// sequence of characters, including dots.
+///
+/// A value is returned if its path, or the path of one of its parents, matches one of
+/// the include patterns (or if there are no include patterns), and neither its path nor
+/// the path of one of its parents match one of the exclude patterns. Arrays are
+/// transparent: their elements have the same path as the array itself. Objects that end
+/// up empty are dropped, and so is a JSON field none of whose values are returned.
+///
+/// Only JSON fields are filtered: the values of the other fields are always returned.
+///
+/// ```rust
+/// use tantivy::schema::JsonPathFilter;
+///
+/// let filter = JsonPathFilter::default()
+///     .include("attributes.title")
+///     .include("attributes.author.*")
+///     .exclude("*.internal");
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct JsonPathFilter {
+    includes: Vec<String>,
+    excludes: Vec<String>,
+}
+
+/// What to do with a value, given its path.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum PathFilterResult {
+    /// The value is dropped, with all of its children.
+    Drop,
+    /// The value is kept, with all of its children.
+    Keep,
+    /// Only some of the children of the value may be kept. A value without children is
+    /// kept if `keep_leaf` is true.
+    Filter { keep_leaf: bool },
+}
+
+impl JsonPathFilter {
+    /// Returns the values matching `pattern`, in addition to the ones matching the other
+    /// include patterns.
+    #[must_use]
+    pub fn include(mut self, pattern: impl ToString) -> JsonPathFilter {
+        self.includes.push(pattern.to_string());
+        self
+    }
+
+    /// Drops the values matching `pattern`.
+    #[must_use]
+    pub fn exclude(mut self, pattern: impl ToString) -> JsonPathFilter {
+        self.excludes.push(pattern.to_string());
+        self
+    }
+
+    /// Returns true if the filter returns all of the values.
+    pub fn is_empty(&self) -> bool {
+        self.includes.is_empty() && self.excludes.is_empty()
+    }
+
+    /// Decides what to do with the value at `path`, assuming its parent has been
+    /// filtered with [`PathFilterResult::Filter`].
+    pub(crate) fn filter_path(&self, path: &str) -> PathFilterResult {
+        let matches_any = |patterns: &[String]| {
+            patterns
+                .iter()
+                .any(|pattern| glob_match(pattern.as_bytes(), path.as_bytes(), false))
+        };
+        // A pattern may match one of the children if it matches a prefix of `path.`.
+        let child_prefix = format!("{path}.");
+        let may_match_child = |patterns: &[String]| {
+            patterns
+                .iter()
+                .any(|pattern| glob_match(pattern.as_bytes(), child_prefix.as_bytes(), true))
+        };
+        if matches_any(&self.excludes) {
+            return PathFilterResult::Drop;
+        }
+        let is_included = self.includes.is_empty() || matches_any(&self.includes);
+        if is_included {
+            if may_match_child(&self.excludes) {
+                PathFilterResult::Filter { keep_leaf: true }
+            } else {
+                PathFilterResult::Keep
+            }
+        } else if may_match_child(&self.includes) {
+            PathFilterResult::Filter { keep_leaf: false }
+        } else {
+            PathFilterResult::Drop
+        }
+    }
+}
+
+/// Matches `text` against a pattern in which `*` matches any sequence of bytes.
+///
+/// If `prefix` is true, returns true if `pattern` matches some text starting with `text`.
+fn glob_match(pattern: &[u8], text: &[u8], prefix: bool) -> bool {
+    let (mut pattern_pos, mut text_pos) = (0, 0);
+    // Position of the last star in the pattern, and of the text it was matched against.
+    let mut last_star: Option<(usize, usize)> = None;
+    while text_pos < text.len() {
+        match pattern.get(pattern_pos) {
+            Some(b'*') => {
+                last_star = Some((pattern_pos, text_pos));
+                pattern_pos += 1;
+            }
+            Some(&byte) if byte == text[text_pos] => {
+                pattern_pos += 1;
+                text_pos += 1;
+            }
+            _ => {
+                // Backtrack, extending the match of the last star by one byte.
+                let Some((star_pos, star_text_pos)) = last_star else {
+                    return false;
+                };
+                last_star = Some((star_pos, star_text_pos + 1));
+                pattern_pos = star_pos + 1;
+                text_pos = star_text_pos + 1;
+            }
+        }
+    }
+    prefix || pattern[pattern_pos..].iter().all(|&byte| byte == b'*')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{glob_match, JsonPathFilter, PathFilterResult};
+
+    #[test]
+    fn test_glob_match() {
+        assert!(glob_match(b"a.b", b"a.b", false));
+        assert!(!glob_match(b"a.b", b"a.bc", false));
+        assert!(glob_match(b"a.*", b"a.b.c", false));
+        assert!(glob_match(b"*.c", b"a.b.c", false));
+        assert!(glob_match(b"a*c", b"abcbc", false));
+        assert!(!glob_match(b"a*c", b"abcb", false));
+        assert!(glob_match(b"a.b.c", b"a.", true));
+        assert!(glob_match(b"*.c", b"a.b.", true));
+        assert!(!glob_match(b"a.b", b"a.c", true));
+    }
+
+    #[test]
+    fn test_filter_path() {
+        let filter = JsonPathFilter::default()
+            .include("json.user")
+            .include("json.meta.*")
+            .exclude("*.secret");
+        assert_eq!(
+            filter.filter_path("json"),
+            PathFilterResult::Filter { keep_leaf: false }
+        );
+        assert_eq!(filter.filter_path("json.title"), PathFilterResult::Drop);
+        assert_eq!(
+            filter.filter_path("json.user"),
+            PathFilterResult::Filter { keep_leaf: true }
+        );
+        assert_eq!(
+            filter.filter_path("json.user.secret"),
+            PathFilterResult::Drop
+        );
+        assert_eq!(
+            filter.filter_path("json.meta"),
+            PathFilterResult::Filter { keep_leaf: false }
+        );
+        assert_eq!(filter.filter_path("other"), PathFilterResult::Drop);
+
+        let filter = JsonPathFilter::default().include("json.user");
+        assert_eq!(filter.filter_path("json.user"), PathFilterResult::Keep);
+        assert_eq!(
+            JsonPathFilter::default().filter_path("json"),
+            PathFilterResult::Keep
+        );
+    }
+}
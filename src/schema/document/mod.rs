@@ -160,6 +160,7 @@
 mod de;
 mod default_document;
 mod existing_type_impls;
+mod json_path_filter;
 mod owned_value;
 mod se;
 mod value;
@@ -167,7 +168,7 @@ mod value;
 use std::collections::BTreeMap;
 use std::mem;
 
-pub(crate) use self::de::BinaryDocumentDeserializer;
+pub(crate) use self::de::{deserialize_value, index_values, BinaryDocumentDeserializer};
 pub use self::de::{
     ArrayAccess, DeserializeError, DocumentDeserialize, DocumentDeserializer, ObjectAccess,
     ValueDeserialize, ValueDeserializer, ValueType, ValueVisitor,
@@ -175,6 +176,7 @@ pub use self::de::{
 pub use self::default_document::{
     CompactDocArrayIter, CompactDocObjectIter, CompactDocValue, DocParsingError, TantivyDocument,
 };
+pub use self::json_path_filter::JsonPathFilter;
 pub use self::owned_value::OwnedValue;
 pub(crate) use self::se::BinaryDocumentSerializer;
 pub use self::value::{ReferenceValue, ReferenceValueLeaf, Value};
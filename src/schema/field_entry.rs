@@ -3,8 +3,8 @@ use serde::{Deserialize, Serialize};
 use super::ip_options::IpAddrOptions;
 use crate::schema::bytes_options::BytesOptions;
 use crate::schema::{
-    is_valid_field_name, DateOptions, FacetOptions, FieldType, JsonObjectOptions, NumericOptions,
-    TextOptions,
+    is_valid_field_name, DateOptions, DenseVectorOptions, FacetOptions, FieldType,
+    JsonObjectOptions, NumericOptions, TextFieldIndexing, TextOptions,
 };
 
 /// A `FieldEntry` represents a field and its configuration.
@@ -116,6 +116,33 @@ impl FieldEntry {
         }
     }
 
+    /// Returns true if the values of the field are written to the blob file of the segment
+    pub fn is_blob(&self) -> bool {
+        match self.field_type {
+            FieldType::Bytes(ref options) => options.is_blob(),
+            _ => false,
+        }
+    }
+
+    /// Returns the options of the vectors of the field, if it is a dense vector field.
+    pub fn dense_vector_options(&self) -> Option<&DenseVectorOptions> {
+        match self.field_type {
+            FieldType::Bytes(ref options) => options.dense_vector_options(),
+            _ => None,
+        }
+    }
+
+    /// Returns true if the term vectors of the field are written to the term vectors file of
+    /// the segment
+    pub fn has_term_vectors(&self) -> bool {
+        match self.field_type {
+            FieldType::Str(ref options) => options
+                .get_indexing_options()
+                .map_or(false, TextFieldIndexing::term_vectors),
+            _ => false,
+        }
+    }
+
     /// Returns true if the field is stored
     #[inline]
     pub fn is_stored(&self) -> bool {
@@ -13,12 +13,13 @@ use super::IntoIpv6Addr;
 use crate::schema::bytes_options::BytesOptions;
 use crate::schema::facet_options::FacetOptions;
 use crate::schema::{
-    DateOptions, Facet, IndexRecordOption, JsonObjectOptions, NumericOptions, OwnedValue,
-    TextFieldIndexing, TextOptions,
+    Bm25Options, DateOptions, DenseVectorOptions, Facet, FastFieldCodec, IndexRecordOption,
+    JsonObjectOptions, NumericOptions, OwnedValue, TextFieldIndexing, TextOptions,
 };
 use crate::time::format_description::well_known::Rfc3339;
 use crate::time::OffsetDateTime;
 use crate::tokenizer::PreTokenizedString;
+use crate::vector::encode_vector;
 use crate::DateTime;
 
 /// Possible error that may occur while parsing a field value
@@ -42,6 +43,11 @@ pub enum ValueParsingError {
     },
     #[error("Invalid base64: {base64}")]
     InvalidBase64 { base64: String },
+    #[error("Dimension error. Expected a vector of {expected} dimensions, got {json}")]
+    DimensionError {
+        expected: u32,
+        json: serde_json::Value,
+    },
 }
 
 /// Type of the value that a field can take.
@@ -222,6 +228,22 @@ impl FieldType {
         }
     }
 
+    /// Returns the parameters of the BM25 scoring of the field.
+    ///
+    /// Only text and JSON fields can set them, the other fields use the default ones.
+    pub fn bm25_options(&self) -> Bm25Options {
+        let text_indexing = match self {
+            FieldType::Str(text_options) => text_options.get_indexing_options(),
+            FieldType::JsonObject(json_object_options) => {
+                json_object_options.get_text_indexing_options()
+            }
+            _ => None,
+        };
+        text_indexing
+            .map(TextFieldIndexing::bm25)
+            .unwrap_or_default()
+    }
+
     /// Returns the index record option for the field.
     ///
     /// If the field is not indexed, returns `None`.
@@ -259,6 +281,17 @@ impl FieldType {
         }
     }
 
+    /// Returns the codec pinned for the fast field, if any.
+    pub fn fastfield_codec(&self) -> Option<FastFieldCodec> {
+        match *self {
+            FieldType::U64(ref int_options)
+            | FieldType::I64(ref int_options)
+            | FieldType::F64(ref int_options)
+            | FieldType::Bool(ref int_options) => int_options.fastfield_codec(),
+            _ => None,
+        }
+    }
+
     /// returns true if the field is normed (see [fieldnorms](crate::fieldnorm)).
     pub fn has_fieldnorms(&self) -> bool {
         match *self {
@@ -335,6 +368,9 @@ impl FieldType {
     /// For instance, If the json value is the integer `3` and the
     /// target field is a `Str`, this method will return an Error if `coerce`
     /// is not enabled.
+    ///
+    /// The value of a dense vector field is either an array of numbers or the base64
+    /// encoding of the vector, and has to have the dimensions of the field.
     pub fn value_from_json(&self, json: JsonValue) -> Result<OwnedValue, ValueParsingError> {
         match json {
             JsonValue::String(field_text) => {
@@ -409,10 +445,10 @@ impl FieldType {
                         }
                     }
                     FieldType::Facet(_) => Ok(OwnedValue::Facet(Facet::from(&field_text))),
-                    FieldType::Bytes(_) => BASE64
+                    FieldType::Bytes(bytes_options) => BASE64
                         .decode(&field_text)
-                        .map(OwnedValue::Bytes)
-                        .map_err(|_| ValueParsingError::InvalidBase64 { base64: field_text }),
+                        .map_err(|_| ValueParsingError::InvalidBase64 { base64: field_text })
+                        .and_then(|bytes| bytes_value(bytes_options, bytes)),
                     FieldType::JsonObject(_) => Err(ValueParsingError::TypeError {
                         expected: "a json object",
                         json: JsonValue::String(field_text),
@@ -540,25 +576,81 @@ impl FieldType {
                     json: JsonValue::Null,
                 }),
             },
-            _ => Err(ValueParsingError::TypeError {
-                expected: self.value_type().name(),
-                json: json.clone(),
-            }),
+            JsonValue::Array(json_items) => match self {
+                FieldType::Bytes(bytes_options) => match bytes_options.dense_vector_options() {
+                    Some(dense_vector_options) => {
+                        vector_from_json(dense_vector_options, json_items)
+                    }
+                    None => Err(ValueParsingError::TypeError {
+                        expected: self.value_type().name(),
+                        json: JsonValue::Array(json_items),
+                    }),
+                },
+                _ => Err(ValueParsingError::TypeError {
+                    expected: self.value_type().name(),
+                    json: JsonValue::Array(json_items),
+                }),
+            },
+        }
+    }
+}
+
+/// Returns the value of a bytes field, checking that it has the dimensions of the field if
+/// it holds dense vectors.
+fn bytes_value(
+    bytes_options: &BytesOptions,
+    bytes: Vec<u8>,
+) -> Result<OwnedValue, ValueParsingError> {
+    if let Some(dense_vector_options) = bytes_options.dense_vector_options() {
+        if bytes.len() != dense_vector_options.dimensions() as usize * 4 {
+            return Err(ValueParsingError::DimensionError {
+                expected: dense_vector_options.dimensions(),
+                json: JsonValue::String(BASE64.encode(&bytes)),
+            });
         }
     }
+    Ok(OwnedValue::Bytes(bytes))
+}
+
+/// Parses a vector given as an array of numbers.
+fn vector_from_json(
+    dense_vector_options: &DenseVectorOptions,
+    json_items: Vec<JsonValue>,
+) -> Result<OwnedValue, ValueParsingError> {
+    let vector: Option<Vec<f32>> = json_items
+        .iter()
+        .map(|json_item| json_item.as_f64().map(|val| val as f32))
+        .collect();
+    let Some(vector) = vector else {
+        return Err(ValueParsingError::TypeError {
+            expected: "an array of numbers",
+            json: JsonValue::Array(json_items),
+        });
+    };
+    if vector.len() != dense_vector_options.dimensions() as usize {
+        return Err(ValueParsingError::DimensionError {
+            expected: dense_vector_options.dimensions(),
+            json: JsonValue::Array(json_items),
+        });
+    }
+    Ok(OwnedValue::Bytes(encode_vector(&vector)))
 }
 
 #[cfg(test)]
 mod tests {
+    use base64::engine::general_purpose::STANDARD as BASE64;
+    use base64::Engine;
     use serde_json::json;
 
     use super::FieldType;
     use crate::schema::field_type::ValueParsingError;
     use crate::schema::{
-        Document, NumericOptions, OwnedValue, Schema, TextOptions, Type, COERCE, INDEXED,
+        BytesOptions, DenseVectorOptions, Document, NumericOptions, OwnedValue, Schema,
+        TextOptions, Type, VectorMetric, COERCE, INDEXED,
     };
     use crate::time::{Date, Month, PrimitiveDateTime, Time};
     use crate::tokenizer::{PreTokenizedString, Token};
+    use crate::vector::encode_vector;
     use crate::{DateTime, TantivyDocument};
 
     #[test]
@@ -701,6 +793,39 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_dense_vector_value_from_json() {
+        let field_type = FieldType::Bytes(
+            BytesOptions::default().set_dense_vector(DenseVectorOptions::new(2, VectorMetric::L2)),
+        );
+        let expected = OwnedValue::Bytes(encode_vector(&[0.5, -1.0]));
+        assert_eq!(
+            field_type.value_from_json(json!([0.5, -1])).unwrap(),
+            expected
+        );
+        // The vector encoded in base64.
+        let base64 = BASE64.encode(encode_vector(&[0.5, -1.0]));
+        assert_eq!(field_type.value_from_json(json!(base64)).unwrap(), expected);
+
+        assert!(matches!(
+            field_type.value_from_json(json!([0.5, -1.0, 2.0])),
+            Err(ValueParsingError::DimensionError { expected: 2, .. })
+        ));
+        assert!(matches!(
+            field_type.value_from_json(json!("AAAAPw==")),
+            Err(ValueParsingError::DimensionError { expected: 2, .. })
+        ));
+        assert!(matches!(
+            field_type.value_from_json(json!([0.5, "a"])),
+            Err(ValueParsingError::TypeError { .. })
+        ));
+        // A bytes field which does not hold vectors.
+        assert!(matches!(
+            FieldType::Bytes(Default::default()).value_from_json(json!([0.5, -1.0])),
+            Err(ValueParsingError::TypeError { .. })
+        ));
+    }
+
     #[test]
     fn test_pre_tok_str_value_from_json() {
         let pre_tokenized_string_json = r#"{
@@ -117,6 +117,7 @@ mod field_type;
 
 mod bytes_options;
 mod date_time_options;
+mod dense_vector_options;
 mod field;
 mod flags;
 mod index_record_option;
@@ -125,12 +126,21 @@ mod json_object_options;
 mod named_field_document;
 mod numeric_options;
 mod text_options;
+mod typed_document;
 
 use columnar::ColumnType;
+#[cfg(feature = "derive")]
+pub use tantivy_derive::TypedDocument;
 
 pub use self::bytes_options::BytesOptions;
 pub use self::date_time_options::{DateOptions, DateTimePrecision, DATE_TIME_PRECISION_INDEXED};
-pub use self::document::{DocParsingError, Document, OwnedValue, TantivyDocument, Value};
+pub use self::dense_vector_options::{
+    DenseVectorOptions, HnswOptions, IvfPqOptions, SpannOptions, VectorIndexOptions, VectorMetric,
+    VectorQuantization,
+};
+pub use self::document::{
+    DocParsingError, Document, JsonPathFilter, OwnedValue, TantivyDocument, Value,
+};
 pub(crate) use self::facet::FACET_SEP_BYTE;
 pub use self::facet::{Facet, FacetParseError};
 pub use self::facet_options::FacetOptions;
@@ -142,10 +152,11 @@ pub use self::index_record_option::IndexRecordOption;
 pub use self::ip_options::{IntoIpv6Addr, IpAddrOptions};
 pub use self::json_object_options::JsonObjectOptions;
 pub use self::named_field_document::NamedFieldDocument;
-pub use self::numeric_options::NumericOptions;
+pub use self::numeric_options::{FastFieldCodec, NumericOptions};
 pub use self::schema::{Schema, SchemaBuilder};
 pub use self::term::{Term, ValueBytes};
-pub use self::text_options::{TextFieldIndexing, TextOptions, STRING, TEXT};
+pub use self::text_options::{Bm25Options, TextFieldIndexing, TextOptions, STRING, TEXT};
+pub use self::typed_document::{TypedDocument, TypedScalar, TypedValue};
 
 /// Validator for a potential `field_name`.
 /// Returns true if the name can be use for a field name.
@@ -1,10 +1,46 @@
 use std::ops::BitOr;
 
+use columnar::column_values::CodecType;
 use serde::{Deserialize, Serialize};
 
 use super::flags::CoerceFlag;
 use crate::schema::flags::{FastFlag, IndexedFlag, SchemaFlagList, StoredFlag};
 
+/// Codec that can be pinned for the fast field of a numerical field, see
+/// [`NumericOptions::set_fastfield_codec`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FastFieldCodec {
+    /// Bitpacks the offsets of the values from the minimum value.
+    Bitpacked,
+    /// Splits the values in blocks of 512 values, and bitpacks the deviations of the values
+    /// from a line interpolating each block.
+    BlockwiseLinear,
+    /// Splits the values in blocks of 512 values, and bitpacks the offsets of the values from
+    /// the minimum value of each block.
+    BlockwiseFor,
+    /// Compresses blocks of 1,024 values with zstd. Smaller, but a lot slower to access: only
+    /// suited to rarely accessed fields. Requires the `zstd-compression` feature.
+    #[cfg(feature = "zstd-compression")]
+    ZstdBlock,
+    /// Bitpacks the value and the end of every run of identical consecutive values. Suited to
+    /// the fields the index is sorted by.
+    RunLength,
+}
+
+impl FastFieldCodec {
+    pub(crate) fn codec_type(self) -> CodecType {
+        match self {
+            FastFieldCodec::Bitpacked => CodecType::Bitpacked,
+            FastFieldCodec::BlockwiseLinear => CodecType::BlockwiseLinear,
+            FastFieldCodec::BlockwiseFor => CodecType::BlockwiseFor,
+            #[cfg(feature = "zstd-compression")]
+            FastFieldCodec::ZstdBlock => CodecType::ZstdBlock,
+            FastFieldCodec::RunLength => CodecType::RunLength,
+        }
+    }
+}
+
 /// Define how an `u64`, `i64`, or `f64` field should be handled by tantivy.
 #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, Default)]
 #[serde(from = "NumericOptionsDeser")]
@@ -16,6 +52,8 @@ pub struct NumericOptions {
     stored: bool,
     #[serde(skip_serializing_if = "is_false")]
     coerce: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    fastfield_codec: Option<FastFieldCodec>,
 }
 
 fn is_false(val: &bool) -> bool {
@@ -37,6 +75,8 @@ struct NumericOptionsDeser {
     stored: bool,
     #[serde(default)]
     coerce: bool,
+    #[serde(default)]
+    fastfield_codec: Option<FastFieldCodec>,
 }
 
 impl From<NumericOptionsDeser> for NumericOptions {
@@ -47,6 +87,7 @@ impl From<NumericOptionsDeser> for NumericOptions {
             fast: deser.fast,
             stored: deser.stored,
             coerce: deser.coerce,
+            fastfield_codec: deser.fastfield_codec,
         }
     }
 }
@@ -82,6 +123,12 @@ impl NumericOptions {
         self.coerce
     }
 
+    /// Returns the codec pinned for the fast field, if any.
+    #[inline]
+    pub fn fastfield_codec(&self) -> Option<FastFieldCodec> {
+        self.fastfield_codec
+    }
+
     /// Try to coerce values if they are not a number. Defaults to false.
     #[must_use]
     pub fn set_coerce(mut self) -> Self {
@@ -129,6 +176,16 @@ impl NumericOptions {
         self.fast = true;
         self
     }
+
+    /// Pins the codec of the fast field.
+    ///
+    /// By default, the codec resulting in the smallest fast field is picked when a segment is
+    /// written or merged. Pinning a codec trades size for predictable access times.
+    #[must_use]
+    pub fn set_fastfield_codec(mut self, fastfield_codec: FastFieldCodec) -> NumericOptions {
+        self.fastfield_codec = Some(fastfield_codec);
+        self
+    }
 }
 
 impl From<()> for NumericOptions {
@@ -145,6 +202,7 @@ impl From<CoerceFlag> for NumericOptions {
             stored: false,
             fast: false,
             coerce: true,
+            fastfield_codec: None,
         }
     }
 }
@@ -157,6 +215,7 @@ impl From<FastFlag> for NumericOptions {
             stored: false,
             fast: true,
             coerce: false,
+            fastfield_codec: None,
         }
     }
 }
@@ -169,6 +228,7 @@ impl From<StoredFlag> for NumericOptions {
             stored: true,
             fast: false,
             coerce: false,
+            fastfield_codec: None,
         }
     }
 }
@@ -181,6 +241,7 @@ impl From<IndexedFlag> for NumericOptions {
             stored: false,
             fast: false,
             coerce: false,
+            fastfield_codec: None,
         }
     }
 }
@@ -196,6 +257,7 @@ impl<T: Into<NumericOptions>> BitOr<T> for NumericOptions {
             stored: self.stored | other.stored,
             fast: self.fast | other.fast,
             coerce: self.coerce | other.coerce,
+            fastfield_codec: self.fastfield_codec.or(other.fastfield_codec),
         }
     }
 }
@@ -230,6 +292,7 @@ mod tests {
                 fast: false,
                 stored: false,
                 coerce: false,
+                fastfield_codec: None,
             }
         );
     }
@@ -249,6 +312,7 @@ mod tests {
                 fast: false,
                 stored: false,
                 coerce: false,
+                fastfield_codec: None,
             }
         );
     }
@@ -269,6 +333,7 @@ mod tests {
                 fast: false,
                 stored: false,
                 coerce: false,
+                fastfield_codec: None,
             }
         );
     }
@@ -290,6 +355,7 @@ mod tests {
                 fast: false,
                 stored: false,
                 coerce: false,
+                fastfield_codec: None,
             }
         );
     }
@@ -312,7 +378,21 @@ mod tests {
                 fast: false,
                 stored: false,
                 coerce: true,
+                fastfield_codec: None,
             }
         );
     }
+
+    #[test]
+    fn test_int_options_fastfield_codec_serde() {
+        let int_options = NumericOptions::default()
+            .set_fast()
+            .set_fastfield_codec(FastFieldCodec::Bitpacked);
+        let json = serde_json::to_string(&int_options).unwrap();
+        assert!(json.contains(r#""fastfield_codec":"bitpacked""#));
+        let int_options_deser: NumericOptions = serde_json::from_str(&json).unwrap();
+        assert_eq!(int_options_deser, int_options);
+        let json_without_codec = serde_json::to_string(&NumericOptions::default()).unwrap();
+        assert!(!json_without_codec.contains("fastfield_codec"));
+    }
 }
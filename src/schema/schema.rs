@@ -184,6 +184,22 @@ impl SchemaBuilder {
         self.add_field(field_entry)
     }
 
+    /// Adds a dense vector field to the schema.
+    ///
+    /// This is a bytes field whose values are vectors, see
+    /// [`BytesOptions::set_dense_vector`]. To also store the vectors in the doc store, add
+    /// a bytes field with the dense vector option set instead.
+    pub fn add_dense_vector_field(
+        &mut self,
+        field_name: &str,
+        dense_vector_options: DenseVectorOptions,
+    ) -> Field {
+        self.add_bytes_field(
+            field_name,
+            BytesOptions::default().set_dense_vector(dense_vector_options),
+        )
+    }
+
     /// Adds a json object field to the schema.
     pub fn add_json_field<T: Into<JsonObjectOptions>>(
         &mut self,
@@ -301,6 +317,23 @@ impl Schema {
             .map(|(field_id, field_entry)| (Field::from_field_id(field_id as u32), field_entry))
     }
 
+    /// Returns true if at least one of the fields is a blob field.
+    pub fn has_blob_fields(&self) -> bool {
+        self.fields().any(|(_, field_entry)| field_entry.is_blob())
+    }
+
+    /// Returns true if at least one of the fields is a dense vector field.
+    pub fn has_dense_vector_fields(&self) -> bool {
+        self.fields()
+            .any(|(_, field_entry)| field_entry.dense_vector_options().is_some())
+    }
+
+    /// Returns true if at least one of the fields has term vectors.
+    pub fn has_term_vector_fields(&self) -> bool {
+        self.fields()
+            .any(|(_, field_entry)| field_entry.has_term_vectors())
+    }
+
     /// Creates a new builder.
     pub fn builder() -> SchemaBuilder {
         SchemaBuilder::default()
@@ -185,6 +185,77 @@ impl TokenizerName {
     }
 }
 
+/// Parameters of the BM25 scoring of a field.
+///
+/// A term occurring `freq` times in a document whose field has `dl` tokens, where the field
+/// has `avgdl` tokens on average, contributes `idf * freq * (k1 + 1) / (freq + k1 * (1 - b + b
+/// * dl / avgdl))` to the score of the document.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(try_from = "Bm25OptionsDeser")]
+pub struct Bm25Options {
+    k1: f32,
+    b: f32,
+}
+
+#[derive(Deserialize)]
+struct Bm25OptionsDeser {
+    k1: f32,
+    b: f32,
+}
+
+impl TryFrom<Bm25OptionsDeser> for Bm25Options {
+    type Error = String;
+
+    fn try_from(deser: Bm25OptionsDeser) -> Result<Bm25Options, String> {
+        let Bm25OptionsDeser { k1, b } = deser;
+        if !(k1.is_finite() && k1 >= 0.0) {
+            return Err(format!("k1 must be finite and non-negative, got {k1}"));
+        }
+        if !(0.0..=1.0).contains(&b) {
+            return Err(format!("b must be in [0, 1], got {b}"));
+        }
+        Ok(Bm25Options { k1, b })
+    }
+}
+
+// NaN is neither accepted by `Bm25Options::new` nor by deserialization.
+impl Eq for Bm25Options {}
+
+impl Default for Bm25Options {
+    fn default() -> Self {
+        Bm25Options { k1: 1.2, b: 0.75 }
+    }
+}
+
+impl Bm25Options {
+    /// Creates BM25 parameters.
+    ///
+    /// `k1` controls how quickly the contribution of a term saturates as its frequency in
+    /// the document grows, and `b` how much the length of the field of the document is
+    /// normalized by the average length of the field, from not at all for 0 to fully for 1.
+    ///
+    /// # Panics
+    /// Panics if `k1` is negative or not finite, or if `b` is not in `[0, 1]`.
+    pub fn new(k1: f32, b: f32) -> Bm25Options {
+        assert!(
+            k1.is_finite() && k1 >= 0.0,
+            "k1 must be finite and non-negative, got {k1}"
+        );
+        assert!((0.0..=1.0).contains(&b), "b must be in [0, 1], got {b}");
+        Bm25Options { k1, b }
+    }
+
+    /// Returns the term frequency saturation parameter. Default is 1.2.
+    pub fn k1(&self) -> f32 {
+        self.k1
+    }
+
+    /// Returns the length normalization parameter. Default is 0.75.
+    pub fn b(&self) -> f32 {
+        self.b
+    }
+}
+
 /// Configuration defining indexing for a text field.
 ///
 /// It defines
@@ -194,6 +265,9 @@ impl TokenizerName {
 /// - The name of the `Tokenizer` that should be used to process the field.
 /// - Flag indicating, if fieldnorms should be stored (See [fieldnorm](crate::fieldnorm)). Defaults
 ///   to `true`.
+/// - Flag indicating, if term vectors should be stored (See [termvector](crate::termvector)).
+///   Defaults to `false`.
+/// - The parameters of the BM25 scoring of the field (See [`Bm25Options`]).
 #[derive(Clone, PartialEq, Debug, Eq, Serialize, Deserialize)]
 pub struct TextFieldIndexing {
     #[serde(default)]
@@ -202,6 +276,14 @@ pub struct TextFieldIndexing {
     fieldnorms: bool,
     #[serde(default)]
     tokenizer: TokenizerName,
+    #[serde(default, skip_serializing_if = "is_false")]
+    term_vectors: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    bm25: Option<Bm25Options>,
+}
+
+fn is_false(val: &bool) -> bool {
+    !val
 }
 
 pub(crate) fn default_fieldnorms() -> bool {
@@ -214,6 +296,8 @@ impl Default for TextFieldIndexing {
             tokenizer: TokenizerName::default(),
             record: IndexRecordOption::default(),
             fieldnorms: default_fieldnorms(),
+            term_vectors: false,
+            bm25: None,
         }
     }
 }
@@ -243,6 +327,21 @@ impl TextFieldIndexing {
         self.fieldnorms
     }
 
+    /// Sets whether the term vector of the field, listing the terms of each document with
+    /// their positions and offsets, should be stored.
+    ///
+    /// See [termvector](crate::termvector).
+    #[must_use]
+    pub fn set_term_vectors(mut self, term_vectors: bool) -> TextFieldIndexing {
+        self.term_vectors = term_vectors;
+        self
+    }
+
+    /// Returns true if and only if term vectors are stored.
+    pub fn term_vectors(&self) -> bool {
+        self.term_vectors
+    }
+
     /// Sets which information should be indexed with the tokens.
     ///
     /// See [`IndexRecordOption`] for more detail.
@@ -258,6 +357,21 @@ impl TextFieldIndexing {
     pub fn index_option(&self) -> IndexRecordOption {
         self.record
     }
+
+    /// Sets the parameters of the BM25 scoring of the field.
+    ///
+    /// They are persisted with the schema, and used by all of the queries scoring the field
+    /// with BM25.
+    #[must_use]
+    pub fn set_bm25(mut self, bm25_options: Bm25Options) -> TextFieldIndexing {
+        self.bm25 = Some(bm25_options);
+        self
+    }
+
+    /// Returns the parameters of the BM25 scoring of the field.
+    pub fn bm25(&self) -> Bm25Options {
+        self.bm25.unwrap_or_default()
+    }
 }
 
 /// The field will be untokenized and indexed.
@@ -266,6 +380,8 @@ pub const STRING: TextOptions = TextOptions {
         tokenizer: TokenizerName::from_static(NO_TOKENIZER_NAME),
         fieldnorms: true,
         record: IndexRecordOption::Basic,
+        term_vectors: false,
+        bm25: None,
     }),
     stored: false,
     fast: FastFieldTextOptions::IsEnabled(false),
@@ -278,6 +394,8 @@ pub const TEXT: TextOptions = TextOptions {
         tokenizer: TokenizerName::from_static(DEFAULT_TOKENIZER_NAME),
         fieldnorms: true,
         record: IndexRecordOption::WithFreqsAndPositions,
+        term_vectors: false,
+        bm25: None,
     }),
     stored: false,
     coerce: false,
@@ -433,4 +551,32 @@ mod tests {
             serde_json::from_str(&serde_json::to_string(&options).unwrap()).unwrap();
         assert_eq!(options.fast, FastFieldTextOptions::IsEnabled(false));
     }
+
+    #[test]
+    fn serde_bm25() {
+        let indexing = TextFieldIndexing::default();
+        assert_eq!(indexing.bm25(), Bm25Options::new(1.2, 0.75));
+        assert!(!serde_json::to_string(&indexing).unwrap().contains("bm25"));
+
+        let indexing = indexing.set_bm25(Bm25Options::new(2.0, 0.5));
+        let json = serde_json::to_string(&indexing).unwrap();
+        assert!(json.ends_with(r#""bm25":{"k1":2.0,"b":0.5}}"#), "{json}");
+        let indexing_deser: TextFieldIndexing = serde_json::from_str(&json).unwrap();
+        assert_eq!(indexing_deser, indexing);
+        assert_eq!(indexing_deser.bm25().k1(), 2.0);
+    }
+
+    #[test]
+    fn serde_bm25_invalid() {
+        for bm25_json in [r#"{"k1":-1.0,"b":0.5}"#, r#"{"k1":1.2,"b":1.5}"#] {
+            let err = serde_json::from_str::<Bm25Options>(bm25_json).unwrap_err();
+            assert!(err.to_string().contains("must be"), "{err}");
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "b must be in [0, 1]")]
+    fn test_bm25_options_invalid_b() {
+        Bm25Options::new(1.2, 1.5);
+    }
 }
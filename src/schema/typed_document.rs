@@ -0,0 +1,379 @@
+use std::net::Ipv6Addr;
+
+use super::document::CompactDocValue;
+use super::{
+    DateOptions, Field, IpAddrOptions, NumericOptions, Schema, SchemaBuilder, TantivyDocument,
+    TextOptions, Value,
+};
+use crate::{DateTime, DocAddress, Searcher, TantivyError};
+
+/// Plain Rust struct mapped to the fields of a schema.
+///
+/// This trait is usually implemented with `#[derive(TypedDocument)]`, available with the
+/// `derive` feature. Each field of the struct is mapped to the schema field of the same name,
+/// with the options given by its `#[tantivy(...)]` attribute, e.g. `#[tantivy(TEXT | STORED)]`.
+/// Fields without an attribute are only stored. A field can be renamed with
+/// `#[tantivy(name = "...")]`, and excluded from the schema with `#[tantivy(skip)]`: it is then
+/// set to its default value when the document is read back.
+///
+/// The supported field types are the types implementing [`TypedValue`]: `String`, `u64`,
+/// `i64`, `f64`, `bool`, [`DateTime`] and `Ipv6Addr`, for fields with exactly one value,
+/// `Option` of them for optional fields and `Vec` of them for multivalued fields.
+///
+/// ```rust
+/// # #[cfg(feature = "derive")]
+/// # fn main() -> tantivy::Result<()> {
+/// use tantivy::collector::TopDocs;
+/// use tantivy::query::AllQuery;
+/// use tantivy::schema::TypedDocument;
+/// use tantivy::{Index, IndexWriter};
+///
+/// #[derive(TypedDocument, Debug, PartialEq)]
+/// struct Book {
+///     #[tantivy(TEXT | STORED)]
+///     title: String,
+///     #[tantivy(INDEXED | FAST | STORED)]
+///     year: u64,
+///     #[tantivy(STRING | STORED)]
+///     tags: Vec<String>,
+/// }
+///
+/// let schema = Book::schema();
+/// let index = Index::create_in_ram(schema.clone());
+/// let mut index_writer: IndexWriter = index.writer(15_000_000)?;
+/// let book = Book {
+///     title: "The Old Man and the Sea".to_string(),
+///     year: 1952,
+///     tags: vec!["novel".to_string()],
+/// };
+/// index_writer.add_document(book.to_document(&schema)?)?;
+/// index_writer.commit()?;
+///
+/// let searcher = index.reader()?.searcher();
+/// let hits = searcher.search(&AllQuery, &TopDocs::with_limit(1))?;
+/// assert_eq!(Book::fetch(&searcher, hits[0].1)?, book);
+/// # Ok(())
+/// # }
+/// # #[cfg(not(feature = "derive"))]
+/// # fn main() {}
+/// ```
+pub trait TypedDocument: Sized {
+    /// Adds the fields of the struct to `schema_builder`.
+    fn add_fields(schema_builder: &mut SchemaBuilder);
+
+    /// Builds a schema made of the fields of the struct.
+    fn schema() -> Schema {
+        let mut schema_builder = Schema::builder();
+        Self::add_fields(&mut schema_builder);
+        schema_builder.build()
+    }
+
+    /// Converts the struct into a document of `schema`.
+    ///
+    /// Returns an error if a field of the struct is missing in `schema`.
+    fn to_document(&self, schema: &Schema) -> crate::Result<TantivyDocument>;
+
+    /// Reads the struct from the stored fields of a document of `schema`.
+    ///
+    /// Returns an error if a field is missing in `schema`, or if the document does not have the
+    /// values of a field, e.g. when the field is not stored.
+    fn from_document(document: &TantivyDocument, schema: &Schema) -> crate::Result<Self>;
+
+    /// Fetches the document at `doc_address` and reads the struct from its stored fields.
+    fn fetch(searcher: &Searcher, doc_address: DocAddress) -> crate::Result<Self> {
+        let document: TantivyDocument = searcher.doc(doc_address)?;
+        Self::from_document(&document, searcher.schema())
+    }
+}
+
+/// Type of a field of a [`TypedDocument`].
+pub trait TypedValue: Sized {
+    /// Options of the schema field.
+    type Options;
+
+    /// Adds the schema field `field_name` to `schema_builder`.
+    fn add_field(
+        schema_builder: &mut SchemaBuilder,
+        field_name: &str,
+        options: Self::Options,
+    ) -> Field;
+
+    /// Adds the values of `self` to `document`.
+    fn add_to_document(&self, document: &mut TantivyDocument, field: Field);
+
+    /// Reads the value from the values of the field `field_name` of a document.
+    fn from_values(field_name: &str, values: Vec<CompactDocValue<'_>>) -> crate::Result<Self>;
+}
+
+/// Single value of a field of a [`TypedDocument`].
+///
+/// `Option` and `Vec` of the types implementing this trait are [`TypedValue`]s.
+pub trait TypedScalar: Sized {
+    /// Options of the schema field.
+    type Options;
+
+    /// Adds the schema field `field_name` to `schema_builder`.
+    fn add_field(
+        schema_builder: &mut SchemaBuilder,
+        field_name: &str,
+        options: Self::Options,
+    ) -> Field;
+
+    /// Adds `self` to the values of `field` in `document`.
+    fn add_value(&self, document: &mut TantivyDocument, field: Field);
+
+    /// Reads the value, returning `None` if it does not have the right type.
+    fn from_value(value: CompactDocValue<'_>) -> Option<Self>;
+}
+
+macro_rules! impl_typed_scalar {
+    ($scalar_type:ty, $options:ty, $add_field:ident, $add_value:expr, $from_value:expr) => {
+        impl TypedScalar for $scalar_type {
+            type Options = $options;
+
+            fn add_field(
+                schema_builder: &mut SchemaBuilder,
+                field_name: &str,
+                options: $options,
+            ) -> Field {
+                schema_builder.$add_field(field_name, options)
+            }
+
+            fn add_value(&self, document: &mut TantivyDocument, field: Field) {
+                let add_value: fn(&mut TantivyDocument, Field, &$scalar_type) = $add_value;
+                add_value(document, field, self);
+            }
+
+            fn from_value(value: CompactDocValue<'_>) -> Option<$scalar_type> {
+                let from_value: fn(CompactDocValue<'_>) -> Option<$scalar_type> = $from_value;
+                from_value(value)
+            }
+        }
+
+        impl TypedValue for $scalar_type {
+            type Options = $options;
+
+            fn add_field(
+                schema_builder: &mut SchemaBuilder,
+                field_name: &str,
+                options: $options,
+            ) -> Field {
+                <$scalar_type as TypedScalar>::add_field(schema_builder, field_name, options)
+            }
+
+            fn add_to_document(&self, document: &mut TantivyDocument, field: Field) {
+                self.add_value(document, field);
+            }
+
+            fn from_values(
+                field_name: &str,
+                values: Vec<CompactDocValue<'_>>,
+            ) -> crate::Result<$scalar_type> {
+                let mut values = values.into_iter();
+                match (values.next(), values.next()) {
+                    (Some(value), None) => scalar_from_value(field_name, value),
+                    (None, _) => Err(TantivyError::InvalidArgument(format!(
+                        "the document has no value for the field '{field_name}'"
+                    ))),
+                    (Some(_), Some(_)) => Err(TantivyError::InvalidArgument(format!(
+                        "the document has several values for the field '{field_name}'"
+                    ))),
+                }
+            }
+        }
+    };
+}
+
+impl_typed_scalar!(
+    String,
+    TextOptions,
+    add_text_field,
+    |document, field, text| document.add_text(field, text),
+    |value| value.as_str().map(str::to_string)
+);
+impl_typed_scalar!(
+    u64,
+    NumericOptions,
+    add_u64_field,
+    |document, field, &val| document.add_u64(field, val),
+    |value| value.as_u64()
+);
+impl_typed_scalar!(
+    i64,
+    NumericOptions,
+    add_i64_field,
+    |document, field, &val| document.add_i64(field, val),
+    |value| value.as_i64()
+);
+impl_typed_scalar!(
+    f64,
+    NumericOptions,
+    add_f64_field,
+    |document, field, &val| document.add_f64(field, val),
+    |value| value.as_f64()
+);
+impl_typed_scalar!(
+    bool,
+    NumericOptions,
+    add_bool_field,
+    |document, field, &val| document.add_bool(field, val),
+    |value| value.as_bool()
+);
+impl_typed_scalar!(
+    DateTime,
+    DateOptions,
+    add_date_field,
+    |document, field, &val| document.add_date(field, val),
+    |value| value.as_datetime()
+);
+impl_typed_scalar!(
+    Ipv6Addr,
+    IpAddrOptions,
+    add_ip_addr_field,
+    |document, field, &val| document.add_ip_addr(field, val),
+    |value| value.as_ip_addr()
+);
+
+fn scalar_from_value<T: TypedScalar>(
+    field_name: &str,
+    value: CompactDocValue<'_>,
+) -> crate::Result<T> {
+    T::from_value(value).ok_or_else(|| {
+        TantivyError::InvalidArgument(format!(
+            "the value of the field '{field_name}' does not have the expected type"
+        ))
+    })
+}
+
+impl<T: TypedScalar> TypedValue for Option<T> {
+    type Options = T::Options;
+
+    fn add_field(
+        schema_builder: &mut SchemaBuilder,
+        field_name: &str,
+        options: T::Options,
+    ) -> Field {
+        T::add_field(schema_builder, field_name, options)
+    }
+
+    fn add_to_document(&self, document: &mut TantivyDocument, field: Field) {
+        if let Some(value) = self {
+            value.add_value(document, field);
+        }
+    }
+
+    fn from_values(field_name: &str, values: Vec<CompactDocValue<'_>>) -> crate::Result<Self> {
+        let mut values = values.into_iter();
+        match (values.next(), values.next()) {
+            (None, _) => Ok(None),
+            (Some(value), None) => scalar_from_value(field_name, value).map(Some),
+            (Some(_), Some(_)) => Err(TantivyError::InvalidArgument(format!(
+                "the document has several values for the field '{field_name}'"
+            ))),
+        }
+    }
+}
+
+impl<T: TypedScalar> TypedValue for Vec<T> {
+    type Options = T::Options;
+
+    fn add_field(
+        schema_builder: &mut SchemaBuilder,
+        field_name: &str,
+        options: T::Options,
+    ) -> Field {
+        T::add_field(schema_builder, field_name, options)
+    }
+
+    fn add_to_document(&self, document: &mut TantivyDocument, field: Field) {
+        for value in self {
+            value.add_value(document, field);
+        }
+    }
+
+    fn from_values(field_name: &str, values: Vec<CompactDocValue<'_>>) -> crate::Result<Self> {
+        values
+            .into_iter()
+            .map(|value| scalar_from_value(field_name, value))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{TypedDocument, TypedValue};
+    use crate::schema::{Schema, SchemaBuilder, TantivyDocument, FAST, INDEXED, STORED, TEXT};
+
+    /// Implementation of what `#[derive(TypedDocument)]` generates.
+    #[derive(Debug, PartialEq)]
+    struct Book {
+        title: String,
+        year: Option<u64>,
+        ratings: Vec<f64>,
+    }
+
+    impl TypedDocument for Book {
+        fn add_fields(schema_builder: &mut SchemaBuilder) {
+            <String as TypedValue>::add_field(schema_builder, "title", (TEXT | STORED).into());
+            <Option<u64> as TypedValue>::add_field(
+                schema_builder,
+                "year",
+                (INDEXED | FAST | STORED).into(),
+            );
+            <Vec<f64> as TypedValue>::add_field(schema_builder, "ratings", STORED.into());
+        }
+
+        fn to_document(&self, schema: &Schema) -> crate::Result<TantivyDocument> {
+            let mut document = TantivyDocument::new();
+            self.title
+                .add_to_document(&mut document, schema.get_field("title")?);
+            self.year
+                .add_to_document(&mut document, schema.get_field("year")?);
+            self.ratings
+                .add_to_document(&mut document, schema.get_field("ratings")?);
+            Ok(document)
+        }
+
+        fn from_document(document: &TantivyDocument, schema: &Schema) -> crate::Result<Self> {
+            Ok(Book {
+                title: TypedValue::from_values(
+                    "title",
+                    document.get_all(schema.get_field("title")?).collect(),
+                )?,
+                year: TypedValue::from_values(
+                    "year",
+                    document.get_all(schema.get_field("year")?).collect(),
+                )?,
+                ratings: TypedValue::from_values(
+                    "ratings",
+                    document.get_all(schema.get_field("ratings")?).collect(),
+                )?,
+            })
+        }
+    }
+
+    #[test]
+    fn test_typed_document() -> crate::Result<()> {
+        let schema = Book::schema();
+        let year_entry = schema.get_field_entry(schema.get_field("year")?);
+        assert!(year_entry.is_indexed() && year_entry.is_fast());
+        for book in [
+            Book {
+                title: "The Old Man and the Sea".to_string(),
+                year: Some(1952),
+                ratings: vec![4.5, 3.0],
+            },
+            Book {
+                title: "A Moveable Feast".to_string(),
+                year: None,
+                ratings: Vec::new(),
+            },
+        ] {
+            let document = book.to_document(&schema)?;
+            assert_eq!(Book::from_document(&document, &schema)?, book);
+        }
+        let no_title = TantivyDocument::new();
+        assert!(Book::from_document(&no_title, &schema).is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,183 @@
+use super::{Snippet, SnippetGenerator};
+use crate::query::Query;
+use crate::schema::document::Document;
+use crate::schema::Field;
+use crate::Searcher;
+
+/// The snippet of one of the fields of a document, as returned by a [`Highlighter`].
+#[derive(Debug)]
+pub struct FieldSnippet {
+    /// The highlighted field.
+    pub field: Field,
+    /// The snippet generated for the field.
+    pub snippet: Snippet,
+}
+
+/// Highlights several fields of a document for a given query.
+///
+/// The highlighter holds a [`SnippetGenerator`] for each field, which can each be given
+/// their own maximum number of chars and their own tags. The snippets of all the fields
+/// can also share a global budget of chars, see [`Highlighter::set_max_total_num_chars`].
+///
+/// ```rust
+/// # use tantivy::query::QueryParser;
+/// # use tantivy::schema::{Schema, TEXT};
+/// # use tantivy::{doc, Index};
+/// use tantivy::snippet::Highlighter;
+///
+/// # fn main() -> tantivy::Result<()> {
+/// #    let mut schema_builder = Schema::builder();
+/// #    let title = schema_builder.add_text_field("title", TEXT);
+/// #    let body = schema_builder.add_text_field("body", TEXT);
+/// #    let index = Index::create_in_ram(schema_builder.build());
+/// #    let mut index_writer = index.writer_with_num_threads(1, 20_000_000)?;
+/// #    let doc = doc!(title => "The Rust book", body => "Rust is a systems language.");
+/// #    index_writer.add_document(doc.clone())?;
+/// #    index_writer.commit()?;
+/// #    let searcher = index.reader()?.searcher();
+/// let query = QueryParser::for_index(&index, vec![title, body]).parse_query("rust")?;
+/// let mut highlighter = Highlighter::create(&searcher, &*query, &[title, body])?;
+/// highlighter.set_field_tags(title, "<em>", "</em>");
+/// let snippets = highlighter.highlight(&doc);
+/// assert_eq!(snippets[0].snippet.to_html(), "The <em>Rust</em> book");
+/// assert_eq!(snippets[1].snippet.to_html(), "<b>Rust</b> is a systems language");
+/// #    Ok(())
+/// # }
+/// ```
+pub struct Highlighter {
+    snippet_generators: Vec<SnippetGenerator>,
+    max_total_num_chars: Option<usize>,
+}
+
+impl Highlighter {
+    /// Creates a highlighter for the given fields, in order of priority.
+    pub fn create(
+        searcher: &Searcher,
+        query: &dyn Query,
+        fields: &[Field],
+    ) -> crate::Result<Highlighter> {
+        let snippet_generators = fields
+            .iter()
+            .map(|&field| SnippetGenerator::create(searcher, query, field))
+            .collect::<crate::Result<Vec<_>>>()?;
+        Ok(Highlighter {
+            snippet_generators,
+            max_total_num_chars: None,
+        })
+    }
+
+    /// Returns the snippet generator of `field`, to configure it further.
+    ///
+    /// Returns `None` if the highlighter was not created for `field`.
+    pub fn snippet_generator_mut(&mut self, field: Field) -> Option<&mut SnippetGenerator> {
+        self.snippet_generators
+            .iter_mut()
+            .find(|snippet_generator| snippet_generator.field() == field)
+    }
+
+    /// Sets the maximum number of chars of the snippets of `field`.
+    ///
+    /// Does nothing if the highlighter was not created for `field`.
+    pub fn set_max_num_chars(&mut self, field: Field, max_num_chars: usize) {
+        if let Some(snippet_generator) = self.snippet_generator_mut(field) {
+            snippet_generator.set_max_num_chars(max_num_chars);
+        }
+    }
+
+    /// Sets the tags surrounding the highlighted parts of the snippets of `field`.
+    ///
+    /// Does nothing if the highlighter was not created for `field`.
+    pub fn set_field_tags(&mut self, field: Field, prefix: &str, postfix: &str) {
+        if let Some(snippet_generator) = self.snippet_generator_mut(field) {
+            snippet_generator.set_tags(prefix, postfix);
+        }
+    }
+
+    /// Sets the maximum number of chars of all of the snippets of a document taken
+    /// together.
+    ///
+    /// The fields are highlighted in order of priority, each one using at most the
+    /// budget the previous fields left. Fields without any match do not use any budget.
+    pub fn set_max_total_num_chars(&mut self, max_total_num_chars: usize) {
+        self.max_total_num_chars = Some(max_total_num_chars);
+    }
+
+    /// Highlights the fields of `doc`.
+    ///
+    /// Returns the non-empty snippets, in order of priority of their fields.
+    pub fn highlight<D: Document>(&self, doc: &D) -> Vec<FieldSnippet> {
+        let mut remaining_num_chars = self.max_total_num_chars.unwrap_or(usize::MAX);
+        let mut field_snippets = Vec::new();
+        for snippet_generator in &self.snippet_generators {
+            if remaining_num_chars == 0 {
+                break;
+            }
+            let max_num_chars = snippet_generator.max_num_chars().min(remaining_num_chars);
+            let text = snippet_generator.text_from_doc(doc);
+            let snippet = snippet_generator.snippet_with_max_num_chars(&text, max_num_chars);
+            if snippet.is_empty() {
+                continue;
+            }
+            remaining_num_chars -= snippet.fragment().len().min(remaining_num_chars);
+            field_snippets.push(FieldSnippet {
+                field: snippet_generator.field(),
+                snippet,
+            });
+        }
+        field_snippets
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Highlighter;
+    use crate::query::QueryParser;
+    use crate::schema::{Schema, TEXT};
+    use crate::{doc, Index, IndexWriter};
+
+    #[test]
+    fn test_highlighter() -> crate::Result<()> {
+        let mut schema_builder = Schema::builder();
+        let title = schema_builder.add_text_field("title", TEXT);
+        let body = schema_builder.add_text_field("body", TEXT);
+        let tags = schema_builder.add_text_field("tags", TEXT);
+        let index = Index::create_in_ram(schema_builder.build());
+        let doc = doc!(
+            title => "Rust in action",
+            body => "Learn systems programming with rust. Rust is fast and rust is safe.",
+            tags => "programming",
+        );
+        let mut index_writer: IndexWriter = index.writer_for_tests()?;
+        index_writer.add_document(doc.clone())?;
+        index_writer.commit()?;
+        let searcher = index.reader()?.searcher();
+        let query = QueryParser::for_index(&index, vec![title, body, tags]).parse_query("rust")?;
+
+        let mut highlighter = Highlighter::create(&searcher, &*query, &[title, body, tags])?;
+        highlighter.set_field_tags(body, "<em>", "</em>");
+        let snippets = highlighter.highlight(&doc);
+        // `tags` has no match.
+        assert_eq!(snippets.len(), 2);
+        assert_eq!(snippets[0].field, title);
+        assert_eq!(snippets[0].snippet.to_html(), "<b>Rust</b> in action");
+        assert_eq!(snippets[1].field, body);
+        assert_eq!(
+            snippets[1].snippet.to_html(),
+            "Learn systems programming with <em>rust</em>. <em>Rust</em> is fast and \
+             <em>rust</em> is safe"
+        );
+
+        // The title uses 14 of the 30 chars, leaving 16 chars to the body.
+        highlighter.set_max_total_num_chars(30);
+        let snippets = highlighter.highlight(&doc);
+        assert_eq!(snippets.len(), 2);
+        assert!(snippets[1].snippet.fragment().len() <= 16);
+        assert!(!snippets[1].snippet.is_empty());
+
+        highlighter.set_max_total_num_chars(14);
+        let snippets = highlighter.highlight(&doc);
+        assert_eq!(snippets.len(), 1);
+        assert_eq!(snippets[0].field, title);
+        Ok(())
+    }
+}
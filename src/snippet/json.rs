@@ -0,0 +1,169 @@
+use std::collections::BTreeMap;
+use std::str;
+
+use common::json_path_writer::JSON_END_OF_PATH;
+use common::JsonPathWriter;
+
+use super::{search_fragments, Snippet, SnippetGenerator};
+use crate::core::json_utils::json_path_sep_to_dot;
+use crate::schema::document::{Document, ReferenceValue, Value};
+use crate::{Score, Searcher, Term};
+
+/// The snippet of a string value nested in a JSON field.
+#[derive(Debug)]
+pub struct JsonSnippet {
+    /// Path of the value in the JSON field, with segments separated by dots.
+    pub path: String,
+    /// The snippet generated for the value.
+    pub snippet: Snippet,
+}
+
+impl SnippetGenerator {
+    /// Generates snippets for the string values of the JSON field of the `SnippetGenerator`
+    /// in the given `Document`.
+    ///
+    /// Each value is only highlighted with the terms of the query targeting its path.
+    /// Returns a snippet for each value with a match, in the order of the document.
+    pub fn json_snippets_from_doc<D: Document>(&self, doc: &D) -> Vec<JsonSnippet> {
+        let mut json_snippets = Vec::new();
+        if self.json_terms_text.is_empty() {
+            return json_snippets;
+        }
+        let mut json_path_writer = JsonPathWriter::with_expand_dots(self.json_expand_dots);
+        for (field, value) in doc.iter_fields_and_values() {
+            let value = value as D::Value<'_>;
+            if field != self.field {
+                continue;
+            }
+            json_path_writer.clear();
+            self.collect_json_snippets(value, &mut json_path_writer, &mut json_snippets);
+        }
+        json_snippets
+    }
+
+    fn collect_json_snippets<'a, V: Value<'a>>(
+        &self,
+        json_value: V,
+        json_path_writer: &mut JsonPathWriter,
+        json_snippets: &mut Vec<JsonSnippet>,
+    ) {
+        match json_value.as_value() {
+            ReferenceValue::Leaf(leaf) => {
+                let Some(text) = leaf.as_str() else {
+                    return;
+                };
+                let Some(terms) = self.json_terms_text.get(json_path_writer.as_str()) else {
+                    return;
+                };
+                let fragment_candidates =
+                    search_fragments(&mut self.tokenizer.clone(), text, terms, self.max_num_chars);
+                if fragment_candidates.is_empty() {
+                    return;
+                }
+                let mut path = json_path_writer.as_str().to_string();
+                json_path_sep_to_dot(&mut path);
+                json_snippets.push(JsonSnippet {
+                    path,
+                    snippet: self.select_snippet(&fragment_candidates, text),
+                });
+            }
+            ReferenceValue::Array(elements) => {
+                for element in elements {
+                    self.collect_json_snippets(element, json_path_writer, json_snippets);
+                }
+            }
+            ReferenceValue::Object(object) => {
+                for (json_path_segment, json_value) in object {
+                    if json_path_segment.as_bytes().contains(&JSON_END_OF_PATH) {
+                        continue;
+                    }
+                    json_path_writer.push(json_path_segment);
+                    self.collect_json_snippets(json_value, json_path_writer, json_snippets);
+                    json_path_writer.pop();
+                }
+            }
+        }
+    }
+}
+
+/// Adds the string value of the JSON term `term` to `json_terms_text`, under its path,
+/// if it appears in the index.
+pub(super) fn add_json_term(
+    searcher: &Searcher,
+    term: &Term,
+    json_terms_text: &mut BTreeMap<String, BTreeMap<String, Score>>,
+) -> crate::Result<()> {
+    let term_value = term.value();
+    let Some((json_path_bytes, json_value)) = term_value.as_json() else {
+        return Ok(());
+    };
+    let Some(term_str) = json_value.as_str() else {
+        return Ok(());
+    };
+    // Remove the JSON_END_OF_PATH byte.
+    let Ok(json_path) = str::from_utf8(&json_path_bytes[..json_path_bytes.len() - 1]) else {
+        return Ok(());
+    };
+    let doc_freq = searcher.doc_freq(term)?;
+    if doc_freq > 0 {
+        let score = 1.0 / (1.0 + doc_freq as Score);
+        json_terms_text
+            .entry(json_path.to_string())
+            .or_default()
+            .insert(term_str.to_string(), score);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use crate::query::QueryParser;
+    use crate::schema::{Schema, STORED, TEXT};
+    use crate::snippet::SnippetGenerator;
+    use crate::{doc, Index, IndexWriter};
+
+    #[test]
+    fn test_json_snippets() -> crate::Result<()> {
+        let mut schema_builder = Schema::builder();
+        let attributes = schema_builder.add_json_field("attributes", TEXT | STORED);
+        let title = schema_builder.add_text_field("title", TEXT);
+        let index = Index::create_in_ram(schema_builder.build());
+        let doc = doc!(
+            title => "red",
+            attributes => json!({
+                "color": "red",
+                "description": "Bright red shoes, the reddest of all red shoes.",
+                "nested": {"tags": ["blue", "dark red"]},
+                "other": "red",
+            }),
+        );
+        let mut index_writer: IndexWriter = index.writer_for_tests()?;
+        index_writer.add_document(doc.clone())?;
+        index_writer.commit()?;
+        let searcher = index.reader()?.searcher();
+        let query_parser = QueryParser::for_index(&index, vec![title]);
+        let query = query_parser.parse_query(
+            "title:red attributes.description:red attributes.nested.tags:red attributes.color:blue",
+        )?;
+        let snippet_generator = SnippetGenerator::create(&searcher, &*query, attributes)?;
+        assert!(snippet_generator.snippet_from_doc(&doc).is_empty());
+        let json_snippets = snippet_generator.json_snippets_from_doc(&doc);
+        let snippets: Vec<(&str, String)> = json_snippets
+            .iter()
+            .map(|json_snippet| (json_snippet.path.as_str(), json_snippet.snippet.to_html()))
+            .collect();
+        assert_eq!(
+            snippets,
+            vec![
+                (
+                    "description",
+                    "Bright <b>red</b> shoes, the reddest of all <b>red</b> shoes".to_string()
+                ),
+                ("nested.tags", "dark <b>red</b>".to_string()),
+            ]
+        );
+        Ok(())
+    }
+}
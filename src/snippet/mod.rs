@@ -53,25 +53,68 @@
 //!
 //! SnippetGenerator needs to be created from the `Searcher` and the query, and the field on which
 //! the `SnippetGenerator` should generate the snippets.
+//!
+//! To highlight several fields of a document at once, use a [`Highlighter`]. The string
+//! values nested in a JSON field are highlighted with
+//! [`SnippetGenerator::json_snippets_from_doc`].
+
+mod highlighter;
+mod json;
 
 use std::cmp::Ordering;
-use std::collections::{BTreeMap, BTreeSet};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 use std::ops::Range;
 
 use htmlescape::encode_minimal;
 
+pub use self::highlighter::{FieldSnippet, Highlighter};
+pub use self::json::JsonSnippet;
 use crate::query::Query;
 use crate::schema::document::{Document, Value};
-use crate::schema::Field;
+use crate::schema::{Field, FieldType, Type};
+use crate::termvector::TermVector;
 use crate::tokenizer::{TextAnalyzer, Token};
-use crate::{Score, Searcher, Term};
+use crate::{DocId, Score, Searcher, SegmentReader, Term};
 
 const DEFAULT_MAX_NUM_CHARS: usize = 150;
 
 const DEFAULT_SNIPPET_PREFIX: &str = "<b>";
 const DEFAULT_SNIPPET_POSTFIX: &str = "</b>";
 
-#[derive(Debug)]
+/// Separates the fragments of a snippet made of several fragments.
+const FRAGMENT_SEPARATOR: &str = " ... ";
+
+/// Order of the fragments of a snippet made of several fragments.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum FragmentOrder {
+    /// The best scoring fragments come first.
+    #[default]
+    Score,
+    /// The fragments appear in the order of the text.
+    Position,
+}
+
+/// Controls how the fragments of a snippet are selected.
+#[derive(Clone, Debug)]
+struct FragmentOptions {
+    max_num_fragments: usize,
+    order: FragmentOrder,
+    no_match_size: usize,
+    merge_adjacent: bool,
+}
+
+impl Default for FragmentOptions {
+    fn default() -> Self {
+        FragmentOptions {
+            max_num_fragments: 1,
+            order: FragmentOrder::Score,
+            no_match_size: 0,
+            merge_adjacent: false,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
 pub(crate) struct FragmentCandidate {
     score: Score,
     start_offset: usize,
@@ -96,19 +139,64 @@ impl FragmentCandidate {
 
     /// Updates `score` and `highlighted` fields of the objects.
     ///
-    /// taking the token and terms, the token is added to the fragment.
-    /// if the token is one of the terms, the score
+    /// The token spanning `offsets` is added to the fragment.
+    /// If the token should be highlighted, as indicated by `score`, the score
     /// and highlighted fields are updated in the fragment.
-    fn try_add_token(&mut self, token: &Token, terms: &BTreeMap<String, Score>) {
-        self.stop_offset = token.offset_to;
+    fn add_token(&mut self, offsets: Range<usize>, score: Option<Score>) {
+        self.stop_offset = offsets.end;
 
-        if let Some(&score) = terms.get(&token.text.to_lowercase()) {
+        if let Some(score) = score {
             self.score += score;
-            self.highlighted.push(token.offset_from..token.offset_to);
+            self.highlighted.push(offsets);
         }
     }
 }
 
+/// Splits a text into fragment candidates of at most `max_num_chars`, given its tokens
+/// in order.
+struct FragmentsBuilder {
+    max_num_chars: usize,
+    fragment: FragmentCandidate,
+    fragments: Vec<FragmentCandidate>,
+}
+
+impl FragmentsBuilder {
+    fn new(max_num_chars: usize) -> FragmentsBuilder {
+        FragmentsBuilder {
+            max_num_chars,
+            fragment: FragmentCandidate::new(0),
+            fragments: Vec::new(),
+        }
+    }
+
+    fn add_token(&mut self, offsets: Range<usize>, score: Option<Score>) {
+        if (offsets.end - self.fragment.start_offset) > self.max_num_chars {
+            let fragment =
+                std::mem::replace(&mut self.fragment, FragmentCandidate::new(offsets.start));
+            if fragment.score > 0.0 {
+                self.fragments.push(fragment);
+            }
+        }
+        self.fragment.add_token(offsets, score);
+    }
+
+    /// Returns the fragments with at least one highlighted token.
+    fn build(mut self) -> Vec<FragmentCandidate> {
+        if self.fragment.score > 0.0 {
+            self.fragments.push(self.fragment);
+        }
+        self.fragments
+    }
+}
+
+/// A phrase of the query. Its terms are only highlighted where the whole phrase matches.
+#[derive(Clone, Debug)]
+struct PhraseTerms {
+    /// The terms of the phrase, with their offset in the phrase and their score.
+    terms: Vec<(usize, String, Score)>,
+    slop: u32,
+}
+
 /// `Snippet`
 /// Contains a fragment of a document, and some highlighted parts inside it.
 #[derive(Debug)]
@@ -141,11 +229,17 @@ impl Snippet {
     }
 
     /// Returns `true` if the snippet is empty.
+    ///
+    /// A snippet is empty if nothing matched in the text, unless a fallback was
+    /// requested with [`SnippetGenerator::set_no_match_size`].
     pub fn is_empty(&self) -> bool {
-        self.highlighted.len() == 0
+        self.fragment.is_empty()
     }
 
     /// Returns a highlighted html from the `Snippet`.
+    ///
+    /// The text of the fragment is HTML-escaped, while the prefix and postfix surrounding
+    /// the highlighted parts are inserted as is.
     pub fn to_html(&self) -> String {
         let mut html = String::new();
         let mut start_from: usize = 0;
@@ -173,6 +267,25 @@ impl Snippet {
         &self.highlighted
     }
 
+    /// Returns the highlighted parts of the fragment as char offsets, sorted and without
+    /// overlaps.
+    ///
+    /// This is meant for consumers rendering the highlighted parts themselves, instead of
+    /// parsing the output of [`Snippet::to_html`].
+    pub fn highlighted_char_ranges(&self) -> Vec<Range<usize>> {
+        let mut char_ranges = Vec::new();
+        let mut num_chars = 0;
+        let mut start_from = 0;
+        for item in collapse_overlapped_ranges(&self.highlighted) {
+            num_chars += self.fragment[start_from..item.start].chars().count();
+            let start = num_chars;
+            num_chars += self.fragment[item.clone()].chars().count();
+            char_ranges.push(start..num_chars);
+            start_from = item.end;
+        }
+        char_ranges
+    }
+
     /// Sets highlighted prefix and postfix.
     pub fn set_snippet_prefix_postfix(&mut self, prefix: &str, postfix: &str) {
         self.snippet_prefix = prefix.to_string();
@@ -208,22 +321,127 @@ fn search_fragments(
     max_num_chars: usize,
 ) -> Vec<FragmentCandidate> {
     let mut token_stream = tokenizer.token_stream(text);
-    let mut fragment = FragmentCandidate::new(0);
-    let mut fragments: Vec<FragmentCandidate> = vec![];
+    let mut fragments = FragmentsBuilder::new(max_num_chars);
     while let Some(next) = token_stream.next() {
-        if (next.offset_to - fragment.start_offset) > max_num_chars {
-            if fragment.score > 0.0 {
-                fragments.push(fragment)
+        let score = terms.get(&next.text.to_lowercase()).copied();
+        fragments.add_token(next.offset_from..next.offset_to, score);
+    }
+    fragments.build()
+}
+
+/// Same as [`search_fragments`], except that the terms of `phrases` are only highlighted
+/// where the whole phrase matches.
+fn search_fragments_with_phrases(
+    tokenizer: &mut TextAnalyzer,
+    text: &str,
+    terms: &BTreeMap<String, Score>,
+    phrases: &[PhraseTerms],
+    max_num_chars: usize,
+) -> Vec<FragmentCandidate> {
+    let mut tokens: Vec<Token> = Vec::new();
+    tokenizer
+        .token_stream(text)
+        .process(&mut |token| tokens.push(token.clone()));
+    fragments_from_tokens(&tokens, terms, phrases, max_num_chars)
+}
+
+/// Returns the fragment candidates of a text, given its tokens sorted by offset.
+fn fragments_from_tokens(
+    tokens: &[Token],
+    terms: &BTreeMap<String, Score>,
+    phrases: &[PhraseTerms],
+    max_num_chars: usize,
+) -> Vec<FragmentCandidate> {
+    let scores = token_scores(tokens, terms, phrases);
+    let mut fragments = FragmentsBuilder::new(max_num_chars);
+    for (token, score) in tokens.iter().zip(scores) {
+        fragments.add_token(token.offset_from..token.offset_to, score);
+    }
+    fragments.build()
+}
+
+/// Rebuilds the tokens of `text` from a term vector of the field.
+///
+/// `value_offsets` gives, for each value of the field, the range of `text` it spans, if
+/// any. Occurrences that do not fit in their value are ignored.
+fn tokens_from_term_vector(
+    term_vector: &TermVector,
+    value_offsets: &[Option<Range<usize>>],
+) -> Vec<Token> {
+    let mut tokens = Vec::new();
+    for entry in term_vector.entries() {
+        for occurrence in &entry.occurrences {
+            let Some(Some(value_range)) = value_offsets.get(occurrence.value_ord as usize) else {
+                continue;
             };
-            fragment = FragmentCandidate::new(next.offset_from);
+            if occurrence.offset_to > value_range.len() {
+                continue;
+            }
+            tokens.push(Token {
+                offset_from: value_range.start + occurrence.offset_from,
+                offset_to: value_range.start + occurrence.offset_to,
+                position: occurrence.position as usize,
+                text: entry.term.clone(),
+                position_length: 1,
+            });
         }
-        fragment.try_add_token(next, terms);
-    }
-    if fragment.score > 0.0 {
-        fragments.push(fragment)
     }
+    tokens.sort_by_key(|token| (token.offset_from, token.position));
+    tokens
+}
 
-    fragments
+/// Returns the score of each token, or `None` if the token should not be highlighted.
+///
+/// A token is highlighted if it is one of `terms`, or if it is part of a match of one of
+/// the `phrases`. The terms of a sloppy phrase match if each of them is within `slop`
+/// positions of where it is expected in the phrase.
+fn token_scores(
+    tokens: &[Token],
+    terms: &BTreeMap<String, Score>,
+    phrases: &[PhraseTerms],
+) -> Vec<Option<Score>> {
+    let texts: Vec<String> = tokens
+        .iter()
+        .map(|token| token.text.to_lowercase())
+        .collect();
+    let mut scores: Vec<Option<Score>> =
+        texts.iter().map(|text| terms.get(text).copied()).collect();
+    let mut occurrences: HashMap<&str, Vec<usize>> = HashMap::new();
+    for (ord, text) in texts.iter().enumerate() {
+        occurrences.entry(text.as_str()).or_default().push(ord);
+    }
+    for phrase in phrases {
+        let Some((first_offset, first_term, _)) = phrase.terms.first() else {
+            continue;
+        };
+        let Some(starts) = occurrences.get(first_term.as_str()) else {
+            continue;
+        };
+        for &start in starts {
+            let Some(phrase_position) = tokens[start].position.checked_sub(*first_offset) else {
+                continue;
+            };
+            let phrase_match: Option<Vec<(usize, Score)>> = phrase
+                .terms
+                .iter()
+                .map(|(offset, term, score)| {
+                    let expected_position = phrase_position + offset;
+                    occurrences
+                        .get(term.as_str())?
+                        .iter()
+                        .copied()
+                        .find(|&ord| {
+                            tokens[ord].position.abs_diff(expected_position) <= phrase.slop as usize
+                        })
+                        .map(|ord| (ord, *score))
+                })
+                .collect();
+            for (ord, score) in phrase_match.into_iter().flatten() {
+                scores[ord] = Some(scores[ord].map_or(score, |previous| previous.max(score)));
+            }
+        }
+    }
+    scores
 }
 
 /// Returns a Snippet
@@ -231,30 +449,103 @@ fn search_fragments(
 /// Takes a vector of `FragmentCandidate`s and the text.
 /// Figures out the best fragment from it and creates a snippet.
 fn select_best_fragment_combination(fragments: &[FragmentCandidate], text: &str) -> Snippet {
-    let best_fragment_opt = fragments.iter().max_by(|left, right| {
-        let cmp_score = left
-            .score
-            .partial_cmp(&right.score)
-            .unwrap_or(Ordering::Equal);
-        if cmp_score == Ordering::Equal {
-            (right.start_offset, right.stop_offset).cmp(&(left.start_offset, left.stop_offset))
-        } else {
-            cmp_score
-        }
-    });
-    if let Some(fragment) = best_fragment_opt {
-        let fragment_text = &text[fragment.start_offset..fragment.stop_offset];
-        let highlighted = fragment
-            .highlighted
-            .iter()
-            .map(|item| item.start - fragment.start_offset..item.end - fragment.start_offset)
-            .collect();
-        Snippet::new(fragment_text, highlighted)
+    select_fragments(fragments, text, &FragmentOptions::default())
+}
+
+/// Orders fragments by decreasing score, then by position.
+fn cmp_fragments_by_score(left: &FragmentCandidate, right: &FragmentCandidate) -> Ordering {
+    let cmp_score = right
+        .score
+        .partial_cmp(&left.score)
+        .unwrap_or(Ordering::Equal);
+    if cmp_score == Ordering::Equal {
+        (left.start_offset, left.stop_offset).cmp(&(right.start_offset, right.stop_offset))
     } else {
-        // When there are no fragments to chose from,
-        // for now create an empty snippet.
-        Snippet::empty()
+        cmp_score
+    }
+}
+
+/// Returns a Snippet made of the best fragments, as configured by `options`.
+///
+/// The selected fragments are joined with [`FRAGMENT_SEPARATOR`].
+fn select_fragments(
+    fragments: &[FragmentCandidate],
+    text: &str,
+    options: &FragmentOptions,
+) -> Snippet {
+    if fragments.is_empty() {
+        return no_match_snippet(text, options.no_match_size);
+    }
+    let mut selected: Vec<FragmentCandidate> = fragments.to_vec();
+    selected.sort_by(cmp_fragments_by_score);
+    selected.truncate(options.max_num_fragments.max(1));
+    selected.sort_by_key(|fragment| fragment.start_offset);
+    if options.merge_adjacent {
+        selected = merge_adjacent_fragments(selected, text);
+    }
+    if options.order == FragmentOrder::Score {
+        selected.sort_by(cmp_fragments_by_score);
+    }
+    let mut snippet_text = String::new();
+    let mut highlighted = Vec::new();
+    for fragment in &selected {
+        if !snippet_text.is_empty() {
+            snippet_text.push_str(FRAGMENT_SEPARATOR);
+        }
+        let base_offset = snippet_text.len();
+        snippet_text.push_str(&text[fragment.start_offset..fragment.stop_offset]);
+        highlighted.extend(fragment.highlighted.iter().map(|item| {
+            base_offset + item.start - fragment.start_offset
+                ..base_offset + item.end - fragment.start_offset
+        }));
+    }
+    Snippet::new(&snippet_text, highlighted)
+}
+
+/// Merges the fragments, sorted by position, that are only separated by whitespace and
+/// punctuation.
+fn merge_adjacent_fragments(
+    fragments: Vec<FragmentCandidate>,
+    text: &str,
+) -> Vec<FragmentCandidate> {
+    let mut merged: Vec<FragmentCandidate> = Vec::with_capacity(fragments.len());
+    for fragment in fragments {
+        if let Some(last) = merged.last_mut() {
+            let is_adjacent = last.stop_offset >= fragment.start_offset
+                || !text[last.stop_offset..fragment.start_offset]
+                    .chars()
+                    .any(char::is_alphanumeric);
+            if is_adjacent {
+                last.score += fragment.score;
+                last.stop_offset = last.stop_offset.max(fragment.stop_offset);
+                last.highlighted.extend(fragment.highlighted);
+                continue;
+            }
+        }
+        merged.push(fragment);
+    }
+    merged
+}
+
+/// Returns a snippet made of the beginning of `text`, without any highlighted part.
+///
+/// The text is cut after at most `no_match_size` chars, at the last whitespace if possible.
+/// The snippet is empty if `no_match_size` is 0.
+fn no_match_snippet(text: &str, no_match_size: usize) -> Snippet {
+    if no_match_size == 0 {
+        return Snippet::empty();
+    }
+    let end = text
+        .char_indices()
+        .nth(no_match_size)
+        .map_or(text.len(), |(offset, _)| offset);
+    let mut prefix = &text[..end];
+    if end < text.len() {
+        if let Some(whitespace_offset) = prefix.rfind(char::is_whitespace) {
+            prefix = &prefix[..whitespace_offset];
+        }
     }
+    Snippet::new(prefix.trim_end(), Vec::new())
 }
 
 /// Returns ranges that are collapsed into non-overlapped ranges.
@@ -347,9 +638,16 @@ fn is_sorted(mut it: impl Iterator<Item = usize>) -> bool {
 /// ```
 pub struct SnippetGenerator {
     terms_text: BTreeMap<String, Score>,
+    phrases: Vec<PhraseTerms>,
+    /// Terms of a JSON field, by JSON path.
+    json_terms_text: BTreeMap<String, BTreeMap<String, Score>>,
+    json_expand_dots: bool,
     tokenizer: TextAnalyzer,
     field: Field,
     max_num_chars: usize,
+    fragment_options: FragmentOptions,
+    snippet_prefix: String,
+    snippet_postfix: String,
 }
 
 impl SnippetGenerator {
@@ -362,43 +660,81 @@ impl SnippetGenerator {
     ) -> Self {
         SnippetGenerator {
             terms_text,
+            phrases: Vec::new(),
+            json_terms_text: BTreeMap::new(),
+            json_expand_dots: false,
             tokenizer,
             field,
             max_num_chars,
+            fragment_options: FragmentOptions::default(),
+            snippet_prefix: DEFAULT_SNIPPET_PREFIX.to_string(),
+            snippet_postfix: DEFAULT_SNIPPET_POSTFIX.to_string(),
         }
     }
     /// Creates a new snippet generator
+    ///
+    /// The terms of the phrases of the query are only highlighted where the whole phrase
+    /// matches, unless they also appear outside of a phrase in the query.
     pub fn create(
         searcher: &Searcher,
         query: &dyn Query,
         field: Field,
     ) -> crate::Result<SnippetGenerator> {
+        let mut query_phrases: Vec<(&[(usize, Term)], u32)> = Vec::new();
+        query.query_phrases(&mut |phrase_terms, slop| {
+            if phrase_terms.first().map_or(false, |(_, term)| {
+                term.field() == field && term.typ() == Type::Str
+            }) {
+                query_phrases.push((phrase_terms, slop));
+            }
+        });
+        let phrase_terms: BTreeSet<&Term> = query_phrases
+            .iter()
+            .flat_map(|(phrase_terms, _)| phrase_terms.iter().map(|(_, term)| term))
+            .collect();
         let mut terms: BTreeSet<&Term> = BTreeSet::new();
-        query.query_terms(&mut |term, _| {
-            if term.field() == field {
+        query.query_terms(&mut |term, need_positions| {
+            if term.field() == field && !(need_positions && phrase_terms.contains(term)) {
                 terms.insert(term);
             }
         });
         let mut terms_text: BTreeMap<String, Score> = Default::default();
+        let mut json_terms_text: BTreeMap<String, BTreeMap<String, Score>> = Default::default();
         for term in terms {
-            let term_value = term.value();
-            let term_str = if let Some(term_str) = term_value.as_str() {
-                term_str
-            } else {
-                continue;
-            };
-            let doc_freq = searcher.doc_freq(term)?;
-            if doc_freq > 0 {
-                let score = 1.0 / (1.0 + doc_freq as Score);
-                terms_text.insert(term_str.to_string(), score);
+            if term.typ() == Type::Json {
+                json::add_json_term(searcher, term, &mut json_terms_text)?;
+            } else if let Some((term_str, score)) = term_text_and_score(searcher, term)? {
+                terms_text.insert(term_str, score);
             }
         }
+        let json_expand_dots = match searcher.schema().get_field_entry(field).field_type() {
+            FieldType::JsonObject(json_options) => json_options.is_expand_dots_enabled(),
+            _ => false,
+        };
+        let mut phrases = Vec::new();
+        'phrases: for (phrase_terms, slop) in query_phrases {
+            let mut terms = Vec::with_capacity(phrase_terms.len());
+            for (offset, term) in phrase_terms {
+                // A phrase with a term absent from the index cannot match.
+                let Some((term_str, score)) = term_text_and_score(searcher, term)? else {
+                    continue 'phrases;
+                };
+                terms.push((*offset, term_str, score));
+            }
+            phrases.push(PhraseTerms { terms, slop });
+        }
         let tokenizer = searcher.index().tokenizer_for_field(field)?;
         Ok(SnippetGenerator {
             terms_text,
+            phrases,
+            json_terms_text,
+            json_expand_dots,
             tokenizer,
             field,
             max_num_chars: DEFAULT_MAX_NUM_CHARS,
+            fragment_options: FragmentOptions::default(),
+            snippet_prefix: DEFAULT_SNIPPET_PREFIX.to_string(),
+            snippet_postfix: DEFAULT_SNIPPET_POSTFIX.to_string(),
         })
     }
 
@@ -407,6 +743,48 @@ impl SnippetGenerator {
         self.max_num_chars = max_num_chars;
     }
 
+    /// Returns the maximum number of chars of the snippets.
+    pub fn max_num_chars(&self) -> usize {
+        self.max_num_chars
+    }
+
+    /// Sets the maximum number of fragments of a snippet. Default is 1.
+    ///
+    /// Each fragment has at most `max_num_chars` chars. The fragments are separated by
+    /// `" ... "` in the snippet.
+    pub fn set_max_num_fragments(&mut self, max_num_fragments: usize) {
+        self.fragment_options.max_num_fragments = max_num_fragments;
+    }
+
+    /// Sets the order of the fragments of a snippet. Default is [`FragmentOrder::Score`].
+    pub fn set_fragment_order(&mut self, fragment_order: FragmentOrder) {
+        self.fragment_options.order = fragment_order;
+    }
+
+    /// Sets the number of chars of the beginning of the text returned as the snippet
+    /// when nothing matched in the text. Default is 0, i.e. an empty snippet.
+    pub fn set_no_match_size(&mut self, no_match_size: usize) {
+        self.fragment_options.no_match_size = no_match_size;
+    }
+
+    /// If true, the selected fragments that are only separated by whitespace and
+    /// punctuation are merged into a single fragment. Default is false.
+    pub fn set_merge_adjacent_fragments(&mut self, merge_adjacent: bool) {
+        self.fragment_options.merge_adjacent = merge_adjacent;
+    }
+
+    /// Sets the prefix and postfix surrounding the highlighted parts of the snippets in
+    /// [`Snippet::to_html`]. Default is `<b>` and `</b>`.
+    pub fn set_tags(&mut self, prefix: &str, postfix: &str) {
+        self.snippet_prefix = prefix.to_string();
+        self.snippet_postfix = postfix.to_string();
+    }
+
+    /// Returns the field the snippets are generated for.
+    pub fn field(&self) -> Field {
+        self.field
+    }
+
     #[cfg(test)]
     pub fn terms_text(&self) -> &BTreeMap<String, Score> {
         &self.terms_text
@@ -417,6 +795,11 @@ impl SnippetGenerator {
     /// This method extract the text associated with the `SnippetGenerator`'s field
     /// and computes a snippet.
     pub fn snippet_from_doc<D: Document>(&self, doc: &D) -> Snippet {
+        self.snippet(&self.text_from_doc(doc))
+    }
+
+    /// Concatenates the values of the field in `doc`.
+    fn text_from_doc<D: Document>(&self, doc: &D) -> String {
         let mut text = String::new();
         for (field, value) in doc.iter_fields_and_values() {
             let value = value as D::Value<'_>;
@@ -430,19 +813,114 @@ impl SnippetGenerator {
             }
         }
 
-        self.snippet(text.trim())
+        text.trim().to_string()
+    }
+
+    /// Generates a snippet for the given `Document`, using the offsets recorded in
+    /// `term_vector` instead of running the tokenizer over the text again.
+    ///
+    /// `term_vector` must be the term vector of the field of the snippet generator in
+    /// `doc`, as returned by [`TermVectorsReader::get`](crate::termvector::TermVectorsReader::get).
+    /// This is faster on large texts, and highlights the terms exactly as they were
+    /// indexed, even if the tokenizer of the field changed since then.
+    pub fn snippet_from_term_vector<D: Document>(
+        &self,
+        doc: &D,
+        term_vector: &TermVector,
+    ) -> Snippet {
+        let mut text = String::new();
+        let mut value_offsets = Vec::new();
+        for (field, value) in doc.iter_fields_and_values() {
+            let value = value as D::Value<'_>;
+            if field != self.field {
+                continue;
+            }
+            if let Some(val) = value.as_str() {
+                if !text.is_empty() {
+                    text.push(' ');
+                }
+                let start = text.len();
+                text.push_str(val);
+                value_offsets.push(Some(start..text.len()));
+            } else {
+                value_offsets.push(None);
+            }
+        }
+        let tokens = tokens_from_term_vector(term_vector, &value_offsets);
+        let fragment_candidates =
+            fragments_from_tokens(&tokens, &self.terms_text, &self.phrases, self.max_num_chars);
+        self.select_snippet(&fragment_candidates[..], &text)
+    }
+
+    /// Generates a snippet for the document `doc_id` of `segment_reader`, whose stored
+    /// fields are `doc`.
+    ///
+    /// The offsets of the term vector of the field are used if the field has term
+    /// vectors, see [`SnippetGenerator::snippet_from_term_vector`]. Otherwise, the text
+    /// is tokenized again as in [`SnippetGenerator::snippet_from_doc`].
+    pub fn snippet_from_segment_doc<D: Document>(
+        &self,
+        segment_reader: &SegmentReader,
+        doc_id: DocId,
+        doc: &D,
+    ) -> crate::Result<Snippet> {
+        let field_entry = segment_reader.schema().get_field_entry(self.field);
+        if !field_entry.has_term_vectors() {
+            return Ok(self.snippet_from_doc(doc));
+        }
+        let term_vector = segment_reader
+            .term_vectors()
+            .get(doc_id, self.field)?
+            .unwrap_or_default();
+        Ok(self.snippet_from_term_vector(doc, &term_vector))
     }
 
     /// Generates a snippet for the given text.
     pub fn snippet(&self, text: &str) -> Snippet {
-        let fragment_candidates = search_fragments(
-            &mut self.tokenizer.clone(),
-            text,
-            &self.terms_text,
-            self.max_num_chars,
-        );
-        select_best_fragment_combination(&fragment_candidates[..], text)
+        self.snippet_with_max_num_chars(text, self.max_num_chars)
+    }
+
+    fn snippet_with_max_num_chars(&self, text: &str, max_num_chars: usize) -> Snippet {
+        let fragment_candidates = if self.phrases.is_empty() {
+            search_fragments(
+                &mut self.tokenizer.clone(),
+                text,
+                &self.terms_text,
+                max_num_chars,
+            )
+        } else {
+            search_fragments_with_phrases(
+                &mut self.tokenizer.clone(),
+                text,
+                &self.terms_text,
+                &self.phrases,
+                max_num_chars,
+            )
+        };
+        self.select_snippet(&fragment_candidates[..], text)
+    }
+
+    /// Builds the snippet of `text` from its fragment candidates.
+    fn select_snippet(&self, fragment_candidates: &[FragmentCandidate], text: &str) -> Snippet {
+        let mut snippet = select_fragments(fragment_candidates, text, &self.fragment_options);
+        snippet.set_snippet_prefix_postfix(&self.snippet_prefix, &self.snippet_postfix);
+        snippet
+    }
+}
+
+/// Returns the text of `term` and its score, or `None` if the term is not a string or
+/// does not appear in the index.
+fn term_text_and_score(searcher: &Searcher, term: &Term) -> crate::Result<Option<(String, Score)>> {
+    let term_value = term.value();
+    let Some(term_str) = term_value.as_str() else {
+        return Ok(None);
+    };
+    let doc_freq = searcher.doc_freq(term)?;
+    if doc_freq == 0 {
+        return Ok(None);
     }
+    let score = 1.0 / (1.0 + doc_freq as Score);
+    Ok(Some((term_str.to_string(), score)))
 }
 
 #[cfg(test)]
@@ -454,9 +932,9 @@ mod tests {
     use super::{collapse_overlapped_ranges, search_fragments, select_best_fragment_combination};
     use crate::query::QueryParser;
     use crate::schema::{IndexRecordOption, Schema, TextFieldIndexing, TextOptions, TEXT};
-    use crate::snippet::SnippetGenerator;
+    use crate::snippet::{FragmentOrder, SnippetGenerator};
     use crate::tokenizer::{NgramTokenizer, SimpleTokenizer};
-    use crate::Index;
+    use crate::{Index, IndexWriter};
 
     const TEST_TEXT: &str = r#"Rust is a systems programming language sponsored by
 Mozilla which describes it as a "safe, concurrent, practical language", supporting functional and
@@ -741,6 +1219,210 @@ Survey in 2016, 2017, and 2018."#;
         Ok(())
     }
 
+    #[test]
+    fn test_snippet_generator_phrase() -> crate::Result<()> {
+        let mut schema_builder = Schema::builder();
+        let text_field = schema_builder.add_text_field("text", TEXT);
+        let index = Index::create_in_ram(schema_builder.build());
+        let text = "A new car in York. The big apple is New York, not York in the UK.";
+        {
+            let mut index_writer: IndexWriter = index.writer_for_tests()?;
+            index_writer.add_document(doc!(text_field => text))?;
+            index_writer.commit()?;
+        }
+        let searcher = index.reader()?.searcher();
+        let query_parser = QueryParser::for_index(&index, vec![text_field]);
+        {
+            let query = query_parser.parse_query("\"new york\"")?;
+            let snippet_generator = SnippetGenerator::create(&searcher, &*query, text_field)?;
+            assert!(snippet_generator.terms_text().is_empty());
+            assert_eq!(
+                snippet_generator.snippet(text).to_html(),
+                "A new car in York. The big apple is <b>New</b> <b>York</b>, not York in the UK"
+            );
+        }
+        {
+            let query = query_parser.parse_query("\"new york\" car")?;
+            let snippet_generator = SnippetGenerator::create(&searcher, &*query, text_field)?;
+            assert_eq!(
+                snippet_generator.snippet(text).to_html(),
+                "A new <b>car</b> in York. The big apple is <b>New</b> <b>York</b>, not York in \
+                 the UK"
+            );
+        }
+        {
+            // The standalone term is highlighted everywhere.
+            let query = query_parser.parse_query("\"new york\" york")?;
+            let snippet_generator = SnippetGenerator::create(&searcher, &*query, text_field)?;
+            assert_eq!(
+                snippet_generator.snippet(text).to_html(),
+                "A new car in <b>York</b>. The big apple is <b>New</b> <b>York</b>, not \
+                 <b>York</b> in the UK"
+            );
+        }
+        {
+            let query = query_parser.parse_query("\"new york\"~2")?;
+            let snippet_generator = SnippetGenerator::create(&searcher, &*query, text_field)?;
+            assert_eq!(
+                snippet_generator.snippet(text).to_html(),
+                "A <b>new</b> car in <b>York</b>. The big apple is <b>New</b> <b>York</b>, not \
+                 York in the UK"
+            );
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_snippet_from_term_vector() -> crate::Result<()> {
+        let mut schema_builder = Schema::builder();
+        let text_options = TextOptions::default().set_stored().set_indexing_options(
+            TextFieldIndexing::default()
+                .set_tokenizer("default")
+                .set_index_option(IndexRecordOption::WithFreqsAndPositions)
+                .set_term_vectors(true),
+        );
+        let text_field = schema_builder.add_text_field("text", text_options);
+        let other_field = schema_builder.add_text_field("other", TEXT);
+        let index = Index::create_in_ram(schema_builder.build());
+        let doc = doc!(
+            text_field => "Some rust here.",
+            other_field => "rust elsewhere",
+            text_field => "Rust is a new language, unlike new york. Rust!",
+        );
+        {
+            let mut index_writer: IndexWriter = index.writer_for_tests()?;
+            index_writer.add_document(doc.clone())?;
+            index_writer.commit()?;
+        }
+        let searcher = index.reader()?.searcher();
+        let segment_reader = searcher.segment_reader(0);
+        let term_vector = segment_reader.term_vectors().get(0, text_field)?.unwrap();
+        let query_parser = QueryParser::for_index(&index, vec![text_field]);
+        for query_str in ["rust", "\"new york\" language", "missing"] {
+            let query = query_parser.parse_query(query_str)?;
+            let mut snippet_generator = SnippetGenerator::create(&searcher, &*query, text_field)?;
+            snippet_generator.set_max_num_chars(30);
+            let snippet = snippet_generator.snippet_from_term_vector(&doc, &term_vector);
+            assert_eq!(
+                snippet.to_html(),
+                snippet_generator.snippet_from_doc(&doc).to_html()
+            );
+            assert_eq!(
+                snippet_generator
+                    .snippet_from_segment_doc(segment_reader, 0, &doc)?
+                    .to_html(),
+                snippet.to_html()
+            );
+        }
+        let query = query_parser.parse_query("\"new york\"")?;
+        let snippet_generator = SnippetGenerator::create(&searcher, &*query, text_field)?;
+        assert_eq!(
+            snippet_generator
+                .snippet_from_term_vector(&doc, &term_vector)
+                .to_html(),
+            "Some rust here. Rust is a new language, unlike <b>new</b> <b>york</b>. Rust"
+        );
+
+        // Without term vectors, the text is tokenized again.
+        let query = QueryParser::for_index(&index, vec![other_field]).parse_query("rust")?;
+        let snippet_generator = SnippetGenerator::create(&searcher, &*query, other_field)?;
+        assert_eq!(
+            snippet_generator
+                .snippet_from_segment_doc(segment_reader, 0, &doc)?
+                .to_html(),
+            "<b>rust</b> elsewhere"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_snippet_generator_fragment_options() -> crate::Result<()> {
+        let mut schema_builder = Schema::builder();
+        let text_field = schema_builder.add_text_field("text", TEXT);
+        let index = Index::create_in_ram(schema_builder.build());
+        let text = "One apple a day, they say. Plenty of words here. Apple bananas. Bananas";
+        {
+            let mut index_writer: IndexWriter = index.writer_for_tests()?;
+            index_writer.add_document(doc!(text_field => text))?;
+            index_writer.commit()?;
+        }
+        let searcher = index.reader()?.searcher();
+        let query_parser = QueryParser::for_index(&index, vec![text_field]);
+        let query = query_parser.parse_query("apple bananas")?;
+        let mut snippet_generator = SnippetGenerator::create(&searcher, &*query, text_field)?;
+        snippet_generator.set_max_num_chars(20);
+        assert_eq!(
+            snippet_generator.snippet(text).to_html(),
+            "<b>bananas</b>. <b>Bananas</b>"
+        );
+
+        snippet_generator.set_max_num_fragments(3);
+        let snippet = snippet_generator.snippet(text);
+        assert_eq!(
+            snippet.to_html(),
+            "<b>bananas</b>. <b>Bananas</b> ... One <b>apple</b> a day ... words here. \
+             <b>Apple</b>"
+        );
+        assert_eq!(
+            &snippet.fragment()[snippet.highlighted()[2].clone()],
+            "apple"
+        );
+
+        snippet_generator.set_fragment_order(FragmentOrder::Position);
+        assert_eq!(
+            snippet_generator.snippet(text).to_html(),
+            "One <b>apple</b> a day ... words here. <b>Apple</b> ... <b>bananas</b>. \
+             <b>Bananas</b>"
+        );
+
+        snippet_generator.set_merge_adjacent_fragments(true);
+        assert_eq!(
+            snippet_generator.snippet(text).to_html(),
+            "One <b>apple</b> a day ... words here. <b>Apple</b> <b>bananas</b>. <b>Bananas</b>"
+        );
+
+        assert!(snippet_generator.snippet("no match").is_empty());
+        snippet_generator.set_no_match_size(12);
+        let snippet = snippet_generator.snippet("nothing matches in this text");
+        assert!(!snippet.is_empty());
+        assert!(snippet.highlighted().is_empty());
+        assert_eq!(snippet.fragment(), "nothing");
+        assert_eq!(snippet_generator.snippet("short").fragment(), "short");
+        Ok(())
+    }
+
+    #[test]
+    fn test_snippet_generator_tags() -> crate::Result<()> {
+        let mut schema_builder = Schema::builder();
+        let text_field = schema_builder.add_text_field("text", TEXT);
+        let index = Index::create_in_ram(schema_builder.build());
+        let text = "<script>alert(été)</script> & café crème";
+        {
+            let mut index_writer: IndexWriter = index.writer_for_tests()?;
+            index_writer.add_document(doc!(text_field => text))?;
+            index_writer.commit()?;
+        }
+        let searcher = index.reader()?.searcher();
+        let query = QueryParser::for_index(&index, vec![text_field]).parse_query("été café")?;
+        let mut snippet_generator = SnippetGenerator::create(&searcher, &*query, text_field)?;
+        snippet_generator.set_tags("<mark class=\"hl\">", "</mark>");
+        let snippet = snippet_generator.snippet(text);
+        assert_eq!(
+            snippet.to_html(),
+            "&lt;script&gt;alert(<mark class=\"hl\">été</mark>)&lt;/script&gt; &amp; <mark \
+             class=\"hl\">café</mark> crème"
+        );
+        assert_eq!(snippet.highlighted_char_ranges(), vec![14..17, 30..34]);
+        let fragment_chars: Vec<char> = snippet.fragment().chars().collect();
+        let highlighted: Vec<String> = snippet
+            .highlighted_char_ranges()
+            .into_iter()
+            .map(|range| fragment_chars[range].iter().collect())
+            .collect();
+        assert_eq!(highlighted, vec!["été", "café"]);
+        Ok(())
+    }
+
     #[test]
     fn test_collapse_overlapped_ranges() {
         #![allow(clippy::single_range_in_vec_init)]
@@ -72,6 +72,15 @@ pub struct SegmentSpaceUsage {
 
     store: StoreSpaceUsage,
 
+    #[serde(default)]
+    blobs: ByteCount,
+
+    #[serde(default)]
+    term_vectors: ByteCount,
+
+    #[serde(default)]
+    vector_index: PerFieldSpaceUsage,
+
     deletes: ByteCount,
 
     total: ByteCount,
@@ -87,6 +96,9 @@ impl SegmentSpaceUsage {
         fast_fields: PerFieldSpaceUsage,
         fieldnorms: PerFieldSpaceUsage,
         store: StoreSpaceUsage,
+        blobs: ByteCount,
+        term_vectors: ByteCount,
+        vector_index: PerFieldSpaceUsage,
         deletes: ByteCount,
     ) -> SegmentSpaceUsage {
         let total = termdict.total()
@@ -95,6 +107,9 @@ impl SegmentSpaceUsage {
             + fast_fields.total()
             + fieldnorms.total()
             + store.total()
+            + blobs
+            + term_vectors
+            + vector_index.total()
             + deletes;
         SegmentSpaceUsage {
             num_docs,
@@ -104,6 +119,9 @@ impl SegmentSpaceUsage {
             fast_fields,
             fieldnorms,
             store,
+            blobs,
+            term_vectors,
+            vector_index,
             deletes,
             total,
         }
@@ -125,6 +143,9 @@ impl SegmentSpaceUsage {
             SegmentComponent::Store => ComponentSpaceUsage::Store(self.store().clone()),
             SegmentComponent::TempStore => ComponentSpaceUsage::Store(self.store().clone()),
             Delete => Basic(self.deletes()),
+            Blobs => Basic(self.blobs()),
+            TermVectors => Basic(self.term_vectors()),
+            VectorIndex => PerField(self.vector_index().clone()),
         }
     }
 
@@ -163,6 +184,21 @@ impl SegmentSpaceUsage {
         &self.store
     }
 
+    /// Space usage for the blob fields
+    pub fn blobs(&self) -> ByteCount {
+        self.blobs
+    }
+
+    /// Space usage for the term vectors
+    pub fn term_vectors(&self) -> ByteCount {
+        self.term_vectors
+    }
+
+    /// Space usage for the vector indexes of the dense vector fields
+    pub fn vector_index(&self) -> &PerFieldSpaceUsage {
+        &self.vector_index
+    }
+
     /// Space usage for document deletions
     pub fn deletes(&self) -> ByteCount {
         self.deletes
@@ -210,7 +246,7 @@ impl StoreSpaceUsage {
 ///
 /// A field can appear with a single index (typically 0) or with multiple indexes.
 /// Multiple indexes are used to handle variable length things, where
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
 pub struct PerFieldSpaceUsage {
     fields: HashMap<Field, FieldUsage>,
     total: ByteCount,
@@ -0,0 +1,372 @@
+use std::io::{self, Read};
+
+use common::{BinarySerializable, ByteCount, CountingWriter, HasLen, OwnedBytes, TerminatingWrite};
+
+use crate::directory::{FileSlice, WritePtr};
+use crate::schema::document::{Document, Value};
+use crate::schema::{Field, Schema};
+use crate::DocId;
+
+/// An entry of the blob index: doc id, field, start offset and end offset.
+const ENTRY_NUM_BYTES: usize = 4 + 4 + 8 + 8;
+
+/// Location of a blob in the blob file of a segment.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct BlobEntry {
+    doc: DocId,
+    field: Field,
+    start: u64,
+    end: u64,
+}
+
+impl BlobEntry {
+    fn serialize<W: io::Write + ?Sized>(&self, writer: &mut W) -> io::Result<()> {
+        self.doc.serialize(writer)?;
+        self.field.field_id().serialize(writer)?;
+        self.start.serialize(writer)?;
+        self.end.serialize(writer)?;
+        Ok(())
+    }
+
+    fn deserialize(mut bytes: &[u8]) -> io::Result<BlobEntry> {
+        let doc = DocId::deserialize(&mut bytes)?;
+        let field = Field::from_field_id(u32::deserialize(&mut bytes)?);
+        let start = u64::deserialize(&mut bytes)?;
+        let end = u64::deserialize(&mut bytes)?;
+        Ok(BlobEntry {
+            doc,
+            field,
+            start,
+            end,
+        })
+    }
+}
+
+/// Writes the values of the blob fields of a segment.
+///
+/// The blobs are written as is, one after the other, in the order in which they are added.
+/// They are followed by an index sorted by `(doc, field)`, and by the offset of this index.
+///
+/// Documents are expected to be added in increasing doc id order.
+pub struct BlobStoreWriter {
+    write: CountingWriter<WritePtr>,
+    entries: Vec<BlobEntry>,
+}
+
+impl BlobStoreWriter {
+    /// Creates a blob store writer.
+    pub fn new(write: WritePtr) -> BlobStoreWriter {
+        BlobStoreWriter {
+            write: CountingWriter::wrap(write),
+            entries: Vec::new(),
+        }
+    }
+
+    /// Writes the values of the blob fields of `document`.
+    pub fn store<D: Document>(
+        &mut self,
+        doc: DocId,
+        document: &D,
+        schema: &Schema,
+    ) -> io::Result<()> {
+        for (field, value) in document.iter_fields_and_values() {
+            if !schema.get_field_entry(field).is_blob() {
+                continue;
+            }
+            if let Some(bytes) = value.as_bytes() {
+                self.add_blob(doc, field, bytes)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Appends a blob, streaming it from `blob`.
+    pub fn add_blob<R: Read>(&mut self, doc: DocId, field: Field, mut blob: R) -> io::Result<()> {
+        debug_assert!(self.entries.last().map_or(true, |entry| entry.doc <= doc));
+        let start = self.write.written_bytes();
+        io::copy(&mut blob, &mut self.write)?;
+        let end = self.write.written_bytes();
+        self.entries.push(BlobEntry {
+            doc,
+            field,
+            start,
+            end,
+        });
+        Ok(())
+    }
+
+    /// Memory used by the index of the blobs written so far.
+    pub fn mem_usage(&self) -> usize {
+        self.entries.capacity() * std::mem::size_of::<BlobEntry>()
+    }
+
+    /// Writes the index and finalizes the blob file.
+    pub fn close(mut self) -> io::Result<()> {
+        // The sort is stable: the values of a multivalued field keep their order.
+        self.entries.sort_by_key(|entry| (entry.doc, entry.field));
+        let index_offset = self.write.written_bytes();
+        for entry in &self.entries {
+            entry.serialize(&mut self.write)?;
+        }
+        index_offset.serialize(&mut self.write)?;
+        self.write.terminate()
+    }
+}
+
+/// Reads the blobs of a segment.
+///
+/// Only the index of the blobs is loaded in memory. The blobs themselves are read
+/// lazily, see [`Blob`].
+#[derive(Clone)]
+pub struct BlobStoreReader {
+    data: FileSlice,
+    index: OwnedBytes,
+    num_bytes: ByteCount,
+}
+
+impl BlobStoreReader {
+    /// Opens a blob file.
+    pub fn open(file: FileSlice) -> io::Result<BlobStoreReader> {
+        let num_bytes = file.num_bytes();
+        if file.len() < 8 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "blob file is too short",
+            ));
+        }
+        let (data_and_index, footer) = file.split_from_end(8);
+        let index_offset = u64::deserialize(&mut footer.read_bytes()?.as_slice())? as usize;
+        if index_offset > data_and_index.len() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "blob index offset out of bounds",
+            ));
+        }
+        let (data, index) = data_and_index.split(index_offset);
+        let index = index.read_bytes()?;
+        if index.len() % ENTRY_NUM_BYTES != 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "blob index length is not a multiple of the entry length",
+            ));
+        }
+        Ok(BlobStoreReader {
+            data,
+            index,
+            num_bytes,
+        })
+    }
+
+    /// Returns a reader for a segment without any blob.
+    pub fn empty() -> BlobStoreReader {
+        BlobStoreReader {
+            data: FileSlice::empty(),
+            index: OwnedBytes::empty(),
+            num_bytes: ByteCount::default(),
+        }
+    }
+
+    /// Returns the number of blobs in the segment.
+    pub fn num_blobs(&self) -> usize {
+        self.index.len() / ENTRY_NUM_BYTES
+    }
+
+    fn entry(&self, ord: usize) -> io::Result<BlobEntry> {
+        let start = ord * ENTRY_NUM_BYTES;
+        BlobEntry::deserialize(&self.index.as_slice()[start..start + ENTRY_NUM_BYTES])
+    }
+
+    /// Returns the ordinal of the first entry whose `(doc, field)` is greater or equal
+    /// to the given key.
+    fn lower_bound(&self, doc: DocId, field: Option<Field>) -> io::Result<usize> {
+        let (mut lo, mut hi) = (0, self.num_blobs());
+        while lo < hi {
+            let mid = lo + (hi - lo) / 2;
+            let entry = self.entry(mid)?;
+            let is_before = match field {
+                Some(field) => (entry.doc, entry.field) < (doc, field),
+                None => entry.doc < doc,
+            };
+            if is_before {
+                lo = mid + 1;
+            } else {
+                hi = mid;
+            }
+        }
+        Ok(lo)
+    }
+
+    fn blob(&self, entry: &BlobEntry) -> io::Result<Blob> {
+        if entry.start > entry.end || entry.end as usize > self.data.len() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "blob out of bounds",
+            ));
+        }
+        Ok(Blob {
+            data: self.data.slice(entry.start as usize..entry.end as usize),
+        })
+    }
+
+    /// Returns the blobs of the field `field` of the document `doc`, in the order in which
+    /// they were added to the document.
+    pub fn get(&self, doc: DocId, field: Field) -> io::Result<Vec<Blob>> {
+        let mut blobs = Vec::new();
+        for ord in self.lower_bound(doc, Some(field))?..self.num_blobs() {
+            let entry = self.entry(ord)?;
+            if entry.doc != doc || entry.field != field {
+                break;
+            }
+            blobs.push(self.blob(&entry)?);
+        }
+        Ok(blobs)
+    }
+
+    /// Returns all of the blobs of the document `doc`, sorted by field.
+    pub fn get_all(&self, doc: DocId) -> io::Result<Vec<(Field, Blob)>> {
+        let mut blobs = Vec::new();
+        for ord in self.lower_bound(doc, None)?..self.num_blobs() {
+            let entry = self.entry(ord)?;
+            if entry.doc != doc {
+                break;
+            }
+            blobs.push((entry.field, self.blob(&entry)?));
+        }
+        Ok(blobs)
+    }
+
+    /// Returns the size of the blob file.
+    pub fn space_usage(&self) -> ByteCount {
+        self.num_bytes
+    }
+}
+
+/// A binary payload of a blob field.
+///
+/// Creating a `Blob` does not read anything: its content can either be read at once
+/// with [`Blob::read_bytes`], or streamed with [`Blob::reader`].
+#[derive(Clone, Debug)]
+pub struct Blob {
+    data: FileSlice,
+}
+
+impl Blob {
+    /// Returns the length of the blob in bytes.
+    pub fn len(&self) -> usize {
+        self.data.len()
+    }
+
+    /// Returns true if the blob is empty.
+    pub fn is_empty(&self) -> bool {
+        self.data.len() == 0
+    }
+
+    /// Returns the underlying file slice.
+    pub fn file_slice(&self) -> &FileSlice {
+        &self.data
+    }
+
+    /// Reads the entire blob.
+    pub fn read_bytes(&self) -> io::Result<OwnedBytes> {
+        self.data.read_bytes()
+    }
+
+    /// Returns a reader streaming the content of the blob.
+    pub fn reader(&self) -> BlobReader {
+        BlobReader {
+            remaining: self.data.clone(),
+        }
+    }
+}
+
+/// Streams the content of a [`Blob`].
+///
+/// Each call to `read` only reads the requested bytes from the underlying file.
+pub struct BlobReader {
+    remaining: FileSlice,
+}
+
+impl Read for BlobReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let num_bytes = buf.len().min(self.remaining.len());
+        if num_bytes == 0 {
+            return Ok(0);
+        }
+        let bytes = self.remaining.read_bytes_slice(0..num_bytes)?;
+        buf[..num_bytes].copy_from_slice(bytes.as_slice());
+        self.remaining = self.remaining.slice_from(num_bytes);
+        Ok(num_bytes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Read;
+    use std::path::Path;
+
+    use super::{BlobStoreReader, BlobStoreWriter};
+    use crate::directory::{Directory, RamDirectory};
+    use crate::schema::Field;
+
+    #[test]
+    fn test_blob_store() -> crate::Result<()> {
+        let directory = RamDirectory::create();
+        let path = Path::new("test.blob");
+        let (field_a, field_b) = (Field::from_field_id(0), Field::from_field_id(1));
+        let mut writer = BlobStoreWriter::new(directory.open_write(path)?);
+        writer.add_blob(0, field_b, &b"b0"[..])?;
+        writer.add_blob(0, field_a, &b"a0"[..])?;
+        writer.add_blob(0, field_b, &b"b1"[..])?;
+        writer.add_blob(2, field_a, &vec![7u8; 100_000][..])?;
+        writer.close()?;
+
+        let reader = BlobStoreReader::open(directory.open_read(path)?)?;
+        assert_eq!(reader.num_blobs(), 4);
+        let read_all = |blobs: Vec<super::Blob>| -> Vec<Vec<u8>> {
+            blobs
+                .iter()
+                .map(|blob| blob.read_bytes().unwrap().as_slice().to_vec())
+                .collect()
+        };
+        assert_eq!(read_all(reader.get(0, field_a)?), vec![b"a0".to_vec()]);
+        assert_eq!(
+            read_all(reader.get(0, field_b)?),
+            vec![b"b0".to_vec(), b"b1".to_vec()]
+        );
+        assert!(reader.get(1, field_a)?.is_empty());
+        assert!(reader.get(3, field_a)?.is_empty());
+        let all_fields: Vec<Field> = reader
+            .get_all(0)?
+            .into_iter()
+            .map(|(field, _)| field)
+            .collect();
+        assert_eq!(all_fields, vec![field_a, field_b, field_b]);
+
+        let blob = reader.get(2, field_a)?.pop().unwrap();
+        assert_eq!(blob.len(), 100_000);
+        let mut blob_reader = blob.reader();
+        let mut buffer = [0u8; 4096];
+        let mut num_bytes_read = 0;
+        loop {
+            let num_bytes = blob_reader.read(&mut buffer)?;
+            if num_bytes == 0 {
+                break;
+            }
+            assert!(buffer[..num_bytes].iter().all(|&byte| byte == 7));
+            num_bytes_read += num_bytes;
+        }
+        assert_eq!(num_bytes_read, 100_000);
+        Ok(())
+    }
+
+    #[test]
+    fn test_empty_blob_store() -> crate::Result<()> {
+        let directory = RamDirectory::create();
+        let path = Path::new("test.blob");
+        BlobStoreWriter::new(directory.open_write(path)?).close()?;
+        let reader = BlobStoreReader::open(directory.open_read(path)?)?;
+        assert_eq!(reader.num_blobs(), 0);
+        assert!(reader.get(0, Field::from_field_id(0))?.is_empty());
+        Ok(())
+    }
+}
@@ -0,0 +1,87 @@
+use std::collections::HashMap;
+use std::io;
+use std::sync::{Arc, RwLock};
+
+use once_cell::sync::Lazy;
+
+use crate::TantivyError;
+
+/// Smallest id a custom codec can be registered with. Lower ids are reserved for the
+/// codecs built into tantivy.
+pub const MIN_CUSTOM_CODEC_ID: u8 = 128;
+
+/// A doc store compression codec provided by the application.
+///
+/// Custom codecs are registered once per process with [`register_store_codec`], and can
+/// then be selected for new segments with [`Compressor::Custom`](super::Compressor::Custom).
+/// The id of the codec is recorded in the doc store of each segment, so a segment can only
+/// be read by a process in which its codec is registered with the same id.
+///
+/// Each block of documents is compressed and decompressed independently.
+pub trait StoreCodec: Send + Sync + 'static {
+    /// Name of the codec, used to refer to it in the index settings.
+    fn name(&self) -> &'static str;
+
+    /// Compresses `uncompressed` into `compressed`, replacing its content.
+    fn compress_into(&self, uncompressed: &[u8], compressed: &mut Vec<u8>) -> io::Result<()>;
+
+    /// Decompresses `compressed` into `decompressed`, replacing its content.
+    fn decompress_into(&self, compressed: &[u8], decompressed: &mut Vec<u8>) -> io::Result<()>;
+}
+
+static STORE_CODECS: Lazy<RwLock<HashMap<u8, Arc<dyn StoreCodec>>>> = Lazy::new(Default::default);
+
+/// Registers a custom doc store codec under the given id.
+///
+/// The id must be at least [`MIN_CUSTOM_CODEC_ID`], and neither the id nor the name of the
+/// codec may already be registered.
+pub fn register_store_codec<C: StoreCodec>(id: u8, codec: C) -> crate::Result<()> {
+    if id < MIN_CUSTOM_CODEC_ID {
+        return Err(TantivyError::InvalidArgument(format!(
+            "custom doc store codec ids start at {MIN_CUSTOM_CODEC_ID}, got {id}"
+        )));
+    }
+    let mut codecs = STORE_CODECS.write().unwrap();
+    if let Some(registered) = codecs.get(&id) {
+        return Err(TantivyError::InvalidArgument(format!(
+            "doc store codec id {id} is already used by {:?}",
+            registered.name()
+        )));
+    }
+    if codecs
+        .values()
+        .any(|registered| registered.name() == codec.name())
+    {
+        return Err(TantivyError::InvalidArgument(format!(
+            "a doc store codec named {:?} is already registered",
+            codec.name()
+        )));
+    }
+    codecs.insert(id, Arc::new(codec));
+    Ok(())
+}
+
+/// Returns the codec registered under `id`.
+pub(crate) fn store_codec(id: u8) -> io::Result<Arc<dyn StoreCodec>> {
+    STORE_CODECS
+        .read()
+        .unwrap()
+        .get(&id)
+        .cloned()
+        .ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("the doc store codec {id} is not registered"),
+            )
+        })
+}
+
+/// Returns the id of the codec registered as `name`.
+pub(crate) fn store_codec_id(name: &str) -> Option<u8> {
+    STORE_CODECS
+        .read()
+        .unwrap()
+        .iter()
+        .find(|(_, codec)| codec.name() == name)
+        .map(|(id, _)| *id)
+}
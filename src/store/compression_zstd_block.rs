@@ -1,6 +1,7 @@
 use std::io;
 
-use zstd::bulk::{compress_to_buffer, decompress_to_buffer};
+use zstd::bulk::{compress_to_buffer, decompress_to_buffer, Compressor, Decompressor};
+use zstd::dict::DecoderDictionary;
 use zstd::DEFAULT_COMPRESSION_LEVEL;
 
 #[inline]
@@ -8,6 +9,32 @@ pub fn compress(
     uncompressed: &[u8],
     compressed: &mut Vec<u8>,
     compression_level: Option<i32>,
+) -> io::Result<()> {
+    compress_with(uncompressed, compressed, |src, dst| {
+        compress_to_buffer(
+            src,
+            dst,
+            compression_level.unwrap_or(DEFAULT_COMPRESSION_LEVEL),
+        )
+    })
+}
+
+/// Compresses a block with a compressor holding a dictionary.
+#[inline]
+pub fn compress_with_dictionary(
+    uncompressed: &[u8],
+    compressed: &mut Vec<u8>,
+    compressor: &mut Compressor<'static>,
+) -> io::Result<()> {
+    compress_with(uncompressed, compressed, |src, dst| {
+        compressor.compress_to_buffer(src, dst)
+    })
+}
+
+fn compress_with(
+    uncompressed: &[u8],
+    compressed: &mut Vec<u8>,
+    compress_fn: impl FnOnce(&[u8], &mut [u8]) -> io::Result<usize>,
 ) -> io::Result<()> {
     let count_size = std::mem::size_of::<u32>();
     let max_size = zstd::zstd_safe::compress_bound(uncompressed.len()) + count_size;
@@ -15,11 +42,7 @@ pub fn compress(
     compressed.clear();
     compressed.resize(max_size, 0);
 
-    let compressed_size = compress_to_buffer(
-        uncompressed,
-        &mut compressed[count_size..],
-        compression_level.unwrap_or(DEFAULT_COMPRESSION_LEVEL),
-    )?;
+    let compressed_size = compress_fn(uncompressed, &mut compressed[count_size..])?;
 
     compressed[0..count_size].copy_from_slice(&(uncompressed.len() as u32).to_le_bytes());
     compressed.resize(compressed_size + count_size, 0);
@@ -29,6 +52,29 @@ pub fn compress(
 
 #[inline]
 pub fn decompress(compressed: &[u8], decompressed: &mut Vec<u8>) -> io::Result<()> {
+    decompress_with(compressed, decompressed, |src, dst| {
+        decompress_to_buffer(src, dst)
+    })
+}
+
+/// Decompresses a block compressed with [`compress_with_dictionary`].
+#[inline]
+pub fn decompress_with_dictionary(
+    compressed: &[u8],
+    decompressed: &mut Vec<u8>,
+    dictionary: &DecoderDictionary<'static>,
+) -> io::Result<()> {
+    let mut decompressor = Decompressor::with_prepared_dictionary(dictionary)?;
+    decompress_with(compressed, decompressed, |src, dst| {
+        decompressor.decompress_to_buffer(src, dst)
+    })
+}
+
+fn decompress_with(
+    compressed: &[u8],
+    decompressed: &mut Vec<u8>,
+    decompress_fn: impl FnOnce(&[u8], &mut Vec<u8>) -> io::Result<usize>,
+) -> io::Result<()> {
     let count_size = std::mem::size_of::<u32>();
     let uncompressed_size = u32::from_le_bytes(
         compressed
@@ -41,7 +87,7 @@ pub fn decompress(compressed: &[u8], decompressed: &mut Vec<u8>) -> io::Result<(
     decompressed.clear();
     decompressed.resize(uncompressed_size, 0);
 
-    let decompressed_size = decompress_to_buffer(&compressed[count_size..], decompressed)?;
+    let decompressed_size = decompress_fn(&compressed[count_size..], decompressed)?;
 
     if decompressed_size != uncompressed_size {
         return Err(io::Error::new(
@@ -52,3 +98,26 @@ pub fn decompress(compressed: &[u8], decompressed: &mut Vec<u8>) -> io::Result<(
 
     Ok(())
 }
+
+/// Trains a dictionary of at most `dict_size` bytes.
+///
+/// `samples` is the concatenation of the samples, whose respective sizes are given by
+/// `sample_sizes`.
+pub fn train_dictionary(
+    samples: &[u8],
+    sample_sizes: &[usize],
+    dict_size: usize,
+) -> io::Result<Vec<u8>> {
+    zstd::dict::from_continuous(samples, sample_sizes, dict_size)
+}
+
+/// Creates a compressor using the given dictionary.
+pub fn dictionary_compressor(
+    dictionary: &[u8],
+    compression_level: Option<i32>,
+) -> io::Result<Compressor<'static>> {
+    Compressor::with_dictionary(
+        compression_level.unwrap_or(DEFAULT_COMPRESSION_LEVEL),
+        dictionary,
+    )
+}
@@ -2,6 +2,8 @@ use std::io;
 
 use serde::{Deserialize, Deserializer, Serialize};
 
+use super::codec_registry::{store_codec, store_codec_id};
+
 /// Compressor can be used on `IndexSettings` to choose
 /// the compressor used to compress the doc store.
 ///
@@ -16,6 +18,9 @@ pub enum Compressor {
     /// Use the zstd compressor
     #[cfg(feature = "zstd-compression")]
     Zstd(ZstdCompressor),
+    /// Use the custom codec registered with the given id, see
+    /// [`register_store_codec`](crate::store::register_store_codec).
+    Custom(u8),
 }
 
 impl Serialize for Compressor {
@@ -27,6 +32,10 @@ impl Serialize for Compressor {
             Compressor::Lz4 => serializer.serialize_str("lz4"),
             #[cfg(feature = "zstd-compression")]
             Compressor::Zstd(zstd) => serializer.serialize_str(&zstd.ser_to_string()),
+            Compressor::Custom(id) => {
+                let codec = store_codec(id).map_err(serde::ser::Error::custom)?;
+                serializer.serialize_str(codec.name())
+            }
         }
     }
 }
@@ -57,6 +66,9 @@ impl<'de> Deserialize<'de> for Compressor {
                 ))
             }
             _ => {
+                if let Some(id) = store_codec_id(&buf) {
+                    return Ok(Compressor::Custom(id));
+                }
                 return Err(serde::de::Error::unknown_variant(
                     &buf,
                     &[
@@ -67,6 +79,8 @@ impl<'de> Deserialize<'de> for Compressor {
                         "zstd",
                         #[cfg(feature = "zstd-compression")]
                         "zstd(compression_level=5)",
+                        #[cfg(feature = "zstd-compression")]
+                        "zstd(compression_level=5,dict_size=65536)",
                     ],
                 ));
             }
@@ -81,6 +95,14 @@ impl<'de> Deserialize<'de> for Compressor {
 pub struct ZstdCompressor {
     /// The compression level, if unset defaults to zstd::DEFAULT_COMPRESSION_LEVEL = 3
     pub compression_level: Option<i32>,
+    /// If set, a zstd dictionary of at most `dict_size` bytes is trained on the documents of
+    /// each segment, and stored in the segment's doc store.
+    ///
+    /// This can improve the compression ratio a lot for small, similar documents. Segments
+    /// compressed with a dictionary cannot be stacked as is while merging, so merges are
+    /// slower.
+    #[serde(default)]
+    pub dict_size: Option<usize>,
 }
 
 #[cfg(feature = "zstd-compression")]
@@ -96,7 +118,7 @@ impl ZstdCompressor {
 
         let mut compressor = ZstdCompressor::default();
         for option in options.split(',') {
-            let (opt_name, value) = options
+            let (opt_name, value) = option
                 .split_once('=')
                 .ok_or_else(|| format!("no '=' found in option {option:?}"))?;
 
@@ -105,6 +127,13 @@ impl ZstdCompressor {
                     let value = value.parse::<i32>().map_err(|err| {
                         format!("Could not parse value {value} of option {opt_name}, e: {err}")
                     })?;
+                    let level_range = zstd::compression_level_range();
+                    if !level_range.contains(&value) {
+                        return Err(format!(
+                            "compression_level {value} is out of the supported range \
+                             {level_range:?}"
+                        ));
+                    }
                     if value >= 15 {
                         warn!(
                             "High zstd compression level detected: {:?}. High compression levels \
@@ -114,6 +143,12 @@ impl ZstdCompressor {
                     }
                     compressor.compression_level = Some(value);
                 }
+                "dict_size" => {
+                    let value = value.parse::<usize>().map_err(|err| {
+                        format!("Could not parse value {value} of option {opt_name}, e: {err}")
+                    })?;
+                    compressor.dict_size = Some(value);
+                }
                 _ => {
                     return Err(format!("unknown zstd option {opt_name:?}"));
                 }
@@ -122,10 +157,17 @@ impl ZstdCompressor {
         Ok(compressor)
     }
     fn ser_to_string(&self) -> String {
+        let mut options = Vec::new();
         if let Some(compression_level) = self.compression_level {
-            format!("zstd(compression_level={compression_level})")
-        } else {
+            options.push(format!("compression_level={compression_level}"));
+        }
+        if let Some(dict_size) = self.dict_size {
+            options.push(format!("dict_size={dict_size}"));
+        }
+        if options.is_empty() {
             "zstd".to_string()
+        } else {
+            format!("zstd({})", options.join(","))
         }
     }
 }
@@ -144,6 +186,15 @@ impl Default for Compressor {
 }
 
 impl Compressor {
+    /// Returns the size of the dictionary to train, if the compressor uses one.
+    pub(crate) fn dict_size(&self) -> Option<usize> {
+        match self {
+            #[cfg(feature = "zstd-compression")]
+            Self::Zstd(zstd_compressor) => zstd_compressor.dict_size,
+            _ => None,
+        }
+    }
+
     #[inline]
     pub(crate) fn compress_into(
         &self,
@@ -164,6 +215,7 @@ impl Compressor {
                 compressed,
                 _zstd_compressor.compression_level,
             ),
+            Self::Custom(id) => store_codec(*id)?.compress_into(uncompressed, compressed),
         }
     }
 }
@@ -176,6 +228,7 @@ mod tests {
     fn zstd_serde_roundtrip() {
         let compressor = ZstdCompressor {
             compression_level: Some(15),
+            dict_size: None,
         };
 
         assert_eq!(
@@ -201,9 +254,26 @@ mod tests {
         assert_eq!(
             ZstdCompressor::deser_from_str("zstd(compression_level=15)").unwrap(),
             ZstdCompressor {
-                compression_level: Some(15)
+                compression_level: Some(15),
+                dict_size: None,
+            }
+        );
+        assert_eq!(
+            ZstdCompressor::deser_from_str("zstd(compression_level=15,dict_size=65536)").unwrap(),
+            ZstdCompressor {
+                compression_level: Some(15),
+                dict_size: Some(65536),
             }
         );
+        let compressor = ZstdCompressor {
+            compression_level: None,
+            dict_size: Some(1024),
+        };
+        assert_eq!(compressor.ser_to_string(), "zstd(dict_size=1024)");
+        assert_eq!(
+            ZstdCompressor::deser_from_str(&compressor.ser_to_string()).unwrap(),
+            compressor
+        );
         assert_eq!(
             ZstdCompressor::deser_from_str("zstd(compresion_level=15)").unwrap_err(),
             "unknown zstd option \"compresion_level\""
@@ -212,6 +282,9 @@ mod tests {
             ZstdCompressor::deser_from_str("zstd(compression_level->2)").unwrap_err(),
             "no '=' found in option \"compression_level->2\""
         );
+        assert!(ZstdCompressor::deser_from_str("zstd(compression_level=23)")
+            .unwrap_err()
+            .starts_with("compression_level 23 is out of the supported range"));
         assert_eq!(
             ZstdCompressor::deser_from_str("zstd(compression_level=over9000)").unwrap_err(),
             "Could not parse value over9000 of option compression_level, e: invalid digit found \
@@ -2,6 +2,7 @@ use std::io;
 
 use serde::{Deserialize, Serialize};
 
+use super::codec_registry::{store_codec, MIN_CUSTOM_CODEC_ID};
 use super::Compressor;
 
 /// Decompressor is deserialized from the doc store footer, when opening an index.
@@ -15,6 +16,8 @@ pub enum Decompressor {
     /// Use the zstd decompressor
     #[cfg(feature = "zstd-compression")]
     Zstd,
+    /// Use the custom codec registered with the given id
+    Custom(u8),
 }
 
 impl From<Compressor> for Decompressor {
@@ -25,19 +28,30 @@ impl From<Compressor> for Decompressor {
             Compressor::Lz4 => Decompressor::Lz4,
             #[cfg(feature = "zstd-compression")]
             Compressor::Zstd(_) => Decompressor::Zstd,
+            Compressor::Custom(id) => Decompressor::Custom(id),
         }
     }
 }
 
 impl Decompressor {
     pub(crate) fn from_id(id: u8) -> Decompressor {
+        Decompressor::try_from_id(id).unwrap_or_else(|_| panic!("unknown compressor id {id:?}"))
+    }
+
+    /// Returns the decompressor with the given id, or an error if the id is unknown, e.g. if
+    /// it was read from a corrupted file.
+    pub(crate) fn try_from_id(id: u8) -> io::Result<Decompressor> {
         match id {
-            0 => Decompressor::None,
+            0 => Ok(Decompressor::None),
             #[cfg(feature = "lz4-compression")]
-            1 => Decompressor::Lz4,
+            1 => Ok(Decompressor::Lz4),
             #[cfg(feature = "zstd-compression")]
-            4 => Decompressor::Zstd,
-            _ => panic!("unknown compressor id {id:?}"),
+            4 => Ok(Decompressor::Zstd),
+            id if id >= MIN_CUSTOM_CODEC_ID => Ok(Decompressor::Custom(id)),
+            _ => Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("unknown compressor id {id:?}"),
+            )),
         }
     }
 
@@ -48,6 +62,7 @@ impl Decompressor {
             Self::Lz4 => 1,
             #[cfg(feature = "zstd-compression")]
             Self::Zstd => 4,
+            Self::Custom(id) => *id,
         }
     }
 
@@ -73,6 +88,7 @@ impl Decompressor {
             Self::Lz4 => super::compression_lz4_block::decompress(compressed, decompressed),
             #[cfg(feature = "zstd-compression")]
             Self::Zstd => super::compression_zstd_block::decompress(compressed, decompressed),
+            Self::Custom(id) => store_codec(*id)?.decompress_into(compressed, decompressed),
         }
     }
 }
@@ -9,18 +9,22 @@ use crate::directory::FileSlice;
 pub struct DocStoreFooter {
     pub offset: u64,
     pub decompressor: Decompressor,
+    /// Size of the compression dictionary, stored right before the skip index.
+    pub dictionary_num_bytes: u32,
 }
 
 /// Serialises the footer to a byte-array
 /// - offset : 8 bytes
 /// - compressor id: 1 byte
-/// - reserved for future use: 15 bytes
+/// - dictionary size: 4 bytes
+/// - reserved for future use: 11 bytes
 impl BinarySerializable for DocStoreFooter {
     fn serialize<W: io::Write + ?Sized>(&self, writer: &mut W) -> io::Result<()> {
         BinarySerializable::serialize(&DOC_STORE_VERSION, writer)?;
         BinarySerializable::serialize(&self.offset, writer)?;
         BinarySerializable::serialize(&self.decompressor.get_id(), writer)?;
-        writer.write_all(&[0; 15])?;
+        BinarySerializable::serialize(&self.dictionary_num_bytes, writer)?;
+        writer.write_all(&[0; 11])?;
         Ok(())
     }
 
@@ -31,11 +35,13 @@ impl BinarySerializable for DocStoreFooter {
         }
         let offset = u64::deserialize(reader)?;
         let compressor_id = u8::deserialize(reader)?;
-        let mut skip_buf = [0; 15];
+        let dictionary_num_bytes = u32::deserialize(reader)?;
+        let mut skip_buf = [0; 11];
         reader.read_exact(&mut skip_buf)?;
         Ok(DocStoreFooter {
             offset,
             decompressor: Decompressor::from_id(compressor_id),
+            dictionary_num_bytes,
         })
     }
 }
@@ -45,10 +51,11 @@ impl FixedSize for DocStoreFooter {
 }
 
 impl DocStoreFooter {
-    pub fn new(offset: u64, decompressor: Decompressor) -> Self {
+    pub fn new(offset: u64, decompressor: Decompressor, dictionary_num_bytes: u32) -> Self {
         DocStoreFooter {
             offset,
             decompressor,
+            dictionary_num_bytes,
         }
     }
 
@@ -0,0 +1,93 @@
+use std::ops::Range;
+
+use common::OwnedBytes;
+use once_cell::sync::OnceCell;
+
+use crate::schema::document::{
+    deserialize_value, index_values, BinaryDocumentDeserializer, DocumentDeserialize,
+    ValueDeserialize,
+};
+use crate::schema::Field;
+
+/// A stored document whose values are only decoded when accessed.
+///
+/// The document keeps the decompressed doc store block it belongs to alive, so accessing
+/// its values does not read or decompress anything. On first access, the values of the
+/// document are listed without being decoded. Each access then only decodes the values of
+/// the requested field.
+///
+/// This is cheaper than [`Searcher::doc`](crate::Searcher::doc) when only a few fields of
+/// the document are inspected, e.g. by a collector looking at one field of many candidate
+/// documents.
+#[derive(Clone)]
+pub struct LazyDocument {
+    doc_bytes: OwnedBytes,
+    values: OnceCell<Vec<(Field, Range<usize>)>>,
+}
+
+impl LazyDocument {
+    pub(crate) fn new(doc_bytes: OwnedBytes) -> LazyDocument {
+        LazyDocument {
+            doc_bytes,
+            values: OnceCell::new(),
+        }
+    }
+
+    /// Returns the field and the byte range of each value of the document.
+    fn values(&self) -> crate::Result<&[(Field, Range<usize>)]> {
+        let values = self
+            .values
+            .get_or_try_init(|| index_values(self.doc_bytes.as_slice()))?;
+        Ok(values)
+    }
+
+    fn field_values<'a>(
+        &'a self,
+        field: Field,
+    ) -> crate::Result<impl Iterator<Item = &'a [u8]> + 'a> {
+        let doc_bytes = self.doc_bytes.as_slice();
+        Ok(self
+            .values()?
+            .iter()
+            .filter(move |(value_field, _)| *value_field == field)
+            .map(move |(_, range)| &doc_bytes[range.clone()]))
+    }
+
+    /// Returns true if the document has at least one value for `field`.
+    pub fn has_field(&self, field: Field) -> crate::Result<bool> {
+        Ok(self.field_values(field)?.next().is_some())
+    }
+
+    /// Returns the fields of the document, in the order of their values. A field appears
+    /// once per value.
+    pub fn fields(&self) -> crate::Result<Vec<Field>> {
+        Ok(self.values()?.iter().map(|(field, _)| *field).collect())
+    }
+
+    /// Decodes the first value of `field`, if any.
+    pub fn get_first<V: ValueDeserialize>(&self, field: Field) -> crate::Result<Option<V>> {
+        let Some(value_bytes) = self.field_values(field)?.next() else {
+            return Ok(None);
+        };
+        Ok(Some(deserialize_value(value_bytes)?))
+    }
+
+    /// Decodes all of the values of `field`.
+    pub fn get_all<V: ValueDeserialize>(&self, field: Field) -> crate::Result<Vec<V>> {
+        self.field_values(field)?
+            .map(|value_bytes| deserialize_value(value_bytes).map_err(crate::TantivyError::from))
+            .collect()
+    }
+
+    /// Decodes the entire document.
+    pub fn deserialize<D: DocumentDeserialize>(&self) -> crate::Result<D> {
+        let mut doc_bytes = self.doc_bytes.as_slice();
+        let deserializer = BinaryDocumentDeserializer::from_reader(&mut doc_bytes)?;
+        Ok(D::deserialize(deserializer)?)
+    }
+
+    /// Returns the size of the serialized document, in bytes.
+    pub fn num_bytes(&self) -> usize {
+        self.doc_bytes.len()
+    }
+}
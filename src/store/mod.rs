@@ -5,7 +5,8 @@
 //!
 //! Internally, documents (or rather their stored fields) are serialized to a buffer.
 //! When the buffer exceeds `block_size` (defaults to 16K), the buffer is compressed
-//! using LZ4 or Zstd and the resulting block is written to disk.
+//! using LZ4, Zstd or a custom codec registered with [`register_store_codec`], and the
+//! resulting block is written to disk.
 //!
 //! One can then request for a specific `DocId`.
 //! A skip list helps navigating to the right block,
@@ -29,14 +30,20 @@
 //! [`SegmentReader`'s `doc` method](../struct.SegmentReader.html#method.doc)
 //! - at the index level, the [`Searcher::doc()`](crate::Searcher::doc) method
 
+mod blob;
+mod codec_registry;
 mod compressors;
 mod decompressors;
 mod footer;
 mod index;
+mod lazy_document;
 mod reader;
 mod writer;
+pub use self::blob::{Blob, BlobReader, BlobStoreReader, BlobStoreWriter};
+pub use self::codec_registry::{register_store_codec, StoreCodec, MIN_CUSTOM_CODEC_ID};
 pub use self::compressors::{Compressor, ZstdCompressor};
 pub use self::decompressors::Decompressor;
+pub use self::lazy_document::LazyDocument;
 pub(crate) use self::reader::DOCSTORE_CACHE_CAPACITY;
 pub use self::reader::{CacheStats, StoreReader};
 pub use self::writer::StoreWriter;
@@ -54,6 +61,7 @@ mod compression_zstd_block;
 #[cfg(test)]
 pub mod tests {
 
+    use std::io;
     use std::path::Path;
 
     use super::*;
@@ -204,6 +212,55 @@ pub mod tests {
         test_store(Compressor::Lz4, BLOCK_SIZE, true)
     }
 
+    /// Stores the blocks reversed.
+    struct ReversingCodec;
+
+    impl StoreCodec for ReversingCodec {
+        fn name(&self) -> &'static str {
+            "reversing"
+        }
+
+        fn compress_into(&self, uncompressed: &[u8], compressed: &mut Vec<u8>) -> io::Result<()> {
+            compressed.clear();
+            compressed.extend(uncompressed.iter().rev());
+            Ok(())
+        }
+
+        fn decompress_into(&self, compressed: &[u8], decompressed: &mut Vec<u8>) -> io::Result<()> {
+            self.compress_into(compressed, decompressed)
+        }
+    }
+
+    #[test]
+    fn test_store_custom_codec() -> crate::Result<()> {
+        register_store_codec(200, ReversingCodec)?;
+        assert!(register_store_codec(201, ReversingCodec).is_err());
+        assert!(register_store_codec(MIN_CUSTOM_CODEC_ID - 1, ReversingCodec).is_err());
+
+        let compressor = Compressor::Custom(200);
+        assert_eq!(serde_json::to_string(&compressor).unwrap(), "\"reversing\"");
+        assert_eq!(
+            serde_json::from_str::<Compressor>("\"reversing\"").unwrap(),
+            compressor
+        );
+        test_store(compressor, BLOCK_SIZE, true)?;
+
+        let path = Path::new("store");
+        let directory = RamDirectory::create();
+        write_lorem_ipsum_store(
+            directory.open_write(path)?,
+            10,
+            compressor,
+            BLOCK_SIZE,
+            false,
+        );
+        let store = StoreReader::open(directory.open_read(path)?, 10)?;
+        assert_eq!(store.decompressor(), Decompressor::Custom(200));
+        // Segments compressed with a codec that is not registered cannot be read.
+        assert!(Decompressor::Custom(201).decompress(b"abc").is_err());
+        Ok(())
+    }
+
     #[cfg(feature = "zstd-compression")]
     #[test]
     fn test_store_zstd() -> crate::Result<()> {
@@ -214,6 +271,62 @@ pub mod tests {
         )
     }
 
+    #[cfg(feature = "zstd-compression")]
+    #[test]
+    fn test_store_zstd_dictionary() -> crate::Result<()> {
+        let compressor = Compressor::Zstd(ZstdCompressor {
+            compression_level: None,
+            dict_size: Some(4_096),
+        });
+        test_store(compressor, BLOCK_SIZE, false)?;
+        test_store(compressor, BLOCK_SIZE, true)?;
+
+        let path = Path::new("store");
+        let directory = RamDirectory::create();
+        let store_wrt = directory.open_write(path)?;
+        write_lorem_ipsum_store(store_wrt, NUM_DOCS, compressor, BLOCK_SIZE, true);
+        let store = StoreReader::open(directory.open_read(path)?, 10)?;
+        assert!(store.has_dictionary());
+        assert_eq!(store.decompressor(), Decompressor::Zstd);
+        Ok(())
+    }
+
+    #[cfg(feature = "zstd-compression")]
+    #[test]
+    fn test_docstore_settings_are_persisted() -> crate::Result<()> {
+        let mut schema_builder = schema::Schema::builder();
+        let text_field = schema_builder.add_text_field("text_field", TEXT | STORED);
+        let schema = schema_builder.build();
+        let settings = crate::IndexSettings {
+            docstore_compression: Compressor::Zstd(ZstdCompressor {
+                compression_level: Some(19),
+                dict_size: None,
+            }),
+            docstore_blocksize: 1_000_000,
+            ..Default::default()
+        };
+        let directory = RamDirectory::create();
+        Index::builder()
+            .schema(schema)
+            .settings(settings.clone())
+            .open_or_create(directory.clone())?;
+        let index = Index::open(directory)?;
+        assert_eq!(index.settings(), &settings);
+        {
+            let mut index_writer: IndexWriter = index.writer_for_tests()?;
+            for _ in 0..200 {
+                index_writer.add_document(doc!(text_field=> LOREM))?;
+            }
+            index_writer.commit()?;
+        }
+        let searcher = index.reader()?.searcher();
+        let store = searcher.segment_reader(0).get_store_reader(10)?;
+        // All of the documents fit in a single block.
+        assert_eq!(store.block_checkpoints().count(), 1);
+        assert_eq!(store.decompressor(), Decompressor::Zstd);
+        Ok(())
+    }
+
     #[test]
     fn test_store_with_delete() -> crate::Result<()> {
         let mut schema_builder = schema::Schema::builder();
@@ -317,6 +430,335 @@ pub mod tests {
         Ok(())
     }
 
+    #[cfg(feature = "zstd-compression")]
+    #[test]
+    fn test_merge_with_zstd_dictionary() -> crate::Result<()> {
+        let mut schema_builder = schema::Schema::builder();
+        let text_field = schema_builder.add_text_field("text_field", TEXT | STORED);
+        let schema = schema_builder.build();
+        let mut index = Index::builder().schema(schema).create_in_ram()?;
+        index.settings_mut().docstore_compression = Compressor::Zstd(ZstdCompressor {
+            compression_level: None,
+            dict_size: Some(4_096),
+        });
+        {
+            let mut index_writer: IndexWriter = index.writer_for_tests()?;
+            for commit in 0..2 {
+                for i in 0..200 {
+                    index_writer
+                        .add_document(doc!(text_field=> format!("{commit} {i} {LOREM}")))?;
+                }
+                index_writer.commit()?;
+            }
+            let segment_ids = index.searchable_segment_ids()?;
+            index_writer.merge(&segment_ids).wait()?;
+            index_writer.wait_merging_threads()?;
+        }
+        let searcher = index.reader()?.searcher();
+        assert_eq!(searcher.segment_readers().len(), 1);
+        let reader = searcher.segment_reader(0);
+        let store = reader.get_store_reader(10)?;
+        assert!(store.has_dictionary());
+        let mut texts: Vec<String> = store
+            .iter::<TantivyDocument>(reader.alive_bitset())
+            .map(|doc| {
+                let doc = doc?;
+                Ok(doc
+                    .get_first(text_field)
+                    .unwrap()
+                    .as_str()
+                    .unwrap()
+                    .to_string())
+            })
+            .collect::<crate::Result<_>>()?;
+        texts.sort();
+        assert_eq!(texts.len(), 400);
+        assert_eq!(texts[0], format!("0 0 {LOREM}"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_doc_fields() -> crate::Result<()> {
+        use crate::DocAddress;
+
+        let mut schema_builder = schema::Schema::builder();
+        let title_field = schema_builder.add_text_field("title", TEXT | STORED);
+        let body_field = schema_builder.add_text_field("body", TEXT | STORED);
+        let count_field = schema_builder.add_u64_field("count", STORED);
+        let index = Index::create_in_ram(schema_builder.build());
+        {
+            let mut index_writer: IndexWriter = index.writer_for_tests()?;
+            index_writer.add_document(doc!(
+                title_field => "first",
+                body_field => LOREM,
+                count_field => 1u64,
+                title_field => "second",
+            ))?;
+            index_writer.commit()?;
+        }
+        let searcher = index.reader()?.searcher();
+        let doc_address = DocAddress::new(0, 0);
+        let doc: TantivyDocument = searcher.doc_fields(doc_address, &[title_field, count_field])?;
+        let titles: Vec<&str> = doc
+            .get_all(title_field)
+            .map(|value| value.as_str().unwrap())
+            .collect();
+        assert_eq!(titles, vec!["first", "second"]);
+        assert_eq!(doc.get_first(count_field).unwrap().as_u64(), Some(1));
+        assert!(doc.get_first(body_field).is_none());
+        let doc: TantivyDocument = searcher.doc_fields(doc_address, &[])?;
+        assert_eq!(doc.field_values().count(), 0);
+        Ok(())
+    }
+
+    #[test]
+    fn test_docs_in_input_order() -> crate::Result<()> {
+        use crate::schema::INDEXED;
+        use crate::DocAddress;
+
+        let mut schema_builder = schema::Schema::builder();
+        let id_field = schema_builder.add_u64_field("id", INDEXED | STORED);
+        let index = Index::create_in_ram(schema_builder.build());
+        {
+            let mut index_writer: IndexWriter = index.writer_for_tests()?;
+            for segment in 0..2u64 {
+                for id in 0..1_000u64 {
+                    index_writer.add_document(doc!(id_field => segment * 1_000 + id))?;
+                }
+                index_writer.commit()?;
+            }
+        }
+        let searcher = index.reader()?.searcher();
+        let doc_addresses: Vec<DocAddress> = [(1, 999), (0, 3), (1, 0), (0, 3), (0, 998)]
+            .into_iter()
+            .map(|(segment_ord, doc_id)| DocAddress::new(segment_ord, doc_id))
+            .collect();
+        let docs: Vec<TantivyDocument> = searcher.docs(&doc_addresses)?;
+        let doc_ids: Vec<u64> = docs
+            .iter()
+            .map(|doc| doc.get_first(id_field).unwrap().as_u64().unwrap() % 1_000)
+            .collect();
+        let expected_doc_ids: Vec<u64> = doc_addresses
+            .iter()
+            .map(|doc_address| doc_address.doc_id as u64)
+            .collect();
+        assert_eq!(doc_ids, expected_doc_ids);
+        assert!(searcher.docs::<TantivyDocument>(&[]).unwrap().is_empty());
+        assert!(searcher
+            .docs::<TantivyDocument>(&[DocAddress::new(0, 1_000)])
+            .is_err());
+        Ok(())
+    }
+
+    #[cfg(feature = "quickwit")]
+    #[test]
+    fn test_docs_async() -> crate::Result<()> {
+        use futures::executor::block_on;
+
+        use crate::schema::INDEXED;
+        use crate::DocAddress;
+
+        let mut schema_builder = schema::Schema::builder();
+        let id_field = schema_builder.add_u64_field("id", INDEXED | STORED);
+        let index = Index::create_in_ram(schema_builder.build());
+        {
+            let mut index_writer: IndexWriter = index.writer_for_tests()?;
+            for segment in 0..2u64 {
+                for id in 0..1_000u64 {
+                    index_writer.add_document(doc!(id_field => segment * 1_000 + id))?;
+                }
+                index_writer.commit()?;
+            }
+        }
+        let searcher = index.reader()?.searcher();
+        let get_id = |doc: &TantivyDocument| doc.get_first(id_field).unwrap().as_u64().unwrap();
+        let segment_base_id = |segment_ord: u32| -> crate::Result<u64> {
+            let doc: TantivyDocument = searcher.doc(DocAddress::new(segment_ord, 0))?;
+            Ok(get_id(&doc))
+        };
+        let doc_address = DocAddress::new(1, 10);
+        let doc: TantivyDocument = block_on(searcher.doc_async(doc_address))?;
+        assert_eq!(get_id(&doc), segment_base_id(1)? + 10);
+
+        let doc_addresses: Vec<DocAddress> = [(1, 999), (0, 3), (1, 0), (0, 3), (0, 998)]
+            .into_iter()
+            .map(|(segment_ord, doc_id)| DocAddress::new(segment_ord, doc_id))
+            .collect();
+        let docs: Vec<TantivyDocument> = block_on(searcher.docs_async(&doc_addresses))?;
+        let ids: Vec<u64> = docs.iter().map(get_id).collect();
+        let expected_ids: Vec<u64> = doc_addresses
+            .iter()
+            .map(|doc_address| {
+                segment_base_id(doc_address.segment_ord).unwrap() + doc_address.doc_id as u64
+            })
+            .collect();
+        assert_eq!(ids, expected_ids);
+        assert!(
+            block_on(searcher.docs_async::<TantivyDocument>(&[DocAddress::new(0, 1_000)])).is_err()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_lazy_document() -> crate::Result<()> {
+        use crate::DocAddress;
+
+        let mut schema_builder = schema::Schema::builder();
+        let title_field = schema_builder.add_text_field("title", TEXT | STORED);
+        let body_field = schema_builder.add_text_field("body", TEXT | STORED);
+        let count_field = schema_builder.add_u64_field("count", STORED);
+        let missing_field = schema_builder.add_u64_field("missing", STORED);
+        let index = Index::create_in_ram(schema_builder.build());
+        {
+            let mut index_writer: IndexWriter = index.writer_for_tests()?;
+            index_writer.add_document(doc!(
+                title_field => "first",
+                body_field => LOREM,
+                count_field => 1u64,
+                title_field => "second",
+            ))?;
+            index_writer.commit()?;
+        }
+        let searcher = index.reader()?.searcher();
+        let doc = searcher.doc_lazy(DocAddress::new(0, 0))?;
+        assert_eq!(doc.get_first::<u64>(count_field)?, Some(1));
+        assert_eq!(
+            doc.get_all::<String>(title_field)?,
+            vec!["first".to_string(), "second".to_string()]
+        );
+        assert!(doc.has_field(body_field)?);
+        assert!(!doc.has_field(missing_field)?);
+        assert_eq!(doc.get_first::<u64>(missing_field)?, None);
+        assert_eq!(
+            doc.fields()?,
+            vec![title_field, body_field, count_field, title_field]
+        );
+        assert!(doc.get_first::<u64>(title_field).is_err());
+        let full_doc: TantivyDocument = doc.deserialize()?;
+        assert_eq!(
+            full_doc.get_first(body_field).unwrap().as_str(),
+            Some(LOREM)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_doc_with_json_path_filter() -> crate::Result<()> {
+        use serde_json::json;
+
+        use crate::schema::{Document, JsonPathFilter};
+        use crate::DocAddress;
+
+        let mut schema_builder = schema::Schema::builder();
+        schema_builder.add_text_field("title", TEXT | STORED);
+        schema_builder.add_json_field("attributes", STORED);
+        let schema = schema_builder.build();
+        let index = Index::create_in_ram(schema.clone());
+        {
+            let mut index_writer: IndexWriter = index.writer_for_tests()?;
+            index_writer.add_document(TantivyDocument::parse_json(
+                &schema,
+                r#"{
+                    "title": "hello",
+                    "attributes": {
+                        "price": 10,
+                        "author": {"name": "Paul", "email": "paul@example.com"},
+                        "tags": [{"name": "a", "internal": true}, {"name": "b"}, "c"],
+                        "body": "long text"
+                    }
+                }"#,
+            )?)?;
+            index_writer.commit()?;
+        }
+        let searcher = index.reader()?.searcher();
+        let fetch = |filter: &JsonPathFilter| -> crate::Result<serde_json::Value> {
+            let doc: TantivyDocument =
+                searcher.doc_with_json_path_filter(DocAddress::new(0, 0), filter)?;
+            Ok(serde_json::from_str(&doc.to_json(&schema)).unwrap())
+        };
+
+        let filter = JsonPathFilter::default()
+            .include("attributes.price")
+            .include("attributes.author.name")
+            .include("attributes.tags.*")
+            .exclude("*.internal");
+        assert_eq!(
+            fetch(&filter)?,
+            json!({
+                "title": ["hello"],
+                "attributes": [{
+                    "price": 10,
+                    "author": {"name": "Paul"},
+                    "tags": [{"name": "a"}, {"name": "b"}],
+                }],
+            })
+        );
+
+        let filter = JsonPathFilter::default().exclude("attributes.*");
+        assert_eq!(fetch(&filter)?, json!({"title": ["hello"]}));
+
+        let filter = JsonPathFilter::default().exclude("attributes.body");
+        let doc = fetch(&filter)?;
+        assert_eq!(doc["attributes"][0]["tags"][2], json!("c"));
+        assert!(doc["attributes"][0].get("body").is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn test_blob_fields() -> crate::Result<()> {
+        use std::io::Read;
+
+        use crate::schema::{BytesOptions, INDEXED};
+        use crate::DocAddress;
+
+        let mut schema_builder = schema::Schema::builder();
+        let id_field = schema_builder.add_u64_field("id", INDEXED | STORED);
+        let attachment_field =
+            schema_builder.add_bytes_field("attachment", BytesOptions::default().set_blob());
+        let index = Index::create_in_ram(schema_builder.build());
+        let payload = |id: u64| vec![id as u8; (id as usize + 1) * 10_000];
+        {
+            let mut index_writer: IndexWriter = index.writer_for_tests()?;
+            for id in 0..3u64 {
+                index_writer.add_document(doc!(
+                    id_field => id,
+                    attachment_field => payload(id)
+                ))?;
+            }
+            index_writer.commit()?;
+            index_writer.add_document(doc!(id_field => 3u64))?;
+            index_writer.add_document(doc!(id_field => 4u64, attachment_field => payload(4)))?;
+            index_writer.commit()?;
+            index_writer.delete_term(Term::from_field_u64(id_field, 1));
+            index_writer.commit()?;
+            let segment_ids = index.searchable_segment_ids()?;
+            assert_eq!(segment_ids.len(), 2);
+            index_writer.merge(&segment_ids).wait()?;
+            index_writer.wait_merging_threads()?;
+        }
+        let searcher = index.reader()?.searcher();
+        assert_eq!(searcher.segment_readers().len(), 1);
+        assert_eq!(searcher.num_docs(), 4);
+        for doc_id in 0..4 {
+            let doc_address = DocAddress::new(0, doc_id);
+            let doc: TantivyDocument = searcher.doc(doc_address)?;
+            let id = doc.get_first(id_field).unwrap().as_u64().unwrap();
+            // Blob fields are not part of the stored document.
+            assert!(doc.get_first(attachment_field).is_none());
+            let blobs = searcher.blobs(doc_address, attachment_field)?;
+            if id == 3 {
+                assert!(blobs.is_empty());
+                continue;
+            }
+            assert_eq!(blobs.len(), 1);
+            let mut content = Vec::new();
+            blobs[0].reader().read_to_end(&mut content)?;
+            assert_eq!(content, payload(id));
+        }
+        assert!(searcher.blobs(DocAddress::new(0, 0), id_field)?.is_empty());
+        Ok(())
+    }
+
     #[test]
     fn test_merge_of_small_segments() -> crate::Result<()> {
         let mut schema_builder = schema::Schema::builder();
@@ -10,11 +10,13 @@ use lru::LruCache;
 
 use super::footer::DocStoreFooter;
 use super::index::SkipIndex;
+use super::lazy_document::LazyDocument;
 use super::Decompressor;
 use crate::directory::FileSlice;
 use crate::error::DataCorruption;
 use crate::fastfield::AliveBitSet;
 use crate::schema::document::{BinaryDocumentDeserializer, DocumentDeserialize};
+use crate::schema::{Field, JsonPathFilter, Schema};
 use crate::space_usage::StoreSpaceUsage;
 use crate::store::index::Checkpoint;
 use crate::DocId;
@@ -27,13 +29,44 @@ type Block = OwnedBytes;
 
 /// Reads document off tantivy's [`Store`](./index.html)
 pub struct StoreReader {
-    decompressor: Decompressor,
+    decompressor: BlockDecompressor,
     data: FileSlice,
     skip_index: Arc<SkipIndex>,
     space_usage: StoreSpaceUsage,
     cache: BlockCache,
 }
 
+/// Decompresses blocks, using the dictionary stored in the doc store if any.
+#[derive(Clone)]
+struct BlockDecompressor {
+    decompressor: Decompressor,
+    #[cfg(feature = "zstd-compression")]
+    dictionary: Option<Arc<zstd::dict::DecoderDictionary<'static>>>,
+}
+
+impl BlockDecompressor {
+    fn has_dictionary(&self) -> bool {
+        #[cfg(feature = "zstd-compression")]
+        return self.dictionary.is_some();
+        #[cfg(not(feature = "zstd-compression"))]
+        false
+    }
+
+    fn decompress(&self, compressed_block: &[u8]) -> io::Result<Vec<u8>> {
+        #[cfg(feature = "zstd-compression")]
+        if let Some(dictionary) = &self.dictionary {
+            let mut decompressed_block = Vec::new();
+            super::compression_zstd_block::decompress_with_dictionary(
+                compressed_block,
+                &mut decompressed_block,
+                dictionary,
+            )?;
+            return Ok(decompressed_block);
+        }
+        self.decompressor.decompress(compressed_block)
+    }
+}
+
 /// The cache for decompressed blocks.
 struct BlockCache {
     cache: Option<Mutex<LruCache<usize, Block>>>,
@@ -115,6 +148,12 @@ impl Sum for CacheStats {
 }
 
 impl StoreReader {
+    /// Returns the part of the doc store file holding the index of its blocks.
+    pub(crate) fn index_file_slice(store_file: FileSlice) -> io::Result<FileSlice> {
+        let (footer, data_and_offset) = DocStoreFooter::extract_footer(store_file)?;
+        Ok(data_and_offset.slice_from(footer.offset as usize))
+    }
+
     /// Opens a store reader
     ///
     /// `cache_num_blocks` sets the number of decompressed blocks to be cached in an LRU.
@@ -122,13 +161,39 @@ impl StoreReader {
     pub fn open(store_file: FileSlice, cache_num_blocks: usize) -> io::Result<StoreReader> {
         let (footer, data_and_offset) = DocStoreFooter::extract_footer(store_file)?;
 
-        let (data_file, offset_index_file) = data_and_offset.split(footer.offset as usize);
+        let (data_and_dictionary, offset_index_file) =
+            data_and_offset.split(footer.offset as usize);
+        let (data_file, dictionary_file) =
+            data_and_dictionary.split_from_end(footer.dictionary_num_bytes as usize);
+        #[cfg(feature = "zstd-compression")]
+        let dictionary = if dictionary_file.is_empty() {
+            None
+        } else {
+            let dictionary_bytes = dictionary_file.read_bytes()?;
+            Some(Arc::new(zstd::dict::DecoderDictionary::copy(
+                dictionary_bytes.as_slice(),
+            )))
+        };
+        #[cfg(not(feature = "zstd-compression"))]
+        if !dictionary_file.is_empty() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "The doc store is compressed with a dictionary, which requires the \
+                 `zstd-compression` feature.",
+            ));
+        }
         let index_data = offset_index_file.read_bytes()?;
-        let space_usage =
-            StoreSpaceUsage::new(data_file.num_bytes(), offset_index_file.num_bytes());
+        let space_usage = StoreSpaceUsage::new(
+            data_and_dictionary.num_bytes(),
+            offset_index_file.num_bytes(),
+        );
         let skip_index = SkipIndex::open(index_data);
         Ok(StoreReader {
-            decompressor: footer.decompressor,
+            decompressor: BlockDecompressor {
+                decompressor: footer.decompressor,
+                #[cfg(feature = "zstd-compression")]
+                dictionary,
+            },
             data: data_file,
             cache: BlockCache {
                 cache: NonZeroUsize::new(cache_num_blocks)
@@ -146,7 +211,12 @@ impl StoreReader {
     }
 
     pub(crate) fn decompressor(&self) -> Decompressor {
-        self.decompressor
+        self.decompressor.decompressor
+    }
+
+    /// Returns true if the blocks are compressed with a dictionary stored in the doc store.
+    pub(crate) fn has_dictionary(&self) -> bool {
+        self.decompressor.has_dictionary()
     }
 
     /// Returns the cache hit and miss statistics of the store reader.
@@ -208,6 +278,80 @@ impl StoreReader {
         D::deserialize(deserializer).map_err(crate::TantivyError::from)
     }
 
+    /// Reads several documents, returned in the order of `doc_ids`.
+    ///
+    /// Documents are read ordered by doc id, so that each block is only decompressed once,
+    /// regardless of the size of the cache.
+    pub fn get_many<D: DocumentDeserialize>(&self, doc_ids: &[DocId]) -> crate::Result<Vec<D>> {
+        let mut docs = Vec::with_capacity(doc_ids.len());
+        let mut current_block: Option<(Checkpoint, Block)> = None;
+        for (pos, doc_id) in sorted_by_doc_id(doc_ids) {
+            let (checkpoint, block) = match current_block.take() {
+                Some((checkpoint, block)) if checkpoint.doc_range.contains(&doc_id) => {
+                    (checkpoint, block)
+                }
+                _ => {
+                    let checkpoint = self.block_checkpoint(doc_id)?;
+                    let block = self.read_block(&checkpoint)?;
+                    (checkpoint, block)
+                }
+            };
+            let doc_bytes =
+                Self::get_document_bytes_from_block(block.clone(), doc_id, &checkpoint)?;
+            docs.push((pos, deserialize_doc(doc_bytes)?));
+            current_block = Some((checkpoint, block));
+        }
+        Ok(restore_order(docs))
+    }
+
+    /// Reads the values of the given fields of a document.
+    ///
+    /// The block still needs to be decompressed, but the values of the other fields are
+    /// skipped rather than decoded. The returned document only contains the requested
+    /// fields.
+    pub fn get_fields<D: DocumentDeserialize>(
+        &self,
+        doc_id: DocId,
+        fields: &[Field],
+    ) -> crate::Result<D> {
+        let mut doc_bytes = self.get_document_bytes(doc_id)?;
+
+        let deserializer =
+            BinaryDocumentDeserializer::from_reader_with_fields(&mut doc_bytes, fields)
+                .map_err(crate::TantivyError::from)?;
+        D::deserialize(deserializer).map_err(crate::TantivyError::from)
+    }
+
+    /// Reads a document, only returning the parts of its JSON fields accepted by
+    /// `json_path_filter`.
+    ///
+    /// The values that are filtered out are skipped rather than decoded. `schema` must be
+    /// the schema of the index.
+    pub fn get_with_json_path_filter<D: DocumentDeserialize>(
+        &self,
+        doc_id: DocId,
+        json_path_filter: &JsonPathFilter,
+        schema: &Schema,
+    ) -> crate::Result<D> {
+        let mut doc_bytes = self.get_document_bytes(doc_id)?;
+
+        let deserializer = BinaryDocumentDeserializer::from_reader_with_json_path_filter(
+            &mut doc_bytes,
+            json_path_filter,
+            schema,
+        )
+        .map_err(crate::TantivyError::from)?;
+        D::deserialize(deserializer).map_err(crate::TantivyError::from)
+    }
+
+    /// Returns a document whose values are only decoded when accessed.
+    ///
+    /// See [`LazyDocument`].
+    pub fn get_lazy(&self, doc_id: DocId) -> crate::Result<LazyDocument> {
+        let doc_bytes = self.get_document_bytes(doc_id)?;
+        Ok(LazyDocument::new(doc_bytes))
+    }
+
     /// Returns raw bytes of a given document.
     ///
     /// Calling `.get(doc)` is relatively costly as it requires
@@ -314,6 +458,25 @@ impl StoreReader {
     }
 }
 
+/// Returns the doc ids, along with their position in `doc_ids`, sorted by doc id.
+fn sorted_by_doc_id(doc_ids: &[DocId]) -> Vec<(usize, DocId)> {
+    let mut sorted_doc_ids: Vec<(usize, DocId)> = doc_ids.iter().copied().enumerate().collect();
+    sorted_doc_ids.sort_by_key(|&(_, doc_id)| doc_id);
+    sorted_doc_ids
+}
+
+/// Sorts documents by their position, and drops the positions.
+fn restore_order<D>(mut docs: Vec<(usize, D)>) -> Vec<D> {
+    docs.sort_by_key(|(pos, _)| *pos);
+    docs.into_iter().map(|(_, doc)| doc).collect()
+}
+
+fn deserialize_doc<D: DocumentDeserialize>(mut doc_bytes: OwnedBytes) -> crate::Result<D> {
+    let deserializer = BinaryDocumentDeserializer::from_reader(&mut doc_bytes)
+        .map_err(crate::TantivyError::from)?;
+    D::deserialize(deserializer).map_err(crate::TantivyError::from)
+}
+
 fn block_read_index(block: &[u8], doc_pos: u32) -> crate::Result<Range<usize>> {
     let doc_pos = doc_pos as usize;
     let size_of_u32 = std::mem::size_of::<u32>();
@@ -359,7 +522,7 @@ impl StoreReader {
             .read_bytes_async()
             .await?;
 
-        let decompressor = self.decompressor;
+        let decompressor = self.decompressor.clone();
         let maybe_decompressed_block = executor
             .spawn_blocking(move || decompressor.decompress(compressed_block.as_ref()))
             .await
@@ -395,6 +558,48 @@ impl StoreReader {
             .map_err(crate::TantivyError::from)?;
         D::deserialize(deserializer).map_err(crate::TantivyError::from)
     }
+
+    /// Reads several documents asynchronously. Async version of
+    /// [`get_many`](Self::get_many).
+    ///
+    /// The blocks holding the documents are read concurrently, which hides most of the
+    /// latency of high-latency storage.
+    pub async fn get_many_async<D: DocumentDeserialize>(
+        &self,
+        doc_ids: &[DocId],
+        executor: &Executor,
+    ) -> crate::Result<Vec<D>> {
+        let sorted_doc_ids = sorted_by_doc_id(doc_ids);
+        let mut checkpoints: Vec<Checkpoint> = Vec::new();
+        for &(_, doc_id) in &sorted_doc_ids {
+            let in_last_block = checkpoints
+                .last()
+                .map_or(false, |checkpoint| checkpoint.doc_range.contains(&doc_id));
+            if !in_last_block {
+                checkpoints.push(self.block_checkpoint(doc_id)?);
+            }
+        }
+        let blocks = futures_util::future::try_join_all(
+            checkpoints
+                .iter()
+                .map(|checkpoint| self.read_block_async(checkpoint, executor)),
+        )
+        .await?;
+        let mut docs = Vec::with_capacity(doc_ids.len());
+        let mut block_ord = 0;
+        for (pos, doc_id) in sorted_doc_ids {
+            while !checkpoints[block_ord].doc_range.contains(&doc_id) {
+                block_ord += 1;
+            }
+            let doc_bytes = Self::get_document_bytes_from_block(
+                blocks[block_ord].clone(),
+                doc_id,
+                &checkpoints[block_ord],
+            )?;
+            docs.push((pos, deserialize_doc(doc_bytes)?));
+        }
+        Ok(restore_order(docs))
+    }
 }
 
 #[cfg(test)]
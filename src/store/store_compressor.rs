@@ -53,6 +53,20 @@ impl BlockCompressor {
         Ok(())
     }
 
+    /// Sets the dictionary used to compress the following blocks.
+    #[cfg(feature = "zstd-compression")]
+    pub fn set_dictionary(&mut self, dictionary: Vec<u8>) -> io::Result<()> {
+        match &mut self.0 {
+            BlockCompressorVariants::SameThread(block_compressor) => {
+                block_compressor.set_dictionary(dictionary)?;
+            }
+            BlockCompressorVariants::DedicatedThread(different_thread_block_compressor) => {
+                different_thread_block_compressor.set_dictionary(dictionary)?;
+            }
+        }
+        Ok(())
+    }
+
     pub fn stack_reader(&mut self, store_reader: StoreReader) -> io::Result<()> {
         match &mut self.0 {
             BlockCompressorVariants::SameThread(block_compressor) => {
@@ -78,6 +92,9 @@ impl BlockCompressor {
 
 struct BlockCompressorImpl {
     compressor: Compressor,
+    /// Dictionary set with `set_dictionary`, and the compressor using it.
+    #[cfg(feature = "zstd-compression")]
+    dictionary: Option<(Vec<u8>, zstd::bulk::Compressor<'static>)>,
     first_doc_in_block: DocId,
     offset_index_writer: SkipIndexBuilder,
     intermediary_buffer: Vec<u8>,
@@ -88,6 +105,8 @@ impl BlockCompressorImpl {
     fn new(compressor: Compressor, writer: WritePtr) -> Self {
         Self {
             compressor,
+            #[cfg(feature = "zstd-compression")]
+            dictionary: None,
             first_doc_in_block: 0,
             offset_index_writer: SkipIndexBuilder::new(),
             intermediary_buffer: Vec::new(),
@@ -98,8 +117,7 @@ impl BlockCompressorImpl {
     fn compress_block_and_write(&mut self, data: &[u8], num_docs_in_block: u32) -> io::Result<()> {
         assert!(num_docs_in_block > 0);
         self.intermediary_buffer.clear();
-        self.compressor
-            .compress_into(data, &mut self.intermediary_buffer)?;
+        self.compress_into_buffer(data)?;
 
         let start_offset = self.writer.written_bytes() as usize;
         self.writer.write_all(&self.intermediary_buffer)?;
@@ -112,6 +130,45 @@ impl BlockCompressorImpl {
         Ok(())
     }
 
+    fn compress_into_buffer(&mut self, data: &[u8]) -> io::Result<()> {
+        #[cfg(feature = "zstd-compression")]
+        if let Some((_, dictionary_compressor)) = &mut self.dictionary {
+            return super::compression_zstd_block::compress_with_dictionary(
+                data,
+                &mut self.intermediary_buffer,
+                dictionary_compressor,
+            );
+        }
+        self.compressor
+            .compress_into(data, &mut self.intermediary_buffer)
+    }
+
+    #[cfg(feature = "zstd-compression")]
+    fn set_dictionary(&mut self, dictionary: Vec<u8>) -> io::Result<()> {
+        let Compressor::Zstd(zstd_compressor) = self.compressor else {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "Dictionaries are only supported by the zstd compressor.",
+            ));
+        };
+        let dictionary_compressor = super::compression_zstd_block::dictionary_compressor(
+            &dictionary,
+            zstd_compressor.compression_level,
+        )?;
+        self.dictionary = Some((dictionary, dictionary_compressor));
+        Ok(())
+    }
+
+    /// Writes the dictionary, if any, and returns its size.
+    fn write_dictionary(&mut self) -> io::Result<u32> {
+        #[cfg(feature = "zstd-compression")]
+        if let Some((dictionary, _)) = &self.dictionary {
+            self.writer.write_all(dictionary)?;
+            return Ok(dictionary.len() as u32);
+        }
+        Ok(0)
+    }
+
     fn register_checkpoint(&mut self, checkpoint: Checkpoint) {
         self.offset_index_writer.insert(checkpoint.clone());
         self.first_doc_in_block = checkpoint.doc_range.end;
@@ -142,9 +199,13 @@ impl BlockCompressorImpl {
     }
 
     fn close(mut self) -> io::Result<()> {
+        let dictionary_num_bytes = self.write_dictionary()?;
         let header_offset: u64 = self.writer.written_bytes();
-        let docstore_footer =
-            DocStoreFooter::new(header_offset, Decompressor::from(self.compressor));
+        let docstore_footer = DocStoreFooter::new(
+            header_offset,
+            Decompressor::from(self.compressor),
+            dictionary_num_bytes,
+        );
         self.offset_index_writer.serialize_into(&mut self.writer)?;
         docstore_footer.serialize(&mut self.writer)?;
         self.writer.terminate()
@@ -158,6 +219,8 @@ enum BlockCompressorMessage {
         num_docs_in_block: u32,
     },
     Stack(StoreReader),
+    #[cfg(feature = "zstd-compression")]
+    SetDictionary(Vec<u8>),
 }
 
 struct DedicatedThreadBlockCompressorImpl {
@@ -186,6 +249,10 @@ impl DedicatedThreadBlockCompressorImpl {
                         BlockCompressorMessage::Stack(store_reader) => {
                             block_compressor.stack(store_reader)?;
                         }
+                        #[cfg(feature = "zstd-compression")]
+                        BlockCompressorMessage::SetDictionary(dictionary) => {
+                            block_compressor.set_dictionary(dictionary)?;
+                        }
                     }
                 }
                 block_compressor.close()?;
@@ -208,6 +275,11 @@ impl DedicatedThreadBlockCompressorImpl {
         self.send(BlockCompressorMessage::Stack(store_reader))
     }
 
+    #[cfg(feature = "zstd-compression")]
+    fn set_dictionary(&mut self, dictionary: Vec<u8>) -> io::Result<()> {
+        self.send(BlockCompressorMessage::SetDictionary(dictionary))
+    }
+
     fn send(&mut self, msg: BlockCompressorMessage) -> io::Result<()> {
         if self.tx.send(msg).is_err() {
             harvest_thread_result(self.join_handle.take())?;
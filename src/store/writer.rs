@@ -1,4 +1,4 @@
-use std::io;
+use std::{io, iter};
 
 use common::BinarySerializable;
 
@@ -10,6 +10,61 @@ use crate::schema::Schema;
 use crate::store::store_compressor::BlockCompressor;
 use crate::DocId;
 
+/// Documents are buffered until the samples amount to this many times the size of the
+/// dictionary to train.
+const DICTIONARY_SAMPLES_RATIO: usize = 100;
+
+/// Blocks held back until enough documents have been stored to train a compression
+/// dictionary.
+#[cfg_attr(not(feature = "zstd-compression"), allow(dead_code))]
+struct DictionaryTraining {
+    dict_size: usize,
+    samples: Vec<u8>,
+    sample_sizes: Vec<usize>,
+    pending_blocks: Vec<(Vec<u8>, u32)>,
+}
+
+impl DictionaryTraining {
+    fn new(dict_size: usize) -> DictionaryTraining {
+        DictionaryTraining {
+            dict_size,
+            samples: Vec::new(),
+            sample_sizes: Vec::new(),
+            pending_blocks: Vec::new(),
+        }
+    }
+
+    /// Adds the documents of a block, given their start positions, as samples.
+    fn add_samples(&mut self, docs: &[u8], doc_pos: &[u32]) {
+        self.samples.extend_from_slice(docs);
+        let doc_ends = doc_pos
+            .iter()
+            .skip(1)
+            .copied()
+            .chain(iter::once(docs.len() as u32));
+        self.sample_sizes.extend(
+            doc_pos
+                .iter()
+                .zip(doc_ends)
+                .map(|(start, end)| (end - start) as usize),
+        );
+    }
+
+    fn has_enough_samples(&self) -> bool {
+        self.samples.len() >= self.dict_size * DICTIONARY_SAMPLES_RATIO
+    }
+
+    fn mem_usage(&self) -> usize {
+        self.samples.capacity()
+            + self.sample_sizes.capacity() * std::mem::size_of::<usize>()
+            + self
+                .pending_blocks
+                .iter()
+                .map(|(block, _)| block.capacity())
+                .sum::<usize>()
+    }
+}
+
 /// Write tantivy's [`Store`](./index.html)
 ///
 /// Contrary to the other components of `tantivy`,
@@ -24,6 +79,7 @@ pub struct StoreWriter {
     current_block: Vec<u8>,
     doc_pos: Vec<u32>,
     block_compressor: BlockCompressor,
+    dictionary_training: Option<DictionaryTraining>,
 }
 
 impl StoreWriter {
@@ -45,6 +101,7 @@ impl StoreWriter {
             doc_pos: Vec::new(),
             current_block: Vec::new(),
             block_compressor,
+            dictionary_training: compressor.dict_size().map(DictionaryTraining::new),
         })
     }
 
@@ -54,7 +111,12 @@ impl StoreWriter {
 
     /// The memory used (inclusive childs)
     pub fn mem_usage(&self) -> usize {
-        self.current_block.capacity() + self.doc_pos.capacity() * std::mem::size_of::<u32>()
+        self.current_block.capacity()
+            + self.doc_pos.capacity() * std::mem::size_of::<u32>()
+            + self
+                .dictionary_training
+                .as_ref()
+                .map_or(0, DictionaryTraining::mem_usage)
     }
 
     /// Checks if the current block is full, and if so, compresses and flushes it.
@@ -75,6 +137,10 @@ impl StoreWriter {
             return Ok(());
         }
 
+        if let Some(dictionary_training) = &mut self.dictionary_training {
+            dictionary_training.add_samples(&self.current_block, &self.doc_pos);
+        }
+
         let size_of_u32 = std::mem::size_of::<u32>();
         self.current_block
             .reserve((self.doc_pos.len() + 1) * size_of_u32);
@@ -84,14 +150,48 @@ impl StoreWriter {
         }
         (self.doc_pos.len() as u32).serialize(&mut self.current_block)?;
 
-        self.block_compressor
-            .compress_block_and_write(&self.current_block, self.num_docs_in_current_block)?;
+        if let Some(dictionary_training) = &mut self.dictionary_training {
+            dictionary_training
+                .pending_blocks
+                .push((self.current_block.clone(), self.num_docs_in_current_block));
+            if dictionary_training.has_enough_samples() {
+                self.train_dictionary_and_flush()?;
+            }
+        } else {
+            self.block_compressor
+                .compress_block_and_write(&self.current_block, self.num_docs_in_current_block)?;
+        }
         self.doc_pos.clear();
         self.current_block.clear();
         self.num_docs_in_current_block = 0;
         Ok(())
     }
 
+    /// Trains the compression dictionary on the buffered documents, and compresses the
+    /// blocks held back so far.
+    ///
+    /// If training fails, typically because there are too few documents, the blocks are
+    /// compressed without a dictionary.
+    fn train_dictionary_and_flush(&mut self) -> io::Result<()> {
+        let Some(dictionary_training) = self.dictionary_training.take() else {
+            return Ok(());
+        };
+        #[cfg(feature = "zstd-compression")]
+        match super::compression_zstd_block::train_dictionary(
+            &dictionary_training.samples,
+            &dictionary_training.sample_sizes,
+            dictionary_training.dict_size,
+        ) {
+            Ok(dictionary) => self.block_compressor.set_dictionary(dictionary)?,
+            Err(err) => debug!("Failed to train a doc store dictionary: {err:?}"),
+        }
+        for (block, num_docs_in_block) in dictionary_training.pending_blocks {
+            self.block_compressor
+                .compress_block_and_write(&block, num_docs_in_block)?;
+        }
+        Ok(())
+    }
+
     /// Store a new document.
     ///
     /// The document id is implicitly the current number
@@ -124,6 +224,13 @@ impl StoreWriter {
     /// in the store and adding them one by one, as the store's data will
     /// not be decompressed and then recompressed.
     pub fn stack(&mut self, store_reader: StoreReader) -> io::Result<()> {
+        // Blocks compressed with different dictionaries cannot be mixed.
+        if self.compressor.dict_size().is_some() || store_reader.has_dictionary() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "Doc stores compressed with a dictionary cannot be stacked.",
+            ));
+        }
         // We flush the current block first before stacking
         self.send_current_block_to_compressor()?;
         self.block_compressor.stack_reader(store_reader)?;
@@ -136,6 +243,7 @@ impl StoreWriter {
     /// and serializes the skip list index on disc.
     pub fn close(mut self) -> io::Result<()> {
         self.send_current_block_to_compressor()?;
+        self.train_dictionary_and_flush()?;
         self.block_compressor.close()?;
         Ok(())
     }
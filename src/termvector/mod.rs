@@ -0,0 +1,201 @@
+//! Term vectors list, for a given field of a given document, the terms of the
+//! document with the positions and offsets of each of their occurrences.
+//!
+//! They make it possible to highlight a document, or to find the terms
+//! characterizing it (e.g. for "more like this" queries), without reading the
+//! stored document and running the tokenizer again.
+//!
+//! Term vectors are only stored for the text fields whose indexing options
+//! enable them, see
+//! [`TextFieldIndexing::set_term_vectors`](crate::schema::TextFieldIndexing::set_term_vectors).
+//! They are written to a dedicated file of the segment, which only exists if the
+//! schema has at least one such field.
+mod reader;
+mod writer;
+
+use std::io;
+
+use common::{BinarySerializable, VInt};
+
+pub use self::reader::TermVectorsReader;
+pub use self::writer::TermVectorsWriter;
+pub(crate) use self::writer::{TermVectorBuilder, TermVectorRecorder};
+
+/// An occurrence of a term in a field of a document.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TermOccurrence {
+    /// Ordinal of the value of the field the term occurs in, for multivalued fields.
+    pub value_ord: u32,
+    /// Position of the term, as indexed in the positions of the inverted index.
+    pub position: u32,
+    /// Offset (byte index) of the first character of the term in the value.
+    pub offset_from: usize,
+    /// Offset (byte index) of the last character of the term in the value + 1.
+    pub offset_to: usize,
+}
+
+/// A term of a term vector, with its occurrences.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TermVectorEntry {
+    /// The text of the term.
+    pub term: String,
+    /// The occurrences of the term, sorted by value and position.
+    pub occurrences: Vec<TermOccurrence>,
+}
+
+impl TermVectorEntry {
+    /// Returns the number of occurrences of the term.
+    pub fn term_freq(&self) -> u32 {
+        self.occurrences.len() as u32
+    }
+}
+
+/// The terms of a field of a document, sorted in lexicographic order.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct TermVector {
+    entries: Vec<TermVectorEntry>,
+}
+
+impl TermVector {
+    /// Returns the terms with their occurrences.
+    pub fn entries(&self) -> &[TermVectorEntry] {
+        &self.entries
+    }
+
+    /// Returns the entry of `term`, if it occurs in the field.
+    pub fn get(&self, term: &str) -> Option<&TermVectorEntry> {
+        self.entries
+            .binary_search_by(|entry| entry.term.as_str().cmp(term))
+            .ok()
+            .map(|ord| &self.entries[ord])
+    }
+
+    /// Returns the number of distinct terms.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns true if the field has no terms.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    fn serialize<W: io::Write + ?Sized>(&self, writer: &mut W) -> io::Result<()> {
+        VInt(self.entries.len() as u64).serialize(writer)?;
+        for entry in &self.entries {
+            entry.term.serialize(writer)?;
+            VInt(entry.occurrences.len() as u64).serialize(writer)?;
+            for occurrence in &entry.occurrences {
+                VInt(occurrence.value_ord as u64).serialize(writer)?;
+                VInt(occurrence.position as u64).serialize(writer)?;
+                VInt(occurrence.offset_from as u64).serialize(writer)?;
+                VInt((occurrence.offset_to - occurrence.offset_from) as u64).serialize(writer)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn deserialize<R: io::Read>(reader: &mut R) -> io::Result<TermVector> {
+        let num_entries = VInt::deserialize_u64(reader)? as usize;
+        let mut entries = Vec::with_capacity(num_entries);
+        for _ in 0..num_entries {
+            let term = String::deserialize(reader)?;
+            let num_occurrences = VInt::deserialize_u64(reader)? as usize;
+            let mut occurrences = Vec::with_capacity(num_occurrences);
+            for _ in 0..num_occurrences {
+                let value_ord = VInt::deserialize_u64(reader)? as u32;
+                let position = VInt::deserialize_u64(reader)? as u32;
+                let offset_from = VInt::deserialize_u64(reader)? as usize;
+                let len = VInt::deserialize_u64(reader)? as usize;
+                occurrences.push(TermOccurrence {
+                    value_ord,
+                    position,
+                    offset_from,
+                    offset_to: offset_from + len,
+                });
+            }
+            entries.push(TermVectorEntry { term, occurrences });
+        }
+        Ok(TermVector { entries })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::TermOccurrence;
+    use crate::collector::TopDocs;
+    use crate::query::AllQuery;
+    use crate::schema::{Schema, TextFieldIndexing, TextOptions, TEXT};
+    use crate::{doc, Index, IndexWriter};
+
+    #[test]
+    fn test_term_vectors() -> crate::Result<()> {
+        let mut schema_builder = Schema::builder();
+        let text_options = TextOptions::default().set_indexing_options(
+            TextFieldIndexing::default()
+                .set_tokenizer("default")
+                .set_term_vectors(true),
+        );
+        let body = schema_builder.add_text_field("body", text_options);
+        let title = schema_builder.add_text_field("title", TEXT);
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer: IndexWriter = index.writer_for_tests()?;
+        index_writer.add_document(doc!(
+            body => "Hello happy world",
+            body => "hello again",
+            title => "not recorded",
+        ))?;
+        index_writer.commit()?;
+        index_writer.add_document(doc!(title => "no body"))?;
+        index_writer.commit()?;
+
+        let check = |index: &Index| -> crate::Result<()> {
+            let searcher = index.reader()?.searcher();
+            let mut num_term_vectors = 0;
+            for (_, doc_address) in searcher.search(&AllQuery, &TopDocs::with_limit(10))? {
+                let segment_reader = searcher.segment_reader(doc_address.segment_ord);
+                let term_vectors = segment_reader.term_vectors();
+                assert!(term_vectors.get(doc_address.doc_id, title)?.is_none());
+                let Some(term_vector) = term_vectors.get(doc_address.doc_id, body)? else {
+                    continue;
+                };
+                num_term_vectors += 1;
+                let terms: Vec<&str> = term_vector
+                    .entries()
+                    .iter()
+                    .map(|entry| entry.term.as_str())
+                    .collect();
+                assert_eq!(terms, vec!["again", "happy", "hello", "world"]);
+                let hello = term_vector.get("hello").unwrap();
+                assert_eq!(hello.term_freq(), 2);
+                assert_eq!(
+                    hello.occurrences[0],
+                    TermOccurrence {
+                        value_ord: 0,
+                        position: 0,
+                        offset_from: 0,
+                        offset_to: 5,
+                    }
+                );
+                let second_value_hello = hello.occurrences[1];
+                assert_eq!(second_value_hello.value_ord, 1);
+                assert!(second_value_hello.position > 2);
+                assert_eq!(
+                    (second_value_hello.offset_from, second_value_hello.offset_to),
+                    (0, 5)
+                );
+                assert!(term_vector.get("missing").is_none());
+            }
+            assert_eq!(num_term_vectors, 1);
+            Ok(())
+        };
+        check(&index)?;
+
+        let segment_ids = index.searchable_segment_ids()?;
+        index_writer.merge(&segment_ids).wait()?;
+        index_writer.wait_merging_threads()?;
+        assert_eq!(index.searchable_segment_ids()?.len(), 1);
+        check(&index)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,55 @@
+use common::ByteCount;
+
+use super::TermVector;
+use crate::directory::FileSlice;
+use crate::schema::Field;
+use crate::store::{Blob, BlobStoreReader};
+use crate::DocId;
+
+/// Reads the term vectors of a segment.
+///
+/// Only the index of the term vectors is loaded in memory: each call to
+/// [`TermVectorsReader::get`] reads and decodes one term vector.
+#[derive(Clone)]
+pub struct TermVectorsReader {
+    blob_store: BlobStoreReader,
+}
+
+impl TermVectorsReader {
+    /// Opens a term vectors file.
+    pub fn open(file: FileSlice) -> crate::Result<TermVectorsReader> {
+        Ok(TermVectorsReader {
+            blob_store: BlobStoreReader::open(file)?,
+        })
+    }
+
+    /// Returns a reader for a segment without any term vector.
+    pub fn empty() -> TermVectorsReader {
+        TermVectorsReader {
+            blob_store: BlobStoreReader::empty(),
+        }
+    }
+
+    /// Returns the term vector of the field `field` of the document `doc`.
+    ///
+    /// Returns `None` if the field does not have term vectors, or if the document does not
+    /// have any term for this field.
+    pub fn get(&self, doc: DocId, field: Field) -> crate::Result<Option<TermVector>> {
+        let Some(blob) = self.blob_store.get(doc, field)?.into_iter().next() else {
+            return Ok(None);
+        };
+        let bytes = blob.read_bytes()?;
+        let term_vector = TermVector::deserialize(&mut bytes.as_slice())?;
+        Ok(Some(term_vector))
+    }
+
+    /// Returns the serialized term vectors of the document `doc`, sorted by field.
+    pub(crate) fn get_all_serialized(&self, doc: DocId) -> crate::Result<Vec<(Field, Blob)>> {
+        Ok(self.blob_store.get_all(doc)?)
+    }
+
+    /// Returns the size of the term vectors file.
+    pub fn space_usage(&self) -> ByteCount {
+        self.blob_store.space_usage()
+    }
+}
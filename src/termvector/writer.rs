@@ -0,0 +1,150 @@
+use std::collections::BTreeMap;
+use std::io::{self, Read};
+
+use super::{TermOccurrence, TermVector, TermVectorEntry};
+use crate::directory::WritePtr;
+use crate::schema::Field;
+use crate::store::BlobStoreWriter;
+use crate::tokenizer::{Token, TokenStream, MAX_TOKEN_LEN};
+use crate::DocId;
+
+/// Writes the term vectors of a segment.
+///
+/// Each term vector is serialized as a blob of the underlying
+/// [`BlobStoreWriter`](crate::store::BlobStoreWriter), keyed by document and field.
+///
+/// Documents are expected to be added in increasing doc id order.
+pub struct TermVectorsWriter {
+    blob_store_writer: BlobStoreWriter,
+    buffer: Vec<u8>,
+}
+
+impl TermVectorsWriter {
+    /// Creates a term vectors writer.
+    pub fn new(write: WritePtr) -> TermVectorsWriter {
+        TermVectorsWriter {
+            blob_store_writer: BlobStoreWriter::new(write),
+            buffer: Vec::new(),
+        }
+    }
+
+    /// Writes the term vector of the field `field` of the document `doc`.
+    pub fn add_term_vector(
+        &mut self,
+        doc: DocId,
+        field: Field,
+        term_vector: &TermVector,
+    ) -> io::Result<()> {
+        self.buffer.clear();
+        term_vector.serialize(&mut self.buffer)?;
+        self.blob_store_writer
+            .add_blob(doc, field, self.buffer.as_slice())
+    }
+
+    /// Copies a serialized term vector, as read from another segment.
+    pub(crate) fn add_serialized_term_vector<R: Read>(
+        &mut self,
+        doc: DocId,
+        field: Field,
+        serialized: R,
+    ) -> io::Result<()> {
+        self.blob_store_writer.add_blob(doc, field, serialized)
+    }
+
+    /// Memory used by the index of the term vectors written so far.
+    pub fn mem_usage(&self) -> usize {
+        self.blob_store_writer.mem_usage() + self.buffer.capacity()
+    }
+
+    /// Finalizes the term vectors file.
+    pub fn close(self) -> io::Result<()> {
+        self.blob_store_writer.close()
+    }
+}
+
+/// Accumulates the occurrences of the terms of a field of a document.
+#[derive(Default)]
+pub(crate) struct TermVectorBuilder {
+    occurrences: BTreeMap<String, Vec<TermOccurrence>>,
+}
+
+impl TermVectorBuilder {
+    fn record(&mut self, token: &Token, value_ord: u32, start_position: u32) {
+        let occurrence = TermOccurrence {
+            value_ord,
+            position: start_position + token.position as u32,
+            offset_from: token.offset_from,
+            offset_to: token.offset_to,
+        };
+        if let Some(occurrences) = self.occurrences.get_mut(&token.text) {
+            occurrences.push(occurrence);
+        } else {
+            self.occurrences
+                .insert(token.text.clone(), vec![occurrence]);
+        }
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.occurrences.is_empty()
+    }
+
+    pub(crate) fn build(self) -> TermVector {
+        let entries = self
+            .occurrences
+            .into_iter()
+            .map(|(term, occurrences)| TermVectorEntry { term, occurrences })
+            .collect();
+        TermVector { entries }
+    }
+}
+
+/// Wraps the token stream of a value of a field, recording its tokens in a
+/// [`TermVectorBuilder`] as they are indexed.
+///
+/// Tokens exceeding [`MAX_TOKEN_LEN`] are skipped, as they are not indexed either.
+pub(crate) struct TermVectorRecorder<'a> {
+    token_stream: &'a mut dyn TokenStream,
+    builder: &'a mut TermVectorBuilder,
+    value_ord: u32,
+    start_position: u32,
+}
+
+impl<'a> TermVectorRecorder<'a> {
+    /// `start_position` is the position of the first token of the value in the field,
+    /// as computed by the postings writer.
+    pub(crate) fn new(
+        token_stream: &'a mut dyn TokenStream,
+        builder: &'a mut TermVectorBuilder,
+        value_ord: u32,
+        start_position: u32,
+    ) -> TermVectorRecorder<'a> {
+        TermVectorRecorder {
+            token_stream,
+            builder,
+            value_ord,
+            start_position,
+        }
+    }
+}
+
+impl<'a> TokenStream for TermVectorRecorder<'a> {
+    fn advance(&mut self) -> bool {
+        if !self.token_stream.advance() {
+            return false;
+        }
+        let token = self.token_stream.token();
+        if token.text.len() <= MAX_TOKEN_LEN {
+            self.builder
+                .record(token, self.value_ord, self.start_position);
+        }
+        true
+    }
+
+    fn token(&self) -> &Token {
+        self.token_stream.token()
+    }
+
+    fn token_mut(&mut self) -> &mut Token {
+        self.token_stream.token_mut()
+    }
+}
@@ -0,0 +1,534 @@
+use std::cmp::{Ordering, Reverse};
+use std::collections::{BinaryHeap, HashSet};
+use std::io;
+
+use common::{BinarySerializable, VInt};
+
+use super::parallel_map;
+use crate::schema::HnswOptions;
+
+/// Levels above this one are never assigned, whatever the random draw.
+const MAX_LEVEL: usize = 16;
+
+/// Number of nodes inserted per batch and per thread when the graph is built with several
+/// threads.
+const BATCH_NODES_PER_THREAD: usize = 16;
+
+#[derive(Clone, Copy, Debug)]
+struct Candidate {
+    distance: f32,
+    node: u32,
+}
+
+impl PartialEq for Candidate {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Candidate {}
+
+impl PartialOrd for Candidate {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Candidate {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.distance
+            .total_cmp(&other.distance)
+            .then(self.node.cmp(&other.node))
+    }
+}
+
+/// Draws the top level of `node`.
+///
+/// The draw is a hash of the node, so that building the graph of the same vectors
+/// always gives the same graph.
+fn random_level(node: u32, level_multiplier: f64) -> usize {
+    // splitmix64
+    let mut z = (node as u64).wrapping_add(0x9E37_79B9_7F4A_7C15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^= z >> 31;
+    // Uniform in (0, 1].
+    let uniform = ((z >> 11) as f64 + 1.0) / (1u64 << 53) as f64;
+    ((-uniform.ln() * level_multiplier) as usize).min(MAX_LEVEL)
+}
+
+fn accept_all(_node: u32) -> bool {
+    true
+}
+
+/// A hierarchical navigable small world graph.
+///
+/// The graph only knows about node ordinals: the distances between nodes are given by
+/// closures, so that the graph is independent of the way the vectors are stored.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct HnswGraph {
+    entry_point: Option<u32>,
+    /// `neighbors[node][level]` lists the neighbors of `node` on `level`.
+    neighbors: Vec<Vec<Vec<u32>>>,
+}
+
+impl HnswGraph {
+    /// Builds the graph of `num_nodes` nodes, given the distance between two nodes.
+    pub fn build(
+        num_nodes: u32,
+        options: &HnswOptions,
+        num_threads: usize,
+        distance: impl Fn(u32, u32) -> f32 + Sync,
+    ) -> HnswGraph {
+        HnswGraph::extend(
+            HnswGraph::default(),
+            &[],
+            num_nodes,
+            options,
+            num_threads,
+            distance,
+        )
+    }
+
+    /// Builds the graph of `num_nodes` nodes from `base`, the graph of some of them.
+    ///
+    /// The node `i` of `base` is the node `base_nodes[i]` of the new graph. The nodes of
+    /// `base` keep their neighbors, and the other nodes are inserted, which is much cheaper
+    /// than building the graph from scratch when `base` holds most of the nodes.
+    ///
+    /// With several threads, the nodes are inserted by batches: the neighbors of the nodes
+    /// of a batch are searched concurrently, then the nodes are linked one after the other,
+    /// along with the nodes of the batch linked before them.
+    pub fn extend(
+        base: HnswGraph,
+        base_nodes: &[u32],
+        num_nodes: u32,
+        options: &HnswOptions,
+        num_threads: usize,
+        distance: impl Fn(u32, u32) -> f32 + Sync,
+    ) -> HnswGraph {
+        let m = options.m().max(2) as usize;
+        let ef_construction = (options.ef_construction() as usize).max(m);
+        let level_multiplier = 1.0 / (m as f64).ln();
+        let mut graph = HnswGraph {
+            entry_point: base.entry_point.map(|node| base_nodes[node as usize]),
+            neighbors: vec![Vec::new(); num_nodes as usize],
+        };
+        for (node_neighbors, &node) in base.neighbors.into_iter().zip(base_nodes) {
+            graph.neighbors[node as usize] = node_neighbors
+                .into_iter()
+                .map(|level_neighbors| {
+                    level_neighbors
+                        .into_iter()
+                        .map(|neighbor| base_nodes[neighbor as usize])
+                        .collect()
+                })
+                .collect();
+        }
+        let new_nodes: Vec<u32> = (0..num_nodes)
+            .filter(|&node| graph.neighbors[node as usize].is_empty())
+            .collect();
+        let batch_size = if num_threads > 1 {
+            num_threads * BATCH_NODES_PER_THREAD
+        } else {
+            1
+        };
+        let mut remaining_nodes = &new_nodes[..];
+        while let Some(&first_node) = remaining_nodes.first() {
+            if graph.entry_point.is_none() {
+                let level = random_level(first_node, level_multiplier);
+                graph.neighbors[first_node as usize] = vec![Vec::new(); level + 1];
+                graph.entry_point = Some(first_node);
+                remaining_nodes = &remaining_nodes[1..];
+                continue;
+            }
+            let (batch, rest) = remaining_nodes.split_at(batch_size.min(remaining_nodes.len()));
+            let batch_candidates = parallel_map(batch, num_threads, |&node| {
+                let level = random_level(node, level_multiplier);
+                graph.layer_candidates(level, ef_construction, |other| distance(node, other))
+            });
+            for (batch_ord, (&node, layer_candidates)) in
+                batch.iter().zip(batch_candidates).enumerate()
+            {
+                graph.link(node, layer_candidates, &batch[..batch_ord], m, &distance);
+            }
+            remaining_nodes = rest;
+        }
+        graph
+    }
+
+    /// Returns the level of the entry point, which is the top level of the graph.
+    fn max_level(&self) -> Option<usize> {
+        let entry_point = self.entry_point?;
+        Some(self.neighbors[entry_point as usize].len() - 1)
+    }
+
+    /// Returns, for each layer of a new node of top level `level`, the candidate neighbors
+    /// of the node on the layer, sorted by increasing distance.
+    ///
+    /// The graph must not be empty.
+    fn layer_candidates(
+        &self,
+        level: usize,
+        ef_construction: usize,
+        distance_to_node: impl Fn(u32) -> f32,
+    ) -> Vec<Vec<Candidate>> {
+        let entry_point = self.entry_point.expect("the graph is not empty");
+        let max_level = self.neighbors[entry_point as usize].len() - 1;
+        let mut entry_points = vec![Candidate {
+            distance: distance_to_node(entry_point),
+            node: entry_point,
+        }];
+        for layer in (level + 1..=max_level).rev() {
+            entry_points =
+                self.search_layer(&entry_points, 1, layer, &distance_to_node, &accept_all);
+        }
+        let mut layer_candidates = vec![Vec::new(); level + 1];
+        for layer in (0..=level.min(max_level)).rev() {
+            let candidates = self.search_layer(
+                &entry_points,
+                ef_construction,
+                layer,
+                &distance_to_node,
+                &accept_all,
+            );
+            entry_points = candidates.clone();
+            layer_candidates[layer] = candidates;
+        }
+        layer_candidates
+    }
+
+    /// Links `node` to its closest candidates on each of its layers, and to the closest of
+    /// `peers`, nodes linked after its candidates were searched.
+    fn link(
+        &mut self,
+        node: u32,
+        mut layer_candidates: Vec<Vec<Candidate>>,
+        peers: &[u32],
+        m: usize,
+        distance: &impl Fn(u32, u32) -> f32,
+    ) {
+        let level = layer_candidates.len() - 1;
+        let max_level = self.max_level().unwrap_or(0);
+        self.neighbors[node as usize] = vec![Vec::new(); level + 1];
+        for (layer, candidates) in layer_candidates.iter_mut().enumerate() {
+            let num_candidates = candidates.len();
+            for &peer in peers {
+                if self.neighbors[peer as usize].len() > layer {
+                    candidates.push(Candidate {
+                        distance: distance(node, peer),
+                        node: peer,
+                    });
+                }
+            }
+            if candidates.len() > num_candidates {
+                candidates.sort_unstable();
+            }
+            let layer_max_neighbors = if layer == 0 { 2 * m } else { m };
+            let neighbors: Vec<u32> = candidates
+                .iter()
+                .take(layer_max_neighbors)
+                .map(|candidate| candidate.node)
+                .collect();
+            for &neighbor in &neighbors {
+                let neighbor_neighbors = &mut self.neighbors[neighbor as usize][layer];
+                neighbor_neighbors.push(node);
+                if neighbor_neighbors.len() > layer_max_neighbors {
+                    // Only keep the closest neighbors.
+                    let mut closest: Vec<Candidate> = neighbor_neighbors
+                        .iter()
+                        .map(|&other| Candidate {
+                            distance: distance(neighbor, other),
+                            node: other,
+                        })
+                        .collect();
+                    closest.sort_unstable();
+                    closest.truncate(layer_max_neighbors);
+                    *neighbor_neighbors = closest
+                        .into_iter()
+                        .map(|candidate| candidate.node)
+                        .collect();
+                }
+            }
+            self.neighbors[node as usize][layer] = neighbors;
+        }
+        if level > max_level {
+            self.entry_point = Some(node);
+        }
+    }
+
+    /// Returns the number of nodes of the graph.
+    pub fn num_nodes(&self) -> usize {
+        self.neighbors.len()
+    }
+
+    fn neighbors(&self, node: u32, level: usize) -> &[u32] {
+        self.neighbors[node as usize]
+            .get(level)
+            .map_or(&[], Vec::as_slice)
+    }
+
+    /// Returns the `ef` accepted nodes closest to the query on the given level, sorted by
+    /// increasing distance, starting from `entry_points`.
+    ///
+    /// The nodes that are not accepted are still traversed, so that the accepted nodes
+    /// they lead to are found.
+    fn search_layer(
+        &self,
+        entry_points: &[Candidate],
+        ef: usize,
+        level: usize,
+        distance: &impl Fn(u32) -> f32,
+        accept: &impl Fn(u32) -> bool,
+    ) -> Vec<Candidate> {
+        let mut visited: HashSet<u32> = entry_points.iter().map(|entry| entry.node).collect();
+        let mut candidates: BinaryHeap<Reverse<Candidate>> =
+            entry_points.iter().copied().map(Reverse).collect();
+        let mut results: BinaryHeap<Candidate> = entry_points
+            .iter()
+            .copied()
+            .filter(|entry| accept(entry.node))
+            .collect();
+        while results.len() > ef {
+            results.pop();
+        }
+        while let Some(Reverse(candidate)) = candidates.pop() {
+            let furthest_distance = results
+                .peek()
+                .map_or(f32::INFINITY, |furthest| furthest.distance);
+            if results.len() >= ef && candidate.distance > furthest_distance {
+                break;
+            }
+            for &neighbor in self.neighbors(candidate.node, level) {
+                if !visited.insert(neighbor) {
+                    continue;
+                }
+                let neighbor = Candidate {
+                    distance: distance(neighbor),
+                    node: neighbor,
+                };
+                let furthest_distance = results
+                    .peek()
+                    .map_or(f32::INFINITY, |furthest| furthest.distance);
+                if results.len() < ef || neighbor.distance < furthest_distance {
+                    candidates.push(Reverse(neighbor));
+                    if accept(neighbor.node) {
+                        results.push(neighbor);
+                        if results.len() > ef {
+                            results.pop();
+                        }
+                    }
+                }
+            }
+        }
+        results.into_sorted_vec()
+    }
+
+    /// Returns the (at most) `ef` nodes closest to the query, with their distance, sorted by
+    /// increasing distance.
+    ///
+    /// `distance` returns the distance between the query and a node.
+    pub fn search(&self, ef: usize, distance: impl Fn(u32) -> f32) -> Vec<(u32, f32)> {
+        self.search_filtered(ef, distance, accept_all)
+    }
+
+    /// Returns the (at most) `ef` accepted nodes closest to the query, with their distance,
+    /// sorted by increasing distance.
+    ///
+    /// The filter is applied while traversing the graph rather than on the `ef` closest
+    /// nodes, so that `ef` nodes are returned as long as enough nodes reachable from the
+    /// entry point are accepted.
+    pub fn search_filtered(
+        &self,
+        ef: usize,
+        distance: impl Fn(u32) -> f32,
+        accept: impl Fn(u32) -> bool,
+    ) -> Vec<(u32, f32)> {
+        let (Some(entry_point), Some(max_level)) = (self.entry_point, self.max_level()) else {
+            return Vec::new();
+        };
+        let mut entry_points = vec![Candidate {
+            distance: distance(entry_point),
+            node: entry_point,
+        }];
+        for level in (1..=max_level).rev() {
+            entry_points = self.search_layer(&entry_points, 1, level, &distance, &accept_all);
+        }
+        self.search_layer(&entry_points, ef.max(1), 0, &distance, &accept)
+            .into_iter()
+            .map(|candidate| (candidate.node, candidate.distance))
+            .collect()
+    }
+
+    pub fn serialize<W: io::Write + ?Sized>(&self, writer: &mut W) -> io::Result<()> {
+        VInt(self.neighbors.len() as u64).serialize(writer)?;
+        VInt(self.entry_point.unwrap_or(0) as u64).serialize(writer)?;
+        for node_neighbors in &self.neighbors {
+            VInt(node_neighbors.len() as u64).serialize(writer)?;
+            for level_neighbors in node_neighbors {
+                VInt(level_neighbors.len() as u64).serialize(writer)?;
+                for &neighbor in level_neighbors {
+                    VInt(neighbor as u64).serialize(writer)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Deserializes a graph, checking that the entry point and the neighbors are nodes of the
+    /// graph, so that searching it cannot panic.
+    pub fn deserialize<R: io::Read>(reader: &mut R) -> io::Result<HnswGraph> {
+        let invalid_data = |msg: &str| io::Error::new(io::ErrorKind::InvalidData, msg.to_string());
+        let num_nodes = VInt::deserialize_u64(reader)?;
+        let entry_point = VInt::deserialize_u64(reader)?;
+        // The counts are only trusted once they are read: each of them takes at least one
+        // byte, so that a corrupted count fails on the end of the data rather than on a huge
+        // allocation.
+        let mut neighbors = Vec::new();
+        for _ in 0..num_nodes {
+            let num_levels = VInt::deserialize_u64(reader)?;
+            if num_levels > MAX_LEVEL as u64 + 1 {
+                return Err(invalid_data("HNSW node has too many levels"));
+            }
+            let mut node_neighbors = Vec::with_capacity(num_levels as usize);
+            for _ in 0..num_levels {
+                let num_neighbors = VInt::deserialize_u64(reader)?;
+                let mut level_neighbors = Vec::new();
+                for _ in 0..num_neighbors {
+                    let neighbor = VInt::deserialize_u64(reader)?;
+                    if neighbor >= num_nodes {
+                        return Err(invalid_data("HNSW neighbor is out of the graph"));
+                    }
+                    level_neighbors.push(neighbor as u32);
+                }
+                node_neighbors.push(level_neighbors);
+            }
+            neighbors.push(node_neighbors);
+        }
+        if num_nodes == 0 {
+            return Ok(HnswGraph::default());
+        }
+        if entry_point >= num_nodes || neighbors[entry_point as usize].is_empty() {
+            return Err(invalid_data("HNSW entry point is not a node of the graph"));
+        }
+        Ok(HnswGraph {
+            entry_point: Some(entry_point as u32),
+            neighbors,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use common::{BinarySerializable, VInt};
+
+    use super::HnswGraph;
+    use crate::schema::HnswOptions;
+
+    #[test]
+    fn test_hnsw_graph() {
+        let points: Vec<f32> = (0..500).map(|i| ((i * 7919) % 1000) as f32).collect();
+        let distance = |left: f32, right: f32| (left - right).abs();
+        let graph = HnswGraph::build(points.len() as u32, &HnswOptions::default(), 1, |a, b| {
+            distance(points[a as usize], points[b as usize])
+        });
+        assert_eq!(graph.num_nodes(), 500);
+        let mut serialized = Vec::new();
+        graph.serialize(&mut serialized).unwrap();
+        let graph = HnswGraph::deserialize(&mut &serialized[..]).unwrap();
+        for query in [0.0f32, 333.3, 999.0, 2000.0] {
+            let results = graph.search(20, |node| distance(query, points[node as usize]));
+            assert_eq!(results.len(), 20);
+            let mut expected: Vec<f32> =
+                points.iter().map(|&point| distance(query, point)).collect();
+            expected.sort_by(f32::total_cmp);
+            let distances: Vec<f32> = results.iter().map(|(_, distance)| *distance).collect();
+            assert_eq!(distances[..5], expected[..5]);
+        }
+        assert!(HnswGraph::default().search(10, |_| 0.0).is_empty());
+
+        // A graph whose entry point or neighbors are not nodes of the graph is rejected.
+        let deserialize_vints = |vints: &[u64]| {
+            let mut bytes = Vec::new();
+            for &vint in vints {
+                VInt(vint).serialize(&mut bytes).unwrap();
+            }
+            HnswGraph::deserialize(&mut &bytes[..])
+        };
+        // Two nodes of a single level, the entry point and the neighbor of each node coming
+        // after the number of nodes.
+        assert_eq!(
+            deserialize_vints(&[2, 1, 1, 1, 1, 1, 1, 0])
+                .unwrap()
+                .num_nodes(),
+            2
+        );
+        assert!(deserialize_vints(&[2, 2, 1, 1, 1, 1, 1, 0]).is_err());
+        assert!(deserialize_vints(&[2, 1, 1, 1, 1, 1, 1, 2]).is_err());
+        // The entry point has no level.
+        assert!(deserialize_vints(&[2, 1, 1, 1, 1, 0]).is_err());
+
+        // Only the even nodes are accepted.
+        let results = graph.search_filtered(
+            20,
+            |node| distance(333.3, points[node as usize]),
+            |node| node % 2 == 0,
+        );
+        assert_eq!(results.len(), 20);
+        assert!(results.iter().all(|(node, _)| node % 2 == 0));
+        let mut expected: Vec<f32> = points
+            .iter()
+            .step_by(2)
+            .map(|&point| distance(333.3, point))
+            .collect();
+        expected.sort_by(f32::total_cmp);
+        assert_eq!(results[0].1, expected[0]);
+    }
+
+    #[test]
+    fn test_hnsw_graph_extend() {
+        let points: Vec<f32> = (0..500).map(|i| ((i * 7919) % 1000) as f32).collect();
+        let distance = |left: f32, right: f32| (left - right).abs();
+        let check_graph = |graph: &HnswGraph| {
+            assert_eq!(graph.num_nodes(), 500);
+            for query in [0.0f32, 333.3, 999.0] {
+                let results = graph.search(20, |node| distance(query, points[node as usize]));
+                let mut expected: Vec<f32> =
+                    points.iter().map(|&point| distance(query, point)).collect();
+                expected.sort_by(f32::total_cmp);
+                let distances: Vec<f32> = results.iter().map(|(_, distance)| *distance).collect();
+                assert_eq!(distances[..5], expected[..5]);
+            }
+        };
+
+        // Built with several threads.
+        let graph = HnswGraph::build(500, &HnswOptions::default(), 4, |a, b| {
+            distance(points[a as usize], points[b as usize])
+        });
+        check_graph(&graph);
+
+        // The odd nodes are inserted in the graph of the even nodes.
+        let even_nodes: Vec<u32> = (0..500).step_by(2).collect();
+        let base = HnswGraph::build(250, &HnswOptions::default(), 1, |a, b| {
+            distance(
+                points[even_nodes[a as usize] as usize],
+                points[even_nodes[b as usize] as usize],
+            )
+        });
+        let base_neighbors = base.neighbors[0][0].clone();
+        let graph = HnswGraph::extend(
+            base,
+            &even_nodes,
+            500,
+            &HnswOptions::default(),
+            2,
+            |a, b| distance(points[a as usize], points[b as usize]),
+        );
+        check_graph(&graph);
+        // The links of the base graph are kept, unless replaced by closer ones.
+        assert!(graph.neighbors[0][0]
+            .iter()
+            .filter(|&&neighbor| neighbor % 2 == 0)
+            .all(|&neighbor| base_neighbors.contains(&(neighbor / 2))));
+    }
+}
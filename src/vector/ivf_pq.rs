@@ -0,0 +1,412 @@
+use std::borrow::Cow;
+use std::io;
+
+use common::{BinarySerializable, VInt};
+
+use super::{distance, dot_product, parallel_map, squared_l2};
+use crate::schema::{IvfPqOptions, VectorMetric};
+
+const KMEANS_NUM_ITERATIONS: usize = 10;
+/// The centroids are trained on a sample of at most this many points per centroid.
+const MAX_TRAINING_POINTS_PER_CENTROID: usize = 64;
+/// Codes are single bytes.
+const MAX_CODEBOOK_SIZE: usize = 256;
+
+fn nearest_centroid(centroids: &[f32], dimensions: usize, point: &[f32]) -> usize {
+    centroids
+        .chunks_exact(dimensions)
+        .map(|centroid| squared_l2(centroid, point))
+        .enumerate()
+        .min_by(|(_, left), (_, right)| left.total_cmp(right))
+        .map_or(0, |(ord, _)| ord)
+}
+
+/// Runs Lloyd's algorithm over `points`, a sequence of points of `dimensions` dimensions,
+/// and returns `k` centroids.
+///
+/// `k` has to be lower than or equal to the number of points. The centroids are
+/// initialized with evenly spaced points, so that the result is deterministic.
+pub(super) fn kmeans(points: &[f32], dimensions: usize, k: usize) -> Vec<f32> {
+    let num_points = points.len() / dimensions;
+    let point = |ord: usize| &points[ord * dimensions..(ord + 1) * dimensions];
+    let stride = (num_points / (k * MAX_TRAINING_POINTS_PER_CENTROID)).max(1);
+    let sample: Vec<usize> = (0..num_points).step_by(stride).collect();
+    let mut centroids: Vec<f32> = (0..k)
+        .flat_map(|centroid_ord| {
+            point(sample[centroid_ord * sample.len() / k])
+                .iter()
+                .copied()
+        })
+        .collect();
+    let mut sums = vec![0.0f32; k * dimensions];
+    let mut counts = vec![0usize; k];
+    for _ in 0..KMEANS_NUM_ITERATIONS {
+        sums.fill(0.0);
+        counts.fill(0);
+        for &ord in &sample {
+            let centroid_ord = nearest_centroid(&centroids, dimensions, point(ord));
+            counts[centroid_ord] += 1;
+            let sum = &mut sums[centroid_ord * dimensions..(centroid_ord + 1) * dimensions];
+            for (sum_val, val) in sum.iter_mut().zip(point(ord)) {
+                *sum_val += val;
+            }
+        }
+        // Empty clusters keep their centroid.
+        for (centroid_ord, &count) in counts.iter().enumerate() {
+            if count == 0 {
+                continue;
+            }
+            let range = centroid_ord * dimensions..(centroid_ord + 1) * dimensions;
+            for (val, sum_val) in centroids[range.clone()].iter_mut().zip(&sums[range]) {
+                *val = sum_val / count as f32;
+            }
+        }
+    }
+    centroids
+}
+
+/// Returns the largest divisor of `dimensions` lower than or equal to `max`.
+fn num_subquantizers(dimensions: usize, max: usize) -> usize {
+    (1..=max.clamp(1, dimensions.max(1)))
+        .rev()
+        .find(|divisor| dimensions % divisor == 0)
+        .unwrap_or(1)
+}
+
+pub(super) fn serialize_f32s<W: io::Write + ?Sized>(
+    vals: &[f32],
+    writer: &mut W,
+) -> io::Result<()> {
+    VInt(vals.len() as u64).serialize(writer)?;
+    for val in vals {
+        writer.write_all(&val.to_le_bytes())?;
+    }
+    Ok(())
+}
+
+pub(super) fn deserialize_f32s<R: io::Read>(reader: &mut R) -> io::Result<Vec<f32>> {
+    let num_vals = VInt::deserialize_u64(reader)? as usize;
+    let mut bytes = vec![0u8; num_vals * 4];
+    reader.read_exact(&mut bytes)?;
+    super::decode_vector(&bytes)
+}
+
+/// An inverted file over product quantized vectors.
+///
+/// Each vector is assigned to the list of its closest centroid. The residual, that is the
+/// difference between the vector and the centroid, is split into `num_subquantizers`
+/// subvectors, and each subvector is replaced by the ordinal of its closest entry in the
+/// codebook of its subquantizer.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct IvfPqIndex {
+    dimensions: usize,
+    /// `num_lists * dimensions` values.
+    centroids: Vec<f32>,
+    num_subquantizers: usize,
+    codebook_size: usize,
+    /// The codebooks of the subquantizers one after the other, each made of
+    /// `codebook_size` subvectors of `dimensions / num_subquantizers` values.
+    codebooks: Vec<f32>,
+    /// Ordinals of the vectors of each list.
+    lists: Vec<Vec<u32>>,
+    /// `num_subquantizers` codes per vector, in the order of the ordinals.
+    codes: Vec<u8>,
+}
+
+impl IvfPqIndex {
+    /// Trains the centroids and the codebooks on `vectors`, a sequence of vectors of
+    /// `dimensions` dimensions, and encodes them.
+    ///
+    /// The codebooks of the subquantizers are trained concurrently on up to `num_threads`
+    /// threads.
+    pub fn build(
+        vectors: &[f32],
+        dimensions: usize,
+        options: &IvfPqOptions,
+        num_threads: usize,
+    ) -> IvfPqIndex {
+        let num_vectors = vectors.len() / dimensions;
+        if num_vectors == 0 {
+            return IvfPqIndex {
+                dimensions,
+                ..Default::default()
+            };
+        }
+        let num_lists =
+            (options.num_lists() as usize).min(((num_vectors as f64).sqrt() as usize).max(1));
+        let centroids = kmeans(vectors, dimensions, num_lists);
+        let mut residuals = Vec::with_capacity(vectors.len());
+        for vector in vectors.chunks_exact(dimensions) {
+            let list_ord = nearest_centroid(&centroids, dimensions, vector);
+            let centroid = &centroids[list_ord * dimensions..(list_ord + 1) * dimensions];
+            residuals.extend(vector.iter().zip(centroid).map(|(val, c)| val - c));
+        }
+
+        let num_subquantizers = num_subquantizers(dimensions, options.num_subquantizers() as usize);
+        let sub_dimensions = dimensions / num_subquantizers;
+        let codebook_size = MAX_CODEBOOK_SIZE.min(num_vectors);
+        let subquantizers: Vec<usize> = (0..num_subquantizers).collect();
+        let codebooks: Vec<f32> = parallel_map(&subquantizers, num_threads, |&subquantizer| {
+            let subvectors: Vec<f32> = residuals
+                .chunks_exact(dimensions)
+                .flat_map(|residual| {
+                    residual[subquantizer * sub_dimensions..(subquantizer + 1) * sub_dimensions]
+                        .iter()
+                        .copied()
+                })
+                .collect();
+            kmeans(&subvectors, sub_dimensions, codebook_size)
+        })
+        .concat();
+
+        let quantizer = IvfPqIndex {
+            dimensions,
+            centroids,
+            num_subquantizers,
+            codebook_size,
+            codebooks,
+            lists: vec![Vec::new(); num_lists],
+            codes: Vec::new(),
+        };
+        IvfPqIndex::build_with_quantizer(&quantizer, vectors)
+    }
+
+    /// Encodes `vectors` with the centroids and the codebooks of `quantizer`, without
+    /// training new ones.
+    ///
+    /// The centroids and the codebooks trained on a large enough sample of the vectors of a
+    /// field remain relevant for the other vectors, so that they can be reused when segments
+    /// are merged.
+    pub fn build_with_quantizer(quantizer: &IvfPqIndex, vectors: &[f32]) -> IvfPqIndex {
+        let dimensions = quantizer.dimensions;
+        let sub_dimensions = quantizer.sub_dimensions();
+        let num_vectors = vectors.len() / dimensions;
+        let mut index = IvfPqIndex {
+            dimensions,
+            centroids: quantizer.centroids.clone(),
+            num_subquantizers: quantizer.num_subquantizers,
+            codebook_size: quantizer.codebook_size,
+            codebooks: quantizer.codebooks.clone(),
+            lists: vec![Vec::new(); quantizer.lists.len()],
+            codes: Vec::with_capacity(num_vectors * quantizer.num_subquantizers),
+        };
+        let mut residual = vec![0.0f32; dimensions];
+        for (ord, vector) in vectors.chunks_exact(dimensions).enumerate() {
+            let list_ord = nearest_centroid(&index.centroids, dimensions, vector);
+            index.lists[list_ord].push(ord as u32);
+            for ((residual_val, val), c) in residual
+                .iter_mut()
+                .zip(vector)
+                .zip(index.centroid(list_ord))
+            {
+                *residual_val = val - c;
+            }
+            for (subquantizer, subvector) in residual.chunks_exact(sub_dimensions).enumerate() {
+                let code =
+                    nearest_centroid(index.codebook(subquantizer), sub_dimensions, subvector);
+                index.codes.push(code as u8);
+            }
+        }
+        index
+    }
+
+    /// Returns the number of vectors of the index.
+    pub fn num_vectors(&self) -> usize {
+        self.lists.iter().map(Vec::len).sum()
+    }
+
+    /// Returns true if the index has centroids and codebooks, which is the case unless
+    /// it was built over no vector.
+    pub fn is_trained(&self) -> bool {
+        !self.lists.is_empty()
+    }
+
+    fn sub_dimensions(&self) -> usize {
+        self.dimensions / self.num_subquantizers
+    }
+
+    fn codebook(&self, subquantizer: usize) -> &[f32] {
+        let codebook_len = self.codebook_size * self.sub_dimensions();
+        &self.codebooks[subquantizer * codebook_len..(subquantizer + 1) * codebook_len]
+    }
+
+    fn centroid(&self, list_ord: usize) -> &[f32] {
+        &self.centroids[list_ord * self.dimensions..(list_ord + 1) * self.dimensions]
+    }
+
+    /// Computes, for each subquantizer and each entry of its codebook, the distance
+    /// between the subvector of `query` and the entry.
+    fn distance_table(
+        &self,
+        query: &[f32],
+        sub_distance: impl Fn(&[f32], &[f32]) -> f32,
+    ) -> Vec<f32> {
+        let sub_dimensions = self.sub_dimensions();
+        query
+            .chunks_exact(sub_dimensions)
+            .enumerate()
+            .flat_map(|(subquantizer, subquery)| {
+                self.codebook(subquantizer)
+                    .chunks_exact(sub_dimensions)
+                    .map(|entry| sub_distance(subquery, entry))
+                    .collect::<Vec<f32>>()
+            })
+            .collect()
+    }
+
+    /// Returns the ordinals of the vectors of the `num_probes` lists closest to `query`,
+    /// with their approximate distance to `query`, sorted by increasing distance.
+    ///
+    /// `query` has to be prepared for `metric`.
+    pub fn search(
+        &self,
+        metric: VectorMetric,
+        query: &[f32],
+        num_probes: usize,
+    ) -> Vec<(u32, f32)> {
+        if self.lists.is_empty() {
+            return Vec::new();
+        }
+        let mut list_distances: Vec<(usize, f32)> = (0..self.lists.len())
+            .map(|list_ord| (list_ord, distance(metric, query, self.centroid(list_ord))))
+            .collect();
+        list_distances.sort_by(|left, right| left.1.total_cmp(&right.1));
+        // With a dot product based metric, the distance to a vector is the distance to its
+        // centroid minus the dot product of the query and the residual, so that the table
+        // does not depend on the list.
+        let dot_table = (metric != VectorMetric::L2)
+            .then(|| self.distance_table(query, |subquery, entry| -dot_product(subquery, entry)));
+        let mut candidates = Vec::new();
+        for &(list_ord, centroid_distance) in list_distances.iter().take(num_probes) {
+            let (table, offset) = if let Some(dot_table) = &dot_table {
+                (Cow::Borrowed(dot_table), centroid_distance)
+            } else {
+                let residual_query: Vec<f32> = query
+                    .iter()
+                    .zip(self.centroid(list_ord))
+                    .map(|(val, c)| val - c)
+                    .collect();
+                (
+                    Cow::Owned(self.distance_table(&residual_query, squared_l2)),
+                    0.0,
+                )
+            };
+            for &ord in &self.lists[list_ord] {
+                let codes = &self.codes[ord as usize * self.num_subquantizers
+                    ..(ord as usize + 1) * self.num_subquantizers];
+                let distance: f32 = codes
+                    .iter()
+                    .enumerate()
+                    .map(|(subquantizer, &code)| {
+                        table[subquantizer * self.codebook_size + code as usize]
+                    })
+                    .sum();
+                candidates.push((ord, offset + distance));
+            }
+        }
+        candidates.sort_by(|left, right| left.1.total_cmp(&right.1));
+        candidates
+    }
+
+    pub fn serialize<W: io::Write + ?Sized>(&self, writer: &mut W) -> io::Result<()> {
+        serialize_f32s(&self.centroids, writer)?;
+        VInt(self.num_subquantizers as u64).serialize(writer)?;
+        VInt(self.codebook_size as u64).serialize(writer)?;
+        serialize_f32s(&self.codebooks, writer)?;
+        VInt(self.lists.len() as u64).serialize(writer)?;
+        for list in &self.lists {
+            VInt(list.len() as u64).serialize(writer)?;
+            for ord in list {
+                ord.serialize(writer)?;
+            }
+        }
+        writer.write_all(&self.codes)
+    }
+
+    pub fn deserialize<R: io::Read>(reader: &mut R, dimensions: usize) -> io::Result<IvfPqIndex> {
+        let centroids = deserialize_f32s(reader)?;
+        let num_subquantizers = VInt::deserialize_u64(reader)? as usize;
+        let codebook_size = VInt::deserialize_u64(reader)? as usize;
+        let codebooks = deserialize_f32s(reader)?;
+        let num_lists = VInt::deserialize_u64(reader)? as usize;
+        let mut lists = Vec::with_capacity(num_lists);
+        let mut num_vectors = 0;
+        for _ in 0..num_lists {
+            let list_len = VInt::deserialize_u64(reader)? as usize;
+            let mut list = Vec::with_capacity(list_len);
+            for _ in 0..list_len {
+                list.push(u32::deserialize(reader)?);
+            }
+            num_vectors += list_len;
+            lists.push(list);
+        }
+        let mut codes = vec![0u8; num_vectors * num_subquantizers];
+        reader.read_exact(&mut codes)?;
+        Ok(IvfPqIndex {
+            dimensions,
+            centroids,
+            num_subquantizers,
+            codebook_size,
+            codebooks,
+            lists,
+            codes,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    use super::{num_subquantizers, IvfPqIndex};
+    use crate::schema::{IvfPqOptions, VectorMetric};
+
+    #[test]
+    fn test_num_subquantizers() {
+        assert_eq!(num_subquantizers(16, 8), 8);
+        assert_eq!(num_subquantizers(12, 8), 6);
+        assert_eq!(num_subquantizers(7, 16), 7);
+        assert_eq!(num_subquantizers(7, 3), 1);
+    }
+
+    #[test]
+    fn test_ivf_pq_index() {
+        let mut rng = StdRng::seed_from_u64(7);
+        let dimensions = 8;
+        let vectors: Vec<f32> = (0..2_000 * dimensions)
+            .map(|_| rng.gen_range(-1.0..1.0))
+            .collect();
+        let options = IvfPqOptions::default().set_num_subquantizers(4);
+        let index = IvfPqIndex::build(&vectors, dimensions, &options, 1);
+        // At most the square root of the number of vectors.
+        assert_eq!(index.lists.len(), 44);
+        assert_eq!(index.codes.len(), 2_000 * 4);
+        assert_eq!(index.num_vectors(), 2_000);
+
+        // Training the codebooks concurrently gives the same index.
+        let index_parallel = IvfPqIndex::build(&vectors, dimensions, &options, 3);
+        assert_eq!(index_parallel.codebooks, index.codebooks);
+        assert_eq!(index_parallel.codes, index.codes);
+
+        // Reusing the quantizer on a subset of the vectors gives the same codes.
+        let subset = IvfPqIndex::build_with_quantizer(&index, &vectors[dimensions..]);
+        assert_eq!(subset.num_vectors(), 1_999);
+        assert_eq!(subset.codes[..], index.codes[4..]);
+
+        let mut serialized = Vec::new();
+        index.serialize(&mut serialized).unwrap();
+        let index = IvfPqIndex::deserialize(&mut &serialized[..], dimensions).unwrap();
+        assert_eq!(index.codes.len(), 2_000 * 4);
+
+        // Probing all of the lists returns all of the vectors.
+        let query = &vectors[..dimensions];
+        let candidates = index.search(VectorMetric::L2, query, usize::MAX);
+        assert_eq!(candidates.len(), 2_000);
+        // The vector itself is among the closest approximate candidates.
+        assert!(candidates.iter().take(20).any(|&(ord, _)| ord == 0));
+        let candidates = index.search(VectorMetric::DotProduct, query, 4);
+        assert!(candidates.len() < 2_000);
+        assert!(candidates.windows(2).all(|pair| pair[0].1 <= pair[1].1));
+    }
+}
@@ -0,0 +1,57 @@
+use std::arch::x86_64::{
+    __m256, _mm256_fmadd_ps, _mm256_loadu_ps, _mm256_setzero_ps, _mm256_storeu_ps, _mm256_sub_ps,
+};
+
+const NUM_LANES: usize = 8;
+
+#[target_feature(enable = "avx2,fma")]
+unsafe fn horizontal_sum(vals: __m256) -> f32 {
+    let mut lanes = [0.0f32; NUM_LANES];
+    _mm256_storeu_ps(lanes.as_mut_ptr(), vals);
+    lanes.iter().sum()
+}
+
+/// # Safety
+///
+/// The CPU has to support AVX2 and FMA.
+#[target_feature(enable = "avx2,fma")]
+pub unsafe fn dot_product(left: &[f32], right: &[f32]) -> f32 {
+    let len = left.len().min(right.len());
+    let num_words = len / NUM_LANES;
+    let mut sums = _mm256_setzero_ps();
+    for word in 0..num_words {
+        let left_vals = _mm256_loadu_ps(left.as_ptr().add(word * NUM_LANES));
+        let right_vals = _mm256_loadu_ps(right.as_ptr().add(word * NUM_LANES));
+        sums = _mm256_fmadd_ps(left_vals, right_vals, sums);
+    }
+    let remainder = num_words * NUM_LANES..len;
+    let remainder_sum: f32 = left[remainder.clone()]
+        .iter()
+        .zip(&right[remainder])
+        .map(|(l, r)| l * r)
+        .sum();
+    horizontal_sum(sums) + remainder_sum
+}
+
+/// # Safety
+///
+/// The CPU has to support AVX2 and FMA.
+#[target_feature(enable = "avx2,fma")]
+pub unsafe fn squared_l2(left: &[f32], right: &[f32]) -> f32 {
+    let len = left.len().min(right.len());
+    let num_words = len / NUM_LANES;
+    let mut sums = _mm256_setzero_ps();
+    for word in 0..num_words {
+        let left_vals = _mm256_loadu_ps(left.as_ptr().add(word * NUM_LANES));
+        let right_vals = _mm256_loadu_ps(right.as_ptr().add(word * NUM_LANES));
+        let diffs = _mm256_sub_ps(left_vals, right_vals);
+        sums = _mm256_fmadd_ps(diffs, diffs, sums);
+    }
+    let remainder = num_words * NUM_LANES..len;
+    let remainder_sum: f32 = left[remainder.clone()]
+        .iter()
+        .zip(&right[remainder])
+        .map(|(l, r)| (l - r) * (l - r))
+        .sum();
+    horizontal_sum(sums) + remainder_sum
+}
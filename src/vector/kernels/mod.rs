@@ -0,0 +1,140 @@
+//! Distance kernels, dispatched at runtime to the best instruction set available.
+
+#[cfg(target_arch = "x86_64")]
+mod avx2;
+
+mod scalar;
+
+#[derive(Clone, Copy, Eq, PartialEq, Debug)]
+#[repr(u8)]
+enum KernelImplPerInstructionSet {
+    #[cfg(target_arch = "x86_64")]
+    AVX2 = 0u8,
+    Scalar = 1u8,
+}
+
+impl KernelImplPerInstructionSet {
+    #[inline]
+    fn is_available(&self) -> bool {
+        match *self {
+            #[cfg(target_arch = "x86_64")]
+            KernelImplPerInstructionSet::AVX2 => {
+                is_x86_feature_detected!("avx2") && is_x86_feature_detected!("fma")
+            }
+            KernelImplPerInstructionSet::Scalar => true,
+        }
+    }
+}
+
+// List of available implementation in preferred order.
+#[cfg(target_arch = "x86_64")]
+const IMPLS: [KernelImplPerInstructionSet; 2] = [
+    KernelImplPerInstructionSet::AVX2,
+    KernelImplPerInstructionSet::Scalar,
+];
+
+#[cfg(not(target_arch = "x86_64"))]
+const IMPLS: [KernelImplPerInstructionSet; 1] = [KernelImplPerInstructionSet::Scalar];
+
+impl KernelImplPerInstructionSet {
+    #[allow(unused_variables)]
+    #[inline]
+    fn from(code: u8) -> KernelImplPerInstructionSet {
+        #[cfg(target_arch = "x86_64")]
+        if code == KernelImplPerInstructionSet::AVX2 as u8 {
+            return KernelImplPerInstructionSet::AVX2;
+        }
+        KernelImplPerInstructionSet::Scalar
+    }
+
+    #[inline]
+    fn dot_product(self, left: &[f32], right: &[f32]) -> f32 {
+        match self {
+            // Safety: the instruction set was detected by `is_available`.
+            #[cfg(target_arch = "x86_64")]
+            KernelImplPerInstructionSet::AVX2 => unsafe { avx2::dot_product(left, right) },
+            KernelImplPerInstructionSet::Scalar => scalar::dot_product(left, right),
+        }
+    }
+
+    #[inline]
+    fn squared_l2(self, left: &[f32], right: &[f32]) -> f32 {
+        match self {
+            // Safety: the instruction set was detected by `is_available`.
+            #[cfg(target_arch = "x86_64")]
+            KernelImplPerInstructionSet::AVX2 => unsafe { avx2::squared_l2(left, right) },
+            KernelImplPerInstructionSet::Scalar => scalar::squared_l2(left, right),
+        }
+    }
+}
+
+#[inline]
+fn get_best_available_instruction_set() -> KernelImplPerInstructionSet {
+    use std::sync::atomic::{AtomicU8, Ordering};
+    static INSTRUCTION_SET_BYTE: AtomicU8 = AtomicU8::new(u8::MAX);
+    let instruction_set_byte: u8 = INSTRUCTION_SET_BYTE.load(Ordering::Relaxed);
+    if instruction_set_byte == u8::MAX {
+        // Let's initialize the instruction set and cache it.
+        let instruction_set = IMPLS
+            .into_iter()
+            .find(KernelImplPerInstructionSet::is_available)
+            .unwrap();
+        INSTRUCTION_SET_BYTE.store(instruction_set as u8, Ordering::Relaxed);
+        return instruction_set;
+    }
+    KernelImplPerInstructionSet::from(instruction_set_byte)
+}
+
+/// Returns the dot product of two vectors of the same length.
+pub fn dot_product(left: &[f32], right: &[f32]) -> f32 {
+    debug_assert_eq!(left.len(), right.len());
+    get_best_available_instruction_set().dot_product(left, right)
+}
+
+/// Returns the squared euclidean distance between two vectors of the same length.
+pub fn squared_l2(left: &[f32], right: &[f32]) -> f32 {
+    debug_assert_eq!(left.len(), right.len());
+    get_best_available_instruction_set().squared_l2(left, right)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_get_best_available_instruction_set() {
+        // We just make sure the function returns without crashing and returns the same result.
+        let instruction_set = get_best_available_instruction_set();
+        assert_eq!(get_best_available_instruction_set(), instruction_set);
+    }
+
+    fn test_kernels_aux(kernel_impl: KernelImplPerInstructionSet) {
+        assert_eq!(kernel_impl.dot_product(&[], &[]), 0.0);
+        // Lengths around the number of lanes, to exercise the remainders.
+        for len in [1, 7, 8, 9, 16, 21] {
+            let left: Vec<f32> = (0..len).map(|i| i as f32).collect();
+            let right: Vec<f32> = (0..len).map(|i| (len - i) as f32 * 0.5).collect();
+            let expected_dot: f32 = left.iter().zip(&right).map(|(l, r)| l * r).sum();
+            let expected_l2: f32 = left
+                .iter()
+                .zip(&right)
+                .map(|(l, r)| (l - r) * (l - r))
+                .sum();
+            assert!((kernel_impl.dot_product(&left, &right) - expected_dot).abs() < 1e-3);
+            assert!((kernel_impl.squared_l2(&left, &right) - expected_l2).abs() < 1e-3);
+        }
+    }
+
+    #[test]
+    fn test_kernels_scalar() {
+        test_kernels_aux(KernelImplPerInstructionSet::Scalar);
+    }
+
+    #[cfg(target_arch = "x86_64")]
+    #[test]
+    fn test_kernels_avx2() {
+        if KernelImplPerInstructionSet::AVX2.is_available() {
+            test_kernels_aux(KernelImplPerInstructionSet::AVX2);
+        }
+    }
+}
@@ -0,0 +1,38 @@
+/// Number of independent accumulators, so that the compiler can vectorize the loops.
+const NUM_LANES: usize = 8;
+
+pub fn dot_product(left: &[f32], right: &[f32]) -> f32 {
+    let mut sums = [0.0f32; NUM_LANES];
+    let left_chunks = left.chunks_exact(NUM_LANES);
+    let right_chunks = right.chunks_exact(NUM_LANES);
+    let remainder: f32 = left_chunks
+        .remainder()
+        .iter()
+        .zip(right_chunks.remainder())
+        .map(|(l, r)| l * r)
+        .sum();
+    for (left_chunk, right_chunk) in left_chunks.zip(right_chunks) {
+        for ((sum, l), r) in sums.iter_mut().zip(left_chunk).zip(right_chunk) {
+            *sum += l * r;
+        }
+    }
+    sums.iter().sum::<f32>() + remainder
+}
+
+pub fn squared_l2(left: &[f32], right: &[f32]) -> f32 {
+    let mut sums = [0.0f32; NUM_LANES];
+    let left_chunks = left.chunks_exact(NUM_LANES);
+    let right_chunks = right.chunks_exact(NUM_LANES);
+    let remainder: f32 = left_chunks
+        .remainder()
+        .iter()
+        .zip(right_chunks.remainder())
+        .map(|(l, r)| (l - r) * (l - r))
+        .sum();
+    for (left_chunk, right_chunk) in left_chunks.zip(right_chunks) {
+        for ((sum, l), r) in sums.iter_mut().zip(left_chunk).zip(right_chunk) {
+            *sum += (l - r) * (l - r);
+        }
+    }
+    sums.iter().sum::<f32>() + remainder
+}
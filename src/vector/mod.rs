@@ -0,0 +1,373 @@
+//! Dense vectors and nearest neighbor search.
+//!
+//! A dense vector field is a bytes field whose values are vectors of `f32` with a fixed
+//! number of dimensions, see
+//! [`BytesOptions::set_dense_vector`](crate::schema::BytesOptions::set_dense_vector).
+//! The vectors are encoded with [`encode_vector`], or added to a document as a
+//! [`DenseVector`]. In JSON documents, a vector is an array of numbers, or its encoding
+//! in base64.
+//!
+//! Each segment has a vector index file, which holds for each dense vector field its
+//! vectors and an index over them, selected by the
+//! [`VectorIndexOptions`](crate::schema::VectorIndexOptions) of the field: an HNSW graph,
+//! an IVF-PQ index whose centroids and codebooks are trained on the vectors of the
+//! segment, or a SPANN index whose lists of vectors are read from disk at search time.
+//! The index is built when the segment is written, either at flush or at merge. It is
+//! searched with a [`KnnQuery`](crate::query::KnnQuery), while an
+//! [`ExactKnnQuery`](crate::query::ExactKnnQuery) scans the vectors instead.
+//!
+//! A document can have several vectors for a field. It is then matched by a
+//! [`KnnQuery`](crate::query::KnnQuery) through its closest vector, and a
+//! [`MaxSimQuery`](crate::query::MaxSimQuery) scores it against several query vectors, as
+//! late interaction models do.
+mod hnsw;
+mod ivf_pq;
+mod kernels;
+mod reader;
+mod scalar_quantizer;
+mod spann;
+mod writer;
+
+use std::io;
+
+pub(crate) use self::hnsw::HnswGraph;
+pub(crate) use self::ivf_pq::IvfPqIndex;
+pub(crate) use self::kernels::{dot_product, squared_l2};
+pub use self::reader::{FieldVectorIndex, VectorIndexReader};
+pub(crate) use self::scalar_quantizer::ScalarQuantizer;
+pub(crate) use self::spann::SpannIndex;
+pub use self::writer::VectorIndexWriter;
+use crate::schema::document::{Document, Value};
+use crate::schema::{OwnedValue, Schema, VectorMetric};
+use crate::{Score, TantivyError};
+
+/// Encodes a vector as the value of a dense vector field.
+pub fn encode_vector(vector: &[f32]) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(vector.len() * 4);
+    for val in vector {
+        bytes.extend_from_slice(&val.to_le_bytes());
+    }
+    bytes
+}
+
+/// A vector, as a value of a dense vector field.
+///
+/// It holds the vector encoded with [`encode_vector`], so that it can be added to a
+/// document with the [`doc!`](crate::doc) macro.
+///
+/// ```rust
+/// use tantivy::doc;
+/// use tantivy::schema::{DenseVectorOptions, Schema, VectorMetric};
+/// use tantivy::vector::DenseVector;
+///
+/// let mut schema_builder = Schema::builder();
+/// let embedding = schema_builder
+///     .add_dense_vector_field("embedding", DenseVectorOptions::new(3, VectorMetric::Cosine));
+/// let doc = doc!(embedding => DenseVector::from([0.5, 1.0, 0.0]));
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DenseVector(Vec<u8>);
+
+impl DenseVector {
+    /// Returns the vector, encoded with [`encode_vector`].
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.0
+    }
+
+    /// Returns the number of dimensions of the vector.
+    pub fn dimensions(&self) -> usize {
+        self.0.len() / 4
+    }
+
+    /// Decodes the vector.
+    pub fn to_vec(&self) -> Vec<f32> {
+        decode_vector(&self.0).expect("the length is a multiple of 4")
+    }
+}
+
+impl From<&[f32]> for DenseVector {
+    fn from(vector: &[f32]) -> DenseVector {
+        DenseVector(encode_vector(vector))
+    }
+}
+
+impl From<Vec<f32>> for DenseVector {
+    fn from(vector: Vec<f32>) -> DenseVector {
+        DenseVector::from(&vector[..])
+    }
+}
+
+impl<const N: usize> From<[f32; N]> for DenseVector {
+    fn from(vector: [f32; N]) -> DenseVector {
+        DenseVector::from(&vector[..])
+    }
+}
+
+impl From<DenseVector> for OwnedValue {
+    fn from(vector: DenseVector) -> OwnedValue {
+        OwnedValue::Bytes(vector.0)
+    }
+}
+
+/// Decodes the value of a dense vector field.
+///
+/// Returns an error if the length of `bytes` is not a multiple of 4.
+pub fn decode_vector(bytes: &[u8]) -> io::Result<Vec<f32>> {
+    if bytes.len() % 4 != 0 {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("{} bytes do not encode a vector of f32", bytes.len()),
+        ));
+    }
+    Ok(bytes
+        .chunks_exact(4)
+        .map(|chunk| f32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]))
+        .collect())
+}
+
+/// Checks that the vectors of `doc` have the number of dimensions of their field.
+pub(crate) fn check_document_vectors<D: Document>(schema: &Schema, doc: &D) -> crate::Result<()> {
+    for (field, value) in doc.iter_fields_and_values() {
+        let field_entry = schema.get_field_entry(field);
+        let (Some(options), Some(bytes)) = (field_entry.dense_vector_options(), value.as_bytes())
+        else {
+            continue;
+        };
+        let num_bytes = options.dimensions() as usize * 4;
+        if bytes.len() != num_bytes {
+            return Err(TantivyError::InvalidArgument(format!(
+                "Field `{}` expects vectors of {} dimensions ({} bytes), got {} bytes",
+                field_entry.name(),
+                options.dimensions(),
+                num_bytes,
+                bytes.len()
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Normalizes `vector` in place, if the metric expects normalized vectors.
+pub(crate) fn prepare_vector(metric: VectorMetric, vector: &mut [f32]) {
+    if metric == VectorMetric::Cosine {
+        let norm = dot_product(vector, vector).sqrt();
+        if norm > 0.0 {
+            for val in vector.iter_mut() {
+                *val /= norm;
+            }
+        }
+    }
+}
+
+/// Returns the distance between two vectors prepared with [`prepare_vector`]. The lower,
+/// the closer.
+pub(crate) fn distance(metric: VectorMetric, left: &[f32], right: &[f32]) -> f32 {
+    match metric {
+        VectorMetric::L2 => squared_l2(left, right),
+        VectorMetric::Cosine => 1.0 - dot_product(left, right),
+        VectorMetric::DotProduct => -dot_product(left, right),
+    }
+}
+
+/// Maps `f` over `items` with up to `num_threads` threads, keeping the order of the items.
+pub(crate) fn parallel_map<T: Sync, R: Send>(
+    items: &[T],
+    num_threads: usize,
+    f: impl Fn(&T) -> R + Sync,
+) -> Vec<R> {
+    if num_threads <= 1 || items.len() <= 1 {
+        return items.iter().map(f).collect();
+    }
+    let chunk_size = (items.len() + num_threads - 1) / num_threads;
+    let f = &f;
+    std::thread::scope(|scope| {
+        let handles: Vec<_> = items
+            .chunks(chunk_size)
+            .map(|chunk| scope.spawn(move || chunk.iter().map(f).collect::<Vec<R>>()))
+            .collect();
+        handles
+            .into_iter()
+            .flat_map(|handle| handle.join().expect("vector index build thread panicked"))
+            .collect()
+    })
+}
+
+/// Converts a distance into a score. The closer, the higher.
+///
+/// - `L2`: `1 / (1 + squared distance)`
+/// - `Cosine`: `(1 + cosine) / 2`
+/// - `DotProduct`: the dot product
+pub(crate) fn score_from_distance(metric: VectorMetric, distance: f32) -> Score {
+    match metric {
+        VectorMetric::L2 => 1.0 / (1.0 + distance),
+        VectorMetric::Cosine => (2.0 - distance) / 2.0,
+        VectorMetric::DotProduct => -distance,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    use super::{decode_vector, encode_vector, DenseVector};
+    use crate::collector::TopDocs;
+    use crate::query::KnnQuery;
+    use crate::schema::{
+        DenseVectorOptions, IvfPqOptions, Schema, SpannOptions, Value, VectorIndexOptions,
+        VectorMetric, VectorQuantization, STORED, STRING,
+    };
+    use crate::{doc, Index, IndexSettings, IndexWriter, TantivyDocument, TantivyError, Term};
+
+    #[test]
+    fn test_encode_vector() {
+        let vector = vec![1.0, -2.5, 0.0];
+        assert_eq!(decode_vector(&encode_vector(&vector)).unwrap(), vector);
+        assert!(decode_vector(&[0u8; 5]).is_err());
+        let dense_vector = DenseVector::from([1.0, -2.5, 0.0]);
+        assert_eq!(dense_vector.as_bytes(), &encode_vector(&vector)[..]);
+        assert_eq!(dense_vector.dimensions(), 3);
+        assert_eq!(dense_vector.to_vec(), vector);
+    }
+
+    #[test]
+    fn test_add_document_vector_dimensions() -> crate::Result<()> {
+        let mut schema_builder = Schema::builder();
+        let embedding = schema_builder
+            .add_dense_vector_field("embedding", DenseVectorOptions::new(2, VectorMetric::L2));
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer: IndexWriter = index.writer_for_tests()?;
+        index_writer.add_document(doc!(embedding => DenseVector::from([1.0, 0.0])))?;
+        // The document is rejected before it reaches the indexing threads, which keep
+        // running.
+        assert!(matches!(
+            index_writer.add_document(doc!(embedding => DenseVector::from([1.0]))),
+            Err(TantivyError::InvalidArgument(_))
+        ));
+        index_writer.add_document(doc!(embedding => DenseVector::from([0.0, 1.0])))?;
+        index_writer.commit()?;
+        assert_eq!(index.reader()?.searcher().num_docs(), 2);
+        Ok(())
+    }
+
+    fn test_knn_query_aux(
+        dense_vector_options: DenseVectorOptions,
+        vector_index_build_threads: usize,
+    ) -> crate::Result<()> {
+        let mut schema_builder = Schema::builder();
+        let id = schema_builder.add_text_field("id", STRING | STORED);
+        let embedding = schema_builder.add_dense_vector_field("embedding", dense_vector_options);
+        let settings = IndexSettings {
+            vector_index_build_threads,
+            ..Default::default()
+        };
+        let index = Index::builder()
+            .schema(schema_builder.build())
+            .settings(settings)
+            .create_in_ram()?;
+        let mut rng = StdRng::seed_from_u64(42);
+        let vectors: Vec<Vec<f32>> = (0..300)
+            .map(|_| (0..4).map(|_| rng.gen_range(-1.0..1.0)).collect())
+            .collect();
+        let mut index_writer: IndexWriter = index.writer_for_tests()?;
+        for (doc_id, vector) in vectors.iter().enumerate() {
+            index_writer.add_document(doc!(
+                id => doc_id.to_string(),
+                embedding => encode_vector(vector),
+            ))?;
+            if doc_id % 100 == 99 {
+                index_writer.commit()?;
+            }
+        }
+        index_writer.add_document(doc!(id => "no vector"))?;
+        index_writer.commit()?;
+
+        let nearest = |query: &[f32], k: usize| -> Vec<String> {
+            let mut distances: Vec<(f32, usize)> = vectors
+                .iter()
+                .enumerate()
+                .map(|(doc_id, vector)| (super::distance(VectorMetric::L2, query, vector), doc_id))
+                .collect();
+            distances.sort_by(|left, right| left.0.total_cmp(&right.0));
+            distances
+                .iter()
+                .take(k)
+                .map(|(_, doc_id)| doc_id.to_string())
+                .collect()
+        };
+        let search = |query: &[f32], k: usize| -> crate::Result<Vec<String>> {
+            let searcher = index.reader()?.searcher();
+            let knn_query = KnnQuery::new(embedding, query.to_vec(), k).with_ef_search(1_000);
+            searcher
+                .search(&knn_query, &TopDocs::with_limit(k))?
+                .into_iter()
+                .map(|(_, doc_address)| {
+                    let doc: TantivyDocument = searcher.doc(doc_address)?;
+                    Ok(doc.get_first(id).unwrap().as_str().unwrap().to_string())
+                })
+                .collect()
+        };
+        let query = [0.1, 0.2, -0.3, 0.4];
+        assert_eq!(search(&query, 5)?, nearest(&query, 5));
+        assert_eq!(search(&vectors[7], 1)?, vec!["7".to_string()]);
+
+        let segment_ids = index.searchable_segment_ids()?;
+        index_writer.merge(&segment_ids).wait()?;
+        index_writer.wait_merging_threads()?;
+        assert_eq!(index.searchable_segment_ids()?.len(), 1);
+        assert_eq!(search(&query, 5)?, nearest(&query, 5));
+
+        // Deleted documents are not returned.
+        let closest = nearest(&query, 1).remove(0);
+        index_writer.delete_term(Term::from_field_text(id, &closest));
+        index_writer.commit()?;
+        assert_eq!(search(&query, 4)?, nearest(&query, 5)[1..].to_vec());
+        Ok(())
+    }
+
+    #[test]
+    fn test_knn_query_hnsw() -> crate::Result<()> {
+        test_knn_query_aux(DenseVectorOptions::new(4, VectorMetric::L2), 1)
+    }
+
+    #[test]
+    fn test_knn_query_hnsw_parallel_build() -> crate::Result<()> {
+        test_knn_query_aux(DenseVectorOptions::new(4, VectorMetric::L2), 4)
+    }
+
+    #[test]
+    fn test_knn_query_hnsw_int8() -> crate::Result<()> {
+        // The candidates found with the quantized vectors are rescored exactly.
+        test_knn_query_aux(
+            DenseVectorOptions::new(4, VectorMetric::L2)
+                .set_quantization(VectorQuantization::Int8 { rescore: true }),
+            1,
+        )
+    }
+
+    #[test]
+    fn test_knn_query_ivf_pq() -> crate::Result<()> {
+        // Probing all of the lists and rescoring all of the candidates gives exact results.
+        let ivf_pq_options = IvfPqOptions::default()
+            .set_num_subquantizers(2)
+            .set_num_probes(1_000);
+        test_knn_query_aux(
+            DenseVectorOptions::new(4, VectorMetric::L2)
+                .set_index(VectorIndexOptions::IvfPq(ivf_pq_options)),
+            2,
+        )
+    }
+
+    #[test]
+    fn test_knn_query_spann() -> crate::Result<()> {
+        // Probing all of the lists gives exact results.
+        let spann_options = SpannOptions::default()
+            .set_list_size(16)
+            .set_num_probes(1_000);
+        test_knn_query_aux(
+            DenseVectorOptions::new(4, VectorMetric::L2)
+                .set_index(VectorIndexOptions::Spann(spann_options)),
+            2,
+        )
+    }
+}
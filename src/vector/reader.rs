@@ -0,0 +1,445 @@
+use std::collections::HashMap;
+use std::io;
+use std::ops::Range;
+use std::sync::{Arc, RwLock};
+
+use common::{BinarySerializable, OwnedBytes, VInt};
+
+use super::{
+    decode_vector, distance, prepare_vector, score_from_distance, HnswGraph, IvfPqIndex,
+    ScalarQuantizer, SpannIndex,
+};
+use crate::directory::{CompositeFile, FileSlice};
+use crate::schema::{
+    DenseVectorOptions, Field, Schema, VectorIndexOptions, VectorMetric, VectorQuantization,
+};
+use crate::space_usage::PerFieldSpaceUsage;
+use crate::{DocId, Score};
+
+/// The index over the vectors of a field.
+pub(crate) enum AnnIndex {
+    /// The vectors are kept decoded in memory, as searching the graph computes the
+    /// distance to many of them.
+    Hnsw { values: Vec<f32>, graph: HnswGraph },
+    /// The graph is searched with the int8 codes of the vectors, `dimensions` bytes per
+    /// vector.
+    QuantizedHnsw {
+        quantizer: ScalarQuantizer,
+        codes: OwnedBytes,
+        rescore: bool,
+        graph: HnswGraph,
+    },
+    IvfPq {
+        index: IvfPqIndex,
+        num_probes: usize,
+    },
+    /// Only the centroids are held in memory, the lists are read at search time.
+    Spann {
+        index: SpannIndex,
+        num_probes: usize,
+    },
+}
+
+/// Deserializes the HNSW graph of `num_vectors` vectors.
+fn deserialize_graph(reader: &mut &[u8], num_vectors: usize) -> io::Result<HnswGraph> {
+    let graph = HnswGraph::deserialize(reader)?;
+    if graph.num_nodes() != num_vectors {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!(
+                "HNSW graph has {} nodes, while the field has {num_vectors} vectors",
+                graph.num_nodes()
+            ),
+        ));
+    }
+    Ok(graph)
+}
+
+/// The vectors of a dense vector field in a segment, along with their index.
+pub struct FieldVectorIndex {
+    dimensions: usize,
+    metric: VectorMetric,
+    /// Doc id of each vector, in increasing order.
+    doc_ids: Vec<DocId>,
+    /// The vectors, encoded with [`encode_vector`](super::encode_vector). They are only
+    /// read when needed, unless the index keeps them decoded in memory.
+    raw_vectors: FileSlice,
+    ann_index: AnnIndex,
+}
+
+impl FieldVectorIndex {
+    fn open(file_slice: FileSlice, options: &DenseVectorOptions) -> io::Result<FieldVectorIndex> {
+        let dimensions = options.dimensions() as usize;
+        let truncated = || io::Error::new(io::ErrorKind::UnexpectedEof, "truncated vector index");
+        // A VInt takes at most 10 bytes.
+        let num_vectors_bytes = file_slice.read_bytes_slice(0..file_slice.len().min(10))?;
+        let mut reader = num_vectors_bytes.as_slice();
+        let num_vectors = VInt::deserialize_u64(&mut reader)? as usize;
+        let doc_ids_start = num_vectors_bytes.len() - reader.len();
+        let raw_vectors_start = doc_ids_start + num_vectors * 4;
+        let index_start = raw_vectors_start + num_vectors * dimensions * 4;
+        if file_slice.len() < index_start {
+            return Err(truncated());
+        }
+        let doc_ids_bytes = file_slice.read_bytes_slice(doc_ids_start..raw_vectors_start)?;
+        let mut reader = doc_ids_bytes.as_slice();
+        let mut doc_ids = Vec::with_capacity(num_vectors);
+        for _ in 0..num_vectors {
+            doc_ids.push(DocId::deserialize(&mut reader)?);
+        }
+        let raw_vectors = file_slice.slice(raw_vectors_start..index_start);
+        let index_slice = file_slice.slice_from(index_start);
+        let ann_index = match (options.index(), options.quantization()) {
+            (VectorIndexOptions::Hnsw(_), VectorQuantization::None) => AnnIndex::Hnsw {
+                values: decode_vector(raw_vectors.read_bytes()?.as_slice())?,
+                graph: deserialize_graph(&mut index_slice.read_bytes()?.as_slice(), num_vectors)?,
+            },
+            (VectorIndexOptions::Hnsw(_), VectorQuantization::Int8 { rescore }) => {
+                let bytes = index_slice.read_bytes()?;
+                let mut reader = bytes.as_slice();
+                let quantizer = ScalarQuantizer::deserialize(&mut reader, dimensions)?;
+                let num_code_bytes = num_vectors * dimensions;
+                if reader.len() < num_code_bytes {
+                    return Err(truncated());
+                }
+                let codes_start = bytes.len() - reader.len();
+                let codes = bytes.slice(codes_start..codes_start + num_code_bytes);
+                reader = &reader[num_code_bytes..];
+                AnnIndex::QuantizedHnsw {
+                    quantizer,
+                    codes,
+                    rescore,
+                    graph: deserialize_graph(&mut reader, num_vectors)?,
+                }
+            }
+            (VectorIndexOptions::IvfPq(ivf_pq_options), _) => AnnIndex::IvfPq {
+                index: IvfPqIndex::deserialize(
+                    &mut index_slice.read_bytes()?.as_slice(),
+                    dimensions,
+                )?,
+                num_probes: ivf_pq_options.num_probes() as usize,
+            },
+            (VectorIndexOptions::Spann(spann_options), _) => AnnIndex::Spann {
+                index: SpannIndex::open(index_slice, dimensions)?,
+                num_probes: spann_options.num_probes() as usize,
+            },
+        };
+        Ok(FieldVectorIndex {
+            dimensions,
+            metric: options.metric(),
+            doc_ids,
+            raw_vectors,
+            ann_index,
+        })
+    }
+
+    /// Returns the number of vectors of the field in the segment.
+    pub fn num_vectors(&self) -> usize {
+        self.doc_ids.len()
+    }
+
+    /// Returns the number of dimensions of the vectors.
+    pub fn dimensions(&self) -> usize {
+        self.dimensions
+    }
+
+    /// Returns the metric used to compare the vectors.
+    pub fn metric(&self) -> VectorMetric {
+        self.metric
+    }
+
+    pub(crate) fn ann_index(&self) -> &AnnIndex {
+        &self.ann_index
+    }
+
+    /// Returns the ordinals of the vectors of the document `doc`.
+    pub(crate) fn ords(&self, doc: DocId) -> Range<u32> {
+        let start = self.doc_ids.partition_point(|&doc_id| doc_id < doc);
+        let end = self.doc_ids.partition_point(|&doc_id| doc_id <= doc);
+        start as u32..end as u32
+    }
+
+    fn vector(&self, ord: u32) -> io::Result<Vec<f32>> {
+        let mut buffer = Vec::new();
+        Ok(self.vector_in(ord, &mut buffer)?.to_vec())
+    }
+
+    /// Returns the vector `ord`, decoding it in `buffer` if it is not kept decoded in memory.
+    fn vector_in<'a>(&'a self, ord: u32, buffer: &'a mut Vec<f32>) -> io::Result<&'a [f32]> {
+        let start = ord as usize * self.dimensions;
+        if let AnnIndex::Hnsw { values, .. } = &self.ann_index {
+            return Ok(&values[start..start + self.dimensions]);
+        }
+        let bytes = self
+            .raw_vectors
+            .read_bytes_slice(start * 4..(start + self.dimensions) * 4)?;
+        buffer.clear();
+        buffer.extend(
+            bytes
+                .as_slice()
+                .chunks_exact(4)
+                .map(|chunk| f32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]])),
+        );
+        Ok(buffer)
+    }
+
+    /// Returns the vectors of the document `doc`.
+    ///
+    /// The vectors of a field compared with the cosine metric are returned normalized.
+    pub fn vectors(&self, doc: DocId) -> impl Iterator<Item = io::Result<Vec<f32>>> + '_ {
+        self.ords(doc).map(move |ord| self.vector(ord))
+    }
+
+    /// Returns the (at most) `k` documents closest to `query`, with their distance, sorted by
+    /// increasing distance.
+    ///
+    /// `ef_search` is the number of candidates considered while searching the index: the
+    /// higher, the better the recall and the slower the search. With an IVF-PQ index, it is
+    /// the number of candidates rescored with their raw vector. It is ignored by a SPANN
+    /// index, whose lists of vectors are read from disk.
+    ///
+    /// Only the documents for which `accept` returns true are returned. The filter is applied
+    /// while searching the index, so that `k` documents are returned even if the filter
+    /// rejects most of the closest ones. A document with several vectors is returned once,
+    /// with the distance of its closest vector.
+    pub fn search(
+        &self,
+        query: &[f32],
+        k: usize,
+        ef_search: usize,
+        accept: impl Fn(DocId) -> bool,
+    ) -> io::Result<Vec<(DocId, f32)>> {
+        let mut query = query.to_vec();
+        prepare_vector(self.metric, &mut query);
+        let num_candidates = ef_search.max(k);
+        let candidates = match &self.ann_index {
+            AnnIndex::Hnsw { values, graph } => graph.search_filtered(
+                num_candidates,
+                |ord| {
+                    let start = ord as usize * self.dimensions;
+                    distance(self.metric, &query, &values[start..start + self.dimensions])
+                },
+                |ord| accept(self.doc_ids[ord as usize]),
+            ),
+            AnnIndex::QuantizedHnsw {
+                quantizer,
+                codes,
+                rescore,
+                graph,
+            } => {
+                let query_distance = quantizer.query_distance(self.metric, &query);
+                let mut candidates = graph.search_filtered(
+                    num_candidates,
+                    |ord| {
+                        let start = ord as usize * self.dimensions;
+                        query_distance.distance(&codes.as_slice()[start..start + self.dimensions])
+                    },
+                    |ord| accept(self.doc_ids[ord as usize]),
+                );
+                if *rescore {
+                    let mut buffer = Vec::with_capacity(self.dimensions);
+                    for (ord, candidate_distance) in &mut candidates {
+                        *candidate_distance =
+                            distance(self.metric, &query, self.vector_in(*ord, &mut buffer)?);
+                    }
+                    candidates.sort_by(|left, right| left.1.total_cmp(&right.1));
+                }
+                candidates
+            }
+            AnnIndex::IvfPq { index, num_probes } => {
+                let mut candidates: Vec<(u32, f32)> = index
+                    .search(self.metric, &query, *num_probes)
+                    .into_iter()
+                    .filter(|&(ord, _)| accept(self.doc_ids[ord as usize]))
+                    .take(num_candidates)
+                    .map(|(ord, _)| Ok((ord, distance(self.metric, &query, &self.vector(ord)?))))
+                    .collect::<io::Result<_>>()?;
+                candidates.sort_by(|left, right| left.1.total_cmp(&right.1));
+                candidates
+            }
+            AnnIndex::Spann { index, num_probes } => {
+                index.search(self.metric, &query, *num_probes, |ord| {
+                    accept(self.doc_ids[ord as usize])
+                })?
+            }
+        };
+        let mut hits: Vec<(DocId, f32)> = Vec::with_capacity(k);
+        for (ord, distance) in candidates {
+            let doc = self.doc_ids[ord as usize];
+            if !accept(doc) || hits.iter().any(|&(hit_doc, _)| hit_doc == doc) {
+                continue;
+            }
+            hits.push((doc, distance));
+            if hits.len() == k {
+                break;
+            }
+        }
+        Ok(hits)
+    }
+
+    /// Returns the (at most) `k` documents of `docs` closest to `query`, with their
+    /// distance, sorted by increasing distance.
+    ///
+    /// Unlike [`FieldVectorIndex::search`], the distance to each of the vectors of the
+    /// documents is computed, so that the result is exact. This is faster than searching the
+    /// index when `docs` is small. `docs` has to be sorted by increasing doc id. The
+    /// documents without any vector are ignored.
+    pub fn exact_search(
+        &self,
+        query: &[f32],
+        k: usize,
+        docs: impl Iterator<Item = DocId>,
+    ) -> io::Result<Vec<(DocId, f32)>> {
+        let mut query = query.to_vec();
+        prepare_vector(self.metric, &mut query);
+        let mut buffer = Vec::with_capacity(self.dimensions);
+        let mut hits: Vec<(DocId, f32)> = Vec::new();
+        let mut ord = 0;
+        for doc in docs {
+            ord += self.doc_ids[ord..].partition_point(|&doc_id| doc_id < doc);
+            let mut closest_distance: Option<f32> = None;
+            while self.doc_ids.get(ord) == Some(&doc) {
+                let vector_distance = distance(
+                    self.metric,
+                    &query,
+                    self.vector_in(ord as u32, &mut buffer)?,
+                );
+                closest_distance = Some(
+                    closest_distance
+                        .map_or(vector_distance, |closest| closest.min(vector_distance)),
+                );
+                ord += 1;
+            }
+            if let Some(distance) = closest_distance {
+                hits.push((doc, distance));
+            }
+        }
+        let cmp_distance = |left: &(DocId, f32), right: &(DocId, f32)| left.1.total_cmp(&right.1);
+        if hits.len() > k {
+            hits.select_nth_unstable_by(k, cmp_distance);
+            hits.truncate(k);
+        }
+        hits.sort_by(cmp_distance);
+        Ok(hits)
+    }
+
+    /// Returns the (at most) `k` documents the most similar to the multi-vector query
+    /// `queries`, with their similarity, sorted by decreasing similarity.
+    ///
+    /// The similarity of a document is the late interaction (or "max-sim") similarity of
+    /// ColBERT-like models: the sum, over the query vectors, of the similarity of the
+    /// closest vector of the document, as given by
+    /// [`score_from_distance`](super::score_from_distance). The candidates are the
+    /// `ef_search` nearest neighbors of each query vector, found by searching the index,
+    /// and their similarity is then computed exactly.
+    ///
+    /// Only the documents for which `accept` returns true are returned.
+    pub fn max_sim_search(
+        &self,
+        queries: &[Vec<f32>],
+        k: usize,
+        ef_search: usize,
+        accept: impl Fn(DocId) -> bool,
+    ) -> io::Result<Vec<(DocId, Score)>> {
+        let mut candidates: Vec<DocId> = Vec::new();
+        for query in queries {
+            let hits = self.search(query, ef_search.max(k), ef_search, &accept)?;
+            candidates.extend(hits.into_iter().map(|(doc, _)| doc));
+        }
+        candidates.sort_unstable();
+        candidates.dedup();
+        let queries: Vec<Vec<f32>> = queries
+            .iter()
+            .map(|query| {
+                let mut query = query.clone();
+                prepare_vector(self.metric, &mut query);
+                query
+            })
+            .collect();
+        let mut buffer = Vec::with_capacity(self.dimensions);
+        let mut max_sims = vec![Score::NEG_INFINITY; queries.len()];
+        let mut hits: Vec<(DocId, Score)> = Vec::with_capacity(candidates.len());
+        let mut ord = 0;
+        for doc in candidates {
+            ord += self.doc_ids[ord..].partition_point(|&doc_id| doc_id < doc);
+            max_sims.fill(Score::NEG_INFINITY);
+            while self.doc_ids.get(ord) == Some(&doc) {
+                let vector = self.vector_in(ord as u32, &mut buffer)?;
+                for (query, max_sim) in queries.iter().zip(max_sims.iter_mut()) {
+                    let sim =
+                        score_from_distance(self.metric, distance(self.metric, query, vector));
+                    *max_sim = max_sim.max(sim);
+                }
+                ord += 1;
+            }
+            hits.push((doc, max_sims.iter().sum()));
+        }
+        let cmp_sim = |left: &(DocId, Score), right: &(DocId, Score)| right.1.total_cmp(&left.1);
+        if hits.len() > k {
+            hits.select_nth_unstable_by(k, cmp_sim);
+            hits.truncate(k);
+        }
+        hits.sort_by(cmp_sim);
+        Ok(hits)
+    }
+}
+
+/// Reads the vector index of a segment.
+#[derive(Clone)]
+pub struct VectorIndexReader {
+    composite_file: CompositeFile,
+    schema: Schema,
+    cache: Arc<RwLock<HashMap<Field, Arc<FieldVectorIndex>>>>,
+}
+
+impl VectorIndexReader {
+    /// Opens the vector index file of a segment.
+    pub fn open(file: FileSlice, schema: Schema) -> crate::Result<VectorIndexReader> {
+        Ok(VectorIndexReader {
+            composite_file: CompositeFile::open(&file)?,
+            schema,
+            cache: Default::default(),
+        })
+    }
+
+    /// Returns a vector index reader without any vector.
+    pub fn empty(schema: Schema) -> VectorIndexReader {
+        VectorIndexReader {
+            composite_file: CompositeFile::empty(),
+            schema,
+            cache: Default::default(),
+        }
+    }
+
+    /// Returns the vector index of the dense vector field `field`.
+    ///
+    /// Returns `None` if the field is not a dense vector field, or if none of the
+    /// documents of the segment has a vector for it.
+    pub fn field_index(&self, field: Field) -> crate::Result<Option<Arc<FieldVectorIndex>>> {
+        if let Some(field_index) = self
+            .cache
+            .read()
+            .expect("Lock poisoned. This should never happen")
+            .get(&field)
+        {
+            return Ok(Some(Arc::clone(field_index)));
+        }
+        let Some(options) = self.schema.get_field_entry(field).dense_vector_options() else {
+            return Ok(None);
+        };
+        let Some(file_slice) = self.composite_file.open_read(field) else {
+            return Ok(None);
+        };
+        let field_index = Arc::new(FieldVectorIndex::open(file_slice, options)?);
+        self.cache
+            .write()
+            .expect("Lock poisoned. This should never happen")
+            .insert(field, Arc::clone(&field_index));
+        Ok(Some(field_index))
+    }
+
+    /// Returns the space usage of the vector index, field by field.
+    pub fn space_usage(&self) -> PerFieldSpaceUsage {
+        self.composite_file.space_usage()
+    }
+}
@@ -0,0 +1,199 @@
+use std::io;
+
+use common::BinarySerializable;
+
+use crate::schema::VectorMetric;
+
+/// Quantizes each dimension of a vector to a byte.
+///
+/// The values of each dimension are mapped linearly from the range they take over the
+/// vectors the quantizer is trained on to `[0, 255]`: a value `v` is encoded as
+/// `round((v - offset) / scale)`.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct ScalarQuantizer {
+    offsets: Vec<f32>,
+    scales: Vec<f32>,
+}
+
+impl ScalarQuantizer {
+    /// Computes the range of each dimension over `vectors`, a sequence of vectors of
+    /// `dimensions` dimensions.
+    pub fn train(vectors: &[f32], dimensions: usize) -> ScalarQuantizer {
+        let mut min_vals = vec![f32::INFINITY; dimensions];
+        let mut max_vals = vec![f32::NEG_INFINITY; dimensions];
+        for vector in vectors.chunks_exact(dimensions) {
+            for ((min_val, max_val), &val) in min_vals.iter_mut().zip(&mut max_vals).zip(vector) {
+                *min_val = min_val.min(val);
+                *max_val = max_val.max(val);
+            }
+        }
+        let (offsets, scales) = min_vals
+            .into_iter()
+            .zip(max_vals)
+            .map(|(min_val, max_val)| {
+                if min_val < max_val {
+                    (min_val, (max_val - min_val) / 255.0)
+                } else if min_val == max_val {
+                    (min_val, 1.0)
+                } else {
+                    // No vector at all.
+                    (0.0, 1.0)
+                }
+            })
+            .unzip();
+        ScalarQuantizer { offsets, scales }
+    }
+
+    /// Appends the code of `vector` to `codes`.
+    pub fn encode(&self, vector: &[f32], codes: &mut Vec<u8>) {
+        codes.extend(
+            vector
+                .iter()
+                .zip(self.offsets.iter().zip(&self.scales))
+                .map(|(val, (offset, scale))| {
+                    ((val - offset) / scale).round().clamp(0.0, 255.0) as u8
+                }),
+        );
+    }
+
+    /// Returns the distance function between `query` and the codes of vectors, for
+    /// `metric`.
+    ///
+    /// `query` has to be prepared for `metric`. The distance is computed without decoding
+    /// the codes: the parts of the computation that only depend on the query are done once.
+    pub fn query_distance(&self, metric: VectorMetric, query: &[f32]) -> QuantizedDistance {
+        match metric {
+            VectorMetric::L2 => QuantizedDistance::L2 {
+                shifted_query: query
+                    .iter()
+                    .zip(&self.offsets)
+                    .map(|(val, offset)| val - offset)
+                    .collect(),
+                scales: self.scales.clone(),
+            },
+            VectorMetric::Cosine | VectorMetric::DotProduct => QuantizedDistance::Dot {
+                metric,
+                query_dot_offsets: query
+                    .iter()
+                    .zip(&self.offsets)
+                    .map(|(val, offset)| val * offset)
+                    .sum(),
+                scaled_query: query
+                    .iter()
+                    .zip(&self.scales)
+                    .map(|(val, scale)| val * scale)
+                    .collect(),
+            },
+        }
+    }
+
+    pub fn serialize<W: io::Write + ?Sized>(&self, writer: &mut W) -> io::Result<()> {
+        for val in self.offsets.iter().chain(&self.scales) {
+            val.serialize(writer)?;
+        }
+        Ok(())
+    }
+
+    pub fn deserialize<R: io::Read>(
+        reader: &mut R,
+        dimensions: usize,
+    ) -> io::Result<ScalarQuantizer> {
+        let mut vals = Vec::with_capacity(2 * dimensions);
+        for _ in 0..2 * dimensions {
+            vals.push(f32::deserialize(reader)?);
+        }
+        let scales = vals.split_off(dimensions);
+        Ok(ScalarQuantizer {
+            offsets: vals,
+            scales,
+        })
+    }
+}
+
+/// The distance between a query and codes of a [`ScalarQuantizer`].
+pub(crate) enum QuantizedDistance {
+    /// `sum((query - offset - code * scale)²)`
+    L2 {
+        shifted_query: Vec<f32>,
+        scales: Vec<f32>,
+    },
+    /// The dot product is `query · offset + sum(query * scale * code)`.
+    Dot {
+        metric: VectorMetric,
+        query_dot_offsets: f32,
+        scaled_query: Vec<f32>,
+    },
+}
+
+impl QuantizedDistance {
+    /// Returns the distance between the query and the vector encoded as `code`, as given by
+    /// [`distance`](super::distance) for the decoded vector.
+    pub fn distance(&self, code: &[u8]) -> f32 {
+        match self {
+            QuantizedDistance::L2 {
+                shifted_query,
+                scales,
+            } => shifted_query
+                .iter()
+                .zip(scales)
+                .zip(code)
+                .map(|((val, scale), &code)| {
+                    let diff = val - code as f32 * scale;
+                    diff * diff
+                })
+                .sum(),
+            QuantizedDistance::Dot {
+                metric,
+                query_dot_offsets,
+                scaled_query,
+            } => {
+                let dot = query_dot_offsets
+                    + scaled_query
+                        .iter()
+                        .zip(code)
+                        .map(|(val, &code)| val * code as f32)
+                        .sum::<f32>();
+                if *metric == VectorMetric::Cosine {
+                    1.0 - dot
+                } else {
+                    -dot
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ScalarQuantizer;
+    use crate::schema::VectorMetric;
+    use crate::vector::{distance, prepare_vector};
+
+    #[test]
+    fn test_scalar_quantizer() {
+        let vectors: Vec<f32> = vec![0.0, -1.0, 2.0, 0.6, 1.0, 2.0, 1.0, 1.0, 2.0, 0.25, 0.0, 2.0];
+        let quantizer = ScalarQuantizer::train(&vectors, 3);
+        let mut codes = Vec::new();
+        for vector in vectors.chunks_exact(3) {
+            quantizer.encode(vector, &mut codes);
+        }
+        // The last dimension takes a single value.
+        assert_eq!(&codes[..6], &[0, 0, 0, 153, 255, 0]);
+
+        let mut serialized = Vec::new();
+        quantizer.serialize(&mut serialized).unwrap();
+        assert_eq!(serialized.len(), 6 * 4);
+        let quantizer = ScalarQuantizer::deserialize(&mut &serialized[..], 3).unwrap();
+
+        for metric in [VectorMetric::L2, VectorMetric::DotProduct] {
+            let mut query = vec![0.3, 0.2, 1.0];
+            prepare_vector(metric, &mut query);
+            let query_distance = quantizer.query_distance(metric, &query);
+            for (vector, code) in vectors.chunks_exact(3).zip(codes.chunks_exact(3)) {
+                let error =
+                    (query_distance.distance(code) - distance(metric, &query, vector)).abs();
+                assert!(error < 0.02, "{metric:?} {error}");
+            }
+        }
+    }
+}
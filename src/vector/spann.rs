@@ -0,0 +1,251 @@
+use std::io;
+
+use common::{BinarySerializable, VInt};
+
+use super::ivf_pq::{deserialize_f32s, kmeans, serialize_f32s};
+use super::{distance, parallel_map, squared_l2};
+use crate::directory::FileSlice;
+use crate::schema::{SpannOptions, VectorMetric};
+
+/// A vector is stored in the list of a centroid other than its closest one only if it is at
+/// most this many times further from it, in squared distance.
+const REPLICA_MAX_DISTANCE_RATIO: f32 = 1.2;
+
+/// A SPANN-like index, for vectors that do not fit in memory.
+///
+/// The vectors are partitioned into lists around centroids. Only the centroids and the
+/// offsets of the lists are held in memory. Each list is stored contiguously, with the full
+/// vectors it holds, so that searching a list takes a single read. A vector close to the
+/// boundary between lists is stored in several of them.
+///
+/// The index is serialized as the length of its header as a `u64`, the header (the
+/// centroids and the offsets of the lists), and the lists. An entry of a list is the
+/// ordinal of a vector as a `u32`, followed by its values.
+pub(crate) struct SpannIndex {
+    dimensions: usize,
+    /// `num_lists * dimensions` values.
+    centroids: Vec<f32>,
+    /// Offset of each list in `postings`, in number of entries, followed by the total number
+    /// of entries.
+    list_offsets: Vec<u64>,
+    postings: FileSlice,
+}
+
+impl SpannIndex {
+    /// Trains the centroids on `vectors`, a sequence of vectors of `dimensions` dimensions,
+    /// and writes the index.
+    ///
+    /// The vectors are assigned to their lists concurrently on up to `num_threads` threads.
+    pub fn write<W: io::Write + ?Sized>(
+        vectors: &[f32],
+        dimensions: usize,
+        options: &SpannOptions,
+        num_threads: usize,
+        writer: &mut W,
+    ) -> io::Result<()> {
+        let num_vectors = vectors.len() / dimensions;
+        let list_size = options.list_size() as usize;
+        let num_lists = (num_vectors + list_size - 1) / list_size;
+        let centroids = if num_lists == 0 {
+            Vec::new()
+        } else {
+            kmeans(vectors, dimensions, num_lists)
+        };
+        let vector = |ord: u32| &vectors[ord as usize * dimensions..][..dimensions];
+        let max_replicas = (options.num_replicas() as usize).min(num_lists);
+        let ords: Vec<u32> = (0..num_vectors as u32).collect();
+        let assignments: Vec<Vec<u32>> = parallel_map(&ords, num_threads, |&ord| {
+            let mut list_distances: Vec<(u32, f32)> = centroids
+                .chunks_exact(dimensions)
+                .enumerate()
+                .map(|(list_ord, centroid)| (list_ord as u32, squared_l2(vector(ord), centroid)))
+                .collect();
+            let cmp_distance = |left: &(u32, f32), right: &(u32, f32)| left.1.total_cmp(&right.1);
+            if list_distances.len() > max_replicas {
+                list_distances.select_nth_unstable_by(max_replicas - 1, cmp_distance);
+                list_distances.truncate(max_replicas);
+            }
+            list_distances.sort_by(cmp_distance);
+            let closest_distance = list_distances[0].1;
+            list_distances
+                .iter()
+                .take_while(|&&(_, list_distance)| {
+                    list_distance <= closest_distance * REPLICA_MAX_DISTANCE_RATIO
+                })
+                .map(|&(list_ord, _)| list_ord)
+                .collect()
+        });
+        let mut lists: Vec<Vec<u32>> = vec![Vec::new(); num_lists];
+        for (ord, list_ords) in assignments.into_iter().enumerate() {
+            for list_ord in list_ords {
+                lists[list_ord as usize].push(ord as u32);
+            }
+        }
+
+        let mut header = Vec::new();
+        serialize_f32s(&centroids, &mut header)?;
+        VInt(num_lists as u64).serialize(&mut header)?;
+        let mut offset = 0u64;
+        offset.serialize(&mut header)?;
+        for list in &lists {
+            offset += list.len() as u64;
+            offset.serialize(&mut header)?;
+        }
+        (header.len() as u64).serialize(writer)?;
+        writer.write_all(&header)?;
+        for list in &lists {
+            for &ord in list {
+                ord.serialize(writer)?;
+                for val in vector(ord) {
+                    writer.write_all(&val.to_le_bytes())?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Opens the index, only reading its header.
+    pub fn open(file_slice: FileSlice, dimensions: usize) -> io::Result<SpannIndex> {
+        let truncated = || io::Error::new(io::ErrorKind::UnexpectedEof, "truncated SPANN index");
+        if file_slice.len() < 8 {
+            return Err(truncated());
+        }
+        let header_len = u64::deserialize(&mut file_slice.read_bytes_slice(0..8)?.as_slice())?;
+        let postings_start = 8 + header_len as usize;
+        if file_slice.len() < postings_start {
+            return Err(truncated());
+        }
+        let header = file_slice.read_bytes_slice(8..postings_start)?;
+        let mut reader = header.as_slice();
+        let centroids = deserialize_f32s(&mut reader)?;
+        let num_lists = VInt::deserialize_u64(&mut reader)? as usize;
+        let mut list_offsets = Vec::with_capacity(num_lists + 1);
+        for _ in 0..=num_lists {
+            list_offsets.push(u64::deserialize(&mut reader)?);
+        }
+        Ok(SpannIndex {
+            dimensions,
+            centroids,
+            list_offsets,
+            postings: file_slice.slice_from(postings_start),
+        })
+    }
+
+    /// Returns the accepted vectors of the `num_probes` lists closest to `query`, with
+    /// their distance to `query`, sorted by increasing distance.
+    ///
+    /// `query` has to be prepared for `metric`. The distances are exact, as the lists hold
+    /// the full vectors. Lists that are next to each other in the file are read at once.
+    pub fn search(
+        &self,
+        metric: VectorMetric,
+        query: &[f32],
+        num_probes: usize,
+        accept: impl Fn(u32) -> bool,
+    ) -> io::Result<Vec<(u32, f32)>> {
+        let mut list_distances: Vec<(usize, f32)> = self
+            .centroids
+            .chunks_exact(self.dimensions)
+            .enumerate()
+            .map(|(list_ord, centroid)| (list_ord, distance(metric, query, centroid)))
+            .collect();
+        list_distances.sort_by(|left, right| left.1.total_cmp(&right.1));
+        let mut probed_lists: Vec<usize> = list_distances
+            .iter()
+            .take(num_probes)
+            .map(|&(list_ord, _)| list_ord)
+            .collect();
+        probed_lists.sort_unstable();
+
+        let entry_len = 4 + 4 * self.dimensions;
+        let mut vector = vec![0.0f32; self.dimensions];
+        let mut candidates: Vec<(u32, f32)> = Vec::new();
+        let mut start = 0;
+        while start < probed_lists.len() {
+            let mut end = start + 1;
+            while end < probed_lists.len() && probed_lists[end] == probed_lists[end - 1] + 1 {
+                end += 1;
+            }
+            let byte_range = self.list_offsets[probed_lists[start]] as usize * entry_len
+                ..self.list_offsets[probed_lists[end - 1] + 1] as usize * entry_len;
+            let entries = self.postings.read_bytes_slice(byte_range)?;
+            for entry in entries.as_slice().chunks_exact(entry_len) {
+                let ord = u32::from_le_bytes([entry[0], entry[1], entry[2], entry[3]]);
+                if !accept(ord) {
+                    continue;
+                }
+                for (val, bytes) in vector.iter_mut().zip(entry[4..].chunks_exact(4)) {
+                    *val = f32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+                }
+                candidates.push((ord, distance(metric, query, &vector)));
+            }
+            start = end;
+        }
+        // A vector stored in several of the probed lists is returned once.
+        candidates.sort_unstable_by_key(|&(ord, _)| ord);
+        candidates.dedup_by_key(|&mut (ord, _)| ord);
+        candidates.sort_by(|left, right| left.1.total_cmp(&right.1));
+        Ok(candidates)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    use super::SpannIndex;
+    use crate::directory::FileSlice;
+    use crate::schema::{SpannOptions, VectorMetric};
+    use crate::vector::distance;
+
+    #[test]
+    fn test_spann_index() {
+        let mut rng = StdRng::seed_from_u64(3);
+        let dimensions = 4;
+        let vectors: Vec<f32> = (0..1_000 * dimensions)
+            .map(|_| rng.gen_range(-1.0..1.0))
+            .collect();
+        let options = SpannOptions::default()
+            .set_list_size(50)
+            .set_num_replicas(2);
+        let mut serialized = Vec::new();
+        SpannIndex::write(&vectors, dimensions, &options, 2, &mut serialized).unwrap();
+        let index = SpannIndex::open(FileSlice::from(serialized), dimensions).unwrap();
+        assert_eq!(index.list_offsets.len(), 21);
+        // Some of the vectors are stored in two lists.
+        let num_entries = *index.list_offsets.last().unwrap();
+        assert!(num_entries > 1_000 && num_entries <= 2_000);
+
+        let query = &vectors[..dimensions];
+        // Probing all of the lists returns all of the vectors, once.
+        let candidates = index
+            .search(VectorMetric::L2, query, usize::MAX, |_| true)
+            .unwrap();
+        assert_eq!(candidates.len(), 1_000);
+        assert_eq!(candidates[0], (0, 0.0));
+        let mut expected: Vec<f32> = vectors
+            .chunks_exact(dimensions)
+            .map(|vector| distance(VectorMetric::L2, query, vector))
+            .collect();
+        expected.sort_by(f32::total_cmp);
+        let distances: Vec<f32> = candidates.iter().map(|&(_, distance)| distance).collect();
+        assert_eq!(distances, expected);
+
+        let candidates = index
+            .search(VectorMetric::L2, query, 3, |ord| ord % 2 == 0)
+            .unwrap();
+        assert!(!candidates.is_empty() && candidates.len() < 500);
+        assert!(candidates.iter().all(|&(ord, _)| ord % 2 == 0));
+        assert_eq!(candidates[0], (0, 0.0));
+
+        // An index without any vector.
+        let mut serialized = Vec::new();
+        SpannIndex::write(&[], dimensions, &options, 1, &mut serialized).unwrap();
+        let index = SpannIndex::open(FileSlice::from(serialized), dimensions).unwrap();
+        assert!(index
+            .search(VectorMetric::L2, query, 10, |_| true)
+            .unwrap()
+            .is_empty());
+    }
+}
@@ -0,0 +1,243 @@
+use std::io::{self, Write};
+
+use common::{BinarySerializable, VInt};
+
+use super::reader::AnnIndex;
+use super::{
+    decode_vector, distance, prepare_vector, FieldVectorIndex, HnswGraph, IvfPqIndex,
+    ScalarQuantizer, SpannIndex,
+};
+use crate::directory::{CompositeWrite, WritePtr};
+use crate::schema::document::{Document, Value};
+use crate::schema::{DenseVectorOptions, Field, Schema, VectorIndexOptions, VectorQuantization};
+use crate::{DocId, TantivyError};
+
+/// The index of one of the segments being merged, from which the index of the merged
+/// segment is built.
+enum ReusedIndex {
+    /// The graph, and for each of its nodes the ordinal of its vector in the merged segment.
+    Hnsw { graph: HnswGraph, nodes: Vec<u32> },
+    /// The centroids and codebooks are reused, the codes are not.
+    IvfPq(IvfPqIndex),
+}
+
+/// Vectors of a dense vector field, accumulated until the segment is written.
+struct FieldVectorsBuffer {
+    field: Field,
+    field_name: String,
+    options: DenseVectorOptions,
+    doc_ids: Vec<DocId>,
+    values: Vec<f32>,
+    reused_index: Option<ReusedIndex>,
+}
+
+impl FieldVectorsBuffer {
+    fn vector(&self, ord: u32) -> &[f32] {
+        let dimensions = self.options.dimensions() as usize;
+        let start = ord as usize * dimensions;
+        &self.values[start..start + dimensions]
+    }
+
+    fn serialize<W: Write>(mut self, writer: &mut W, num_threads: usize) -> io::Result<()> {
+        let num_vectors = self.doc_ids.len() as u32;
+        VInt(num_vectors as u64).serialize(writer)?;
+        for doc_id in &self.doc_ids {
+            doc_id.serialize(writer)?;
+        }
+        for val in &self.values {
+            writer.write_all(&val.to_le_bytes())?;
+        }
+        let dimensions = self.options.dimensions() as usize;
+        match (self.options.index(), self.reused_index.take()) {
+            (VectorIndexOptions::Hnsw(hnsw_options), reused_index) => {
+                if let VectorQuantization::Int8 { .. } = self.options.quantization() {
+                    let quantizer = ScalarQuantizer::train(&self.values, dimensions);
+                    quantizer.serialize(writer)?;
+                    let mut codes = Vec::with_capacity(self.values.len());
+                    for vector in self.values.chunks_exact(dimensions) {
+                        quantizer.encode(vector, &mut codes);
+                    }
+                    writer.write_all(&codes)?;
+                }
+                // The graph is built with the full precision vectors.
+                let metric = self.options.metric();
+                let distance =
+                    |left, right| distance(metric, self.vector(left), self.vector(right));
+                if let Some(ReusedIndex::Hnsw { graph, nodes }) = reused_index {
+                    HnswGraph::extend(
+                        graph,
+                        &nodes,
+                        num_vectors,
+                        hnsw_options,
+                        num_threads,
+                        distance,
+                    )
+                    .serialize(writer)
+                } else {
+                    HnswGraph::build(num_vectors, hnsw_options, num_threads, distance)
+                        .serialize(writer)
+                }
+            }
+            (VectorIndexOptions::IvfPq(_), Some(ReusedIndex::IvfPq(quantizer)))
+                // The quantizer was trained on at least half of the vectors.
+                if quantizer.num_vectors() * 2 >= num_vectors as usize =>
+            {
+                IvfPqIndex::build_with_quantizer(&quantizer, &self.values).serialize(writer)
+            }
+            (VectorIndexOptions::IvfPq(ivf_pq_options), _) => {
+                IvfPqIndex::build(&self.values, dimensions, ivf_pq_options, num_threads)
+                    .serialize(writer)
+            }
+            (VectorIndexOptions::Spann(spann_options), _) => {
+                SpannIndex::write(&self.values, dimensions, spann_options, num_threads, writer)
+            }
+        }
+    }
+}
+
+/// Writes the vector index of a segment.
+///
+/// For each dense vector field, the vectors are written along with their doc ids and the
+/// index built over them when the writer is closed: an HNSW graph, preceded by the int8
+/// codes of the vectors if they are quantized, the centroids, codebooks and codes of an
+/// IVF-PQ index, or the centroids and lists of vectors of a SPANN index.
+///
+/// Documents are expected to be added in increasing doc id order.
+///
+/// When segments are merged, the index of the largest of them can be reused with
+/// [`VectorIndexWriter::reuse_index`] rather than built from scratch.
+pub struct VectorIndexWriter {
+    composite_write: CompositeWrite<WritePtr>,
+    fields: Vec<FieldVectorsBuffer>,
+    num_build_threads: usize,
+}
+
+impl VectorIndexWriter {
+    /// Creates a vector index writer for the dense vector fields of `schema`, whose
+    /// indexes are built with up to `num_build_threads` threads.
+    pub fn new(write: WritePtr, schema: &Schema, num_build_threads: usize) -> VectorIndexWriter {
+        let fields = schema
+            .fields()
+            .filter_map(|(field, field_entry)| {
+                let options = field_entry.dense_vector_options()?;
+                Some(FieldVectorsBuffer {
+                    field,
+                    field_name: field_entry.name().to_string(),
+                    options: options.clone(),
+                    doc_ids: Vec::new(),
+                    values: Vec::new(),
+                    reused_index: None,
+                })
+            })
+            .collect();
+        VectorIndexWriter {
+            composite_write: CompositeWrite::wrap(write),
+            fields,
+            num_build_threads: num_build_threads.max(1),
+        }
+    }
+
+    fn field_buffer(&mut self, field: Field) -> crate::Result<&mut FieldVectorsBuffer> {
+        self.fields
+            .iter_mut()
+            .find(|buffer| buffer.field == field)
+            .ok_or_else(|| {
+                TantivyError::SchemaError(format!("Field {field:?} is not a dense vector field"))
+            })
+    }
+
+    /// Adds the vectors of the dense vector fields of `doc`.
+    pub fn add_document<D: Document>(&mut self, doc_id: DocId, doc: &D) -> crate::Result<()> {
+        for (field, value) in doc.iter_fields_and_values() {
+            if !self.fields.iter().any(|buffer| buffer.field == field) {
+                continue;
+            }
+            if let Some(bytes) = value.as_bytes() {
+                let vector = decode_vector(bytes)?;
+                self.add_vector(doc_id, field, &vector)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Adds a vector of the field `field` to the document `doc_id`.
+    ///
+    /// Returns an error if the vector does not have the dimensions of the field.
+    pub fn add_vector(&mut self, doc_id: DocId, field: Field, vector: &[f32]) -> crate::Result<()> {
+        let buffer = self.field_buffer(field)?;
+        let dimensions = buffer.options.dimensions() as usize;
+        if vector.len() != dimensions {
+            return Err(TantivyError::InvalidArgument(format!(
+                "Field `{}` expects vectors of {} dimensions, got {}",
+                buffer.field_name,
+                dimensions,
+                vector.len()
+            )));
+        }
+        debug_assert!(buffer.doc_ids.last().map_or(true, |&last| last <= doc_id));
+        let start = buffer.values.len();
+        buffer.values.extend_from_slice(vector);
+        prepare_vector(buffer.options.metric(), &mut buffer.values[start..]);
+        buffer.doc_ids.push(doc_id);
+        Ok(())
+    }
+
+    /// Builds the index of the field `field` from `index`, the index of the field in one of
+    /// the segments being merged, rather than from scratch.
+    ///
+    /// `new_ords[ord]` is the ordinal, among the vectors added to this writer, of the vector
+    /// `ord` of `index`, or `None` if its document was deleted. An HNSW graph is extended
+    /// with the other vectors, unless some of its vectors were deleted. The centroids and
+    /// codebooks of an IVF-PQ index are reused if they were trained on at least half of the
+    /// vectors.
+    pub(crate) fn reuse_index(
+        &mut self,
+        field: Field,
+        index: &FieldVectorIndex,
+        new_ords: &[Option<u32>],
+    ) -> crate::Result<()> {
+        let buffer = self.field_buffer(field)?;
+        buffer.reused_index = match (buffer.options.index(), index.ann_index()) {
+            (
+                VectorIndexOptions::Hnsw(_),
+                AnnIndex::Hnsw { graph, .. } | AnnIndex::QuantizedHnsw { graph, .. },
+            ) => new_ords
+                .iter()
+                .copied()
+                .collect::<Option<Vec<u32>>>()
+                .map(|nodes| ReusedIndex::Hnsw {
+                    graph: graph.clone(),
+                    nodes,
+                }),
+            (VectorIndexOptions::IvfPq(_), AnnIndex::IvfPq { index, .. }) if index.is_trained() => {
+                Some(ReusedIndex::IvfPq(index.clone()))
+            }
+            _ => None,
+        };
+        Ok(())
+    }
+
+    /// Memory used by the vectors added so far.
+    pub fn mem_usage(&self) -> usize {
+        self.fields
+            .iter()
+            .map(|buffer| {
+                buffer.doc_ids.capacity() * std::mem::size_of::<DocId>()
+                    + buffer.values.capacity() * std::mem::size_of::<f32>()
+            })
+            .sum()
+    }
+
+    /// Builds the indexes and finalizes the vector index file.
+    pub fn close(mut self) -> io::Result<()> {
+        for buffer in std::mem::take(&mut self.fields) {
+            if buffer.doc_ids.is_empty() {
+                continue;
+            }
+            let writer = self.composite_write.for_field(buffer.field);
+            buffer.serialize(writer, self.num_build_threads)?;
+            writer.flush()?;
+        }
+        self.composite_write.close()
+    }
+}
@@ -50,6 +50,13 @@ impl ArenaHashMap {
         self.shared_arena_hashmap.is_empty()
     }
 
+    /// Removes all of the entries, keeping the capacity of the table and the pages of the
+    /// memory arena to reuse them.
+    pub fn clear(&mut self) {
+        self.shared_arena_hashmap.clear();
+        self.memory_arena.clear();
+    }
+
     #[inline]
     pub fn len(&self) -> usize {
         self.shared_arena_hashmap.len()
@@ -115,6 +122,23 @@ mod tests {
         }
         assert_eq!(vanilla_hash_map.len(), 2);
     }
+    #[test]
+    fn test_hash_map_clear() {
+        let mut hash_map: ArenaHashMap = ArenaHashMap::with_capacity(16);
+        hash_map.mutate_or_create(b"abc", |_: Option<u32>| 3u32);
+        let mem_usage = hash_map.mem_usage();
+        hash_map.clear();
+        assert!(hash_map.is_empty());
+        assert_eq!(hash_map.get::<u32>(b"abc"), None);
+        assert_eq!(hash_map.mem_usage(), mem_usage);
+        hash_map.mutate_or_create(b"abc", |opt_val: Option<u32>| {
+            assert_eq!(opt_val, None);
+            4u32
+        });
+        assert_eq!(hash_map.get::<u32>(b"abc"), Some(4u32));
+        assert_eq!(hash_map.len(), 1);
+    }
+
     #[test]
     fn test_empty_hashmap() {
         let hash_map: ArenaHashMap = ArenaHashMap::default();
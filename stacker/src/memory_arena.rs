@@ -92,6 +92,8 @@ pub fn load<Item: Copy + 'static>(data: &[u8]) -> Item {
 #[allow(clippy::new_without_default)]
 pub struct MemoryArena {
     pages: Vec<Page>,
+    /// Pages released by `clear`, reused before allocating new ones.
+    free_pages: Vec<Page>,
 }
 
 impl Default for MemoryArena {
@@ -99,6 +101,7 @@ impl Default for MemoryArena {
         let first_page = Page::new(0);
         MemoryArena {
             pages: vec![first_page],
+            free_pages: Vec::new(),
         }
     }
 }
@@ -109,6 +112,8 @@ impl MemoryArena {
     ///
     /// Internally, it counts a number of `1MB` pages
     /// and therefore delivers an upperbound.
+    ///
+    /// The pages kept by [`MemoryArena::clear`] are not counted until they are reused.
     pub fn mem_usage(&self) -> usize {
         self.pages.len() * PAGE_SIZE
     }
@@ -122,6 +127,15 @@ impl MemoryArena {
         self.len() == 0
     }
 
+    /// Removes all of the allocations, invalidating all of the addresses.
+    ///
+    /// The pages are kept to be reused by the next allocations, which saves allocating and
+    /// zeroing them again. Their content is not erased.
+    pub fn clear(&mut self) {
+        self.free_pages.extend(self.pages.drain(1..));
+        self.pages[0].len = 0;
+    }
+
     #[inline]
     pub fn write_at<Item: Copy + 'static>(&mut self, addr: Addr, val: Item) {
         let dest = self.slice_mut(addr, std::mem::size_of::<Item>());
@@ -173,7 +187,13 @@ impl MemoryArena {
     /// Return the address
     fn add_page(&mut self, len: usize) -> Addr {
         let new_page_id = self.pages.len();
-        let mut page = Page::new(new_page_id);
+        let mut page = match self.free_pages.pop() {
+            Some(mut page) => {
+                page.page_id = new_page_id;
+                page
+            }
+            None => Page::new(new_page_id),
+        };
         page.len = len;
         self.pages.push(page);
         Addr::new(new_page_id, 0)
@@ -295,6 +315,28 @@ mod tests {
         assert_eq!(arena.slice(addr_d, 1)[0], 4);
     }
 
+    #[test]
+    fn test_arena_clear() {
+        let mut arena = MemoryArena::default();
+        for _ in 0..3 {
+            arena.allocate_space(PAGE_SIZE - 1);
+        }
+        assert_eq!(arena.mem_usage(), 3 * PAGE_SIZE);
+        arena.clear();
+        assert!(arena.is_empty());
+        assert_eq!(arena.mem_usage(), PAGE_SIZE);
+        assert_eq!(arena.free_pages.len(), 2);
+
+        let addr_a = arena.allocate_space(PAGE_SIZE - 1);
+        let addr_b = arena.allocate_space(5);
+        arena.slice_mut(addr_b, 5).copy_from_slice(b"hello");
+        assert_eq!(arena.free_pages.len(), 1);
+        assert_eq!(arena.len(), PAGE_SIZE + 5);
+        assert_eq!(addr_a.page_id(), 0);
+        assert_eq!(addr_b.page_id(), 1);
+        assert_eq!(arena.slice(addr_b, 5), b"hello");
+    }
+
     #[derive(Clone, Copy, Debug, Eq, PartialEq)]
     struct MyTest {
         pub a: usize,
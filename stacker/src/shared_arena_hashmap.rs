@@ -227,6 +227,14 @@ impl SharedArenaHashMap {
         self.len() == 0
     }
 
+    /// Removes all of the entries, keeping the capacity of the table.
+    ///
+    /// The keys and values remain in the memory arena.
+    pub fn clear(&mut self) {
+        self.table.fill(KeyValue::default());
+        self.len = 0;
+    }
+
     #[inline]
     pub fn len(&self) -> usize {
         self.len